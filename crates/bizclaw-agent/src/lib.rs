@@ -5,12 +5,14 @@ pub mod engine;
 pub mod context;
 
 use bizclaw_core::config::BizClawConfig;
-use bizclaw_core::error::Result;
-use bizclaw_core::traits::Provider;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::{AgentProgress, Channel, Provider};
 use bizclaw_core::traits::SecurityPolicy;
 use bizclaw_core::traits::memory::MemoryBackend;
+use bizclaw_core::tokens::{self, TruncationStrategy};
 use bizclaw_core::traits::provider::GenerateParams;
-use bizclaw_core::types::{Message, OutgoingMessage};
+use bizclaw_core::types::{AttachmentKind, ImagePart, IncomingMessage, Message, OutgoingMessage, Role};
+use futures::StreamExt;
 
 /// The BizClaw agent — processes messages using LLM providers and tools.
 pub struct Agent {
@@ -20,6 +22,12 @@ pub struct Agent {
     tools: bizclaw_tools::ToolRegistry,
     security: bizclaw_security::DefaultSecurityPolicy,
     conversation: Vec<Message>,
+    /// `[safety]` rules every response is checked against, when enabled.
+    safety: Option<bizclaw_core::safety::SafetyFilter>,
+    /// Where the steps of a turn are reported, for presence updates.
+    progress: Option<tokio::sync::mpsc::UnboundedSender<AgentProgress>>,
+    /// The model's context window, once the provider has listed it.
+    context_length: Option<usize>,
 }
 
 impl Agent {
@@ -27,11 +35,15 @@ impl Agent {
     pub fn new(config: BizClawConfig) -> Result<Self> {
         let provider = bizclaw_providers::create_provider(&config)?;
         let memory = bizclaw_memory::create_memory(&config.memory)?;
-        let tools = bizclaw_tools::ToolRegistry::with_defaults();
+        let tools = bizclaw_tools::ToolRegistry::from_config(&config);
         let security = bizclaw_security::DefaultSecurityPolicy::new(config.autonomy.clone());
+        let safety = if config.safety.enabled {
+            Some(bizclaw_core::safety::SafetyFilter::from_config(&config.safety)?)
+        } else {
+            None
+        };
 
-        let mut conversation = vec![];
-        conversation.push(Message::system(&config.identity.system_prompt));
+        let conversation = vec![Message::system(&config.identity.system_prompt)];
 
         Ok(Self {
             config,
@@ -40,71 +52,67 @@ impl Agent {
             tools,
             security,
             conversation,
+            safety,
+            progress: None,
+            context_length: None,
         })
     }
 
     /// Process a user message and generate a response.
     pub async fn process(&mut self, user_message: &str) -> Result<String> {
+        self.respond(Message::user(user_message)).await
+    }
+
+    async fn respond(&mut self, message: Message) -> Result<String> {
+        let user_message = message.content.clone();
+        let user_message = user_message.as_str();
+
         // Add user message to conversation
-        self.conversation.push(Message::user(user_message));
+        self.conversation.push(message);
+        self.fit_context().await;
 
         // Get tool definitions
         let tool_defs = self.tools.list();
 
         // Create generation params
-        let params = GenerateParams {
-            model: self.config.default_model.clone(),
-            temperature: self.config.default_temperature,
-            max_tokens: 4096,
-            top_p: 0.9,
-            stop: vec![],
-        };
+        let params = self.generate_params();
 
         // Call the provider
+        self.report(AgentProgress::Thinking);
         let response = self.provider.chat(&self.conversation, &tool_defs, &params).await?;
 
         // Handle tool calls
         if !response.tool_calls.is_empty() {
-            let mut tool_results = Vec::new();
-
+            // Security check first, then run what passed (in parallel when
+            // the model asked for several tools at once).
+            let mut denied = Vec::with_capacity(response.tool_calls.len());
+            let mut allowed = Vec::new();
             for tc in &response.tool_calls {
                 tracing::info!("Tool call: {} with args: {}", tc.function.name, tc.function.arguments);
 
-                // Security check
-                if tc.function.name == "shell" {
-                    if let Ok(args) = serde_json::from_str::<serde_json::Value>(&tc.function.arguments) {
-                        if let Some(cmd) = args["command"].as_str() {
-                            if !self.security.check_command(cmd).await? {
-                                tool_results.push(Message::tool(
-                                    format!("Permission denied: command '{}' not allowed", cmd),
-                                    &tc.id,
-                                ));
-                                continue;
-                            }
-                        }
-                    }
-                }
-
-                // Execute tool
-                if let Some(tool) = self.tools.get(&tc.function.name) {
-                    match tool.execute(&tc.function.arguments).await {
-                        Ok(result) => {
-                            tool_results.push(Message::tool(&result.output, &tc.id));
-                        }
-                        Err(e) => {
-                            tool_results.push(Message::tool(
-                                format!("Tool error: {e}"),
-                                &tc.id,
-                            ));
-                        }
-                    }
-                } else {
-                    tool_results.push(Message::tool(
-                        format!("Tool not found: {}", tc.function.name),
+                if tc.function.name == "shell"
+                    && let Ok(args) = serde_json::from_str::<serde_json::Value>(&tc.function.arguments)
+                    && let Some(cmd) = args["command"].as_str()
+                    && !self.security.check_command(cmd).await? {
+                    denied.push(Some(Message::tool(
+                        format!("Permission denied: command '{}' not allowed", cmd),
                         &tc.id,
-                    ));
+                    )));
+                    continue;
                 }
+                denied.push(None);
+                allowed.push(tc.clone());
+            }
+
+            for tc in &allowed {
+                self.report(AgentProgress::ToolCall { name: tc.function.name.clone() });
             }
+            let mut executed = self.tools.execute_batch(&allowed).await.into_iter();
+            let tool_results: Vec<Message> = denied.into_iter()
+                .filter_map(|denial| denial.or_else(|| {
+                    executed.next().map(|result| Message::tool(result.output, result.tool_call_id))
+                }))
+                .collect();
 
             // Add assistant message with tool calls
             self.conversation.push(Message {
@@ -113,6 +121,7 @@ impl Agent {
                 name: None,
                 tool_call_id: None,
                 tool_calls: Some(response.tool_calls.clone()),
+                images: Vec::new(),
             });
 
             // Add tool results
@@ -121,8 +130,9 @@ impl Agent {
             }
 
             // Get final response after tool execution
+            self.report(AgentProgress::Thinking);
             let final_response = self.provider.chat(&self.conversation, &[], &params).await?;
-            let content = final_response.content.unwrap_or_else(|| "I executed the tools.".into());
+            let content = self.check_safety(final_response.content.unwrap_or_else(|| "I executed the tools.".into()));
             self.conversation.push(Message::assistant(&content));
 
             // Save to memory
@@ -132,7 +142,7 @@ impl Agent {
         }
 
         // No tool calls — just text response
-        let content = response.content.unwrap_or_else(|| "I'm not sure how to respond.".into());
+        let content = self.check_safety(response.content.unwrap_or_else(|| "I'm not sure how to respond.".into()));
         self.conversation.push(Message::assistant(&content));
 
         // Save to memory
@@ -141,6 +151,64 @@ impl Agent {
         Ok(content)
     }
 
+    /// Drop the oldest messages of the conversation that don't fit the
+    /// model's context window. The window is asked of the provider once per
+    /// model; until it answers, a default is assumed.
+    async fn fit_context(&mut self) {
+        let model = self.config.default_model.clone();
+        let context_length = match self.context_length {
+            Some(length) => length,
+            None => match self.provider.list_models().await {
+                Ok(models) => *self.context_length.insert(tokens::context_length(&models, &model)),
+                Err(e) => {
+                    tracing::debug!("Context window of {model} unknown: {e}");
+                    tokens::DEFAULT_CONTEXT_LENGTH
+                }
+            },
+        };
+        let before = self.conversation.len();
+        let mut conversation = tokens::truncate_to_budget(
+            &model,
+            std::mem::take(&mut self.conversation),
+            tokens::prompt_budget(context_length),
+            TruncationStrategy::DropOldest,
+        );
+        if conversation.len() < before {
+            // Start at a user message, not at tool results whose call was dropped.
+            let system = conversation.iter().take_while(|m| m.role == Role::System).count();
+            let orphans = conversation[system..].iter().take_while(|m| m.role != Role::User).count();
+            conversation.drain(system..system + orphans);
+            tracing::debug!("Dropped {} old messages to fit {model}'s context window", before - conversation.len());
+        }
+        self.conversation = conversation;
+    }
+
+    fn generate_params(&self) -> GenerateParams {
+        GenerateParams {
+            model: self.config.default_model.clone(),
+            temperature: self.config.default_temperature,
+            max_tokens: 4096,
+            top_p: 0.9,
+            stop: vec![],
+        }
+    }
+
+    /// The response to send after the `[safety]` rules: redacted, replaced
+    /// with the block message, or as it was.
+    fn check_safety(&self, content: String) -> String {
+        match &self.safety {
+            Some(filter) => filter.check(&content).cleaned_response,
+            None => content,
+        }
+    }
+
+    /// Report a step of the turn to the progress subscriber, if any.
+    fn report(&self, step: AgentProgress) {
+        if let Some(progress) = &self.progress {
+            let _ = progress.send(step);
+        }
+    }
+
     /// Save interaction to memory.
     async fn save_memory(&self, user_msg: &str, assistant_msg: &str) {
         if self.config.memory.auto_save {
@@ -160,7 +228,8 @@ impl Agent {
 
     /// Process incoming message and create an outgoing response.
     pub async fn handle_incoming(&mut self, msg: &bizclaw_core::types::IncomingMessage) -> Result<OutgoingMessage> {
-        let response = self.process(&msg.content).await?;
+        self.buffer_group_message(msg);
+        let response = self.respond(self.user_message(msg)).await?;
         Ok(OutgoingMessage {
             thread_id: msg.thread_id.clone(),
             content: response,
@@ -169,11 +238,168 @@ impl Agent {
         })
     }
 
+    /// The user message for `msg`. Images go to the model as images when the
+    /// provider takes them; other attachments are noted with the path they
+    /// were saved at, for the file and document tools.
+    fn user_message(&self, msg: &IncomingMessage) -> Message {
+        let mut content = msg.content.clone();
+        let mut images = Vec::new();
+        for attachment in &msg.attachments {
+            if attachment.kind == AttachmentKind::Image
+                && self.provider.supports_vision()
+                && let Ok(data) = std::fs::read(&attachment.path)
+            {
+                let mime_type = attachment.mime_type.clone().unwrap_or_else(|| "image/jpeg".into());
+                images.push(ImagePart { mime_type, data });
+                continue;
+            }
+            let name = attachment.file_name.as_deref().map(|n| format!(" '{n}'")).unwrap_or_default();
+            content.push_str(&format!("\n[{}{name} saved at {}]", attachment.kind, attachment.path));
+        }
+        Message::user(content.trim_start()).with_images(images)
+    }
+
+    /// Keep group messages for the group summarizer. Telegram and Zalo
+    /// buffer their monitored groups themselves, with media and the bot's
+    /// own messages, so their messages are skipped here.
+    fn buffer_group_message(&self, msg: &bizclaw_core::types::IncomingMessage) {
+        if self.config.tools.group_summarizer.enabled
+            && msg.thread_type == bizclaw_core::types::ThreadType::Group
+            && !matches!(msg.channel.as_str(), "telegram" | "zalo")
+        {
+            bizclaw_core::group_buffer::MessageBuffer::global()
+                .push(bizclaw_core::group_buffer::BufferedMessage::from_incoming(msg));
+        }
+    }
+
+    /// Answer an incoming message on `channel`.
+    ///
+    /// With streaming on for the channel, the response is shown as it is generated.
+    /// A streamed completion can't call tools, so when the provider takes
+    /// tools and some are registered the response is sent complete instead.
+    /// So is every response while safety rules are on, since they can only
+    /// check a response before any of it is shown.
+    pub async fn reply(&mut self, msg: &bizclaw_core::types::IncomingMessage, channel: &dyn Channel) -> Result<()> {
+        let uses_tools = self.provider.supports_tools() && !self.tools.list().is_empty();
+        if !self.config.channel.streams(channel.name()) || uses_tools || self.safety.is_some() {
+            let response = self.handle_incoming(msg).await?;
+            return channel.send(response).await;
+        }
+
+        self.buffer_group_message(msg);
+        self.conversation.push(self.user_message(msg));
+        self.fit_context().await;
+        let params = self.generate_params();
+        self.report(AgentProgress::Thinking);
+        let tokens = self.provider.chat_stream(&self.conversation, &params).await?;
+
+        // Keep a copy of what was shown, for the conversation and memory.
+        let shown = std::sync::Arc::new(std::sync::Mutex::new(String::new()));
+        let tee = {
+            let shown = shown.clone();
+            tokens.inspect(move |token| {
+                if let Ok(token) = token {
+                    shown.lock().unwrap().push_str(token);
+                }
+            })
+        };
+        let sent = channel.send_streaming(&msg.thread_id, msg.thread_type.clone(), Box::pin(tee)).await;
+
+        let content = std::mem::take(&mut *shown.lock().unwrap());
+        if !content.is_empty() {
+            self.conversation.push(Message::assistant(&content));
+            self.save_memory(&msg.content, &content).await;
+        }
+        sent
+    }
+
+    /// Run a scheduled job and build the message to send to its channel.
+    ///
+    /// Prompt jobs go through the conversation like any incoming message;
+    /// message and tool jobs don't touch the conversation.
+    pub async fn run_job(&mut self, fired: &bizclaw_tools::scheduler::FiredJob) -> Result<OutgoingMessage> {
+        use bizclaw_core::types::ThreadType;
+        use bizclaw_tools::scheduler::JobAction;
+
+        let job = &fired.job;
+        let thread_type = if job.is_group { ThreadType::Group } else { ThreadType::Direct };
+        let content = match &job.action {
+            JobAction::Message { text } => text.clone(),
+            JobAction::Tool { name, arguments } => {
+                let result = self.tools.execute(name, arguments).await?;
+                if !result.success {
+                    return Err(BizClawError::Tool(format!("Job '{}': {}", job.name, result.output)));
+                }
+                result.output
+            }
+            JobAction::Prompt { prompt } => {
+                let incoming = bizclaw_core::types::IncomingMessage {
+                    channel: job.channel.clone(),
+                    thread_id: job.thread_id.clone(),
+                    sender_id: "scheduler".into(),
+                    sender_name: Some(format!("Scheduled job '{}'", job.name)),
+                    content: prompt.clone(),
+                    thread_type,
+                    timestamp: chrono::Utc::now(),
+                    reply_to: None,
+                    attachments: Vec::new(),
+                    message_id: None,
+                };
+                return self.handle_incoming(&incoming).await;
+            }
+        };
+        Ok(OutgoingMessage {
+            thread_id: job.thread_id.clone(),
+            content,
+            thread_type,
+            reply_to: None,
+        })
+    }
+
     /// Get provider name.
     pub fn provider_name(&self) -> &str {
         self.provider.name()
     }
 
+    /// Model the agent answers with.
+    pub fn model(&self) -> &str {
+        &self.config.default_model
+    }
+
+    /// Answer with `model` from now on.
+    pub fn set_model(&mut self, model: &str) {
+        if self.config.default_model != model {
+            self.context_length = None;
+        }
+        self.config.default_model = model.to_string();
+    }
+
+    /// Answer with the `name` provider from now on. On error the current
+    /// provider stays.
+    pub fn set_provider(&mut self, name: &str) -> Result<()> {
+        let mut config = self.config.clone();
+        config.default_provider = name.to_string();
+        self.provider = bizclaw_providers::create_provider(&config)?;
+        self.config = config;
+        self.context_length = None;
+        Ok(())
+    }
+
+    /// Tools the model can call.
+    pub fn tools(&self) -> Vec<bizclaw_core::types::ToolDefinition> {
+        self.tools.list()
+    }
+
+    /// Let the model call `tool` too, such as one bound to the chat it answers.
+    pub fn register_tool(&mut self, tool: Box<dyn bizclaw_core::traits::Tool>) {
+        self.tools.register(tool);
+    }
+
+    /// Report the steps of each turn (model calls, tool runs) to `progress`.
+    pub fn set_progress(&mut self, progress: Option<tokio::sync::mpsc::UnboundedSender<AgentProgress>>) {
+        self.progress = progress;
+    }
+
     /// Get conversation history.
     pub fn conversation(&self) -> &[Message] {
         &self.conversation
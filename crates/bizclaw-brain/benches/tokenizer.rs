@@ -0,0 +1,65 @@
+//! `count_tokens` throughput. Target: more than 1M tokens/sec.
+//!
+//! Run with `cargo bench -p bizclaw-brain --bench tokenizer`. Uses a
+//! synthetic SentencePiece vocabulary, or the tokenizer of the GGUF file
+//! in `BIZCLAW_BENCH_MODEL` when it is set.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+use bizclaw_brain::gguf::GgufValue;
+use bizclaw_brain::tokenizer::Tokenizer;
+
+const TEXT: &str = "Xin chào! BizClaw answers customers on Zalo, Telegram and email, \
+    keeps notes about each conversation and runs a local model when no cloud \
+    provider is configured. Orders placed before noon ship the same day; \
+    refunds take three to five business days. ";
+
+/// Every word of `TEXT` with all its prefixes, scored by length so merges
+/// grow one character at a time, plus byte fallback tokens.
+fn synthetic() -> Tokenizer {
+    let mut tokens = vec!["<unk>".to_string(), "<s>".into(), "</s>".into()];
+    tokens.extend((0..=255u8).map(|b| format!("<0x{b:02X}>")));
+    for word in TEXT.split(' ') {
+        let word = format!("▁{word}");
+        for (end, c) in word.char_indices() {
+            tokens.push(word[..end + c.len_utf8()].to_string());
+            tokens.push(c.to_string());
+        }
+    }
+    tokens.sort();
+    tokens.dedup();
+
+    let scores = tokens.iter()
+        .map(|t| GgufValue::F32(if t.starts_with('<') { 0.0 } else { t.chars().count() as f32 }))
+        .collect();
+    let metadata = HashMap::from([
+        ("tokenizer.ggml.tokens".to_string(), GgufValue::Array(tokens.into_iter().map(GgufValue::String).collect())),
+        ("tokenizer.ggml.scores".to_string(), GgufValue::Array(scores)),
+    ]);
+    Tokenizer::from_metadata(&metadata).expect("synthetic vocabulary")
+}
+
+fn main() {
+    let tokenizer = match std::env::var("BIZCLAW_BENCH_MODEL") {
+        Ok(path) => Tokenizer::from_gguf(Path::new(&path)).expect("tokenizer from BIZCLAW_BENCH_MODEL"),
+        Err(_) => synthetic(),
+    };
+    let text = TEXT.repeat(2000);
+
+    // Warm up.
+    let per_call = tokenizer.count_tokens(&text);
+
+    let mut tokens = 0;
+    let start = Instant::now();
+    while start.elapsed().as_secs_f64() < 3.0 {
+        tokens += tokenizer.count_tokens(&text);
+    }
+    let rate = tokens as f64 / start.elapsed().as_secs_f64();
+    println!(
+        "count_tokens: {per_call} tokens per {} KB call, {:.2}M tokens/sec ({})",
+        text.len() / 1024,
+        rate / 1e6,
+        if rate > 1e6 { "meets the 1M target" } else { "below the 1M target" },
+    );
+}
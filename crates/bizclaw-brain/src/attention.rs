@@ -3,6 +3,32 @@
 //! Computes attention scores incrementally without materializing
 //! the full QK^T matrix, saving O(seq_len) memory.
 
+use bitvec::vec::BitVec;
+
+/// Which key positions a query may attend to.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AttentionMask {
+    /// Every position up to and including the query's.
+    #[default]
+    Causal,
+    /// Causal, except that positions in the first `prefix_len` (the prompt
+    /// of a prefix LM) also see each other in both directions.
+    PrefixLM { prefix_len: usize },
+    /// Key positions whose bit is set, for any query.
+    Custom(BitVec),
+}
+
+impl AttentionMask {
+    /// Whether the query at `query` may attend to the key at `key`.
+    pub fn allows(&self, query: usize, key: usize) -> bool {
+        match self {
+            Self::Causal => key <= query,
+            Self::PrefixLM { prefix_len } => key <= query || (query < *prefix_len && key < *prefix_len),
+            Self::Custom(bits) => bits.get(key).is_some_and(|bit| *bit),
+        }
+    }
+}
+
 /// Compute single-head attention output for a single query position.
 /// Uses online softmax (flash attention) — no intermediate score buffer.
 ///
@@ -75,6 +101,10 @@ pub fn attention(
 }
 
 /// Multi-head attention: apply attention for all heads in parallel.
+///
+/// `query_pos` is the position of `q`; of the `seq_len` cached keys, those
+/// `mask` hides from it are left out, as if their score were -inf.
+#[allow(clippy::too_many_arguments)]
 pub fn multi_head_attention(
     output: &mut [f32],
     q: &[f32],
@@ -84,6 +114,8 @@ pub fn multi_head_attention(
     n_kv_heads: usize,
     seq_len: usize,
     head_dim: usize,
+    mask: &AttentionMask,
+    query_pos: usize,
 ) {
     let gqa_ratio = n_heads / n_kv_heads;
 
@@ -111,11 +143,14 @@ pub fn multi_head_attention(
             kv_stride,
             k_base,
             v_base,
+            mask,
+            query_pos,
         );
     }
 }
 
 /// Strided attention — works with interleaved multi-head KV cache layout.
+#[allow(clippy::too_many_arguments)]
 fn attention_strided(
     output: &mut [f32],
     q: &[f32],
@@ -126,7 +161,14 @@ fn attention_strided(
     kv_stride: usize,
     k_base: usize,
     v_base: usize,
+    mask: &AttentionMask,
+    query_pos: usize,
 ) {
+    // Causal needs no per-key check: just stop after the query's position.
+    let (seq_len, causal) = match mask {
+        AttentionMask::Causal => (seq_len.min(query_pos + 1), true),
+        _ => (seq_len, false),
+    };
     if seq_len == 0 {
         for v in output.iter_mut() { *v = 0.0; }
         return;
@@ -138,6 +180,9 @@ fn attention_strided(
     for v in output.iter_mut() { *v = 0.0; }
 
     for t in 0..seq_len {
+        if !causal && !mask.allows(query_pos, t) {
+            continue;
+        }
         let k_offset = t * kv_stride + k_base;
         let v_offset = t * kv_stride + v_base;
 
@@ -224,4 +269,48 @@ mod tests {
             assert_eq!(*v, 0.0);
         }
     }
+
+    /// One head with 1-d keys `[1, 2, 3, 4]` and values `[10, 20, 30, 40]`.
+    fn attend(mask: &AttentionMask, query_pos: usize) -> f32 {
+        let keys = [1.0, 2.0, 3.0, 4.0];
+        let values = [10.0, 20.0, 30.0, 40.0];
+        let mut output = [0.0];
+        multi_head_attention(&mut output, &[1.0], &keys, &values, 1, 1, 4, 1, mask, query_pos);
+        output[0]
+    }
+
+    /// Softmax-weighted mean of `values[t]` for the visible `t`.
+    fn expected(visible: &[usize]) -> f32 {
+        let weights: Vec<f32> = visible.iter().map(|&t| (t as f32 + 1.0).exp()).collect();
+        let total: f32 = weights.iter().sum();
+        visible.iter().zip(&weights).map(|(&t, w)| w * 10.0 * (t as f32 + 1.0)).sum::<f32>() / total
+    }
+
+    #[test]
+    fn test_prefix_lm_mask() {
+        let mask = AttentionMask::PrefixLM { prefix_len: 3 };
+        // Prefix positions see the whole prefix, not what follows it.
+        assert!(mask.allows(0, 2));
+        assert!(!mask.allows(0, 3));
+        assert!(!mask.allows(2, 3));
+        // Past the prefix it is causal.
+        assert!(mask.allows(3, 0) && mask.allows(3, 3));
+        assert!(!mask.allows(3, 4));
+
+        assert!((attend(&mask, 0) - expected(&[0, 1, 2])).abs() < 1e-4);
+        assert!((attend(&mask, 3) - expected(&[0, 1, 2, 3])).abs() < 1e-4);
+        // A causal query at 0 only sees itself.
+        assert!((attend(&AttentionMask::Causal, 0) - 10.0).abs() < 1e-4);
+        assert!((attend(&AttentionMask::PrefixLM { prefix_len: 0 }, 1) - expected(&[0, 1])).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_custom_mask() {
+        let mut bits = BitVec::repeat(false, 4);
+        bits.set(1, true);
+        bits.set(3, true);
+        let mask = AttentionMask::Custom(bits);
+        assert!(!mask.allows(3, 0) && mask.allows(0, 3) && !mask.allows(0, 9));
+        assert!((attend(&mask, 0) - expected(&[1, 3])).abs() < 1e-4);
+    }
 }
@@ -84,6 +84,7 @@ pub fn forward(
     let head_dim = params.head_dim as usize;
     let kv_dim = n_kv_heads * head_dim;
     let vocab_size = params.vocab_size as usize;
+    kv_cache.check_room()?;
 
     // ---- Step 1: Token embedding lookup ----
     let mut x = vec![0.0f32; dim];
@@ -96,10 +97,10 @@ pub fn forward(
         // If embedding is F32, direct copy. Otherwise dequantize.
         if embd_tensor.ggml_type == crate::gguf::GgmlType::F32 {
             let byte_offset = offset * 4;
-            for i in 0..dim {
+            for (i, xi) in x.iter_mut().enumerate().take(dim) {
                 let o = byte_offset + i * 4;
                 if o + 4 <= embd_data.len() {
-                    x[i] = f32::from_le_bytes([embd_data[o], embd_data[o+1], embd_data[o+2], embd_data[o+3]]);
+                    *xi = f32::from_le_bytes([embd_data[o], embd_data[o+1], embd_data[o+2], embd_data[o+3]]);
                 }
             }
         } else {
@@ -10,6 +10,8 @@
 //! - Padding to alignment boundary
 //! - Tensor data
 
+pub mod validate;
+
 use std::collections::HashMap;
 use std::io::{Read, Seek};
 use bizclaw_core::error::{BizClawError, Result};
@@ -185,7 +187,7 @@ impl TensorInfo {
         let n = self.n_elements() as usize;
         let bs = self.ggml_type.block_size();
         let ts = self.ggml_type.type_size();
-        ((n + bs - 1) / bs * ts) as u64
+        (n.div_ceil(bs) * ts) as u64
     }
 }
 
@@ -255,7 +257,7 @@ impl GgufFile {
         // Calculate data offset (aligned to alignment)
         let current_pos = reader.stream_position()
             .map_err(|e| BizClawError::GgufParse(e.to_string()))?;
-        let data_offset = (current_pos + alignment - 1) / alignment * alignment;
+        let data_offset = current_pos.div_ceil(alignment) * alignment;
 
         Ok(GgufFile {
             version,
@@ -0,0 +1,225 @@
+//! Up-front checks for a GGUF model file.
+//!
+//! Reads only the header and metadata, so a wrong `model_path` (not a GGUF
+//! file, a truncated download, an unsupported version, missing
+//! hyperparameters) is reported clearly before anything is mapped or
+//! allocated for the model.
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use bizclaw_core::error::{BizClawError, Result};
+use serde::Serialize;
+
+use super::{GGUF_MAGIC, GGUF_VERSION, GgufValue, read_string, read_u32, read_u64, read_value};
+
+/// What a valid GGUF file says about its model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GgufMetadata {
+    pub version: u32,
+    pub arch: String,
+    pub tensor_count: u64,
+    pub context_length: u32,
+    pub n_heads: u32,
+    /// `{arch}.attention.head_count_kv`; equals `n_heads` without GQA.
+    pub n_kv_heads: u32,
+    pub n_layers: u32,
+    pub embedding_length: u32,
+    /// `{arch}.rope.freq_base`; models without it use 10000.
+    pub rope_theta: Option<f32>,
+    /// `{arch}.vocab_size`, or the length of the tokenizer's token list.
+    pub vocab_size: Option<u32>,
+}
+
+/// Check that `path` is a GGUF file this engine can load, and return its
+/// metadata.
+pub fn validate_gguf(path: &Path) -> Result<GgufMetadata> {
+    let err = |msg: String| BizClawError::Config(format!("Model {}: {msg}", path.display()));
+    let file = File::open(path).map_err(|e| err(format!("can't open: {e}")))?;
+    validate_reader(&mut BufReader::new(file)).map_err(err)
+}
+
+fn validate_reader<R: Read>(reader: &mut R) -> std::result::Result<GgufMetadata, String> {
+    let magic = read_u32(reader).map_err(truncated("the magic bytes"))?;
+    if magic != GGUF_MAGIC {
+        return Err(format!(
+            "not a GGUF file (starts with {:?}, expected \"GGUF\")",
+            String::from_utf8_lossy(&magic.to_le_bytes())
+        ));
+    }
+    let version = read_u32(reader).map_err(truncated("the version"))?;
+    if version != GGUF_VERSION {
+        return Err(format!("GGUF version {version} is not supported (expected {GGUF_VERSION}); re-convert the model"));
+    }
+    let tensor_count = read_u64(reader).map_err(truncated("the tensor count"))?;
+    let kv_count = read_u64(reader).map_err(truncated("the metadata count"))?;
+
+    let mut metadata = std::collections::HashMap::new();
+    for i in 0..kv_count {
+        let key = read_string(reader).map_err(truncated(&format!("metadata key {} of {kv_count}", i + 1)))?;
+        let value = read_value(reader).map_err(truncated(&format!("metadata value '{key}'")))?;
+        metadata.insert(key, value);
+    }
+
+    let arch = metadata.get("general.architecture")
+        .and_then(GgufValue::as_str)
+        .ok_or("missing required metadata 'general.architecture'")?
+        .to_string();
+    let positive = |key: &str| match metadata.get(key).map(GgufValue::as_u32) {
+        Some(Some(v)) if v > 0 => Ok(Some(v)),
+        Some(Some(_)) => Err(format!("metadata '{key}' is 0")),
+        Some(None) => Err(format!("metadata '{key}' is not an integer")),
+        None => Ok(None),
+    };
+    let required = |field: &str| {
+        let key = format!("{arch}.{field}");
+        positive(&key)?.ok_or_else(|| format!("missing required metadata '{key}'"))
+    };
+
+    let n_heads = required("attention.head_count")?;
+    let embedding_length = required("embedding_length")?;
+    if embedding_length % n_heads != 0 {
+        return Err(format!(
+            "embedding_length {embedding_length} is not a multiple of attention.head_count {n_heads}"
+        ));
+    }
+    let n_kv_heads = positive(&format!("{arch}.attention.head_count_kv"))?.unwrap_or(n_heads);
+    if n_heads % n_kv_heads != 0 {
+        return Err(format!(
+            "attention.head_count {n_heads} is not a multiple of attention.head_count_kv {n_kv_heads}"
+        ));
+    }
+    let context_length = match positive("general.context_length")? {
+        Some(general) => positive(&format!("{arch}.context_length"))?.unwrap_or(general),
+        None => required("context_length")?,
+    };
+    let vocab_size = match positive(&format!("{arch}.vocab_size"))? {
+        Some(v) => Some(v),
+        None => match metadata.get("tokenizer.ggml.tokens") {
+            Some(GgufValue::Array(tokens)) => Some(tokens.len() as u32),
+            _ => None,
+        },
+    };
+    Ok(GgufMetadata {
+        version,
+        tensor_count,
+        context_length,
+        n_heads,
+        n_kv_heads,
+        n_layers: required("block_count")?,
+        embedding_length,
+        rope_theta: metadata.get(&format!("{arch}.rope.freq_base")).and_then(GgufValue::as_f32),
+        vocab_size,
+        arch,
+    })
+}
+
+fn truncated(what: &str) -> impl FnOnce(BizClawError) -> String + '_ {
+    move |e| format!("truncated or corrupt while reading {what}: {e}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn string(out: &mut Vec<u8>, s: &str) {
+        out.extend((s.len() as u64).to_le_bytes());
+        out.extend(s.as_bytes());
+    }
+
+    /// A GGUF v3 header with the given metadata and no tensors.
+    fn gguf(kvs: &[(&str, GgufValue)]) -> Vec<u8> {
+        let mut out = b"GGUF".to_vec();
+        out.extend(3u32.to_le_bytes());
+        out.extend(0u64.to_le_bytes());
+        out.extend((kvs.len() as u64).to_le_bytes());
+        for (key, value) in kvs {
+            string(&mut out, key);
+            match value {
+                GgufValue::U32(v) => { out.extend(4u32.to_le_bytes()); out.extend(v.to_le_bytes()); }
+                GgufValue::F32(v) => { out.extend(6u32.to_le_bytes()); out.extend(v.to_le_bytes()); }
+                GgufValue::String(s) => { out.extend(8u32.to_le_bytes()); string(&mut out, s); }
+                other => unimplemented!("{other:?}"),
+            }
+        }
+        out
+    }
+
+    fn llama() -> Vec<(&'static str, GgufValue)> {
+        vec![
+            ("general.architecture", GgufValue::String("llama".into())),
+            ("llama.context_length", GgufValue::U32(2048)),
+            ("llama.attention.head_count", GgufValue::U32(32)),
+            ("llama.block_count", GgufValue::U32(22)),
+            ("llama.embedding_length", GgufValue::U32(2048)),
+            ("llama.rope.freq_base", GgufValue::F32(10000.0)),
+        ]
+    }
+
+    fn validate(bytes: &[u8]) -> std::result::Result<GgufMetadata, String> {
+        validate_reader(&mut &bytes[..])
+    }
+
+    #[test]
+    fn test_valid_header() {
+        assert_eq!(validate(&gguf(&llama())).unwrap(), GgufMetadata {
+            version: 3,
+            arch: "llama".into(),
+            tensor_count: 0,
+            context_length: 2048,
+            n_heads: 32,
+            n_kv_heads: 32,
+            n_layers: 22,
+            embedding_length: 2048,
+            rope_theta: Some(10000.0),
+            vocab_size: None,
+        });
+    }
+
+    #[test]
+    fn test_optional_params() {
+        let mut kvs = llama();
+        kvs.retain(|(k, _)| *k != "llama.context_length");
+        kvs.push(("general.context_length", GgufValue::U32(4096)));
+        kvs.push(("llama.attention.head_count_kv", GgufValue::U32(4)));
+        kvs.push(("llama.vocab_size", GgufValue::U32(32000)));
+        let meta = validate(&gguf(&kvs)).unwrap();
+        assert_eq!((meta.context_length, meta.n_kv_heads, meta.vocab_size), (4096, 4, Some(32000)));
+
+        kvs.retain(|(k, _)| *k != "llama.attention.head_count_kv");
+        kvs.push(("llama.attention.head_count_kv", GgufValue::U32(5)));
+        assert_eq!(
+            validate(&gguf(&kvs)).unwrap_err(),
+            "attention.head_count 32 is not a multiple of attention.head_count_kv 5"
+        );
+    }
+
+    #[test]
+    fn test_invalid_headers() {
+        let err = validate(b"PK\x03\x04rest of a zip").unwrap_err();
+        assert!(err.contains("not a GGUF file"), "{err}");
+
+        let mut v2 = gguf(&llama());
+        v2[4] = 2;
+        assert!(validate(&v2).unwrap_err().contains("version 2 is not supported"));
+
+        let full = gguf(&llama());
+        let err = validate(&full[..full.len() - 3]).unwrap_err();
+        assert!(err.contains("truncated or corrupt while reading metadata value 'llama.rope.freq_base'"), "{err}");
+
+        let mut kvs = llama();
+        kvs.retain(|(k, _)| *k != "llama.block_count");
+        assert_eq!(validate(&gguf(&kvs)).unwrap_err(), "missing required metadata 'llama.block_count'");
+
+        let mut kvs = llama();
+        kvs[2].1 = GgufValue::U32(0);
+        assert_eq!(validate(&gguf(&kvs)).unwrap_err(), "metadata 'llama.attention.head_count' is 0");
+    }
+
+    #[test]
+    fn test_validate_gguf_names_the_file() {
+        let err = validate_gguf(Path::new("/nonexistent/model.gguf")).unwrap_err().to_string();
+        assert!(err.contains("/nonexistent/model.gguf") && err.contains("can't open"), "{err}");
+    }
+}
@@ -3,6 +3,11 @@
 //! Pre-analyzes vocabulary tokens at load time for JSON structure properties
 //! (brace delta, bracket delta, quote parity). During generation, masks logits
 //! to guarantee syntactically valid JSON — essential for tool calling with small models.
+//!
+//! [`Grammar`] does the same for any GBNF grammar: each step only the tokens
+//! that keep the output a valid prefix of the grammar are allowed.
+
+use bizclaw_core::error::{BizClawError, Result};
 
 /// JSON grammar state machine for constrained decoding.
 #[derive(Debug, Clone)]
@@ -25,7 +30,7 @@ pub struct TokenJsonProps {
 }
 
 /// JSON parsing state — tracks structure validity.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct JsonState {
     pub brace_depth: i32,
     pub bracket_depth: i32,
@@ -35,19 +40,6 @@ pub struct JsonState {
     pub completed: bool,
 }
 
-impl Default for JsonState {
-    fn default() -> Self {
-        Self {
-            brace_depth: 0,
-            bracket_depth: 0,
-            in_string: false,
-            expect_value: false,
-            started: false,
-            completed: false,
-        }
-    }
-}
-
 impl JsonGrammar {
     /// Analyze all tokens in vocabulary for JSON properties (done once at load).
     pub fn new(vocab: &[String]) -> Self {
@@ -163,6 +155,444 @@ impl JsonGrammar {
     }
 }
 
+/// Token texts of a model's vocabulary, as the grammar sees them.
+#[derive(Debug, Clone)]
+pub struct Vocabulary {
+    /// Text each token adds to the output; empty for special tokens, which
+    /// a grammar only allows as EOS.
+    tokens: Vec<String>,
+    /// Allowed once the grammar is complete.
+    eos_id: u32,
+}
+
+impl Vocabulary {
+    pub fn new(tokens: Vec<String>, eos_id: u32) -> Self {
+        Self { tokens, eos_id }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+/// One symbol of a rule alternative.
+#[derive(Debug, Clone, PartialEq)]
+enum Symbol {
+    /// A character in (or, if negated, outside) the ranges.
+    Char { ranges: Vec<(char, char)>, negated: bool },
+    /// Another rule, by index.
+    Rule(usize),
+}
+
+impl Symbol {
+    fn literal(c: char) -> Self {
+        Symbol::Char { ranges: vec![(c, c)], negated: false }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Symbol::Char { ranges, negated } => ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated,
+            Symbol::Rule(_) => false,
+        }
+    }
+}
+
+/// Where a parse continues: (rule, alternative, symbol) positions, the next
+/// one to match last.
+type Stack = Vec<(usize, usize, usize)>;
+
+/// A GBNF grammar (the llama.cpp format) that generation can be held to.
+///
+/// Supports rules (`name ::= ...`), alternatives (`|`), string literals,
+/// character classes (`[a-z]`, `[^"]`, `.`), groups and the `*`, `+` and
+/// `?` repetitions. Parsing starts at the `root` rule.
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    /// Alternatives of each rule; repetitions and groups become extra rules.
+    rules: Vec<Vec<Vec<Symbol>>>,
+    names: Vec<String>,
+    root: usize,
+}
+
+impl std::str::FromStr for Grammar {
+    type Err = BizClawError;
+
+    fn from_str(gbnf: &str) -> Result<Self> {
+        let mut parser = GbnfParser { src: gbnf, pos: 0, grammar: Grammar { rules: Vec::new(), names: Vec::new(), root: 0 }, defined: Vec::new() };
+        parser.parse()?;
+        let mut grammar = parser.grammar;
+        if let Some(missing) = grammar.names.iter().enumerate().find(|(i, _)| !parser.defined[*i]) {
+            return Err(BizClawError::Brain(format!("Grammar rule '{}' is used but not defined", missing.1)));
+        }
+        grammar.root = grammar.names.iter().position(|n| n == "root")
+            .ok_or_else(|| BizClawError::Brain("Grammar has no 'root' rule".into()))?;
+        grammar.check_left_recursion()?;
+        Ok(grammar)
+    }
+}
+
+impl Grammar {
+    /// IDs of the tokens whose text can legally follow `partial_output`,
+    /// plus EOS when `partial_output` is already a complete match. Empty if
+    /// `partial_output` doesn't match the grammar.
+    pub fn allowed_tokens(&self, vocabulary: &Vocabulary, partial_output: &str) -> Vec<u32> {
+        let stacks = self.advance(self.start(), partial_output);
+        if stacks.is_empty() {
+            return Vec::new();
+        }
+        let mut allowed: Vec<u32> = vocabulary.tokens.iter().enumerate()
+            .filter(|(_, text)| !text.is_empty() && !self.advance(stacks.clone(), text).is_empty())
+            .map(|(id, _)| id as u32)
+            .collect();
+        if stacks.iter().any(|s| s.is_empty()) && (vocabulary.eos_id as usize) < vocabulary.tokens.len() {
+            allowed.push(vocabulary.eos_id);
+            allowed.sort_unstable();
+            allowed.dedup();
+        }
+        allowed
+    }
+
+    /// Whether `text` matches the whole grammar.
+    pub fn accepts(&self, text: &str) -> bool {
+        self.advance(self.start(), text).iter().any(|s| s.is_empty())
+    }
+
+    fn start(&self) -> Vec<Stack> {
+        let mut stacks = Vec::new();
+        for alt in 0..self.rules[self.root].len() {
+            let stack = if self.rules[self.root][alt].is_empty() { Vec::new() } else { vec![(self.root, alt, 0)] };
+            self.expand(stack, &mut stacks);
+        }
+        stacks
+    }
+
+    /// The stacks left after matching `text`.
+    fn advance(&self, mut stacks: Vec<Stack>, text: &str) -> Vec<Stack> {
+        for c in text.chars() {
+            let mut next = Vec::new();
+            for mut stack in stacks {
+                let Some(&(rule, alt, pos)) = stack.last() else { continue };
+                if !self.rules[rule][alt][pos].matches(c) {
+                    continue;
+                }
+                stack.pop();
+                if pos + 1 < self.rules[rule][alt].len() {
+                    stack.push((rule, alt, pos + 1));
+                }
+                self.expand(stack, &mut next);
+            }
+            next.sort_unstable();
+            next.dedup();
+            if next.is_empty() {
+                return next;
+            }
+            stacks = next;
+        }
+        stacks
+    }
+
+    /// Push rule references until every stack has a character next (or is
+    /// empty: the grammar is complete).
+    fn expand(&self, mut stack: Stack, out: &mut Vec<Stack>) {
+        let Some(&(rule, alt, pos)) = stack.last() else {
+            out.push(stack);
+            return;
+        };
+        let Symbol::Rule(sub) = self.rules[rule][alt][pos] else {
+            out.push(stack);
+            return;
+        };
+        stack.pop();
+        if pos + 1 < self.rules[rule][alt].len() {
+            stack.push((rule, alt, pos + 1));
+        }
+        for (sub_alt, symbols) in self.rules[sub].iter().enumerate() {
+            let mut branch = stack.clone();
+            if !symbols.is_empty() {
+                branch.push((sub, sub_alt, 0));
+            }
+            self.expand(branch, out);
+        }
+    }
+
+    /// Left recursion would make [`Grammar::expand`] loop forever.
+    fn check_left_recursion(&self) -> Result<()> {
+        let mut nullable = vec![false; self.rules.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (i, alts) in self.rules.iter().enumerate() {
+                if !nullable[i] && alts.iter().any(|alt| alt.iter().all(|s| matches!(s, Symbol::Rule(r) if nullable[*r]))) {
+                    nullable[i] = true;
+                    changed = true;
+                }
+            }
+        }
+        // The rules each rule can start with.
+        let firsts: Vec<Vec<usize>> = self.rules.iter().map(|alts| {
+            let mut first = Vec::new();
+            for alt in alts {
+                for symbol in alt {
+                    let Symbol::Rule(r) = symbol else { break };
+                    first.push(*r);
+                    if !nullable[*r] {
+                        break;
+                    }
+                }
+            }
+            first
+        }).collect();
+        for start in 0..self.rules.len() {
+            let mut seen = vec![false; self.rules.len()];
+            let mut todo = firsts[start].clone();
+            while let Some(r) = todo.pop() {
+                if r == start {
+                    return Err(BizClawError::Brain(format!("Grammar rule '{}' is left-recursive", self.names[start])));
+                }
+                if !std::mem::replace(&mut seen[r], true) {
+                    todo.extend(&firsts[r]);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Set the logits of all tokens but `allowed` (sorted) to -inf.
+pub fn mask_logits(logits: &mut [f32], allowed: &[u32]) {
+    for (id, logit) in logits.iter_mut().enumerate() {
+        if allowed.binary_search(&(id as u32)).is_err() {
+            *logit = f32::NEG_INFINITY;
+        }
+    }
+}
+
+struct GbnfParser<'a> {
+    src: &'a str,
+    pos: usize,
+    grammar: Grammar,
+    /// Per rule, whether it has a definition (not just references).
+    defined: Vec<bool>,
+}
+
+impl GbnfParser<'_> {
+    fn parse(&mut self) -> Result<()> {
+        self.skip_space();
+        while self.peek().is_some() {
+            let name = self.name()?;
+            self.skip_space();
+            if !self.src[self.pos..].starts_with("::=") {
+                return Err(self.error("expected '::='"));
+            }
+            self.pos += 3;
+            let rule = self.rule_id(&name);
+            if self.defined[rule] {
+                return Err(BizClawError::Brain(format!("Grammar rule '{name}' is defined twice")));
+            }
+            self.defined[rule] = true;
+            self.grammar.rules[rule] = self.alternatives(false)?;
+        }
+        Ok(())
+    }
+
+    fn alternatives(&mut self, in_group: bool) -> Result<Vec<Vec<Symbol>>> {
+        let mut alts = vec![self.sequence(in_group)?];
+        while self.peek() == Some('|') {
+            self.pos += 1;
+            alts.push(self.sequence(in_group)?);
+        }
+        Ok(alts)
+    }
+
+    fn sequence(&mut self, in_group: bool) -> Result<Vec<Symbol>> {
+        let mut symbols = Vec::new();
+        loop {
+            self.skip_space();
+            let start = symbols.len();
+            match self.peek() {
+                None | Some('|') => break,
+                Some(')') if in_group => break,
+                Some('"') => {
+                    self.pos += 1;
+                    while self.peek() != Some('"') {
+                        let c = self.char_in_literal()?;
+                        symbols.push(Symbol::literal(c));
+                    }
+                    self.pos += 1;
+                }
+                Some('[') => {
+                    self.pos += 1;
+                    symbols.push(self.class()?);
+                }
+                Some('.') => {
+                    self.pos += 1;
+                    symbols.push(Symbol::Char { ranges: Vec::new(), negated: true });
+                }
+                Some('(') => {
+                    self.pos += 1;
+                    let alts = self.alternatives(true)?;
+                    if self.peek() != Some(')') {
+                        return Err(self.error("expected ')'"));
+                    }
+                    self.pos += 1;
+                    symbols.push(Symbol::Rule(self.anonymous(alts)));
+                }
+                Some(c) if is_name_char(c) => {
+                    // A name followed by `::=` starts the next rule.
+                    let save = self.pos;
+                    let name = self.name()?;
+                    let after_name = self.pos;
+                    self.skip_space();
+                    if self.src[self.pos..].starts_with("::=") {
+                        self.pos = save;
+                        break;
+                    }
+                    self.pos = after_name;
+                    symbols.push(Symbol::Rule(self.rule_id(&name)));
+                }
+                Some(c) => return Err(self.error(&format!("unexpected '{c}'"))),
+            }
+            self.repetition(&mut symbols, start);
+        }
+        Ok(symbols)
+    }
+
+    /// Apply a `*`, `+` or `?` after the element `symbols[start..]`.
+    fn repetition(&mut self, symbols: &mut Vec<Symbol>, start: usize) {
+        let op = match self.peek() {
+            Some(op @ ('*' | '+' | '?')) => op,
+            _ => return,
+        };
+        self.pos += 1;
+        let element = symbols.split_off(start);
+        let rule = match op {
+            // x? ::= x | ε
+            '?' => self.anonymous(vec![element, Vec::new()]),
+            // x* ::= x x* | ε
+            _ => {
+                let rule = self.anonymous(Vec::new());
+                let mut repeat = element.clone();
+                repeat.push(Symbol::Rule(rule));
+                self.grammar.rules[rule] = vec![repeat, Vec::new()];
+                if op == '+' {
+                    symbols.extend(element);
+                }
+                rule
+            }
+        };
+        symbols.push(Symbol::Rule(rule));
+    }
+
+    fn class(&mut self) -> Result<Symbol> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.pos += 1;
+        }
+        let mut ranges = Vec::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("unterminated character class")),
+                Some(']') => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => {}
+            }
+            let lo = self.char_in_literal()?;
+            let hi = if self.peek() == Some('-') && !self.src[self.pos + 1..].starts_with(']') {
+                self.pos += 1;
+                self.char_in_literal()?
+            } else {
+                lo
+            };
+            ranges.push((lo, hi));
+        }
+        Ok(Symbol::Char { ranges, negated })
+    }
+
+    fn char_in_literal(&mut self) -> Result<char> {
+        let c = self.peek().ok_or_else(|| self.error("unterminated literal"))?;
+        self.pos += c.len_utf8();
+        if c != '\\' {
+            return Ok(c);
+        }
+        let escaped = self.peek().ok_or_else(|| self.error("unterminated escape"))?;
+        self.pos += escaped.len_utf8();
+        let hex_digits = match escaped {
+            'n' => return Ok('\n'),
+            'r' => return Ok('\r'),
+            't' => return Ok('\t'),
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            other => return Ok(other),
+        };
+        let hex = self.src.get(self.pos..self.pos + hex_digits).ok_or_else(|| self.error("short escape"))?;
+        self.pos += hex_digits;
+        u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+            .ok_or_else(|| self.error(&format!("invalid escape '\\{escaped}{hex}'")))
+    }
+
+    fn name(&mut self) -> Result<String> {
+        let len = self.src[self.pos..].find(|c: char| !is_name_char(c)).unwrap_or(self.src.len() - self.pos);
+        if len == 0 {
+            return Err(self.error("expected a rule name"));
+        }
+        let name = self.src[self.pos..self.pos + len].to_string();
+        self.pos += len;
+        Ok(name)
+    }
+
+    fn rule_id(&mut self, name: &str) -> usize {
+        if let Some(id) = self.grammar.names.iter().position(|n| n == name) {
+            return id;
+        }
+        self.grammar.names.push(name.to_string());
+        self.grammar.rules.push(Vec::new());
+        self.defined.push(false);
+        self.grammar.rules.len() - 1
+    }
+
+    /// A rule for a group or repetition.
+    fn anonymous(&mut self, alts: Vec<Vec<Symbol>>) -> usize {
+        let id = self.grammar.rules.len();
+        self.grammar.names.push(format!("<{id}>"));
+        self.grammar.rules.push(alts);
+        self.defined.push(true);
+        id
+    }
+
+    /// Skip whitespace and `#` comments.
+    fn skip_space(&mut self) {
+        loop {
+            let rest = &self.src[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if !trimmed.starts_with('#') {
+                break;
+            }
+            self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn error(&self, message: &str) -> BizClawError {
+        let line = self.src[..self.pos].matches('\n').count() + 1;
+        BizClawError::Brain(format!("Invalid grammar (line {line}): {message}"))
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -224,4 +654,62 @@ mod tests {
         assert!(logits[2] == f32::NEG_INFINITY); // hello
         assert!(logits[3].is_finite()); // [
     }
+
+    const JSON_OBJECT: &str = r#"
+        # A flat JSON object of string and number values.
+        root   ::= "{" ws ( pair ( "," ws pair )* )? "}"
+        pair   ::= string ":" ws value ws
+        value  ::= string | number | "true" | "false" | "null"
+        string ::= "\"" [^"\\]* "\""
+        number ::= "-"? [0-9]+ ("." [0-9]+)?
+        ws     ::= [ \t\n]*
+    "#;
+
+    #[test]
+    fn test_gbnf_forces_valid_json() {
+        let grammar: Grammar = JSON_OBJECT.parse().unwrap();
+        let texts = ["{", "}", "\"", "name", "\":", " ", "42", ",", "Hello!", "", "true", ".5"];
+        let vocabulary = Vocabulary::new(texts.iter().map(|t| t.to_string()).collect(), 9);
+
+        // A model that would rather chat than write JSON: prose first, then
+        // EOS, then the rest, each less likely the more it was used.
+        let preference = [8, 9, 3, 5, 0, 2, 4, 6, 10, 11, 7, 1];
+        let mut uses = [0.0f32; 12];
+        let mut output = String::new();
+        for _ in 0..40 {
+            let allowed = grammar.allowed_tokens(&vocabulary, &output);
+            let mut logits: Vec<f32> = (0..texts.len())
+                .map(|id| -(preference.iter().position(|&p| p == id).unwrap() as f32) - 5.0 * uses[id])
+                .collect();
+            mask_logits(&mut logits, &allowed);
+            let (next, _) = logits.iter().enumerate().max_by(|a, b| a.1.total_cmp(b.1)).unwrap();
+            if next == 9 {
+                break;
+            }
+            uses[next] += 1.0;
+            output.push_str(texts[next]);
+        }
+        assert!(grammar.accepts(&output), "{output}");
+        let value: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert!(value.is_object(), "{output}");
+
+        // EOS only once the object is closed; tokens that break it never.
+        assert_eq!(grammar.allowed_tokens(&vocabulary, "{\"name\": "), vec![2, 4, 5, 6, 10]);
+        assert!(grammar.allowed_tokens(&vocabulary, "{").contains(&1));
+        assert!(!grammar.allowed_tokens(&vocabulary, "{").contains(&9));
+        assert!(grammar.allowed_tokens(&vocabulary, "{}").contains(&9));
+        assert!(grammar.allowed_tokens(&vocabulary, "Hello!").is_empty());
+        assert!(grammar.accepts("{\"a\": -1.5, \"b\": \"x\"}"));
+        assert!(!grammar.accepts("{\"a\": 1,}"));
+    }
+
+    #[test]
+    fn test_gbnf_errors() {
+        let err = |gbnf: &str| gbnf.parse::<Grammar>().unwrap_err().to_string();
+        assert!(err("root ::= item").contains("'item' is used but not defined"));
+        assert!(err("item ::= \"a\"").contains("no 'root' rule"));
+        assert!(err("root ::= root \"a\" | \"b\"").contains("left-recursive"));
+        assert!(err("root ::= \"a\" x\nx ::= [a-").contains("line 2"));
+        assert!("root ::= (\"ab\" | [x-z]+)? .\n".parse::<Grammar>().unwrap().accepts("abé"));
+    }
 }
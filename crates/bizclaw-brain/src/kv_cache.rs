@@ -1,12 +1,93 @@
 //! KV Cache — both f32 (compatible) and FP16 (memory-optimised) variants.
 //!
 //! FP16 variant halves memory (88MB → 44MB for typical models).
-//! Includes KV Cache Persistence (save/load .bckv files)
+//! Includes KV Cache Persistence (save/load .bckv files, optionally gzip-compressed)
 //! and Pre-computed RoPE tables for fast positional encoding.
 
 use std::io::{Read, Write};
 use std::path::Path;
 
+use bizclaw_core::error::{BizClawError, Result};
+use serde::{Deserialize, Serialize};
+
+/// On-disk format version of `.bckv` files. Bump when the layout changes.
+pub const KV_CACHE_FORMAT_VERSION: u8 = 3;
+
+/// Header flag: the data section is gzip-compressed.
+const FLAG_GZIP: u8 = 0x01;
+
+// ── Eviction ────────────────────────────────────────────────
+
+/// What to do once a sequence fills all `max_seq_len` cache slots.
+///
+/// Evicted entries keep the RoPE rotation of their original position, so
+/// the window is an approximation of the full context, not a re-encoding.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum KvCacheEviction {
+    /// Fail with an error when a token is stored past the last slot.
+    #[default]
+    Error,
+    /// Keep only the last `window_size` tokens.
+    SlidingWindow { window_size: usize },
+    /// Keep the first `sink_count` tokens (StreamingLLM attention sinks)
+    /// and the last `window_size` after them.
+    SinkTokens { sink_count: usize, window_size: usize },
+}
+
+impl KvCacheEviction {
+    /// `(sink, window)`: the leading entries left in place and the trailing
+    /// entries moved up behind them. Both are clamped so at least one slot
+    /// is freed. `None` for [`KvCacheEviction::Error`].
+    fn retain(&self, max_seq_len: usize) -> Option<(usize, usize)> {
+        let room = max_seq_len.saturating_sub(1);
+        match *self {
+            Self::Error => None,
+            Self::SlidingWindow { window_size } => Some((0, window_size.min(room))),
+            Self::SinkTokens { sink_count, window_size } => {
+                let sink = sink_count.min(room);
+                Some((sink, window_size.min(room - sink)))
+            }
+        }
+    }
+}
+
+/// Advance `pos` and, if that fills the cache, evict per `eviction` by
+/// moving the kept window of every layer up behind the sink tokens. With
+/// [`KvCacheEviction::Error`] the cache is left full, every slot used.
+/// Fails if called with the cache already full.
+fn advance_cache<T: Copy>(
+    caches: [&mut [T]; 2],
+    layout: (usize, usize, usize),
+    pos: &mut usize,
+    eviction: &KvCacheEviction,
+) -> Result<()> {
+    let (n_layers, max_seq_len, kv_dim) = layout;
+    if *pos >= max_seq_len {
+        return Err(cache_full(max_seq_len));
+    }
+    *pos += 1;
+    if *pos < max_seq_len {
+        return Ok(());
+    }
+    let Some((sink, window)) = eviction.retain(max_seq_len) else {
+        return Ok(());
+    };
+    for cache in caches {
+        for layer in 0..n_layers {
+            let base = layer * max_seq_len;
+            let from = (base + *pos - window) * kv_dim;
+            cache.copy_within(from..from + window * kv_dim, (base + sink) * kv_dim);
+        }
+    }
+    *pos = sink + window;
+    Ok(())
+}
+
+fn cache_full(max_seq_len: usize) -> BizClawError {
+    BizClawError::Brain(format!("KV cache full ({max_seq_len} tokens)"))
+}
+
 // ── f32 KV Cache (backward compatible) ──────────────────────
 
 /// Standard f32 KV Cache for transformer inference.
@@ -47,7 +128,27 @@ impl KvCache {
     }
 
     pub fn advance(&mut self) { self.pos += 1; }
+
+    /// Advance past the entry just stored at `pos()`, evicting per
+    /// `eviction` when that fills the cache.
+    pub fn advance_with_eviction(&mut self, eviction: &KvCacheEviction) -> Result<()> {
+        let layout = (self.n_layers, self.max_seq_len, self.kv_dim);
+        advance_cache([&mut self.key_cache, &mut self.value_cache], layout, &mut self.pos, eviction)
+    }
+
     pub fn pos(&self) -> usize { self.pos }
+    pub fn n_layers(&self) -> usize { self.n_layers }
+
+    /// Every slot holds a token; the next one can't be stored.
+    pub fn is_full(&self) -> bool { self.pos >= self.max_seq_len }
+
+    /// Fail if the next token can't be stored.
+    pub fn check_room(&self) -> Result<()> {
+        if self.is_full() {
+            return Err(cache_full(self.max_seq_len));
+        }
+        Ok(())
+    }
 
     pub fn reset(&mut self) {
         self.key_cache.fill(0.0);
@@ -64,6 +165,8 @@ impl KvCache {
 
 
 /// Convert f32 to IEEE 754 half-precision float (FP16).
+///
+/// Rounds to nearest, ties to even, so repeated conversions carry no bias.
 #[inline(always)]
 pub fn fp32_to_fp16(value: f32) -> u16 {
     let bits = value.to_bits();
@@ -85,11 +188,33 @@ pub fn fp32_to_fp16(value: f32) -> u16 {
         if exp < -10 {
             return sign as u16; // Too small → zero
         }
-        let m = (mantissa | 0x800000) >> (1 - exp);
-        return (sign | (m >> 13)) as u16;
+        // Subnormal: shift the implicit-1 mantissa down, rounding on the bits lost.
+        // A carry out of the mantissa lands on the smallest normal, as it should.
+        let full = mantissa | 0x800000;
+        let shift = (14 - exp) as u32;
+        let m = round_shift_even(full, shift);
+        return (sign | m) as u16;
     }
 
-    (sign | ((exp as u32) << 10) | (mantissa >> 13)) as u16
+    // A carry out of the mantissa bumps the exponent; past the max it becomes infinity.
+    let truncated = ((exp as u32) << 10) | (mantissa >> 13);
+    let rounded = truncated + round_up_bit(mantissa, 13, truncated);
+    (sign | rounded) as u16
+}
+
+/// `value >> shift`, rounded to nearest with ties to even.
+#[inline(always)]
+fn round_shift_even(value: u32, shift: u32) -> u32 {
+    let truncated = value >> shift;
+    truncated + round_up_bit(value, shift, truncated)
+}
+
+/// 1 if dropping the low `shift` bits of `value` should round `truncated` up.
+#[inline(always)]
+fn round_up_bit(value: u32, shift: u32, truncated: u32) -> u32 {
+    let rem = value & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    u32::from(rem > halfway || (rem == halfway && truncated & 1 == 1))
 }
 
 /// Convert IEEE 754 half-precision float (FP16) to f32.
@@ -121,6 +246,12 @@ pub fn fp16_to_fp32(value: u16) -> f32 {
     f32::from_bits(bits)
 }
 
+/// Number of leading tokens two prompts share — how much of a cache built for
+/// `cached` can be kept for `prompt`.
+pub fn common_prefix_len(cached: &[u32], prompt: &[u32]) -> usize {
+    cached.iter().zip(prompt).take_while(|(a, b)| a == b).count()
+}
+
 /// FP16 KV Cache — 50% less memory than f32 cache.
 pub struct Fp16KvCache {
     /// Key cache stored as FP16: [n_layers x max_seq_len x kv_dim]
@@ -168,8 +299,8 @@ impl Fp16KvCache {
     pub fn load_keys(&self, layer: usize, seq_len: usize, output: &mut [f32]) {
         let offset = layer * self.max_seq_len * self.kv_dim;
         let count = seq_len * self.kv_dim;
-        for i in 0..count {
-            output[i] = fp16_to_fp32(self.key_cache[offset + i]);
+        for (out, &h) in output[..count].iter_mut().zip(&self.key_cache[offset..offset + count]) {
+            *out = fp16_to_fp32(h);
         }
     }
 
@@ -177,17 +308,35 @@ impl Fp16KvCache {
     pub fn load_values(&self, layer: usize, seq_len: usize, output: &mut [f32]) {
         let offset = layer * self.max_seq_len * self.kv_dim;
         let count = seq_len * self.kv_dim;
-        for i in 0..count {
-            output[i] = fp16_to_fp32(self.value_cache[offset + i]);
+        for (out, &h) in output[..count].iter_mut().zip(&self.value_cache[offset..offset + count]) {
+            *out = fp16_to_fp32(h);
         }
     }
 
     /// Advance the position counter.
     pub fn advance(&mut self) { self.pos += 1; }
 
+    /// Advance past the entry just stored at `pos()`. When that fills the
+    /// cache, make room for the next token per `eviction`.
+    pub fn advance_with_eviction(&mut self, eviction: &KvCacheEviction) -> Result<()> {
+        let layout = (self.n_layers, self.max_seq_len, self.kv_dim);
+        advance_cache([&mut self.key_cache, &mut self.value_cache], layout, &mut self.pos, eviction)
+    }
+
     /// Get current position.
     pub fn pos(&self) -> usize { self.pos }
 
+    /// Every slot holds a token; the next one can't be stored.
+    pub fn is_full(&self) -> bool { self.pos >= self.max_seq_len }
+
+    /// Fail if the next token can't be stored.
+    pub fn check_room(&self) -> Result<()> {
+        if self.is_full() {
+            return Err(cache_full(self.max_seq_len));
+        }
+        Ok(())
+    }
+
     /// Reset cache.
     pub fn reset(&mut self) {
         self.key_cache.fill(0);
@@ -195,55 +344,125 @@ impl Fp16KvCache {
         self.pos = 0;
     }
 
+    /// Keep entries at positions `< pos` and rewind to `pos`.
+    ///
+    /// Used to reuse a cached prompt prefix: load the cache, truncate to the
+    /// length shared with the new prompt (see `common_prefix_len`), then only
+    /// run the divergent suffix through the model. No-op if `pos >= self.pos()`.
+    pub fn truncate_to(&mut self, pos: usize) {
+        if pos >= self.pos {
+            return;
+        }
+        for layer in 0..self.n_layers {
+            let start = (layer * self.max_seq_len + pos) * self.kv_dim;
+            let end = (layer * self.max_seq_len + self.pos) * self.kv_dim;
+            self.key_cache[start..end].fill(0);
+            self.value_cache[start..end].fill(0);
+        }
+        self.pos = pos;
+    }
+
     /// Memory usage in bytes (half of f32 cache).
     pub fn memory_usage(&self) -> usize {
         (self.key_cache.len() + self.value_cache.len()) * std::mem::size_of::<u16>()
     }
 
     /// Save KV cache to disk for persistence (74% latency reduction on reload).
-    pub fn save(&self, path: &Path) -> std::io::Result<()> {
-        let mut file = std::fs::File::create(path)?;
-        // Header: magic + metadata
+    ///
+    /// `model_fingerprint` identifies the model the cache was built with
+    /// (see `ModelParams::fingerprint`) and is checked again on load.
+    /// With `compress`, the data section is gzipped; the header stays plain.
+    pub fn save(&self, path: &Path, model_fingerprint: u64, compress: bool) -> std::io::Result<()> {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+        // Header: magic + version + flags + model fingerprint + metadata
         file.write_all(b"BCKV")?; // magic
+        file.write_all(&[KV_CACHE_FORMAT_VERSION])?;
+        file.write_all(&[if compress { FLAG_GZIP } else { 0 }])?;
+        file.write_all(&model_fingerprint.to_le_bytes())?;
         file.write_all(&(self.n_layers as u32).to_le_bytes())?;
         file.write_all(&(self.max_seq_len as u32).to_le_bytes())?;
         file.write_all(&(self.kv_dim as u32).to_le_bytes())?;
         file.write_all(&(self.pos as u32).to_le_bytes())?;
         // Data
+        if compress {
+            let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+            self.write_data(&mut encoder)?;
+            encoder.finish()?.flush()
+        } else {
+            self.write_data(&mut file)?;
+            file.flush()
+        }
+    }
+
+    /// Write the raw key + value sections.
+    fn write_data(&self, out: &mut impl Write) -> std::io::Result<()> {
         let key_bytes: Vec<u8> = self.key_cache.iter()
             .flat_map(|&v| v.to_le_bytes())
             .collect();
-        file.write_all(&key_bytes)?;
+        out.write_all(&key_bytes)?;
         let val_bytes: Vec<u8> = self.value_cache.iter()
             .flat_map(|&v| v.to_le_bytes())
             .collect();
-        file.write_all(&val_bytes)?;
-        Ok(())
+        out.write_all(&val_bytes)
     }
 
     /// Load KV cache from disk.
-    pub fn load_from(path: &Path) -> std::io::Result<Self> {
-        let mut file = std::fs::File::open(path)?;
+    ///
+    /// Rejects files written by a different format version or for a model
+    /// whose fingerprint differs from `expected_fingerprint`. Compression is
+    /// detected from the header flags.
+    pub fn load_from(path: &Path, expected_fingerprint: u64) -> std::io::Result<Self> {
+        let mut file = std::io::BufReader::new(std::fs::File::open(path)?);
         let mut magic = [0u8; 4];
         file.read_exact(&mut magic)?;
         if &magic != b"BCKV" {
             return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Not a BizClaw KV cache file"));
         }
+        let mut version = [0u8; 1];
+        file.read_exact(&mut version)?;
+        if version[0] != KV_CACHE_FORMAT_VERSION {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Unsupported KV cache version {} (expected {KV_CACHE_FORMAT_VERSION}); rebuild the cache",
+                    version[0]
+                ),
+            ));
+        }
+        let mut flags = [0u8; 1];
+        file.read_exact(&mut flags)?;
+        let mut buf8 = [0u8; 8];
+        file.read_exact(&mut buf8)?;
+        let fingerprint = u64::from_le_bytes(buf8);
+        if fingerprint != expected_fingerprint {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "KV cache was built for a different model (fingerprint {fingerprint:016x}, expected {expected_fingerprint:016x})"
+                ),
+            ));
+        }
         let mut buf4 = [0u8; 4];
         file.read_exact(&mut buf4)?; let n_layers = u32::from_le_bytes(buf4) as usize;
         file.read_exact(&mut buf4)?; let max_seq_len = u32::from_le_bytes(buf4) as usize;
         file.read_exact(&mut buf4)?; let kv_dim = u32::from_le_bytes(buf4) as usize;
         file.read_exact(&mut buf4)?; let pos = u32::from_le_bytes(buf4) as usize;
 
+        let mut data: Box<dyn Read> = if flags[0] & FLAG_GZIP != 0 {
+            Box::new(flate2::read::GzDecoder::new(file))
+        } else {
+            Box::new(file)
+        };
+
         let total = n_layers * max_seq_len * kv_dim;
         let mut key_bytes = vec![0u8; total * 2];
-        file.read_exact(&mut key_bytes)?;
+        data.read_exact(&mut key_bytes)?;
         let key_cache: Vec<u16> = key_bytes.chunks_exact(2)
             .map(|c| u16::from_le_bytes([c[0], c[1]]))
             .collect();
 
         let mut val_bytes = vec![0u8; total * 2];
-        file.read_exact(&mut val_bytes)?;
+        data.read_exact(&mut val_bytes)?;
         let value_cache: Vec<u16> = val_bytes.chunks_exact(2)
             .map(|c| u16::from_le_bytes([c[0], c[1]]))
             .collect();
@@ -311,6 +530,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_fp16_roundtrip() {
         let values = [0.0f32, 1.0, -1.0, 0.5, 3.14, -0.001, 65504.0];
         for &v in &values {
@@ -322,6 +542,141 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_fp16_round_to_nearest_even() {
+        let ulp = 2f32.powi(-10);
+        // Exactly halfway: ties go to the even mantissa.
+        assert_eq!(fp32_to_fp16(1.0 + ulp / 2.0), 0x3C00);
+        assert_eq!(fp32_to_fp16(1.0 + 1.5 * ulp), 0x3C02);
+        // Just past halfway rounds up, just below rounds down.
+        assert_eq!(fp32_to_fp16(1.0 + ulp * 0.51), 0x3C01);
+        assert_eq!(fp32_to_fp16(1.0 + ulp * 0.49), 0x3C00);
+        // Mantissa carry into the exponent.
+        assert_eq!(fp32_to_fp16(2047.6), 0x6800);
+        // Largest finite value, and rounding past it.
+        assert_eq!(fp32_to_fp16(65519.0), 0x7BFF);
+        assert_eq!(fp32_to_fp16(65520.0), 0x7C00);
+        // Subnormals round too; the largest one carries into the smallest normal.
+        let sub_ulp = 2f32.powi(-24);
+        assert_eq!(fp32_to_fp16(sub_ulp * 0.6), 0x0001);
+        assert_eq!(fp32_to_fp16(sub_ulp * 2.5), 0x0002);
+        assert_eq!(fp32_to_fp16(sub_ulp * 1023.7), 0x0400);
+    }
+
+    #[test]
+    fn test_fp16_no_rounding_bias() {
+        // Truncation would push every error the same way; RNE stays centered.
+        let mut total_err = 0.0f64;
+        let mut worst = 0.0f32;
+        for i in 0..10_000 {
+            let v = 1.0 + (i as f32) * 0.000_123_7;
+            let back = fp16_to_fp32(fp32_to_fp16(v));
+            total_err += (back - v) as f64;
+            worst = worst.max(((back - v) / v).abs());
+        }
+        assert!((total_err / 10_000.0).abs() < 1e-5, "mean error {}", total_err / 10_000.0);
+        assert!(worst <= 2f32.powi(-11));
+    }
+
+    #[test]
+    fn test_truncate_to_keeps_prefix() {
+        let mut cache = Fp16KvCache::new(2, 8, 1, 2);
+        for pos in 0..5 {
+            for layer in 0..2 {
+                cache.store_key(layer, pos, &[pos as f32, 1.0]);
+                cache.store_value(layer, pos, &[1.0, pos as f32]);
+            }
+            cache.advance();
+        }
+
+        let cached = [1u32, 2, 3, 4, 5];
+        let prompt = [1u32, 2, 3, 9, 9, 9];
+        let keep = common_prefix_len(&cached, &prompt);
+        assert_eq!(keep, 3);
+
+        cache.truncate_to(keep);
+        assert_eq!(cache.pos(), 3);
+
+        let mut keys = [0.0f32; 8];
+        cache.load_keys(1, 4, &mut keys);
+        assert_eq!(&keys[..6], &[0.0, 1.0, 1.0, 1.0, 2.0, 1.0]);
+        assert_eq!(&keys[6..], &[0.0, 0.0]);
+
+        // Truncating past the current position changes nothing.
+        cache.truncate_to(7);
+        assert_eq!(cache.pos(), 3);
+    }
+
+    /// Fill a 1-layer, 8-slot cache with keys `[n, -n]` for tokens `0..count`.
+    fn fill(eviction: &KvCacheEviction, count: usize) -> Result<Fp16KvCache> {
+        let mut cache = Fp16KvCache::new(1, 8, 1, 2);
+        for n in 0..count {
+            cache.check_room()?;
+            let pos = cache.pos();
+            cache.store_key(0, pos, &[n as f32, -(n as f32)]);
+            cache.store_value(0, pos, &[n as f32, 0.0]);
+            cache.advance_with_eviction(eviction)?;
+        }
+        Ok(cache)
+    }
+
+    fn key_tokens(cache: &Fp16KvCache) -> Vec<f32> {
+        let mut keys = vec![0.0f32; cache.pos() * 2];
+        cache.load_keys(0, cache.pos(), &mut keys);
+        keys.iter().step_by(2).copied().collect()
+    }
+
+    #[test]
+    fn test_eviction_error_when_full() {
+        assert!(!fill(&KvCacheEviction::Error, 7).unwrap().is_full());
+        // All 8 slots hold a token; only a 9th is refused.
+        let cache = fill(&KvCacheEviction::Error, 8).unwrap();
+        assert_eq!((cache.pos(), cache.is_full()), (8, true));
+        assert_eq!(key_tokens(&cache), [0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+        assert!(fill(&KvCacheEviction::Error, 9).is_err());
+    }
+
+    #[test]
+    fn test_sliding_window_eviction() {
+        let cache = fill(&KvCacheEviction::SlidingWindow { window_size: 4 }, 20).unwrap();
+        // Full at token 7 (keeps 4..=7), then 12 and 16 (keeps 16..=19).
+        assert_eq!(key_tokens(&cache), [16.0, 17.0, 18.0, 19.0]);
+        let mut values = [0.0f32; 8];
+        cache.load_values(0, 4, &mut values);
+        assert_eq!(values, [16.0, 0.0, 17.0, 0.0, 18.0, 0.0, 19.0, 0.0]);
+
+        // An oversized window still frees a slot.
+        let cache = fill(&KvCacheEviction::SlidingWindow { window_size: 100 }, 50).unwrap();
+        assert_eq!(key_tokens(&cache), [43.0, 44.0, 45.0, 46.0, 47.0, 48.0, 49.0]);
+    }
+
+    #[test]
+    fn test_sink_tokens_eviction() {
+        let eviction = KvCacheEviction::SinkTokens { sink_count: 2, window_size: 3 };
+        let cache = fill(&eviction, 30).unwrap();
+        assert_eq!(key_tokens(&cache)[..2], [0.0, 1.0]);
+        // The window kept at token 25 plus the tokens since.
+        assert_eq!(key_tokens(&cache)[2..], [26.0, 27.0, 28.0, 29.0]);
+    }
+
+    #[test]
+    fn test_eviction_moves_every_layer() {
+        let mut cache = KvCache::new(2, 4, 1, 1);
+        let eviction = KvCacheEviction::SlidingWindow { window_size: 2 };
+        for n in 0..10 {
+            let pos = cache.pos();
+            for layer in 0..2 {
+                cache.key_at_mut(layer, pos)[0] = (layer * 100 + n) as f32;
+                cache.value_at_mut(layer, pos)[0] = n as f32;
+            }
+            cache.advance_with_eviction(&eviction).unwrap();
+        }
+        assert_eq!(cache.pos(), 2);
+        assert_eq!(cache.keys(0, 2), [8.0, 9.0]);
+        assert_eq!(cache.keys(1, 2), [108.0, 109.0]);
+        assert_eq!(cache.values(1, 2), [8.0, 9.0]);
+    }
+
     #[test]
     fn test_fp16_kv_cache_store_load() {
         let mut cache = Fp16KvCache::new(1, 4, 1, 4);
@@ -353,9 +708,9 @@ mod tests {
         cache.pos = 5;
 
         let path = std::env::temp_dir().join("bizclaw_test_kv.bckv");
-        cache.save(&path).unwrap();
+        cache.save(&path, 0xABCD, false).unwrap();
 
-        let loaded = Fp16KvCache::load_from(&path).unwrap();
+        let loaded = Fp16KvCache::load_from(&path, 0xABCD).unwrap();
         assert_eq!(loaded.pos(), 5);
         assert_eq!(loaded.n_layers, 2);
         assert_eq!(loaded.memory_usage(), cache.memory_usage());
@@ -363,6 +718,53 @@ mod tests {
         let _ = std::fs::remove_file(&path);
     }
 
+    #[test]
+    fn test_kv_cache_save_load_compressed() {
+        let mut cache = Fp16KvCache::new(2, 64, 2, 4);
+        cache.store_key(1, 10, &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        cache.pos = 11;
+
+        let plain = std::env::temp_dir().join("bizclaw_test_kv_plain.bckv");
+        let packed = std::env::temp_dir().join("bizclaw_test_kv_packed.bckv");
+        cache.save(&plain, 7, false).unwrap();
+        cache.save(&packed, 7, true).unwrap();
+        assert!(std::fs::metadata(&packed).unwrap().len() < std::fs::metadata(&plain).unwrap().len() / 4);
+
+        let loaded = Fp16KvCache::load_from(&packed, 7).unwrap();
+        assert_eq!(loaded.pos(), 11);
+        assert_eq!(loaded.key_cache, cache.key_cache);
+        assert_eq!(loaded.value_cache, cache.value_cache);
+
+        let _ = std::fs::remove_file(&plain);
+        let _ = std::fs::remove_file(&packed);
+    }
+
+    #[test]
+    fn test_kv_cache_load_rejects_other_model() {
+        let cache = Fp16KvCache::new(1, 4, 1, 4);
+        let path = std::env::temp_dir().join("bizclaw_test_kv_fingerprint.bckv");
+        cache.save(&path, 1, false).unwrap();
+
+        let err = Fp16KvCache::load_from(&path, 2).err().expect("fingerprint mismatch must fail");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("different model"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_kv_cache_load_rejects_old_version() {
+        let path = std::env::temp_dir().join("bizclaw_test_kv_version.bckv");
+        let mut bytes = b"BCKV".to_vec();
+        bytes.push(KV_CACHE_FORMAT_VERSION - 1);
+        std::fs::write(&path, &bytes).unwrap();
+
+        let err = Fp16KvCache::load_from(&path, 0).err().expect("old version must fail");
+        assert!(err.to_string().contains("Unsupported KV cache version"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
     #[test]
     fn test_rope_table_position_0() {
         let table = RopeTable::new(16, 4, 10000.0);
@@ -12,6 +12,7 @@ pub mod quant;
 pub mod simd;
 pub mod tokenizer;
 pub mod sampler;
+pub mod stop;
 pub mod attention;
 pub mod kv_cache;
 pub mod grammar;
@@ -28,10 +29,35 @@ use serde::{Deserialize, Serialize};
 pub struct BrainConfig {
     pub threads: u32,
     pub max_tokens: u32,
+    /// Context window in tokens; 0 uses the model's own.
     pub context_length: u32,
     pub temperature: f32,
+    /// 1.0 disables top-p.
     pub top_p: f32,
+    /// 0 disables top-k.
+    pub top_k: u32,
+    /// 0 disables min-p.
+    pub min_p: f32,
+    /// Mirostat v2 in place of top-k, top-p and min-p.
+    pub mirostat_v2: Option<sampler::MirostatParams>,
+    /// 1.0 disables.
+    pub repetition_penalty: f32,
+    pub frequency_penalty: f32,
+    pub presence_penalty: f32,
+    pub penalty_context_tokens: usize,
+    pub seed: Option<u64>,
+    /// Generation stops at these strings, which are left out of the output.
+    pub stop_sequences: Vec<String>,
     pub json_mode: bool,
+    /// GBNF grammar the output must match (see [`grammar::Grammar`]).
+    pub grammar: Option<String>,
+    /// File to read the grammar from when `grammar` isn't set.
+    pub grammar_file: Option<PathBuf>,
+    /// What to drop once the context window is full.
+    pub kv_eviction: kv_cache::KvCacheEviction,
+    /// Take hyperparameters from the GGUF metadata instead of the
+    /// TinyLlama defaults.
+    pub auto_detect_params: bool,
 }
 
 impl Default for BrainConfig {
@@ -39,14 +65,36 @@ impl Default for BrainConfig {
         Self {
             threads: 4,
             max_tokens: 256,
-            context_length: 2048,
+            context_length: 0,
             temperature: 0.7,
             top_p: 0.9,
+            top_k: 40,
+            min_p: 0.0,
+            mirostat_v2: None,
+            repetition_penalty: 1.1,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            penalty_context_tokens: 64,
+            seed: None,
+            stop_sequences: vec!["</s>".into(), "<|eot_id|>".into()],
             json_mode: false,
+            grammar: None,
+            grammar_file: None,
+            kv_eviction: kv_cache::KvCacheEviction::Error,
+            auto_detect_params: true,
         }
     }
 }
 
+/// Text generated by [`BrainEngine::complete`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion {
+    pub text: String,
+    /// "stop" (EOS or a stop sequence) or "length" (`max_tokens` reached or
+    /// the context full).
+    pub finish_reason: &'static str,
+}
+
 /// The main brain engine for local LLM inference.
 pub struct BrainEngine {
     config: BrainConfig,
@@ -63,11 +111,15 @@ struct LoadedModel {
     /// Weight indices
     weights: forward::TransformerWeights,
     /// BPE tokenizer
-    tokenizer: tokenizer::BpeTokenizer,
+    tokenizer: tokenizer::Tokenizer,
     /// KV cache for generation
     kv_cache: kv_cache::KvCache,
     /// Sampler
     sampler: sampler::Sampler,
+    /// Built from the sampler's stop sequences
+    stops: stop::StopSequences,
+    /// Grammar the output is held to, with the vocabulary it masks
+    grammar: Option<(grammar::Grammar, grammar::Vocabulary)>,
     /// Model file path
     path: PathBuf,
 }
@@ -89,14 +141,13 @@ impl BrainEngine {
     /// Load a GGUF model into the engine.
     pub fn load_model(&mut self, model_path: &Path) -> Result<()> {
         tracing::info!("Loading model from: {}", model_path.display());
+        let grammar = self.load_grammar()?;
 
-        let mmap_model = mmap::MmapModel::load(model_path)?;
-        let params = model::ModelParams::from_gguf(&mmap_model.gguf);
+        let meta = gguf::validate::validate_gguf(model_path)?;
+        tracing::info!("GGUF v{}: arch={}, layers={}, context={}", meta.version, meta.arch, meta.n_layers, meta.context_length);
 
-        tracing::info!(
-            "Model params: dim={}, layers={}, heads={}, kv_heads={}, vocab={}",
-            params.dim, params.n_layers, params.n_heads, params.n_kv_heads, params.vocab_size
-        );
+        let mmap_model = mmap::MmapModel::load(model_path)?;
+        let params = model::ModelParams::resolve(&mmap_model.gguf, &self.config);
 
         // Build weight index
         let weights = forward::TransformerWeights::from_gguf(&mmap_model, &params);
@@ -108,10 +159,10 @@ impl BrainEngine {
         );
 
         // Load tokenizer
-        let tokenizer = tokenizer::BpeTokenizer::from_gguf(&mmap_model.gguf.metadata)
+        let tokenizer = tokenizer::Tokenizer::from_metadata(&mmap_model.gguf.metadata)
             .unwrap_or_else(|e| {
                 tracing::warn!("Failed to load tokenizer: {e}, using fallback");
-                tokenizer::BpeTokenizer::fallback()
+                tokenizer::Tokenizer::fallback()
             });
 
         tracing::info!("Tokenizer loaded: vocab_size={}", tokenizer.vocab_size());
@@ -126,13 +177,21 @@ impl BrainEngine {
         tracing::info!("KV cache: {:.1} MB", kv_cache.memory_usage() as f64 / 1024.0 / 1024.0);
 
         // Create sampler
-        let sampler = sampler::Sampler::new(sampler::SamplerConfig {
+        let sampler = sampler::Sampler::new(sampler::SamplingParams {
+            repetition_penalty: self.config.repetition_penalty,
+            frequency_penalty: self.config.frequency_penalty,
+            presence_penalty: self.config.presence_penalty,
+            penalty_context_tokens: self.config.penalty_context_tokens,
             temperature: self.config.temperature,
-            top_p: self.config.top_p,
-            top_k: 40,
-            repeat_penalty: 1.1,
-            repeat_last_n: 64,
+            top_k: (self.config.top_k > 0).then_some(self.config.top_k),
+            top_p: (self.config.top_p < 1.0).then_some(self.config.top_p),
+            min_p: (self.config.min_p > 0.0).then_some(self.config.min_p),
+            mirostat_v2: self.config.mirostat_v2,
+            seed: self.config.seed,
+            stop_sequences: self.config.stop_sequences.clone(),
         });
+        let stops = stop::StopSequences::new(&sampler.params().stop_sequences);
+        let grammar = grammar.map(|g| (g, tokenizer.grammar_vocabulary()));
 
         self.model = Some(LoadedModel {
             mmap_model,
@@ -141,6 +200,8 @@ impl BrainEngine {
             tokenizer,
             kv_cache,
             sampler,
+            stops,
+            grammar,
             path: model_path.to_path_buf(),
         });
 
@@ -148,6 +209,17 @@ impl BrainEngine {
         Ok(())
     }
 
+    /// The configured grammar, inline or from `grammar_file`.
+    fn load_grammar(&self) -> Result<Option<grammar::Grammar>> {
+        let gbnf = match (&self.config.grammar, &self.config.grammar_file) {
+            (Some(gbnf), _) => gbnf.clone(),
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .map_err(|e| BizClawError::Brain(format!("Can't read grammar {}: {e}", path.display())))?,
+            (None, None) => return Ok(None),
+        };
+        gbnf.parse().map(Some)
+    }
+
     /// Check if a model is loaded.
     pub fn is_loaded(&self) -> bool {
         self.model.is_some()
@@ -155,11 +227,16 @@ impl BrainEngine {
 
     /// Generate text completion using the loaded model.
     pub fn generate(&mut self, prompt: &str, max_tokens: u32) -> Result<String> {
+        self.complete(prompt, max_tokens).map(|completion| completion.text)
+    }
+
+    /// Generate a completion until EOS, a stop sequence or `max_tokens`.
+    pub fn complete(&mut self, prompt: &str, max_tokens: u32) -> Result<Completion> {
         let model = self.model.as_mut()
             .ok_or_else(|| BizClawError::Brain("Model not loaded".into()))?;
 
         // Tokenize prompt
-        let mut input_tokens = vec![model.tokenizer.bos_id];
+        let mut input_tokens = vec![model.tokenizer.bos_id()];
         input_tokens.extend(model.tokenizer.encode(prompt));
 
         let total_len = input_tokens.len();
@@ -168,49 +245,81 @@ impl BrainEngine {
         let mut output_tokens = Vec::new();
         let max_gen = max_tokens.min(self.config.max_tokens) as usize;
         let mut logits = vec![0.0f32; model.params.vocab_size as usize];
+        let mut finish_reason = "length";
+        // Output decoded so far; only kept up to date for stop sequences or a grammar.
+        let mut text = String::new();
+        let keep_text = !model.stops.is_empty() || model.grammar.is_some();
+        let mut stopped_at = None;
+        let mut state = sampler::GenerationState::default();
+        model.kv_cache.reset();
 
         for step in 0..total_len + max_gen {
             // Get the token to process
-            let token = if step < total_len {
-                input_tokens[step]
+            let token = if let Some(&token) = input_tokens.get(step) {
+                token
             } else if let Some(&last) = output_tokens.last() {
                 last
             } else {
                 break;
             };
 
+            // The context is used up: end with what was generated.
+            if model.kv_cache.is_full() {
+                break;
+            }
+
             // Run forward pass
+            let pos = model.kv_cache.pos();
             forward::forward(
                 &model.mmap_model,
                 &model.weights,
                 &model.params,
                 &mut model.kv_cache,
                 token,
-                step,
+                pos,
                 &mut logits,
             )?;
+            model.kv_cache.advance_with_eviction(&self.config.kv_eviction)?;
 
             // Only sample after processing all input tokens
             if step >= total_len - 1 {
-                let all_tokens: Vec<u32> = input_tokens.iter()
-                    .chain(output_tokens.iter())
-                    .copied()
-                    .collect();
-                let next_token = model.sampler.sample(&mut logits, &all_tokens);
+                if let Some((grammar, vocabulary)) = &model.grammar {
+                    let allowed = grammar.allowed_tokens(vocabulary, &text);
+                    if allowed.is_empty() {
+                        finish_reason = "stop";
+                        break;
+                    }
+                    grammar::mask_logits(&mut logits, &allowed);
+                }
+                let next_token = model.sampler.sample(&mut logits, &output_tokens, &mut state);
 
                 // Check for EOS
-                if next_token == model.tokenizer.eos_id {
+                if next_token == model.tokenizer.eos_id() {
+                    finish_reason = "stop";
                     break;
                 }
 
                 output_tokens.push(next_token);
+
+                if keep_text {
+                    let new_from = text.len();
+                    text = model.tokenizer.decode(&output_tokens);
+                    if let Some(at) = model.stops.find(&text, new_from) {
+                        finish_reason = "stop";
+                        stopped_at = Some(at);
+                        break;
+                    }
+                }
             }
         }
 
         // Decode output tokens
-        let output = model.tokenizer.decode(&output_tokens);
-        tracing::debug!("Generated {} tokens", output_tokens.len());
-        Ok(output)
+        let mut output = model.tokenizer.decode(&output_tokens);
+        if let Some(at) = stopped_at {
+            output.truncate(at);
+        }
+        tracing::debug!("Generated {} tokens ({finish_reason})", output_tokens.len());
+        Ok(Completion { text: output, finish_reason })
     }
 
     /// Generate with JSON grammar constraint.
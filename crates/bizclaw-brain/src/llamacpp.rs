@@ -38,6 +38,12 @@ pub struct LlamaCppBackend {
     loaded: bool,
 }
 
+impl Default for LlamaCppBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl LlamaCppBackend {
     /// Create a new llama.cpp backend instance.
     pub fn new() -> Self {
@@ -123,9 +129,11 @@ impl LlamaCppBackend {
         // FFI call placeholder — actual implementation requires linking to libllama
         // For now, document the expected FFI interface
         tracing::info!(
-            "llama.cpp generate: prompt_len={}, max_tokens={}",
+            "llama.cpp generate: prompt_len={}, max_tokens={}, temperature={}, top_p={}",
             prompt.len(),
-            max_tokens
+            max_tokens,
+            self.temperature,
+            self.top_p
         );
 
         // Design note: Actual FFI calls require linking libllama at compile time.
@@ -202,16 +210,15 @@ impl SmartBrainEngine {
     /// Load a model — tries llama.cpp first, falls back to pure Rust.
     pub fn load_model(&mut self, model_path: &Path) -> Result<()> {
         // Try llama.cpp first
-        if self.prefer_llamacpp {
-            if let Some(ref mut backend) = self.llamacpp {
-                match backend.load_model(model_path) {
-                    Ok(()) => {
-                        tracing::info!("✅ Using llama.cpp backend (3-5x faster)");
-                        return Ok(());
-                    }
-                    Err(e) => {
-                        tracing::warn!("llama.cpp not available: {e}, falling back to pure Rust");
-                    }
+        if self.prefer_llamacpp
+            && let Some(ref mut backend) = self.llamacpp {
+            match backend.load_model(model_path) {
+                Ok(()) => {
+                    tracing::info!("✅ Using llama.cpp backend (3-5x faster)");
+                    return Ok(());
+                }
+                Err(e) => {
+                    tracing::warn!("llama.cpp not available: {e}, falling back to pure Rust");
                 }
             }
         }
@@ -225,10 +232,9 @@ impl SmartBrainEngine {
     /// Generate text — automatically selects the loaded backend.
     pub fn generate(&mut self, prompt: &str, max_tokens: u32) -> Result<String> {
         // Try llama.cpp first
-        if let Some(ref backend) = self.llamacpp {
-            if backend.is_loaded() {
-                return backend.generate(prompt, max_tokens);
-            }
+        if let Some(ref backend) = self.llamacpp
+            && backend.is_loaded() {
+            return backend.generate(prompt, max_tokens);
         }
 
         // Fallback to pure Rust
@@ -237,10 +243,9 @@ impl SmartBrainEngine {
 
     /// Get info about which backend is active.
     pub fn backend_info(&self) -> String {
-        if let Some(ref backend) = self.llamacpp {
-            if backend.is_loaded() {
-                return format!("🚀 {}", backend.info());
-            }
+        if let Some(ref backend) = self.llamacpp
+            && backend.is_loaded() {
+            return format!("🚀 {}", backend.info());
         }
         format!(
             "🧠 Pure Rust BrainEngine ({})",
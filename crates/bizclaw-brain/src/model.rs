@@ -38,6 +38,37 @@ impl Default for ModelParams {
 }
 
 impl ModelParams {
+    /// Stable fingerprint of the architecture params (FNV-1a over the fields).
+    ///
+    /// Used to tag persisted state such as KV cache files, so a file built for
+    /// one model is never fed to another that happens to share dimensions.
+    pub fn fingerprint(&self) -> u64 {
+        const FNV_OFFSET: u64 = 0xcbf29ce484222325;
+        const FNV_PRIME: u64 = 0x100000001b3;
+
+        let fields = [
+            self.vocab_size,
+            self.dim,
+            self.hidden_dim,
+            self.n_layers,
+            self.n_heads,
+            self.n_kv_heads,
+            self.head_dim,
+            self.max_seq_len,
+            self.rope_theta.to_bits(),
+            self.rms_norm_eps.to_bits(),
+        ];
+
+        let mut hash = FNV_OFFSET;
+        for field in fields {
+            for byte in field.to_le_bytes() {
+                hash ^= byte as u64;
+                hash = hash.wrapping_mul(FNV_PRIME);
+            }
+        }
+        hash
+    }
+
     /// Extract model parameters from GGUF metadata.
     pub fn from_gguf(gguf: &crate::gguf::GgufFile) -> Self {
         let arch = gguf.architecture().unwrap_or("llama");
@@ -64,9 +95,111 @@ impl ModelParams {
             n_heads,
             n_kv_heads,
             head_dim: dim / n_heads,
-            max_seq_len: gguf.get_u32(&format!("{prefix}context_length")).unwrap_or(2048),
+            max_seq_len: gguf.get_u32(&format!("{prefix}context_length"))
+                .or_else(|| gguf.get_u32("general.context_length"))
+                .unwrap_or(2048),
             rope_theta: gguf.get_f32(&format!("{prefix}rope.freq_base")).unwrap_or(10000.0),
             rms_norm_eps: gguf.get_f32(&format!("{prefix}attention.layer_norm_rms_epsilon")).unwrap_or(1e-5),
         }
     }
+
+    /// Parameters to run a model with: read from its GGUF metadata when
+    /// `config.auto_detect_params` is on (TinyLlama defaults otherwise), with
+    /// a non-zero `config.context_length` taking precedence.
+    pub fn resolve(gguf: &crate::gguf::GgufFile, config: &crate::BrainConfig) -> Self {
+        let mut params = if config.auto_detect_params {
+            let params = Self::from_gguf(gguf);
+            tracing::info!(
+                "Detected model params: context={}, layers={}, heads={}, kv_heads={}, dim={}, rope_theta={}, vocab={}",
+                params.max_seq_len, params.n_layers, params.n_heads, params.n_kv_heads,
+                params.dim, params.rope_theta, params.vocab_size
+            );
+            params
+        } else {
+            Self::default()
+        };
+
+        if config.context_length > 0 && config.context_length != params.max_seq_len {
+            if config.auto_detect_params {
+                tracing::warn!(
+                    "brain.context_length = {} overrides the model's context length {}",
+                    config.context_length, params.max_seq_len
+                );
+            }
+            params.max_seq_len = config.context_length;
+        }
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gguf(kvs: &[(&str, crate::gguf::GgufValue)]) -> crate::gguf::GgufFile {
+        crate::gguf::GgufFile {
+            version: 3,
+            metadata: kvs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect(),
+            tensors: Vec::new(),
+            data_offset: 0,
+            alignment: 32,
+        }
+    }
+
+    fn llama3() -> crate::gguf::GgufFile {
+        use crate::gguf::GgufValue::{F32, String, U32};
+        gguf(&[
+            ("general.architecture", String("llama".into())),
+            ("llama.context_length", U32(8192)),
+            ("llama.block_count", U32(32)),
+            ("llama.attention.head_count", U32(32)),
+            ("llama.attention.head_count_kv", U32(8)),
+            ("llama.embedding_length", U32(4096)),
+            ("llama.rope.freq_base", F32(500000.0)),
+            ("llama.vocab_size", U32(128256)),
+        ])
+    }
+
+    #[test]
+    fn test_resolve_detects_params() {
+        let params = ModelParams::resolve(&llama3(), &crate::BrainConfig::default());
+        assert_eq!(params.max_seq_len, 8192);
+        assert_eq!((params.n_layers, params.n_heads, params.n_kv_heads), (32, 32, 8));
+        assert_eq!((params.dim, params.head_dim, params.vocab_size), (4096, 128, 128256));
+        assert_eq!(params.rope_theta, 500000.0);
+    }
+
+    #[test]
+    fn test_resolve_explicit_context_wins() {
+        let config = crate::BrainConfig { context_length: 2048, ..Default::default() };
+        let params = ModelParams::resolve(&llama3(), &config);
+        assert_eq!((params.max_seq_len, params.n_layers), (2048, 32));
+
+        let config = crate::BrainConfig { auto_detect_params: false, ..Default::default() };
+        let params = ModelParams::resolve(&llama3(), &config);
+        assert_eq!((params.max_seq_len, params.n_layers), (2048, 22));
+    }
+
+    #[test]
+    fn test_general_context_length_fallback() {
+        use crate::gguf::GgufValue::{String, U32};
+        let file = gguf(&[
+            ("general.architecture", String("llama".into())),
+            ("general.context_length", U32(4096)),
+        ]);
+        assert_eq!(ModelParams::from_gguf(&file).max_seq_len, 4096);
+    }
+
+    #[test]
+    fn test_fingerprint_stable() {
+        assert_eq!(ModelParams::default().fingerprint(), ModelParams::default().fingerprint());
+    }
+
+    #[test]
+    fn test_fingerprint_differs_with_same_dims() {
+        let a = ModelParams::default();
+        let b = ModelParams { vocab_size: 32001, rope_theta: 500000.0, ..ModelParams::default() };
+        assert_eq!(a.dim, b.dim);
+        assert_ne!(a.fingerprint(), b.fingerprint());
+    }
 }
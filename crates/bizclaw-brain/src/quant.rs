@@ -45,10 +45,10 @@ pub fn dequantize_row(
     match ggml_type {
         crate::gguf::GgmlType::F32 => {
             // Direct copy from bytes to f32
-            for i in 0..n_elements {
+            for (i, out) in output.iter_mut().enumerate().take(n_elements) {
                 let offset = i * 4;
                 if offset + 4 <= data.len() {
-                    output[i] = f32::from_le_bytes([
+                    *out = f32::from_le_bytes([
                         data[offset], data[offset + 1],
                         data[offset + 2], data[offset + 3],
                     ]);
@@ -56,10 +56,10 @@ pub fn dequantize_row(
             }
         }
         crate::gguf::GgmlType::F16 => {
-            for i in 0..n_elements {
+            for (i, out) in output.iter_mut().enumerate().take(n_elements) {
                 let offset = i * 2;
                 if offset + 2 <= data.len() {
-                    output[i] = half::f16::from_le_bytes([data[offset], data[offset + 1]]).to_f32();
+                    *out = half::f16::from_le_bytes([data[offset], data[offset + 1]]).to_f32();
                 }
             }
         }
@@ -1,127 +1,260 @@
-//! Temperature + Top-p/Top-k sampling for token generation.
+//! Repetition penalties, then temperature, top-k, top-p and min-p (or
+//! Mirostat v2) sampling for token generation.
 
-use rand::Rng;
+use std::collections::HashMap;
 
-/// Sampler configuration.
-#[derive(Debug, Clone)]
-pub struct SamplerConfig {
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// How the next token is picked from the logits.
+///
+/// Penalties for recently generated tokens apply first, then the filters in
+/// order: temperature → top-k → top-p → min-p; the token is then drawn from
+/// what is left. With `mirostat_v2` set, Mirostat replaces top-k, top-p and
+/// min-p. A temperature of 0 is greedy decoding (still penalized).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SamplingParams {
+    /// Divide positive logits (multiply negative ones) of recent tokens by
+    /// this, as llama.cpp does; 1.0 disables.
+    pub repetition_penalty: f32,
+    /// Subtract this times a recent token's count from its logit (OpenAI's
+    /// `frequency_penalty`).
+    pub frequency_penalty: f32,
+    /// Subtract this once from the logit of every recent token (OpenAI's
+    /// `presence_penalty`).
+    pub presence_penalty: f32,
+    /// How many of the latest generated tokens the penalties look at.
+    pub penalty_context_tokens: usize,
     pub temperature: f32,
-    pub top_p: f32,
-    pub top_k: u32,
-    pub repeat_penalty: f32,
-    pub repeat_last_n: usize,
+    /// Keep only the `k` most likely tokens.
+    pub top_k: Option<u32>,
+    /// Keep the most likely tokens until their probabilities add up to `p`.
+    pub top_p: Option<f32>,
+    /// Drop tokens less likely than `min_p` times the most likely one.
+    pub min_p: Option<f32>,
+    /// Keep the surprise of the output near a target instead of cutting off
+    /// at a fixed k or p; see [`MirostatParams`].
+    pub mirostat_v2: Option<MirostatParams>,
+    /// Seed for reproducible output; random when `None`.
+    pub seed: Option<u64>,
+    /// Generation ends at any of these; see [`crate::stop::StopSequences`].
+    pub stop_sequences: Vec<String>,
 }
 
-impl Default for SamplerConfig {
+impl Default for SamplingParams {
     fn default() -> Self {
         Self {
+            repetition_penalty: 1.0,
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            penalty_context_tokens: 64,
             temperature: 0.7,
-            top_p: 0.9,
-            top_k: 40,
-            repeat_penalty: 1.1,
-            repeat_last_n: 64,
+            top_k: Some(40),
+            top_p: Some(0.9),
+            min_p: None,
+            mirostat_v2: None,
+            seed: None,
+            stop_sequences: Vec::new(),
         }
     }
 }
 
-/// Token sampler — selects next token from logits.
-pub struct Sampler {
-    config: SamplerConfig,
+/// Mirostat v2 (Basu et al., 2021): each step drops the tokens whose
+/// surprise (`-log2 p`) is above μ, samples from the rest, then moves μ by
+/// `eta` times how far the sampled token's surprise was from `tau`.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct MirostatParams {
+    /// Target surprise in bits; higher gives more varied text. Default 5.0.
+    pub tau: f32,
+    /// How fast μ follows the observed surprise. Default 0.1.
+    pub eta: f32,
 }
 
-impl Sampler {
-    pub fn new(config: SamplerConfig) -> Self {
-        Self { config }
-    }
-
-    /// Sample a token from logits.
-    pub fn sample(&self, logits: &mut [f32], last_tokens: &[u32]) -> u32 {
-        // Apply repeat penalty
-        if self.config.repeat_penalty != 1.0 {
-            let n = last_tokens.len().min(self.config.repeat_last_n);
-            for &token_id in &last_tokens[last_tokens.len().saturating_sub(n)..] {
-                let idx = token_id as usize;
-                if idx < logits.len() {
-                    if logits[idx] > 0.0 {
-                        logits[idx] /= self.config.repeat_penalty;
-                    } else {
-                        logits[idx] *= self.config.repeat_penalty;
-                    }
-                }
-            }
-        }
+impl Default for MirostatParams {
+    fn default() -> Self {
+        Self { tau: 5.0, eta: 0.1 }
+    }
+}
 
-        // Apply temperature
-        if self.config.temperature > 0.0 && self.config.temperature != 1.0 {
-            let inv_temp = 1.0 / self.config.temperature;
-            for logit in logits.iter_mut() {
-                *logit *= inv_temp;
-            }
-        }
+/// Sampling state carried from one token to the next in a generation;
+/// start each generation with a fresh one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GenerationState {
+    /// Mirostat's surprise cutoff μ, set to `2 * tau` at the first token.
+    pub mirostat_mu: Option<f32>,
+}
 
-        // If temperature is 0, return argmax (greedy)
-        if self.config.temperature <= 0.0 {
-            return argmax(logits);
+impl SamplingParams {
+    fn rng(&self) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
         }
+    }
+}
 
-        // Create sorted indices
-        let mut indices: Vec<(usize, f32)> = logits.iter()
-            .enumerate()
-            .map(|(i, &v)| (i, v))
-            .collect();
-        indices.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+/// Sample a token from `logits` after the tokens generated so far
+/// (`recent`, oldest first), with a generator seeded from `params.seed`.
+///
+/// The logits are left penalized and temperature-scaled, with filtered-out
+/// tokens at `-inf`.
+pub fn sample(logits: &mut [f32], params: &SamplingParams, recent: &[u32]) -> u32 {
+    sample_with(logits, params, recent, &mut GenerationState::default(), &mut params.rng())
+}
 
-        // Top-K filtering
-        let top_k = if self.config.top_k > 0 {
-            (self.config.top_k as usize).min(indices.len())
-        } else {
-            indices.len()
-        };
-        indices.truncate(top_k);
-
-        // Softmax
-        let max_logit = indices[0].1;
-        let mut probs: Vec<(usize, f32)> = indices.iter()
-            .map(|&(i, v)| (i, (v - max_logit).exp()))
-            .collect();
-        let sum: f32 = probs.iter().map(|&(_, p)| p).sum();
-        for p in probs.iter_mut() {
-            p.1 /= sum;
+/// [`sample`] drawing from `rng` and updating `state`, for callers sampling
+/// many tokens from one seeded sequence.
+pub fn sample_with(
+    logits: &mut [f32],
+    params: &SamplingParams,
+    recent: &[u32],
+    state: &mut GenerationState,
+    rng: &mut impl Rng,
+) -> u32 {
+    apply_penalties(logits, params, recent);
+
+    if params.temperature <= 0.0 || logits.len() <= 1 {
+        return argmax(logits);
+    }
+
+    if params.temperature != 1.0 {
+        let inv_temp = 1.0 / params.temperature;
+        for logit in logits.iter_mut() {
+            *logit *= inv_temp;
         }
+    }
+
+    // Candidates, most likely first.
+    let mut candidates: Vec<(usize, f32)> = logits.iter().copied().enumerate().collect();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    // Top-K
+    if let Some(k) = params.top_k.filter(|_| params.mirostat_v2.is_none()) {
+        candidates.truncate((k as usize).max(1));
+    }
+
+    // Softmax
+    let max_logit = candidates[0].1;
+    let mut probs: Vec<(usize, f32)> = candidates.iter()
+        .map(|&(i, v)| (i, (v - max_logit).exp()))
+        .collect();
+    normalize(&mut probs);
 
-        // Top-P (nucleus) sampling
-        if self.config.top_p < 1.0 {
-            let mut cumulative = 0.0;
-            let mut cutoff = probs.len();
-            for (i, &(_, p)) in probs.iter().enumerate() {
+    // Mirostat v2: keep the tokens no more surprising than μ (at least one).
+    if let Some(mirostat) = &params.mirostat_v2 {
+        let mu = *state.mirostat_mu.get_or_insert(2.0 * mirostat.tau);
+        let keep = probs.iter().take_while(|&&(_, p)| -p.log2() <= mu).count();
+        probs.truncate(keep.max(1));
+        normalize(&mut probs);
+    }
+
+    // Top-P (nucleus): the shortest prefix whose probability exceeds p.
+    if let Some(top_p) = params.top_p.filter(|&p| p < 1.0 && params.mirostat_v2.is_none()) {
+        let mut cumulative = 0.0;
+        let cutoff = probs.iter()
+            .position(|&(_, p)| {
                 cumulative += p;
-                if cumulative > self.config.top_p {
-                    cutoff = i + 1;
-                    break;
-                }
-            }
-            probs.truncate(cutoff);
+                cumulative > top_p
+            })
+            .map_or(probs.len(), |i| i + 1);
+        probs.truncate(cutoff);
+        normalize(&mut probs);
+    }
 
-            // Re-normalize
-            let sum: f32 = probs.iter().map(|&(_, p)| p).sum();
-            for p in probs.iter_mut() {
-                p.1 /= sum;
-            }
+    // Min-P
+    if let Some(min_p) = params.min_p.filter(|&p| p > 0.0 && params.mirostat_v2.is_none()) {
+        let threshold = min_p * probs[0].1;
+        probs.retain(|&(_, p)| p >= threshold);
+        normalize(&mut probs);
+    }
+
+    let mut kept = vec![false; logits.len()];
+    for &(idx, _) in &probs {
+        kept[idx] = true;
+    }
+    for (logit, kept) in logits.iter_mut().zip(kept) {
+        if !kept {
+            *logit = f32::NEG_INFINITY;
         }
+    }
 
-        // Random sampling
-        let mut rng = rand::thread_rng();
-        let r: f32 = rng.r#gen();
-        let mut cumulative = 0.0;
-        for &(idx, prob) in &probs {
-            cumulative += prob;
-            if r < cumulative {
-                return idx as u32;
-            }
+    let (token, prob) = draw(&probs, rng);
+
+    // Move μ toward the target by the error in the sampled token's surprise.
+    if let (Some(mirostat), Some(mu)) = (&params.mirostat_v2, state.mirostat_mu.as_mut()) {
+        *mu -= mirostat.eta * (-prob.log2() - mirostat.tau);
+    }
+    token as u32
+}
+
+/// Draw a token and its probability from `probs`, which add up to 1.
+fn draw(probs: &[(usize, f32)], rng: &mut impl Rng) -> (usize, f32) {
+    let r: f32 = rng.r#gen();
+    let mut cumulative = 0.0;
+    for &(idx, prob) in probs {
+        cumulative += prob;
+        if r < cumulative {
+            return (idx, prob);
+        }
+    }
+
+    // Rounding left the sum just under r.
+    probs.last().copied().unwrap_or((0, 1.0))
+}
+
+/// Penalize the tokens among the last `penalty_context_tokens` of `recent`.
+fn apply_penalties(logits: &mut [f32], params: &SamplingParams, recent: &[u32]) {
+    let disabled = params.repetition_penalty == 1.0
+        && params.frequency_penalty == 0.0
+        && params.presence_penalty == 0.0;
+    if disabled {
+        return;
+    }
+    let window = &recent[recent.len().saturating_sub(params.penalty_context_tokens)..];
+    let mut counts: HashMap<u32, u32> = HashMap::new();
+    for &token in window {
+        *counts.entry(token).or_default() += 1;
+    }
+    for (token, count) in counts {
+        let Some(logit) = logits.get_mut(token as usize) else { continue };
+        if *logit > 0.0 {
+            *logit /= params.repetition_penalty;
+        } else {
+            *logit *= params.repetition_penalty;
         }
+        *logit -= count as f32 * params.frequency_penalty + params.presence_penalty;
+    }
+}
 
-        // Fallback
-        probs.last().map(|&(idx, _)| idx as u32).unwrap_or(0)
+fn normalize(probs: &mut [(usize, f32)]) {
+    let sum: f32 = probs.iter().map(|&(_, p)| p).sum();
+    for p in probs.iter_mut() {
+        p.1 /= sum;
+    }
+}
+
+/// Token sampler — selects next token from logits.
+pub struct Sampler {
+    params: SamplingParams,
+    /// One generator per sampler, so a seeded model gives the same text for
+    /// the same prompt.
+    rng: StdRng,
+}
+
+impl Sampler {
+    pub fn new(params: SamplingParams) -> Self {
+        let rng = params.rng();
+        Self { params, rng }
+    }
+
+    pub fn params(&self) -> &SamplingParams {
+        &self.params
+    }
+
+    /// Sample a token from logits, given the tokens generated so far and
+    /// the state of this generation.
+    pub fn sample(&mut self, logits: &mut [f32], generated: &[u32], state: &mut GenerationState) -> u32 {
+        sample_with(logits, &self.params, generated, state, &mut self.rng)
     }
 }
 
@@ -133,3 +266,162 @@ fn argmax(values: &[f32]) -> u32 {
         .map(|(i, _)| i as u32)
         .unwrap_or(0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn params(temperature: f32) -> SamplingParams {
+        SamplingParams { temperature, top_k: None, top_p: None, min_p: None, seed: Some(7), ..Default::default() }
+    }
+
+    /// Probabilities of `logits` at temperature 1.
+    fn softmax(logits: &[f32]) -> Vec<f32> {
+        let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+        let exps: Vec<f32> = logits.iter().map(|&l| (l - max).exp()).collect();
+        let sum: f32 = exps.iter().sum();
+        exps.iter().map(|e| e / sum).collect()
+    }
+
+    #[test]
+    fn test_filters_mark_dropped_logits() {
+        let mut logits = vec![1.0, 4.0, 3.0, 2.0];
+        let token = sample(&mut logits, &SamplingParams { top_k: Some(2), ..params(1.0) }, &[]);
+        assert!(token == 1 || token == 2);
+        assert_eq!(logits, [f32::NEG_INFINITY, 4.0, 3.0, f32::NEG_INFINITY]);
+
+        // exp(0) vs exp(-5): the second token is under 10% of the first.
+        let mut logits = vec![5.0, 0.0];
+        assert_eq!(sample(&mut logits, &SamplingParams { min_p: Some(0.1), ..params(1.0) }, &[]), 0);
+        assert_eq!(logits[1], f32::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_seeded_sampler_is_reproducible() {
+        let (mut a, mut b) = (Sampler::new(params(1.0)), Sampler::new(params(1.0)));
+        let draws = |s: &mut Sampler| (0..20).map(|_| s.sample(&mut [0.0; 50], &[], &mut GenerationState::default())).collect::<Vec<_>>();
+        let first = draws(&mut a);
+        assert_eq!(first, draws(&mut b));
+        assert!(first.iter().any(|&t| t != first[0]), "one generator should advance between tokens");
+    }
+
+    #[test]
+    fn test_repetition_penalty_breaks_loop() {
+        // A bigram "model" stuck on A B A B ...: after A, B is the most
+        // likely token and after B, A is; C and D are close runners-up.
+        const A: u32 = 0;
+        const B: u32 = 1;
+        let next_logits = |last: u32| -> Vec<f32> {
+            match last {
+                A => vec![0.0, 2.0, 1.8, 0.0],
+                _ => vec![2.0, 0.0, 0.0, 1.7],
+            }
+        };
+        let generate = |params: &SamplingParams| {
+            let mut sampler = Sampler::new(params.clone());
+            let mut generated = vec![A];
+            for _ in 0..8 {
+                let mut logits = next_logits(*generated.last().unwrap());
+                let token = sampler.sample(&mut logits, &generated, &mut GenerationState::default());
+                generated.push(token);
+            }
+            generated
+        };
+
+        let looping = generate(&params(0.0));
+        assert_eq!(looping, [A, B, A, B, A, B, A, B, A]);
+        let penalized = generate(&SamplingParams { repetition_penalty: 1.3, ..params(0.0) });
+        assert!(penalized.iter().any(|&t| t != A && t != B), "{penalized:?}");
+        assert_eq!(&penalized[..3], [A, B, 3], "A's logit 2.0 / 1.3 falls below D's 1.7");
+    }
+
+    #[test]
+    fn test_frequency_and_presence_penalties() {
+        let mut logits = vec![1.0, 1.0, 1.0, -1.0];
+        let penalties = SamplingParams {
+            repetition_penalty: 2.0,
+            frequency_penalty: 0.5,
+            presence_penalty: 0.25,
+            penalty_context_tokens: 4,
+            ..params(0.0)
+        };
+        // Token 0 fell out of the window; token 1 is in it twice.
+        apply_penalties(&mut logits, &penalties, &[0, 1, 2, 1, 3]);
+        assert_eq!(logits, [1.0, 0.5 - 1.0 - 0.25, 0.5 - 0.5 - 0.25, -2.0 - 0.5 - 0.25]);
+    }
+
+    #[test]
+    fn test_mirostat_cuts_off_above_mu() {
+        let params = SamplingParams { mirostat_v2: Some(MirostatParams { tau: 0.1, eta: 0.1 }), ..params(1.0) };
+        // μ starts at 0.2 bits, so only a token with p >= 0.87 survives: the top one.
+        let mut logits = vec![1.0, 3.0, 2.9, 0.5];
+        let mut state = GenerationState::default();
+        assert_eq!(sample_with(&mut logits, &params, &[], &mut state, &mut params.rng()), 1);
+        assert_eq!(logits.iter().filter(|l| l.is_finite()).count(), 1);
+        // Its surprise after the cutoff is 0, 0.1 under tau, so μ rises.
+        assert!((state.mirostat_mu.unwrap() - 0.21).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_mirostat_golden_output() {
+        // A flat Zipf-like distribution over 256 tokens, most of them more
+        // surprising than tau, so the cutoff matters.
+        let logits: Vec<f32> = (0..256).map(|i| -0.8 * (i as f32 + 1.0).ln()).collect();
+        let params = SamplingParams { mirostat_v2: Some(MirostatParams::default()), ..params(1.0) };
+        let mut sampler = Sampler::new(params);
+        let mut state = GenerationState::default();
+        let tokens: Vec<u32> = (0..16).map(|_| sampler.sample(&mut logits.clone(), &[], &mut state)).collect();
+        assert_eq!(tokens, [17, 0, 1, 8, 0, 1, 61, 35, 45, 4, 61, 109, 20, 1, 37, 2]);
+        assert!((state.mirostat_mu.unwrap() - 8.5187).abs() < 1e-3, "{state:?}");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_token_in_range_and_kept(logits in prop::collection::vec(-20.0f32..20.0, 1..64), seed: u64) {
+            let mut scaled = logits.clone();
+            let params = SamplingParams { seed: Some(seed), ..SamplingParams::default() };
+            let token = sample(&mut scaled, &params, &[]) as usize;
+            prop_assert!(token < logits.len());
+            prop_assert!(scaled[token].is_finite());
+        }
+
+        #[test]
+        fn prop_zero_temperature_is_greedy(logits in prop::collection::vec(-20.0f32..20.0, 1..64)) {
+            let token = sample(&mut logits.clone(), &params(0.0), &[]) as usize;
+            prop_assert!(logits.iter().all(|&l| l <= logits[token]));
+        }
+
+        #[test]
+        fn prop_same_seed_same_token(logits in prop::collection::vec(-5.0f32..5.0, 2..64), seed: u64) {
+            let params = SamplingParams { seed: Some(seed), min_p: Some(0.05), ..SamplingParams::default() };
+            prop_assert_eq!(sample(&mut logits.clone(), &params, &[1, 0]), sample(&mut logits.clone(), &params, &[1, 0]));
+        }
+
+        #[test]
+        fn prop_top_k_picks_a_top_token(logits in prop::collection::vec(-20.0f32..20.0, 1..64), k in 1u32..8, seed: u64) {
+            let token = sample(&mut logits.clone(), &SamplingParams { top_k: Some(k), seed: Some(seed), ..params(1.0) }, &[]) as usize;
+            let mut sorted = logits.clone();
+            sorted.sort_by(|a, b| b.total_cmp(a));
+            let kth = sorted[(k as usize).min(sorted.len()) - 1];
+            prop_assert!(logits[token] >= kth);
+        }
+
+        #[test]
+        fn prop_top_p_picks_from_nucleus(logits in prop::collection::vec(-10.0f32..10.0, 1..64), p in 0.05f32..0.99, seed: u64) {
+            let token = sample(&mut logits.clone(), &SamplingParams { top_p: Some(p), seed: Some(seed), ..params(1.0) }, &[]) as usize;
+            // Everything strictly more likely than the pick adds up to at most p.
+            let probs = softmax(&logits);
+            let above: f32 = probs.iter().filter(|&&q| q > probs[token]).sum();
+            prop_assert!(above <= p + 1e-4, "above={above} p={p}");
+        }
+
+        #[test]
+        fn prop_min_p_drops_unlikely_tokens(logits in prop::collection::vec(-10.0f32..10.0, 1..64), min_p in 0.01f32..1.0, seed: u64) {
+            let token = sample(&mut logits.clone(), &SamplingParams { min_p: Some(min_p), seed: Some(seed), ..params(1.0) }, &[]) as usize;
+            let probs = softmax(&logits);
+            let max = probs.iter().copied().fold(0.0, f32::max);
+            prop_assert!(probs[token] >= min_p * max * (1.0 - 1e-4));
+        }
+    }
+}
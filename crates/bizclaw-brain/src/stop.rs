@@ -0,0 +1,90 @@
+//! Stop sequences — strings that end generation when the model writes them.
+
+use aho_corasick::AhoCorasick;
+
+/// Finds the first stop sequence in generated text.
+#[derive(Debug, Clone, Default)]
+pub struct StopSequences {
+    /// `None` when there are no (non-empty) stop sequences.
+    automaton: Option<AhoCorasick>,
+    /// Bytes in the longest stop sequence.
+    longest: usize,
+}
+
+impl StopSequences {
+    pub fn new(sequences: &[String]) -> Self {
+        let sequences: Vec<&str> = sequences.iter().map(String::as_str).filter(|s| !s.is_empty()).collect();
+        let automaton = if sequences.is_empty() {
+            None
+        } else {
+            // Plain strings always build; a failure would only mean no stops.
+            AhoCorasick::new(&sequences).ok()
+        };
+        let longest = sequences.iter().map(|s| s.len()).max().unwrap_or(0);
+        Self { automaton, longest }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.automaton.is_none()
+    }
+
+    /// Where the first stop sequence in `text` starts, if one ends at or
+    /// after byte `new_from` (where the latest token began). Text before
+    /// that was already checked, so only its last bytes are searched again,
+    /// for a sequence split across tokens.
+    pub fn find(&self, text: &str, new_from: usize) -> Option<usize> {
+        let automaton = self.automaton.as_ref()?;
+        let mut from = new_from.min(text.len()).saturating_sub(self.longest.saturating_sub(1));
+        while !text.is_char_boundary(from) {
+            from -= 1;
+        }
+        automaton.find(&text[from..]).map(|m| from + m.start())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decode `tokens` one at a time like the generation loop; the output
+    /// (cut at the stop sequence) and whether one was found.
+    fn generate(stops: &StopSequences, tokens: &[&str]) -> (String, bool) {
+        let mut text = String::new();
+        for token in tokens {
+            let new_from = text.len();
+            text.push_str(token);
+            if let Some(at) = stops.find(&text, new_from) {
+                text.truncate(at);
+                return (text, true);
+            }
+        }
+        (text, false)
+    }
+
+    #[test]
+    fn test_multi_token_stop_sequences() {
+        let stops = StopSequences::new(&["\nHuman:".into(), "</s>".into(), "<|eot_id|>".into()]);
+        assert_eq!(generate(&stops, &["Chào", " bạn", "!", "\n", "Hu", "man", ":", " hỏi"]), ("Chào bạn!".into(), true));
+        assert_eq!(generate(&stops, &["Xong", "</", "s", ">", "thêm"]), ("Xong".into(), true));
+        assert_eq!(generate(&stops, &["OK", "<|eot_id|>"]), ("OK".into(), true));
+        // A stop sequence inside one token, with text after it.
+        assert_eq!(generate(&stops, &["Hết.</s><s>[INST]"]), ("Hết.".into(), true));
+        // Almost a stop sequence.
+        assert_eq!(generate(&stops, &["\n", "Human", " resources"]), ("\nHuman resources".into(), false));
+    }
+
+    #[test]
+    fn test_no_stop_sequences() {
+        let stops = StopSequences::new(&[String::new()]);
+        assert!(stops.is_empty());
+        assert_eq!(stops.find("anything </s>", 0), None);
+    }
+
+    #[test]
+    fn test_search_start_on_char_boundary() {
+        let stops = StopSequences::new(&["###".into()]);
+        let text = "Giá ưu đãi ###";
+        assert_eq!(stops.find(text, text.len() - 1), Some(text.len() - 3));
+        assert_eq!(stops.find("ưu", 3), None);
+    }
+}
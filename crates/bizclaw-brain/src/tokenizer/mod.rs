@@ -0,0 +1,658 @@
+//! BPE (Byte Pair Encoding) tokenizer for LLaMA-family models.
+//!
+//! Reads vocabulary and merge rules from GGUF metadata and converts
+//! text to/from token IDs. SentencePiece vocabularies (`llama`) merge by
+//! token score with `<0xXX>` byte fallback; byte-level vocabularies
+//! (`gpt2`) merge by rank from `tokenizer.ggml.merges`.
+//!
+//! [`Tokenizer::from_gguf`] reads only the file's metadata, so counting
+//! tokens doesn't need the model weights in memory.
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::path::Path;
+use bizclaw_core::error::{BizClawError, Result};
+use crate::gguf::{GgufFile, GgufValue};
+
+/// SentencePiece's stand-in for a space.
+const SPM_SPACE: char = '▁';
+
+/// Token strings, scores and special token IDs.
+pub struct Vocabulary {
+    /// Token ID → string mapping.
+    tokens: Vec<String>,
+    /// String → Token ID mapping.
+    token_to_id: HashMap<String, u32>,
+    /// Token scores (used for BPE merge priority).
+    scores: Vec<f32>,
+    /// The token each byte falls back to: `<0xXX>` for SentencePiece,
+    /// the byte's stand-in character for byte-level vocabularies.
+    byte_ids: [Option<u32>; 256],
+    /// Special token IDs.
+    pub bos_id: u32,
+    pub eos_id: u32,
+    pub pad_id: u32,
+    pub unk_id: u32,
+}
+
+impl Vocabulary {
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// The token's text, as stored in the vocabulary.
+    pub fn token(&self, id: u32) -> Option<&str> {
+        self.tokens.get(id as usize).map(|s| s.as_str())
+    }
+
+    /// The ID of a token's exact text.
+    pub fn id(&self, token: &str) -> Option<u32> {
+        self.token_to_id.get(token).copied()
+    }
+
+    /// Merge priority of a SentencePiece token; 0 when the model has none.
+    pub fn score(&self, id: u32) -> f32 {
+        self.scores.get(id as usize).copied().unwrap_or(0.0)
+    }
+
+    /// Check if a token is a special token.
+    pub fn is_special(&self, id: u32) -> bool {
+        id == self.bos_id || id == self.eos_id || id == self.pad_id
+    }
+}
+
+/// BPE tokenizer for LLaMA-family models.
+pub struct Tokenizer {
+    vocab: Vocabulary,
+    /// Byte-level merge rules, highest priority first; empty for
+    /// SentencePiece vocabularies, which merge by score instead.
+    bpe_merges: Vec<(String, String)>,
+    /// `(left, right)` → `(rank, merged)` by token ID, from `bpe_merges`.
+    merge_ranks: HashMap<(u32, u32), (u32, u32)>,
+    /// Bytes map to printable stand-ins (GPT-2) rather than `<0xXX>` tokens.
+    byte_level: bool,
+    /// SentencePiece: encode text as if it started with a space.
+    add_space_prefix: bool,
+}
+
+impl Tokenizer {
+    /// Load the tokenizer from a GGUF file's metadata, without its weights.
+    pub fn from_gguf(path: &Path) -> Result<Self> {
+        let file = std::fs::File::open(path)
+            .map_err(|e| BizClawError::Brain(format!("Can't open {}: {e}", path.display())))?;
+        let gguf = GgufFile::parse(&mut std::io::BufReader::new(file))?;
+        Self::from_metadata(&gguf.metadata)
+    }
+
+    /// Create a tokenizer from GGUF metadata.
+    pub fn from_metadata(metadata: &HashMap<String, GgufValue>) -> Result<Self> {
+        let array = |key: &str| match metadata.get(key) {
+            Some(GgufValue::Array(arr)) => Some(arr),
+            _ => None,
+        };
+
+        // Extract vocabulary tokens
+        let tokens: Vec<String> = array("tokenizer.ggml.tokens")
+            .ok_or_else(|| BizClawError::Brain("Missing tokenizer.ggml.tokens".into()))?
+            .iter()
+            .filter_map(|v| v.as_str().map(|s| s.to_string()))
+            .collect();
+
+        if tokens.is_empty() {
+            return Err(BizClawError::Brain("Empty vocabulary".into()));
+        }
+
+        // Extract scores
+        let scores: Vec<f32> = array("tokenizer.ggml.scores")
+            .map(|arr| arr.iter().filter_map(|v| v.as_f32()).collect())
+            .unwrap_or_else(|| vec![0.0; tokens.len()]);
+
+        let bpe_merges: Vec<(String, String)> = array("tokenizer.ggml.merges")
+            .map(|arr| arr.iter()
+                .filter_map(|v| v.as_str()?.split_once(' '))
+                .map(|(l, r)| (l.to_string(), r.to_string()))
+                .collect())
+            .unwrap_or_default();
+
+        // Extract special tokens
+        let special = |key: &str, default: u32| {
+            metadata.get(key).and_then(|v| v.as_u32()).unwrap_or(default)
+        };
+        let bos_id = special("tokenizer.ggml.bos_token_id", 1);
+        let eos_id = special("tokenizer.ggml.eos_token_id", 2);
+        let pad_id = special("tokenizer.ggml.padding_token_id", 0);
+        let unk_id = special("tokenizer.ggml.unknown_token_id", 0);
+
+        let byte_level = !bpe_merges.is_empty()
+            || metadata.get("tokenizer.ggml.model").and_then(|v| v.as_str()) == Some("gpt2");
+        let add_space_prefix = metadata.get("tokenizer.ggml.add_space_prefix")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(!byte_level);
+
+        tracing::info!(
+            "Tokenizer loaded: vocab_size={}, merges={}, bos={}, eos={}",
+            tokens.len(), bpe_merges.len(), bos_id, eos_id
+        );
+
+        Ok(Self::new(tokens, scores, bpe_merges, byte_level, add_space_prefix, [bos_id, eos_id, pad_id, unk_id]))
+    }
+
+    /// Create a simple fallback tokenizer (for testing without a model):
+    /// one token per byte, so any text round-trips.
+    pub fn fallback() -> Self {
+        let mut tokens: Vec<String> = vec!["<unk>".into(), "<s>".into(), "</s>".into()];
+        tokens.extend((0..=255u8).map(|b| format!("<0x{b:02X}>")));
+        let scores = vec![0.0; tokens.len()];
+        Self::new(tokens, scores, Vec::new(), false, false, [1, 2, 0, 0])
+    }
+
+    fn new(
+        tokens: Vec<String>,
+        scores: Vec<f32>,
+        bpe_merges: Vec<(String, String)>,
+        byte_level: bool,
+        add_space_prefix: bool,
+        [bos_id, eos_id, pad_id, unk_id]: [u32; 4],
+    ) -> Self {
+        // Build reverse mapping
+        let token_to_id: HashMap<String, u32> = tokens.iter()
+            .enumerate()
+            .map(|(i, t)| (t.clone(), i as u32))
+            .collect();
+
+        let mut byte_ids = [None; 256];
+        for (b, id) in byte_ids.iter_mut().enumerate() {
+            *id = if byte_level {
+                token_to_id.get(&*byte_to_char(b as u8).encode_utf8(&mut [0; 4])).copied()
+            } else {
+                token_to_id.get(&format!("<0x{b:02X}>")).copied()
+            };
+        }
+
+        let merge_ranks = bpe_merges.iter().enumerate()
+            .filter_map(|(rank, (l, r))| {
+                let merged = token_to_id.get(&format!("{l}{r}"))?;
+                Some(((*token_to_id.get(l)?, *token_to_id.get(r)?), (rank as u32, *merged)))
+            })
+            .collect();
+
+        let vocab = Vocabulary { tokens, token_to_id, scores, byte_ids, bos_id, eos_id, pad_id, unk_id };
+        Self { vocab, bpe_merges, merge_ranks, byte_level, add_space_prefix }
+    }
+
+    pub fn vocab(&self) -> &Vocabulary {
+        &self.vocab
+    }
+
+    /// Byte-level merge rules in priority order; empty for SentencePiece.
+    pub fn bpe_merges(&self) -> &[(String, String)] {
+        &self.bpe_merges
+    }
+
+    pub fn bos_id(&self) -> u32 {
+        self.vocab.bos_id
+    }
+
+    pub fn eos_id(&self) -> u32 {
+        self.vocab.eos_id
+    }
+
+    /// Encode text into token IDs using BPE.
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        let mut tokens = Vec::new();
+        if text.is_empty() {
+            return tokens;
+        }
+        if self.byte_level {
+            self.encode_bytes(text, &mut tokens);
+        } else {
+            self.encode_spm(text, &mut tokens);
+        }
+        tokens
+    }
+
+    /// The number of tokens `text` encodes to.
+    pub fn count_tokens(&self, text: &str) -> usize {
+        self.encode(text).len()
+    }
+
+    /// SentencePiece: spaces become `▁`, characters merge by highest
+    /// score, and pieces missing from the vocabulary fall back to bytes.
+    /// Each word merges on its own, with the spaces before it.
+    fn encode_spm(&self, text: &str, out: &mut Vec<u32>) {
+        let mut word = String::new();
+        let mut merger = Merger::default();
+        for (i, piece) in split_words(text).enumerate() {
+            word.clear();
+            if i == 0 && self.add_space_prefix {
+                word.push(SPM_SPACE);
+            }
+            word.extend(piece.chars().map(|c| if c == ' ' { SPM_SPACE } else { c }));
+
+            let symbols = word.char_indices()
+                .map(|(i, c)| Symbol::new(i, i + c.len_utf8(), self.vocab.id(&word[i..i + c.len_utf8()])));
+            let symbols = merger.merge(symbols, |left, right| {
+                let id = self.vocab.id(&word[left.start..right.end])?;
+                Some((self.vocab.score(id), id))
+            });
+
+            for symbol in symbols {
+                match symbol.id {
+                    Some(id) => out.push(id),
+                    None => {
+                        let mut buf = [0; 4];
+                        for c in word[symbol.start..symbol.end].chars() {
+                            let c = if c == SPM_SPACE { ' ' } else { c };
+                            out.extend(c.encode_utf8(&mut buf).bytes()
+                                .map(|b| self.vocab.byte_ids[b as usize].unwrap_or(self.vocab.unk_id)));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Byte-level BPE over each pre-tokenized word, lowest rank first.
+    fn encode_bytes(&self, text: &str, out: &mut Vec<u32>) {
+        let mut merger = Merger::default();
+        for word in pre_tokenize(text) {
+            let symbols = word.bytes().enumerate()
+                .map(|(i, b)| Symbol::new(i, i + 1, self.vocab.byte_ids[b as usize]));
+            let symbols = merger.merge(symbols, |left, right| {
+                let &(rank, merged) = self.merge_ranks.get(&(left.id?, right.id?))?;
+                Some((-(rank as f32), merged))
+            });
+            out.extend(symbols.map(|s| s.id.unwrap_or(self.vocab.unk_id)));
+        }
+    }
+
+    /// Decode a single token ID to string.
+    pub fn decode_token(&self, id: u32) -> &str {
+        self.vocab.token(id).unwrap_or("<unk>")
+    }
+
+    /// Decode a sequence of token IDs to text, skipping special tokens.
+    pub fn decode(&self, tokens: &[u32]) -> String {
+        let mut bytes = Vec::new();
+        for &id in tokens {
+            if !self.vocab.is_special(id) {
+                self.push_bytes(id, &mut bytes);
+            }
+        }
+        let text = String::from_utf8_lossy(&bytes);
+        match text.strip_prefix(' ') {
+            Some(rest) if self.add_space_prefix => rest.to_string(),
+            _ => text.into_owned(),
+        }
+    }
+
+    /// Append the bytes a token stands for.
+    fn push_bytes(&self, id: u32, bytes: &mut Vec<u8>) {
+        let token = self.decode_token(id);
+        let mut buf = [0; 4];
+        if self.byte_level {
+            for c in token.chars() {
+                match char_to_byte(c) {
+                    Some(b) => bytes.push(b),
+                    None => bytes.extend(c.encode_utf8(&mut buf).as_bytes()),
+                }
+            }
+        } else if let Some(b) = parse_byte_token(token) {
+            bytes.push(b);
+        } else {
+            for c in token.chars() {
+                bytes.extend(if c == SPM_SPACE { " " } else { c.encode_utf8(&mut buf) }.as_bytes());
+            }
+        }
+    }
+
+    /// Get vocabulary size.
+    pub fn vocab_size(&self) -> usize {
+        self.vocab.len()
+    }
+
+    /// Token texts for grammar-constrained generation; special tokens and
+    /// partial UTF-8 bytes are left empty so a grammar never allows them as text.
+    pub fn grammar_vocabulary(&self) -> crate::grammar::Vocabulary {
+        let tokens = (0..self.vocab.len() as u32)
+            .map(|id| {
+                let mut bytes = Vec::new();
+                if !self.vocab.is_special(id) {
+                    self.push_bytes(id, &mut bytes);
+                }
+                String::from_utf8(bytes).unwrap_or_default()
+            })
+            .collect();
+        crate::grammar::Vocabulary::new(tokens, self.vocab.eos_id)
+    }
+
+    /// Check if a token is a special token.
+    pub fn is_special(&self, id: u32) -> bool {
+        self.vocab.is_special(id)
+    }
+}
+
+/// A span of the input, linked to its neighbours while merging.
+#[derive(Debug, Clone, Copy)]
+struct Symbol {
+    start: usize,
+    end: usize,
+    /// The span's token, if the vocabulary has one.
+    id: Option<u32>,
+    prev: Option<usize>,
+    next: Option<usize>,
+}
+
+impl Symbol {
+    fn new(start: usize, end: usize, id: Option<u32>) -> Self {
+        Self { start, end, id, prev: None, next: None }
+    }
+}
+
+/// A possible merge of two adjacent symbols.
+struct Candidate {
+    priority: f32,
+    left: usize,
+    right: usize,
+    /// Combined length when queued; a mismatch means a neighbour merged first.
+    len: usize,
+    merged: u32,
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    /// Highest priority first, leftmost on ties.
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority.total_cmp(&other.priority).then(other.left.cmp(&self.left))
+    }
+}
+
+/// Merges symbols, reusing its buffers from one word to the next.
+#[derive(Default)]
+struct Merger {
+    symbols: Vec<Symbol>,
+    queue: BinaryHeap<Candidate>,
+}
+
+impl Merger {
+    /// Repeatedly merge the best adjacent pair, as chosen by `pair`, and
+    /// return the surviving symbols in order.
+    fn merge(
+        &mut self,
+        symbols: impl Iterator<Item = Symbol>,
+        pair: impl Fn(&Symbol, &Symbol) -> Option<(f32, u32)>,
+    ) -> impl Iterator<Item = &Symbol> {
+        let Self { symbols: buf, queue } = self;
+        buf.clear();
+        buf.extend(symbols);
+        let n = buf.len();
+        for (i, symbol) in buf.iter_mut().enumerate() {
+            symbol.prev = i.checked_sub(1);
+            symbol.next = (i + 1 < n).then_some(i + 1);
+        }
+
+        let candidate = |symbols: &[Symbol], left: usize, right: usize| {
+            let (l, r) = (&symbols[left], &symbols[right]);
+            pair(l, r).map(|(priority, merged)| Candidate { priority, left, right, len: r.end - l.start, merged })
+        };
+        queue.clear();
+        queue.extend((1..n).filter_map(|i| candidate(buf, i - 1, i)));
+
+        while let Some(c) = queue.pop() {
+            let (l, r) = (buf[c.left], buf[c.right]);
+            if l.start == l.end || r.start == r.end || l.next != Some(c.right) || r.end - l.start != c.len {
+                continue;
+            }
+            buf[c.left].end = r.end;
+            buf[c.left].id = Some(c.merged);
+            buf[c.left].next = r.next;
+            buf[c.right].start = r.end;
+            if let Some(next) = r.next {
+                buf[next].prev = Some(c.left);
+                queue.extend(candidate(buf, c.left, next));
+            }
+            if let Some(prev) = l.prev {
+                queue.extend(candidate(buf, prev, c.left));
+            }
+        }
+
+        buf.iter().filter(|s| s.start != s.end)
+    }
+}
+
+/// Split text before each space that follows a non-space, so every word
+/// carries the spaces in front of it.
+fn split_words(text: &str) -> impl Iterator<Item = &str> {
+    let bytes = text.as_bytes();
+    let mut start = 0;
+    let mut ends = (1..bytes.len())
+        .filter(move |&i| bytes[i] == b' ' && bytes[i - 1] != b' ')
+        .chain(std::iter::once(bytes.len()));
+    std::iter::from_fn(move || {
+        let end = ends.next()?;
+        let word = &text[start..end];
+        start = end;
+        Some(word)
+    })
+}
+
+/// Split text into words the way GPT-2 does, roughly: runs of letters,
+/// digits or punctuation, each with at most one leading space.
+fn pre_tokenize(text: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class { Letter, Digit, Space, Other }
+    let class = |c: char| {
+        if c.is_alphabetic() { Class::Letter }
+        else if c.is_numeric() { Class::Digit }
+        else if c.is_whitespace() { Class::Space }
+        else { Class::Other }
+    };
+
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let at = |i: usize| chars.get(i).map_or(text.len(), |&(pos, _)| pos);
+    let mut words = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        if class(chars[i].1) == Class::Space {
+            while i < chars.len() && class(chars[i].1) == Class::Space {
+                i += 1;
+            }
+            // A single space before a word belongs to the word.
+            if i < chars.len() && chars[i - 1].1 == ' ' {
+                i -= 1;
+                if i > start {
+                    words.push(&text[at(start)..at(i)]);
+                }
+            } else {
+                words.push(&text[at(start)..at(i)]);
+                continue;
+            }
+        }
+        let word_start = i;
+        if chars[i].1 == ' ' {
+            i += 1;
+        }
+        let kind = class(chars[i].1);
+        while i < chars.len() && class(chars[i].1) == kind {
+            i += 1;
+        }
+        words.push(&text[at(word_start)..at(i)]);
+    }
+    words
+}
+
+/// GPT-2's printable stand-in for a byte: printable Latin-1 maps to
+/// itself, everything else to U+0100 onwards.
+fn byte_to_char(b: u8) -> char {
+    let c = match b {
+        0..=32 => 256 + b as u32,
+        127..=160 => 256 + 33 + (b - 127) as u32,
+        173 => 256 + 67,
+        _ => b as u32,
+    };
+    char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+/// The byte behind a byte-level stand-in character.
+fn char_to_byte(c: char) -> Option<u8> {
+    match c as u32 {
+        c @ (33..=126 | 161..=172 | 174..=255) => Some(c as u8),
+        c @ 256..=288 => Some((c - 256) as u8),
+        c @ 289..=322 => Some((c - 289 + 127) as u8),
+        323 => Some(173),
+        _ => None,
+    }
+}
+
+/// The byte of a SentencePiece `<0xXX>` token.
+fn parse_byte_token(token: &str) -> Option<u8> {
+    let hex = token.strip_prefix("<0x")?.strip_suffix('>')?;
+    if hex.len() != 2 {
+        return None;
+    }
+    u8::from_str_radix(hex, 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata(kvs: Vec<(&str, GgufValue)>) -> HashMap<String, GgufValue> {
+        kvs.into_iter().map(|(k, v)| (k.to_string(), v)).collect()
+    }
+
+    fn strings(items: &[&str]) -> GgufValue {
+        GgufValue::Array(items.iter().map(|s| GgufValue::String(s.to_string())).collect())
+    }
+
+    /// A small SentencePiece vocabulary where longer pieces score higher.
+    fn spm() -> Tokenizer {
+        let mut tokens = vec!["<unk>", "<s>", "</s>", "▁", "h", "e", "l", "o", "w", "r", "d",
+            "he", "ll", "llo", "hell", "hello", "▁hello", "▁w", "or", "ld", "▁wor", "▁world"];
+        let bytes: Vec<String> = (0..=255u8).map(|b| format!("<0x{b:02X}>")).collect();
+        tokens.extend(bytes.iter().map(|s| s.as_str()));
+        let scores = tokens.iter()
+            .map(|t| GgufValue::F32(if t.starts_with('<') { 0.0 } else { t.chars().count() as f32 }))
+            .collect();
+        Tokenizer::from_metadata(&metadata(vec![
+            ("tokenizer.ggml.model", GgufValue::String("llama".into())),
+            ("tokenizer.ggml.tokens", strings(&tokens)),
+            ("tokenizer.ggml.scores", GgufValue::Array(scores)),
+        ])).unwrap()
+    }
+
+    #[test]
+    fn test_spm_encode_merges_by_score() {
+        let tok = spm();
+        let ids = tok.encode("hello world");
+        let pieces: Vec<&str> = ids.iter().map(|&id| tok.decode_token(id)).collect();
+        assert_eq!(pieces, ["▁hello", "▁world"]);
+        assert_eq!(tok.decode(&ids), "hello world");
+        assert_eq!(tok.count_tokens("hello world"), 2);
+        assert!(tok.encode("").is_empty());
+    }
+
+    #[test]
+    fn test_spm_byte_fallback() {
+        let tok = spm();
+        let ids = tok.encode("hé!");
+        let pieces: Vec<&str> = ids.iter().map(|&id| tok.decode_token(id)).collect();
+        assert_eq!(pieces, ["▁", "h", "<0xC3>", "<0xA9>", "<0x21>"]);
+        assert_eq!(tok.decode(&ids), "hé!");
+    }
+
+    #[test]
+    fn test_byte_level_merges_by_rank() {
+        // "Ġ" is the byte-level stand-in for a space.
+        let tok = Tokenizer::from_metadata(&metadata(vec![
+            ("tokenizer.ggml.model", GgufValue::String("gpt2".into())),
+            ("tokenizer.ggml.tokens", strings(&["<|endoftext|>", "h", "i", "Ġ", "!", "hi", "Ġhi", "Ġ!"])),
+            ("tokenizer.ggml.merges", strings(&["h i", "Ġ hi"])),
+            ("tokenizer.ggml.bos_token_id", GgufValue::U32(0)),
+            ("tokenizer.ggml.eos_token_id", GgufValue::U32(0)),
+        ])).unwrap();
+        assert_eq!(tok.bpe_merges().len(), 2);
+
+        let ids = tok.encode("hi hi!");
+        let pieces: Vec<&str> = ids.iter().map(|&id| tok.decode_token(id)).collect();
+        // "Ġ!" is in the vocabulary but has no merge rule.
+        assert_eq!(pieces, ["hi", "Ġhi", "!"]);
+        assert_eq!(tok.decode(&ids), "hi hi!");
+    }
+
+    #[test]
+    fn test_split_words() {
+        assert_eq!(split_words("hello  world !").collect::<Vec<_>>(), ["hello", "  world", " !"]);
+        assert_eq!(split_words(" a").collect::<Vec<_>>(), [" a"]);
+    }
+
+    #[test]
+    fn test_pre_tokenize() {
+        assert_eq!(pre_tokenize("Hello, world  42x"), ["Hello", ",", " world", " ", " 42", "x"]);
+        assert_eq!(pre_tokenize(" a\n"), [" a", "\n"]);
+    }
+
+    #[test]
+    fn test_byte_chars_round_trip() {
+        for b in 0..=255u8 {
+            assert_eq!(char_to_byte(byte_to_char(b)), Some(b), "byte {b}");
+        }
+        assert_eq!(byte_to_char(b' '), 'Ġ');
+    }
+
+    #[test]
+    fn test_fallback_round_trips() {
+        let tok = Tokenizer::fallback();
+        let ids = tok.encode("Xin chào!");
+        assert_eq!(ids.len(), "Xin chào!".len());
+        assert_eq!(tok.decode(&ids), "Xin chào!");
+    }
+
+    #[test]
+    fn test_from_gguf_reads_metadata_only() {
+        fn string(out: &mut Vec<u8>, s: &str) {
+            out.extend((s.len() as u64).to_le_bytes());
+            out.extend(s.as_bytes());
+        }
+        let tokens = ["<unk>", "<s>", "</s>", "▁", "h", "i", "▁h", "▁hi"];
+        let mut gguf = b"GGUF".to_vec();
+        gguf.extend(3u32.to_le_bytes());
+        gguf.extend(0u64.to_le_bytes());
+        gguf.extend(1u64.to_le_bytes());
+        string(&mut gguf, "tokenizer.ggml.tokens");
+        gguf.extend(9u32.to_le_bytes());
+        gguf.extend(8u32.to_le_bytes());
+        gguf.extend((tokens.len() as u64).to_le_bytes());
+        for t in tokens {
+            string(&mut gguf, t);
+        }
+
+        let path = std::env::temp_dir().join(format!("bizclaw-tokenizer-{}.gguf", std::process::id()));
+        std::fs::write(&path, gguf).unwrap();
+        let tok = Tokenizer::from_gguf(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(tok.vocab_size(), 8);
+        assert_eq!(tok.encode("hi"), [7]);
+
+        let err = Tokenizer::from_gguf(Path::new("/nonexistent/model.gguf")).err().unwrap().to_string();
+        assert!(err.contains("/nonexistent/model.gguf"), "{err}");
+    }
+}
@@ -0,0 +1,98 @@
+//! Per-chat settings changed with bot commands (`/model`, `/reset`), kept
+//! in a JSON file so they survive restarts.
+
+use bizclaw_core::error::{BizClawError, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Settings of one chat.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChatSettings {
+    /// Model used instead of `default_model`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// When the conversation was last cleared; it starts over after this.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reset_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Settings of every chat of a channel, keyed by thread id.
+#[derive(Debug)]
+pub struct ChatSettingsStore {
+    path: PathBuf,
+    chats: Mutex<HashMap<String, ChatSettings>>,
+}
+
+impl ChatSettingsStore {
+    /// Load the settings saved at `path`; a missing or unreadable file
+    /// starts empty.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let chats = match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+                tracing::warn!("Ignoring invalid chat settings in {}: {e}", path.display());
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self { path, chats: Mutex::new(chats) }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Settings of `chat_id` (the defaults if none were changed).
+    pub fn get(&self, chat_id: &str) -> ChatSettings {
+        self.chats.lock().unwrap().get(chat_id).cloned().unwrap_or_default()
+    }
+
+    /// Change the settings of `chat_id` and save them all.
+    pub fn update(&self, chat_id: &str, change: impl FnOnce(&mut ChatSettings)) -> Result<ChatSettings> {
+        let mut chats = self.chats.lock().unwrap();
+        let settings = chats.entry(chat_id.to_string()).or_default();
+        change(settings);
+        let settings = settings.clone();
+        if settings == ChatSettings::default() {
+            chats.remove(chat_id);
+        }
+        self.save(&chats)?;
+        Ok(settings)
+    }
+
+    fn save(&self, chats: &HashMap<String, ChatSettings>) -> Result<()> {
+        let save_error = |e: std::io::Error| BizClawError::Channel(format!("Can't save {}: {e}", self.path.display()));
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(save_error)?;
+        }
+        let content = serde_json::to_string_pretty(chats)
+            .map_err(|e| BizClawError::Channel(format!("Can't serialize chat settings: {e}")))?;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, content).and_then(|_| std::fs::rename(&tmp, &self.path)).map_err(save_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_settings_survive_reopen() {
+        let path = std::env::temp_dir().join(format!("bizclaw-chat-settings-{}.json", std::process::id()));
+        let store = ChatSettingsStore::open(&path);
+        assert_eq!(store.get("42"), ChatSettings::default());
+        store.update("42", |s| s.model = Some("gpt-4o".into())).unwrap();
+        store.update("7", |s| s.reset_at = Some(chrono::Utc::now())).unwrap();
+
+        let reopened = ChatSettingsStore::open(&path);
+        assert_eq!(reopened.get("42").model.as_deref(), Some("gpt-4o"));
+        assert!(reopened.get("7").reset_at.is_some());
+
+        // Back to the defaults: the chat is dropped from the file.
+        reopened.update("42", |s| s.model = None).unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().contains("\"42\""));
+        std::fs::remove_file(path).ok();
+    }
+}
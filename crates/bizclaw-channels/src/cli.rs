@@ -4,9 +4,57 @@ use async_trait::async_trait;
 use bizclaw_core::error::Result;
 use bizclaw_core::traits::Channel;
 use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
+use std::path::PathBuf;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio_stream::Stream;
 
+/// Shown for `/help`.
+pub const HELP: &str = "\
+/model [name]     Show or switch the model
+/provider [name]  Show or switch the provider
+/clear            Start a new conversation
+/tools            List the available tools
+/save <file>      Write the conversation to a file
+/info             Show provider, model and message count
+/quit             Exit";
+
+/// A command typed at the prompt, handled by the REPL instead of the agent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SlashCommand {
+    Model(Option<String>),
+    Provider(Option<String>),
+    Clear,
+    Tools,
+    Save(Option<PathBuf>),
+    Info,
+    Help,
+    /// Looks like a command but isn't one of ours.
+    Unknown(String),
+}
+
+impl SlashCommand {
+    /// Parse `/name args`. `None` for anything else, e.g. a path like
+    /// `/etc/hosts is missing`, which goes to the agent as it is.
+    pub fn parse(line: &str) -> Option<Self> {
+        let rest = line.trim().strip_prefix('/')?;
+        let (name, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return None;
+        }
+        let arg = Some(args.trim()).filter(|a| !a.is_empty());
+        Some(match name {
+            "model" => Self::Model(arg.map(String::from)),
+            "provider" => Self::Provider(arg.map(String::from)),
+            "clear" => Self::Clear,
+            "tools" => Self::Tools,
+            "save" => Self::Save(arg.map(PathBuf::from)),
+            "info" => Self::Info,
+            "help" => Self::Help,
+            other => Self::Unknown(other.to_string()),
+        })
+    }
+}
+
 pub struct CliChannel {
     connected: bool,
 }
@@ -57,6 +105,8 @@ impl Channel for CliChannel {
                             thread_type: ThreadType::Direct,
                             timestamp: chrono::Utc::now(),
                             reply_to: None,
+                            attachments: Vec::new(),
+                            message_id: None,
                         };
                     }
                     Ok(None) => break,
@@ -72,3 +122,22 @@ impl Channel for CliChannel {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_slash_commands() {
+        assert_eq!(SlashCommand::parse("/model  gpt-4o-mini "), Some(SlashCommand::Model(Some("gpt-4o-mini".into()))));
+        assert_eq!(SlashCommand::parse("/model"), Some(SlashCommand::Model(None)));
+        assert_eq!(SlashCommand::parse("/provider ollama"), Some(SlashCommand::Provider(Some("ollama".into()))));
+        assert_eq!(SlashCommand::parse("/clear"), Some(SlashCommand::Clear));
+        assert_eq!(SlashCommand::parse("/tools"), Some(SlashCommand::Tools));
+        assert_eq!(SlashCommand::parse("/save notes/run 1.md"), Some(SlashCommand::Save(Some("notes/run 1.md".into()))));
+        assert_eq!(SlashCommand::parse("/save"), Some(SlashCommand::Save(None)));
+        assert_eq!(SlashCommand::parse("/compact"), Some(SlashCommand::Unknown("compact".into())));
+        assert_eq!(SlashCommand::parse("/etc/hosts is missing"), None);
+        assert_eq!(SlashCommand::parse("what does /model do?"), None);
+    }
+}
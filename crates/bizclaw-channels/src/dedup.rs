@@ -0,0 +1,278 @@
+//! Drops inbound messages a channel delivers more than once.
+//!
+//! Webhooks are retried when the answer is slow, Telegram resends updates
+//! after a restart and IMAP can list a mail again. [`DedupChannel`] wraps a
+//! channel and passes on only the first copy of each message; the channel
+//! itself still acknowledges every delivery. Messages are keyed by
+//! `(channel, message_id)` and remembered in SQLite for the configured
+//! window, so a restart doesn't let redeliveries through. When the channel
+//! has no stable id, a hash of sender, thread and content stands in for it,
+//! remembered for at most [`CONTENT_KEY_WINDOW`] so the same words sent again
+//! later still get through. The receive time is not part of the hash: a
+//! redelivery is received later than the original.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::provider::TokenStream;
+use bizclaw_core::traits::{AgentProgress, Channel, ChannelHealth, DroppedMessage};
+use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
+use futures::stream::Stream;
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc::UnboundedSender;
+
+/// How long a message without an id is remembered by its content.
+pub const CONTENT_KEY_WINDOW: Duration = Duration::from_secs(600);
+
+/// Messages seen recently, by channel.
+pub struct InboundDedup {
+    conn: Mutex<rusqlite::Connection>,
+    window: Duration,
+    duplicates: AtomicU64,
+}
+
+impl InboundDedup {
+    /// Open (or create) the store at `path`, remembering messages for `window`.
+    pub fn open(path: &Path, window: Duration) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = rusqlite::Connection::open(path)
+            .map_err(|e| BizClawError::Channel(format!("Dedup store open error: {e}")))?;
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS seen_messages (
+                channel TEXT NOT NULL,
+                message_id TEXT NOT NULL,
+                seen_at INTEGER NOT NULL,
+                PRIMARY KEY (channel, message_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_seen_messages_seen_at ON seen_messages(seen_at);
+        ").map_err(|e| BizClawError::Channel(format!("Dedup store migration error: {e}")))?;
+        Ok(Self { conn: Mutex::new(conn), window, duplicates: AtomicU64::new(0) })
+    }
+
+    /// Open `<data dir>/inbound_seen.db`.
+    pub fn open_default(window: Duration) -> Result<Self> {
+        Self::open(&bizclaw_core::config::BizClawConfig::data_dir().join("inbound_seen.db"), window)
+    }
+
+    /// The message's id on its channel, or `sha256:<hex>` of its sender,
+    /// thread and content.
+    pub fn key(message: &IncomingMessage) -> String {
+        if let Some(id) = Self::message_id(message) {
+            return id.to_string();
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(message.sender_id.as_bytes());
+        hasher.update([0]);
+        hasher.update(message.thread_id.as_bytes());
+        hasher.update([0]);
+        hasher.update(message.content.as_bytes());
+        let hex: String = hasher.finalize().iter().map(|b| format!("{b:02x}")).collect();
+        format!("sha256:{hex}")
+    }
+
+    /// Remember `message`; false if it was already seen within the window.
+    pub fn first_seen(&self, message: &IncomingMessage) -> Result<bool> {
+        self.first_seen_at(message, chrono::Utc::now().timestamp())
+    }
+
+    fn message_id(message: &IncomingMessage) -> Option<&str> {
+        message.message_id.as_deref().filter(|id| !id.is_empty())
+    }
+
+    fn first_seen_at(&self, message: &IncomingMessage, now: i64) -> Result<bool> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let ago = |window: Duration| now.saturating_sub(window.as_secs().try_into().unwrap_or(i64::MAX));
+        let oldest = ago(self.window);
+        // A content key seen before its shorter window counts as new again.
+        let expired = match Self::message_id(message) {
+            Some(_) => oldest,
+            None => ago(self.window.min(CONTENT_KEY_WINDOW)),
+        };
+        let inserted = conn.execute("DELETE FROM seen_messages WHERE seen_at < ?1", [oldest])
+            .and_then(|_| conn.execute(
+                "INSERT INTO seen_messages (channel, message_id, seen_at) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (channel, message_id) DO UPDATE SET seen_at = excluded.seen_at WHERE seen_at < ?4",
+                rusqlite::params![message.channel, Self::key(message), now, expired],
+            ))
+            .map_err(|e| BizClawError::Channel(format!("Dedup store error: {e}")))?;
+        if inserted == 0 {
+            self.duplicates.fetch_add(1, Ordering::Relaxed);
+        }
+        Ok(inserted == 1)
+    }
+
+    /// Messages dropped as repeats since the store was opened.
+    pub fn duplicates(&self) -> u64 {
+        self.duplicates.load(Ordering::Relaxed)
+    }
+}
+
+/// A channel that hands on each received message once. If the store
+/// fails the message is handed on, with a warning.
+pub struct DedupChannel<C> {
+    inner: C,
+    dedup: Arc<InboundDedup>,
+}
+
+impl<C: Channel> DedupChannel<C> {
+    pub fn new(inner: C, dedup: Arc<InboundDedup>) -> Self {
+        Self { inner, dedup }
+    }
+}
+
+#[async_trait]
+impl<C: Channel> Channel for DedupChannel<C> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        self.inner.connect().await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.inner.disconnect().await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
+        self.inner.listen().await
+    }
+
+    async fn start(&mut self, agent_tx: UnboundedSender<IncomingMessage>) -> Result<()> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<IncomingMessage>();
+        self.inner.start(tx).await?;
+        let (channel, dedup, dropped) = (self.inner.name().to_string(), self.dedup.clone(), self.inner.on_dropped());
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                match dedup.first_seen(&message) {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        tracing::debug!("{channel}: dropping repeated message {}", InboundDedup::key(&message));
+                        if let Some(dropped) = &dropped {
+                            dropped(&message);
+                        }
+                        continue;
+                    }
+                    Err(e) => tracing::warn!("{channel}: cannot check for a repeated message: {e}"),
+                }
+                if agent_tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn health(&self) -> ChannelHealth {
+        self.inner.health()
+    }
+
+    fn force_reconnect(&self) -> Result<()> {
+        self.inner.force_reconnect()
+    }
+
+    fn on_dropped(&self) -> Option<DroppedMessage> {
+        self.inner.on_dropped()
+    }
+
+    async fn send(&self, message: OutgoingMessage) -> Result<()> {
+        self.inner.send(message).await
+    }
+
+    async fn send_media(&self, thread_id: &str, thread_type: ThreadType, path: &Path) -> Result<()> {
+        self.inner.send_media(thread_id, thread_type, path).await
+    }
+
+    async fn send_streaming(&self, thread_id: &str, thread_type: ThreadType, tokens: TokenStream) -> Result<()> {
+        self.inner.send_streaming(thread_id, thread_type, tokens).await
+    }
+
+    async fn send_typing(&self, thread_id: &str) -> Result<()> {
+        self.inner.send_typing(thread_id).await
+    }
+
+    async fn send_progress(&self, thread_id: &str, progress: &AgentProgress) -> Result<()> {
+        self.inner.send_progress(thread_id, progress).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(channel: &str, message_id: Option<&str>, content: &str) -> IncomingMessage {
+        IncomingMessage {
+            channel: channel.into(),
+            thread_id: "t1".into(),
+            sender_id: "u1".into(),
+            sender_name: None,
+            content: content.into(),
+            thread_type: ThreadType::Direct,
+            timestamp: chrono::DateTime::from_timestamp(1_760_000_000, 0).unwrap(),
+            reply_to: None,
+            attachments: Vec::new(),
+            message_id: message_id.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_replays_within_window_are_dropped() {
+        let path = std::env::temp_dir().join(format!("bizclaw-seen-{}.db", uuid::Uuid::new_v4().simple()));
+        let dedup = InboundDedup::open(&path, Duration::from_secs(3600)).unwrap();
+        let now = 1_760_000_000;
+
+        let order = message("telegram", Some("901"), "Cho mình 2 áo size M");
+        assert!(dedup.first_seen_at(&order, now).unwrap());
+        assert!(!dedup.first_seen_at(&order, now + 5).unwrap());
+        // The same id on another channel is another message.
+        assert!(dedup.first_seen_at(&message("discord", Some("901"), "hi"), now).unwrap());
+        // Without an id, sender, thread and content decide.
+        let bare = message("webhook", None, "Đơn #88");
+        assert!(InboundDedup::key(&bare).starts_with("sha256:"));
+        assert!(dedup.first_seen_at(&bare, now).unwrap());
+        assert!(!dedup.first_seen_at(&bare, now).unwrap());
+        assert!(dedup.first_seen_at(&message("webhook", None, "Đơn #89"), now).unwrap());
+        assert_eq!(dedup.duplicates(), 2);
+
+        // Remembered across a restart, forgotten after the window.
+        let reopened = InboundDedup::open(&path, Duration::from_secs(3600)).unwrap();
+        assert!(!reopened.first_seen_at(&order, now + 60).unwrap());
+        assert!(reopened.first_seen_at(&order, now + 3601).unwrap());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_later_redelivery_without_id_is_dropped() {
+        let path = std::env::temp_dir().join(format!("bizclaw-seen-{}.db", uuid::Uuid::new_v4().simple()));
+        let dedup = InboundDedup::open(&path, Duration::from_secs(86400)).unwrap();
+        let now = 1_760_000_000;
+
+        // The retry is stamped when it arrives, 40 seconds after the original.
+        let original = message("webhook", None, "Đơn #88");
+        let mut retry = original.clone();
+        retry.timestamp = original.timestamp + chrono::Duration::seconds(40);
+        assert_eq!(InboundDedup::key(&original), InboundDedup::key(&retry));
+        assert!(dedup.first_seen_at(&original, now).unwrap());
+        assert!(!dedup.first_seen_at(&retry, now + 40).unwrap());
+        // Another thread is another message.
+        let mut elsewhere = original.clone();
+        elsewhere.thread_id = "t2".into();
+        assert!(dedup.first_seen_at(&elsewhere, now + 40).unwrap());
+
+        // The same words sent again later are a new message.
+        let window = CONTENT_KEY_WINDOW.as_secs() as i64;
+        assert!(dedup.first_seen_at(&original, now + window + 41).unwrap());
+        assert!(!dedup.first_seen_at(&original, now + window + 45).unwrap());
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -1,16 +1,26 @@
 //! Discord Bot channel — REST API + Gateway WebSocket.
 //!
 //! Connects to Discord Gateway for real-time events (messages, reactions, etc.)
-//! and uses REST API for sending messages.
+//! and uses REST API for sending messages. A dropped Gateway connection is
+//! resumed through the session's `resume_gateway_url`, so no events are lost
+//! while reconnecting.
 
 use async_trait::async_trait;
+use bizclaw_core::config::ChannelRateLimitConfig;
 use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::Channel;
+use bizclaw_core::traits::provider::TokenStream;
 use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::format::{Dialect, format_message};
+use crate::rate_limit::RateLimiter;
 
 /// Discord channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -21,19 +31,88 @@ pub struct DiscordConfig {
     /// Gateway intents bitmask.
     #[serde(default = "default_intents")]
     pub intents: u64,
+    /// Channels the bot answers; empty means all.
+    #[serde(default)]
+    pub allowed_channel_ids: Vec<u64>,
+    /// REST API base (a test server in tests).
+    #[serde(default = "default_api_base")]
+    pub api_base: String,
+    /// Limits on sent messages.
+    #[serde(default)]
+    pub rate_limit: ChannelRateLimitConfig,
+    /// Least time between edits of a streamed reply.
+    #[serde(default = "default_edit_interval")]
+    pub edit_interval_ms: u64,
 }
 
 fn default_true() -> bool { true }
+fn default_edit_interval() -> u64 { 1000 }
 fn default_intents() -> u64 {
     // GUILDS | GUILD_MESSAGES | DIRECT_MESSAGES | MESSAGE_CONTENT
     (1 << 0) | (1 << 9) | (1 << 12) | (1 << 15)
 }
+fn default_api_base() -> String { "https://discord.com/api/v10".into() }
+
+impl Default for DiscordConfig {
+    fn default() -> Self {
+        Self {
+            bot_token: String::new(),
+            enabled: true,
+            intents: default_intents(),
+            allowed_channel_ids: Vec::new(),
+            api_base: default_api_base(),
+            rate_limit: ChannelRateLimitConfig::default(),
+            edit_interval_ms: default_edit_interval(),
+        }
+    }
+}
+
+impl From<&bizclaw_core::config::DiscordChannelConfig> for DiscordConfig {
+    fn from(cfg: &bizclaw_core::config::DiscordChannelConfig) -> Self {
+        Self {
+            bot_token: cfg.bot_token.clone(),
+            enabled: cfg.enabled,
+            intents: cfg.intents,
+            allowed_channel_ids: cfg.allowed_channel_ids.clone(),
+            rate_limit: cfg.rate_limit.clone(),
+            edit_interval_ms: cfg.presence.edit_interval_ms,
+            ..Self::default()
+        }
+    }
+}
+
+/// Gateway opcodes.
+mod op {
+    pub const DISPATCH: u64 = 0;
+    pub const HEARTBEAT: u64 = 1;
+    pub const IDENTIFY: u64 = 2;
+    pub const RESUME: u64 = 6;
+    pub const RECONNECT: u64 = 7;
+    pub const INVALID_SESSION: u64 = 9;
+    pub const HELLO: u64 = 10;
+    pub const HEARTBEAT_ACK: u64 = 11;
+}
+
+/// Close codes after which reconnecting can't help: authentication failed,
+/// invalid shard, sharding required, invalid API version, invalid or
+/// disallowed intents.
+const FATAL_CLOSE_CODES: &[u16] = &[4004, 4010, 4011, 4012, 4013, 4014];
+/// Close codes that end the session: reconnect with a fresh Identify.
+const SESSION_CLOSE_CODES: &[u16] = &[4007, 4009];
+/// First wait before reconnecting after a failed connection, doubled up to
+/// `MAX_RECONNECT_BACKOFF`.
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(60);
+/// Longest text one Discord message can hold.
+const MAX_MESSAGE_CHARS: usize = 2000;
 
 /// Discord Bot channel.
+#[derive(Clone)]
 pub struct DiscordChannel {
     config: DiscordConfig,
     client: reqwest::Client,
     connected: bool,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl DiscordChannel {
@@ -48,17 +127,51 @@ impl DiscordChannel {
             .build()
             .unwrap_or_default();
 
-        Self { config, client, connected: false }
+        let rate_limiter = RateLimiter::new(&config.rate_limit).map(Arc::new);
+        Self { config, client, connected: false, rate_limiter }
+    }
+
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/{path}", self.config.api_base.trim_end_matches('/'))
     }
 
-    /// Send a message to a channel.
+    /// Send a message to a channel, split into as many messages as
+    /// Discord's 2000-character limit needs, in order.
     pub async fn send_message(&self, channel_id: &str, content: &str) -> Result<()> {
-        let url = format!("https://discord.com/api/v10/channels/{channel_id}/messages");
+        for part in format_message(content, Dialect::Discord, MAX_MESSAGE_CHARS) {
+            self.post_message(channel_id, &part).await?;
+        }
+        Ok(())
+    }
+
+    /// Send a message and return its id.
+    async fn post_message(&self, channel_id: &str, content: &str) -> Result<String> {
+        let url = self.api_url(&format!("channels/{channel_id}/messages"));
         let body = serde_json::json!({ "content": content });
 
         let response = self.client.post(&url).json(&body).send().await
             .map_err(|e| BizClawError::Channel(format!("Discord send failed: {e}")))?;
 
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(BizClawError::Channel(format!("Discord {status}: {text}")));
+        }
+        let message: serde_json::Value = response.json().await
+            .map_err(|e| BizClawError::Channel(format!("Invalid response: {e}")))?;
+        message["id"].as_str()
+            .map(String::from)
+            .ok_or_else(|| crate::streaming::missing_id("Discord send"))
+    }
+
+    /// Replace the content of a sent message.
+    pub async fn edit_message(&self, channel_id: &str, message_id: &str, content: &str) -> Result<()> {
+        let url = self.api_url(&format!("channels/{channel_id}/messages/{message_id}"));
+        let body = serde_json::json!({ "content": content });
+
+        let response = self.client.patch(&url).json(&body).send().await
+            .map_err(|e| BizClawError::Channel(format!("Discord edit failed: {e}")))?;
+
         if !response.status().is_success() {
             let status = response.status();
             let text = response.text().await.unwrap_or_default();
@@ -67,9 +180,30 @@ impl DiscordChannel {
         Ok(())
     }
 
+    /// Delete a sent message.
+    pub async fn delete_message(&self, channel_id: &str, message_id: &str) -> Result<()> {
+        let url = self.api_url(&format!("channels/{channel_id}/messages/{message_id}"));
+        let response = self.client.delete(&url).send().await
+            .map_err(|e| BizClawError::Channel(format!("Discord delete failed: {e}")))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(BizClawError::Channel(format!("Discord {status}: {text}")));
+        }
+        Ok(())
+    }
+
+    /// Send a response as it is generated: a "Typing..." message edited with
+    /// the text so far, continued in new messages past 2000 characters.
+    pub async fn send_streaming(&self, channel_id: &str, tokens: TokenStream) -> Result<String> {
+        let edit_interval = Duration::from_millis(self.config.edit_interval_ms);
+        crate::streaming::relay(&DiscordReply { channel: self, channel_id }, tokens, edit_interval).await
+    }
+
     /// Send typing indicator.
     pub async fn send_typing_indicator(&self, channel_id: &str) -> Result<()> {
-        let url = format!("https://discord.com/api/v10/channels/{channel_id}/typing");
+        let url = self.api_url(&format!("channels/{channel_id}/typing"));
         let _ = self.client.post(&url).send().await;
         Ok(())
     }
@@ -77,7 +211,7 @@ impl DiscordChannel {
     /// Get current bot info.
     pub async fn get_me(&self) -> Result<DiscordUser> {
         let response = self.client
-            .get("https://discord.com/api/v10/users/@me")
+            .get(self.api_url("users/@me"))
             .send().await
             .map_err(|e| BizClawError::Channel(format!("getMe failed: {e}")))?;
         response.json().await
@@ -87,7 +221,7 @@ impl DiscordChannel {
     /// Get Gateway WebSocket URL.
     pub async fn get_gateway_url(&self) -> Result<String> {
         let response = self.client
-            .get("https://discord.com/api/v10/gateway/bot")
+            .get(self.api_url("gateway/bot"))
             .send().await
             .map_err(|e| BizClawError::Channel(format!("Gateway request failed: {e}")))?;
 
@@ -95,183 +229,331 @@ impl DiscordChannel {
             .map_err(|e| BizClawError::Channel(format!("Invalid gateway response: {e}")))?;
 
         body["url"].as_str()
-            .map(|s| format!("{s}/?v=10&encoding=json"))
+            .map(gateway_query)
             .ok_or_else(|| BizClawError::Channel("No gateway URL".into()))
     }
 
     /// Start Gateway WebSocket connection — returns a stream of IncomingMessages.
+    ///
+    /// Runs until the stream is dropped or Discord refuses the bot for good
+    /// (bad token, disallowed intents). Any other disconnect reconnects,
+    /// resuming the session where possible.
     pub fn start_gateway(self) -> DiscordGatewayStream {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
         tokio::spawn(async move {
-            let channel = self;
-            tracing::info!("Discord Gateway connecting...");
-
-            // Get gateway URL
-            let gateway_url = match channel.get_gateway_url().await {
-                Ok(url) => url,
-                Err(e) => {
-                    tracing::error!("Failed to get gateway URL: {e}");
-                    return;
-                }
-            };
-
-            // Connect WebSocket
-            let ws_result = tokio_tungstenite::connect_async(&gateway_url).await;
-            let (mut ws, _) = match ws_result {
-                Ok(conn) => conn,
-                Err(e) => {
-                    tracing::error!("Gateway WebSocket connect failed: {e}");
-                    return;
-                }
-            };
+            let mut session = GatewaySession::default();
+            let mut backoff = RECONNECT_BACKOFF;
 
-            tracing::info!("Discord Gateway connected");
+            loop {
+                let url = match session.resume_target() {
+                    Some(url) => url,
+                    None => match self.get_gateway_url().await {
+                        Ok(url) => url,
+                        Err(e) => {
+                            tracing::error!("Failed to get gateway URL: {e}");
+                            tokio::time::sleep(backoff).await;
+                            backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                            continue;
+                        }
+                    },
+                };
 
-            use futures::{SinkExt, StreamExt};
-            use tokio_tungstenite::tungstenite::Message as WsMsg;
+                match self.run_connection(&url, &mut session, &tx).await {
+                    Disconnect::Resume => {
+                        backoff = RECONNECT_BACKOFF;
+                        tracing::info!("Discord Gateway reconnecting to resume the session");
+                    }
+                    Disconnect::Reidentify => {
+                        backoff = RECONNECT_BACKOFF;
+                        session.reset();
+                        // Discord asks for a random 1–5s wait before identifying again.
+                        tokio::time::sleep(Duration::from_millis(1000 + rand::random::<u64>() % 4000)).await;
+                    }
+                    Disconnect::ConnectFailed(e) => {
+                        tracing::error!("Gateway WebSocket connect failed: {e}");
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                    }
+                    Disconnect::Fatal(reason) => {
+                        tracing::error!("Discord Gateway closed the connection for good: {reason}");
+                        break;
+                    }
+                    Disconnect::StreamClosed => {
+                        tracing::info!("Discord stream closed");
+                        break;
+                    }
+                }
+            }
 
-            let mut heartbeat_interval_ms: u64 = 41250;
-            let mut seq: Option<u64> = None;
-            let mut identified = false;
+            tracing::info!("Discord Gateway loop exited");
+        });
 
-            loop {
-                tokio::select! {
-                    msg = ws.next() => {
-                        match msg {
-                            Some(Ok(WsMsg::Text(text))) => {
-                                let payload: serde_json::Value = match serde_json::from_str(&text) {
-                                    Ok(v) => v,
-                                    Err(_) => continue,
-                                };
-
-                                let op = payload["op"].as_u64().unwrap_or(0);
-                                if let Some(s) = payload["s"].as_u64() {
-                                    seq = Some(s);
-                                }
+        DiscordGatewayStream { rx }
+    }
 
-                                match op {
-                                    // Hello — start heartbeating
-                                    10 => {
-                                        heartbeat_interval_ms = payload["d"]["heartbeat_interval"]
-                                            .as_u64().unwrap_or(41250);
-                                        tracing::debug!("Gateway Hello: heartbeat={}ms", heartbeat_interval_ms);
-
-                                        // Send Identify
-                                        if !identified {
-                                            let identify = serde_json::json!({
-                                                "op": 2,
-                                                "d": {
-                                                    "token": channel.config.bot_token,
-                                                    "intents": channel.config.intents,
-                                                    "properties": {
-                                                        "os": std::env::consts::OS,
-                                                        "browser": "bizclaw",
-                                                        "device": "bizclaw"
-                                                    }
-                                                }
-                                            });
-                                            let _ = ws.send(WsMsg::Text(identify.to_string())).await;
-                                            identified = true;
-                                        }
-                                    }
-                                    // Heartbeat ACK
-                                    11 => {
-                                        tracing::trace!("Heartbeat ACK");
-                                    }
-                                    // Dispatch (event)
-                                    0 => {
-                                        let event_name = payload["t"].as_str().unwrap_or("");
-                                        match event_name {
-                                            "READY" => {
-                                                let user = payload["d"]["user"]["username"]
-                                                    .as_str().unwrap_or("unknown");
-                                                tracing::info!("Discord Gateway READY as {user}");
-                                            }
-                                            "MESSAGE_CREATE" => {
-                                                let d = &payload["d"];
-                                                // Skip bot messages
-                                                if d["author"]["bot"].as_bool().unwrap_or(false) {
-                                                    continue;
-                                                }
-
-                                                let msg = IncomingMessage {
-                                                    channel: "discord".into(),
-                                                    thread_id: d["channel_id"].as_str()
-                                                        .unwrap_or("").into(),
-                                                    sender_id: d["author"]["id"].as_str()
-                                                        .unwrap_or("").into(),
-                                                    sender_name: d["author"]["username"].as_str()
-                                                        .map(String::from),
-                                                    content: d["content"].as_str()
-                                                        .unwrap_or("").into(),
-                                                    thread_type: if d["guild_id"].is_null() {
-                                                        ThreadType::Direct
-                                                    } else {
-                                                        ThreadType::Group
-                                                    },
-                                                    timestamp: chrono::Utc::now(),
-                                                    reply_to: d["referenced_message"]["id"]
-                                                        .as_str().map(String::from),
-                                                };
-
-                                                if tx.send(msg).is_err() {
-                                                    tracing::info!("Discord stream closed");
-                                                    return;
-                                                }
-                                            }
-                                            _ => {
-                                                tracing::trace!("Ignoring event: {event_name}");
-                                            }
-                                        }
-                                    }
-                                    // Reconnect
-                                    7 => {
-                                        tracing::warn!("Gateway requesting reconnect");
-                                        break;
-                                    }
-                                    // Invalid Session
-                                    9 => {
-                                        tracing::warn!("Invalid session, re-identifying");
-                                        identified = false;
-                                    }
-                                    _ => {}
-                                }
+    /// Run one Gateway connection: handshake, heartbeats and dispatches,
+    /// until it ends.
+    async fn run_connection(
+        &self,
+        url: &str,
+        session: &mut GatewaySession,
+        tx: &UnboundedSender<IncomingMessage>,
+    ) -> Disconnect {
+        use futures::{SinkExt, StreamExt};
+        use tokio::time::Instant;
+        use tokio_tungstenite::tungstenite::Message as WsMsg;
+
+        let mut ws = match tokio_tungstenite::connect_async(url).await {
+            Ok((ws, _)) => ws,
+            Err(e) => return Disconnect::ConnectFailed(e.to_string()),
+        };
+        tracing::info!("Discord Gateway connected");
+
+        // No heartbeats until Hello says how often.
+        let mut heartbeat_interval: Option<Duration> = None;
+        let mut next_heartbeat = Instant::now() + Duration::from_secs(3600);
+        let mut awaiting_ack = false;
+
+        loop {
+            let send = tokio::select! {
+                msg = ws.next() => match msg {
+                    Some(Ok(WsMsg::Text(text))) => {
+                        let Ok(payload) = serde_json::from_str::<serde_json::Value>(&text) else { continue };
+                        match session.handle(&payload, &self.config.allowed_channel_ids) {
+                            GatewayEvent::Hello(interval) => {
+                                tracing::debug!("Gateway Hello: heartbeat={}ms", interval.as_millis());
+                                heartbeat_interval = Some(interval);
+                                // The first beat is jittered so reconnecting bots don't beat in sync.
+                                next_heartbeat = Instant::now() + interval.mul_f64(rand::random::<f64>());
+                                Some(session.handshake(&self.config))
                             }
-                            Some(Ok(WsMsg::Close(_))) => {
-                                tracing::info!("Discord Gateway closed");
-                                break;
+                            GatewayEvent::HeartbeatRequested => Some(session.heartbeat()),
+                            GatewayEvent::HeartbeatAck => {
+                                awaiting_ack = false;
+                                None
                             }
-                            Some(Err(e)) => {
-                                tracing::error!("Gateway error: {e}");
-                                break;
+                            GatewayEvent::Message(msg) => {
+                                if tx.send(msg).is_err() {
+                                    return Disconnect::StreamClosed;
+                                }
+                                None
                             }
-                            None => break,
-                            _ => {}
+                            GatewayEvent::Disconnect(disconnect) => {
+                                let _ = ws.close(None).await;
+                                return disconnect;
+                            }
+                            GatewayEvent::None => None,
                         }
                     }
-                    // Heartbeat timer
-                    _ = tokio::time::sleep(tokio::time::Duration::from_millis(heartbeat_interval_ms)) => {
-                        let heartbeat = serde_json::json!({
-                            "op": 1,
-                            "d": seq,
-                        });
-                        if ws.send(WsMsg::Text(heartbeat.to_string())).await.is_err() {
-                            tracing::error!("Heartbeat send failed");
-                            break;
-                        }
-                        tracing::trace!("Heartbeat sent (seq={:?})", seq);
+                    Some(Ok(WsMsg::Close(frame))) => {
+                        let code = frame.map(|f| u16::from(f.code)).unwrap_or(1000);
+                        tracing::info!("Discord Gateway closed (code {code})");
+                        return Disconnect::for_close_code(code);
                     }
+                    Some(Err(e)) => {
+                        tracing::warn!("Gateway error: {e}");
+                        return Disconnect::Resume;
+                    }
+                    None => return Disconnect::Resume,
+                    _ => None,
+                },
+                _ = tokio::time::sleep_until(next_heartbeat) => {
+                    if awaiting_ack {
+                        // No ACK since the last beat: the connection is dead.
+                        tracing::warn!("Discord Gateway missed a heartbeat ACK");
+                        return Disconnect::Resume;
+                    }
+                    awaiting_ack = true;
+                    next_heartbeat = Instant::now() + heartbeat_interval.unwrap_or(Duration::from_secs(3600));
+                    tracing::trace!("Heartbeat sent (seq={:?})", session.seq);
+                    Some(session.heartbeat())
                 }
+            };
+
+            if let Some(payload) = send
+                && let Err(e) = ws.send(WsMsg::Text(payload.to_string())).await
+            {
+                tracing::warn!("Gateway send failed: {e}");
+                return Disconnect::Resume;
             }
+        }
+    }
+}
 
-            tracing::info!("Discord Gateway loop exited");
-        });
+/// The Gateway URL with the API version and encoding the bot speaks.
+fn gateway_query(url: &str) -> String {
+    format!("{}/?v=10&encoding=json", url.trim_end_matches('/'))
+}
 
-        DiscordGatewayStream { rx }
+/// How a Gateway connection ended, and what to do next.
+#[derive(Debug, PartialEq)]
+enum Disconnect {
+    /// Reconnect and resume the session.
+    Resume,
+    /// The session is gone: reconnect and identify again.
+    Reidentify,
+    /// The WebSocket couldn't be opened.
+    ConnectFailed(String),
+    /// Discord won't accept this bot; stop.
+    Fatal(String),
+    /// Nobody reads the messages any more; stop.
+    StreamClosed,
+}
+
+impl Disconnect {
+    fn for_close_code(code: u16) -> Self {
+        if FATAL_CLOSE_CODES.contains(&code) {
+            Disconnect::Fatal(format!("close code {code}"))
+        } else if SESSION_CLOSE_CODES.contains(&code) {
+            Disconnect::Reidentify
+        } else {
+            Disconnect::Resume
+        }
+    }
+}
+
+/// What one Gateway payload means for the connection.
+#[derive(Debug)]
+enum GatewayEvent {
+    /// Start heartbeating at this interval, then identify or resume.
+    Hello(Duration),
+    HeartbeatRequested,
+    HeartbeatAck,
+    Message(IncomingMessage),
+    Disconnect(Disconnect),
+    None,
+}
+
+/// Gateway session state, kept across connections so a dropped one can be
+/// resumed.
+#[derive(Debug, Default)]
+struct GatewaySession {
+    session_id: Option<String>,
+    resume_url: Option<String>,
+    /// Last dispatch sequence number, sent with heartbeats and Resume.
+    seq: Option<u64>,
+}
+
+impl GatewaySession {
+    /// The URL to reconnect to when the session can be resumed.
+    fn resume_target(&self) -> Option<String> {
+        self.session_id.as_ref()?;
+        self.resume_url.as_deref().map(gateway_query)
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Resume the session if there is one, else Identify.
+    fn handshake(&self, config: &DiscordConfig) -> serde_json::Value {
+        match &self.session_id {
+            Some(session_id) => serde_json::json!({
+                "op": op::RESUME,
+                "d": { "token": config.bot_token, "session_id": session_id, "seq": self.seq }
+            }),
+            None => serde_json::json!({
+                "op": op::IDENTIFY,
+                "d": {
+                    "token": config.bot_token,
+                    "intents": config.intents,
+                    "properties": {
+                        "os": std::env::consts::OS,
+                        "browser": "bizclaw",
+                        "device": "bizclaw"
+                    }
+                }
+            }),
+        }
+    }
+
+    fn heartbeat(&self) -> serde_json::Value {
+        serde_json::json!({ "op": op::HEARTBEAT, "d": self.seq })
+    }
+
+    /// Update the session from a payload and say what it asks for.
+    fn handle(&mut self, payload: &serde_json::Value, allowed_channel_ids: &[u64]) -> GatewayEvent {
+        if let Some(s) = payload["s"].as_u64() {
+            self.seq = Some(s);
+        }
+        let d = &payload["d"];
+
+        match payload["op"].as_u64() {
+            Some(op::HELLO) => GatewayEvent::Hello(Duration::from_millis(d["heartbeat_interval"].as_u64().unwrap_or(41250))),
+            Some(op::HEARTBEAT) => GatewayEvent::HeartbeatRequested,
+            Some(op::HEARTBEAT_ACK) => {
+                tracing::trace!("Heartbeat ACK");
+                GatewayEvent::HeartbeatAck
+            }
+            Some(op::RECONNECT) => {
+                tracing::info!("Gateway requesting reconnect");
+                GatewayEvent::Disconnect(Disconnect::Resume)
+            }
+            Some(op::INVALID_SESSION) => {
+                // `d` says whether the session may still be resumed.
+                if d.as_bool().unwrap_or(false) {
+                    tracing::warn!("Invalid session, resuming");
+                    GatewayEvent::Disconnect(Disconnect::Resume)
+                } else {
+                    tracing::warn!("Invalid session, re-identifying");
+                    GatewayEvent::Disconnect(Disconnect::Reidentify)
+                }
+            }
+            Some(op::DISPATCH) => match payload["t"].as_str().unwrap_or("") {
+                "READY" => {
+                    self.session_id = d["session_id"].as_str().map(String::from);
+                    self.resume_url = d["resume_gateway_url"].as_str().map(String::from);
+                    let user = d["user"]["username"].as_str().unwrap_or("unknown");
+                    tracing::info!("Discord Gateway READY as {user}");
+                    GatewayEvent::None
+                }
+                "RESUMED" => {
+                    tracing::info!("Discord Gateway session resumed");
+                    GatewayEvent::None
+                }
+                "MESSAGE_CREATE" => match incoming_message(d, allowed_channel_ids) {
+                    Some(msg) => GatewayEvent::Message(msg),
+                    None => GatewayEvent::None,
+                },
+                event_name => {
+                    tracing::trace!("Ignoring event: {event_name}");
+                    GatewayEvent::None
+                }
+            },
+            _ => GatewayEvent::None,
+        }
     }
 }
 
+/// A MESSAGE_CREATE the bot should answer: not from a bot, in an allowed
+/// channel.
+fn incoming_message(d: &serde_json::Value, allowed_channel_ids: &[u64]) -> Option<IncomingMessage> {
+    if d["author"]["bot"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    let channel_id = d["channel_id"].as_str().unwrap_or("");
+    if !allowed_channel_ids.is_empty()
+        && !channel_id.parse().is_ok_and(|id: u64| allowed_channel_ids.contains(&id))
+    {
+        tracing::debug!("Discord: ignoring message in channel {channel_id} (not in allowed_channel_ids)");
+        return None;
+    }
+
+    Some(IncomingMessage {
+        channel: "discord".into(),
+        thread_id: channel_id.into(),
+        sender_id: d["author"]["id"].as_str().unwrap_or("").into(),
+        sender_name: d["author"]["username"].as_str().map(String::from),
+        content: d["content"].as_str().unwrap_or("").into(),
+        thread_type: if d["guild_id"].is_null() { ThreadType::Direct } else { ThreadType::Group },
+        timestamp: chrono::Utc::now(),
+        reply_to: d["referenced_message"]["id"].as_str().map(String::from),
+        attachments: Vec::new(),
+        message_id: d["id"].as_str().map(String::from),
+    })
+}
+
 /// Stream of incoming Discord messages from Gateway.
 pub struct DiscordGatewayStream {
     rx: tokio::sync::mpsc::UnboundedReceiver<IncomingMessage>,
@@ -287,6 +569,29 @@ impl Stream for DiscordGatewayStream {
 
 impl Unpin for DiscordGatewayStream {}
 
+/// Messages of one streamed reply in a Discord channel.
+struct DiscordReply<'a> {
+    channel: &'a DiscordChannel,
+    channel_id: &'a str,
+}
+
+#[async_trait]
+impl crate::streaming::EditableChat for DiscordReply<'_> {
+    const MAX_CHARS: usize = MAX_MESSAGE_CHARS;
+
+    async fn post(&self, text: &str) -> Result<String> {
+        self.channel.post_message(self.channel_id, text).await
+    }
+
+    async fn edit(&self, message_id: &str, text: &str) -> Result<()> {
+        self.channel.edit_message(self.channel_id, message_id, text).await
+    }
+
+    async fn delete(&self, message_id: &str) -> Result<()> {
+        self.channel.delete_message(self.channel_id, message_id).await
+    }
+}
+
 #[async_trait]
 impl Channel for DiscordChannel {
     fn name(&self) -> &str { "discord" }
@@ -306,15 +611,25 @@ impl Channel for DiscordChannel {
     fn is_connected(&self) -> bool { self.connected }
 
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire("discord", &message.thread_id).await?;
+        }
         self.send_message(&message.thread_id, &message.content).await
     }
 
+    async fn send_streaming(&self, thread_id: &str, _thread_type: ThreadType, tokens: TokenStream) -> Result<()> {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire("discord", thread_id).await?;
+        }
+        DiscordChannel::send_streaming(self, thread_id, tokens).await.map(|_| ())
+    }
+
     async fn send_typing(&self, thread_id: &str) -> Result<()> {
         self.send_typing_indicator(thread_id).await
     }
 
     async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
-        Ok(Box::new(futures::stream::pending()))
+        Ok(Box::new(self.clone().start_gateway()))
     }
 }
 
@@ -336,3 +651,157 @@ pub struct DiscordMessage {
     pub content: String,
     pub guild_id: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::Message as WsMsg;
+
+    fn message_create(channel_id: &str, bot: bool) -> serde_json::Value {
+        serde_json::json!({
+            "channel_id": channel_id,
+            "guild_id": "1",
+            "content": "hi",
+            "author": { "id": "42", "username": "an", "bot": bot }
+        })
+    }
+
+    #[test]
+    fn test_incoming_message_filters() {
+        let msg = incoming_message(&message_create("7", false), &[]).unwrap();
+        assert_eq!(msg.thread_id, "7");
+        assert_eq!(msg.thread_type, ThreadType::Group);
+        assert!(incoming_message(&message_create("7", true), &[]).is_none());
+        assert!(incoming_message(&message_create("7", false), &[7, 8]).is_some());
+        assert!(incoming_message(&message_create("5", false), &[7, 8]).is_none());
+    }
+
+    #[test]
+    fn test_session_resume_and_invalidation() {
+        let config = DiscordConfig { bot_token: "t".into(), ..Default::default() };
+        let mut session = GatewaySession::default();
+        assert_eq!(session.handshake(&config)["op"], op::IDENTIFY);
+        assert_eq!(session.handshake(&config)["d"]["intents"], default_intents());
+
+        session.handle(&serde_json::json!({
+            "op": 0, "t": "READY", "s": 1,
+            "d": { "session_id": "abc", "resume_gateway_url": "wss://resume.discord.gg", "user": { "username": "bot" } }
+        }), &[]);
+        session.handle(&serde_json::json!({ "op": 0, "t": "TYPING_START", "s": 5, "d": {} }), &[]);
+        assert_eq!(session.resume_target().as_deref(), Some("wss://resume.discord.gg/?v=10&encoding=json"));
+        let resume = session.handshake(&config);
+        assert_eq!(resume["op"], op::RESUME);
+        assert_eq!(resume["d"]["session_id"], "abc");
+        assert_eq!(resume["d"]["seq"], 5);
+        assert_eq!(session.heartbeat()["d"], 5);
+
+        assert!(matches!(
+            session.handle(&serde_json::json!({ "op": 7, "d": null }), &[]),
+            GatewayEvent::Disconnect(Disconnect::Resume)
+        ));
+        assert!(matches!(
+            session.handle(&serde_json::json!({ "op": 9, "d": false }), &[]),
+            GatewayEvent::Disconnect(Disconnect::Reidentify)
+        ));
+        session.reset();
+        assert!(session.resume_target().is_none());
+    }
+
+    #[test]
+    fn test_close_codes() {
+        assert_eq!(Disconnect::for_close_code(1001), Disconnect::Resume);
+        assert_eq!(Disconnect::for_close_code(4000), Disconnect::Resume);
+        assert_eq!(Disconnect::for_close_code(4009), Disconnect::Reidentify);
+        assert!(matches!(Disconnect::for_close_code(4014), Disconnect::Fatal(_)));
+    }
+
+    /// Read the next JSON payload the bot sends, skipping heartbeats.
+    async fn next_payload<S>(ws: &mut S) -> serde_json::Value
+    where
+        S: futures::Stream<Item = std::result::Result<WsMsg, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        loop {
+            if let Some(Ok(WsMsg::Text(text))) = ws.next().await {
+                let payload: serde_json::Value = serde_json::from_str(&text).unwrap();
+                if payload["op"] != op::HEARTBEAT {
+                    return payload;
+                }
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gateway_identifies_and_resumes() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let ws_listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}", ws_listener.local_addr().unwrap());
+        // REST API: only `gateway/bot` is called.
+        let api = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_base = format!("http://{}", api.local_addr().unwrap());
+        let body = serde_json::json!({ "url": ws_url }).to_string();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = api.accept().await {
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+
+        // Gateway: the first connection identifies and drops after two
+        // messages; the second must resume.
+        let (handshakes_tx, mut handshakes) = tokio::sync::mpsc::unbounded_channel();
+        let resume_url = ws_url.clone();
+        tokio::spawn(async move {
+            let hello = serde_json::json!({ "op": 10, "d": { "heartbeat_interval": 45000 } }).to_string();
+
+            let (socket, _) = ws_listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            ws.send(WsMsg::Text(hello.clone())).await.unwrap();
+            handshakes_tx.send(next_payload(&mut ws).await).unwrap();
+            for (s, payload) in [
+                serde_json::json!({ "op": 0, "t": "READY", "d": {
+                    "session_id": "s1", "resume_gateway_url": resume_url, "user": { "username": "bot" }
+                }}),
+                serde_json::json!({ "op": 0, "t": "MESSAGE_CREATE", "d": message_create("5", false) }),
+                serde_json::json!({ "op": 0, "t": "MESSAGE_CREATE", "d": message_create("7", false) }),
+            ].into_iter().enumerate() {
+                let mut payload = payload;
+                payload["s"] = serde_json::json!(s + 1);
+                ws.send(WsMsg::Text(payload.to_string())).await.unwrap();
+            }
+            drop(ws);
+
+            let (socket, _) = ws_listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            ws.send(WsMsg::Text(hello)).await.unwrap();
+            handshakes_tx.send(next_payload(&mut ws).await).unwrap();
+            std::future::pending::<()>().await;
+        });
+
+        let channel = DiscordChannel::new(DiscordConfig {
+            bot_token: "t".into(),
+            allowed_channel_ids: vec![7],
+            api_base,
+            ..Default::default()
+        });
+        let mut messages = channel.start_gateway();
+        let timeout = Duration::from_secs(10);
+
+        let identify = tokio::time::timeout(timeout, handshakes.recv()).await.unwrap().unwrap();
+        assert_eq!(identify["op"], op::IDENTIFY);
+        assert_eq!(identify["d"]["intents"], default_intents());
+        let msg = tokio::time::timeout(timeout, messages.next()).await.unwrap().unwrap();
+        assert_eq!(msg.thread_id, "7");
+
+        let resume = tokio::time::timeout(timeout, handshakes.recv()).await.unwrap().unwrap();
+        assert_eq!(resume["op"], op::RESUME);
+        assert_eq!(resume["d"]["session_id"], "s1");
+        assert_eq!(resume["d"]["seq"], 3);
+    }
+}
@@ -2,16 +2,32 @@
 //!
 //! Reads emails via IMAP (sync, in spawn_blocking), routes them to the AI agent,
 //! and sends replies via SMTP (async lettre). Supports Gmail, Outlook, custom servers.
+//!
+//! Each email thread is one conversation: replies go back to the sender with
+//! `In-Reply-To`/`References` set, so mail clients and helpdesks keep them in
+//! the original thread.
+//!
+//! New mail is waited for with IMAP IDLE where the server has it. Only
+//! `allowed_senders` are answered, quoted history and signatures are cut
+//! from the text the agent sees, and attachments are saved for it. The last
+//! UID handled is kept on disk, so a restart doesn't answer old mail again.
+//! Logins use the password or an OAuth2 token (XOAUTH2).
 
 use async_trait::async_trait;
+use bizclaw_core::config::{BizClawConfig, ChannelRateLimitConfig, EmailOAuth2Config};
 use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::Channel;
-use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
+use bizclaw_core::types::{Attachment, AttachmentKind, IncomingMessage, OutgoingMessage, ThreadType};
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::pin::Pin;
 use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::rate_limit::RateLimiter;
 
 /// Email channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -36,6 +52,30 @@ pub struct EmailConfig {
     pub mark_as_read: bool,
     #[serde(default = "default_true")]
     pub smtp_enabled: bool,
+    /// "starttls", "tls" (implicit TLS), or "none" for a local relay.
+    #[serde(default = "default_smtp_security")]
+    pub smtp_security: String,
+    /// Limits on sent messages.
+    #[serde(default)]
+    pub rate_limit: ChannelRateLimitConfig,
+    /// Use IMAP IDLE when the server supports it.
+    #[serde(default = "default_true")]
+    pub idle: bool,
+    /// XOAUTH2 credentials, used instead of `password`.
+    #[serde(default)]
+    pub oauth2: Option<EmailOAuth2Config>,
+    /// Addresses or `@domain`s answered; everyone when empty.
+    #[serde(default)]
+    pub allowed_senders: Vec<String>,
+    /// Largest attachment saved, in MB; 0 ignores attachments.
+    #[serde(default = "default_max_attachment_mb")]
+    pub max_attachment_mb: u64,
+    /// Attachments are saved under `<attachments_dir>/email/<uid>/`.
+    #[serde(default)]
+    pub attachments_dir: Option<PathBuf>,
+    /// File keeping the last UID handled; in memory only when `None`.
+    #[serde(default)]
+    pub state_path: Option<PathBuf>,
 }
 
 fn default_imap_port() -> u16 { 993 }
@@ -43,6 +83,10 @@ fn default_smtp_port() -> u16 { 587 }
 fn default_mailbox() -> String { "INBOX".into() }
 fn default_poll_interval() -> u64 { 30 }
 fn default_true() -> bool { true }
+fn default_smtp_security() -> String { "starttls".into() }
+fn default_max_attachment_mb() -> u64 { 10 }
+/// Longest IDLE before reconnecting; servers drop idle clients after 30 minutes.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(25 * 60);
 
 impl Default for EmailConfig {
     fn default() -> Self {
@@ -59,6 +103,43 @@ impl Default for EmailConfig {
             unread_only: true,
             mark_as_read: true,
             smtp_enabled: true,
+            smtp_security: default_smtp_security(),
+            rate_limit: ChannelRateLimitConfig::default(),
+            idle: true,
+            oauth2: None,
+            allowed_senders: Vec::new(),
+            max_attachment_mb: default_max_attachment_mb(),
+            attachments_dir: None,
+            state_path: None,
+        }
+    }
+}
+
+impl From<&bizclaw_core::config::EmailChannelConfig> for EmailConfig {
+    fn from(cfg: &bizclaw_core::config::EmailChannelConfig) -> Self {
+        Self {
+            imap_host: cfg.imap_host.clone(),
+            imap_port: cfg.imap_port,
+            smtp_host: cfg.smtp_host.clone(),
+            smtp_port: cfg.smtp_port,
+            email: cfg.email.clone(),
+            password: cfg.password.clone(),
+            display_name: cfg.display_name.clone(),
+            mailbox: cfg.mailbox.clone(),
+            poll_interval_secs: cfg.poll_interval_secs,
+            smtp_security: cfg.smtp_security.clone(),
+            rate_limit: cfg.rate_limit.clone(),
+            idle: cfg.idle,
+            oauth2: cfg.oauth2.clone(),
+            allowed_senders: cfg.allowed_senders.clone(),
+            max_attachment_mb: cfg.max_attachment_mb,
+            attachments_dir: Some(
+                cfg.attachments_dir.as_deref()
+                    .map(|dir| PathBuf::from(shellexpand::tilde(dir).as_ref()))
+                    .unwrap_or_else(|| BizClawConfig::data_dir().join("media")),
+            ),
+            state_path: Some(BizClawConfig::data_dir().join("email_uids.json")),
+            ..Self::default()
         }
     }
 }
@@ -72,40 +153,274 @@ pub struct ParsedEmail {
     pub subject: String,
     pub body_text: String,
     pub message_id: Option<String>,
+    pub in_reply_to: Option<String>,
+    /// Message-IDs of the earlier messages in the thread, oldest first.
+    pub references: Vec<String>,
+    /// Sent by an autoresponder or mailing list (`Auto-Submitted`,
+    /// `Precedence: bulk`); never answered, to avoid mail loops.
+    pub auto_submitted: bool,
+    pub attachments: Vec<EmailAttachment>,
+}
+
+/// A file attached to an email.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailAttachment {
+    pub file_name: Option<String>,
+    pub mime_type: Option<String>,
+    pub data: Vec<u8>,
+}
+
+impl ParsedEmail {
+    /// The Message-ID that started the thread, which identifies the
+    /// conversation for every later reply in it.
+    pub fn thread_key(&self) -> String {
+        self.references.first()
+            .or(self.in_reply_to.as_ref())
+            .or(self.message_id.as_ref())
+            .cloned()
+            .unwrap_or_else(|| self.from.clone())
+    }
+
+    fn to_incoming(&self) -> IncomingMessage {
+        let from = match &self.from_name {
+            Some(name) => format!("{name} <{}>", self.from),
+            None => self.from.clone(),
+        };
+        IncomingMessage {
+            channel: "email".into(),
+            thread_id: self.thread_key(),
+            sender_id: self.from.clone(),
+            sender_name: self.from_name.clone(),
+            content: format!("📧 From: {from}\nSubject: {}\n\n{}", self.subject, self.body_text),
+            thread_type: ThreadType::Direct,
+            timestamp: chrono::Utc::now(),
+            reply_to: self.message_id.clone(),
+            attachments: Vec::new(),
+            message_id: self.message_id.clone(),
+        }
+    }
+
+    /// Headers for answering this email in its thread.
+    fn reply_context(&self) -> ReplyContext {
+        let mut references = self.references.clone();
+        if references.is_empty() && let Some(parent) = &self.in_reply_to {
+            references.push(parent.clone());
+        }
+        references.extend(self.message_id.clone());
+        ReplyContext {
+            to: self.from.clone(),
+            subject: reply_subject(&self.subject),
+            in_reply_to: self.message_id.clone(),
+            references,
+        }
+    }
+}
+
+/// Where a reply goes and the headers that keep it in its thread.
+#[derive(Debug, Clone, PartialEq)]
+struct ReplyContext {
+    to: String,
+    subject: String,
+    in_reply_to: Option<String>,
+    references: Vec<String>,
+}
+
+fn reply_subject(subject: &str) -> String {
+    if subject.get(..3).is_some_and(|p| p.eq_ignore_ascii_case("re:")) {
+        subject.to_string()
+    } else {
+        format!("Re: {subject}")
+    }
+}
+
+/// Credentials for IMAP and SMTP.
+enum Login {
+    Password(String),
+    /// An OAuth2 access token, for XOAUTH2.
+    OAuth2(String),
+}
+
+/// SASL XOAUTH2 for IMAP `AUTHENTICATE`.
+struct XOAuth2<'a> {
+    user: &'a str,
+    token: &'a str,
+}
+
+impl imap::Authenticator for XOAuth2<'_> {
+    type Response = String;
+
+    fn process(&self, _challenge: &[u8]) -> String {
+        format!("user={}\x01auth=Bearer {}\x01\x01", self.user, self.token)
+    }
+}
+
+/// Access tokens for XOAUTH2: the fixed one, or one from the refresh token,
+/// kept until shortly before it expires.
+struct OAuth2Tokens {
+    config: EmailOAuth2Config,
+    client: reqwest::Client,
+    cached: tokio::sync::Mutex<Option<(String, Instant)>>,
+}
+
+impl OAuth2Tokens {
+    async fn access_token(&self) -> Result<String> {
+        let Some(refresh_token) = &self.config.refresh_token else {
+            return self.config.access_token.clone()
+                .ok_or_else(|| BizClawError::AuthFailed("Email OAuth2: no access_token or refresh_token".into()));
+        };
+        let mut cached = self.cached.lock().await;
+        if let Some((token, expires)) = cached.as_ref()
+            && Instant::now() < *expires
+        {
+            return Ok(token.clone());
+        }
+
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+            #[serde(default = "default_token_lifetime")]
+            expires_in: u64,
+        }
+        fn default_token_lifetime() -> u64 { 3600 }
+
+        let resp = self.client.post(&self.config.token_url)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token.as_str()),
+                ("client_id", self.config.client_id.as_str()),
+                ("client_secret", self.config.client_secret.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| BizClawError::AuthFailed(format!("Email OAuth2 refresh: {e}")))?;
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let body = resp.text().await.unwrap_or_default();
+            return Err(BizClawError::AuthFailed(format!("Email OAuth2 refresh: HTTP {status}: {body}")));
+        }
+        let token: TokenResponse = resp.json().await
+            .map_err(|e| BizClawError::AuthFailed(format!("Email OAuth2 refresh: {e}")))?;
+        // Renew a minute early so a token doesn't expire mid-session.
+        let expires = Instant::now() + Duration::from_secs(token.expires_in.saturating_sub(60));
+        *cached = Some((token.access_token.clone(), expires));
+        Ok(token.access_token)
+    }
+}
+
+/// The last UID handled in each mailbox, with the mailbox's UIDVALIDITY,
+/// saved as JSON so restarts pick up where they left off.
+struct SeenUids {
+    path: Option<PathBuf>,
+    /// This account and mailbox, the key in the file.
+    key: String,
+    state: Mutex<MailboxUids>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+struct MailboxUids {
+    uid_validity: Option<u32>,
+    last_uid: u32,
+}
+
+impl SeenUids {
+    fn open(path: Option<PathBuf>, key: String) -> Self {
+        let state = path.as_deref()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|content| serde_json::from_str::<HashMap<String, MailboxUids>>(&content).ok())
+            .and_then(|mailboxes| mailboxes.get(&key).copied())
+            .unwrap_or_default();
+        Self { path, key, state: Mutex::new(state) }
+    }
+
+    /// The last UID handled, for a mailbox whose UIDVALIDITY is
+    /// `uid_validity`; 0 when it changed, since the old UIDs then mean nothing.
+    fn last_uid(&self, uid_validity: Option<u32>) -> u32 {
+        let state = self.state.lock().unwrap();
+        if state.uid_validity.is_some() && state.uid_validity != uid_validity { 0 } else { state.last_uid }
+    }
+
+    fn set(&self, uid_validity: Option<u32>, last_uid: u32) {
+        let state = MailboxUids { uid_validity, last_uid };
+        *self.state.lock().unwrap() = state;
+        let Some(path) = &self.path else { return };
+        let mut mailboxes: HashMap<String, MailboxUids> = std::fs::read_to_string(path).ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        mailboxes.insert(self.key.clone(), state);
+        let saved = path.parent().map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(path, serde_json::to_string_pretty(&mailboxes).unwrap_or_default()));
+        if let Err(e) = saved {
+            tracing::warn!("Could not save email UIDs to {}: {e}", path.display());
+        }
+    }
 }
 
 /// Email channel — IMAP reading + SMTP sending.
+#[derive(Clone)]
 pub struct EmailChannel {
     config: EmailConfig,
     connected: bool,
-    last_seen_uid: Arc<Mutex<u32>>,
+    seen: Arc<SeenUids>,
+    oauth2: Option<Arc<OAuth2Tokens>>,
+    /// Reply headers of each thread seen so far, by `thread_id`.
+    threads: Arc<Mutex<HashMap<String, ReplyContext>>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl EmailChannel {
     pub fn new(config: EmailConfig) -> Self {
+        let key = format!("{}/{}", config.email.to_lowercase(), config.mailbox);
         Self {
+            rate_limiter: RateLimiter::new(&config.rate_limit).map(Arc::new),
+            seen: Arc::new(SeenUids::open(config.state_path.clone(), key)),
+            oauth2: config.oauth2.clone().map(|config| Arc::new(OAuth2Tokens {
+                config,
+                client: reqwest::Client::new(),
+                cached: tokio::sync::Mutex::new(None),
+            })),
             config,
             connected: false,
-            last_seen_uid: Arc::new(Mutex::new(0)),
+            threads: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The password, or a current OAuth2 access token.
+    async fn login(&self) -> Result<Login> {
+        match &self.oauth2 {
+            Some(tokens) => Ok(Login::OAuth2(tokens.access_token().await?)),
+            None => Ok(Login::Password(self.config.password.clone())),
         }
     }
 
     /// Fetch unread emails (sync IMAP inside spawn_blocking).
     pub async fn fetch_unread(&self) -> Result<Vec<ParsedEmail>> {
-        let host = self.config.imap_host.clone();
-        let port = self.config.imap_port;
-        let email = self.config.email.clone();
-        let password = self.config.password.clone();
-        let mailbox = self.config.mailbox.clone();
-        let unread_only = self.config.unread_only;
-        let mark_as_read = self.config.mark_as_read;
-        let last_seen_uid = self.last_seen_uid.clone();
+        let login = self.login().await?;
+        let config = self.config.clone();
+        let seen = self.seen.clone();
+        tokio::task::spawn_blocking(move || imap_fetch_sync(&config, &login, &seen))
+            .await
+            .map_err(|e| BizClawError::Channel(format!("IMAP task panicked: {e}")))?
+    }
 
+    /// Wait with IMAP IDLE until the mailbox changes (or `IDLE_TIMEOUT`
+    /// passes). `Ok(false)` if the server doesn't support IDLE.
+    async fn wait_for_mail(&self) -> Result<bool> {
+        let login = self.login().await?;
+        let config = self.config.clone();
         tokio::task::spawn_blocking(move || {
-            imap_fetch_sync(
-                &host, port, &email, &password, &mailbox,
-                unread_only, mark_as_read, &last_seen_uid,
-            )
+            let mut session = imap_session(&config, &login)?;
+            let supported = session.capabilities()
+                .map_err(|e| BizClawError::Channel(format!("IMAP capabilities: {e}")))?
+                .has_str("IDLE");
+            if supported {
+                session.select(&config.mailbox)
+                    .map_err(|e| BizClawError::Channel(format!("Select: {e}")))?;
+                session.idle()
+                    .and_then(|idle| idle.wait_with_timeout(IDLE_TIMEOUT))
+                    .map_err(|e| BizClawError::Channel(format!("IMAP IDLE: {e}")))?;
+            }
+            session.logout().ok();
+            Ok(supported)
         })
         .await
         .map_err(|e| BizClawError::Channel(format!("IMAP task panicked: {e}")))?
@@ -119,87 +434,166 @@ impl EmailChannel {
         body: &str,
         in_reply_to: Option<&str>,
     ) -> Result<()> {
-        use lettre::{
-            message::header::ContentType,
-            transport::smtp::authentication::Credentials,
-            AsyncSmtpTransport, AsyncTransport, Message as LettreMessage,
-            message::Mailbox,
+        let reply = ReplyContext {
+            to: to.to_string(),
+            subject: subject.to_string(),
+            in_reply_to: in_reply_to.map(String::from),
+            references: in_reply_to.map(String::from).into_iter().collect(),
         };
+        self.deliver(self.compose(&reply, body)?).await?;
+        tracing::info!("📤 Email sent to: {to}");
+        Ok(())
+    }
+
+    /// Build a plain-text message, threaded under `reply.in_reply_to`.
+    fn compose(&self, reply: &ReplyContext, body: &str) -> Result<lettre::Message> {
+        use lettre::message::{Mailbox, header::ContentType};
 
         let from_name = self.config.display_name.as_deref().unwrap_or("BizClaw AI");
         let from_mailbox: Mailbox = format!("{from_name} <{}>", self.config.email)
             .parse()
             .map_err(|e| BizClawError::Channel(format!("Invalid from: {e}")))?;
 
-        let to_mailbox: Mailbox = to.parse()
+        let to_mailbox: Mailbox = reply.to.parse()
             .map_err(|e| BizClawError::Channel(format!("Invalid to: {e}")))?;
 
-        let mut builder = LettreMessage::builder()
+        let mut builder = lettre::Message::builder()
             .from(from_mailbox)
             .to(to_mailbox)
-            .subject(subject)
+            .subject(&reply.subject)
             .header(ContentType::TEXT_PLAIN);
 
-        if let Some(reply_id) = in_reply_to {
-            builder = builder.in_reply_to(reply_id.to_string());
+        if let Some(parent) = &reply.in_reply_to {
+            builder = builder.in_reply_to(format!("<{parent}>"));
+        }
+        if !reply.references.is_empty() {
+            let ids: Vec<String> = reply.references.iter().map(|id| format!("<{id}>")).collect();
+            builder = builder.references(ids.join(" "));
         }
 
-        let email = builder
+        builder
             .body(body.to_string())
-            .map_err(|e| BizClawError::Channel(format!("Build email: {e}")))?;
+            .map_err(|e| BizClawError::Channel(format!("Build email: {e}")))
+    }
 
-        let creds = Credentials::new(
-            self.config.email.clone(),
-            self.config.password.clone(),
-        );
+    async fn deliver(&self, email: lettre::Message) -> Result<()> {
+        use lettre::{AsyncSmtpTransport, AsyncTransport};
+        use lettre::transport::smtp::authentication::{Credentials, DEFAULT_MECHANISMS, Mechanism};
 
-        let mailer = AsyncSmtpTransport::<lettre::Tokio1Executor>::starttls_relay(
-            &self.config.smtp_host,
-        )
-        .map_err(|e| BizClawError::Channel(format!("SMTP relay: {e}")))?
-        .port(self.config.smtp_port)
-        .credentials(creds)
-        .build();
+        let (secret, mechanisms) = match self.login().await? {
+            Login::Password(password) => (password, DEFAULT_MECHANISMS.to_vec()),
+            Login::OAuth2(token) => (token, vec![Mechanism::Xoauth2]),
+        };
+        let creds = Credentials::new(self.config.email.clone(), secret);
+
+        let relay = match self.config.smtp_security.as_str() {
+            "tls" => AsyncSmtpTransport::<lettre::Tokio1Executor>::relay(&self.config.smtp_host),
+            "none" => Ok(AsyncSmtpTransport::<lettre::Tokio1Executor>::builder_dangerous(&self.config.smtp_host)),
+            _ => AsyncSmtpTransport::<lettre::Tokio1Executor>::starttls_relay(&self.config.smtp_host),
+        };
+        let mailer = relay
+            .map_err(|e| BizClawError::Channel(format!("SMTP relay: {e}")))?
+            .port(self.config.smtp_port)
+            .credentials(creds)
+            .authentication(mechanisms)
+            .build();
 
         mailer.send(email).await
             .map_err(|e| BizClawError::Channel(format!("SMTP send: {e}")))?;
-
-        tracing::info!("📤 Email sent to: {to}");
         Ok(())
     }
 
+    /// Whether to hand `email` to the agent: not our own mail, not an
+    /// autoresponder's, and from an allowed sender.
+    fn should_answer(&self, email: &ParsedEmail) -> bool {
+        !email.auto_submitted
+            && !email.from.eq_ignore_ascii_case(&self.config.email)
+            && sender_allowed(&self.config.allowed_senders, &email.from)
+    }
+
+    /// Save the attachments of `email` and list them on `incoming`; ones
+    /// over `max_attachment_mb` or that can't be saved are noted in the text.
+    fn save_attachments(&self, email: &ParsedEmail, incoming: &mut IncomingMessage) {
+        let Some(dir) = self.config.attachments_dir.as_ref().filter(|_| self.config.max_attachment_mb > 0) else {
+            return;
+        };
+        let max_bytes = self.config.max_attachment_mb.saturating_mul(1024 * 1024);
+        let dir = dir.join("email").join(email.uid.to_string());
+        for (i, attachment) in email.attachments.iter().enumerate() {
+            let name = attachment.file_name.as_deref().map(safe_file_name)
+                .filter(|n| !n.is_empty())
+                .unwrap_or_else(|| format!("attachment-{}", i + 1));
+            if attachment.data.len() as u64 > max_bytes {
+                incoming.content.push_str(&format!(
+                    "\n[attachment '{name}' not saved: larger than {} MB]", self.config.max_attachment_mb,
+                ));
+                continue;
+            }
+            let path = dir.join(&name);
+            if let Err(e) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, &attachment.data)) {
+                tracing::warn!("Email: could not save {}: {e}", path.display());
+                incoming.content.push_str(&format!("\n[attachment '{name}' not saved]"));
+                continue;
+            }
+            let kind = match attachment.mime_type.as_deref() {
+                Some(mime) if mime.starts_with("image/") => AttachmentKind::Image,
+                _ => AttachmentKind::Document,
+            };
+            incoming.attachments.push(Attachment {
+                kind,
+                path: path.display().to_string(),
+                mime_type: attachment.mime_type.clone(),
+                file_name: attachment.file_name.clone(),
+            });
+        }
+    }
+
+    /// Hand the new mail to `tx`; false once the receiver is gone.
+    async fn receive(&self, tx: &tokio::sync::mpsc::UnboundedSender<IncomingMessage>) -> bool {
+        let emails = match self.fetch_unread().await {
+            Ok(emails) => emails,
+            Err(e) => {
+                tracing::error!("IMAP poll: {e}");
+                return true;
+            }
+        };
+        for em in emails {
+            if !self.should_answer(&em) {
+                tracing::debug!("📧 Skipping automatic, own or unlisted sender's email from {}", em.from);
+                continue;
+            }
+            let mut incoming = em.to_incoming();
+            self.save_attachments(&em, &mut incoming);
+            self.threads.lock().unwrap().insert(incoming.thread_id.clone(), em.reply_context());
+            if tx.send(incoming).is_err() {
+                return false;
+            }
+        }
+        true
+    }
+
     /// Start IMAP polling loop — returns a stream of IncomingMessages.
+    /// Between fetches it waits with IDLE when enabled and supported,
+    /// otherwise for `poll_interval_secs`.
     pub fn start_polling(self) -> EmailPollingStream {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        let config = self.config.clone();
-        let last_seen = self.last_seen_uid.clone();
 
         tokio::spawn(async move {
-            let ch = EmailChannel {
-                config,
-                connected: true,
-                last_seen_uid: last_seen,
-            };
-            loop {
-                match ch.fetch_unread().await {
-                    Ok(emails) => {
-                        for em in emails {
-                            let incoming = IncomingMessage {
-                                channel: "email".into(),
-                                thread_id: em.from.clone(),
-                                sender_id: em.from.clone(),
-                                sender_name: em.from_name.clone(),
-                                content: format!("📧 Subject: {}\n\n{}", em.subject, em.body_text),
-                                thread_type: ThreadType::Direct,
-                                timestamp: chrono::Utc::now(),
-                                reply_to: em.message_id,
-                            };
-                            if tx.send(incoming).is_err() { return; }
+            let ch = self;
+            let mut idle = ch.config.idle;
+            let poll_interval = Duration::from_secs(ch.config.poll_interval_secs);
+            while ch.receive(&tx).await {
+                if idle {
+                    match ch.wait_for_mail().await {
+                        Ok(true) => continue,
+                        Ok(false) => {
+                            tracing::info!("📧 {} has no IMAP IDLE; polling every {}s", ch.config.imap_host, poll_interval.as_secs());
+                            idle = false;
                         }
+                        Err(e) => tracing::warn!("IMAP IDLE: {e}"),
                     }
-                    Err(e) => tracing::error!("IMAP poll: {e}"),
                 }
-                tokio::time::sleep(std::time::Duration::from_secs(ch.config.poll_interval_secs)).await;
+                tokio::time::sleep(poll_interval).await;
             }
         });
 
@@ -207,6 +601,28 @@ impl EmailChannel {
     }
 }
 
+/// Whether `allowed` (addresses and `@domain`s) lets `address` through;
+/// an empty list lets everyone through.
+fn sender_allowed(allowed: &[String], address: &str) -> bool {
+    allowed.is_empty() || allowed.iter().any(|entry| {
+        if entry.starts_with('@') {
+            address.to_lowercase().ends_with(&entry.to_lowercase())
+        } else {
+            address.eq_ignore_ascii_case(entry)
+        }
+    })
+}
+
+/// The last path component of `name`, with only letters, digits, `.`, `-`
+/// and `_`, and no leading dots.
+fn safe_file_name(name: &str) -> String {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name: String = name.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '.' | '-' | '_') { c } else { '_' })
+        .collect();
+    name.trim_start_matches('.').to_string()
+}
+
 /// Stream of incoming email messages.
 pub struct EmailPollingStream {
     rx: tokio::sync::mpsc::UnboundedReceiver<IncomingMessage>,
@@ -225,20 +641,11 @@ impl Channel for EmailChannel {
     fn name(&self) -> &str { "email" }
 
     async fn connect(&mut self) -> Result<()> {
-        let host = self.config.imap_host.clone();
-        let port = self.config.imap_port;
-        let email = self.config.email.clone();
-        let password = self.config.password.clone();
+        let login = self.login().await?;
+        let config = self.config.clone();
 
         tokio::task::spawn_blocking(move || {
-            let tls = native_tls::TlsConnector::builder().build()
-                .map_err(|e| BizClawError::Channel(format!("TLS: {e}")))?;
-            let client = imap::connect(
-                (host.as_str(), port), &host, &tls,
-            ).map_err(|e| BizClawError::Channel(format!("IMAP connect: {e}")))?;
-            let mut session = client.login(&email, &password)
-                .map_err(|e| BizClawError::AuthFailed(format!("IMAP auth: {}", e.0)))?;
-            session.logout().ok();
+            imap_session(&config, &login)?.logout().ok();
             Ok::<(), BizClawError>(())
         })
         .await
@@ -257,47 +664,63 @@ impl Channel for EmailChannel {
     fn is_connected(&self) -> bool { self.connected }
 
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
-        let subject = message.reply_to.as_deref()
-            .map(|r| format!("Re: {r}"))
-            .unwrap_or_else(|| "From BizClaw AI".into());
-        self.send_email(&message.thread_id, &subject, &message.content, message.reply_to.as_deref()).await
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire("email", &message.thread_id).await?;
+        }
+        let thread = self.threads.lock().unwrap().get(&message.thread_id).cloned();
+        match thread {
+            Some(reply) => {
+                self.deliver(self.compose(&reply, &message.content)?).await?;
+                tracing::info!("📤 Email reply sent to: {}", reply.to);
+                Ok(())
+            }
+            // Not a thread we received: `thread_id` is the recipient's address.
+            None => self.send_email(
+                &message.thread_id, "From BizClaw AI", &message.content, message.reply_to.as_deref(),
+            ).await,
+        }
     }
 
     async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
-        Ok(Box::new(futures::stream::pending()))
+        Ok(Box::new(self.clone().start_polling()))
     }
 }
 
-/// Synchronous IMAP fetch — called inside spawn_blocking.
-fn imap_fetch_sync(
-    host: &str,
-    port: u16,
-    email: &str,
-    password: &str,
-    mailbox: &str,
-    unread_only: bool,
-    mark_as_read: bool,
-    last_seen_uid: &Arc<Mutex<u32>>,
-) -> Result<Vec<ParsedEmail>> {
+type ImapSession = imap::Session<native_tls::TlsStream<std::net::TcpStream>>;
+
+/// Connect and log in, with the password or XOAUTH2.
+fn imap_session(config: &EmailConfig, login: &Login) -> Result<ImapSession> {
+    let host = config.imap_host.as_str();
     let tls = native_tls::TlsConnector::builder()
         .build()
         .map_err(|e| BizClawError::Channel(format!("TLS: {e}")))?;
 
-    let client = imap::connect((host, port), host, &tls)
+    let client = imap::connect((host, config.imap_port), host, &tls)
         .map_err(|e| BizClawError::Channel(format!("IMAP connect: {e}")))?;
 
-    let mut session = client.login(email, password)
-        .map_err(|e| BizClawError::Channel(format!("IMAP login: {}", e.0)))?;
+    match login {
+        Login::Password(password) => client.login(&config.email, password)
+            .map_err(|e| BizClawError::AuthFailed(format!("IMAP auth: {}", e.0))),
+        Login::OAuth2(token) => client.authenticate("XOAUTH2", &XOAuth2 { user: &config.email, token })
+            .map_err(|e| BizClawError::AuthFailed(format!("IMAP XOAUTH2: {}", e.0))),
+    }
+}
+
+/// Synchronous IMAP fetch — called inside spawn_blocking. Fetches mail
+/// after the last UID handled, and records the new last one.
+fn imap_fetch_sync(config: &EmailConfig, login: &Login, seen: &SeenUids) -> Result<Vec<ParsedEmail>> {
+    let mut session = imap_session(config, login)?;
 
-    session.select(mailbox)
+    let mailbox = session.select(&config.mailbox)
         .map_err(|e| BizClawError::Channel(format!("Select: {e}")))?;
 
-    let search = if unread_only { "UNSEEN" } else { "ALL" };
+    let search = if config.unread_only { "UNSEEN" } else { "ALL" };
     let uids = session.uid_search(search)
         .map_err(|e| BizClawError::Channel(format!("Search: {e}")))?;
 
-    let last = *last_seen_uid.lock().unwrap();
-    let new_uids: Vec<u32> = uids.into_iter().filter(|&u| u > last).collect();
+    let last = seen.last_uid(mailbox.uid_validity);
+    let mut new_uids: Vec<u32> = uids.into_iter().filter(|&u| u > last).collect();
+    new_uids.sort_unstable();
 
     if new_uids.is_empty() {
         session.logout().ok();
@@ -305,7 +728,8 @@ fn imap_fetch_sync(
     }
 
     let uid_set = new_uids.iter().map(|u| u.to_string()).collect::<Vec<_>>().join(",");
-    let messages = session.uid_fetch(&uid_set, "(UID RFC822)")
+    // PEEK leaves \\Seen alone; it is only set below when `mark_as_read`.
+    let messages = session.uid_fetch(&uid_set, "(UID BODY.PEEK[])")
         .map_err(|e| BizClawError::Channel(format!("Fetch: {e}")))?;
 
     let mut emails = Vec::new();
@@ -314,18 +738,18 @@ fn imap_fetch_sync(
     for msg in messages.iter() {
         let uid = msg.uid.unwrap_or(0);
         if uid > max_uid { max_uid = uid; }
-        if let Some(body) = msg.body() {
-            if let Some(parsed) = parse_email_bytes(body, uid) {
-                emails.push(parsed);
-            }
+        if let Some(body) = msg.body()
+            && let Some(parsed) = parse_email_bytes(body, uid) {
+            emails.push(parsed);
         }
     }
+    emails.sort_by_key(|e| e.uid);
 
-    if mark_as_read && !new_uids.is_empty() {
+    if config.mark_as_read {
         session.uid_store(&uid_set, "+FLAGS (\\Seen)").ok();
     }
 
-    *last_seen_uid.lock().unwrap() = max_uid;
+    seen.set(mailbox.uid_validity, max_uid);
     session.logout().ok();
     tracing::info!("📧 Fetched {} email(s)", emails.len());
     Ok(emails)
@@ -333,7 +757,7 @@ fn imap_fetch_sync(
 
 /// Parse raw email bytes.
 fn parse_email_bytes(raw: &[u8], uid: u32) -> Option<ParsedEmail> {
-    use mail_parser::MessageParser;
+    use mail_parser::{HeaderValue, MessageParser, MimeHeaders, PartType};
     let parsed = MessageParser::default().parse(raw)?;
 
     let from = parsed.from()
@@ -348,33 +772,366 @@ fn parse_email_bytes(raw: &[u8], uid: u32) -> Option<ParsedEmail> {
 
     let subject = parsed.subject().unwrap_or("(no subject)").to_string();
 
-    let body_text = parsed.body_text(0)
-        .map(|s| s.to_string())
-        .unwrap_or_else(|| {
-            parsed.body_html(0)
-                .map(|h| strip_html(&h))
-                .unwrap_or_default()
-        });
+    // The text bodies are the text/plain alternatives where a message has
+    // them, and its HTML parts otherwise.
+    let body_text = parsed.text_bodies()
+        .filter_map(|part| match &part.body {
+            PartType::Text(text) => Some(text.trim().to_string()),
+            PartType::Html(html) => Some(strip_html(html)),
+            _ => None,
+        })
+        .map(|text| strip_quoted(&text))
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let attachments = parsed.attachments()
+        .filter(|part| !part.is_message())
+        .map(|part| EmailAttachment {
+            file_name: part.attachment_name().map(String::from),
+            mime_type: part.content_type().map(|ct| match ct.subtype() {
+                Some(subtype) => format!("{}/{subtype}", ct.ctype()),
+                None => ct.ctype().to_string(),
+            }),
+            data: part.contents().to_vec(),
+        })
+        .collect();
 
     let message_id = parsed.message_id().map(String::from);
+    let in_reply_to = parsed.in_reply_to().as_text().map(String::from);
+    let references = parsed.references().as_text_list()
+        .map(|ids| ids.into_iter().map(String::from).collect())
+        .unwrap_or_default();
+
+    let header = |name: &str| parsed.header(name).and_then(HeaderValue::as_text).map(str::to_ascii_lowercase);
+    let auto_submitted = header("Auto-Submitted").is_some_and(|v| v != "no")
+        || header("Precedence").is_some_and(|v| matches!(v.as_str(), "bulk" | "junk" | "list"));
 
     Some(ParsedEmail {
         uid, from, from_name, subject,
         body_text: body_text.chars().take(4000).collect(),
         message_id,
+        in_reply_to,
+        references,
+        auto_submitted,
+        attachments,
     })
 }
 
+/// The new text of a reply: the quoted earlier messages (`> ` lines, and
+/// everything after an "On ... wrote:" or "-----Original Message-----"
+/// header) and the signature (after a `-- ` line, or a "Sent from my ..."
+/// line) removed.
+fn strip_quoted(text: &str) -> String {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut kept: Vec<&str> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        let lower = trimmed.to_lowercase();
+        // Gmail may wrap "On <date>, <name> wrote:" over two lines.
+        let attribution = |l: &str| {
+            let l = l.trim_end().to_lowercase();
+            l.ends_with("wrote:") || l.ends_with("đã viết:") || l.ends_with("schrieb:") || l.ends_with("a écrit :")
+        };
+        let starts_attribution = (lower.starts_with("on ") || lower.starts_with("vào ") || lower.starts_with("le ") || lower.starts_with("am "))
+            && (attribution(trimmed) || lines.get(i + 1).is_some_and(|next| attribution(next)));
+        let outlook = lower.starts_with("-----original message-----")
+            || trimmed.starts_with("________________________________")
+            || (lower.starts_with("from:") && lines.get(i + 1).is_some_and(|next| {
+                let next = next.trim().to_lowercase();
+                next.starts_with("sent:") || next.starts_with("date:")
+            }));
+        let signature = *line == "-- " || trimmed == "--" || lower.starts_with("sent from my ");
+        if starts_attribution || outlook || signature {
+            break;
+        }
+        if trimmed.starts_with('>') {
+            continue;
+        }
+        kept.push(line.trim_end());
+    }
+    kept.join("\n").trim().to_string()
+}
+
+/// Plain text of an HTML body: tags, scripts and styles removed, block
+/// elements on their own lines, common entities decoded.
 fn strip_html(html: &str) -> String {
     let mut out = String::new();
-    let mut in_tag = false;
-    for ch in html.chars() {
-        match ch {
-            '<' => in_tag = true,
-            '>' => in_tag = false,
-            _ if !in_tag => out.push(ch),
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('>') else { break };
+        let tag = rest[start + 1..start + len]
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        rest = &rest[start + len + 1..];
+        match tag.as_str() {
+            "script" | "style" | "head" => {
+                let close = format!("</{tag}");
+                rest = match rest.to_ascii_lowercase().find(&close) {
+                    Some(end) => &rest[end + close.len()..],
+                    None => "",
+                };
+                if let Some(gt) = rest.find('>') {
+                    rest = &rest[gt + 1..];
+                }
+            }
+            "br" | "p" | "div" | "tr" | "li" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "blockquote" => {
+                out.push('\n');
+            }
             _ => {}
         }
     }
-    out.trim().to_string()
+    if !rest.contains('<') {
+        out.push_str(rest);
+    }
+
+    let text = out
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&");
+
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() && lines.last().is_none_or(|last| last.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    lines.join("\n").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> ParsedEmail {
+        parse_email_bytes(raw.replace('\n', "\r\n").as_bytes(), 7).unwrap()
+    }
+
+    #[test]
+    fn test_multipart_prefers_plain_text() {
+        let email = parse(
+            "From: Lan Nguyen <lan@customer.vn>\n\
+             To: support@shop.vn\n\
+             Subject: Order #1042\n\
+             Message-ID: <m2@customer.vn>\n\
+             In-Reply-To: <m1@shop.vn>\n\
+             References: <m0@customer.vn> <m1@shop.vn>\n\
+             MIME-Version: 1.0\n\
+             Content-Type: multipart/alternative; boundary=\"b\"\n\
+             \n\
+             --b\n\
+             Content-Type: text/plain; charset=utf-8\n\
+             \n\
+             Where is my order?\n\
+             --b\n\
+             Content-Type: text/html; charset=utf-8\n\
+             \n\
+             <p>Where is <b>my order</b>?</p>\n\
+             --b--\n",
+        );
+        assert_eq!(email.body_text, "Where is my order?");
+        assert_eq!(email.from_name.as_deref(), Some("Lan Nguyen"));
+        assert_eq!(email.references, ["m0@customer.vn", "m1@shop.vn"]);
+        assert_eq!(email.thread_key(), "m0@customer.vn");
+        assert!(!email.auto_submitted);
+
+        let incoming = email.to_incoming();
+        assert_eq!(incoming.sender_id, "lan@customer.vn");
+        assert!(incoming.content.starts_with("📧 From: Lan Nguyen <lan@customer.vn>\nSubject: Order #1042\n\n"));
+
+        assert_eq!(email.reply_context(), ReplyContext {
+            to: "lan@customer.vn".into(),
+            subject: "Re: Order #1042".into(),
+            in_reply_to: Some("m2@customer.vn".into()),
+            references: vec!["m0@customer.vn".into(), "m1@shop.vn".into(), "m2@customer.vn".into()],
+        });
+    }
+
+    #[test]
+    fn test_html_only_and_auto_replies() {
+        let email = parse(
+            "From: shop@partner.vn\n\
+             Subject: RE: Invoice\n\
+             Message-ID: <x1@partner.vn>\n\
+             Auto-Submitted: auto-replied\n\
+             Content-Type: text/html\n\
+             \n\
+             <html><head><style>p { color: red }</style></head>\
+             <body><p>Out of office&nbsp;until Monday.</p><p>Tom &amp; Jerry</p></body></html>\n",
+        );
+        assert_eq!(email.body_text, "Out of office until Monday.\n\nTom & Jerry");
+        assert_eq!(email.thread_key(), "x1@partner.vn");
+        assert_eq!(email.reply_context().subject, "RE: Invoice");
+        assert!(email.auto_submitted);
+
+        let channel = EmailChannel::new(EmailConfig { email: "support@shop.vn".into(), ..Default::default() });
+        assert!(!channel.should_answer(&email));
+        let own = ParsedEmail { from: "Support@Shop.vn".into(), auto_submitted: false, ..email };
+        assert!(!channel.should_answer(&own));
+    }
+
+    #[test]
+    fn test_quoted_history_and_signature_stripped() {
+        let gmail = "Yes, please ship it.\n\nThanks,\nLan\n\nOn Mon, 3 Mar 2025 at 09:12, Shop Support\n<support@shop.vn> wrote:\n> Do you want express?\n";
+        assert_eq!(strip_quoted(gmail), "Yes, please ship it.\n\nThanks,\nLan");
+        let outlook = "Đồng ý.\n\nFrom: Shop Support <support@shop.vn>\nSent: Monday, March 3, 2025\nSubject: Order\n\nOld text";
+        assert_eq!(strip_quoted(outlook), "Đồng ý.");
+        let signature = "See inline:\n> old question\nNew answer\n-- \nLan Nguyen\nCEO";
+        assert_eq!(strip_quoted(signature), "See inline:\nNew answer");
+        assert_eq!(strip_quoted("Ok\n\nSent from my iPhone"), "Ok");
+        assert_eq!(strip_quoted("Vâng.\nVào 10:00 ngày 3/3, Shop <support@shop.vn> đã viết:\n> Hỏi"), "Vâng.");
+    }
+
+    #[test]
+    fn test_sender_allowlist() {
+        let allowed = ["@customer.vn".to_string(), "boss@partner.vn".to_string()];
+        assert!(sender_allowed(&allowed, "Lan@Customer.vn"));
+        assert!(sender_allowed(&allowed, "boss@partner.vn"));
+        assert!(!sender_allowed(&allowed, "other@partner.vn"));
+        assert!(!sender_allowed(&allowed, "x@evilcustomer.vn"));
+        assert!(sender_allowed(&[], "anyone@example.com"));
+
+        let channel = EmailChannel::new(EmailConfig { allowed_senders: allowed.to_vec(), ..Default::default() });
+        let email = parse("From: x@other.vn\nSubject: Hi\n\nHello\n");
+        assert!(!channel.should_answer(&email));
+    }
+
+    #[test]
+    fn test_attachments_saved_under_cap() {
+        let email = parse(
+            "From: lan@customer.vn\n\
+             Subject: Invoice\n\
+             Message-ID: <a1@customer.vn>\n\
+             MIME-Version: 1.0\n\
+             Content-Type: multipart/mixed; boundary=\"b\"\n\
+             \n\
+             --b\n\
+             Content-Type: text/plain\n\
+             \n\
+             Invoice attached.\n\
+             --b\n\
+             Content-Type: application/pdf; name=\"../invoice 3.pdf\"\n\
+             Content-Disposition: attachment; filename=\"../invoice 3.pdf\"\n\
+             Content-Transfer-Encoding: base64\n\
+             \n\
+             JVBERi0xLjQ=\n\
+             --b\n\
+             Content-Type: image/png\n\
+             Content-Disposition: attachment; filename=\"big.png\"\n\
+             Content-Transfer-Encoding: base64\n\
+             \n\
+             iVBORw0KGgo=\n\
+             --b--\n",
+        );
+        assert_eq!(email.body_text, "Invoice attached.");
+        assert_eq!(email.attachments.len(), 2);
+        assert_eq!(email.attachments[0].data, b"%PDF-1.4");
+        assert_eq!(email.attachments[0].mime_type.as_deref(), Some("application/pdf"));
+
+        let dir = std::env::temp_dir().join(format!("bizclaw-email-{}", uuid::Uuid::new_v4().simple()));
+        let channel = EmailChannel::new(EmailConfig { attachments_dir: Some(dir.clone()), max_attachment_mb: 1, ..Default::default() });
+        let big = EmailAttachment { data: vec![0; 1024 * 1024 + 1], ..email.attachments[1].clone() };
+        let email = ParsedEmail { attachments: vec![email.attachments[0].clone(), big], ..email };
+        let mut incoming = email.to_incoming();
+        channel.save_attachments(&email, &mut incoming);
+
+        let saved = dir.join("email").join("7").join("invoice_3.pdf");
+        assert_eq!(incoming.attachments, vec![Attachment {
+            kind: AttachmentKind::Document,
+            path: saved.display().to_string(),
+            mime_type: Some("application/pdf".into()),
+            file_name: Some("../invoice 3.pdf".into()),
+        }]);
+        assert_eq!(std::fs::read(&saved).unwrap(), b"%PDF-1.4");
+        assert!(incoming.content.ends_with("[attachment 'big.png' not saved: larger than 1 MB]"), "{}", incoming.content);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_seen_uids_survive_restart() {
+        let path = std::env::temp_dir().join(format!("bizclaw-email-uids-{}.json", uuid::Uuid::new_v4().simple()));
+        let seen = SeenUids::open(Some(path.clone()), "support@shop.vn/INBOX".into());
+        assert_eq!(seen.last_uid(Some(5)), 0);
+        seen.set(Some(5), 42);
+        SeenUids::open(Some(path.clone()), "sales@shop.vn/INBOX".into()).set(Some(9), 7);
+
+        let reopened = SeenUids::open(Some(path.clone()), "support@shop.vn/INBOX".into());
+        assert_eq!(reopened.last_uid(Some(5)), 42);
+        // A new UIDVALIDITY renumbers the mailbox.
+        assert_eq!(reopened.last_uid(Some(6)), 0);
+        assert_eq!(SeenUids::open(Some(path.clone()), "sales@shop.vn/INBOX".into()).last_uid(Some(9)), 7);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_oauth2_token_refreshed_once() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let router = axum::Router::new().route("/token", axum::routing::post(move |body: String| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            async move {
+                assert!(body.contains("grant_type=refresh_token") && body.contains("refresh_token=r1"), "{body}");
+                axum::Json(serde_json::json!({ "access_token": "ya29.fresh", "expires_in": 3599 }))
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let token_url = format!("http://{}/token", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let channel = EmailChannel::new(EmailConfig {
+            email: "support@shop.vn".into(),
+            oauth2: Some(EmailOAuth2Config {
+                refresh_token: Some("r1".into()),
+                client_id: "app".into(),
+                token_url,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        for _ in 0..2 {
+            assert!(matches!(channel.login().await.unwrap(), Login::OAuth2(token) if token == "ya29.fresh"));
+        }
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        let sasl = imap::Authenticator::process(&XOAuth2 { user: "support@shop.vn", token: "ya29.fresh" }, b"");
+        assert_eq!(sasl, "user=support@shop.vn\x01auth=Bearer ya29.fresh\x01\x01");
+    }
+
+    #[test]
+    fn test_strip_html() {
+        assert_eq!(
+            strip_html("<div>Hi<br>there</div><script>alert('x')</script>\n\n\n<p>a &lt; b</p>"),
+            "Hi\nthere\n\na < b"
+        );
+    }
+
+    #[test]
+    fn test_reply_is_threaded() {
+        let channel = EmailChannel::new(EmailConfig {
+            email: "support@shop.vn".into(),
+            display_name: Some("Shop Support".into()),
+            ..Default::default()
+        });
+        let reply = ReplyContext {
+            to: "lan@customer.vn".into(),
+            subject: "Re: Order #1042".into(),
+            in_reply_to: Some("m2@customer.vn".into()),
+            references: vec!["m0@customer.vn".into(), "m2@customer.vn".into()],
+        };
+        let raw = String::from_utf8(channel.compose(&reply, "It ships today.").unwrap().formatted()).unwrap();
+        assert!(raw.contains("Subject: Re: Order #1042\r\n"), "{raw}");
+        assert!(raw.contains("In-Reply-To: <m2@customer.vn>\r\n"), "{raw}");
+        assert!(raw.contains("References: <m0@customer.vn> <m2@customer.vn>\r\n"), "{raw}");
+        assert!(raw.contains("From: \"Shop Support\" <support@shop.vn>\r\n"), "{raw}");
+        assert!(raw.ends_with("It ships today."), "{raw}");
+    }
 }
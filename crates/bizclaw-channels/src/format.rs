@@ -0,0 +1,546 @@
+//! Fits the agent's Markdown replies to a channel: split into messages under
+//! the channel's length limit, then rendered in the channel's markup.
+//!
+//! Tables become code blocks first, since no chat app draws Markdown
+//! tables; a code block keeps the columns lined up.
+
+/// Markup a channel understands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// Telegram MarkdownV2: code, bold and links kept, the rest escaped.
+    MarkdownV2,
+    /// Discord: Markdown as the model wrote it.
+    Discord,
+    /// WhatsApp: `*bold*`, `_italic_`, `~strike~` and ``` blocks.
+    WhatsApp,
+    /// No markup (Zalo): formatting dropped, links written out.
+    Plain,
+}
+
+/// Characters MarkdownV2 reads as markup outside code.
+const MARKDOWN_V2_SPECIAL: &[char] = &[
+    '_', '*', '[', ']', '(', ')', '~', '`', '>', '#', '+', '-', '=', '|', '{', '}', '.', '!', '\\',
+];
+
+/// The messages to send for `text` on a channel taking `dialect` and at
+/// most `max_chars` characters per message, in order.
+///
+/// Rendering only shortens text, except MarkdownV2 escapes, which Telegram
+/// doesn't count against its limit.
+pub fn format_message(text: &str, dialect: Dialect, max_chars: usize) -> Vec<String> {
+    prepare(text, max_chars).iter().map(|part| render(part, dialect)).collect()
+}
+
+/// `text` with tables as code blocks, split into Markdown messages of at
+/// most `max_chars` characters, for channels that render each part
+/// themselves (Telegram retries a part as plain text).
+pub fn prepare(text: &str, max_chars: usize) -> Vec<String> {
+    split_message(&tables_to_code(text), max_chars, true)
+}
+
+/// Convert one message of the model's Markdown to `dialect`.
+pub fn render(text: &str, dialect: Dialect) -> String {
+    match dialect {
+        Dialect::MarkdownV2 => to_markdown_v2(text),
+        Dialect::Discord => text.to_string(),
+        Dialect::WhatsApp => rewrite(text, &WHATSAPP),
+        Dialect::Plain => rewrite(text, &PLAIN),
+    }
+}
+
+/// Split a reply into messages of at most `max_chars` characters, breaking
+/// between lines where possible. A fenced code block too long for one
+/// message is closed at the break and reopened in the next, so each
+/// message's Markdown stands on its own.
+///
+/// With `prefer_boundaries`, a paragraph or code block that fits in one
+/// message is moved whole to the next message rather than split.
+pub fn split_message(text: &str, max_chars: usize, prefer_boundaries: bool) -> Vec<String> {
+    let count = |s: &str| s.chars().count();
+    let is_blank = |line: &str| line.trim().is_empty();
+    // Lines too long for a message are cut first; half the limit leaves
+    // room for the fences around a piece. A fence line is kept whole, with
+    // its language dropped if reopening it wouldn't leave that room.
+    let width = (max_chars / 2).max(1);
+    let max_fence = max_chars.saturating_sub(width + 4);
+    let lines: Vec<(&str, bool)> = text.split_inclusive('\n')
+        .flat_map(|line| {
+            if line.trim_start().starts_with("```") {
+                let fence = if count(line) <= max_fence { line } else { "```\n" };
+                vec![(fence, true)]
+            } else {
+                // A piece starting with ``` would read as a fence if it began
+                // a message, so it takes the last character before it too.
+                let mut cuts = vec![0];
+                for piece in crate::streaming::split_message(line, width) {
+                    cuts.push(cuts[cuts.len() - 1] + piece.len());
+                }
+                for k in 1..cuts.len() - 1 {
+                    while cuts[k] > cuts[k - 1] && line[cuts[k]..].trim_start().starts_with("```") {
+                        cuts[k] -= line[..cuts[k]].chars().next_back().map_or(0, char::len_utf8);
+                    }
+                }
+                cuts.dedup();
+                cuts.windows(2).map(|w| (&line[w[0]..w[1]], false)).collect()
+            }
+        })
+        .collect();
+
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut open_fence: Option<&str> = None;
+    let mut flush = |current: &mut String, open_fence: Option<&str>| {
+        if open_fence.is_some() {
+            if !current.ends_with('\n') {
+                current.push('\n');
+            }
+            current.push_str("```");
+        }
+        if !current.trim().is_empty() {
+            parts.push(std::mem::take(current));
+        }
+        current.clear();
+        if let Some(fence) = open_fence {
+            current.push_str(fence.trim_end());
+            current.push('\n');
+        }
+    };
+
+    for (i, &(line, is_fence)) in lines.iter().enumerate() {
+        let opens = open_fence.is_none() && is_fence;
+        if prefer_boundaries && open_fence.is_none() {
+            // A code block runs to its closing fence, a paragraph to the
+            // next blank line or fence.
+            let starts_paragraph = !is_blank(line) && (i == 0 || is_blank(lines[i - 1].0) || lines[i - 1].1);
+            let end = if opens {
+                lines[i + 1..].iter().position(|l| l.1).map_or(lines.len(), |p| i + p + 2)
+            } else if starts_paragraph {
+                lines[i..].iter().position(|l| is_blank(l.0) || l.1).map_or(lines.len(), |p| i + p)
+            } else {
+                i
+            };
+            let unit: usize = lines[i..end].iter().map(|l| count(l.0)).sum();
+            if end > i && unit <= max_chars && count(&current) + unit > max_chars {
+                flush(&mut current, None);
+            }
+        }
+        // Room to close the block if the message has to end inside it.
+        let closes = open_fence.is_some() && is_fence;
+        let reserve = if (open_fence.is_some() && !closes) || opens { 4 } else { 0 };
+        if count(&current) + count(line) + reserve > max_chars {
+            flush(&mut current, open_fence);
+        }
+        current.push_str(line);
+        if is_fence {
+            open_fence = if open_fence.is_some() { None } else { Some(line) };
+        }
+    }
+    // Close a block the text left open.
+    flush(&mut current, open_fence);
+    parts
+}
+
+/// Replace Markdown tables (a header row, a `|---|` row, then rows) with
+/// code blocks of aligned columns. Code blocks are left alone.
+pub fn tables_to_code(text: &str) -> String {
+    let lines: Vec<&str> = text.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(text.len());
+    let mut in_fence = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        let is_table = !in_fence
+            && line.contains('|')
+            && lines.get(i + 1).is_some_and(|next| is_table_rule(next));
+        if !is_table {
+            out.push_str(line);
+            i += 1;
+            continue;
+        }
+
+        let mut rows = vec![table_cells(line)];
+        i += 2;
+        while let Some(row) = lines.get(i).filter(|l| l.contains('|') && !l.trim().is_empty()) {
+            rows.push(table_cells(row));
+            i += 1;
+        }
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|c| rows.iter().filter_map(|r| r.get(c)).map(|cell| cell.chars().count()).max().unwrap_or(0))
+            .collect();
+        let format_row = |cells: &[&str]| {
+            let padded: Vec<String> = widths.iter().enumerate()
+                .map(|(c, &width)| format!("{:width$}", cells.get(c).copied().unwrap_or("")))
+                .collect();
+            padded.join("  ").trim_end().to_string()
+        };
+
+        out.push_str("```\n");
+        out.push_str(&format_row(&rows[0]));
+        out.push('\n');
+        let rule: Vec<String> = widths.iter().map(|&w| "-".repeat(w.max(1))).collect();
+        out.push_str(&rule.join("  "));
+        out.push('\n');
+        for row in &rows[1..] {
+            out.push_str(&format_row(row));
+            out.push('\n');
+        }
+        out.push_str("```\n");
+    }
+    out
+}
+
+/// `|---|:---:|` and the like, the line under a table's header.
+fn is_table_rule(line: &str) -> bool {
+    let line = line.trim();
+    line.contains('-')
+        && line.contains(['|', ':'])
+        && line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' '))
+}
+
+fn table_cells(line: &str) -> Vec<&str> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = line.strip_suffix('|').unwrap_or(line);
+    line.split('|').map(str::trim).collect()
+}
+
+/// Convert the model's Markdown to Telegram's MarkdownV2. Code spans and
+/// fenced blocks, `**bold**` and `[links](url)` are kept; every other
+/// special character is escaped, so the text reads as written.
+pub fn to_markdown_v2(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / 8);
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        if let Some((lang, code, len)) = parse_code_block(rest) {
+            out.push_str("```");
+            out.push_str(&escape_code(lang));
+            out.push('\n');
+            out.push_str(&escape_code(code));
+            out.push_str("```");
+            rest = &rest[len..];
+        } else if let Some((code, len)) = parse_code_span(rest) {
+            out.push('`');
+            out.push_str(&escape_code(code));
+            out.push('`');
+            rest = &rest[len..];
+        } else if let Some(after) = rest.strip_prefix("**")
+            && let Some(end) = after.find("**").filter(|&end| end > 0)
+        {
+            out.push('*');
+            out.push_str(&escape_markdown_v2(&after[..end]));
+            out.push('*');
+            rest = &after[end + 2..];
+        } else if let Some((label, url, len)) = parse_link(rest) {
+            out.push('[');
+            out.push_str(&escape_markdown_v2(label));
+            out.push_str("](");
+            out.push_str(&url.replace('\\', "\\\\").replace(')', "\\)"));
+            out.push(')');
+            rest = &rest[len..];
+        } else {
+            if MARKDOWN_V2_SPECIAL.contains(&c) {
+                out.push('\\');
+            }
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+fn escape_markdown_v2(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if MARKDOWN_V2_SPECIAL.contains(&c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Inside code only the backtick and backslash need escaping.
+fn escape_code(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('`', "\\`")
+}
+
+/// How [`rewrite`] writes each kind of Markdown.
+struct Style {
+    bold: &'static str,
+    italic: &'static str,
+    strike: &'static str,
+    /// Keep code spans and blocks in backticks.
+    code: bool,
+    bullet: &'static str,
+}
+
+const WHATSAPP: Style = Style { bold: "*", italic: "_", strike: "~", code: true, bullet: "• " };
+const PLAIN: Style = Style { bold: "", italic: "", strike: "", code: false, bullet: "• " };
+
+/// Rewrite the model's Markdown in `style`. Headings become bold lines,
+/// `- item` bullets become `• item` and links `label (url)`.
+fn rewrite(text: &str, style: &Style) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(c) = rest.chars().next() {
+        let line_start = out.is_empty() || out.ends_with('\n');
+        if let Some((_, code, len)) = parse_code_block(rest) {
+            if style.code {
+                out.push_str("```");
+                out.push_str(code);
+                out.push_str("```");
+            } else {
+                out.push_str(code.strip_suffix('\n').unwrap_or(code));
+            }
+            rest = &rest[len..];
+        } else if let Some((code, len)) = parse_code_span(rest) {
+            if style.code {
+                out.push('`');
+                out.push_str(code);
+                out.push('`');
+            } else {
+                out.push_str(code);
+            }
+            rest = &rest[len..];
+        } else if line_start && let Some(heading) = parse_heading(rest) {
+            let line = heading.split_inclusive('\n').next().unwrap_or(heading);
+            let title = line.trim_end();
+            out.push_str(style.bold);
+            out.push_str(&strip_emphasis(title));
+            out.push_str(style.bold);
+            out.push_str(&line[title.len()..]);
+            rest = &heading[line.len()..];
+        } else if line_start && let Some(item) = rest.strip_prefix("- ").or_else(|| rest.strip_prefix("* ")) {
+            out.push_str(style.bullet);
+            rest = item;
+        } else if let Some((inner, len)) = parse_delimited(rest, "**").or_else(|| parse_delimited(rest, "__")) {
+            out.push_str(style.bold);
+            out.push_str(inner);
+            out.push_str(style.bold);
+            rest = &rest[len..];
+        } else if let Some((inner, len)) = parse_delimited(rest, "~~") {
+            out.push_str(style.strike);
+            out.push_str(inner);
+            out.push_str(style.strike);
+            rest = &rest[len..];
+        } else if let Some((inner, len)) = parse_delimited(rest, "*")
+            .or_else(|| parse_delimited(rest, "_").filter(|_| !out.ends_with(char::is_alphanumeric)))
+        {
+            out.push_str(style.italic);
+            out.push_str(inner);
+            out.push_str(style.italic);
+            rest = &rest[len..];
+        } else if let Some((label, url, len)) = parse_link(rest) {
+            out.push_str(label);
+            if label != url {
+                out.push_str(" (");
+                out.push_str(url);
+                out.push(')');
+            }
+            rest = &rest[len..];
+        } else {
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+/// The text after `# `..`###### ` at the start of `text`.
+fn parse_heading(text: &str) -> Option<&str> {
+    let hashes = text.len() - text.trim_start_matches('#').len();
+    (1..=6).contains(&hashes).then(|| text[hashes..].strip_prefix(' '))?
+}
+
+/// Headings are often bold already; don't double the markers.
+fn strip_emphasis(text: &str) -> String {
+    text.replace("**", "").replace("__", "")
+}
+
+/// A fenced block at the start of `text`: its language, its code and the
+/// length of the whole block.
+fn parse_code_block(text: &str) -> Option<(&str, &str, usize)> {
+    let after = text.strip_prefix("```")?;
+    let end = after.find("```")?;
+    let block = &after[..end];
+    let (lang, code) = match block.split_once('\n') {
+        Some((lang, code)) if !lang.contains(char::is_whitespace) => (lang, code),
+        _ => ("", block),
+    };
+    Some((lang, code, 3 + end + 3))
+}
+
+/// A `code span` at the start of `text`, closed on the same line: the code
+/// and the span's length.
+fn parse_code_span(text: &str) -> Option<(&str, usize)> {
+    let after = text.strip_prefix('`')?;
+    let end = after.find(['`', '\n']).filter(|&end| after[end..].starts_with('`'))?;
+    Some((&after[..end], 1 + end + 1))
+}
+
+/// Text between `marker`s at the start of `text`, on one line and not
+/// padded with spaces: the text and the length with both markers.
+fn parse_delimited<'a>(text: &'a str, marker: &str) -> Option<(&'a str, usize)> {
+    let after = text.strip_prefix(marker)?;
+    let end = after.find(marker)?;
+    let inner = &after[..end];
+    let valid = !inner.is_empty()
+        && !inner.contains('\n')
+        && !inner.starts_with(char::is_whitespace)
+        && !inner.ends_with(char::is_whitespace);
+    valid.then_some((inner, marker.len() * 2 + end))
+}
+
+/// `[label](url)` at the start of `text`: the label, the URL and the length.
+fn parse_link(text: &str) -> Option<(&str, &str, usize)> {
+    let after = text.strip_prefix('[')?;
+    let label_end = after.find([']', '[', '\n'])?;
+    let url_part = after[label_end..].strip_prefix("](")?;
+    let url_end = url_part.find([')', ' ', '\n'])?;
+    if !url_part[url_end..].starts_with(')') || label_end == 0 || url_end == 0 {
+        return None;
+    }
+    let label = &after[..label_end];
+    let url = &url_part[..url_end];
+    Some((label, url, 1 + label_end + 2 + url_end + 1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn test_to_markdown_v2() {
+        assert_eq!(to_markdown_v2("Giá: 1.500.000đ (ưu đãi!)"), "Giá: 1\\.500\\.000đ \\(ưu đãi\\!\\)");
+        assert_eq!(to_markdown_v2("**Lưu ý** - a_b"), "*Lưu ý* \\- a\\_b");
+        assert_eq!(to_markdown_v2("Run `a.b(c)` now"), "Run `a.b(c)` now");
+        assert_eq!(to_markdown_v2("```rust\nlet x = \"`\";\n```"), "```rust\nlet x = \"\\`\";\n```");
+        assert_eq!(
+            to_markdown_v2("Xem [tài liệu_1](https://example.com/a_b?x=1)."),
+            "Xem [tài liệu\\_1](https://example.com/a_b?x=1)\\.",
+        );
+        assert_eq!(to_markdown_v2("[x] done, 2*3"), "\\[x\\] done, 2\\*3");
+        // An unclosed code span is just a backtick.
+        assert_eq!(to_markdown_v2("a ` b"), "a \\` b");
+    }
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("short", 4096, true), vec!["short"]);
+        assert!(split_message("", 4096, true).is_empty());
+
+        let text = format!("{}\n{}\n", "a".repeat(30), "b".repeat(30));
+        assert_eq!(split_message(&text, 40, true), vec![format!("{}\n", "a".repeat(30)), format!("{}\n", "b".repeat(30))]);
+
+        // A block that fits in one message is moved whole to the next one.
+        let text = format!("intro line\n```\n{}\n```\nafter\n", "x".repeat(20));
+        let parts = split_message(&text, 32, true);
+        assert_eq!(parts[0], "intro line\n");
+        assert_eq!(parts[1], format!("```\n{}\n```\n", "x".repeat(20)));
+
+        // A block too long for one message is closed and reopened.
+        let code: String = (0..10).map(|i| format!("line {i}\n")).collect();
+        let text = format!("```python\n{code}```\n");
+        let parts = split_message(&text, 40, true);
+        assert!(parts.len() > 1);
+        for part in &parts {
+            assert!(part.chars().count() <= 40, "{part:?}");
+            assert!(part.starts_with("```python\n"), "{part:?}");
+            assert!(part.trim_end().ends_with("```"), "{part:?}");
+        }
+        let joined: String = parts.iter().map(|p| p.trim_start_matches("```python\n").trim_end().trim_end_matches("```")).collect();
+        assert_eq!(joined, code);
+
+        // Very long lines are cut.
+        let parts = split_message(&"word ".repeat(2000), 4096, true);
+        assert_eq!(parts.len(), 3);
+        assert!(parts.iter().all(|p| p.chars().count() <= 4096));
+        assert_eq!(parts.concat(), "word ".repeat(2000));
+    }
+
+    #[test]
+    fn test_split_message_keeps_paragraphs() {
+        let text = "Một hai ba.\n\nBốn năm\nsáu bảy.\n";
+        assert_eq!(split_message(text, 24, true), ["Một hai ba.\n\n", "Bốn năm\nsáu bảy.\n"]);
+        assert_eq!(split_message(text, 24, false), ["Một hai ba.\n\nBốn năm\n", "sáu bảy.\n"]);
+    }
+
+    #[test]
+    fn test_tables_to_code() {
+        let text = "Bảng giá:\n| Món | Giá |\n|---|--:|\n| Phở | 50k |\n| Bánh mì | 25k |\n\nHết.";
+        assert_eq!(
+            tables_to_code(text),
+            "Bảng giá:\n```\nMón      Giá\n-------  ---\nPhở      50k\nBánh mì  25k\n```\n\nHết.",
+        );
+        // Not a table without the rule line, or inside code.
+        assert_eq!(tables_to_code("a | b\nc | d\n"), "a | b\nc | d\n");
+        let code = "```\n| a |\n|---|\n```\n";
+        assert_eq!(tables_to_code(code), code);
+    }
+
+    #[test]
+    fn test_render_dialects() {
+        let text = "## **Đơn hàng**\n- Mã: `A_1`\n- *Mới* ~~cũ~~, xem [đây](https://x.vn/a_b)\nsnake_case_name\n```rust\nlet x = 1;\n```";
+        assert_eq!(
+            render(text, Dialect::WhatsApp),
+            "*Đơn hàng*\n• Mã: `A_1`\n• _Mới_ ~cũ~, xem đây (https://x.vn/a_b)\nsnake_case_name\n```let x = 1;\n```",
+        );
+        assert_eq!(
+            render(text, Dialect::Plain),
+            "Đơn hàng\n• Mã: A_1\n• Mới cũ, xem đây (https://x.vn/a_b)\nsnake_case_name\nlet x = 1;",
+        );
+        assert_eq!(render(text, Dialect::Discord), text);
+        assert_eq!(render("2 * 3 * 4", Dialect::Plain), "2 * 3 * 4");
+    }
+
+    #[test]
+    fn test_format_message() {
+        let table = "| a | b |\n|---|---|\n| 1 | 2 |\n";
+        assert_eq!(format_message(table, Dialect::Plain, 100), ["a  b\n-  -\n1  2\n"]);
+        assert_eq!(format_message(table, Dialect::MarkdownV2, 100), ["```\na  b\n-  -\n1  2\n```\n"]);
+    }
+
+    /// Every message opens as many code blocks as it closes.
+    fn fences_balanced(part: &str) -> bool {
+        part.lines().filter(|l| l.trim_start().starts_with("```")).count() % 2 == 0
+    }
+
+    fn markdown() -> impl Strategy<Value = String> {
+        let line = prop_oneof![
+            "[a-zA-Zàđ *_`|-]{0,60}",
+            Just("```".to_string()),
+            Just("```rust".to_string()),
+            Just(String::new()),
+            Just("| a | b |".to_string()),
+            Just("|---|---|".to_string()),
+        ];
+        prop::collection::vec(line, 0..40).prop_map(|lines| lines.join("\n"))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_chunks_fit_and_fences_balance(text in markdown(), limit in 32usize..200, prefer: bool) {
+            for part in split_message(&text, limit, prefer) {
+                prop_assert!(part.chars().count() <= limit, "{part:?} over {limit}");
+                prop_assert!(fences_balanced(&part), "{part:?}");
+            }
+        }
+
+        #[test]
+        fn prop_formatted_messages_fit(text in markdown(), limit in 32usize..200) {
+            for dialect in [Dialect::Discord, Dialect::WhatsApp, Dialect::Plain] {
+                for part in format_message(&text, dialect, limit) {
+                    prop_assert!(part.chars().count() <= limit, "{dialect:?}: {part:?} over {limit}");
+                }
+            }
+            for part in prepare(&text, limit) {
+                prop_assert!(fences_balanced(&part), "{part:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,102 @@
+//! Feeds messages from monitored group chats into the group summarizer's
+//! buffer.
+
+use bizclaw_core::config::SummarizeGroupsConfig;
+use bizclaw_core::group_buffer::{BufferedMessage, MessageBuffer};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Buffers messages from a channel's configured groups.
+pub struct GroupMonitor {
+    channel: &'static str,
+    buffer: MessageBuffer,
+    config: SummarizeGroupsConfig,
+    /// Group names learned from the platform, by group id.
+    names: Mutex<HashMap<String, String>>,
+}
+
+impl GroupMonitor {
+    pub fn new(channel: &'static str, buffer: MessageBuffer, config: SummarizeGroupsConfig) -> Self {
+        Self { channel, buffer, config, names: Mutex::new(HashMap::new()) }
+    }
+
+    /// Whether messages from `group_id` are buffered.
+    pub fn monitors(&self, group_id: &str) -> bool {
+        self.config.group_ids.iter().any(|id| id == group_id)
+    }
+
+    /// Remember a group's display name, used when a message doesn't carry it.
+    pub fn set_group_name(&self, group_id: &str, name: &str) {
+        self.names.lock().unwrap().insert(group_id.to_string(), name.to_string());
+    }
+
+    /// Buffer a message if its group is monitored. `own` marks the bot's
+    /// own messages, kept only with `include_own_messages`. Returns whether
+    /// it was buffered.
+    pub fn record(
+        &self,
+        group_id: &str,
+        group_name: Option<&str>,
+        sender_name: &str,
+        content: &str,
+        timestamp: DateTime<Utc>,
+        own: bool,
+    ) -> bool {
+        if !self.monitors(group_id) || (own && !self.config.include_own_messages) || content.trim().is_empty() {
+            return false;
+        }
+        let group_name = match group_name {
+            Some(name) => {
+                self.set_group_name(group_id, name);
+                name.to_string()
+            }
+            None => self.names.lock().unwrap().get(group_id).cloned().unwrap_or_else(|| group_id.to_string()),
+        };
+        self.buffer.push(BufferedMessage {
+            sender_name: sender_name.to_string(),
+            content: content.to_string(),
+            timestamp,
+            group_id: group_id.to_string(),
+            group_name,
+            channel: self.channel.to_string(),
+        });
+        true
+    }
+}
+
+/// `[kind]`, followed by the caption when there is one.
+pub fn with_placeholder(kind: &str, caption: Option<&str>) -> String {
+    match caption.map(str::trim).filter(|c| !c.is_empty()) {
+        Some(caption) => format!("[{kind}] {caption}"),
+        None => format!("[{kind}]"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_filters_and_names() {
+        let buffer = MessageBuffer::new();
+        let monitor = GroupMonitor::new("zalo", buffer.clone(), SummarizeGroupsConfig {
+            group_ids: vec!["g1".into()],
+            include_own_messages: false,
+        });
+        assert!(!monitor.record("g2", None, "An", "hi", Utc::now(), false));
+        assert!(!monitor.record("g1", None, "Bot", "hi", Utc::now(), true));
+        assert!(!monitor.record("g1", None, "An", "  ", Utc::now(), false));
+
+        assert!(monitor.record("g1", None, "An", "first", Utc::now(), false));
+        monitor.set_group_name("g1", "Sales team");
+        assert!(monitor.record("g1", None, "Binh", "second", Utc::now(), false));
+        let messages = buffer.drain_group("g1");
+        let names: Vec<_> = messages.iter().map(|m| m.group_name.as_str()).collect();
+        assert_eq!(names, ["g1", "Sales team"]);
+        assert!(messages.iter().all(|m| m.channel == "zalo"));
+
+        assert_eq!(with_placeholder("photo", Some(" ")), "[photo]");
+        assert_eq!(with_placeholder("photo", Some("Bảng giá")), "[photo] Bảng giá");
+    }
+}
@@ -0,0 +1,151 @@
+//! Conversation history kept outside the agent process.
+//!
+//! [`HistoryChannel`] wraps a channel and records every message it receives
+//! and sends in a [`MessageHistory`], such as the platform's
+//! `channel_messages` table, so conversations survive an agent crash.
+
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bizclaw_core::error::Result;
+use bizclaw_core::traits::provider::TokenStream;
+use bizclaw_core::traits::{AgentProgress, Channel, ChannelHealth, DroppedMessage};
+use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
+use futures::stream::Stream;
+use tokio::sync::mpsc::UnboundedSender;
+
+/// Sender recorded for messages the bot sends.
+pub const BOT_SENDER: &str = "bot";
+
+/// Whether a message came from a user or was sent by the bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageDirection {
+    Inbound,
+    Outbound,
+}
+
+impl MessageDirection {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Inbound => "inbound",
+            Self::Outbound => "outbound",
+        }
+    }
+}
+
+/// Where channel messages are recorded.
+pub trait MessageHistory: Send + Sync {
+    fn record(&self, channel: &str, thread_id: &str, direction: MessageDirection, sender: &str, content: &str) -> Result<()>;
+}
+
+/// A channel whose received and sent messages are recorded in a
+/// [`MessageHistory`]. Failing to record only logs a warning.
+pub struct HistoryChannel<C> {
+    inner: C,
+    history: Arc<dyn MessageHistory>,
+}
+
+impl<C: Channel> HistoryChannel<C> {
+    pub fn new(inner: C, history: Arc<dyn MessageHistory>) -> Self {
+        Self { inner, history }
+    }
+
+    fn record(&self, thread_id: &str, direction: MessageDirection, sender: &str, content: &str) {
+        if let Err(e) = self.history.record(self.inner.name(), thread_id, direction, sender, content) {
+            tracing::warn!("{}: cannot record {} message: {e}", self.inner.name(), direction.as_str());
+        }
+    }
+}
+
+#[async_trait]
+impl<C: Channel> Channel for HistoryChannel<C> {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        self.inner.connect().await
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.inner.disconnect().await
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_connected()
+    }
+
+    async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
+        self.inner.listen().await
+    }
+
+    async fn start(&mut self, agent_tx: UnboundedSender<IncomingMessage>) -> Result<()> {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<IncomingMessage>();
+        self.inner.start(tx).await?;
+        let (channel, history) = (self.inner.name().to_string(), self.history.clone());
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                if let Err(e) = history.record(
+                    &channel, &message.thread_id, MessageDirection::Inbound, &message.sender_id, &message.content,
+                ) {
+                    tracing::warn!("{channel}: cannot record inbound message: {e}");
+                }
+                if agent_tx.send(message).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn health(&self) -> ChannelHealth {
+        self.inner.health()
+    }
+
+    fn force_reconnect(&self) -> Result<()> {
+        self.inner.force_reconnect()
+    }
+
+    fn on_dropped(&self) -> Option<DroppedMessage> {
+        self.inner.on_dropped()
+    }
+
+    async fn send(&self, message: OutgoingMessage) -> Result<()> {
+        let (thread_id, content) = (message.thread_id.clone(), message.content.clone());
+        self.inner.send(message).await?;
+        self.record(&thread_id, MessageDirection::Outbound, BOT_SENDER, &content);
+        Ok(())
+    }
+
+    async fn send_media(&self, thread_id: &str, thread_type: ThreadType, path: &Path) -> Result<()> {
+        self.inner.send_media(thread_id, thread_type, path).await?;
+        self.record(thread_id, MessageDirection::Outbound, BOT_SENDER, &format!("[file {}]", path.display()));
+        Ok(())
+    }
+
+    async fn send_streaming(&self, thread_id: &str, thread_type: ThreadType, tokens: TokenStream) -> Result<()> {
+        use futures::StreamExt;
+
+        // Keep a copy of the text as the inner channel consumes it.
+        let sent = Arc::new(Mutex::new(String::new()));
+        let copy = sent.clone();
+        let tokens = tokens.inspect(move |token| {
+            if let Ok(token) = token {
+                copy.lock().unwrap().push_str(token);
+            }
+        });
+        self.inner.send_streaming(thread_id, thread_type, Box::pin(tokens)).await?;
+        let content = std::mem::take(&mut *sent.lock().unwrap());
+        self.record(thread_id, MessageDirection::Outbound, BOT_SENDER, &content);
+        Ok(())
+    }
+
+    async fn send_typing(&self, thread_id: &str) -> Result<()> {
+        self.inner.send_typing(thread_id).await
+    }
+
+    async fn send_progress(&self, thread_id: &str, progress: &AgentProgress) -> Result<()> {
+        self.inner.send_progress(thread_id, progress).await
+    }
+}
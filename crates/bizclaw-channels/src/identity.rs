@@ -0,0 +1,75 @@
+//! One identity for a user across channels.
+//!
+//! A [`UserIdentities`] maps a channel's user ID to a canonical user ID, so
+//! someone who starts on Telegram and continues on Discord keeps the same
+//! conversation once their identities are linked.
+
+use bizclaw_core::error::Result;
+use bizclaw_core::types::{IncomingMessage, ThreadType};
+
+/// Where channel users are matched to canonical users, such as the
+/// platform's `user_identities` table.
+pub trait UserIdentities: Send + Sync {
+    /// The canonical ID of `channel_user_id` on `channel`, created on first sight.
+    fn resolve_user(&self, channel: &str, channel_user_id: &str) -> Result<String>;
+}
+
+/// The conversation `msg` belongs to: the sender's, shared across channels,
+/// for a direct chat whose sender resolves; otherwise the chat's own.
+pub fn session_id(msg: &IncomingMessage, identities: Option<&dyn UserIdentities>) -> String {
+    let chat = format!("{}:{}", msg.channel, msg.thread_id);
+    let Some(identities) = identities else { return chat };
+    if msg.thread_type != ThreadType::Direct || msg.sender_id.is_empty() {
+        return chat;
+    }
+    match identities.resolve_user(&msg.channel, &msg.sender_id) {
+        Ok(user) => format!("user:{user}"),
+        Err(e) => {
+            tracing::warn!("{}: cannot resolve user {}: {e}", msg.channel, msg.sender_id);
+            chat
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::error::BizClawError;
+
+    struct Linked;
+
+    impl UserIdentities for Linked {
+        fn resolve_user(&self, channel: &str, channel_user_id: &str) -> Result<String> {
+            match (channel, channel_user_id) {
+                ("telegram", "42") | ("discord", "minh#1") => Ok("u-minh".into()),
+                _ => Err(BizClawError::Memory("database is locked".into())),
+            }
+        }
+    }
+
+    fn message(channel: &str, sender: &str, thread_type: ThreadType) -> IncomingMessage {
+        IncomingMessage {
+            channel: channel.into(),
+            thread_id: format!("chat-{sender}"),
+            sender_id: sender.into(),
+            sender_name: None,
+            content: "xin chào".into(),
+            thread_type,
+            timestamp: chrono::Utc::now(),
+            reply_to: None,
+            attachments: Vec::new(),
+            message_id: None,
+        }
+    }
+
+    #[test]
+    fn test_session_id() {
+        let linked: &dyn UserIdentities = &Linked;
+        assert_eq!(session_id(&message("telegram", "42", ThreadType::Direct), Some(linked)), "user:u-minh");
+        assert_eq!(session_id(&message("discord", "minh#1", ThreadType::Direct), Some(linked)), "user:u-minh");
+        // Groups keep one conversation per chat; failures fall back to the chat.
+        assert_eq!(session_id(&message("telegram", "42", ThreadType::Group), Some(linked)), "telegram:chat-42");
+        assert_eq!(session_id(&message("zalo", "7", ThreadType::Direct), Some(linked)), "zalo:chat-7");
+        assert_eq!(session_id(&message("telegram", "42", ThreadType::Direct), None), "telegram:chat-42");
+    }
+}
@@ -8,3 +8,34 @@ pub mod whatsapp;
 pub mod webhook;
 pub mod zalo;
 pub mod email;
+pub mod stt;
+pub mod streaming;
+pub mod presence;
+pub mod quota;
+pub mod format;
+pub mod group_monitor;
+pub mod chat_settings;
+pub mod manager;
+pub mod dedup;
+pub mod router;
+pub mod history;
+pub mod identity;
+pub mod rate_limit;
+
+use std::sync::Arc;
+
+use bizclaw_core::config::ChannelConfig;
+use bizclaw_core::traits::Channel;
+
+/// Enabled channels that can send without a running listener (Telegram and
+/// Discord use plain HTTP calls), for pushing messages such as summaries.
+pub fn senders(config: &ChannelConfig) -> Vec<Arc<dyn Channel>> {
+    let mut senders: Vec<Arc<dyn Channel>> = Vec::new();
+    if let Some(tg) = config.telegram.as_ref().filter(|c| c.enabled) {
+        senders.push(Arc::new(telegram::TelegramChannel::new(tg.into())));
+    }
+    if let Some(dc) = config.discord.as_ref().filter(|c| c.enabled) {
+        senders.push(Arc::new(discord::DiscordChannel::new(dc.into())));
+    }
+    senders
+}
@@ -0,0 +1,383 @@
+//! Starts the enabled channels and routes outgoing messages to them.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bizclaw_core::config::{BizClawConfig, TelegramChannelConfig};
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::group_buffer::MessageBuffer;
+use bizclaw_core::traits::{Channel, ChannelHealth};
+use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
+use bizclaw_security::allowlist::Allowlist;
+use bizclaw_security::approval::ApprovalBroker;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::chat_settings::ChatSettingsStore;
+use crate::dedup::{DedupChannel, InboundDedup};
+use crate::history::{HistoryChannel, MessageHistory};
+
+/// Records a channel's connection status where operators can see it, such
+/// as the platform's `tenant_channels` table.
+pub trait ChannelStatusStore: Send + Sync {
+    /// `status` is `connected`, `disconnected` or `error`, with why.
+    fn set_status(&self, channel: &str, status: &str, message: Option<&str>) -> Result<()>;
+}
+
+/// The Telegram channel for `tg`. At autonomy level "approval" it prompts
+/// `approval_chat_ids` about the global broker's pending tool calls.
+fn telegram_channel(config: &BizClawConfig, tg: &TelegramChannelConfig) -> crate::telegram::TelegramChannel {
+    let channel = crate::telegram::TelegramChannel::new(tg.into())
+        .with_outgoing_files(Allowlist::new(&config.autonomy));
+    if config.autonomy.level != "approval" {
+        return channel;
+    }
+    if tg.approval_chat_ids.is_empty() {
+        tracing::warn!("channel.telegram.approval_chat_ids is empty; approve tool calls from the dashboard");
+        return channel;
+    }
+    channel.with_approvals(ApprovalBroker::global(), tg.approval_chat_ids.clone())
+}
+
+/// The running channels, by name.
+#[derive(Default)]
+pub struct ChannelManager {
+    channels: Vec<Arc<dyn Channel>>,
+    /// Per-chat settings of channels that keep them (Telegram).
+    chat_settings: HashMap<String, Arc<ChatSettingsStore>>,
+    /// Where channels added from now on record their messages.
+    history: Option<Arc<dyn MessageHistory>>,
+    /// Drops messages that channels added from now on deliver twice.
+    dedup: Option<Arc<InboundDedup>>,
+}
+
+impl ChannelManager {
+    /// Connect and start every enabled channel in `config` (or just `only`),
+    /// handing their messages to `agent_tx`. Channels that reconnect by
+    /// themselves report their status to `status`, every message received
+    /// or sent is recorded in `history`, and a message delivered again
+    /// within `channel.dedup_window_secs` is dropped.
+    pub async fn start(
+        config: &BizClawConfig,
+        only: Option<&str>,
+        agent_tx: UnboundedSender<IncomingMessage>,
+        status: Option<Arc<dyn ChannelStatusStore>>,
+        history: Option<Arc<dyn MessageHistory>>,
+    ) -> Result<Self> {
+        let wanted = |name: &str| only.is_none_or(|only| only == name);
+        let group_buffer = config.tools.group_summarizer.enabled.then(MessageBuffer::global);
+        let mut manager = Self { history, ..Self::default() };
+        if config.channel.dedup_window_secs > 0 {
+            match InboundDedup::open_default(std::time::Duration::from_secs(config.channel.dedup_window_secs)) {
+                Ok(dedup) => manager.dedup = Some(Arc::new(dedup)),
+                Err(e) => tracing::warn!("Repeated inbound messages won't be dropped: {e}"),
+            }
+        }
+
+        if wanted("zalo")
+            && let Some(zalo) = config.channel.zalo.as_ref().filter(|c| c.enabled)
+        {
+            let mut channel = crate::zalo::ZaloChannel::new(zalo.clone())
+                .with_outgoing_files(Allowlist::new(&config.autonomy));
+            if let Some(buffer) = &group_buffer {
+                channel = channel.with_group_buffer(buffer.clone());
+            }
+            if let Some(status) = &status {
+                channel = channel.with_status_store(status.clone());
+            }
+            if let Some(alert) = &zalo.personal.alert {
+                match crate::senders(&config.channel).into_iter().find(|s| s.name() == alert.channel) {
+                    Some(sender) => channel = channel.with_alert(sender, &alert.thread_id),
+                    None => tracing::warn!("Zalo alert channel '{}' is not enabled; alerts are off", alert.channel),
+                }
+            }
+            manager.add(channel, &agent_tx).await?;
+        }
+        if wanted("telegram")
+            && let Some(tg) = config.channel.telegram.as_ref().filter(|c| c.enabled)
+        {
+            let mut channel = telegram_channel(config, tg);
+            if let Some(buffer) = &group_buffer {
+                channel = channel.with_group_buffer(buffer.clone(), tg.summarize_groups.clone());
+            }
+            manager.chat_settings.insert("telegram".into(), channel.chat_settings());
+            manager.add(channel, &agent_tx).await?;
+        }
+        if wanted("discord")
+            && let Some(dc) = config.channel.discord.as_ref().filter(|c| c.enabled)
+        {
+            manager.add(crate::discord::DiscordChannel::new(dc.into()), &agent_tx).await?;
+        }
+        if wanted("whatsapp")
+            && let Some(wa) = config.channel.whatsapp.as_ref().filter(|c| c.enabled)
+        {
+            let mut channel = crate::whatsapp::WhatsAppChannel::new(wa.into());
+            match crate::whatsapp::WhatsAppStore::open_default() {
+                Ok(store) => channel = channel.with_store(Arc::new(store)),
+                Err(e) => tracing::warn!("WhatsApp sessions and deliveries won't be persisted: {e}"),
+            }
+            manager.add(channel, &agent_tx).await?;
+        }
+        if wanted("email")
+            && let Some(em) = config.channel.email.as_ref().filter(|c| c.enabled)
+        {
+            manager.add(crate::email::EmailChannel::new(em.into()), &agent_tx).await?;
+        }
+        if wanted("webhook")
+            && let Some(wh) = config.channel.webhook.as_ref().filter(|c| c.enabled)
+        {
+            let mut channel = crate::webhook::WebhookChannel::new(wh.into());
+            match crate::webhook::WebhookDeliveryLog::open_default() {
+                Ok(log) => channel = channel.with_delivery_log(Arc::new(log)),
+                Err(e) => tracing::warn!("Webhook deliveries won't be logged: {e}"),
+            }
+            manager.add(channel, &agent_tx).await?;
+        }
+        Ok(manager)
+    }
+
+    /// Record the messages of channels added from now on in `history`.
+    pub fn with_history(mut self, history: Arc<dyn MessageHistory>) -> Self {
+        self.history = Some(history);
+        self
+    }
+
+    /// Hand on only the first copy of each message of channels added from
+    /// now on.
+    pub fn with_dedup(mut self, dedup: Arc<InboundDedup>) -> Self {
+        self.dedup = Some(dedup);
+        self
+    }
+
+    /// Inbound messages dropped as repeats.
+    pub fn duplicates(&self) -> u64 {
+        self.dedup.as_ref().map_or(0, |dedup| dedup.duplicates())
+    }
+
+    /// Connect and start `channel`, then keep it for sending.
+    pub async fn add(&mut self, channel: impl Channel + 'static, agent_tx: &UnboundedSender<IncomingMessage>) -> Result<()> {
+        match self.dedup.clone() {
+            Some(dedup) => self.add_recorded(DedupChannel::new(channel, dedup), agent_tx).await,
+            None => self.add_recorded(channel, agent_tx).await,
+        }
+    }
+
+    async fn add_recorded(&mut self, channel: impl Channel + 'static, agent_tx: &UnboundedSender<IncomingMessage>) -> Result<()> {
+        match self.history.clone() {
+            Some(history) => self.start_channel(HistoryChannel::new(channel, history), agent_tx).await,
+            None => self.start_channel(channel, agent_tx).await,
+        }
+    }
+
+    async fn start_channel(&mut self, mut channel: impl Channel + 'static, agent_tx: &UnboundedSender<IncomingMessage>) -> Result<()> {
+        tracing::info!("Starting channel {}", channel.name());
+        channel.connect().await?;
+        channel.start(agent_tx.clone()).await?;
+        self.channels.push(Arc::new(channel));
+        Ok(())
+    }
+
+    pub fn channels(&self) -> &[Arc<dyn Channel>] {
+        &self.channels
+    }
+
+    /// The running channel called `name`.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn Channel>> {
+        self.channels.iter().find(|c| c.name() == name).cloned()
+    }
+
+    /// Make `channel` drop its connection and connect again now.
+    pub fn force_reconnect(&self, channel: &str) -> Result<()> {
+        self.get(channel)
+            .ok_or_else(|| BizClawError::Channel(format!("Channel '{channel}' is not running")))?
+            .force_reconnect()
+    }
+
+    /// Per-chat settings of `channel`, if it keeps them.
+    pub fn chat_settings(&self, channel: &str) -> Option<Arc<ChatSettingsStore>> {
+        self.chat_settings.get(channel).cloned()
+    }
+
+    /// Send `message` on the channel called `channel`.
+    pub async fn send(&self, channel: &str, message: OutgoingMessage) -> Result<()> {
+        let target = self.get(channel)
+            .ok_or_else(|| BizClawError::Channel(format!("Channel '{channel}' is not running")))?;
+        target.send(message).await
+    }
+
+    /// Health of each running channel.
+    pub fn health(&self) -> Vec<(String, ChannelHealth)> {
+        self.channels.iter().map(|c| (c.name().to_string(), c.health())).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::types::ThreadType;
+    use std::sync::Mutex;
+
+    /// Receives one message and records what it is sent.
+    #[derive(Default)]
+    struct FakeChannel {
+        connected: bool,
+        sent: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl Channel for FakeChannel {
+        fn name(&self) -> &str { "fake" }
+        async fn connect(&mut self) -> Result<()> {
+            self.connected = true;
+            Ok(())
+        }
+        async fn disconnect(&mut self) -> Result<()> { Ok(()) }
+        fn is_connected(&self) -> bool { self.connected }
+        async fn listen(&self) -> Result<Box<dyn futures::Stream<Item = IncomingMessage> + Send + Unpin>> {
+            Ok(Box::new(futures::stream::iter([IncomingMessage {
+                channel: "fake".into(),
+                thread_id: "t1".into(),
+                sender_id: "u1".into(),
+                sender_name: None,
+                content: "xin chào".into(),
+                thread_type: ThreadType::Direct,
+                timestamp: chrono::Utc::now(),
+                reply_to: None,
+                attachments: Vec::new(),
+                message_id: None,
+            }])))
+        }
+        async fn send(&self, message: OutgoingMessage) -> Result<()> {
+            self.sent.lock().unwrap().push(message.content);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_starts_channels_and_routes_replies() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let fake = FakeChannel::default();
+        let sent = fake.sent.clone();
+        let mut manager = ChannelManager::default();
+        manager.add(fake, &tx).await.unwrap();
+
+        assert_eq!(manager.health(), vec![("fake".to_string(), ChannelHealth::Healthy)]);
+        let incoming = rx.recv().await.unwrap();
+        assert_eq!(incoming.content, "xin chào");
+
+        let reply = |content: &str| OutgoingMessage {
+            thread_id: incoming.thread_id.clone(),
+            content: content.into(),
+            thread_type: ThreadType::Direct,
+            reply_to: None,
+        };
+        manager.send(&incoming.channel, reply("chào bạn")).await.unwrap();
+        assert_eq!(*sent.lock().unwrap(), ["chào bạn"]);
+        let err = manager.send("discord", reply("?")).await.unwrap_err();
+        assert!(err.to_string().contains("'discord' is not running"), "{err}");
+    }
+
+    #[test]
+    fn test_telegram_prompts_approval_chats() {
+        let tg: TelegramChannelConfig = serde_json::from_value(serde_json::json!({
+            "enabled": true,
+            "bot_token": "t",
+            "approval_chat_ids": [42],
+        })).unwrap();
+        let mut config = BizClawConfig::default();
+
+        config.autonomy.level = "approval".into();
+        let channel = telegram_channel(&config, &tg);
+        let (broker, chat_ids) = channel.approvals().expect("approval prompts are wired");
+        assert!(Arc::ptr_eq(broker, &ApprovalBroker::global()));
+        assert_eq!(chat_ids, [42]);
+
+        config.autonomy.level = "supervised".into();
+        assert!(telegram_channel(&config, &tg).approvals().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_records_history() {
+        use crate::history::MessageDirection;
+
+        #[derive(Default)]
+        struct Recorded(Mutex<Vec<(String, MessageDirection, String, String)>>);
+        impl MessageHistory for Recorded {
+            fn record(&self, channel: &str, _thread_id: &str, direction: MessageDirection, sender: &str, content: &str) -> Result<()> {
+                self.0.lock().unwrap().push((channel.into(), direction, sender.into(), content.into()));
+                Ok(())
+            }
+        }
+
+        let history = Arc::new(Recorded::default());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut manager = ChannelManager::default().with_history(history.clone());
+        manager.add(FakeChannel::default(), &tx).await.unwrap();
+        let incoming = rx.recv().await.unwrap();
+        manager.send("fake", OutgoingMessage {
+            thread_id: incoming.thread_id,
+            content: "chào bạn".into(),
+            thread_type: ThreadType::Direct,
+            reply_to: None,
+        }).await.unwrap();
+
+        let recorded = history.0.lock().unwrap();
+        assert_eq!(*recorded, [
+            ("fake".to_string(), MessageDirection::Inbound, "u1".to_string(), "xin chào".to_string()),
+            ("fake".to_string(), MessageDirection::Outbound, "bot".to_string(), "chào bạn".to_string()),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn test_drops_redelivered_messages() {
+        use crate::history::MessageDirection;
+
+        /// Delivers its first message three times, like a retrying webhook.
+        struct Redelivering;
+
+        #[async_trait::async_trait]
+        impl Channel for Redelivering {
+            fn name(&self) -> &str { "webhook" }
+            async fn connect(&mut self) -> Result<()> { Ok(()) }
+            async fn disconnect(&mut self) -> Result<()> { Ok(()) }
+            fn is_connected(&self) -> bool { true }
+            async fn listen(&self) -> Result<Box<dyn futures::Stream<Item = IncomingMessage> + Send + Unpin>> {
+                let message = |id: &str, content: &str| IncomingMessage {
+                    channel: "webhook".into(),
+                    thread_id: "crm-7".into(),
+                    sender_id: "crm-7".into(),
+                    sender_name: None,
+                    content: content.into(),
+                    thread_type: ThreadType::Direct,
+                    timestamp: chrono::Utc::now(),
+                    reply_to: None,
+                    attachments: Vec::new(),
+                    message_id: Some(id.into()),
+                };
+                let order = message("evt-1", "Đặt 2 áo size M");
+                Ok(Box::new(futures::stream::iter([order.clone(), order.clone(), order, message("evt-2", "Hủy đơn")])))
+            }
+            async fn send(&self, _message: OutgoingMessage) -> Result<()> { Ok(()) }
+        }
+
+        #[derive(Default)]
+        struct Inbound(Mutex<Vec<String>>);
+        impl MessageHistory for Inbound {
+            fn record(&self, _channel: &str, _thread_id: &str, direction: MessageDirection, _sender: &str, content: &str) -> Result<()> {
+                assert_eq!(direction, MessageDirection::Inbound);
+                self.0.lock().unwrap().push(content.into());
+                Ok(())
+            }
+        }
+
+        let dedup = InboundDedup::open(std::path::Path::new(":memory:"), std::time::Duration::from_secs(60)).unwrap();
+        let history = Arc::new(Inbound::default());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut manager = ChannelManager::default().with_history(history.clone()).with_dedup(Arc::new(dedup));
+        manager.add(Redelivering, &tx).await.unwrap();
+
+        assert_eq!(rx.recv().await.unwrap().content, "Đặt 2 áo size M");
+        assert_eq!(rx.recv().await.unwrap().content, "Hủy đơn");
+        assert_eq!(manager.duplicates(), 2);
+        assert_eq!(*history.0.lock().unwrap(), ["Đặt 2 áo size M", "Hủy đơn"]);
+    }
+}
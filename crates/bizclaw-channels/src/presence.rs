@@ -0,0 +1,125 @@
+//! Typing indicators while the agent works on a reply.
+//!
+//! Chats show a typing indicator for a few seconds only (5 on Telegram, 10
+//! on Discord), so it is sent again every `typing_interval_secs` until the
+//! turn ends, and on each step the agent reports.
+
+use bizclaw_core::config::PresenceConfig;
+use bizclaw_core::traits::{AgentProgress, Channel};
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+/// Run `turn`, keeping `channel`'s typing indicator up in `thread_id` and
+/// passing the agent's `progress` on, as `config` says.
+pub async fn while_busy<T>(
+    channel: &dyn Channel,
+    thread_id: &str,
+    config: &PresenceConfig,
+    progress: UnboundedReceiver<AgentProgress>,
+    turn: impl Future<Output = T>,
+) -> T {
+    if !config.typing {
+        return turn.await;
+    }
+    let every = Duration::from_secs(config.typing_interval_secs.max(1));
+    show_while(channel, thread_id, every, progress, turn).await
+}
+
+async fn show_while<T>(
+    channel: &dyn Channel,
+    thread_id: &str,
+    every: Duration,
+    mut progress: UnboundedReceiver<AgentProgress>,
+    turn: impl Future<Output = T>,
+) -> T {
+    tokio::pin!(turn);
+    let mut ticker = tokio::time::interval(every);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut progress_open = true;
+    loop {
+        tokio::select! {
+            biased;
+            out = &mut turn => return out,
+            step = progress.recv(), if progress_open => match step {
+                Some(step) => {
+                    if let Err(e) = channel.send_progress(thread_id, &step).await {
+                        tracing::debug!("{}: progress update failed: {e}", channel.name());
+                    }
+                    ticker.reset();
+                }
+                None => progress_open = false,
+            },
+            _ = ticker.tick() => {
+                if let Err(e) = channel.send_typing(thread_id).await {
+                    tracing::debug!("{}: typing indicator failed: {e}", channel.name());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use bizclaw_core::error::Result;
+    use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
+    use std::sync::Mutex;
+    use tokio_stream::Stream;
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl Channel for Recorder {
+        fn name(&self) -> &str { "test" }
+        async fn connect(&mut self) -> Result<()> { Ok(()) }
+        async fn disconnect(&mut self) -> Result<()> { Ok(()) }
+        fn is_connected(&self) -> bool { true }
+        async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
+            Ok(Box::new(futures::stream::pending()))
+        }
+        async fn send(&self, _message: OutgoingMessage) -> Result<()> { Ok(()) }
+        async fn send_typing(&self, thread_id: &str) -> Result<()> {
+            self.0.lock().unwrap().push(format!("typing {thread_id}"));
+            Ok(())
+        }
+        async fn send_progress(&self, thread_id: &str, progress: &AgentProgress) -> Result<()> {
+            self.0.lock().unwrap().push(format!("{progress:?} {thread_id}"));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_typing_repeats_until_turn_ends() {
+        let channel = Recorder::default();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let turn = async move {
+            tx.send(AgentProgress::ToolCall { name: "web_search".into() }).unwrap();
+            tokio::time::sleep(Duration::from_millis(230)).await;
+            42
+        };
+        let out = show_while(&channel, "c1", Duration::from_millis(50), rx, turn).await;
+        assert_eq!(out, 42);
+
+        let log = channel.0.lock().unwrap().clone();
+        // The step stands in for the first typing indicator.
+        assert_eq!(log[0], "ToolCall { name: \"web_search\" } c1");
+        let typing = log.iter().filter(|l| l.starts_with("typing")).count();
+        assert!((3..=6).contains(&typing), "{log:?}");
+
+        // Nothing is sent once the turn is over.
+        tokio::time::sleep(Duration::from_millis(120)).await;
+        assert_eq!(channel.0.lock().unwrap().iter().filter(|l| l.starts_with("typing")).count(), typing);
+    }
+
+    #[tokio::test]
+    async fn test_typing_off() {
+        let channel = Recorder::default();
+        let (_tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let config = PresenceConfig { typing: false, ..PresenceConfig::default() };
+        assert_eq!(while_busy(&channel, "c1", &config, rx, async { "done" }).await, "done");
+        assert!(channel.0.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+//! The tenant's daily message quota on channel chats.
+//!
+//! Messages from Telegram, Zalo, Discord and the other channels count
+//! against the same plan limit as the dashboard chat: each is checked and
+//! counted before the agent answers it.
+
+use bizclaw_core::quota::MessageQuota;
+use bizclaw_core::traits::Channel;
+use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
+
+/// Count `message` against `quota`. Once today's quota is used up the
+/// message is refused: false, after telling the chat why.
+pub async fn admit(quota: &dyn MessageQuota, channel: &dyn Channel, message: &IncomingMessage) -> bool {
+    let Err(e) = quota.check_quota().and_then(|_| quota.record_message()) else {
+        return true;
+    };
+    tracing::info!("{}: message in chat {} refused: {e}", channel.name(), message.thread_id);
+    let notice = OutgoingMessage {
+        thread_id: message.thread_id.clone(),
+        content: e.to_string(),
+        thread_type: message.thread_type.clone(),
+        reply_to: None,
+    };
+    if let Err(e) = channel.send(notice).await {
+        tracing::warn!("{}: cannot tell chat {} about the quota: {e}", channel.name(), message.thread_id);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use bizclaw_core::error::{BizClawError, Result};
+    use bizclaw_core::quota::Remaining;
+    use bizclaw_core::types::ThreadType;
+    use std::sync::Mutex;
+    use tokio_stream::Stream;
+
+    struct DailyQuota {
+        limit: u32,
+        used: Mutex<u32>,
+    }
+
+    impl MessageQuota for DailyQuota {
+        fn check_quota(&self) -> Result<Remaining> {
+            let remaining = Remaining { used: *self.used.lock().unwrap(), limit: self.limit };
+            if remaining.exceeded() {
+                return Err(BizClawError::QuotaExceeded(format!("{} messages a day", self.limit)));
+            }
+            Ok(remaining)
+        }
+
+        fn record_message(&self) -> Result<()> {
+            *self.used.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<OutgoingMessage>>);
+
+    #[async_trait]
+    impl Channel for Recorder {
+        fn name(&self) -> &str { "test" }
+        async fn connect(&mut self) -> Result<()> { Ok(()) }
+        async fn disconnect(&mut self) -> Result<()> { Ok(()) }
+        fn is_connected(&self) -> bool { true }
+        async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
+            Ok(Box::new(futures::stream::pending()))
+        }
+        async fn send(&self, message: OutgoingMessage) -> Result<()> {
+            self.0.lock().unwrap().push(message);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_over_quota_message_refused() {
+        let quota = DailyQuota { limit: 2, used: Mutex::new(0) };
+        let channel = Recorder::default();
+        let message = IncomingMessage {
+            channel: "test".into(), thread_id: "c1".into(), sender_id: "u1".into(), sender_name: None,
+            content: "hi".into(), thread_type: ThreadType::Direct, timestamp: chrono::Utc::now(),
+            reply_to: None, attachments: Vec::new(), message_id: None,
+        };
+
+        assert!(admit(&quota, &channel, &message).await);
+        assert!(admit(&quota, &channel, &message).await);
+        assert!(channel.0.lock().unwrap().is_empty());
+
+        assert!(!admit(&quota, &channel, &message).await);
+        assert_eq!(*quota.used.lock().unwrap(), 2);
+        let sent = channel.0.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].thread_id, "c1");
+        assert!(sent[0].content.contains("Quota exceeded"), "{}", sent[0].content);
+    }
+}
@@ -0,0 +1,182 @@
+//! Limits on outgoing messages, shared by the channels' send paths.
+//!
+//! Each limit is a sliding window: the sends of the last minute (or hour)
+//! are remembered, and a send that would go over waits until the oldest one
+//! leaves the window. Sends that would wait longer than `max_wait_secs`
+//! fail with [`BizClawError::RateLimited`] instead.
+
+use bizclaw_core::config::ChannelRateLimitConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(3600);
+/// Chats remembered before those with no recent sends are dropped.
+const MAX_IDLE_CHATS: usize = 1024;
+
+/// Sliding-window limits on one channel's sends, overall and per chat.
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: ChannelRateLimitConfig,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// Sends over all chats in the last hour, oldest first.
+    sent: VecDeque<Instant>,
+    /// Sends to each chat in the last minute, oldest first.
+    per_chat: HashMap<String, VecDeque<Instant>>,
+    /// No sends until then (after the service pushed back).
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    /// `None` when `config` sets no limits.
+    pub fn new(config: &ChannelRateLimitConfig) -> Option<Self> {
+        let limited = config.max_messages_per_minute > 0
+            || config.max_messages_per_hour > 0
+            || config.max_per_chat_per_minute > 0;
+        limited.then(|| Self { config: config.clone(), state: Mutex::new(State::default()) })
+    }
+
+    /// Wait until a message to `chat` is within the limits, and count it.
+    /// Fails without waiting when that would take over `max_wait_secs`.
+    pub async fn acquire(&self, channel: &str, chat: &str) -> Result<()> {
+        let max_wait = Duration::from_secs(self.config.max_wait_secs);
+        let deadline = Instant::now() + max_wait;
+        loop {
+            let now = Instant::now();
+            let wait = match self.try_acquire_at(chat, now) {
+                Ok(()) => return Ok(()),
+                Err(wait) => wait,
+            };
+            if now + wait > deadline {
+                tracing::warn!("{channel}: not sending to {chat}, rate limited for {}s", wait.as_secs().max(1));
+                return Err(BizClawError::RateLimited(format!(
+                    "{channel}: send limit reached, next message to {chat} allowed in {}s", wait.as_secs().max(1)
+                )));
+            }
+            tracing::debug!("{channel}: delaying message to {chat} by {wait:?} (rate limit)");
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Count a message to `chat` if the limits allow it now; otherwise how
+    /// long until they would.
+    pub fn try_acquire(&self, chat: &str) -> std::result::Result<(), Duration> {
+        self.try_acquire_at(chat, Instant::now())
+    }
+
+    fn try_acquire_at(&self, chat: &str, now: Instant) -> std::result::Result<(), Duration> {
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        let mut wait = state.paused_until.map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+
+        while state.sent.front().is_some_and(|&t| now.saturating_duration_since(t) >= HOUR) {
+            state.sent.pop_front();
+        }
+        let last_minute = state.sent.iter().rev().take_while(|&&t| now.saturating_duration_since(t) < MINUTE).count();
+        if let Some(w) = window_wait(&state.sent, last_minute, self.config.max_messages_per_minute, MINUTE, now) {
+            wait = wait.max(w);
+        }
+        if let Some(w) = window_wait(&state.sent, state.sent.len(), self.config.max_messages_per_hour, HOUR, now) {
+            wait = wait.max(w);
+        }
+
+        if state.per_chat.len() > MAX_IDLE_CHATS {
+            state.per_chat.retain(|_, sent| sent.back().is_some_and(|&t| now.saturating_duration_since(t) < MINUTE));
+        }
+        let chat_sent = state.per_chat.entry(chat.to_string()).or_default();
+        while chat_sent.front().is_some_and(|&t| now.saturating_duration_since(t) >= MINUTE) {
+            chat_sent.pop_front();
+        }
+        if let Some(w) = window_wait(chat_sent, chat_sent.len(), self.config.max_per_chat_per_minute, MINUTE, now) {
+            wait = wait.max(w);
+        }
+
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        chat_sent.push_back(now);
+        state.sent.push_back(now);
+        Ok(())
+    }
+
+    /// Hold all sends for `duration`, e.g. after the service refused one
+    /// for sending too fast.
+    pub fn pause(&self, duration: Duration) {
+        let until = Instant::now() + duration;
+        let mut state = self.state.lock().unwrap();
+        state.paused_until = Some(state.paused_until.map_or(until, |current| current.max(until)));
+    }
+}
+
+/// How long until one of the `in_window` latest sends of `sent` leaves a
+/// `window` that allows `limit` of them; `None` if there is room (or no limit).
+fn window_wait(sent: &VecDeque<Instant>, in_window: usize, limit: u32, window: Duration, now: Instant) -> Option<Duration> {
+    let limit = limit as usize;
+    if limit == 0 || in_window < limit {
+        return None;
+    }
+    // The send that has to leave the window for a new one to fit.
+    let oldest = sent[sent.len() - limit];
+    Some((oldest + window).saturating_duration_since(now))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(per_minute: u32, per_hour: u32, per_chat: u32) -> RateLimiter {
+        RateLimiter::new(&ChannelRateLimitConfig {
+            max_messages_per_minute: per_minute,
+            max_messages_per_hour: per_hour,
+            max_per_chat_per_minute: per_chat,
+            max_wait_secs: 0,
+        }).unwrap()
+    }
+
+    #[test]
+    fn test_per_chat_sliding_window() {
+        let limiter = limits(0, 0, 2);
+        let start = Instant::now();
+        assert!(limiter.try_acquire_at("a", start).is_ok());
+        assert!(limiter.try_acquire_at("a", start + Duration::from_secs(20)).is_ok());
+        // Third message within the minute waits for the first to age out.
+        assert_eq!(limiter.try_acquire_at("a", start + Duration::from_secs(30)), Err(Duration::from_secs(30)));
+        // Other chats have their own window.
+        assert!(limiter.try_acquire_at("b", start + Duration::from_secs(30)).is_ok());
+        assert!(limiter.try_acquire_at("a", start + Duration::from_secs(60)).is_ok());
+        assert_eq!(limiter.try_acquire_at("a", start + Duration::from_secs(61)), Err(Duration::from_secs(19)));
+    }
+
+    #[test]
+    fn test_channel_wide_limits() {
+        let limiter = limits(3, 4, 0);
+        let start = Instant::now();
+        for (i, chat) in ["a", "b", "c"].iter().enumerate() {
+            assert!(limiter.try_acquire_at(chat, start + Duration::from_secs(i as u64)).is_ok());
+        }
+        assert_eq!(limiter.try_acquire_at("d", start + Duration::from_secs(10)), Err(Duration::from_secs(50)));
+        assert!(limiter.try_acquire_at("d", start + Duration::from_secs(60)).is_ok());
+        // Four in the hour: the next waits for the first to be an hour old.
+        assert_eq!(limiter.try_acquire_at("e", start + Duration::from_secs(120)), Err(Duration::from_secs(3480)));
+        assert!(limiter.try_acquire_at("e", start + HOUR).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_fails_past_max_wait() {
+        let limiter = limits(0, 0, 1);
+        limiter.acquire("telegram", "42").await.unwrap();
+        let err = limiter.acquire("telegram", "42").await.unwrap_err();
+        assert!(matches!(err, BizClawError::RateLimited(_)), "{err}");
+        assert!(err.to_string().contains("next message to 42 allowed in"), "{err}");
+
+        limiter.pause(Duration::from_secs(30));
+        assert!(limiter.try_acquire("7").unwrap_err() > Duration::from_secs(29));
+        assert!(RateLimiter::new(&ChannelRateLimitConfig { max_per_chat_per_minute: 0, ..Default::default() }).is_none());
+    }
+}
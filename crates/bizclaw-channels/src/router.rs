@@ -0,0 +1,242 @@
+//! Pushes messages to any running channel by destination, for the API,
+//! the scheduler and the platform ("notify all admins on Telegram and
+//! email").
+//!
+//! Every channel's inbound messages already reach one agent pipeline (see
+//! [`crate::manager::ChannelManager::start`]); [`Destination::of`] is where
+//! a message came from, so something can be sent back there later.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Channel;
+use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
+use serde::{Deserialize, Serialize};
+
+use crate::manager::ChannelManager;
+
+/// A chat, user or address on one channel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Destination {
+    pub channel: String,
+    /// The channel's id for it: a chat id, a phone number, an email address.
+    pub to: String,
+    /// Direct when not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_type: Option<ThreadType>,
+}
+
+impl Destination {
+    pub fn new(channel: &str, to: &str) -> Self {
+        Self { channel: channel.to_string(), to: to.to_string(), thread_type: None }
+    }
+
+    /// The chat `message` came from.
+    pub fn of(message: &IncomingMessage) -> Self {
+        Self {
+            channel: message.channel.clone(),
+            to: message.thread_id.clone(),
+            thread_type: Some(message.thread_type.clone()),
+        }
+    }
+
+    fn thread_type(&self) -> ThreadType {
+        self.thread_type.clone().unwrap_or(ThreadType::Direct)
+    }
+}
+
+/// Delivers text and files to destinations on one channel.
+#[async_trait]
+pub trait Sender: Send + Sync {
+    /// The channel it sends on.
+    fn channel(&self) -> &str;
+
+    async fn send_text(&self, to: &Destination, text: &str) -> Result<()>;
+
+    async fn send_file(&self, to: &Destination, path: &Path) -> Result<()>;
+}
+
+#[async_trait]
+impl Sender for Arc<dyn Channel> {
+    fn channel(&self) -> &str {
+        self.name()
+    }
+
+    async fn send_text(&self, to: &Destination, text: &str) -> Result<()> {
+        self.send(OutgoingMessage {
+            thread_id: to.to.clone(),
+            content: text.to_string(),
+            thread_type: to.thread_type(),
+            reply_to: None,
+        }).await
+    }
+
+    async fn send_file(&self, to: &Destination, path: &Path) -> Result<()> {
+        self.send_media(&to.to, to.thread_type(), path).await
+    }
+}
+
+/// How sending to one destination went.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeliveryResult {
+    pub channel: String,
+    pub to: String,
+    pub ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// The channels messages can be pushed to, by name.
+#[derive(Default)]
+pub struct ChannelRouter {
+    senders: HashMap<String, Arc<dyn Sender>>,
+}
+
+impl ChannelRouter {
+    /// Send through `sender`, in place of any other for its channel.
+    pub fn register(&mut self, sender: Arc<dyn Sender>) {
+        self.senders.insert(sender.channel().to_string(), sender);
+    }
+
+    /// Names of the channels it can send on, sorted.
+    pub fn channels(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.senders.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    fn sender(&self, to: &Destination) -> Result<&Arc<dyn Sender>> {
+        if to.to.trim().is_empty() {
+            return Err(BizClawError::Channel(format!("No '{}' destination given", to.channel)));
+        }
+        self.senders.get(&to.channel)
+            .ok_or_else(|| BizClawError::Channel(format!("Unknown channel '{}'", to.channel)))
+    }
+
+    pub async fn send_text(&self, to: &Destination, text: &str) -> Result<()> {
+        self.sender(to)?.send_text(to, text).await
+    }
+
+    pub async fn send_file(&self, to: &Destination, path: &Path) -> Result<()> {
+        self.sender(to)?.send_file(to, path).await
+    }
+
+    /// Send `text`, then the file at `file`, to `to`.
+    pub async fn send(&self, to: &Destination, text: Option<&str>, file: Option<&Path>) -> DeliveryResult {
+        let sent = async {
+            if let Some(text) = text {
+                self.send_text(to, text).await?;
+            }
+            if let Some(file) = file {
+                self.send_file(to, file).await?;
+            }
+            Ok::<_, BizClawError>(())
+        }.await;
+        DeliveryResult {
+            channel: to.channel.clone(),
+            to: to.to.clone(),
+            ok: sent.is_ok(),
+            error: sent.err().map(|e| e.to_string()),
+        }
+    }
+
+    /// [`send`](Self::send) to every destination at once; the results are
+    /// in the same order.
+    pub async fn broadcast(&self, destinations: &[Destination], text: Option<&str>, file: Option<&Path>) -> Vec<DeliveryResult> {
+        futures::future::join_all(destinations.iter().map(|to| self.send(to, text, file))).await
+    }
+}
+
+impl From<&ChannelManager> for ChannelRouter {
+    fn from(manager: &ChannelManager) -> Self {
+        Self::from(manager.channels().to_vec())
+    }
+}
+
+impl From<Vec<Arc<dyn Channel>>> for ChannelRouter {
+    fn from(channels: Vec<Arc<dyn Channel>>) -> Self {
+        let mut router = Self::default();
+        for channel in channels {
+            router.register(Arc::new(channel));
+        }
+        router
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Records what it sends; fails for `down`.
+    struct FakeSender {
+        name: &'static str,
+        sent: Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl Sender for FakeSender {
+        fn channel(&self) -> &str { self.name }
+
+        async fn send_text(&self, to: &Destination, text: &str) -> Result<()> {
+            if to.to == "down" {
+                return Err(BizClawError::Channel("503 Service Unavailable".into()));
+            }
+            self.sent.lock().unwrap().push(format!("{}: {text}", to.to));
+            Ok(())
+        }
+
+        async fn send_file(&self, to: &Destination, path: &Path) -> Result<()> {
+            self.sent.lock().unwrap().push(format!("{}: [file {}]", to.to, path.display()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_reports_each_destination() {
+        let telegram = Arc::new(FakeSender { name: "telegram", sent: Mutex::default() });
+        let email = Arc::new(FakeSender { name: "email", sent: Mutex::default() });
+        let mut router = ChannelRouter::default();
+        router.register(telegram.clone());
+        router.register(email.clone());
+        assert_eq!(router.channels(), ["email", "telegram"]);
+
+        let destinations = [
+            Destination::new("telegram", "42"),
+            Destination::new("email", "admin@shop.vn"),
+            Destination::new("telegram", "down"),
+            Destination::new("sms", "0901234567"),
+            Destination::new("email", " "),
+        ];
+        let results = router.broadcast(&destinations, Some("Đơn mới #88"), Some(Path::new("/tmp/don.pdf"))).await;
+        let outcome: Vec<(&str, bool)> = results.iter().map(|r| (r.to.as_str(), r.ok)).collect();
+        assert_eq!(outcome, [("42", true), ("admin@shop.vn", true), ("down", false), ("0901234567", false), (" ", false)]);
+        assert!(results[2].error.as_deref().unwrap().contains("503"));
+        assert!(results[3].error.as_deref().unwrap().contains("Unknown channel 'sms'"));
+        assert_eq!(*telegram.sent.lock().unwrap(), ["42: Đơn mới #88", "42: [file /tmp/don.pdf]"]);
+        assert_eq!(email.sent.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_destination_of_incoming_message() {
+        let message = IncomingMessage {
+            channel: "zalo".into(),
+            thread_id: "g-1".into(),
+            sender_id: "u1".into(),
+            sender_name: None,
+            content: "hi".into(),
+            thread_type: ThreadType::Group,
+            timestamp: chrono::Utc::now(),
+            reply_to: None,
+            attachments: Vec::new(),
+            message_id: None,
+        };
+        let origin = Destination::of(&message);
+        assert_eq!((origin.channel.as_str(), origin.to.as_str(), origin.thread_type()), ("zalo", "g-1", ThreadType::Group));
+        let parsed: Destination = serde_json::from_str(r#"{"channel": "telegram", "to": "42"}"#).unwrap();
+        assert_eq!(parsed.thread_type(), ThreadType::Direct);
+    }
+}
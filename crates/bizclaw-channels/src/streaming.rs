@@ -0,0 +1,247 @@
+//! Shows a response while it is generated by editing the sent message.
+//!
+//! A placeholder is sent first, then edited with the text so far, at most
+//! once per edit interval so the chat's rate limits hold. Text beyond the
+//! channel's message limit continues in follow-up messages.
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::provider::TokenStream;
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Placeholder sent before the first token arrives.
+pub const PLACEHOLDER: &str = "Typing...";
+/// Default least time between edits.
+pub const EDIT_INTERVAL: Duration = Duration::from_millis(1000);
+
+/// A chat that can post, edit and delete plain-text messages.
+#[async_trait]
+pub(crate) trait EditableChat: Send + Sync {
+    /// Most characters allowed in one message.
+    const MAX_CHARS: usize;
+
+    /// Post a message and return its id.
+    async fn post(&self, text: &str) -> Result<String>;
+
+    /// Replace the text of a posted message.
+    async fn edit(&self, message_id: &str, text: &str) -> Result<()>;
+
+    /// Remove a posted message.
+    async fn delete(&self, message_id: &str) -> Result<()>;
+}
+
+/// Relay `tokens` into `chat`, editing no more often than `edit_interval`,
+/// and return the full response.
+///
+/// A failed edit is retried with the next one. When the response fails
+/// before any text arrived, or the final edit fails, the placeholder is
+/// deleted rather than left behind.
+pub(crate) async fn relay<C: EditableChat>(chat: &C, mut tokens: TokenStream, edit_interval: Duration) -> Result<String> {
+    let mut messages = vec![(chat.post(PLACEHOLDER).await?, PLACEHOLDER.to_string())];
+    let mut text = String::new();
+    let mut dirty = false;
+    let mut failure = None;
+
+    let mut ticker = tokio::time::interval_at(Instant::now() + edit_interval, edit_interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        tokio::select! {
+            token = tokens.next() => match token {
+                Some(Ok(token)) => {
+                    text.push_str(&token);
+                    dirty = true;
+                }
+                Some(Err(e)) => {
+                    failure = Some(e);
+                    break;
+                }
+                None => break,
+            },
+            _ = ticker.tick(), if dirty => {
+                if let Err(e) = show(chat, &mut messages, &text).await {
+                    tracing::warn!("Streamed reply: edit failed, retrying with the next one: {e}");
+                }
+                dirty = false;
+            }
+        }
+    }
+
+    // Final edit, so nothing is left behind the placeholder or a stale chunk.
+    let shown = match &failure {
+        Some(e) if text.trim().is_empty() => {
+            tracing::warn!("Response stream failed: {e}");
+            Err(BizClawError::Channel("no text to show".into()))
+        }
+        Some(e) => {
+            tracing::warn!("Response stream failed: {e}");
+            show(chat, &mut messages, &format!("{text}\n\n⚠️ Response interrupted.")).await
+        }
+        None if text.trim().is_empty() => show(chat, &mut messages, "(empty response)").await,
+        None => show(chat, &mut messages, &text).await,
+    };
+    if let Err(e) = &shown {
+        for (id, _) in messages.iter().filter(|(_, shown)| shown == PLACEHOLDER) {
+            if let Err(e) = chat.delete(id).await {
+                tracing::warn!("Streamed reply: could not delete the placeholder: {e}");
+            }
+        }
+        if failure.is_none() {
+            return Err(BizClawError::Channel(format!("Final edit of a streamed reply failed: {e}")));
+        }
+    }
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(text),
+    }
+}
+
+/// Bring the posted messages up to date with `text`, editing the ones whose
+/// chunk changed and posting new ones for overflow.
+///
+/// Chunks are trimmed as the chat APIs do, so an edit that only adds
+/// whitespace isn't sent (Telegram rejects it as "not modified").
+async fn show<C: EditableChat>(chat: &C, messages: &mut Vec<(String, String)>, text: &str) -> Result<()> {
+    let chunks = split_message(text, C::MAX_CHARS).into_iter().map(str::trim).filter(|c| !c.is_empty());
+    for (i, chunk) in chunks.enumerate() {
+        match messages.get_mut(i) {
+            Some((id, shown)) if shown != chunk => {
+                chat.edit(id, chunk).await?;
+                *shown = chunk.to_string();
+            }
+            Some(_) => {}
+            None => {
+                let id = chat.post(chunk).await?;
+                messages.push((id, chunk.to_string()));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Split `text` into chunks of at most `max_chars` characters, preferring to
+/// break after a newline, then after a space.
+pub fn split_message(text: &str, max_chars: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+    while rest.chars().count() > max_chars {
+        let hard = rest.char_indices().nth(max_chars).map_or(rest.len(), |(i, _)| i);
+        let window = &rest[..hard];
+        let cut = window.rfind('\n')
+            .or_else(|| window.rfind(' '))
+            .filter(|&i| i > 0)
+            .map_or(hard, |i| i + 1);
+        chunks.push(&rest[..cut]);
+        rest = &rest[cut..];
+    }
+    if !rest.is_empty() || chunks.is_empty() {
+        chunks.push(rest);
+    }
+    chunks
+}
+
+/// Error for a chat API call that returned no message id.
+pub(crate) fn missing_id(api: &str) -> BizClawError {
+    BizClawError::Channel(format!("{api}: response has no message id"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FakeChat {
+        log: Mutex<Vec<String>>,
+        fail_edits: bool,
+    }
+
+    #[async_trait]
+    impl EditableChat for FakeChat {
+        const MAX_CHARS: usize = 10;
+
+        async fn post(&self, text: &str) -> Result<String> {
+            let mut log = self.log.lock().unwrap();
+            log.push(format!("post {text}"));
+            Ok(log.len().to_string())
+        }
+
+        async fn edit(&self, message_id: &str, text: &str) -> Result<()> {
+            if self.fail_edits {
+                return Err(BizClawError::Channel("429 Too Many Requests".into()));
+            }
+            self.log.lock().unwrap().push(format!("edit {message_id} {text}"));
+            Ok(())
+        }
+
+        async fn delete(&self, message_id: &str) -> Result<()> {
+            self.log.lock().unwrap().push(format!("delete {message_id}"));
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_split_message() {
+        assert_eq!(split_message("", 5), vec![""]);
+        assert_eq!(split_message("hello world again", 11), vec!["hello ", "world again"]);
+        assert_eq!(split_message("line one\nline two", 12), vec!["line one\n", "line two"]);
+        assert_eq!(split_message("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        // Counts characters, not bytes.
+        assert_eq!(split_message("ăâđêôơư", 7), vec!["ăâđêôơư"]);
+    }
+
+    #[tokio::test]
+    async fn test_relay_edits_and_overflows() {
+        let chat = FakeChat::default();
+        let tokens: TokenStream = Box::pin(futures::stream::iter(
+            ["Hi", " there.", " More text"].map(|t| Ok(t.to_string())),
+        ));
+        let text = relay(&chat, tokens, EDIT_INTERVAL).await.unwrap();
+        assert_eq!(text, "Hi there. More text");
+        assert_eq!(*chat.log.lock().unwrap(), vec![
+            "post Typing...",
+            "edit 1 Hi there.",
+            "post More text",
+        ]);
+
+        let chat = FakeChat::default();
+        let tokens: TokenStream = Box::pin(futures::stream::iter(vec![
+            Ok("Partial".to_string()),
+            Err(BizClawError::Provider("boom".into())),
+        ]));
+        assert!(relay(&chat, tokens, EDIT_INTERVAL).await.is_err());
+        // The partial text stays, followed by the notice (split at 10 chars).
+        let log = chat.log.lock().unwrap();
+        assert_eq!(log[1], "edit 1 Partial");
+        assert!(log[2..].concat().contains("Response"), "{log:?}");
+    }
+
+    #[tokio::test]
+    async fn test_relay_throttles_edits() {
+        let chat = FakeChat::default();
+        let tokens: TokenStream = Box::pin(futures::stream::iter(0..20).then(|n| async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            Ok(format!("{}", n % 10))
+        }));
+        relay(&chat, tokens, Duration::from_millis(80)).await.unwrap();
+        // Twenty tokens over ~200 ms: a couple of edits, not one per token.
+        let edits = chat.log.lock().unwrap().iter().filter(|l| l.starts_with("edit")).count();
+        assert!((1..=4).contains(&edits), "{edits} edits");
+    }
+
+    #[tokio::test]
+    async fn test_relay_deletes_placeholder_on_failure() {
+        // The stream fails before any text.
+        let chat = FakeChat::default();
+        let tokens: TokenStream = Box::pin(futures::stream::iter(vec![Err(BizClawError::Provider("boom".into()))]));
+        assert!(relay(&chat, tokens, EDIT_INTERVAL).await.is_err());
+        assert_eq!(*chat.log.lock().unwrap(), vec!["post Typing...", "delete 1"]);
+
+        // The final edit fails.
+        let chat = FakeChat { fail_edits: true, ..FakeChat::default() };
+        let tokens: TokenStream = Box::pin(futures::stream::iter([Ok("Hello".to_string())]));
+        assert!(relay(&chat, tokens, EDIT_INTERVAL).await.is_err());
+        assert_eq!(*chat.log.lock().unwrap(), vec!["post Typing...", "delete 1"]);
+    }
+}
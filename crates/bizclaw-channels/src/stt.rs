@@ -0,0 +1,103 @@
+//! Speech-to-text for voice messages received by channels.
+
+use async_trait::async_trait;
+use bizclaw_core::config::SpeechToTextConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use std::path::Path;
+
+/// Turns a saved voice message into text.
+#[async_trait]
+pub trait Transcriber: Send + Sync {
+    async fn transcribe(&self, audio: &Path, mime_type: &str) -> Result<String>;
+}
+
+/// An OpenAI-compatible `/audio/transcriptions` endpoint (OpenAI Whisper,
+/// Groq, a local whisper.cpp server, ...).
+pub struct OpenAiTranscriber {
+    config: SpeechToTextConfig,
+    client: reqwest::Client,
+}
+
+impl OpenAiTranscriber {
+    pub fn new(config: SpeechToTextConfig) -> Self {
+        Self { config, client: reqwest::Client::new() }
+    }
+}
+
+#[async_trait]
+impl Transcriber for OpenAiTranscriber {
+    async fn transcribe(&self, audio: &Path, mime_type: &str) -> Result<String> {
+        let data = tokio::fs::read(audio).await
+            .map_err(|e| BizClawError::Channel(format!("Can't read {}: {e}", audio.display())))?;
+        let file_name = audio.file_name().and_then(|n| n.to_str()).unwrap_or("voice.ogg").to_string();
+        let file = reqwest::multipart::Part::bytes(data)
+            .file_name(file_name)
+            .mime_str(mime_type)
+            .map_err(|e| BizClawError::Channel(format!("Invalid audio type {mime_type}: {e}")))?;
+        let form = reqwest::multipart::Form::new()
+            .text("model", self.config.model.clone())
+            .part("file", file);
+
+        let url = format!("{}/audio/transcriptions", self.config.api_base.trim_end_matches('/'));
+        let mut request = self.client.post(url).multipart(form);
+        if !self.config.api_key.is_empty() {
+            request = request.bearer_auth(&self.config.api_key);
+        }
+        let response = request.send().await
+            .map_err(|e| BizClawError::Channel(format!("Transcription request failed: {e}")))?;
+        let status = response.status();
+        let body: serde_json::Value = response.json().await
+            .map_err(|e| BizClawError::Channel(format!("Invalid transcription response ({status}): {e}")))?;
+        if !status.is_success() {
+            return Err(BizClawError::Channel(format!(
+                "Transcription failed ({status}): {}", body["error"]["message"].as_str().unwrap_or("unknown error")
+            )));
+        }
+        body["text"].as_str()
+            .map(|text| text.trim().to_string())
+            .ok_or_else(|| BizClawError::Channel("Transcription response has no text".into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_openai_transcriber() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_base = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !String::from_utf8_lossy(&request).contains("OggS-audio") {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"text":" Cho tôi báo giá nhé. "}"#;
+            let response = format!("HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{body}", body.len());
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+
+        let audio = std::env::temp_dir().join(format!("bizclaw-stt-{}.ogg", std::process::id()));
+        std::fs::write(&audio, "OggS-audio").unwrap();
+        let transcriber = OpenAiTranscriber::new(SpeechToTextConfig {
+            api_base,
+            api_key: "sk-test".into(),
+            model: "whisper-1".into(),
+        });
+        assert_eq!(transcriber.transcribe(&audio, "audio/ogg").await.unwrap(), "Cho tôi báo giá nhé.");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/audio/transcriptions "), "{request}");
+        assert!(request.to_lowercase().contains("authorization: bearer sk-test"));
+        assert!(request.contains("name=\"model\"\r\n\r\nwhisper-1"));
+        std::fs::remove_file(audio).ok();
+    }
+}
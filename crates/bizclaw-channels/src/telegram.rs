@@ -1,14 +1,40 @@
-//! Telegram Bot channel — long polling + message sending via Bot API.
+//! Telegram Bot channel — long polling or webhook + message sending via Bot API.
+//!
+//! Photos, documents and voice messages are downloaded into the media
+//! directory and handed to the agent as attachments; voice messages are
+//! transcribed when `media.stt` is set. Files named in a reply are sent
+//! back as photos or documents.
+//!
+//! `/start`, `/help`, `/reset`, `/model` and `/summary` are handled here
+//! (other commands go to the agent); `/reset` and `/model` are kept per chat
+//! in a [`ChatSettingsStore`]. In groups the bot only answers commands,
+//! @mentions and replies to it, unless `respond_to_all` is set.
 
 use async_trait::async_trait;
+use bizclaw_core::config::{
+    BizClawConfig, ChannelRateLimitConfig, SummarizeGroupsConfig, TelegramMediaConfig, TelegramWebhookConfig,
+};
 use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::group_buffer::MessageBuffer;
 use bizclaw_core::traits::Channel;
-use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
+use bizclaw_core::traits::provider::TokenStream;
+use bizclaw_core::types::{Attachment, AttachmentKind, IncomingMessage, OutgoingMessage, ThreadType};
+use bizclaw_security::allowlist::Allowlist;
+use bizclaw_security::approval::{ApprovalBroker, PendingApproval};
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
 use std::task::{Context, Poll};
 
+use crate::chat_settings::ChatSettingsStore;
+use crate::format::{self, Dialect};
+use crate::group_monitor::{GroupMonitor, with_placeholder};
+use crate::rate_limit::RateLimiter;
+use crate::stt::{OpenAiTranscriber, Transcriber};
+
 /// Telegram channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramConfig {
@@ -17,80 +43,651 @@ pub struct TelegramConfig {
     pub enabled: bool,
     #[serde(default = "default_poll_interval")]
     pub poll_interval: u64,
+    /// Chats the bot answers; empty means all.
+    #[serde(default)]
+    pub allowed_chat_ids: Vec<i64>,
+    /// Bot API server (a local `telegram-bot-api`, or a test server).
+    #[serde(default = "default_api_base")]
+    pub api_base: String,
+    /// Receive updates through this webhook instead of polling.
+    #[serde(default)]
+    pub webhook: Option<TelegramWebhookConfig>,
+    /// "MarkdownV2" or "none" (plain text).
+    #[serde(default = "default_parse_mode")]
+    pub parse_mode: String,
+    #[serde(default)]
+    pub media: TelegramMediaConfig,
+    /// Answer every group message, not only commands and mentions.
+    #[serde(default)]
+    pub respond_to_all: bool,
+    /// Limits on sent messages.
+    #[serde(default)]
+    pub rate_limit: ChannelRateLimitConfig,
+    /// Least time between edits of a streamed reply.
+    #[serde(default = "default_edit_interval")]
+    pub edit_interval_ms: u64,
 }
 
 fn default_true() -> bool { true }
+fn default_edit_interval() -> u64 { 1000 }
 fn default_poll_interval() -> u64 { 1 }
+fn default_api_base() -> String { "https://api.telegram.org".into() }
+fn default_parse_mode() -> String { "MarkdownV2".into() }
+
+impl Default for TelegramConfig {
+    fn default() -> Self {
+        Self {
+            bot_token: String::new(),
+            enabled: true,
+            poll_interval: default_poll_interval(),
+            allowed_chat_ids: Vec::new(),
+            api_base: default_api_base(),
+            webhook: None,
+            parse_mode: default_parse_mode(),
+            media: TelegramMediaConfig::default(),
+            respond_to_all: false,
+            rate_limit: ChannelRateLimitConfig::default(),
+            edit_interval_ms: default_edit_interval(),
+        }
+    }
+}
+
+impl From<&bizclaw_core::config::TelegramChannelConfig> for TelegramConfig {
+    fn from(cfg: &bizclaw_core::config::TelegramChannelConfig) -> Self {
+        Self {
+            bot_token: cfg.bot_token.clone(),
+            enabled: cfg.enabled,
+            allowed_chat_ids: cfg.allowed_chat_ids.clone(),
+            webhook: cfg.webhook.clone().filter(|_| cfg.mode == "webhook"),
+            parse_mode: cfg.parse_mode.clone(),
+            media: cfg.media.clone(),
+            respond_to_all: cfg.respond_to_all,
+            rate_limit: cfg.rate_limit.clone(),
+            edit_interval_ms: cfg.presence.edit_interval_ms,
+            ..Self::default()
+        }
+    }
+}
+
+/// Longest text one Telegram message can hold.
+const MAX_MESSAGE_CHARS: usize = 4096;
+
+/// Seconds `getUpdates` waits for new updates before returning empty.
+const LONG_POLL_TIMEOUT_SECS: u64 = 30;
+/// Wait before polling again after an error.
+const ERROR_BACKOFF: std::time::Duration = std::time::Duration::from_secs(5);
+/// Wait before polling again while another poller holds the token.
+const CONFLICT_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
+/// Largest image `sendPhoto` takes; bigger ones go as documents.
+const MAX_PHOTO_UPLOAD_BYTES: u64 = 10 * 1024 * 1024;
+/// Largest file the Bot API accepts as an upload.
+const MAX_UPLOAD_BYTES: u64 = 50 * 1024 * 1024;
+/// Commands handled by the channel, registered with `setMyCommands`.
+const COMMANDS: &[(&str, &str)] = &[
+    ("start", "Start talking to the bot"),
+    ("help", "Show what the bot can do"),
+    ("reset", "Clear the conversation"),
+    ("model", "Show or change the model for this chat"),
+    ("summary", "Summarize the conversation"),
+];
 
 /// Telegram Bot channel with polling loop.
+#[derive(Clone)]
 pub struct TelegramChannel {
     config: TelegramConfig,
     client: reqwest::Client,
     last_update_id: i64,
     connected: bool,
+    /// Broker whose pending approvals are prompted in `approval_chats`.
+    approvals: Option<(Arc<ApprovalBroker>, Vec<i64>)>,
+    /// Groups whose messages go to the group summarizer.
+    groups: Option<Arc<GroupMonitor>>,
+    /// Bot's display name, for its own buffered messages.
+    bot_name: String,
+    /// Transcribes voice messages.
+    transcriber: Option<Arc<dyn Transcriber>>,
+    /// Paths outside the media directory a reply may send, if any.
+    outgoing_files: Option<Allowlist>,
+    /// Bot's @username, for mentions and `/command@bot`; known after `connect`.
+    bot_username: Option<String>,
+    /// Per-chat model and reset, changed with `/model` and `/reset`.
+    chat_settings: Arc<ChatSettingsStore>,
+    rate_limiter: Option<Arc<RateLimiter>>,
 }
 
 impl TelegramChannel {
     pub fn new(config: TelegramConfig) -> Self {
+        let transcriber = config.media.stt.clone()
+            .map(|stt| Arc::new(OpenAiTranscriber::new(stt)) as Arc<dyn Transcriber>);
+        let rate_limiter = RateLimiter::new(&config.rate_limit).map(Arc::new);
         Self {
             config,
             client: reqwest::Client::new(),
             last_update_id: 0,
             connected: false,
+            approvals: None,
+            groups: None,
+            bot_name: "Bot".into(),
+            transcriber,
+            outgoing_files: None,
+            bot_username: None,
+            chat_settings: Arc::new(ChatSettingsStore::open(
+                BizClawConfig::data_dir().join("telegram").join("chat_settings.json"),
+            )),
+            rate_limiter,
+        }
+    }
+
+    /// Keep per-chat settings in `store` instead of `telegram/chat_settings.json`
+    /// in the data directory.
+    pub fn with_chat_settings(mut self, store: Arc<ChatSettingsStore>) -> Self {
+        self.chat_settings = store;
+        self
+    }
+
+    /// Per-chat settings, for applying `/model` and `/reset` to the agents.
+    pub fn chat_settings(&self) -> Arc<ChatSettingsStore> {
+        self.chat_settings.clone()
+    }
+
+    /// Transcribe voice messages with `transcriber` instead of `media.stt`.
+    pub fn with_transcriber(mut self, transcriber: Arc<dyn Transcriber>) -> Self {
+        self.transcriber = Some(transcriber);
+        self
+    }
+
+    /// Also send files named in replies that `allowlist` permits (files in
+    /// the media directory are always sendable).
+    pub fn with_outgoing_files(mut self, allowlist: Allowlist) -> Self {
+        self.outgoing_files = Some(allowlist);
+        self
+    }
+
+    /// Buffer messages from the groups in `config` into `buffer`, including
+    /// media as placeholders such as "[photo]".
+    pub fn with_group_buffer(mut self, buffer: MessageBuffer, config: SummarizeGroupsConfig) -> Self {
+        self.groups = Some(Arc::new(GroupMonitor::new("telegram", buffer, config)));
+        self
+    }
+
+    /// Buffer an update from a monitored group, then convert it for the
+    /// agent if it comes from an allowed chat and is meant for the bot,
+    /// running built-in commands and downloading its media.
+    async fn handle_update(&self, update: &TelegramUpdate) -> Option<IncomingMessage> {
+        if let Some(groups) = &self.groups
+            && let Some(msg) = &update.message
+            && let Some(content) = msg.summary_text()
+        {
+            let sender = msg.from.as_ref().map_or_else(|| "Unknown".to_string(), TelegramUser::display_name);
+            let timestamp = chrono::DateTime::from_timestamp(msg.date, 0).unwrap_or_else(chrono::Utc::now);
+            groups.record(&msg.chat.id.to_string(), msg.chat.title.as_deref(), &sender, &content, timestamp, false);
+        }
+        let mut incoming = update.to_incoming()?;
+        let allowed = &self.config.allowed_chat_ids;
+        if !allowed.is_empty() && !allowed.iter().any(|id| id.to_string() == incoming.thread_id) {
+            tracing::debug!("Telegram: ignoring message from chat {} (not in allowed_chat_ids)", incoming.thread_id);
+            return None;
+        }
+        let text = incoming.content.clone();
+        let command = parse_command(&text);
+        if let Some((_, _, Some(bot))) = command
+            && !self.is_me(bot)
+        {
+            return None;
+        }
+        let addressed = command.is_some() || update.message.as_ref().is_some_and(|msg| self.addressed(msg));
+        if incoming.thread_type == ThreadType::Group && !self.config.respond_to_all && !addressed {
+            return None;
+        }
+        if let Some(username) = &self.bot_username {
+            incoming.content = strip_mention(&incoming.content, username);
+        }
+        if let Some((name, args, _)) = command {
+            let chat_id: i64 = incoming.thread_id.parse().ok()?;
+            match self.run_command(&incoming, &name.to_lowercase(), args) {
+                CommandOutcome::Reply(text) => {
+                    if let Err(e) = self.send_message(chat_id, &text).await {
+                        tracing::warn!("Telegram: /{name} reply to chat {chat_id} failed: {e}");
+                    }
+                    return None;
+                }
+                CommandOutcome::Ask(prompt) => incoming.content = prompt,
+                CommandOutcome::Pass => {}
+            }
+        }
+        if let Some(media) = update.message.as_ref().and_then(TelegramMessage::media) {
+            self.receive_media(&mut incoming, media).await;
+        }
+        if incoming.content.is_empty() && incoming.attachments.is_empty() {
+            return None;
+        }
+        Some(incoming)
+    }
+
+    /// Whether `username` (from `/command@username` or a mention) is this bot.
+    fn is_me(&self, username: &str) -> bool {
+        self.bot_username.as_deref().is_some_and(|me| me.eq_ignore_ascii_case(username))
+    }
+
+    /// Whether `msg` @mentions the bot or replies to one of its messages.
+    fn addressed(&self, msg: &TelegramMessage) -> bool {
+        let Some(me) = &self.bot_username else { return false };
+        let replies_to_me = msg.reply_to_message.as_ref()
+            .and_then(|r| r.from.as_ref())
+            .and_then(|from| from.username.as_deref())
+            .is_some_and(|username| self.is_me(username));
+        let text = msg.text.as_deref().or(msg.caption.as_deref()).unwrap_or("");
+        replies_to_me || text.to_lowercase().contains(&format!("@{}", me.to_lowercase()))
+    }
+
+    /// Run a built-in command; unknown commands go to the agent as written.
+    fn run_command(&self, incoming: &IncomingMessage, name: &str, args: &str) -> CommandOutcome {
+        let chat = &incoming.thread_id;
+        match name {
+            "start" | "help" => CommandOutcome::Reply(self.help_text(incoming.thread_type == ThreadType::Group)),
+            "reset" => {
+                let saved = self.chat_settings.update(chat, |s| s.reset_at = Some(chrono::Utc::now()));
+                CommandOutcome::Reply(match saved {
+                    Ok(_) => "🧹 Conversation cleared. Let's start over!".into(),
+                    Err(e) => format!("⚠️ Couldn't clear the conversation: {e}"),
+                })
+            }
+            "model" if args.is_empty() => CommandOutcome::Reply(match self.chat_settings.get(chat).model {
+                Some(model) => format!("🤖 This chat uses {model}. /model default goes back to the default model."),
+                None => "🤖 This chat uses the default model. Change it with /model <name>.".into(),
+            }),
+            "model" => {
+                if args.contains(char::is_whitespace) || args.chars().count() > 100 {
+                    return CommandOutcome::Reply("Usage: /model <name>, or /model default".into());
+                }
+                let model = (!matches!(args, "default" | "reset")).then(|| args.to_string());
+                let saved = self.chat_settings.update(chat, |s| s.model = model.clone());
+                CommandOutcome::Reply(match (saved, model) {
+                    (Err(e), _) => format!("⚠️ Couldn't change the model: {e}"),
+                    (Ok(_), Some(model)) => format!("🤖 This chat now uses {model}."),
+                    (Ok(_), None) => "🤖 This chat now uses the default model.".into(),
+                })
+            }
+            "summary" => CommandOutcome::Ask(match &self.groups {
+                Some(groups) if groups.monitors(chat) => format!(
+                    "Summarize the recent messages in this group with the group_summarizer tool \
+                     (action \"summarize\", group_id \"{chat}\")."
+                ),
+                _ => "Summarize our conversation so far in a few short bullet points.".into(),
+            }),
+            _ => CommandOutcome::Pass,
+        }
+    }
+
+    fn help_text(&self, in_group: bool) -> String {
+        let mut text = format!("👋 Hi, I'm {}. Send me a message and I'll answer.\n\nCommands:", self.bot_name);
+        for (command, description) in COMMANDS {
+            text.push_str(&format!("\n/{command} — {description}"));
+        }
+        if in_group && !self.config.respond_to_all
+            && let Some(username) = &self.bot_username
+        {
+            text.push_str(&format!("\n\nIn groups, mention @{username} or reply to my messages."));
+        }
+        text
+    }
+
+    /// Register the built-in commands, so Telegram suggests them.
+    pub async fn set_my_commands(&self) -> Result<()> {
+        let commands: Vec<_> = COMMANDS.iter()
+            .map(|(command, description)| serde_json::json!({ "command": command, "description": description }))
+            .collect();
+        let response = self.client.post(self.api_url("setMyCommands"))
+            .json(&serde_json::json!({ "commands": commands }))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("setMyCommands failed: {e}")))?;
+        let body: TelegramApiResponse<bool> = response.json().await
+            .map_err(|e| BizClawError::Channel(format!("Invalid setMyCommands response: {e}")))?;
+        if !body.ok {
+            return Err(BizClawError::Channel(format!(
+                "setMyCommands failed: {}", body.description.unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Where received files are stored: `media.dir`, or `media/` in the
+    /// (per-tenant) data directory.
+    fn media_dir(&self) -> PathBuf {
+        self.config.media.dir.as_deref()
+            .map(|dir| PathBuf::from(shellexpand::tilde(dir).as_ref()))
+            .unwrap_or_else(|| BizClawConfig::data_dir().join("media"))
+    }
+
+    /// Download `media` if its type is enabled and attach it to `incoming`.
+    /// A transcribed voice message becomes text; a file that can't be
+    /// downloaded is noted in the text, so the agent can say so.
+    async fn receive_media(&self, incoming: &mut IncomingMessage, media: MediaFile) {
+        let enabled = match media.kind {
+            AttachmentKind::Image => self.config.media.photos,
+            AttachmentKind::Document => self.config.media.documents,
+            AttachmentKind::Voice => self.config.media.voice,
+        };
+        if !enabled {
+            tracing::debug!("Telegram: ignoring {} (disabled in channel.telegram.media)", media.kind);
+            return;
+        }
+        let path = match self.download(&incoming.thread_id, &media).await {
+            Ok(path) => path,
+            Err(e) => {
+                tracing::warn!("Telegram: {} from chat {} not downloaded: {e}", media.kind, incoming.thread_id);
+                push_line(&mut incoming.content, &format!("[{} not received: {e}]", media.kind));
+                return;
+            }
+        };
+        if media.kind == AttachmentKind::Voice
+            && let Some(transcriber) = &self.transcriber
+        {
+            let mime_type = media.mime_type.as_deref().unwrap_or("audio/ogg");
+            match transcriber.transcribe(&path, mime_type).await {
+                Ok(text) if !text.is_empty() => {
+                    push_line(&mut incoming.content, &text);
+                    return;
+                }
+                Ok(_) => tracing::warn!("Telegram: voice message transcribed to nothing"),
+                Err(e) => tracing::warn!("Telegram: voice transcription failed: {e}"),
+            }
+        }
+        incoming.attachments.push(Attachment {
+            kind: media.kind,
+            path: path.display().to_string(),
+            mime_type: media.mime_type,
+            file_name: media.file_name,
+        });
+    }
+
+    /// Fetch a file with `getFile` and save it as
+    /// `{media_dir}/telegram/{chat_id}/{file_unique_id}.{ext}`. Files over
+    /// `media.max_download_mb` are refused before and while downloading.
+    async fn download(&self, chat_id: &str, media: &MediaFile) -> std::result::Result<PathBuf, String> {
+        let max_mb = self.config.media.max_download_mb;
+        let max_bytes = max_mb.saturating_mul(1024 * 1024);
+        let too_large = || format!("larger than the {max_mb} MB download limit");
+        if media.file_size.is_some_and(|size| size > max_bytes) {
+            return Err(too_large());
+        }
+
+        let response = self.client
+            .post(self.api_url("getFile"))
+            .json(&serde_json::json!({ "file_id": media.file_id }))
+            .send()
+            .await
+            .map_err(|e| format!("getFile failed: {e}"))?;
+        let body: TelegramApiResponse<TelegramFile> = response.json().await
+            .map_err(|e| format!("invalid getFile response: {e}"))?;
+        let file = match body.result {
+            Some(file) if body.ok => file,
+            _ => return Err(format!("getFile failed: {}", body.description.unwrap_or_default())),
+        };
+        if file.file_size.is_some_and(|size| size > max_bytes) {
+            return Err(too_large());
         }
+        let file_path = file.file_path.ok_or("Telegram returned no file_path")?;
+
+        let url = format!("{}/file/bot{}/{file_path}", self.config.api_base.trim_end_matches('/'), self.config.bot_token);
+        let mut response = self.client.get(url).send().await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| format!("download failed: {}", e.without_url()))?;
+        let mut data = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| format!("download failed: {}", e.without_url()))? {
+            if (data.len() + chunk.len()) as u64 > max_bytes {
+                return Err(too_large());
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        let ext = Path::new(&file_path).extension()
+            .or_else(|| media.file_name.as_deref().and_then(|n| Path::new(n).extension()))
+            .and_then(|e| e.to_str())
+            .unwrap_or(default_extension(media.kind));
+        let dir = self.media_dir().join("telegram").join(safe_file_name(chat_id));
+        let path = dir.join(format!("{}.{}", safe_file_name(&media.file_unique_id), safe_file_name(ext)));
+        tokio::fs::create_dir_all(&dir).await.map_err(|e| format!("can't create {}: {e}", dir.display()))?;
+        tokio::fs::write(&path, &data).await.map_err(|e| format!("can't save {}: {e}", path.display()))?;
+        tracing::info!("Telegram: saved {} ({} bytes) to {}", media.kind, data.len(), path.display());
+        Ok(path)
+    }
+
+    /// Buffer a message the bot sent, if its chat is monitored.
+    fn record_own(&self, chat_id: i64, text: &str) {
+        if let Some(groups) = &self.groups {
+            groups.record(&chat_id.to_string(), None, &self.bot_name, text, chrono::Utc::now(), true);
+        }
+    }
+
+    /// Prompt `chat_ids` with Approve/Deny buttons for each pending tool-call
+    /// approval; button presses from those chats resolve it.
+    pub fn with_approvals(mut self, broker: Arc<ApprovalBroker>, chat_ids: Vec<i64>) -> Self {
+        self.approvals = Some((broker, chat_ids));
+        self
+    }
+
+    /// The broker and chats set by [`Self::with_approvals`].
+    pub fn approvals(&self) -> Option<(&Arc<ApprovalBroker>, &[i64])> {
+        self.approvals.as_ref().map(|(broker, chat_ids)| (broker, chat_ids.as_slice()))
     }
 
     fn api_url(&self, method: &str) -> String {
-        format!("https://api.telegram.org/bot{}/{}", self.config.bot_token, method)
+        format!("{}/bot{}/{}", self.config.api_base.trim_end_matches('/'), self.config.bot_token, method)
     }
 
     /// Get updates using long polling.
-    pub async fn get_updates(&mut self) -> Result<Vec<TelegramUpdate>> {
+    ///
+    /// Each call confirms everything returned before (`offset` is one past
+    /// the highest `update_id` seen), so updates aren't processed twice.
+    /// Returns [`PollError::Conflict`] when another instance is polling the
+    /// same token or a webhook is set.
+    pub async fn get_updates(&mut self) -> std::result::Result<Vec<TelegramUpdate>, PollError> {
         let response = self.client
-            .get(&self.api_url("getUpdates"))
+            .get(self.api_url("getUpdates"))
             .query(&[
                 ("offset", (self.last_update_id + 1).to_string()),
-                ("timeout", "30".into()),
-                ("allowed_updates", "[\"message\"]".into()),
+                ("timeout", LONG_POLL_TIMEOUT_SECS.to_string()),
+                ("allowed_updates", "[\"message\",\"callback_query\"]".into()),
             ])
+            .timeout(std::time::Duration::from_secs(LONG_POLL_TIMEOUT_SECS + 10))
             .send()
             .await
-            .map_err(|e| BizClawError::Channel(format!("Telegram getUpdates failed: {e}")))?;
+            .map_err(|e| PollError::Other(format!("Telegram getUpdates failed: {e}")))?;
 
+        let status = response.status();
         let body: TelegramApiResponse<Vec<TelegramUpdate>> = response.json().await
-            .map_err(|e| BizClawError::Channel(format!("Invalid Telegram response: {e}")))?;
+            .map_err(|e| PollError::Other(format!("Invalid Telegram response ({status}): {e}")))?;
 
         if !body.ok {
-            return Err(BizClawError::Channel(format!(
-                "Telegram API error: {}", body.description.unwrap_or_default()
-            )));
+            let description = body.description.unwrap_or_default();
+            if status == reqwest::StatusCode::CONFLICT || body.error_code == Some(409) {
+                return Err(PollError::Conflict(description));
+            }
+            return Err(PollError::Other(format!("Telegram API error: {description}")));
         }
 
         let updates = body.result.unwrap_or_default();
-        if let Some(last) = updates.last() {
-            self.last_update_id = last.update_id;
+        if let Some(max) = updates.iter().map(|u| u.update_id).max() {
+            self.last_update_id = self.last_update_id.max(max);
         }
         Ok(updates)
     }
 
-    /// Send a text message.
+    /// Send a text message, split into as many messages as Telegram's
+    /// 4096-character limit needs, in order.
+    ///
+    /// With `parse_mode = "MarkdownV2"` the model's Markdown is converted
+    /// (see [`crate::format`]); a part Telegram still can't parse is sent
+    /// again as plain text rather than lost.
     pub async fn send_message(&self, chat_id: i64, text: &str) -> Result<()> {
+        for part in format::prepare(text, MAX_MESSAGE_CHARS) {
+            if self.config.parse_mode == "MarkdownV2" {
+                let body = serde_json::json!({
+                    "chat_id": chat_id,
+                    "text": format::render(&part, Dialect::MarkdownV2),
+                    "parse_mode": "MarkdownV2",
+                });
+                match self.call_for_message("sendMessage", &body).await {
+                    Ok(_) => continue,
+                    Err(e) if e.to_string().contains("can't parse entities") => {
+                        tracing::warn!("Telegram rejected MarkdownV2, sending as plain text: {e}");
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            let body = serde_json::json!({ "chat_id": chat_id, "text": part });
+            self.call_for_message("sendMessage", &body).await?;
+        }
+        Ok(())
+    }
+
+    /// Call a Bot API method that returns a message; returns its id.
+    async fn call_for_message(&self, method: &str, body: &serde_json::Value) -> Result<i64> {
+        self.request_for_message(method, self.client.post(self.api_url(method)).json(body)).await
+    }
+
+    async fn request_for_message(&self, method: &str, request: reqwest::RequestBuilder) -> Result<i64> {
+        let response = request
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("{method} failed: {e}")))?;
+
+        let result: TelegramApiResponse<serde_json::Value> = response.json().await
+            .map_err(|e| BizClawError::Channel(format!("Invalid {method} response: {e}")))?;
+
+        if !result.ok {
+            return Err(BizClawError::Channel(format!(
+                "{method} failed: {}", result.description.unwrap_or_default()
+            )));
+        }
+        result.result
+            .and_then(|m| m["message_id"].as_i64())
+            .ok_or_else(|| crate::streaming::missing_id(method))
+    }
+
+    /// Upload a file: images of up to 10 MB with `sendPhoto`, anything else
+    /// of up to 50 MB with `sendDocument`.
+    pub async fn send_file(&self, chat_id: i64, path: &Path) -> Result<()> {
+        let data = tokio::fs::read(path).await
+            .map_err(|e| BizClawError::Channel(format!("Can't read {}: {e}", path.display())))?;
+        let size = data.len() as u64;
+        if size > MAX_UPLOAD_BYTES {
+            return Err(BizClawError::Channel(format!(
+                "{} is larger than Telegram's 50 MB upload limit", path.display()
+            )));
+        }
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+        let is_photo = matches!(ext.as_str(), "jpg" | "jpeg" | "png" | "webp") && size <= MAX_PHOTO_UPLOAD_BYTES;
+        let (method, field) = if is_photo { ("sendPhoto", "photo") } else { ("sendDocument", "document") };
+        let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or("file").to_string();
+        let form = reqwest::multipart::Form::new()
+            .text("chat_id", chat_id.to_string())
+            .part(field, reqwest::multipart::Part::bytes(data).file_name(file_name));
+        self.request_for_message(method, self.client.post(self.api_url(method)).multipart(form)).await?;
+        Ok(())
+    }
+
+    /// Send the files a reply names (see [`Self::reply_files`]) after it.
+    async fn send_reply_files(&self, chat_id: i64, text: &str) {
+        for path in self.reply_files(text) {
+            if let Err(e) = self.send_file(chat_id, &path).await {
+                tracing::warn!("Telegram: couldn't send {}: {e}", path.display());
+            }
+        }
+    }
+
+    /// Existing files named by absolute (or `~/`, `./`) paths in `text`
+    /// that are in the media directory or allowed by `outgoing_files`.
+    fn reply_files(&self, text: &str) -> Vec<PathBuf> {
+        let media_dir = self.media_dir().canonicalize().ok();
+        let mut files = Vec::new();
+        let words = text.split(|c: char| c.is_whitespace() || matches!(c, '`' | '"' | '\'' | '(' | ')' | '[' | ']' | '<' | '>'));
+        for word in words {
+            let word = word.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            if !(word.starts_with('/') || word.starts_with("~/") || word.starts_with("./")) {
+                continue;
+            }
+            let path = match &self.outgoing_files {
+                Some(allowlist) => allowlist.check_path(word).ok(),
+                None => None,
+            };
+            let path = path.or_else(|| {
+                let path = PathBuf::from(shellexpand::tilde(word).as_ref()).canonicalize().ok()?;
+                media_dir.as_ref().is_some_and(|dir| path.starts_with(dir)).then_some(path)
+            });
+            if let Some(path) = path.filter(|p| p.is_file())
+                && !files.contains(&path)
+            {
+                files.push(path);
+            }
+        }
+        files
+    }
+
+    /// Replace the text of a sent message.
+    pub async fn edit_message_text(&self, chat_id: i64, message_id: i64, text: &str) -> Result<()> {
         let body = serde_json::json!({
             "chat_id": chat_id,
+            "message_id": message_id,
             "text": text,
-            "parse_mode": "Markdown",
         });
+        self.call_for_message("editMessageText", &body).await.map(|_| ())
+    }
 
+    /// Delete a sent message.
+    pub async fn delete_message(&self, chat_id: i64, message_id: i64) -> Result<()> {
+        let body = serde_json::json!({ "chat_id": chat_id, "message_id": message_id });
         let response = self.client
-            .post(&self.api_url("sendMessage"))
+            .post(self.api_url("deleteMessage"))
             .json(&body)
             .send()
             .await
-            .map_err(|e| BizClawError::Channel(format!("sendMessage failed: {e}")))?;
+            .map_err(|e| BizClawError::Channel(format!("deleteMessage failed: {e}")))?;
+        let result: TelegramApiResponse<serde_json::Value> = response.json().await
+            .map_err(|e| BizClawError::Channel(format!("Invalid deleteMessage response: {e}")))?;
+        if !result.ok {
+            return Err(BizClawError::Channel(format!(
+                "deleteMessage failed: {}", result.description.unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
 
+    /// Send a response as it is generated: a "Typing..." message edited with
+    /// the text so far, continued in new messages past Telegram's 4096
+    /// characters. Sent as plain text, since half-received Markdown may not
+    /// parse.
+    pub async fn send_streaming(&self, chat_id: i64, tokens: TokenStream) -> Result<String> {
+        let edit_interval = std::time::Duration::from_millis(self.config.edit_interval_ms);
+        crate::streaming::relay(&TelegramReply { channel: self, chat_id }, tokens, edit_interval).await
+    }
+
+    /// Send an approval request with inline Approve/Deny buttons.
+    pub async fn send_approval_prompt(&self, chat_id: i64, pending: &PendingApproval) -> Result<()> {
+        let args: String = pending.arguments.chars().take(500).collect();
+        let body = serde_json::json!({
+            "chat_id": chat_id,
+            "text": format!(
+                "🔐 Approval needed\nTool: {}\nReason: {}\nArguments: {args}",
+                pending.tool, pending.reason
+            ),
+            "reply_markup": {
+                "inline_keyboard": [[
+                    { "text": "✅ Approve", "callback_data": format!("approval:approve:{}", pending.id) },
+                    { "text": "❌ Deny", "callback_data": format!("approval:deny:{}", pending.id) },
+                ]]
+            },
+        });
+        let response = self.client
+            .post(self.api_url("sendMessage"))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("sendMessage failed: {e}")))?;
         let result: TelegramApiResponse<serde_json::Value> = response.json().await
             .map_err(|e| BizClawError::Channel(format!("Invalid send response: {e}")))?;
-
         if !result.ok {
             return Err(BizClawError::Channel(format!(
                 "Send failed: {}", result.description.unwrap_or_default()
@@ -99,6 +696,32 @@ impl TelegramChannel {
         Ok(())
     }
 
+    /// Resolve an approval from an inline button press.
+    async fn handle_callback(&self, query: &TelegramCallbackQuery) {
+        let Some((broker, chat_ids)) = &self.approvals else { return };
+        let Some((approve, id)) = query.data.as_deref().and_then(parse_approval_callback) else { return };
+
+        let from_allowed_chat = query.message.as_ref().is_some_and(|m| chat_ids.contains(&m.chat.id));
+        let reply = if !from_allowed_chat {
+            "Not allowed from this chat"
+        } else {
+            let approver = format!(
+                "telegram:{}",
+                query.from.username.clone().unwrap_or_else(|| query.from.id.to_string())
+            );
+            match (broker.resolve(id, approve, &approver), approve) {
+                (true, true) => "Approved",
+                (true, false) => "Denied",
+                (false, _) => "Already resolved or expired",
+            }
+        };
+        let _ = self.client
+            .post(self.api_url("answerCallbackQuery"))
+            .json(&serde_json::json!({ "callback_query_id": query.id, "text": reply }))
+            .send()
+            .await;
+    }
+
     /// Send typing indicator.
     pub async fn send_typing(&self, chat_id: i64) -> Result<()> {
         let body = serde_json::json!({
@@ -106,7 +729,7 @@ impl TelegramChannel {
             "action": "typing",
         });
         let _ = self.client
-            .post(&self.api_url("sendChatAction"))
+            .post(self.api_url("sendChatAction"))
             .json(&body)
             .send()
             .await;
@@ -115,37 +738,146 @@ impl TelegramChannel {
 
     /// Get bot info.
     pub async fn get_me(&self) -> Result<TelegramUser> {
-        let response = self.client.get(&self.api_url("getMe")).send().await
+        let response = self.client.get(self.api_url("getMe")).send().await
             .map_err(|e| BizClawError::Channel(format!("getMe failed: {e}")))?;
         let body: TelegramApiResponse<TelegramUser> = response.json().await
             .map_err(|e| BizClawError::Channel(format!("Invalid getMe response: {e}")))?;
         body.result.ok_or_else(|| BizClawError::Channel("No bot info".into()))
     }
 
+    /// Register `webhook.url` with Telegram, so updates are pushed there
+    /// (and `getUpdates` stops working until it is removed).
+    pub async fn set_webhook(&self, webhook: &TelegramWebhookConfig) -> Result<()> {
+        let body = serde_json::json!({
+            "url": webhook.url,
+            "secret_token": webhook.secret_token,
+            "allowed_updates": ["message", "callback_query"],
+        });
+        let response = self.client.post(self.api_url("setWebhook")).json(&body).send().await
+            .map_err(|e| BizClawError::Channel(format!("Telegram setWebhook failed: {e}")))?;
+        let body: TelegramApiResponse<bool> = response.json().await
+            .map_err(|e| BizClawError::Channel(format!("Invalid setWebhook response: {e}")))?;
+        if !body.ok {
+            return Err(BizClawError::Channel(format!(
+                "Telegram setWebhook error: {}", body.description.unwrap_or_default()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Register the configured webhook and serve it on `webhook.listen`;
+    /// returns a stream of IncomingMessages.
+    pub async fn start_webhook(self) -> Result<TelegramPollingStream> {
+        let webhook = self.config.webhook.clone()
+            .ok_or_else(|| BizClawError::Config("channel.telegram: webhook mode needs [channel.telegram.webhook]".into()))?;
+        let path = reqwest::Url::parse(&webhook.url)
+            .map_err(|e| BizClawError::Config(format!("channel.telegram.webhook.url: {e}")))?
+            .path()
+            .to_string();
+        let listener = tokio::net::TcpListener::bind(&webhook.listen).await
+            .map_err(|e| BizClawError::Channel(format!("Telegram webhook: cannot listen on {}: {e}", webhook.listen)))?;
+        self.set_webhook(&webhook).await?;
+        self.spawn_approval_prompts();
+
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let router = self.webhook_router(&path, &webhook.secret_token, tx);
+        tracing::info!("Telegram webhook listening on {}{path}", webhook.listen);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                tracing::error!("Telegram webhook server stopped: {e}");
+            }
+        });
+        Ok(TelegramPollingStream { rx })
+    }
+
+    /// Route receiving webhook updates at `path`. Requests without the
+    /// matching `X-Telegram-Bot-Api-Secret-Token` are refused with 401, and
+    /// updates Telegram redelivers are dropped.
+    pub fn webhook_router(
+        self,
+        path: &str,
+        secret_token: &str,
+        tx: tokio::sync::mpsc::UnboundedSender<IncomingMessage>,
+    ) -> axum::Router {
+        let state = Arc::new(WebhookState {
+            channel: self,
+            secret_token: secret_token.to_string(),
+            tx,
+            last_update_id: AtomicI64::new(0),
+        });
+        axum::Router::new()
+            .route(path, axum::routing::post(receive_webhook_update))
+            .with_state(state)
+    }
+
+    /// Forward new approval requests to the approval chats.
+    fn spawn_approval_prompts(&self) {
+        if let Some((broker, chat_ids)) = &self.approvals {
+            let mut events = broker.subscribe();
+            let prompter = TelegramChannel::new(self.config.clone());
+            let chat_ids = chat_ids.clone();
+            tokio::spawn(async move {
+                loop {
+                    match events.recv().await {
+                        Ok(pending) => {
+                            for chat_id in &chat_ids {
+                                if let Err(e) = prompter.send_approval_prompt(*chat_id, &pending).await {
+                                    tracing::error!("Telegram approval prompt failed: {e}");
+                                }
+                            }
+                        }
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    }
+                }
+            });
+        }
+    }
+
     /// Start polling loop — returns a stream of IncomingMessages.
     pub fn start_polling(self) -> TelegramPollingStream {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        self.spawn_approval_prompts();
 
         // Spawn polling task
         tokio::spawn(async move {
             let mut channel = self;
             tracing::info!("Telegram polling loop started");
 
+            let mut in_conflict = false;
             loop {
                 match channel.get_updates().await {
                     Ok(updates) => {
+                        if std::mem::take(&mut in_conflict) {
+                            tracing::info!("Telegram polling resumed");
+                        }
                         for update in updates {
-                            if let Some(msg) = update.to_incoming() {
-                                if tx.send(msg).is_err() {
-                                    tracing::info!("Telegram polling stopped (receiver dropped)");
-                                    return;
-                                }
+                            if let Some(query) = &update.callback_query {
+                                channel.handle_callback(query).await;
+                                continue;
+                            }
+                            if let Some(msg) = channel.handle_update(&update).await
+                                && tx.send(msg).is_err() {
+                                tracing::info!("Telegram polling stopped (receiver dropped)");
+                                return;
                             }
                         }
                     }
-                    Err(e) => {
+                    Err(PollError::Conflict(description)) => {
+                        // Say it once, loudly; keep retrying in case the other poller stops.
+                        if !std::mem::replace(&mut in_conflict, true) {
+                            tracing::error!(
+                                "Telegram getUpdates conflict: {description}. Another BizClaw instance (or a webhook) \
+                                 is using this bot token; only one can receive updates. Retrying every {}s.",
+                                CONFLICT_BACKOFF.as_secs()
+                            );
+                        }
+                        tokio::time::sleep(CONFLICT_BACKOFF).await;
+                        continue;
+                    }
+                    Err(PollError::Other(e)) => {
                         tracing::error!("Telegram polling error: {e}");
-                        tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                        tokio::time::sleep(ERROR_BACKOFF).await;
                     }
                 }
 
@@ -157,7 +889,70 @@ impl TelegramChannel {
     }
 }
 
-/// Stream of incoming Telegram messages from polling.
+struct WebhookState {
+    channel: TelegramChannel,
+    secret_token: String,
+    tx: tokio::sync::mpsc::UnboundedSender<IncomingMessage>,
+    /// Highest update handled, to drop redeliveries.
+    last_update_id: AtomicI64,
+}
+
+async fn receive_webhook_update(
+    axum::extract::State(state): axum::extract::State<Arc<WebhookState>>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> axum::http::StatusCode {
+    use axum::http::StatusCode;
+    use subtle::ConstantTimeEq;
+
+    let given = headers.get("x-telegram-bot-api-secret-token").map(|v| v.as_bytes()).unwrap_or_default();
+    if !bool::from(given.ct_eq(state.secret_token.as_bytes())) {
+        tracing::warn!(target: "bizclaw::audit", event = "telegram_webhook_rejected", "Telegram webhook request with a wrong secret token");
+        return StatusCode::UNAUTHORIZED;
+    }
+    let update: TelegramUpdate = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(e) => {
+            tracing::warn!("Telegram webhook: invalid update: {e}");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    if state.last_update_id.fetch_max(update.update_id, Ordering::SeqCst) >= update.update_id {
+        tracing::debug!("Telegram webhook: update {} already handled", update.update_id);
+        return StatusCode::OK;
+    }
+    if let Some(query) = &update.callback_query {
+        state.channel.handle_callback(query).await;
+    } else if let Some(msg) = state.channel.handle_update(&update).await {
+        let _ = state.tx.send(msg);
+    }
+    StatusCode::OK
+}
+
+/// Why a `getUpdates` call failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PollError {
+    /// 409: another process is polling this token, or a webhook is set.
+    Conflict(String),
+    Other(String),
+}
+
+impl std::fmt::Display for PollError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Conflict(description) => write!(f, "Telegram getUpdates conflict (409): {description}"),
+            Self::Other(e) => f.write_str(e),
+        }
+    }
+}
+
+impl From<PollError> for BizClawError {
+    fn from(e: PollError) -> Self {
+        BizClawError::Channel(e.to_string())
+    }
+}
+
+/// Stream of incoming Telegram messages from polling or the webhook.
 pub struct TelegramPollingStream {
     rx: tokio::sync::mpsc::UnboundedReceiver<IncomingMessage>,
 }
@@ -172,6 +967,34 @@ impl Stream for TelegramPollingStream {
 
 impl Unpin for TelegramPollingStream {}
 
+/// Messages of one streamed reply in a Telegram chat.
+struct TelegramReply<'a> {
+    channel: &'a TelegramChannel,
+    chat_id: i64,
+}
+
+#[async_trait]
+impl crate::streaming::EditableChat for TelegramReply<'_> {
+    const MAX_CHARS: usize = MAX_MESSAGE_CHARS;
+
+    async fn post(&self, text: &str) -> Result<String> {
+        let body = serde_json::json!({ "chat_id": self.chat_id, "text": text });
+        self.channel.call_for_message("sendMessage", &body).await.map(|id| id.to_string())
+    }
+
+    async fn edit(&self, message_id: &str, text: &str) -> Result<()> {
+        let message_id = message_id.parse()
+            .map_err(|_| BizClawError::Channel(format!("Invalid message_id {message_id}")))?;
+        self.channel.edit_message_text(self.chat_id, message_id, text).await
+    }
+
+    async fn delete(&self, message_id: &str) -> Result<()> {
+        let message_id = message_id.parse()
+            .map_err(|_| BizClawError::Channel(format!("Invalid message_id {message_id}")))?;
+        self.channel.delete_message(self.chat_id, message_id).await
+    }
+}
+
 #[async_trait]
 impl Channel for TelegramChannel {
     fn name(&self) -> &str { "telegram" }
@@ -180,6 +1003,11 @@ impl Channel for TelegramChannel {
         let me = self.get_me().await?;
         tracing::info!("Telegram bot: @{} ({})",
             me.username.as_deref().unwrap_or("unknown"), me.first_name);
+        self.bot_name = me.display_name();
+        self.bot_username = me.username;
+        if let Err(e) = self.set_my_commands().await {
+            tracing::warn!("Telegram: couldn't register bot commands: {e}");
+        }
         self.connected = true;
         Ok(())
     }
@@ -194,7 +1022,25 @@ impl Channel for TelegramChannel {
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
         let chat_id: i64 = message.thread_id.parse()
             .map_err(|_| BizClawError::Channel("Invalid chat_id".into()))?;
-        self.send_message(chat_id, &message.content).await
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire("telegram", &message.thread_id).await?;
+        }
+        self.send_message(chat_id, &message.content).await?;
+        self.record_own(chat_id, &message.content);
+        self.send_reply_files(chat_id, &message.content).await;
+        Ok(())
+    }
+
+    async fn send_streaming(&self, thread_id: &str, _thread_type: ThreadType, tokens: TokenStream) -> Result<()> {
+        let chat_id: i64 = thread_id.parse()
+            .map_err(|_| BizClawError::Channel("Invalid chat_id".into()))?;
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire("telegram", thread_id).await?;
+        }
+        let text = TelegramChannel::send_streaming(self, chat_id, tokens).await?;
+        self.record_own(chat_id, &text);
+        self.send_reply_files(chat_id, &text).await;
+        Ok(())
     }
 
     async fn send_typing(&self, thread_id: &str) -> Result<()> {
@@ -209,6 +1055,17 @@ impl Channel for TelegramChannel {
         // For actual polling, use start_polling() which consumes self
         Ok(Box::new(futures::stream::pending()))
     }
+
+    /// Poll, or serve the webhook in webhook mode.
+    async fn start(&mut self, agent_tx: tokio::sync::mpsc::UnboundedSender<IncomingMessage>) -> Result<()> {
+        let messages = if self.config.webhook.is_some() {
+            self.clone().start_webhook().await?
+        } else {
+            self.clone().start_polling()
+        };
+        bizclaw_core::traits::channel::forward(messages, agent_tx);
+        Ok(())
+    }
 }
 
 // --- Telegram API Types ---
@@ -218,12 +1075,121 @@ pub struct TelegramApiResponse<T> {
     pub ok: bool,
     pub result: Option<T>,
     pub description: Option<String>,
+    pub error_code: Option<i64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramUpdate {
     pub update_id: i64,
     pub message: Option<TelegramMessage>,
+    pub callback_query: Option<TelegramCallbackQuery>,
+}
+
+/// Result of `getFile`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TelegramFile {
+    pub file_id: String,
+    pub file_size: Option<u64>,
+    /// Download path under `/file/bot<token>/`; valid for an hour.
+    pub file_path: Option<String>,
+}
+
+/// A photo, document or voice message to download.
+#[derive(Debug, Clone, PartialEq)]
+struct MediaFile {
+    kind: AttachmentKind,
+    file_id: String,
+    /// Same for every bot and over time, unlike `file_id`; names the saved file.
+    file_unique_id: String,
+    file_size: Option<u64>,
+    mime_type: Option<String>,
+    file_name: Option<String>,
+}
+
+/// What a command does.
+#[derive(Debug, Clone, PartialEq)]
+enum CommandOutcome {
+    /// Handled; send this reply.
+    Reply(String),
+    /// Ask the agent this instead of the command.
+    Ask(String),
+    /// Not a built-in; the agent gets the message as it is.
+    Pass,
+}
+
+/// Split `/name@bot args` into its name, arguments and target bot.
+fn parse_command(text: &str) -> Option<(&str, &str, Option<&str>)> {
+    let rest = text.strip_prefix('/')?;
+    let (command, args) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    let (name, bot) = match command.split_once('@') {
+        Some((name, bot)) => (name, Some(bot)),
+        None => (command, None),
+    };
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    Some((name, args.trim(), bot))
+}
+
+/// `text` without `@username` (any case) and a `,` or `:` after it.
+fn strip_mention(text: &str, username: &str) -> String {
+    let mention = format!("@{}", username.to_lowercase());
+    let lower = text.to_lowercase();
+    // Lowercasing can change lengths outside ASCII; only strip when it didn't.
+    if lower.len() != text.len() || !lower.contains(&mention) {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = 0;
+    for (start, _) in lower.match_indices(&mention) {
+        out.push_str(&text[rest..start]);
+        rest = start + mention.len();
+        if text[rest..].starts_with([',', ':']) {
+            rest += 1;
+        }
+    }
+    out.push_str(&text[rest..]);
+    out.split(' ').filter(|w| !w.is_empty()).collect::<Vec<_>>().join(" ").trim().to_string()
+}
+
+/// Extension for a saved file whose Telegram path has none.
+fn default_extension(kind: AttachmentKind) -> &'static str {
+    match kind {
+        AttachmentKind::Image => "jpg",
+        AttachmentKind::Document => "bin",
+        AttachmentKind::Voice => "ogg",
+    }
+}
+
+/// Letters, digits, `-` and `_` of `name`, so it is one path component.
+fn safe_file_name(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_')).collect()
+}
+
+fn push_line(content: &mut String, line: &str) {
+    if !content.is_empty() {
+        content.push('\n');
+    }
+    content.push_str(line);
+}
+
+/// Inline keyboard button press.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TelegramCallbackQuery {
+    pub id: String,
+    pub from: TelegramUser,
+    pub message: Option<TelegramMessage>,
+    pub data: Option<String>,
+}
+
+/// Parse `approval:approve:<id>` / `approval:deny:<id>` button data.
+fn parse_approval_callback(data: &str) -> Option<(bool, &str)> {
+    let rest = data.strip_prefix("approval:")?;
+    if let Some(id) = rest.strip_prefix("approve:") {
+        Some((true, id))
+    } else {
+        rest.strip_prefix("deny:").map(|id| (false, id))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -234,6 +1200,88 @@ pub struct TelegramMessage {
     pub text: Option<String>,
     pub date: i64,
     pub reply_to_message: Option<Box<TelegramMessage>>,
+    /// Caption of a photo, video, document, ...
+    pub caption: Option<String>,
+    pub photo: Option<serde_json::Value>,
+    pub sticker: Option<serde_json::Value>,
+    pub animation: Option<serde_json::Value>,
+    pub video: Option<serde_json::Value>,
+    pub video_note: Option<serde_json::Value>,
+    pub voice: Option<serde_json::Value>,
+    pub audio: Option<serde_json::Value>,
+    pub document: Option<serde_json::Value>,
+    pub location: Option<serde_json::Value>,
+    pub contact: Option<serde_json::Value>,
+    pub poll: Option<serde_json::Value>,
+}
+
+impl TelegramMessage {
+    /// The photo (its largest size), document or voice message, if any.
+    fn media(&self) -> Option<MediaFile> {
+        let (kind, file) = if let Some(sizes) = self.photo.as_ref().and_then(|p| p.as_array()) {
+            // Sizes are listed smallest first.
+            (AttachmentKind::Image, sizes.last()?)
+        } else if let Some(document) = &self.document {
+            (AttachmentKind::Document, document)
+        } else if let Some(voice) = &self.voice {
+            (AttachmentKind::Voice, voice)
+        } else {
+            return None;
+        };
+        let file_id = file["file_id"].as_str()?.to_string();
+        let mime_type = file["mime_type"].as_str().map(String::from).or(match kind {
+            AttachmentKind::Image => Some("image/jpeg".into()),
+            AttachmentKind::Voice => Some("audio/ogg".into()),
+            AttachmentKind::Document => None,
+        });
+        Some(MediaFile {
+            kind,
+            file_unique_id: file["file_unique_id"].as_str().unwrap_or(&file_id).to_string(),
+            file_id,
+            file_size: file["file_size"].as_u64(),
+            mime_type,
+            file_name: file["file_name"].as_str().map(String::from),
+        })
+    }
+
+    /// The text, or a placeholder such as "[photo] caption" for media, as
+    /// kept for group summaries. `None` for service messages (joins, pins).
+    pub fn summary_text(&self) -> Option<String> {
+        if let Some(text) = &self.text {
+            return Some(text.clone());
+        }
+        let caption = self.caption.as_deref();
+        let kind = if self.photo.is_some() {
+            "photo".to_string()
+        } else if let Some(sticker) = &self.sticker {
+            match sticker["emoji"].as_str() {
+                Some(emoji) => format!("sticker {emoji}"),
+                None => "sticker".into(),
+            }
+        } else if self.animation.is_some() {
+            "GIF".into()
+        } else if self.video.is_some() || self.video_note.is_some() {
+            "video".into()
+        } else if self.voice.is_some() {
+            "voice message".into()
+        } else if self.audio.is_some() {
+            "audio".into()
+        } else if let Some(document) = &self.document {
+            match document["file_name"].as_str() {
+                Some(name) => format!("file: {name}"),
+                None => "file".into(),
+            }
+        } else if self.location.is_some() {
+            "location".into()
+        } else if self.contact.is_some() {
+            "contact".into()
+        } else if let Some(poll) = &self.poll {
+            format!("poll: {}", poll["question"].as_str().unwrap_or(""))
+        } else {
+            return None;
+        };
+        Some(with_placeholder(&kind, caption))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -245,6 +1293,16 @@ pub struct TelegramUser {
     pub username: Option<String>,
 }
 
+impl TelegramUser {
+    /// First and last name.
+    pub fn display_name(&self) -> String {
+        match &self.last_name {
+            Some(last) => format!("{} {last}", self.first_name),
+            None => self.first_name.clone(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TelegramChat {
     pub id: i64,
@@ -254,10 +1312,14 @@ pub struct TelegramChat {
 }
 
 impl TelegramUpdate {
-    /// Convert to BizClaw IncomingMessage.
+    /// Convert to BizClaw IncomingMessage: the text or caption, without the
+    /// media (the channel downloads that). `None` unless there is one of them.
     pub fn to_incoming(&self) -> Option<IncomingMessage> {
         let msg = self.message.as_ref()?;
-        let text = msg.text.as_ref()?;
+        let text = msg.text.as_ref().or(msg.caption.as_ref());
+        if text.is_none() && msg.media().is_none() {
+            return None;
+        }
         let from = msg.from.as_ref()?;
 
         // Skip bot messages
@@ -267,9 +1329,8 @@ impl TelegramUpdate {
             channel: "telegram".into(),
             thread_id: msg.chat.id.to_string(),
             sender_id: from.id.to_string(),
-            sender_name: Some(format!("{}{}", from.first_name,
-                from.last_name.as_deref().map(|l| format!(" {l}")).unwrap_or_default())),
-            content: text.clone(),
+            sender_name: Some(from.display_name()),
+            content: text.cloned().unwrap_or_default(),
             thread_type: match msg.chat.chat_type.as_str() {
                 "private" => ThreadType::Direct,
                 _ => ThreadType::Group,
@@ -277,6 +1338,465 @@ impl TelegramUpdate {
             timestamp: chrono::Utc::now(),
             reply_to: msg.reply_to_message.as_ref()
                 .map(|r| r.message_id.to_string()),
+            attachments: Vec::new(),
+            message_id: Some(self.update_id.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callback_query_update() {
+        let update: TelegramUpdate = serde_json::from_value(serde_json::json!({
+            "update_id": 7,
+            "callback_query": {
+                "id": "cb1",
+                "from": { "id": 42, "is_bot": false, "first_name": "Lan", "username": "lan" },
+                "data": "approval:deny:abc-123"
+            }
+        })).unwrap();
+        assert!(update.to_incoming().is_none());
+        let data = update.callback_query.unwrap().data.unwrap();
+        assert_eq!(parse_approval_callback(&data), Some((false, "abc-123")));
+        assert_eq!(parse_approval_callback("approval:approve:x"), Some((true, "x")));
+        assert_eq!(parse_approval_callback("other"), None);
+    }
+
+    fn group_update(chat_id: i64, message: serde_json::Value) -> TelegramUpdate {
+        let mut message = message;
+        message["message_id"] = 1.into();
+        message["date"] = 1_700_000_000.into();
+        message["chat"] = serde_json::json!({ "id": chat_id, "type": "supergroup", "title": "Sales team" });
+        message["from"] = serde_json::json!({ "id": 42, "is_bot": false, "first_name": "Lan", "last_name": "Tran" });
+        serde_json::from_value(serde_json::json!({ "update_id": 1, "message": message })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_group_updates_buffered() {
+        let buffer = MessageBuffer::new();
+        let media = TelegramMediaConfig { photos: false, ..Default::default() };
+        let config = TelegramConfig { bot_token: "t".into(), media, respond_to_all: true, ..Default::default() };
+        let channel = TelegramChannel::new(config)
+            .with_group_buffer(buffer.clone(), SummarizeGroupsConfig {
+                group_ids: vec!["-100".into()],
+                include_own_messages: true,
+            });
+
+        let incoming = channel.handle_update(&group_update(-100, serde_json::json!({ "text": "Chốt đơn nhé" }))).await;
+        assert_eq!(incoming.unwrap().content, "Chốt đơn nhé");
+        // The agent gets the caption (photos are off), the buffer a placeholder.
+        let incoming = channel.handle_update(&group_update(-100, serde_json::json!({
+            "photo": [{ "file_id": "p" }], "caption": "Bảng giá"
+        }))).await.unwrap();
+        assert_eq!((incoming.content.as_str(), incoming.attachments.len()), ("Bảng giá", 0));
+        assert!(channel.handle_update(&group_update(-100, serde_json::json!({
+            "sticker": { "file_id": "s", "emoji": "👍" }
+        }))).await.is_none());
+        channel.handle_update(&group_update(-100, serde_json::json!({ "new_chat_members": [] }))).await;
+        // Not a monitored group.
+        channel.handle_update(&group_update(-200, serde_json::json!({ "text": "ignored" }))).await;
+        channel.record_own(-100, "Đã ghi nhận");
+
+        assert_eq!(buffer.group_ids(), vec!["-100"]);
+        let messages = buffer.drain_group("-100");
+        let contents: Vec<_> = messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, ["Chốt đơn nhé", "[photo] Bảng giá", "[sticker 👍]", "Đã ghi nhận"]);
+        assert_eq!(messages[0].sender_name, "Lan Tran");
+        assert_eq!(messages[0].group_name, "Sales team");
+        assert_eq!(messages[0].timestamp.timestamp(), 1_700_000_000);
+        assert_eq!((messages[3].sender_name.as_str(), messages[3].group_name.as_str()), ("Bot", "Sales team"));
+        assert!(messages.iter().all(|m| m.channel == "telegram"));
+    }
+
+    /// Bot API stand-in: answers each request with the next canned
+    /// `(status, body)` and reports the requests it saw.
+    async fn bot_api(responses: Vec<(u16, serde_json::Value)>) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            for (status, body) in responses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 1024];
+                loop {
+                    if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&request[..end]).to_lowercase();
+                        let length: usize = head.lines()
+                            .find_map(|l| l.strip_prefix("content-length:").map(|v| v.trim().parse().unwrap_or(0)))
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                    let n = stream.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let _ = tx.send(String::from_utf8_lossy(&request).into_owned());
+                let body = body.to_string();
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (base, rx)
+    }
+
+    fn text_update(update_id: i64, chat_id: i64, text: &str) -> serde_json::Value {
+        serde_json::json!({
+            "update_id": update_id,
+            "message": {
+                "message_id": update_id, "date": 1_700_000_000, "text": text,
+                "chat": { "id": chat_id, "type": "private" },
+                "from": { "id": chat_id, "is_bot": false, "first_name": "Lan" }
+            }
         })
     }
+
+    fn chat_settings(name: &str) -> Arc<ChatSettingsStore> {
+        let path = std::env::temp_dir().join(format!("bizclaw-tg-{name}-{}.json", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        Arc::new(ChatSettingsStore::open(path))
+    }
+
+    #[tokio::test]
+    async fn test_commands() {
+        let sent = serde_json::json!({ "ok": true, "result": { "message_id": 1 } });
+        let (base, mut requests) = bot_api(vec![
+            (200, serde_json::json!({ "ok": true, "result": { "id": 9, "is_bot": true, "first_name": "Shop", "username": "ShopBot" } })),
+            (200, serde_json::json!({ "ok": true, "result": true })),
+            (200, sent.clone()),
+            (200, sent.clone()),
+            (200, sent.clone()),
+            (200, sent),
+        ]).await;
+        let settings = chat_settings("commands");
+        let mut channel = TelegramChannel::new(TelegramConfig {
+            bot_token: "t".into(), api_base: base, parse_mode: "none".into(), ..Default::default()
+        }).with_chat_settings(settings.clone());
+        channel.connect().await.unwrap();
+        requests.recv().await.unwrap();
+        let register = requests.recv().await.unwrap();
+        assert!(register.starts_with("POST /bott/setMyCommands "), "{register}");
+        assert!(register.contains(r#"{"command":"reset","description":"Clear the conversation"}"#));
+
+        let update = |text: &str| serde_json::from_value::<TelegramUpdate>(text_update(1, 42, text)).unwrap();
+        assert!(channel.handle_update(&update("/help")).await.is_none());
+        assert!(requests.recv().await.unwrap().contains("/model — Show or change the model for this chat"));
+
+        assert!(channel.handle_update(&update("/model gpt-4o-mini")).await.is_none());
+        assert!(requests.recv().await.unwrap().contains("This chat now uses gpt-4o-mini."));
+        assert!(channel.handle_update(&update("/reset")).await.is_none());
+        assert!(requests.recv().await.unwrap().contains("Conversation cleared"));
+        // Saved, so a restarted bot keeps them.
+        let reopened = ChatSettingsStore::open(settings.path());
+        assert_eq!(reopened.get("42").model.as_deref(), Some("gpt-4o-mini"));
+        assert!(reopened.get("42").reset_at.is_some());
+
+        assert!(channel.handle_update(&update("/model default")).await.is_none());
+        assert!(requests.recv().await.unwrap().contains("default model"));
+        assert_eq!(settings.get("42").model, None);
+
+        // /summary asks the agent; unknown commands go to it unchanged.
+        let summary = channel.handle_update(&update("/summary@ShopBot")).await.unwrap();
+        assert!(summary.content.starts_with("Summarize our conversation"), "{}", summary.content);
+        assert_eq!(channel.handle_update(&update("/price ao-thun")).await.unwrap().content, "/price ao-thun");
+        // Addressed to another bot.
+        assert!(channel.handle_update(&update("/help@OtherBot")).await.is_none());
+        std::fs::remove_file(settings.path()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_group_answers_commands_and_mentions() {
+        let mut channel = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), ..Default::default() })
+            .with_chat_settings(chat_settings("group"));
+        channel.bot_username = Some("ShopBot".into());
+        let bot = serde_json::json!({ "message_id": 5, "date": 1, "chat": { "id": -100, "type": "group" },
+            "from": { "id": 9, "is_bot": true, "first_name": "Shop", "username": "shopbot" }, "text": "Chào" });
+
+        assert!(channel.handle_update(&group_update(-100, serde_json::json!({ "text": "Ai đi ăn trưa?" }))).await.is_none());
+        let mention = channel.handle_update(&group_update(-100, serde_json::json!({ "text": "@shopbot giá áo thun?" }))).await;
+        assert_eq!(mention.unwrap().content, "giá áo thun?");
+        let reply = channel.handle_update(&group_update(-100, serde_json::json!({ "text": "Còn size M không?", "reply_to_message": bot }))).await;
+        assert_eq!(reply.unwrap().content, "Còn size M không?");
+        assert!(channel.handle_update(&group_update(-100, serde_json::json!({ "text": "/price" }))).await.is_some());
+
+        channel.config.respond_to_all = true;
+        assert!(channel.handle_update(&group_update(-100, serde_json::json!({ "text": "Ai đi ăn trưa?" }))).await.is_some());
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(parse_command("/model  gpt-4o "), Some(("model", "gpt-4o", None)));
+        assert_eq!(parse_command("/help@ShopBot"), Some(("help", "", Some("ShopBot"))));
+        assert_eq!(parse_command("/"), None);
+        assert_eq!(parse_command("/tmp/file.txt"), None);
+        assert_eq!(parse_command("hello /help"), None);
+        assert_eq!(strip_mention("/help@ShopBot", "shopbot"), "/help");
+        assert_eq!(strip_mention("Hi @SHOPBOT, giá?", "ShopBot"), "Hi giá?");
+    }
+
+    #[tokio::test]
+    async fn test_get_updates_offsets_and_conflict() {
+        let (base, mut requests) = bot_api(vec![
+            (200, serde_json::json!({ "ok": true, "result": [text_update(6, 1, "b"), text_update(5, 1, "a")] })),
+            (200, serde_json::json!({ "ok": true, "result": [] })),
+            (409, serde_json::json!({
+                "ok": false, "error_code": 409,
+                "description": "Conflict: terminated by other getUpdates request; make sure that only one bot instance is running"
+            })),
+        ]).await;
+        let mut channel = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), api_base: base, ..Default::default() });
+
+        assert_eq!(channel.get_updates().await.unwrap().len(), 2);
+        assert!(requests.recv().await.unwrap().contains("offset=1&"));
+        // The next poll confirms both updates, even though they arrived out of order.
+        assert!(channel.get_updates().await.unwrap().is_empty());
+        let line = requests.recv().await.unwrap();
+        assert!(line.starts_with("GET /bott/getUpdates?offset=7&timeout=30"), "{line}");
+
+        let err = channel.get_updates().await.unwrap_err();
+        assert!(matches!(&err, PollError::Conflict(d) if d.contains("only one bot instance")), "{err:?}");
+        assert!(BizClawError::from(err).to_string().contains("409"));
+        assert_eq!(channel.last_update_id, 6);
+    }
+
+    #[tokio::test]
+    async fn test_allowed_chat_ids() {
+        let channel = TelegramChannel::new(TelegramConfig {
+            bot_token: "t".into(),
+            allowed_chat_ids: vec![1],
+            ..Default::default()
+        });
+        let update = |chat_id| serde_json::from_value::<TelegramUpdate>(text_update(1, chat_id, "hi")).unwrap();
+        assert_eq!(channel.handle_update(&update(1)).await.unwrap().thread_id, "1");
+        assert!(channel.handle_update(&update(2)).await.is_none());
+
+        let open = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), ..Default::default() });
+        assert!(open.handle_update(&update(2)).await.is_some());
+    }
+
+    fn media_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bizclaw-tg-media-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn media_update(message: serde_json::Value) -> TelegramUpdate {
+        let mut message = message;
+        message["message_id"] = 1.into();
+        message["date"] = 1_700_000_000.into();
+        message["chat"] = serde_json::json!({ "id": 7, "type": "private" });
+        message["from"] = serde_json::json!({ "id": 7, "is_bot": false, "first_name": "Lan" });
+        serde_json::from_value(serde_json::json!({ "update_id": 1, "message": message })).unwrap()
+    }
+
+    struct FakeTranscriber;
+
+    #[async_trait]
+    impl Transcriber for FakeTranscriber {
+        async fn transcribe(&self, audio: &Path, mime_type: &str) -> Result<String> {
+            assert_eq!(std::fs::read(audio).unwrap(), b"\"OggS\"");
+            assert_eq!(mime_type, "audio/ogg");
+            Ok("Cho tôi báo giá nhé".into())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_media_downloaded_and_attached() {
+        let dir = media_dir("in");
+        let file = |path: &str, size: u64| serde_json::json!({
+            "ok": true, "result": { "file_id": "f", "file_size": size, "file_path": path }
+        });
+        let (base, mut requests) = bot_api(vec![
+            (200, file("photos/file_1.jpg", 6)),
+            (200, serde_json::json!("JPEG")),
+            (200, file("voice/file_2.oga", 6)),
+            (200, serde_json::json!("OggS")),
+        ]).await;
+        let media = TelegramMediaConfig { dir: Some(dir.display().to_string()), ..Default::default() };
+        let channel = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), api_base: base, media, ..Default::default() })
+            .with_transcriber(Arc::new(FakeTranscriber));
+
+        // The largest photo size is downloaded.
+        let incoming = channel.handle_update(&media_update(serde_json::json!({
+            "caption": "Giá bao nhiêu?",
+            "photo": [
+                { "file_id": "small", "file_unique_id": "s1", "file_size": 2 },
+                { "file_id": "f", "file_unique_id": "AQADbig", "file_size": 6 }
+            ]
+        }))).await.unwrap();
+        assert_eq!(incoming.content, "Giá bao nhiêu?");
+        let path = dir.join("telegram/7/AQADbig.jpg");
+        assert_eq!(incoming.attachments, vec![Attachment {
+            kind: AttachmentKind::Image,
+            path: path.display().to_string(),
+            mime_type: Some("image/jpeg".into()),
+            file_name: None,
+        }]);
+        assert_eq!(std::fs::read(&path).unwrap(), b"\"JPEG\"");
+        let get_file = requests.recv().await.unwrap();
+        assert!(get_file.starts_with("POST /bott/getFile "), "{get_file}");
+        assert!(get_file.ends_with(r#"{"file_id":"f"}"#), "{get_file}");
+        assert!(requests.recv().await.unwrap().starts_with("GET /file/bott/photos/file_1.jpg "));
+
+        // Voice messages reach the agent as their transcript.
+        let incoming = channel.handle_update(&media_update(serde_json::json!({
+            "voice": { "file_id": "f", "file_unique_id": "v1", "duration": 2, "mime_type": "audio/ogg", "file_size": 6 }
+        }))).await.unwrap();
+        assert_eq!(incoming.content, "Cho tôi báo giá nhé");
+        assert!(incoming.attachments.is_empty());
+
+        // Over the size limit: nothing is requested, and the agent is told.
+        let incoming = channel.handle_update(&media_update(serde_json::json!({
+            "document": { "file_id": "big", "file_unique_id": "d1", "file_name": "catalog.pdf", "file_size": 30 << 20 }
+        }))).await.unwrap();
+        assert_eq!(incoming.content, "[document not received: larger than the 20 MB download limit]");
+        assert!(incoming.attachments.is_empty());
+        assert!(requests.recv().await.unwrap().starts_with("POST /bott/getFile "));
+        assert!(requests.recv().await.unwrap().starts_with("GET /file/bott/voice/file_2.oga "));
+        assert!(requests.try_recv().is_err());
+
+        // A disabled media type without a caption is ignored.
+        let media = TelegramMediaConfig { documents: false, dir: Some(dir.display().to_string()), ..Default::default() };
+        let channel = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), media, ..Default::default() });
+        assert!(channel.handle_update(&media_update(serde_json::json!({
+            "document": { "file_id": "d", "file_unique_id": "d2", "file_size": 10 }
+        }))).await.is_none());
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_reply_sends_named_files() {
+        let dir = media_dir("out");
+        std::fs::write(dir.join("quote.png"), b"PNG").unwrap();
+        std::fs::write(dir.join("quote.xlsx"), b"XLSX").unwrap();
+        let sent = serde_json::json!({ "ok": true, "result": { "message_id": 1 } });
+        let (base, mut requests) = bot_api(vec![(200, sent.clone()), (200, sent.clone()), (200, sent)]).await;
+        let media = TelegramMediaConfig { dir: Some(dir.display().to_string()), ..Default::default() };
+        let channel = TelegramChannel::new(TelegramConfig {
+            bot_token: "t".into(), api_base: base, parse_mode: "none".into(), media, ..Default::default()
+        });
+
+        let outside = std::env::temp_dir().join(format!("bizclaw-tg-outside-{}.txt", std::process::id()));
+        std::fs::write(&outside, "secret").unwrap();
+        let content = format!(
+            "Báo giá: `{0}/quote.png`, bảng tính ({0}/quote.xlsx). Not sent: {1}, {0}/missing.pdf",
+            dir.display(), outside.display(),
+        );
+        channel.send(OutgoingMessage {
+            thread_id: "7".into(), content, thread_type: ThreadType::Direct, reply_to: None,
+        }).await.unwrap();
+
+        assert!(requests.recv().await.unwrap().starts_with("POST /bott/sendMessage "));
+        let photo = requests.recv().await.unwrap();
+        assert!(photo.starts_with("POST /bott/sendPhoto "), "{photo}");
+        assert!(photo.contains("name=\"photo\"; filename=\"quote.png\""), "{photo}");
+        assert!(photo.contains("name=\"chat_id\"\r\n\r\n7\r\n"), "{photo}");
+        let document = requests.recv().await.unwrap();
+        assert!(document.starts_with("POST /bott/sendDocument "), "{document}");
+        assert!(document.contains("filename=\"quote.xlsx\""), "{document}");
+        assert!(requests.try_recv().is_err());
+
+        // With an allowlist, files it permits are sendable too.
+        let autonomy = bizclaw_core::config::AutonomyConfig { workspace_only: false, ..Default::default() };
+        let channel = channel.with_outgoing_files(Allowlist::new(&autonomy));
+        assert_eq!(channel.reply_files(&format!("see {}", outside.display())), vec![outside.canonicalize().unwrap()]);
+        std::fs::remove_file(outside).ok();
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_webhook_secret_and_redelivery() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let channel = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), ..Default::default() });
+        let router = channel.webhook_router("/tg/hook", "s3cret", tx);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/tg/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let post = |secret: Option<&str>, update: serde_json::Value| {
+            let mut req = client.post(&url).json(&update);
+            if let Some(secret) = secret {
+                req = req.header("X-Telegram-Bot-Api-Secret-Token", secret);
+            }
+            async move { req.send().await.unwrap().status().as_u16() }
+        };
+
+        assert_eq!(post(None, text_update(10, 1, "no secret")).await, 401);
+        assert_eq!(post(Some("wrong"), text_update(10, 1, "bad secret")).await, 401);
+        assert_eq!(post(Some("s3cret"), text_update(10, 1, "xin chào")).await, 200);
+        // Telegram retries an update it thinks failed; it is handled once.
+        assert_eq!(post(Some("s3cret"), text_update(10, 1, "xin chào")).await, 200);
+        assert_eq!(post(Some("s3cret"), text_update(11, 1, "lần hai")).await, 200);
+        assert_eq!(post(Some("s3cret"), serde_json::json!({ "nope": true })).await, 400);
+
+        assert_eq!(rx.recv().await.unwrap().content, "xin chào");
+        assert_eq!(rx.recv().await.unwrap().content, "lần hai");
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_message_in_parts() {
+        let sent = serde_json::json!({ "ok": true, "result": { "message_id": 1 } });
+        let (base, mut requests) = bot_api(vec![
+            (200, sent.clone()),
+            (400, serde_json::json!({ "ok": false, "error_code": 400, "description": "Bad Request: can't parse entities: unexpected end" })),
+            (200, sent.clone()),
+        ]).await;
+        let channel = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), api_base: base, ..Default::default() });
+        let text = format!("{}\n{}", "Xin chào.\n".repeat(500), "Hết!");
+        channel.send_message(7, &text).await.unwrap();
+
+        let body = |request: String| -> serde_json::Value {
+            serde_json::from_str(request.split_once("\r\n\r\n").unwrap().1).unwrap()
+        };
+        let first = body(requests.recv().await.unwrap());
+        assert_eq!(first["parse_mode"], "MarkdownV2");
+        assert!(first["text"].as_str().unwrap().starts_with("Xin chào\\."));
+        // The second part failed to parse and went again as plain text.
+        let second = body(requests.recv().await.unwrap());
+        assert_eq!(second["parse_mode"], "MarkdownV2");
+        let retry = body(requests.recv().await.unwrap());
+        assert!(retry.get("parse_mode").is_none());
+        assert!(retry["text"].as_str().unwrap().ends_with("Hết!"));
+        assert!(requests.try_recv().is_err());
+
+        // Other errors aren't retried.
+        let (base, _requests) = bot_api(vec![
+            (403, serde_json::json!({ "ok": false, "error_code": 403, "description": "Forbidden: bot was blocked by the user" })),
+        ]).await;
+        let channel = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), api_base: base, ..Default::default() });
+        let err = channel.send_message(7, "hi").await.unwrap_err();
+        assert!(err.to_string().contains("blocked"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_set_webhook() {
+        let (base, mut requests) = bot_api(vec![
+            (200, serde_json::json!({ "ok": true, "result": true })),
+            (400, serde_json::json!({ "ok": false, "error_code": 400, "description": "Bad Request: bad webhook: HTTPS url must be provided for webhook" })),
+        ]).await;
+        let channel = TelegramChannel::new(TelegramConfig { bot_token: "t".into(), api_base: base, ..Default::default() });
+        let webhook = TelegramWebhookConfig {
+            url: "https://bot.example.com/tg/hook".into(),
+            secret_token: "s3cret".into(),
+            listen: "127.0.0.1:0".into(),
+        };
+        channel.set_webhook(&webhook).await.unwrap();
+        assert!(requests.recv().await.unwrap().starts_with("POST /bott/setWebhook "));
+        let err = channel.set_webhook(&webhook).await.unwrap_err();
+        assert!(err.to_string().contains("HTTPS url must be provided"), "{err}");
+    }
 }
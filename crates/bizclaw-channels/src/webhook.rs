@@ -1,34 +1,540 @@
 //! Webhook channel — receive inbound HTTP webhooks and send outbound.
 //!
-//! Useful for integrating with external systems (Zapier, n8n, custom APIs).
+//! Useful for integrating with external systems (Zapier, n8n, CRMs, custom
+//! APIs). Inbound payloads are POSTed to `path` with an HMAC-SHA256
+//! signature of the raw body, and mapped to a message through dotted field
+//! paths; replies are POSTed to `outbound_url`, signed the same way.
+//!
+//! Conversations can also come in at `POST /webhook/in` (or
+//! `/webhook/in/<name>` for a named endpoint with its own secret) as
+//! `{conversation_id, user, text, metadata}`. Those requests are signed over
+//! `<timestamp>.<raw body>`, with the Unix time in the timestamp header, and
+//! are refused when older than `max_age_secs` or already seen. The reply is
+//! the response to the request, or, when the endpoint has a `callback_url`,
+//! is POSTed there once it is ready.
+//!
+//! Outbound messages go through a [`WebhookDeliveryQueue`], which retries
+//! failed POSTs with backoff and hands deliveries that never succeed to a
+//! [`DeadLetterStore`]. Attempts can be kept in a [`WebhookDeliveryLog`].
 
 use async_trait::async_trait;
+use bizclaw_core::config::{WebhookEndpointConfig, WebhookFieldMap};
 use bizclaw_core::error::{BizClawError, Result};
-use bizclaw_core::traits::Channel;
+use bizclaw_core::traits::{Channel, DroppedMessage};
 use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
 use futures::stream::{self, Stream};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
 use std::pin::Pin;
-use std::sync::Arc;
-use tokio::sync::mpsc;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// Waits before each retry of a failed delivery; after the last one fails,
+/// the delivery goes to the dead-letter store.
+pub const RETRY_DELAYS: [Duration; 5] = [
+    Duration::from_secs(30),
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(30 * 60),
+    Duration::from_secs(2 * 3600),
+    Duration::from_secs(24 * 3600),
+];
+/// Deliveries waiting for the worker before `enqueue` waits.
+const QUEUE_CAPACITY: usize = 1024;
+/// Limit on one delivery attempt.
+const ATTEMPT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Attempts kept in the delivery log.
+const LOG_ROWS: i64 = 1000;
+/// `/webhook/in` conversations with no request waiting are forgotten after
+/// this many seconds without a message.
+const CONVERSATION_IDLE_SECS: i64 = 3600;
+
+/// A payload to POST to a webhook URL.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub id: String,
+    /// Tenant the delivery belongs to, when run under the platform.
+    pub tenant_id: Option<String>,
+    pub url: String,
+    pub payload: serde_json::Value,
+    /// Extra headers, such as a signature of the payload.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub headers: Vec<(String, String)>,
+}
+
+impl WebhookDelivery {
+    pub fn new(tenant_id: Option<String>, url: impl Into<String>, payload: serde_json::Value) -> Self {
+        Self { id: uuid::Uuid::new_v4().to_string(), tenant_id, url: url.into(), payload, headers: Vec::new() }
+    }
+
+    /// Sign the payload (as sent: compact JSON) into `header` as
+    /// `sha256=<hex HMAC-SHA256>`.
+    pub fn signed(mut self, header: &str, secret: &str) -> Self {
+        let signature = sign(secret, self.payload.to_string().as_bytes());
+        self.headers.push((header.to_string(), signature));
+        self
+    }
+
+    /// Put the current Unix time in `timestamp_header` and sign
+    /// `<timestamp>.<payload>` into `signature_header`, the way `/webhook/in`
+    /// requests are signed. Retries carry the same timestamp.
+    pub fn timestamped(mut self, signature_header: &str, timestamp_header: &str, secret: &str) -> Self {
+        let timestamp = chrono::Utc::now().timestamp();
+        let signature = sign_timestamped(secret, timestamp, self.payload.to_string().as_bytes());
+        self.headers.push((timestamp_header.to_string(), timestamp.to_string()));
+        self.headers.push((signature_header.to_string(), signature));
+        self
+    }
+}
+
+/// A delivery that failed every attempt.
+#[derive(Debug, Clone)]
+pub struct FailedDelivery {
+    pub delivery: WebhookDelivery,
+    pub attempts: u32,
+    pub last_error: String,
+}
+
+/// Keeps deliveries that ran out of retries, so they can be inspected and retried by hand.
+pub trait DeadLetterStore: Send + Sync {
+    fn store(&self, failed: &FailedDelivery) -> Result<()>;
+}
+
+/// How a delivery attempt turned out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryOutcome {
+    Delivered,
+    /// Failed; another attempt is scheduled.
+    Retrying,
+    /// Failed, and it was the last attempt.
+    Failed,
+}
+
+impl DeliveryOutcome {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Delivered => "delivered",
+            Self::Retrying => "retrying",
+            Self::Failed => "failed",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "delivered" => Self::Delivered,
+            "retrying" => Self::Retrying,
+            _ => Self::Failed,
+        }
+    }
+}
+
+/// One attempt at a delivery.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeliveryAttempt {
+    pub delivery_id: String,
+    pub url: String,
+    /// 1 for the first try.
+    pub attempt: u32,
+    pub outcome: DeliveryOutcome,
+    pub error: Option<String>,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// The last delivery attempts, in SQLite so the gateway can show what the
+/// channel process sent.
+pub struct WebhookDeliveryLog {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+impl WebhookDeliveryLog {
+    /// Open (or create) the log at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = rusqlite::Connection::open(path)
+            .map_err(|e| BizClawError::Channel(format!("Webhook log open error: {e}")))?;
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS webhook_deliveries (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                delivery_id TEXT NOT NULL,
+                url TEXT NOT NULL,
+                attempt INTEGER NOT NULL,
+                outcome TEXT NOT NULL,
+                error TEXT,
+                at INTEGER NOT NULL
+            );
+        ").map_err(|e| BizClawError::Channel(format!("Webhook log migration error: {e}")))?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Open `<data dir>/webhook_deliveries.db`.
+    pub fn open_default() -> Result<Self> {
+        Self::open(&bizclaw_core::config::BizClawConfig::data_dir().join("webhook_deliveries.db"))
+    }
+
+    /// Add `attempt`, dropping the oldest beyond the last 1000.
+    pub fn record(&self, attempt: &DeliveryAttempt) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO webhook_deliveries (delivery_id, url, attempt, outcome, error, at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                attempt.delivery_id, attempt.url, attempt.attempt, attempt.outcome.as_str(),
+                attempt.error, attempt.at.timestamp_millis(),
+            ],
+        ).map_err(|e| BizClawError::Channel(format!("Record webhook attempt: {e}")))?;
+        conn.execute(
+            "DELETE FROM webhook_deliveries WHERE seq <= (SELECT MAX(seq) FROM webhook_deliveries) - ?1",
+            [LOG_ROWS],
+        ).map_err(|e| BizClawError::Channel(format!("Trim webhook log: {e}")))?;
+        Ok(())
+    }
+
+    /// The last `limit` attempts, newest first.
+    pub fn recent(&self, limit: usize) -> Result<Vec<DeliveryAttempt>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT delivery_id, url, attempt, outcome, error, at FROM webhook_deliveries
+             ORDER BY seq DESC LIMIT ?1",
+        ).map_err(|e| BizClawError::Channel(format!("List webhook attempts: {e}")))?;
+        let rows = stmt.query_map([limit as i64], |row| {
+            Ok(DeliveryAttempt {
+                delivery_id: row.get(0)?,
+                url: row.get(1)?,
+                attempt: row.get(2)?,
+                outcome: DeliveryOutcome::parse(&row.get::<_, String>(3)?),
+                error: row.get(4)?,
+                at: chrono::DateTime::from_timestamp_millis(row.get(5)?).unwrap_or_default(),
+            })
+        }).map_err(|e| BizClawError::Channel(format!("List webhook attempts: {e}")))?;
+        rows.collect::<std::result::Result<_, _>>()
+            .map_err(|e| BizClawError::Channel(format!("List webhook attempts: {e}")))
+    }
+}
+
+/// Queue of outbound webhook deliveries, drained by a background worker.
+///
+/// Each delivery is tried at once, then again after each of the delays in
+/// [`RETRY_DELAYS`]. Attempts carry an `X-Delivery-Attempt: N` header, and
+/// only a 2xx response counts as delivered. Network errors, 5xx, 408 and 429
+/// are retried; any other status means the receiver refused the payload, so
+/// it goes to the dead letters at once. Retries of one delivery don't hold
+/// up the others.
+#[derive(Clone)]
+pub struct WebhookDeliveryQueue {
+    tx: mpsc::Sender<WebhookDelivery>,
+}
+
+impl WebhookDeliveryQueue {
+    /// Start a worker with the standard retry schedule. Must be called
+    /// inside a Tokio runtime.
+    pub fn start(dead_letters: Option<Arc<dyn DeadLetterStore>>) -> Self {
+        Self::with_delays(dead_letters, RETRY_DELAYS.to_vec())
+    }
+
+    /// Start a worker that waits `delays` between attempts.
+    pub fn with_delays(dead_letters: Option<Arc<dyn DeadLetterStore>>, delays: Vec<Duration>) -> Self {
+        Self::with_log(dead_letters, delays, None)
+    }
+
+    /// Start a worker that waits `delays` between attempts and records each
+    /// attempt in `log`.
+    pub fn with_log(
+        dead_letters: Option<Arc<dyn DeadLetterStore>>,
+        delays: Vec<Duration>,
+        log: Option<Arc<WebhookDeliveryLog>>,
+    ) -> Self {
+        let (tx, mut rx) = mpsc::channel::<WebhookDelivery>(QUEUE_CAPACITY);
+        let client = reqwest::Client::builder()
+            .timeout(ATTEMPT_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        let delays: Arc<[Duration]> = delays.into();
+        tokio::spawn(async move {
+            while let Some(delivery) = rx.recv().await {
+                tokio::spawn(deliver_with_retries(client.clone(), delivery, delays.clone(), dead_letters.clone(), log.clone()));
+            }
+        });
+        Self { tx }
+    }
+
+    /// Queue `delivery`; waits only if the queue is full.
+    pub async fn enqueue(&self, delivery: WebhookDelivery) -> Result<()> {
+        self.tx.send(delivery).await
+            .map_err(|_| BizClawError::Channel("Webhook delivery queue closed".into()))
+    }
+}
+
+async fn deliver_with_retries(
+    client: reqwest::Client,
+    delivery: WebhookDelivery,
+    delays: Arc<[Duration]>,
+    dead_letters: Option<Arc<dyn DeadLetterStore>>,
+    log: Option<Arc<WebhookDeliveryLog>>,
+) {
+    let record = |attempt: u32, outcome: DeliveryOutcome, error: Option<&str>| {
+        let Some(log) = &log else { return };
+        let entry = DeliveryAttempt {
+            delivery_id: delivery.id.clone(),
+            url: delivery.url.clone(),
+            attempt,
+            outcome,
+            error: error.map(str::to_string),
+            at: chrono::Utc::now(),
+        };
+        if let Err(e) = log.record(&entry) {
+            tracing::warn!("Could not log webhook delivery {}: {e}", delivery.id);
+        }
+    };
+    let mut attempt = 1;
+    let last_error = loop {
+        let error = match attempt_delivery(&client, &delivery, attempt).await {
+            Ok(()) => {
+                record(attempt, DeliveryOutcome::Delivered, None);
+                return;
+            }
+            Err(e) => e,
+        };
+        let delay = delays.get(attempt as usize - 1).filter(|_| error.retry);
+        let Some(delay) = delay else {
+            record(attempt, DeliveryOutcome::Failed, Some(&error.message));
+            break error.message;
+        };
+        record(attempt, DeliveryOutcome::Retrying, Some(&error.message));
+        tracing::debug!("Webhook delivery {} attempt {attempt} failed: {}; retrying in {}s", delivery.id, error.message, delay.as_secs());
+        tokio::time::sleep(*delay).await;
+        attempt += 1;
+    };
+
+    tracing::warn!(
+        target: "bizclaw::audit",
+        event = "webhook_delivery_failed",
+        delivery_id = %delivery.id,
+        tenant_id = delivery.tenant_id.as_deref().unwrap_or(""),
+        url = %delivery.url,
+        attempts = attempt,
+        "webhook delivery failed: {last_error}"
+    );
+    if let Some(store) = dead_letters {
+        let failed = FailedDelivery { delivery, attempts: attempt, last_error };
+        if let Err(e) = store.store(&failed) {
+            tracing::error!("Could not save failed webhook {}: {e}", failed.delivery.id);
+        }
+    }
+}
+
+/// A failed attempt, and whether another one could succeed.
+struct AttemptError {
+    message: String,
+    retry: bool,
+}
+
+/// One POST of `delivery`; any non-2xx status is an error.
+async fn attempt_delivery(client: &reqwest::Client, delivery: &WebhookDelivery, attempt: u32) -> std::result::Result<(), AttemptError> {
+    let mut request = client.post(&delivery.url)
+        .header("X-Delivery-Attempt", attempt.to_string())
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(delivery.payload.to_string());
+    for (name, value) in &delivery.headers {
+        request = request.header(name, value);
+    }
+    let resp = request.send()
+        .await
+        .map_err(|e| AttemptError { message: e.to_string(), retry: true })?;
+    let status = resp.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        let retry = status.is_server_error()
+            || status == reqwest::StatusCode::REQUEST_TIMEOUT
+            || status == reqwest::StatusCode::TOO_MANY_REQUESTS;
+        Err(AttemptError { message: format!("HTTP {status}"), retry })
+    }
+}
+
+/// `sha256=<hex HMAC-SHA256 of body>`.
+fn sign(secret: &str, body: &[u8]) -> String {
+    use hmac::{Hmac, Mac};
+    // HMAC takes keys of any length.
+    let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC key");
+    mac.update(body);
+    let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{b:02x}")).collect();
+    format!("sha256={hex}")
+}
+
+/// `sha256=<hex HMAC-SHA256 of "<timestamp>.<body>">`.
+fn sign_timestamped(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut signed = format!("{timestamp}.").into_bytes();
+    signed.extend_from_slice(body);
+    sign(secret, &signed)
+}
+
+/// Check a signature made by [`sign`]; the `sha256=` prefix is optional.
+fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    check_signature(&sign(secret, body), signature)
+}
+
+/// Compare `signature` with `expected` in constant time, ignoring case and
+/// an optional `sha256=` prefix.
+fn check_signature(expected: &str, signature: &str) -> bool {
+    use subtle::ConstantTimeEq;
+    let given = signature.trim();
+    let given = given.strip_prefix("sha256=").unwrap_or(given).to_ascii_lowercase();
+    let expected = expected.strip_prefix("sha256=").unwrap_or(expected);
+    bool::from(expected.as_bytes().ct_eq(given.as_bytes()))
+}
 
 /// Webhook channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WebhookConfig {
     /// URL to send outbound messages to.
     pub outbound_url: Option<String>,
-    /// Secret for verifying inbound webhooks.
+    /// Secret for signing both directions; inbound requests must be signed
+    /// when it is set.
     pub secret: Option<String>,
     #[serde(default = "default_true")]
     pub enabled: bool,
+    /// Header carrying the signature.
+    #[serde(default = "default_signature_header")]
+    pub signature_header: String,
+    /// Local address the inbound server listens on.
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Route inbound payloads are POSTed to.
+    #[serde(default = "default_path")]
+    pub path: String,
+    /// Where the message fields are in inbound payloads.
+    #[serde(default)]
+    pub fields: WebhookFieldMap,
+    /// Shape of outbound payloads; see [`render_template`].
+    #[serde(default)]
+    pub outbound_template: Option<serde_json::Value>,
+    /// JMESPath expression inbound payloads go through first; see
+    /// [`apply_transform`].
+    #[serde(default)]
+    pub input_transform: Option<String>,
+    /// JMESPath expression outbound payloads go through last.
+    #[serde(default)]
+    pub output_transform: Option<String>,
+    /// Where the message is in a transformed inbound payload.
+    #[serde(default = "default_content_field")]
+    pub content_field: String,
+    /// Header carrying the signing time of `/webhook/in` requests.
+    #[serde(default = "default_timestamp_header")]
+    pub timestamp_header: String,
+    /// Age, in seconds, past which a `/webhook/in` request is a replay.
+    #[serde(default = "default_max_age")]
+    pub max_age_secs: u64,
+    /// Where replies to `/webhook/in` are POSTed instead of returned.
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    #[serde(default = "default_callback_retries")]
+    pub callback_retries: u32,
+    /// Wait before the first callback retry; it doubles for each next one.
+    #[serde(default = "default_callback_backoff_ms")]
+    pub callback_backoff_ms: u64,
+    /// How long a `/webhook/in` request waits for the reply.
+    #[serde(default = "default_reply_timeout")]
+    pub reply_timeout_secs: u64,
+    /// Endpoints at `/webhook/in/<name>`.
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpointConfig>,
 }
 
 fn default_true() -> bool { true }
+fn default_signature_header() -> String { "X-BizClaw-Signature".into() }
+fn default_listen() -> String { "0.0.0.0:8445".into() }
+fn default_path() -> String { "/webhook".into() }
+fn default_timestamp_header() -> String { "X-BizClaw-Timestamp".into() }
+fn default_max_age() -> u64 { 300 }
+fn default_callback_retries() -> u32 { 5 }
+fn default_callback_backoff_ms() -> u64 { 1000 }
+fn default_reply_timeout() -> u64 { 120 }
+fn default_content_field() -> String { "message".into() }
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            outbound_url: None,
+            secret: None,
+            enabled: true,
+            signature_header: default_signature_header(),
+            listen: default_listen(),
+            path: default_path(),
+            fields: WebhookFieldMap::default(),
+            outbound_template: None,
+            input_transform: None,
+            output_transform: None,
+            content_field: default_content_field(),
+            timestamp_header: default_timestamp_header(),
+            max_age_secs: default_max_age(),
+            callback_url: None,
+            callback_retries: default_callback_retries(),
+            callback_backoff_ms: default_callback_backoff_ms(),
+            reply_timeout_secs: default_reply_timeout(),
+            endpoints: Vec::new(),
+        }
+    }
+}
+
+impl WebhookConfig {
+    /// Secret and callback URL of the `/webhook/in` endpoint `name`;
+    /// `default` is the top-level one, served when `secret` is set.
+    fn endpoint(&self, name: &str) -> Option<(&str, Option<&str>)> {
+        if name == "default" {
+            return self.secret.as_deref().map(|secret| (secret, self.callback_url.as_deref()));
+        }
+        self.endpoints.iter()
+            .find(|e| e.name == name)
+            .map(|e| (e.secret.as_str(), e.callback_url.as_deref()))
+    }
+
+    /// Waits between callback attempts: `callback_backoff_ms`, doubling.
+    fn callback_delays(&self) -> Vec<Duration> {
+        (0..self.callback_retries)
+            .map(|i| Duration::from_millis(self.callback_backoff_ms.saturating_mul(1 << i.min(20))))
+            .collect()
+    }
+}
+
+impl From<&bizclaw_core::config::WebhookChannelConfig> for WebhookConfig {
+    fn from(cfg: &bizclaw_core::config::WebhookChannelConfig) -> Self {
+        Self {
+            outbound_url: cfg.outbound_url.clone(),
+            secret: Some(cfg.secret.clone()).filter(|s| !s.is_empty()),
+            enabled: cfg.enabled,
+            signature_header: cfg.signature_header.clone(),
+            listen: cfg.listen.clone(),
+            path: cfg.path.clone(),
+            fields: cfg.fields.clone(),
+            outbound_template: cfg.outbound_template.clone(),
+            input_transform: cfg.input_transform.clone(),
+            output_transform: cfg.output_transform.clone(),
+            content_field: cfg.content_field.clone(),
+            timestamp_header: cfg.timestamp_header.clone(),
+            max_age_secs: cfg.max_age_secs,
+            callback_url: cfg.callback_url.clone(),
+            callback_retries: cfg.callback_retries,
+            callback_backoff_ms: cfg.callback_backoff_ms,
+            reply_timeout_secs: cfg.reply_timeout_secs,
+            endpoints: cfg.endpoints.clone(),
+        }
+    }
+}
 
 /// Webhook channel.
 pub struct WebhookChannel {
     config: WebhookConfig,
-    client: reqwest::Client,
+    /// Started on first send, inside the runtime.
+    deliveries: OnceLock<WebhookDeliveryQueue>,
+    /// Replies to `/webhook/in` callbacks, retried on `callback_delays`.
+    callbacks: OnceLock<WebhookDeliveryQueue>,
+    log: Option<Arc<WebhookDeliveryLog>>,
+    conversations: Arc<Conversations>,
     connected: bool,
     /// Sender for injecting inbound messages.
     inbound_tx: mpsc::UnboundedSender<IncomingMessage>,
@@ -40,46 +546,464 @@ impl WebhookChannel {
         let (tx, rx) = mpsc::unbounded_channel();
         Self {
             config,
-            client: reqwest::Client::new(),
+            deliveries: OnceLock::new(),
+            callbacks: OnceLock::new(),
+            log: None,
+            conversations: Arc::default(),
             connected: false,
             inbound_tx: tx,
             inbound_rx: Some(rx),
         }
     }
 
+    /// Record every delivery attempt in `log`.
+    pub fn with_delivery_log(mut self, log: Arc<WebhookDeliveryLog>) -> Self {
+        self.log = Some(log);
+        self
+    }
+
     /// Inject an inbound message (called from HTTP handler).
     pub fn inject_message(&self, msg: IncomingMessage) -> Result<()> {
         self.inbound_tx.send(msg)
             .map_err(|_| BizClawError::Channel("Webhook receiver closed".into()))
     }
 
+    /// Take the receiving end of the inbound queue (can only be taken once).
+    pub fn take_receiver(&mut self) -> Option<mpsc::UnboundedReceiver<IncomingMessage>> {
+        self.inbound_rx.take()
+    }
+
     /// Parse and verify an inbound webhook payload.
     pub fn parse_inbound(&self, payload: &str, signature: Option<&str>) -> Result<IncomingMessage> {
-        // Verify signature if secret is configured
-        if let (Some(secret), Some(sig)) = (&self.config.secret, signature) {
-            use sha2::{Sha256, Digest};
-            let mut hasher = Sha256::new();
-            hasher.update(format!("{secret}{payload}"));
-            let expected = format!("{:x}", hasher.finalize());
-            if expected != sig {
-                return Err(BizClawError::AuthFailed("Invalid webhook signature".into()));
+        parse_payload(&self.config, payload.as_bytes(), signature)
+    }
+
+    /// The JSON posted for `message`: `outbound_template` filled in, or
+    /// `{"thread_id", "content", "reply_to"}`, then put through
+    /// `output_transform`.
+    pub fn outbound_payload(&self, message: &OutgoingMessage) -> Result<serde_json::Value> {
+        let vars = [
+            ("content", Some(message.content.as_str())),
+            ("thread_id", Some(message.thread_id.as_str())),
+            ("reply_to", message.reply_to.as_deref()),
+        ];
+        let payload = match &self.config.outbound_template {
+            Some(template) => render_template(template, &vars),
+            None => serde_json::json!({
+                "thread_id": message.thread_id,
+                "content": message.content,
+                "reply_to": message.reply_to,
+            }),
+        };
+        match &self.config.output_transform {
+            Some(expr) => apply_transform(expr, &payload),
+            None => Ok(payload),
+        }
+    }
+
+    /// Serve inbound webhooks on `config.listen`; returns the stream of
+    /// messages. Can be started once.
+    pub async fn start_inbound(&mut self) -> Result<WebhookInboundStream> {
+        let rx = self.take_receiver()
+            .ok_or_else(|| BizClawError::Channel("Webhook inbound already started".into()))?;
+        let listener = tokio::net::TcpListener::bind(&self.config.listen).await
+            .map_err(|e| BizClawError::Channel(format!("Webhook: cannot listen on {}: {e}", self.config.listen)))?;
+        tracing::info!("Webhook channel listening on {}{}", self.config.listen, self.config.path);
+        let router = self.inbound_router();
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                tracing::error!("Webhook server stopped: {e}");
             }
+        });
+        Ok(WebhookInboundStream { rx })
+    }
+
+    /// Routes receiving inbound payloads at `config.path` (401 for a missing
+    /// or wrong signature, 400 for a payload without content) and
+    /// conversations at `/webhook/in` and `/webhook/in/<name>`.
+    pub fn inbound_router(&self) -> axum::Router {
+        let state = Arc::new(InboundState {
+            config: self.config.clone(),
+            tx: self.inbound_tx.clone(),
+            conversations: self.conversations.clone(),
+        });
+        axum::Router::new()
+            .route(&self.config.path, axum::routing::post(receive_inbound))
+            .route("/webhook/in", axum::routing::post(
+                |state, headers, body| receive_conversation(state, axum::extract::Path("default".to_string()), headers, body),
+            ))
+            .route("/webhook/in/{name}", axum::routing::post(receive_conversation))
+            .with_state(state)
+    }
+
+    /// Answer a `/webhook/in` conversation: hand the text to the request
+    /// waiting for it, or POST it to the endpoint's callback.
+    async fn reply_to_conversation(&self, reply: PendingReply, content: String) -> Result<()> {
+        let (endpoint, conversation_id, metadata) = match reply {
+            PendingReply::Waiting(tx) => {
+                if tx.send(content).is_err() {
+                    tracing::warn!("Webhook: reply came after its request timed out; dropped");
+                }
+                return Ok(());
+            }
+            PendingReply::Callback { endpoint, conversation_id, metadata } => (endpoint, conversation_id, metadata),
+        };
+        let Some((secret, Some(url))) = self.config.endpoint(&endpoint) else {
+            tracing::warn!("Webhook: reply to {endpoint}:{conversation_id} has no request or callback; dropped");
+            return Ok(());
+        };
+        let payload = serde_json::json!({
+            "conversation_id": conversation_id,
+            "text": content,
+            "metadata": metadata,
+        });
+        let delivery = WebhookDelivery::new(None, url, payload)
+            .timestamped(&self.config.signature_header, &self.config.timestamp_header, secret);
+        let queue = self.callbacks.get_or_init(|| {
+            WebhookDeliveryQueue::with_log(None, self.config.callback_delays(), self.log.clone())
+        });
+        queue.enqueue(delivery).await
+    }
+}
+
+/// A `/webhook/in` request body.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConversationRequest {
+    pub conversation_id: String,
+    #[serde(default)]
+    pub user: Option<ConversationUser>,
+    pub text: String,
+    /// Returned as is with the reply.
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// Who wrote a `/webhook/in` message: an id, or `{id, name}`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum ConversationUser {
+    Id(String),
+    Profile {
+        id: String,
+        #[serde(default)]
+        name: Option<String>,
+    },
+}
+
+/// `/webhook/in` conversations with replies still to route, and the
+/// signatures recently accepted.
+#[derive(Default)]
+struct Conversations {
+    /// By thread id, `<endpoint>:<conversation_id>`.
+    open: Mutex<HashMap<String, Conversation>>,
+    /// Accepted signatures, with their timestamps.
+    seen: Mutex<HashMap<String, i64>>,
+}
+
+struct Conversation {
+    endpoint: String,
+    conversation_id: String,
+    metadata: serde_json::Value,
+    /// When the last message came in.
+    last_message_at: i64,
+    /// Requests waiting for their reply in the response, with their
+    /// message ids, oldest first; the agent answers a conversation's
+    /// messages in order.
+    waiting: VecDeque<(String, oneshot::Sender<String>)>,
+}
+
+/// Where the reply to a `/webhook/in` message goes.
+enum PendingReply {
+    Waiting(oneshot::Sender<String>),
+    Callback { endpoint: String, conversation_id: String, metadata: serde_json::Value },
+}
+
+impl Conversations {
+    /// Note the message `message_id` on `thread_id`, received at `now`;
+    /// returns the receiver of its reply when the endpoint has no callback.
+    /// Conversations idle for [`CONVERSATION_IDLE_SECS`] with no request
+    /// waiting are forgotten.
+    fn open(&self, thread_id: &str, message_id: &str, endpoint: &str, request: &ConversationRequest, callback: bool, now: i64) -> Option<oneshot::Receiver<String>> {
+        let mut open = self.open.lock().unwrap();
+        open.retain(|_, conversation| {
+            now - conversation.last_message_at <= CONVERSATION_IDLE_SECS
+                || conversation.waiting.iter().any(|(_, tx)| !tx.is_closed())
+        });
+        let conversation = open.entry(thread_id.to_string()).or_insert_with(|| Conversation {
+            endpoint: endpoint.to_string(),
+            conversation_id: request.conversation_id.clone(),
+            metadata: serde_json::Value::Null,
+            last_message_at: now,
+            waiting: VecDeque::new(),
+        });
+        conversation.metadata = request.metadata.clone();
+        conversation.last_message_at = now;
+        if callback {
+            return None;
         }
+        let (tx, rx) = oneshot::channel();
+        conversation.waiting.push_back((message_id.to_string(), tx));
+        Some(rx)
+    }
 
-        let json: serde_json::Value = serde_json::from_str(payload)
-            .map_err(|e| BizClawError::Channel(format!("Invalid webhook JSON: {e}")))?;
+    /// Stop waiting for the reply to `message_id`, which won't reach the
+    /// agent; its request is answered 409.
+    fn drop_waiter(&self, thread_id: &str, message_id: &str) {
+        if let Some(conversation) = self.open.lock().unwrap().get_mut(thread_id) {
+            conversation.waiting.retain(|(id, _)| id != message_id);
+        }
+    }
 
-        Ok(IncomingMessage {
-            channel: "webhook".into(),
-            thread_id: json["thread_id"].as_str().unwrap_or("webhook").into(),
-            sender_id: json["sender_id"].as_str().unwrap_or("external").into(),
-            sender_name: json["sender_name"].as_str().map(String::from),
-            content: json["content"].as_str().unwrap_or("").into(),
-            thread_type: ThreadType::Direct,
-            timestamp: chrono::Utc::now(),
-            reply_to: None,
+    /// Where the next reply on `thread_id` goes; `None` for threads that
+    /// didn't come through `/webhook/in`.
+    fn take_reply(&self, thread_id: &str) -> Option<PendingReply> {
+        let mut open = self.open.lock().unwrap();
+        let conversation = open.get_mut(thread_id)?;
+        // Requests that timed out no longer take a reply.
+        while conversation.waiting.front().is_some_and(|(_, tx)| tx.is_closed()) {
+            conversation.waiting.pop_front();
+        }
+        Some(match conversation.waiting.pop_front() {
+            Some((_, tx)) => PendingReply::Waiting(tx),
+            None => PendingReply::Callback {
+                endpoint: conversation.endpoint.clone(),
+                conversation_id: conversation.conversation_id.clone(),
+                metadata: conversation.metadata.clone(),
+            },
         })
     }
+
+    /// Remember `signature`, signed at `timestamp`; false if it was already
+    /// used. Signatures older than `max_age` are forgotten, since their
+    /// timestamp alone gets them refused.
+    fn first_use(&self, signature: &str, timestamp: i64, now: i64, max_age: i64) -> bool {
+        let mut seen = self.seen.lock().unwrap();
+        seen.retain(|_, at| now - *at <= max_age);
+        seen.insert(signature_key(signature), timestamp).is_none()
+    }
+}
+
+/// `signature` as lowercase hex, without its `sha256=` prefix.
+fn signature_key(signature: &str) -> String {
+    signature.trim().trim_start_matches("sha256=").to_ascii_lowercase()
+}
+
+/// Verify `payload` against `config.secret`, put it through
+/// `input_transform` and map it with `config.fields`; the content is at
+/// `content_field` when transformed, or is the result when that is a string.
+fn parse_payload(config: &WebhookConfig, payload: &[u8], signature: Option<&str>) -> Result<IncomingMessage> {
+    if let Some(secret) = &config.secret
+        && !signature.is_some_and(|sig| verify_signature(secret, payload, sig))
+    {
+        return Err(BizClawError::AuthFailed("Invalid webhook signature".into()));
+    }
+
+    let mut json: serde_json::Value = serde_json::from_slice(payload)
+        .map_err(|e| BizClawError::Channel(format!("Invalid webhook JSON: {e}")))?;
+    let fields = &config.fields;
+    let mut content_field = fields.content.as_str();
+    if let Some(expr) = &config.input_transform {
+        json = apply_transform(expr, &json)?;
+        content_field = &config.content_field;
+    }
+    let content = match &json {
+        serde_json::Value::String(text) => Some(text.clone()),
+        json => lookup(json, content_field),
+    };
+    let content = content
+        .ok_or_else(|| BizClawError::Channel(format!("Webhook payload has no '{content_field}'")))?;
+    let sender_id = lookup(&json, &fields.sender_id).unwrap_or_else(|| "external".into());
+
+    Ok(IncomingMessage {
+        channel: "webhook".into(),
+        thread_id: lookup(&json, &fields.thread_id).unwrap_or_else(|| sender_id.clone()),
+        sender_id,
+        sender_name: lookup(&json, &fields.sender_name),
+        content,
+        thread_type: ThreadType::Direct,
+        timestamp: chrono::Utc::now(),
+        reply_to: None,
+        attachments: Vec::new(),
+        message_id: lookup(&json, &fields.message_id),
+    })
+}
+
+/// The string, number or boolean at a dotted `path`; numeric segments index
+/// arrays.
+fn lookup(json: &serde_json::Value, path: &str) -> Option<String> {
+    let value = path.split('.').try_fold(json, |value, key| match value {
+        serde_json::Value::Array(items) => items.get(key.parse::<usize>().ok()?),
+        _ => value.get(key),
+    })?;
+    match value {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// Evaluate the JMESPath expression `expr` against `value`.
+pub fn apply_transform(expr: &str, value: &serde_json::Value) -> Result<serde_json::Value> {
+    let expr = jmespath::compile(expr)
+        .map_err(|e| BizClawError::Channel(format!("Invalid JMESPath expression: {e}")))?;
+    let result = expr.search(value)
+        .map_err(|e| BizClawError::Channel(format!("JMESPath transform failed: {e}")))?;
+    serde_json::to_value(&*result)
+        .map_err(|e| BizClawError::Channel(format!("JMESPath transform failed: {e}")))
+}
+
+/// Replace `{{name}}` in the strings of `template`. A string that is only a
+/// placeholder whose value is `None` becomes `null`.
+pub fn render_template(template: &serde_json::Value, vars: &[(&str, Option<&str>)]) -> serde_json::Value {
+    use serde_json::Value;
+    match template {
+        Value::String(s) => {
+            if let Some((_, None)) = vars.iter().find(|(name, _)| *s == format!("{{{{{name}}}}}")) {
+                return Value::Null;
+            }
+            let mut out = s.clone();
+            for (name, value) in vars {
+                out = out.replace(&format!("{{{{{name}}}}}"), value.unwrap_or(""));
+            }
+            Value::String(out)
+        }
+        Value::Array(items) => Value::Array(items.iter().map(|item| render_template(item, vars)).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter().map(|(key, value)| (key.clone(), render_template(value, vars))).collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+struct InboundState {
+    config: WebhookConfig,
+    tx: mpsc::UnboundedSender<IncomingMessage>,
+    conversations: Arc<Conversations>,
+}
+
+async fn receive_inbound(
+    axum::extract::State(state): axum::extract::State<Arc<InboundState>>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> axum::http::StatusCode {
+    use axum::http::StatusCode;
+
+    let signature = headers.get(state.config.signature_header.as_str()).and_then(|v| v.to_str().ok());
+    match parse_payload(&state.config, &body, signature) {
+        Ok(msg) => {
+            let _ = state.tx.send(msg);
+            StatusCode::OK
+        }
+        Err(BizClawError::AuthFailed(_)) => {
+            tracing::warn!(target: "bizclaw::audit", event = "webhook_inbound_rejected", "Webhook request with a missing or wrong signature");
+            StatusCode::UNAUTHORIZED
+        }
+        Err(e) => {
+            tracing::warn!("Webhook: {e}");
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
+/// `POST /webhook/in/<name>`: 404 for an unknown endpoint, 401 for a bad
+/// signature or a timestamp outside `max_age_secs`, 409 for a signature
+/// already used, 400 for a bad body, 409 when the message is dropped as a
+/// repeat. Then 202 when the reply goes to the
+/// callback, or 200 with `{conversation_id, text, metadata}` once the agent
+/// replies (504 after `reply_timeout_secs`).
+async fn receive_conversation(
+    axum::extract::State(state): axum::extract::State<Arc<InboundState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> (axum::http::StatusCode, axum::Json<serde_json::Value>) {
+    use axum::http::StatusCode;
+    let error = |status: StatusCode, message: &str| (status, axum::Json(serde_json::json!({ "error": message })));
+    let config = &state.config;
+
+    let Some((secret, callback)) = config.endpoint(&name) else {
+        return error(StatusCode::NOT_FOUND, "Unknown webhook endpoint");
+    };
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    let now = chrono::Utc::now().timestamp();
+    let max_age = config.max_age_secs as i64;
+    let timestamp = header(&config.timestamp_header).and_then(|t| t.trim().parse::<i64>().ok());
+    let Some(timestamp) = timestamp.filter(|t| (now - t).abs() <= max_age) else {
+        tracing::warn!(target: "bizclaw::audit", event = "webhook_inbound_rejected", endpoint = %name, "Webhook request with a missing or stale timestamp");
+        return error(StatusCode::UNAUTHORIZED, "Missing or stale timestamp");
+    };
+    let signature = header(&config.signature_header).unwrap_or("");
+    if !check_signature(&sign_timestamped(secret, timestamp, &body), signature) {
+        tracing::warn!(target: "bizclaw::audit", event = "webhook_inbound_rejected", endpoint = %name, "Webhook request with a missing or wrong signature");
+        return error(StatusCode::UNAUTHORIZED, "Invalid signature");
+    }
+    if !state.conversations.first_use(signature, timestamp, now, max_age) {
+        tracing::warn!(target: "bizclaw::audit", event = "webhook_inbound_replayed", endpoint = %name, "Webhook request replayed");
+        return error(StatusCode::CONFLICT, "Request already received");
+    }
+    let request: ConversationRequest = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => return error(StatusCode::BAD_REQUEST, &format!("Invalid body: {e}")),
+    };
+    if request.text.trim().is_empty() || request.conversation_id.is_empty() {
+        return error(StatusCode::BAD_REQUEST, "conversation_id and text are required");
+    }
+
+    let (sender_id, sender_name) = match &request.user {
+        Some(ConversationUser::Id(id)) => (id.clone(), None),
+        Some(ConversationUser::Profile { id, name }) => (id.clone(), name.clone()),
+        None => ("external".to_string(), None),
+    };
+    let thread_id = format!("{name}:{}", request.conversation_id);
+    // Each accepted request is signed differently, so the signature tells
+    // apart the same words sent twice.
+    let message_id = format!("{name}:{}", signature_key(signature));
+    let reply = state.conversations.open(&thread_id, &message_id, &name, &request, callback.is_some(), now);
+    let msg = IncomingMessage {
+        channel: "webhook".into(),
+        thread_id,
+        sender_id,
+        sender_name,
+        content: request.text.clone(),
+        thread_type: ThreadType::Direct,
+        timestamp: chrono::Utc::now(),
+        reply_to: None,
+        attachments: Vec::new(),
+        message_id: Some(message_id),
+    };
+    if state.tx.send(msg).is_err() {
+        return error(StatusCode::SERVICE_UNAVAILABLE, "Webhook channel stopped");
+    }
+
+    let Some(reply) = reply else {
+        return (StatusCode::ACCEPTED, axum::Json(serde_json::json!({
+            "conversation_id": request.conversation_id,
+            "status": "accepted",
+        })));
+    };
+    match tokio::time::timeout(Duration::from_secs(config.reply_timeout_secs), reply).await {
+        Ok(Ok(text)) => (StatusCode::OK, axum::Json(serde_json::json!({
+            "conversation_id": request.conversation_id,
+            "text": text,
+            "metadata": request.metadata,
+        }))),
+        Ok(Err(_)) => error(StatusCode::CONFLICT, "Request already received"),
+        Err(_) => error(StatusCode::GATEWAY_TIMEOUT, "No reply in time"),
+    }
+}
+
+/// Stream of messages received by the inbound webhook.
+pub struct WebhookInboundStream {
+    rx: mpsc::UnboundedReceiver<IncomingMessage>,
+}
+
+impl Stream for WebhookInboundStream {
+    type Item = IncomingMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
 }
 
 #[async_trait]
@@ -99,19 +1023,22 @@ impl Channel for WebhookChannel {
 
     fn is_connected(&self) -> bool { self.connected }
 
+    /// Queue the message for delivery; failures are retried in the background.
+    /// Replies to `/webhook/in` conversations go back to their request or
+    /// callback instead.
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
+        if let Some(reply) = self.conversations.take_reply(&message.thread_id) {
+            return self.reply_to_conversation(reply, message.content).await;
+        }
         if let Some(url) = &self.config.outbound_url {
-            let body = serde_json::json!({
-                "thread_id": message.thread_id,
-                "content": message.content,
-                "reply_to": message.reply_to,
+            let mut delivery = WebhookDelivery::new(None, url, self.outbound_payload(&message)?);
+            if let Some(secret) = &self.config.secret {
+                delivery = delivery.signed(&self.config.signature_header, secret);
+            }
+            let queue = self.deliveries.get_or_init(|| {
+                WebhookDeliveryQueue::with_log(None, RETRY_DELAYS.to_vec(), self.log.clone())
             });
-
-            self.client.post(url)
-                .json(&body)
-                .send()
-                .await
-                .map_err(|e| BizClawError::Channel(format!("Webhook send failed: {e}")))?;
+            queue.enqueue(delivery).await?;
         }
         Ok(())
     }
@@ -119,6 +1046,22 @@ impl Channel for WebhookChannel {
     async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
         Ok(Box::new(stream::pending()))
     }
+
+    /// Answer the request of a dropped `/webhook/in` message.
+    fn on_dropped(&self) -> Option<DroppedMessage> {
+        let conversations = self.conversations.clone();
+        Some(Arc::new(move |message: &IncomingMessage| {
+            if let Some(message_id) = &message.message_id {
+                conversations.drop_waiter(&message.thread_id, message_id);
+            }
+        }))
+    }
+
+    async fn start(&mut self, agent_tx: mpsc::UnboundedSender<IncomingMessage>) -> Result<()> {
+        let messages = self.start_inbound().await?;
+        bizclaw_core::traits::channel::forward(messages, agent_tx);
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -131,6 +1074,7 @@ mod tests {
             outbound_url: None,
             secret: None,
             enabled: true,
+            ..Default::default()
         });
 
         let payload = r#"{"content":"hello","sender_id":"user1","thread_id":"t1"}"#;
@@ -139,4 +1083,407 @@ mod tests {
         assert_eq!(msg.sender_id, "user1");
         assert_eq!(msg.channel, "webhook");
     }
+
+    #[derive(Default)]
+    struct MemoryDeadLetters(std::sync::Mutex<Vec<FailedDelivery>>);
+
+    impl DeadLetterStore for MemoryDeadLetters {
+        fn store(&self, failed: &FailedDelivery) -> Result<()> {
+            self.0.lock().unwrap().push(failed.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_inbound_signature_and_field_map() {
+        let mut channel = WebhookChannel::new(WebhookConfig {
+            secret: Some("crm-secret".into()),
+            path: "/crm".into(),
+            fields: WebhookFieldMap {
+                content: "data.message.text".into(),
+                sender_id: "data.customer.id".into(),
+                sender_name: "data.customer.name".into(),
+                thread_id: "data.ticket".into(),
+                message_id: "data.event_id".into(),
+            },
+            ..Default::default()
+        });
+        let mut rx = channel.take_receiver().unwrap();
+        let router = channel.inbound_router();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/crm", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let post = |payload: serde_json::Value, signature: Option<String>| {
+            let body = payload.to_string();
+            let mut req = client.post(&url).body(body.clone());
+            if let Some(signature) = signature {
+                req = req.header("X-BizClaw-Signature", signature);
+            }
+            async move { req.send().await.unwrap().status().as_u16() }
+        };
+        let payload = serde_json::json!({
+            "data": { "customer": { "id": 1042, "name": "Chị Lan" }, "message": { "text": "Đơn #88 giao chưa?" } }
+        });
+        let signature = sign("crm-secret", payload.to_string().as_bytes());
+
+        assert_eq!(post(payload.clone(), None).await, 401);
+        assert_eq!(post(payload.clone(), Some(sign("other", payload.to_string().as_bytes()))).await, 401);
+        assert_eq!(post(payload.clone(), Some(signature.clone())).await, 200);
+        // Bare hex is accepted too.
+        assert_eq!(post(payload.clone(), Some(signature.trim_start_matches("sha256=").to_uppercase())).await, 200);
+        let no_content = serde_json::json!({ "data": { "customer": { "id": 1 } } });
+        assert_eq!(post(no_content.clone(), Some(sign("crm-secret", no_content.to_string().as_bytes()))).await, 400);
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.content, "Đơn #88 giao chưa?");
+        assert_eq!((msg.sender_id.as_str(), msg.sender_name.as_deref()), ("1042", Some("Chị Lan")));
+        // No ticket in the payload: the conversation follows the sender.
+        assert_eq!(msg.thread_id, "1042");
+        assert!(rx.recv().await.is_some());
+        assert!(rx.try_recv().is_err());
+    }
+
+    /// POST `body` to `url`, signed for `/webhook/in` at `timestamp`.
+    async fn post_conversation(url: &str, secret: &str, timestamp: i64, body: &serde_json::Value) -> (u16, serde_json::Value) {
+        let body = body.to_string();
+        let resp = reqwest::Client::new().post(url)
+            .header("X-BizClaw-Timestamp", timestamp.to_string())
+            .header("X-BizClaw-Signature", sign_timestamped(secret, timestamp, body.as_bytes()))
+            .body(body)
+            .send().await.unwrap();
+        (resp.status().as_u16(), resp.json().await.unwrap_or_default())
+    }
+
+    async fn serve(channel: &WebhookChannel) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let router = channel.inbound_router();
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        base
+    }
+
+    fn reply(thread_id: &str, content: &str) -> OutgoingMessage {
+        OutgoingMessage { thread_id: thread_id.into(), content: content.into(), thread_type: ThreadType::Direct, reply_to: None }
+    }
+
+    #[tokio::test]
+    async fn test_conversation_sync_reply_and_replay() {
+        let mut channel = WebhookChannel::new(WebhookConfig { secret: Some("s3cret".into()), ..Default::default() });
+        let mut rx = channel.take_receiver().unwrap();
+        let channel = Arc::new(channel);
+        let url = format!("{}/webhook/in", serve(&channel).await);
+        let now = chrono::Utc::now().timestamp();
+        let body = serde_json::json!({
+            "conversation_id": "c-7", "user": { "id": "u1", "name": "Lan" }, "text": "ping", "metadata": { "ticket": 9 },
+        });
+
+        let agent = channel.clone();
+        tokio::spawn(async move {
+            let msg = rx.recv().await.unwrap();
+            assert_eq!((msg.thread_id.as_str(), msg.sender_id.as_str(), msg.content.as_str()), ("default:c-7", "u1", "ping"));
+            assert_eq!(msg.sender_name.as_deref(), Some("Lan"));
+            agent.send(reply(&msg.thread_id, "pong")).await.unwrap();
+        });
+        let (status, json) = post_conversation(&url, "s3cret", now, &body).await;
+        assert_eq!(status, 200, "{json}");
+        assert_eq!(json, serde_json::json!({ "conversation_id": "c-7", "text": "pong", "metadata": { "ticket": 9 } }));
+
+        // The same signed request again is a replay.
+        assert_eq!(post_conversation(&url, "s3cret", now, &body).await.0, 409);
+        assert_eq!(post_conversation(&url, "s3cret", now - 301, &body).await.0, 401);
+        assert_eq!(post_conversation(&url, "other", now, &body).await.0, 401);
+        assert_eq!(post_conversation(&format!("{url}/nope"), "s3cret", now, &body).await.0, 404);
+        let no_text = serde_json::json!({ "conversation_id": "c-7", "user": "u1", "text": " " });
+        assert_eq!(post_conversation(&url, "s3cret", now, &no_text).await.0, 400);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_same_text_twice_through_dedup() {
+        use crate::dedup::{DedupChannel, InboundDedup};
+
+        let channel = WebhookChannel::new(WebhookConfig {
+            secret: Some("s3cret".into()), listen: "127.0.0.1:0".into(), ..Default::default()
+        });
+        let url = format!("{}/webhook/in", serve(&channel).await);
+        let dedup = Arc::new(InboundDedup::open(Path::new(":memory:"), Duration::from_secs(3600)).unwrap());
+        let mut channel = DedupChannel::new(channel, dedup);
+        let (agent_tx, mut rx) = mpsc::unbounded_channel();
+        channel.start(agent_tx).await.unwrap();
+        let channel = Arc::new(channel);
+
+        let agent = channel.clone();
+        tokio::spawn(async move {
+            let mut n = 0;
+            while let Some(msg) = rx.recv().await {
+                n += 1;
+                agent.send(reply(&msg.thread_id, &format!("{} #{n}", msg.content))).await.unwrap();
+            }
+        });
+        let body = serde_json::json!({ "conversation_id": "c-2", "user": "u1", "text": "yes" });
+        let now = chrono::Utc::now().timestamp();
+        let (status, first) = post_conversation(&url, "s3cret", now, &body).await;
+        assert_eq!((status, first["text"].as_str()), (200, Some("yes #1")));
+        let (status, second) = post_conversation(&url, "s3cret", now + 1, &body).await;
+        assert_eq!((status, second["text"].as_str()), (200, Some("yes #2")));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_or_timed_out_waiter_skipped() {
+        let channel = WebhookChannel::new(WebhookConfig::default());
+        let request: ConversationRequest = serde_json::from_value(
+            serde_json::json!({ "conversation_id": "c-3", "text": "hi" }),
+        ).unwrap();
+        let conversations = &channel.conversations;
+        let timed_out = conversations.open("default:c-3", "m1", "default", &request, false, 0).unwrap();
+        let dropped = conversations.open("default:c-3", "m2", "default", &request, false, 0).unwrap();
+        let waiting = conversations.open("default:c-3", "m3", "default", &request, false, 0).unwrap();
+        drop(timed_out);
+
+        let message = IncomingMessage {
+            channel: "webhook".into(), thread_id: "default:c-3".into(), sender_id: "external".into(),
+            sender_name: None, content: "hi".into(), thread_type: ThreadType::Direct,
+            timestamp: chrono::Utc::now(), reply_to: None, attachments: Vec::new(), message_id: Some("m2".into()),
+        };
+        channel.on_dropped().unwrap()(&message);
+        assert!(dropped.await.is_err());
+
+        channel.send(reply("default:c-3", "hello")).await.unwrap();
+        assert_eq!(waiting.await.unwrap(), "hello");
+    }
+
+    #[test]
+    fn test_idle_conversations_forgotten() {
+        let conversations = Conversations::default();
+        let request = |id: &str| -> ConversationRequest {
+            serde_json::from_value(serde_json::json!({ "conversation_id": id, "text": "hi" })).unwrap()
+        };
+        assert!(conversations.open("crm:idle", "m1", "crm", &request("idle"), true, 0).is_none());
+        let _waiting = conversations.open("default:slow", "m2", "default", &request("slow"), false, 0).unwrap();
+        let gone = conversations.open("default:gone", "m3", "default", &request("gone"), false, 0).unwrap();
+        drop(gone);
+        conversations.open("crm:recent", "m4", "crm", &request("recent"), true, 100);
+
+        conversations.open("crm:new", "m5", "crm", &request("new"), true, CONVERSATION_IDLE_SECS + 50);
+        let mut left: Vec<String> = conversations.open.lock().unwrap().keys().cloned().collect();
+        left.sort();
+        // A request still waiting keeps its conversation.
+        assert_eq!(left, ["crm:new", "crm:recent", "default:slow"]);
+    }
+
+    #[tokio::test]
+    async fn test_conversation_callback_retried_and_logged() {
+        let (callback, requests) = webhook_server(vec![500, 200]).await;
+        let log = Arc::new(WebhookDeliveryLog::open(Path::new(":memory:")).unwrap());
+        let mut channel = WebhookChannel::new(WebhookConfig {
+            callback_backoff_ms: 10,
+            endpoints: vec![WebhookEndpointConfig { name: "crm".into(), secret: "crm-secret".into(), callback_url: Some(callback.clone()) }],
+            ..Default::default()
+        }).with_delivery_log(log.clone());
+        let mut rx = channel.take_receiver().unwrap();
+        let base = serve(&channel).await;
+        let body = serde_json::json!({ "conversation_id": "c-1", "user": "u1", "text": "report", "metadata": "m" });
+
+        // Without a top-level secret there is no default endpoint.
+        assert_eq!(post_conversation(&format!("{base}/webhook/in"), "crm-secret", chrono::Utc::now().timestamp(), &body).await.0, 404);
+        let (status, json) = post_conversation(&format!("{base}/webhook/in/crm"), "crm-secret", chrono::Utc::now().timestamp(), &body).await;
+        assert_eq!((status, json["status"].as_str()), (202, Some("accepted")));
+
+        let msg = rx.recv().await.unwrap();
+        assert_eq!(msg.thread_id, "crm:c-1");
+        channel.send(reply(&msg.thread_id, "done")).await.unwrap();
+        wait_for(|| log.recent(10).unwrap().len() == 2).await;
+
+        let request = requests.lock().unwrap()[1].clone();
+        assert_eq!(attempt_number(&request), "2");
+        let (head, payload) = request.split_once("\r\n\r\n").unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(payload).unwrap(), serde_json::json!({
+            "conversation_id": "c-1", "text": "done", "metadata": "m",
+        }));
+        let header = |name: &str| head.lines().find_map(|l| l.strip_prefix(name)).unwrap().to_string();
+        let timestamp: i64 = header("x-bizclaw-timestamp: ").parse().unwrap();
+        assert!(check_signature(&sign_timestamped("crm-secret", timestamp, payload.as_bytes()), &header("x-bizclaw-signature: ")));
+
+        let attempts = log.recent(10).unwrap();
+        assert_eq!(attempts.iter().map(|a| (a.attempt, a.outcome)).collect::<Vec<_>>(),
+            vec![(2, DeliveryOutcome::Delivered), (1, DeliveryOutcome::Retrying)]);
+        assert_eq!(attempts[1].error.as_deref(), Some("HTTP 500 Internal Server Error"));
+        assert_eq!(attempts[0].url, callback);
+    }
+
+    #[test]
+    fn test_render_template() {
+        let template = serde_json::json!({
+            "ticket": "{{thread_id}}",
+            "comment": { "body": "BizClaw: {{content}}", "public": true },
+            "in_reply_to": "{{reply_to}}",
+            "tags": ["bot", "{{reply_to}}x"],
+        });
+        let vars = [("content", Some("Đã giao")), ("thread_id", Some("T-9")), ("reply_to", None)];
+        assert_eq!(render_template(&template, &vars), serde_json::json!({
+            "ticket": "T-9",
+            "comment": { "body": "BizClaw: Đã giao", "public": true },
+            "in_reply_to": null,
+            "tags": ["bot", "x"],
+        }));
+        assert_eq!(lookup(&serde_json::json!({ "items": [{ "n": 2.5 }] }), "items.0.n").as_deref(), Some("2.5"));
+    }
+
+    #[tokio::test]
+    async fn test_send_signed_templated_reply() {
+        let (url, requests) = webhook_server(vec![200]).await;
+        let channel = WebhookChannel::new(WebhookConfig {
+            outbound_url: Some(url),
+            secret: Some("crm-secret".into()),
+            outbound_template: Some(serde_json::json!({ "ticket": "{{thread_id}}", "note": "{{content}}" })),
+            ..Default::default()
+        });
+        channel.send(OutgoingMessage {
+            thread_id: "T-9".into(),
+            content: "Đơn #88 đang giao".into(),
+            thread_type: ThreadType::Direct,
+            reply_to: None,
+        }).await.unwrap();
+
+        wait_for(|| !requests.lock().unwrap().is_empty()).await;
+        let request = requests.lock().unwrap()[0].clone();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert_eq!(serde_json::from_str::<serde_json::Value>(body).unwrap(), serde_json::json!({
+            "ticket": "T-9", "note": "Đơn #88 đang giao",
+        }));
+        let signature = head.lines()
+            .find_map(|l| l.strip_prefix("x-bizclaw-signature: "))
+            .unwrap();
+        assert!(verify_signature("crm-secret", body.as_bytes(), signature));
+    }
+
+    #[tokio::test]
+    async fn test_transforms() {
+        let (url, requests) = webhook_server(vec![200]).await;
+        let channel = WebhookChannel::new(WebhookConfig {
+            outbound_url: Some(url),
+            input_transform: Some("{message: events[0].text, sender_id: events[0].from.id}".into()),
+            output_transform: Some("{reply: {to: thread_id, text: content}}".into()),
+            ..Default::default()
+        });
+        let payload = r#"{"events": [{"text": "Còn size M không?", "from": {"id": "c-7"}}]}"#;
+        let msg = channel.parse_inbound(payload, None).unwrap();
+        assert_eq!((msg.content.as_str(), msg.sender_id.as_str(), msg.thread_id.as_str()), ("Còn size M không?", "c-7", "c-7"));
+        assert!(channel.parse_inbound(r#"{"events": []}"#, None).unwrap_err().to_string().contains("'message'"));
+
+        let plain = WebhookChannel::new(WebhookConfig { input_transform: Some("data.body".into()), ..Default::default() });
+        assert_eq!(plain.parse_inbound(r#"{"data": {"body": "Hi"}}"#, None).unwrap().content, "Hi");
+
+        channel.send(OutgoingMessage {
+            thread_id: "c-7".into(),
+            content: "Còn ạ".into(),
+            thread_type: ThreadType::Direct,
+            reply_to: None,
+        }).await.unwrap();
+        wait_for(|| !requests.lock().unwrap().is_empty()).await;
+        let request = requests.lock().unwrap()[0].clone();
+        let body = request.split_once("\r\n\r\n").unwrap().1;
+        assert_eq!(serde_json::from_str::<serde_json::Value>(body).unwrap(), serde_json::json!({
+            "reply": { "to": "c-7", "text": "Còn ạ" },
+        }));
+        assert!(apply_transform("{a: ", &serde_json::json!({})).is_err());
+    }
+
+    /// Answer each request with the next status from `statuses`, recording
+    /// the requests.
+    async fn webhook_server(statuses: Vec<u16>) -> (String, Arc<std::sync::Mutex<Vec<String>>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let attempts = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = attempts.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                        let head = String::from_utf8_lossy(&request[..end]).to_lowercase();
+                        let length: usize = head.lines()
+                            .find_map(|l| l.strip_prefix("content-length: ").and_then(|v| v.parse().ok()))
+                            .unwrap_or(0);
+                        if request.len() >= end + 4 + length {
+                            break;
+                        }
+                    }
+                    let n = stream.read(&mut buf).await.unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                // Header names lowercased, the body as sent.
+                let text = String::from_utf8_lossy(&request).into_owned();
+                let (head, body) = text.split_once("\r\n\r\n").unwrap_or((&text, ""));
+                seen.lock().unwrap().push(format!("{}\r\n\r\n{body}", head.to_lowercase()));
+                let response = format!("HTTP/1.1 {status} X\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (url, attempts)
+    }
+
+    fn attempt_number(request: &str) -> String {
+        request.lines().find_map(|l| l.strip_prefix("x-delivery-attempt: ")).unwrap_or("").to_string()
+    }
+
+    async fn wait_for(mut done: impl FnMut() -> bool) {
+        for _ in 0..200 {
+            if done() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("timed out");
+    }
+
+    #[tokio::test]
+    async fn test_delivery_retries_until_success() {
+        let (url, attempts) = webhook_server(vec![500, 503, 200]).await;
+        let dead = Arc::new(MemoryDeadLetters::default());
+        let queue = WebhookDeliveryQueue::with_delays(Some(dead.clone()), vec![Duration::from_millis(10); 5]);
+        queue.enqueue(WebhookDelivery::new(None, &url, serde_json::json!({"content": "hi"}))).await.unwrap();
+
+        wait_for(|| attempts.lock().unwrap().len() == 3).await;
+        let numbers: Vec<_> = attempts.lock().unwrap().iter().map(|r| attempt_number(r)).collect();
+        assert_eq!(numbers, vec!["1", "2", "3"]);
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(dead.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_delivery_dead_letters_after_last_retry() {
+        let (url, attempts) = webhook_server(vec![500; 3]).await;
+        let dead = Arc::new(MemoryDeadLetters::default());
+        let queue = WebhookDeliveryQueue::with_delays(Some(dead.clone()), vec![Duration::from_millis(10); 2]);
+        let delivery = WebhookDelivery::new(Some("t1".into()), &url, serde_json::json!({"content": "hi"}));
+        queue.enqueue(delivery.clone()).await.unwrap();
+
+        wait_for(|| !dead.0.lock().unwrap().is_empty()).await;
+        assert_eq!(attempts.lock().unwrap().len(), 3);
+        let failed = dead.0.lock().unwrap()[0].clone();
+        assert_eq!(failed.delivery, delivery);
+        assert_eq!(failed.attempts, 3);
+        assert!(failed.last_error.contains("500"), "{}", failed.last_error);
+    }
+
+    #[tokio::test]
+    async fn test_refused_delivery_not_retried() {
+        let (url, attempts) = webhook_server(vec![422, 200]).await;
+        let dead = Arc::new(MemoryDeadLetters::default());
+        let queue = WebhookDeliveryQueue::with_delays(Some(dead.clone()), vec![Duration::from_millis(10); 2]);
+        queue.enqueue(WebhookDelivery::new(None, &url, serde_json::json!({"content": "hi"}))).await.unwrap();
+
+        wait_for(|| !dead.0.lock().unwrap().is_empty()).await;
+        assert_eq!(attempts.lock().unwrap().len(), 1);
+        assert_eq!(dead.0.lock().unwrap()[0].attempts, 1);
+    }
 }
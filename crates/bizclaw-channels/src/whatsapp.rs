@@ -2,13 +2,32 @@
 //!
 //! Uses the official WhatsApp Business Platform (Cloud API) for messaging.
 //! Requires: Access Token + Phone Number ID from Meta Business Suite.
+//!
+//! Messages are sent through the Graph API and arrive on a webhook: Meta
+//! first checks the callback URL with a `hub.verify_token` challenge, then
+//! POSTs events signed with the app secret (`X-Hub-Signature-256`).
+//!
+//! WhatsApp only allows free-form messages within 24 hours of the customer's
+//! last message; outside that window a business has to send an approved
+//! template. A [`WhatsAppStore`] remembers when each number last wrote and
+//! what happened to each sent message (the webhook's `sent`/`delivered`/
+//! `read`/`failed` status callbacks).
 
 use async_trait::async_trait;
+use bizclaw_core::config::ChannelRateLimitConfig;
 use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::Channel;
-use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
+use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
 use futures::stream::Stream;
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+use crate::format::{Dialect, format_message};
+use crate::rate_limit::RateLimiter;
 
 /// WhatsApp Business channel configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,55 +39,313 @@ pub struct WhatsAppConfig {
     /// Webhook verify token (for incoming messages)
     #[serde(default)]
     pub webhook_verify_token: String,
+    /// App secret, for checking webhook signatures.
+    #[serde(default)]
+    pub app_secret: String,
     /// Business Account ID (optional)
     #[serde(default)]
     pub business_id: String,
+    /// Local address the webhook server listens on.
+    #[serde(default = "default_listen")]
+    pub listen: String,
+    /// Route the webhook is served at.
+    #[serde(default = "default_path")]
+    pub path: String,
+    /// Numbers the bot answers; empty means all.
+    #[serde(default)]
+    pub allowed_numbers: Vec<String>,
+    /// Graph API base (a test server in tests).
+    #[serde(default = "default_api_base")]
+    pub api_base: String,
+    /// Limits on sent messages.
+    #[serde(default)]
+    pub rate_limit: ChannelRateLimitConfig,
 }
 
+fn default_listen() -> String { "0.0.0.0:8444".into() }
+fn default_path() -> String { "/whatsapp/webhook".into() }
+fn default_api_base() -> String { "https://graph.facebook.com/v21.0".into() }
+
 impl Default for WhatsAppConfig {
     fn default() -> Self {
         Self {
             access_token: String::new(),
             phone_number_id: String::new(),
             webhook_verify_token: String::new(),
+            app_secret: String::new(),
             business_id: String::new(),
+            listen: default_listen(),
+            path: default_path(),
+            allowed_numbers: Vec::new(),
+            api_base: default_api_base(),
+            rate_limit: ChannelRateLimitConfig::default(),
         }
     }
 }
 
+impl From<&bizclaw_core::config::WhatsAppChannelConfig> for WhatsAppConfig {
+    fn from(cfg: &bizclaw_core::config::WhatsAppChannelConfig) -> Self {
+        Self {
+            access_token: cfg.access_token.clone(),
+            phone_number_id: cfg.phone_number_id.clone(),
+            webhook_verify_token: cfg.verify_token.clone(),
+            app_secret: cfg.app_secret.clone(),
+            listen: cfg.listen.clone(),
+            path: cfg.path.clone(),
+            allowed_numbers: cfg.allowed_numbers.clone(),
+            rate_limit: cfg.rate_limit.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+/// Longest text one WhatsApp message can hold.
+const MAX_MESSAGE_CHARS: usize = 4096;
+/// Message ids remembered to drop webhook redeliveries.
+const SEEN_MESSAGE_IDS: usize = 1024;
+
+/// How long after a customer's message free-form replies are allowed.
+pub const SESSION_WINDOW: chrono::TimeDelta = chrono::TimeDelta::hours(24);
+/// Sent messages kept in the delivery log.
+const LOG_ROWS: i64 = 1000;
+
+/// A sent message and the latest status WhatsApp reported for it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WhatsAppDelivery {
+    pub message_id: String,
+    pub to: String,
+    /// `text` or `template`.
+    pub kind: String,
+    /// `sent`, `delivered`, `read` or `failed`.
+    pub status: String,
+    /// Why the message failed, from the status callback.
+    pub error: Option<String>,
+    pub sent_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// A delivery status from a webhook event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusUpdate {
+    pub message_id: String,
+    pub recipient: String,
+    pub status: String,
+    pub error: Option<String>,
+    pub at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Order of delivery statuses; callbacks can arrive out of order, and a
+/// message never moves back (a late `delivered` doesn't undo `read`).
+fn status_rank(status: &str) -> u8 {
+    match status {
+        "sent" => 1,
+        "delivered" => 2,
+        "read" => 3,
+        "failed" => 4,
+        _ => 0,
+    }
+}
+
+/// Session windows and the delivery log, in SQLite so the gateway can send
+/// templates and show deliveries for the channel process.
+pub struct WhatsAppStore {
+    conn: Mutex<rusqlite::Connection>,
+}
+
+impl WhatsAppStore {
+    /// Open (or create) the store at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = rusqlite::Connection::open(path)
+            .map_err(|e| BizClawError::Channel(format!("WhatsApp store open error: {e}")))?;
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS whatsapp_sessions (
+                number TEXT PRIMARY KEY,
+                last_inbound INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS whatsapp_deliveries (
+                seq INTEGER PRIMARY KEY AUTOINCREMENT,
+                message_id TEXT NOT NULL UNIQUE,
+                recipient TEXT NOT NULL,
+                kind TEXT NOT NULL,
+                status TEXT NOT NULL,
+                error TEXT,
+                sent_at INTEGER NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+        ").map_err(|e| BizClawError::Channel(format!("WhatsApp store migration error: {e}")))?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Open `<data dir>/whatsapp.db`.
+    pub fn open_default() -> Result<Self> {
+        Self::open(&bizclaw_core::config::BizClawConfig::data_dir().join("whatsapp.db"))
+    }
+
+    fn conn(&self) -> std::sync::MutexGuard<'_, rusqlite::Connection> {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Note a message from `number`, opening (or extending) its session.
+    pub fn record_inbound(&self, number: &str, at: chrono::DateTime<chrono::Utc>) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO whatsapp_sessions (number, last_inbound) VALUES (?1, ?2)
+             ON CONFLICT(number) DO UPDATE SET last_inbound = MAX(last_inbound, excluded.last_inbound)",
+            rusqlite::params![number, at.timestamp()],
+        ).map_err(|e| BizClawError::Channel(format!("Record WhatsApp session: {e}")))?;
+        Ok(())
+    }
+
+    /// When `number` last wrote to us.
+    pub fn last_inbound(&self, number: &str) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+        use rusqlite::OptionalExtension;
+        let at: Option<i64> = self.conn().query_row(
+            "SELECT last_inbound FROM whatsapp_sessions WHERE number = ?1", [number], |row| row.get(0),
+        ).optional().map_err(|e| BizClawError::Channel(format!("Read WhatsApp session: {e}")))?;
+        Ok(at.and_then(|t| chrono::DateTime::from_timestamp(t, 0)))
+    }
+
+    /// Add a message the Graph API accepted, dropping the oldest beyond the last 1000.
+    pub fn record_sent(&self, message_id: &str, to: &str, kind: &str) -> Result<()> {
+        let conn = self.conn();
+        let now = chrono::Utc::now().timestamp_millis();
+        conn.execute(
+            "INSERT OR IGNORE INTO whatsapp_deliveries (message_id, recipient, kind, status, sent_at, updated_at)
+             VALUES (?1, ?2, ?3, 'sent', ?4, ?4)",
+            rusqlite::params![message_id, to, kind, now],
+        ).map_err(|e| BizClawError::Channel(format!("Record WhatsApp message: {e}")))?;
+        conn.execute(
+            "DELETE FROM whatsapp_deliveries WHERE seq <= (SELECT MAX(seq) FROM whatsapp_deliveries) - ?1",
+            [LOG_ROWS],
+        ).map_err(|e| BizClawError::Channel(format!("Trim WhatsApp log: {e}")))?;
+        Ok(())
+    }
+
+    /// Apply a status callback; false when the message isn't in the log or
+    /// already has a later status.
+    pub fn update_status(&self, update: &StatusUpdate) -> Result<bool> {
+        let conn = self.conn();
+        let current: Option<String> = {
+            use rusqlite::OptionalExtension;
+            conn.query_row(
+                "SELECT status FROM whatsapp_deliveries WHERE message_id = ?1", [&update.message_id], |row| row.get(0),
+            ).optional().map_err(|e| BizClawError::Channel(format!("Read WhatsApp message: {e}")))?
+        };
+        let Some(current) = current else { return Ok(false) };
+        if status_rank(&update.status) <= status_rank(&current) {
+            return Ok(false);
+        }
+        conn.execute(
+            "UPDATE whatsapp_deliveries SET status = ?2, error = ?3, updated_at = ?4 WHERE message_id = ?1",
+            rusqlite::params![update.message_id, update.status, update.error, update.at.timestamp_millis()],
+        ).map_err(|e| BizClawError::Channel(format!("Update WhatsApp message: {e}")))?;
+        Ok(true)
+    }
+
+    /// The last `limit` sent messages, newest first.
+    pub fn recent(&self, limit: usize) -> Result<Vec<WhatsAppDelivery>> {
+        let conn = self.conn();
+        let mut stmt = conn.prepare(
+            "SELECT message_id, recipient, kind, status, error, sent_at, updated_at FROM whatsapp_deliveries
+             ORDER BY seq DESC LIMIT ?1",
+        ).map_err(|e| BizClawError::Channel(format!("List WhatsApp messages: {e}")))?;
+        let millis = |t: i64| chrono::DateTime::from_timestamp_millis(t).unwrap_or_default();
+        let rows = stmt.query_map([limit as i64], |row| {
+            Ok(WhatsAppDelivery {
+                message_id: row.get(0)?,
+                to: row.get(1)?,
+                kind: row.get(2)?,
+                status: row.get(3)?,
+                error: row.get(4)?,
+                sent_at: millis(row.get(5)?),
+                updated_at: millis(row.get(6)?),
+            })
+        }).map_err(|e| BizClawError::Channel(format!("List WhatsApp messages: {e}")))?;
+        rows.collect::<std::result::Result<_, _>>()
+            .map_err(|e| BizClawError::Channel(format!("List WhatsApp messages: {e}")))
+    }
+}
+
 /// WhatsApp Business channel implementation.
+#[derive(Clone)]
 pub struct WhatsAppChannel {
     config: WhatsAppConfig,
     client: reqwest::Client,
     connected: bool,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    store: Arc<WhatsAppStore>,
 }
 
 impl WhatsAppChannel {
+    /// A channel with an in-memory store; see [`Self::with_store`].
     pub fn new(config: WhatsAppConfig) -> Self {
         Self {
+            rate_limiter: RateLimiter::new(&config.rate_limit).map(Arc::new),
             config,
             client: reqwest::Client::new(),
             connected: false,
+            store: Arc::new(WhatsAppStore::open(Path::new(":memory:")).expect("in-memory SQLite")),
+        }
+    }
+
+    /// Keep session windows and deliveries in `store`, e.g. one shared with the gateway.
+    pub fn with_store(mut self, store: Arc<WhatsAppStore>) -> Self {
+        self.store = store;
+        self
+    }
+
+    pub fn store(&self) -> &Arc<WhatsAppStore> {
+        &self.store
+    }
+
+    fn api_url(&self, path: &str) -> String {
+        format!("{}/{path}", self.config.api_base.trim_end_matches('/'))
+    }
+
+    /// Send a free-form text message. Refused outside the 24-hour session
+    /// window, where only templates can be sent.
+    pub async fn send_text(&self, to: &str, text: &str) -> Result<String> {
+        let last = self.store.last_inbound(to)?;
+        if last.is_none_or(|at| chrono::Utc::now() - at >= SESSION_WINDOW) {
+            let since = last.map_or("has never written".to_string(), |at| format!("last wrote at {}", at.to_rfc3339()));
+            return Err(BizClawError::Channel(format!(
+                "WhatsApp: {to} {since}, outside the 24-hour session window; send an approved template message instead"
+            )));
+        }
+        self.send_text_message(to, text).await
+    }
+
+    /// Send an approved template, which is allowed outside the session
+    /// window. `params` fill the template body's `{{1}}`, `{{2}}`, ….
+    pub async fn send_template(&self, to: &str, name: &str, language: &str, params: &[String]) -> Result<String> {
+        let mut template = serde_json::json!({ "name": name, "language": { "code": language } });
+        if !params.is_empty() {
+            let parameters: Vec<_> = params.iter().map(|p| serde_json::json!({ "type": "text", "text": p })).collect();
+            template["components"] = serde_json::json!([{ "type": "body", "parameters": parameters }]);
         }
+        self.post_message(to, "template", serde_json::json!({ "template": template })).await
     }
 
     /// Send a text message via WhatsApp Cloud API.
     async fn send_text_message(&self, to: &str, text: &str) -> Result<String> {
-        let url = format!(
-            "https://graph.facebook.com/v21.0/{}/messages",
-            self.config.phone_number_id
-        );
+        self.post_message(to, "text", serde_json::json!({ "text": { "preview_url": false, "body": text } })).await
+    }
 
-        let body = serde_json::json!({
+    /// POST a `kind` message to `to` and log it for status callbacks.
+    async fn post_message(&self, to: &str, kind: &str, payload: serde_json::Value) -> Result<String> {
+        let url = self.api_url(&format!("{}/messages", self.config.phone_number_id));
+
+        let mut body = serde_json::json!({
             "messaging_product": "whatsapp",
             "recipient_type": "individual",
             "to": to,
-            "type": "text",
-            "text": {
-                "preview_url": false,
-                "body": text
-            }
+            "type": kind,
         });
+        if let (Some(body), serde_json::Value::Object(payload)) = (body.as_object_mut(), payload) {
+            body.extend(payload);
+        }
 
         let response = self.client
             .post(&url)
@@ -94,17 +371,17 @@ impl WhatsAppChannel {
             .as_str()
             .unwrap_or("unknown")
             .to_string();
+        if let Err(e) = self.store.record_sent(&msg_id, to, kind) {
+            tracing::warn!("WhatsApp: cannot log message {msg_id}: {e}");
+        }
 
-        tracing::debug!("WhatsApp message sent: {} → {}", msg_id, to);
+        tracing::debug!("WhatsApp {kind} message sent: {} → {}", msg_id, to);
         Ok(msg_id)
     }
 
     /// Mark a message as read.
     pub async fn mark_as_read(&self, message_id: &str) -> Result<()> {
-        let url = format!(
-            "https://graph.facebook.com/v21.0/{}/messages",
-            self.config.phone_number_id
-        );
+        let url = self.api_url(&format!("{}/messages", self.config.phone_number_id));
 
         let body = serde_json::json!({
             "messaging_product": "whatsapp",
@@ -122,6 +399,263 @@ impl WhatsAppChannel {
 
         Ok(())
     }
+
+    /// Serve the webhook on `config.listen` — returns a stream of
+    /// IncomingMessages.
+    pub async fn start_webhook(self) -> Result<WhatsAppWebhookStream> {
+        let listener = tokio::net::TcpListener::bind(&self.config.listen).await
+            .map_err(|e| BizClawError::Channel(format!("WhatsApp webhook: cannot listen on {}: {e}", self.config.listen)))?;
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        let path = self.config.path.clone();
+        tracing::info!("WhatsApp webhook listening on {}{path}", self.config.listen);
+        let router = self.webhook_router(&path, tx);
+        tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, router).await {
+                tracing::error!("WhatsApp webhook server stopped: {e}");
+            }
+        });
+        Ok(WhatsAppWebhookStream { rx })
+    }
+
+    /// Routes for the webhook at `path`: GET answers Meta's verification
+    /// challenge, POST receives events. Events without a valid
+    /// `X-Hub-Signature-256` are refused with 401, and messages Meta
+    /// redelivers are dropped.
+    pub fn webhook_router(
+        self,
+        path: &str,
+        tx: tokio::sync::mpsc::UnboundedSender<IncomingMessage>,
+    ) -> axum::Router {
+        let state = Arc::new(WebhookState { channel: self, tx, seen: Mutex::new(VecDeque::new()) });
+        axum::Router::new()
+            .route(path, axum::routing::get(verify_webhook).post(receive_webhook_event))
+            .with_state(state)
+    }
+
+    /// Messages from a webhook event the bot should answer, with their
+    /// WhatsApp message ids. Each message opens its sender's session window,
+    /// and status callbacks update the delivery log.
+    fn handle_event(&self, event: &serde_json::Value) -> Vec<(String, IncomingMessage)> {
+        let allowed = &self.config.allowed_numbers;
+        let mut messages = Vec::new();
+        for value in event_values(event) {
+            for msg in value["messages"].as_array().into_iter().flatten() {
+                let Some(incoming) = incoming_message(msg, &value["contacts"]) else { continue };
+                let id = msg["id"].as_str().unwrap_or_default().to_string();
+                if !allowed.is_empty() && !allowed.contains(&incoming.sender_id) {
+                    tracing::debug!("WhatsApp: ignoring message from {} (not in allowed_numbers)", incoming.sender_id);
+                    continue;
+                }
+                if let Err(e) = self.store.record_inbound(&incoming.sender_id, incoming.timestamp) {
+                    tracing::warn!("WhatsApp: cannot record session for {}: {e}", incoming.sender_id);
+                }
+                messages.push((id, incoming));
+            }
+        }
+        for update in status_updates(event) {
+            match self.store.update_status(&update) {
+                Ok(true) => tracing::debug!("WhatsApp message {} is {}", update.message_id, update.status),
+                Ok(false) => {}
+                Err(e) => tracing::warn!("WhatsApp: cannot update message {}: {e}", update.message_id),
+            }
+        }
+        messages
+    }
+}
+
+/// Shared state of the webhook routes.
+struct WebhookState {
+    channel: WhatsAppChannel,
+    tx: tokio::sync::mpsc::UnboundedSender<IncomingMessage>,
+    /// Recently delivered message ids, oldest first.
+    seen: Mutex<VecDeque<String>>,
+}
+
+impl WebhookState {
+    /// Remember `id`; false if it was already delivered.
+    fn first_delivery(&self, id: &str) -> bool {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        if seen.iter().any(|s| s == id) {
+            return false;
+        }
+        if seen.len() == SEEN_MESSAGE_IDS {
+            seen.pop_front();
+        }
+        seen.push_back(id.to_string());
+        true
+    }
+}
+
+#[derive(Deserialize)]
+struct VerifyQuery {
+    #[serde(rename = "hub.mode", default)]
+    mode: String,
+    #[serde(rename = "hub.verify_token", default)]
+    verify_token: String,
+    #[serde(rename = "hub.challenge", default)]
+    challenge: String,
+}
+
+/// Echo `hub.challenge` when Meta subscribes with our verify token.
+async fn verify_webhook(
+    axum::extract::State(state): axum::extract::State<Arc<WebhookState>>,
+    axum::extract::Query(query): axum::extract::Query<VerifyQuery>,
+) -> std::result::Result<String, axum::http::StatusCode> {
+    use subtle::ConstantTimeEq;
+
+    let expected = state.channel.config.webhook_verify_token.as_bytes();
+    let token_ok = !expected.is_empty() && bool::from(query.verify_token.as_bytes().ct_eq(expected));
+    if query.mode == "subscribe" && token_ok {
+        tracing::info!("WhatsApp webhook verified");
+        Ok(query.challenge)
+    } else {
+        tracing::warn!(target: "bizclaw::audit", event = "whatsapp_webhook_rejected", "WhatsApp webhook verification with a wrong verify token");
+        Err(axum::http::StatusCode::FORBIDDEN)
+    }
+}
+
+async fn receive_webhook_event(
+    axum::extract::State(state): axum::extract::State<Arc<WebhookState>>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> axum::http::StatusCode {
+    use axum::http::StatusCode;
+
+    let signature = headers.get("x-hub-signature-256").and_then(|v| v.to_str().ok()).unwrap_or("");
+    if !verify_signature(&state.channel.config.app_secret, &body, signature) {
+        tracing::warn!(target: "bizclaw::audit", event = "whatsapp_webhook_rejected", "WhatsApp webhook request with a bad signature");
+        return StatusCode::UNAUTHORIZED;
+    }
+    let event: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(event) => event,
+        Err(e) => {
+            tracing::warn!("WhatsApp webhook: invalid event: {e}");
+            return StatusCode::BAD_REQUEST;
+        }
+    };
+    for (id, msg) in state.channel.handle_event(&event) {
+        if !state.first_delivery(&id) {
+            tracing::debug!("WhatsApp webhook: message {id} already handled");
+            continue;
+        }
+        let _ = state.tx.send(msg);
+    }
+    StatusCode::OK
+}
+
+/// Check `X-Hub-Signature-256` (`sha256=<hex HMAC-SHA256 of the body>`).
+fn verify_signature(app_secret: &str, body: &[u8], signature: &str) -> bool {
+    use hmac::{Hmac, Mac};
+    use subtle::ConstantTimeEq;
+
+    let Some(given) = signature.strip_prefix("sha256=") else { return false };
+    if app_secret.is_empty() {
+        return false;
+    }
+    let Ok(mut mac) = Hmac::<sha2::Sha256>::new_from_slice(app_secret.as_bytes()) else { return false };
+    mac.update(body);
+    let expected: String = mac.finalize().into_bytes().iter().map(|b| format!("{b:02x}")).collect();
+    bool::from(expected.as_bytes().ct_eq(given.to_ascii_lowercase().as_bytes()))
+}
+
+/// The `value` of every change in a webhook event.
+fn event_values(event: &serde_json::Value) -> impl Iterator<Item = &serde_json::Value> {
+    event["entry"].as_array().into_iter().flatten()
+        .flat_map(|entry| entry["changes"].as_array().into_iter().flatten())
+        .map(|change| &change["value"])
+}
+
+/// Delivery statuses in a webhook event, with the first error's title
+/// (and details, when given) for failed messages.
+fn status_updates(event: &serde_json::Value) -> Vec<StatusUpdate> {
+    event_values(event)
+        .flat_map(|value| value["statuses"].as_array().into_iter().flatten())
+        .filter_map(|status| {
+            let error = &status["errors"][0];
+            let error = error.is_object().then(|| {
+                let mut text = format!("{} {}", error["code"], error["title"].as_str().unwrap_or("unknown error"));
+                if let Some(details) = error["error_data"]["details"].as_str() {
+                    text = format!("{text}: {details}");
+                }
+                text
+            });
+            Some(StatusUpdate {
+                message_id: status["id"].as_str()?.to_string(),
+                recipient: status["recipient_id"].as_str().unwrap_or_default().to_string(),
+                status: status["status"].as_str()?.to_string(),
+                error,
+                at: status["timestamp"].as_str()
+                    .and_then(|t| t.parse::<i64>().ok())
+                    .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+                    .unwrap_or_else(chrono::Utc::now),
+            })
+        })
+        .collect()
+}
+
+/// Map one webhook message to the common type. Media is kept as a
+/// placeholder such as `[image] caption`; reactions and unsupported types
+/// are skipped.
+fn incoming_message(msg: &serde_json::Value, contacts: &serde_json::Value) -> Option<IncomingMessage> {
+    let from = msg["from"].as_str()?;
+    let kind = msg["type"].as_str().unwrap_or("");
+    let content = match kind {
+        "text" => msg["text"]["body"].as_str()?.to_string(),
+        "button" => msg["button"]["text"].as_str()?.to_string(),
+        "interactive" => {
+            let reply = &msg["interactive"];
+            reply["button_reply"]["title"].as_str()
+                .or_else(|| reply["list_reply"]["title"].as_str())?
+                .to_string()
+        }
+        "image" | "video" | "audio" | "document" | "sticker" => {
+            let caption = msg[kind]["caption"].as_str().unwrap_or("");
+            format!("[{kind}] {caption}").trim_end().to_string()
+        }
+        "location" => {
+            let loc = &msg["location"];
+            format!("[location] {}, {}", loc["latitude"], loc["longitude"])
+        }
+        _ => {
+            tracing::debug!("WhatsApp: ignoring {kind} message");
+            return None;
+        }
+    };
+
+    let sender_name = contacts.as_array().into_iter().flatten()
+        .find(|c| c["wa_id"].as_str() == Some(from))
+        .and_then(|c| c["profile"]["name"].as_str())
+        .map(String::from);
+    let timestamp = msg["timestamp"].as_str()
+        .and_then(|t| t.parse::<i64>().ok())
+        .and_then(|t| chrono::DateTime::from_timestamp(t, 0))
+        .unwrap_or_else(chrono::Utc::now);
+
+    Some(IncomingMessage {
+        channel: "whatsapp".into(),
+        thread_id: from.into(),
+        sender_id: from.into(),
+        sender_name,
+        content,
+        thread_type: ThreadType::Direct,
+        timestamp,
+        reply_to: msg["context"]["id"].as_str().map(String::from),
+        attachments: Vec::new(),
+        message_id: msg["id"].as_str().map(String::from),
+    })
+}
+
+/// Stream of incoming WhatsApp messages from the webhook.
+pub struct WhatsAppWebhookStream {
+    rx: tokio::sync::mpsc::UnboundedReceiver<IncomingMessage>,
+}
+
+impl Stream for WhatsAppWebhookStream {
+    type Item = IncomingMessage;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
 }
 
 #[async_trait]
@@ -141,10 +675,7 @@ impl Channel for WhatsAppChannel {
         }
 
         // Verify token by checking phone number
-        let url = format!(
-            "https://graph.facebook.com/v21.0/{}",
-            self.config.phone_number_id
-        );
+        let url = self.api_url(&self.config.phone_number_id);
 
         let response = self.client
             .get(&url)
@@ -175,14 +706,16 @@ impl Channel for WhatsAppChannel {
     fn is_connected(&self) -> bool { self.connected }
 
     async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
-        // WhatsApp incoming messages arrive via webhook (HTTP POST).
-        // The webhook handler in bizclaw-gateway converts them to IncomingMessage.
-        tracing::info!("WhatsApp: listening via webhook endpoint");
-        Ok(Box::new(futures::stream::pending::<IncomingMessage>()))
+        Ok(Box::new(self.clone().start_webhook().await?))
     }
 
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
-        self.send_text_message(&message.thread_id, &message.content).await?;
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire("whatsapp", &message.thread_id).await?;
+        }
+        for part in format_message(&message.content, Dialect::WhatsApp, MAX_MESSAGE_CHARS) {
+            self.send_text(&message.thread_id, &part).await?;
+        }
         Ok(())
     }
 
@@ -191,3 +724,232 @@ impl Channel for WhatsAppChannel {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, body: &str) -> String {
+        use hmac::{Hmac, Mac};
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body.as_bytes());
+        let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{b:02x}")).collect();
+        format!("sha256={hex}")
+    }
+
+    fn text_event(id: &str, from: &str, text: &str) -> serde_json::Value {
+        serde_json::json!({
+            "object": "whatsapp_business_account",
+            "entry": [{ "id": "waba", "changes": [{ "field": "messages", "value": {
+                "messaging_product": "whatsapp",
+                "metadata": { "phone_number_id": "123" },
+                "contacts": [{ "wa_id": from, "profile": { "name": "Chị Lan" } }],
+                "messages": [{ "id": id, "from": from, "timestamp": "1760000000", "type": "text", "text": { "body": text } }]
+            }}]}]
+        })
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let body = b"The quick brown fox jumps over the lazy dog";
+        let good = "sha256=f7bc83f430538424b13298e6aa6fb143ef4d59a14946175997479dbc2d1a3cd8";
+        assert!(verify_signature("key", body, good));
+        assert!(verify_signature("key", body, &good.to_uppercase().replace("SHA256=", "sha256=")));
+        assert!(!verify_signature("other", body, good));
+        assert!(!verify_signature("key", b"tampered", good));
+        assert!(!verify_signature("key", body, &good["sha256=".len()..]));
+        assert!(!verify_signature("", body, good));
+    }
+
+    #[test]
+    fn test_incoming_message_types() {
+        let contacts = serde_json::json!([{ "wa_id": "84901", "profile": { "name": "Anh Minh" } }]);
+        let msg = |m: serde_json::Value| incoming_message(&m, &contacts);
+
+        let text = msg(serde_json::json!({
+            "from": "84901", "id": "wamid.1", "timestamp": "1760000000", "type": "text",
+            "text": { "body": "Giá bao nhiêu?" }, "context": { "id": "wamid.0" }
+        })).unwrap();
+        assert_eq!(text.content, "Giá bao nhiêu?");
+        assert_eq!(text.thread_id, "84901");
+        assert_eq!(text.sender_name.as_deref(), Some("Anh Minh"));
+        assert_eq!(text.reply_to.as_deref(), Some("wamid.0"));
+        assert_eq!(text.timestamp.timestamp(), 1760000000);
+
+        let image = msg(serde_json::json!({ "from": "84901", "type": "image", "image": { "caption": "hóa đơn" } })).unwrap();
+        assert_eq!(image.content, "[image] hóa đơn");
+        let button = msg(serde_json::json!({
+            "from": "84901", "type": "interactive", "interactive": { "type": "button_reply", "button_reply": { "id": "yes", "title": "Đồng ý" } }
+        })).unwrap();
+        assert_eq!(button.content, "Đồng ý");
+        assert!(msg(serde_json::json!({ "from": "84901", "type": "reaction", "reaction": { "emoji": "👍" } })).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_verification_and_events() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let channel = WhatsAppChannel::new(WhatsAppConfig {
+            webhook_verify_token: "verify-me".into(),
+            app_secret: "app-secret".into(),
+            allowed_numbers: vec!["84901".into()],
+            ..Default::default()
+        });
+        let router = channel.webhook_router("/wa", tx);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/wa", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+        let client = reqwest::Client::new();
+
+        let verify = |token: &str| client.get(&url)
+            .query(&[("hub.mode", "subscribe"), ("hub.verify_token", token), ("hub.challenge", "1158201444")])
+            .send();
+        let ok = verify("verify-me").await.unwrap();
+        assert_eq!(ok.status(), 200);
+        assert_eq!(ok.text().await.unwrap(), "1158201444");
+        assert_eq!(verify("wrong").await.unwrap().status(), 403);
+
+        let post = |event: serde_json::Value, signature: Option<String>| {
+            let body = event.to_string();
+            let signature = signature.unwrap_or_else(|| sign("app-secret", &body));
+            let req = client.post(&url).header("X-Hub-Signature-256", signature).body(body);
+            async move { req.send().await.unwrap().status().as_u16() }
+        };
+        assert_eq!(post(text_event("wamid.1", "84901", "giả mạo"), Some("sha256=00".into())).await, 401);
+        assert_eq!(post(text_event("wamid.1", "84901", "xin chào"), None).await, 200);
+        // Meta redelivers events it thinks failed; each message is handled once.
+        assert_eq!(post(text_event("wamid.1", "84901", "xin chào"), None).await, 200);
+        assert_eq!(post(text_event("wamid.2", "84999", "người lạ"), None).await, 200);
+        assert_eq!(post(text_event("wamid.3", "84901", "lần hai"), None).await, 200);
+
+        let first = rx.recv().await.unwrap();
+        assert_eq!((first.channel.as_str(), first.content.as_str()), ("whatsapp", "xin chào"));
+        assert_eq!(first.sender_name.as_deref(), Some("Chị Lan"));
+        assert_eq!(rx.recv().await.unwrap().content, "lần hai");
+        assert!(rx.try_recv().is_err());
+    }
+
+    /// A Graph API stand-in answering one request once it contains `expect`;
+    /// resolves to the raw request.
+    async fn mock_graph_api(expect: &'static str) -> (String, tokio::task::JoinHandle<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api_base = format!("http://{}", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !String::from_utf8_lossy(&request).contains(expect) {
+                let n = stream.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"messages":[{"id":"wamid.out"}]}"#;
+            let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}", body.len());
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).into_owned()
+        });
+        (api_base, server)
+    }
+
+    fn api_channel(api_base: String) -> WhatsAppChannel {
+        WhatsAppChannel::new(WhatsAppConfig {
+            access_token: "tok".into(),
+            phone_number_id: "123".into(),
+            api_base,
+            ..Default::default()
+        })
+    }
+
+    #[tokio::test]
+    async fn test_send_text_message() {
+        let (api_base, server) = mock_graph_api("\"body\":\"Chào anh\"").await;
+        let channel = api_channel(api_base);
+        assert_eq!(channel.send_text_message("84901", "Chào anh").await.unwrap(), "wamid.out");
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /123/messages "), "{request}");
+        assert!(request.to_lowercase().contains("authorization: bearer tok"));
+        assert!(request.contains("\"to\":\"84901\""));
+        let sent = channel.store().recent(10).unwrap();
+        assert_eq!((sent[0].message_id.as_str(), sent[0].kind.as_str(), sent[0].status.as_str()), ("wamid.out", "text", "sent"));
+    }
+
+    #[tokio::test]
+    async fn test_send_template_message() {
+        let (api_base, server) = mock_graph_api("\"components\"").await;
+        let channel = api_channel(api_base);
+        let params = ["Chị Lan".to_string(), "DH-1024".to_string()];
+        assert_eq!(channel.send_template("84901", "order_update", "vi", &params).await.unwrap(), "wamid.out");
+        let request = server.await.unwrap();
+        let body: serde_json::Value = serde_json::from_str(request.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["type"], "template");
+        assert_eq!(body["template"]["name"], "order_update");
+        assert_eq!(body["template"]["language"]["code"], "vi");
+        assert_eq!(body["template"]["components"][0]["parameters"][1]["text"], "DH-1024");
+        assert_eq!(channel.store().recent(1).unwrap()[0].kind, "template");
+    }
+
+    #[tokio::test]
+    async fn test_free_form_needs_open_session() {
+        // Refused before any request is made, so no API server is needed.
+        let channel = api_channel("http://127.0.0.1:9".into());
+        let err = channel.send_text("84901", "Chào anh").await.unwrap_err().to_string();
+        assert!(err.contains("never written") && err.contains("template"), "{err}");
+
+        let stale = chrono::Utc::now() - chrono::TimeDelta::hours(25);
+        channel.store().record_inbound("84901", stale).unwrap();
+        let err = channel.send_text("84901", "Chào anh").await.unwrap_err().to_string();
+        assert!(err.contains("24-hour session window"), "{err}");
+
+        // A fresh message reopens the window: the send now reaches the (absent) API.
+        channel.store().record_inbound("84901", chrono::Utc::now()).unwrap();
+        let err = channel.send_text("84901", "Chào anh").await.unwrap_err().to_string();
+        assert!(err.contains("request failed"), "{err}");
+    }
+
+    #[test]
+    fn test_message_fixture() {
+        let event: serde_json::Value = serde_json::from_str(include_str!("../tests/fixtures/whatsapp/messages.json")).unwrap();
+        let channel = WhatsAppChannel::new(WhatsAppConfig::default());
+        let messages = channel.handle_event(&event);
+
+        // The reaction is skipped.
+        assert_eq!(messages.len(), 2);
+        let (id, text) = &messages[0];
+        assert!(id.starts_with("wamid.HBgLODQ5MDEyMzQ1NjcV"));
+        assert_eq!((text.sender_id.as_str(), text.sender_name.as_deref()), ("84901234567", Some("Chị Lan")));
+        assert_eq!(text.content, "Shop còn áo size M không?");
+        let image = &messages[1].1;
+        assert_eq!((image.sender_name.as_deref(), image.content.as_str()), (Some("Anh Minh"), "[image] Mẫu này"));
+        assert!(image.reply_to.is_some());
+
+        // Both senders now have an open session.
+        let last = channel.store().last_inbound("84987654321").unwrap().unwrap();
+        assert_eq!(last.timestamp(), 1760000060);
+    }
+
+    #[test]
+    fn test_status_fixture_updates_delivery_log() {
+        let event: serde_json::Value = serde_json::from_str(include_str!("../tests/fixtures/whatsapp/statuses.json")).unwrap();
+        let updates = status_updates(&event);
+        assert_eq!(updates.iter().map(|u| u.status.as_str()).collect::<Vec<_>>(), ["delivered", "read", "failed"]);
+        assert_eq!(updates[0].recipient, "84901234567");
+        assert_eq!(updates[1].at.timestamp(), 1760000160);
+        let error = updates[2].error.as_deref().unwrap();
+        assert!(error.starts_with("131047 Re-engagement message: Message failed"), "{error}");
+
+        let channel = WhatsAppChannel::new(WhatsAppConfig::default());
+        let store = channel.store();
+        store.record_sent("wamid.out.1", "84901234567", "text").unwrap();
+        store.record_sent("wamid.out.2", "84987654321", "text").unwrap();
+        assert!(channel.handle_event(&event).is_empty());
+
+        let log = store.recent(10).unwrap();
+        assert_eq!((log[0].message_id.as_str(), log[0].status.as_str()), ("wamid.out.2", "failed"));
+        assert!(log[0].error.as_deref().unwrap().contains("24 hours"));
+        assert_eq!((log[1].status.as_str(), log[1].error.as_deref()), ("read", None));
+
+        // A late "delivered" doesn't undo "read", and unknown messages are ignored.
+        assert!(!store.update_status(&updates[0]).unwrap());
+        assert_eq!(store.recent(10).unwrap()[1].status, "read");
+        assert!(!store.update_status(&StatusUpdate { message_id: "wamid.other".into(), ..updates[1].clone() }).unwrap());
+    }
+}
@@ -44,6 +44,13 @@ pub struct LoginData {
     pub zpw_service_map: Option<serde_json::Value>,
 }
 
+impl LoginData {
+    /// First WebSocket server in the service map, for the event listener.
+    pub fn ws_url(&self) -> Option<String> {
+        self.zpw_service_map.as_ref()?["zpw_ws"][0].as_str().map(String::from)
+    }
+}
+
 /// QR code generation result (from /authen/qr/generate).
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QrCodeResult {
@@ -108,6 +115,9 @@ impl ZaloAuth {
     }
 
     /// Login with cookie (fastest method).
+    ///
+    /// `AuthFailed` means Zalo rejected the cookie and a new one is needed;
+    /// network failures are `Channel` errors and worth retrying.
     pub async fn login_with_cookie(&self, cookie: &str) -> Result<LoginData> {
         tracing::info!("Zalo auth: logging in with cookie...");
 
@@ -125,10 +135,10 @@ impl ZaloAuth {
             .header("user-agent", &self.credentials.user_agent)
             .send()
             .await
-            .map_err(|e| BizClawError::AuthFailed(format!("Login request failed: {e}")))?;
+            .map_err(|e| BizClawError::Channel(format!("Login request failed: {e}")))?;
 
         let body: serde_json::Value = response.json().await
-            .map_err(|e| BizClawError::AuthFailed(format!("Invalid login response: {e}")))?;
+            .map_err(|e| BizClawError::Channel(format!("Invalid login response: {e}")))?;
 
         let error_code = body["error_code"].as_i64().unwrap_or(-1);
         if error_code != 0 {
@@ -21,7 +21,7 @@ impl ZaloBusiness {
     /// Get product catalog (OA mode).
     pub async fn get_catalog(&self, access_token: &str) -> Result<Vec<ZaloCatalog>> {
         let response = self.client
-            .get(&format!("{}/store/getslice", self.base_url))
+            .get(format!("{}/store/getslice", self.base_url))
             .bearer_auth(access_token)
             .send()
             .await
@@ -69,7 +69,7 @@ impl ZaloBusiness {
         });
 
         let response = self.client
-            .post(&format!("{}/message/cs", self.base_url))
+            .post(format!("{}/message/cs", self.base_url))
             .bearer_auth(access_token)
             .json(&body)
             .send()
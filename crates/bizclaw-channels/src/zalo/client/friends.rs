@@ -20,7 +20,7 @@ impl ZaloFriends {
     /// Get friends list.
     pub async fn get_friends(&self, cookie: &str) -> Result<Vec<ZaloUser>> {
         let response = self.client
-            .get(&format!("{}/friend/list", self.base_url))
+            .get(format!("{}/friend/list", self.base_url))
             .header("cookie", cookie)
             .send()
             .await
@@ -49,7 +49,7 @@ impl ZaloFriends {
     /// Get user info by ID.
     pub async fn get_user_info(&self, user_id: &str, cookie: &str) -> Result<ZaloUser> {
         let response = self.client
-            .get(&format!("{}/friend/profile", self.base_url))
+            .get(format!("{}/friend/profile", self.base_url))
             .query(&[("fuid", user_id)])
             .header("cookie", cookie)
             .send()
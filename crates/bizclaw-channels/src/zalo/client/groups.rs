@@ -20,7 +20,7 @@ impl ZaloGroups {
     /// Get groups list.
     pub async fn get_groups(&self, cookie: &str) -> Result<Vec<ZaloGroup>> {
         let response = self.client
-            .get(&format!("{}/group/list", self.base_url))
+            .get(format!("{}/group/list", self.base_url))
             .header("cookie", cookie)
             .send()
             .await
@@ -49,7 +49,7 @@ impl ZaloGroups {
     /// Get group info.
     pub async fn get_group_info(&self, group_id: &str, cookie: &str) -> Result<ZaloGroup> {
         let response = self.client
-            .get(&format!("{}/group/info", self.base_url))
+            .get(format!("{}/group/info", self.base_url))
             .query(&[("groupId", group_id)])
             .header("cookie", cookie)
             .send()
@@ -2,9 +2,11 @@
 //! Handles: message, reaction, undo, group_event, typing.
 
 use futures::StreamExt;
+use std::sync::Arc;
 use tokio_tungstenite::tungstenite::Message as WsMessage;
 use bizclaw_core::error::{BizClawError, Result};
-use super::models::ZaloMessage;
+use super::models::{ZaloMessage, ZaloMessageContent};
+use crate::group_monitor::GroupMonitor;
 
 /// WebSocket event types from Zalo.
 #[derive(Debug, Clone)]
@@ -36,6 +38,8 @@ pub enum ConnectionState {
 pub struct ZaloListener {
     ws_url: String,
     connected: bool,
+    /// Groups whose messages go to the group summarizer.
+    groups: Option<Arc<GroupMonitor>>,
 }
 
 impl ZaloListener {
@@ -43,9 +47,16 @@ impl ZaloListener {
         Self {
             ws_url: ws_url.to_string(),
             connected: false,
+            groups: None,
         }
     }
 
+    /// Buffer messages from the groups `monitor` watches.
+    pub fn with_group_monitor(mut self, monitor: Arc<GroupMonitor>) -> Self {
+        self.groups = Some(monitor);
+        self
+    }
+
     /// Connect to Zalo WebSocket server.
     pub async fn connect(&mut self) -> Result<()> {
         tracing::info!("Connecting to Zalo WebSocket: {}", self.ws_url);
@@ -64,16 +75,7 @@ impl ZaloListener {
         while let Some(msg) = read.next().await {
             match msg {
                 Ok(WsMessage::Text(text)) => {
-                    match self.parse_event(&text) {
-                        Ok(event) => {
-                            tracing::debug!("Zalo event: {:?}", event);
-                            // Note: Events are logged. Integration with ZaloChannel
-                            // message stream requires mpsc sender injection at construction time.
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to parse Zalo event: {e}");
-                        }
-                    }
+                    self.handle_text(&text);
                 }
                 Ok(WsMessage::Ping(data)) => {
                     tracing::trace!("Zalo ping received ({} bytes)", data.len());
@@ -95,6 +97,28 @@ impl ZaloListener {
         Ok(())
     }
 
+    /// Parse one WebSocket message, buffering it if it's from a monitored group.
+    fn handle_text(&self, text: &str) -> Option<ZaloEvent> {
+        match self.parse_event(text) {
+            Ok(event) => {
+                tracing::debug!("Zalo event: {:?}", event);
+                // Note: Events are logged. Integration with ZaloChannel
+                // message stream requires mpsc sender injection at construction time.
+                if let (ZaloEvent::Message(msg), Some(groups)) = (&event, &self.groups) {
+                    let sender = msg.sender_name.clone().unwrap_or_else(|| msg.sender_id.clone());
+                    let timestamp = chrono::DateTime::from_timestamp_millis(msg.timestamp as i64)
+                        .unwrap_or_else(chrono::Utc::now);
+                    groups.record(&msg.thread_id, None, &sender, &msg.summary_text(), timestamp, msg.is_self);
+                }
+                Some(event)
+            }
+            Err(e) => {
+                tracing::warn!("Failed to parse Zalo event: {e}");
+                None
+            }
+        }
+    }
+
     /// Parse a WebSocket text message into a ZaloEvent.
     fn parse_event(&self, text: &str) -> Result<ZaloEvent> {
         let json: serde_json::Value = serde_json::from_str(text)
@@ -104,16 +128,24 @@ impl ZaloListener {
 
         match cmd {
             501 => {
-                // New message
+                // New message; media has an object as content
+                let data = &json["data"];
+                let content = match &data["content"] {
+                    serde_json::Value::String(text) => ZaloMessageContent::Text(text.clone()),
+                    serde_json::Value::Null => ZaloMessageContent::Text(String::new()),
+                    attachment => ZaloMessageContent::Attachment(attachment.clone()),
+                };
+                let sender_id = data["uidFrom"].as_str().unwrap_or("");
                 Ok(ZaloEvent::Message(ZaloMessage {
-                    msg_id: json["data"]["msgId"].as_str().unwrap_or("").into(),
-                    thread_id: json["data"]["toid"].as_str().unwrap_or("").into(),
-                    sender_id: json["data"]["uidFrom"].as_str().unwrap_or("").into(),
-                    content: super::models::ZaloMessageContent::Text(
-                        json["data"]["content"].as_str().unwrap_or("").into()
-                    ),
-                    timestamp: json["data"]["ts"].as_u64().unwrap_or(0),
-                    is_self: false,
+                    msg_id: data["msgId"].as_str().unwrap_or("").into(),
+                    thread_id: data["toid"].as_str().unwrap_or("").into(),
+                    sender_id: sender_id.into(),
+                    sender_name: data["dName"].as_str().filter(|n| !n.is_empty()).map(String::from),
+                    msg_type: data["msgType"].as_str().unwrap_or("webchat").into(),
+                    content,
+                    timestamp: data["ts"].as_u64().unwrap_or(0),
+                    // Zalo sends the account's own messages from uid "0".
+                    is_self: sender_id == "0",
                 }))
             }
             521 => {
@@ -142,3 +174,49 @@ impl ZaloListener {
         self.connected
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::config::SummarizeGroupsConfig;
+    use bizclaw_core::group_buffer::MessageBuffer;
+
+    fn event(data: serde_json::Value) -> String {
+        serde_json::json!({ "cmd": 501, "data": data }).to_string()
+    }
+
+    #[test]
+    fn test_group_messages_buffered() {
+        let buffer = MessageBuffer::new();
+        let monitor = Arc::new(GroupMonitor::new("zalo", buffer.clone(), SummarizeGroupsConfig {
+            group_ids: vec!["g1".into()],
+            include_own_messages: false,
+        }));
+        monitor.set_group_name("g1", "Kho Hà Nội");
+        let listener = ZaloListener::new("wss://example").with_group_monitor(monitor);
+
+        let text = event(serde_json::json!({
+            "msgId": "1", "toid": "g1", "uidFrom": "u1", "dName": "Minh", "msgType": "webchat",
+            "content": "Hàng về chưa?", "ts": 1_700_000_000_000u64
+        }));
+        assert!(matches!(listener.handle_text(&text), Some(ZaloEvent::Message(_))));
+        listener.handle_text(&event(serde_json::json!({
+            "msgId": "2", "toid": "g1", "uidFrom": "u2", "msgType": "chat.photo",
+            "content": { "title": "", "href": "https://photo" }, "ts": 1_700_000_001_000u64
+        })));
+        // Own messages are left out unless include_own_messages is set.
+        listener.handle_text(&event(serde_json::json!({
+            "msgId": "3", "toid": "g1", "uidFrom": "0", "dName": "Shop", "content": "Có rồi", "ts": 1
+        })));
+        listener.handle_text(&event(serde_json::json!({
+            "msgId": "4", "toid": "g2", "uidFrom": "u1", "content": "other group", "ts": 1
+        })));
+
+        let messages = buffer.drain_group("g1");
+        let got: Vec<_> = messages.iter().map(|m| (m.sender_name.as_str(), m.content.as_str())).collect();
+        assert_eq!(got, [("Minh", "Hàng về chưa?"), ("u2", "[photo]")]);
+        assert_eq!(messages[0].group_name, "Kho Hà Nội");
+        assert_eq!(messages[0].timestamp.timestamp(), 1_700_000_000);
+        assert_eq!(buffer.total_count(), 0);
+    }
+}
@@ -1,6 +1,8 @@
 //! Zalo messaging — send/receive text, images, stickers, files.
 //! Based on Zalo Web HTTP API endpoints.
 
+use std::path::Path;
+
 use serde::{Deserialize, Serialize};
 use bizclaw_core::error::{BizClawError, Result};
 
@@ -41,10 +43,30 @@ pub enum ThreadType {
     Group = 1,
 }
 
+/// A file uploaded to Zalo's CDN, ready to be sent.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadedMedia {
+    /// Set for photos.
+    #[serde(default)]
+    pub photo_id: Option<String>,
+    /// Set for files.
+    #[serde(default)]
+    pub file_id: Option<String>,
+    #[serde(default)]
+    pub normal_url: Option<String>,
+    #[serde(default)]
+    pub hd_url: Option<String>,
+    #[serde(default)]
+    pub file_url: Option<String>,
+}
+
 /// Zalo messaging client.
 pub struct ZaloMessaging {
     client: reqwest::Client,
     base_url: String,
+    /// Where photos and files are uploaded before they are sent.
+    files_url: String,
 }
 
 impl ZaloMessaging {
@@ -52,6 +74,7 @@ impl ZaloMessaging {
         Self {
             client: reqwest::Client::new(),
             base_url: "https://tt-chat-wpa.chat.zalo.me/api".into(),
+            files_url: "https://tt-files-wpa.chat.zalo.me/api".into(),
         }
     }
 
@@ -75,33 +98,147 @@ impl ZaloMessaging {
             "clientId": generate_client_id(),
         });
 
+        let body = self.post(&endpoint, &params, cookie).await?;
+        Ok(message_id(&body, thread_id))
+    }
+
+    /// Send the image at `image_path` to a user.
+    pub async fn send_image(&self, to_id: &str, image_path: &Path, cookie: &str) -> Result<String> {
+        self.send_photo(to_id, ThreadType::User, image_path, cookie).await
+    }
+
+    /// Send the image at `image_path` to a group.
+    pub async fn send_image_to_group(&self, group_id: &str, image_path: &Path, cookie: &str) -> Result<String> {
+        self.send_photo(group_id, ThreadType::Group, image_path, cookie).await
+    }
+
+    /// Upload the image, then send a message with the photo ID.
+    async fn send_photo(
+        &self,
+        thread_id: &str,
+        thread_type: ThreadType,
+        image_path: &Path,
+        cookie: &str,
+    ) -> Result<String> {
+        let uploaded = self.upload(thread_id, thread_type, image_path, MessageType::Image, cookie).await?;
+        let photo_id = uploaded.photo_id
+            .ok_or_else(|| BizClawError::Channel("Image upload returned no photo ID".into()))?;
+        let normal_url = uploaded.normal_url.unwrap_or_default();
+        let params = serde_json::json!({
+            "toid": thread_id,
+            "photoId": photo_id,
+            "hdUrl": uploaded.hd_url.unwrap_or_else(|| normal_url.clone()),
+            "normalUrl": normal_url,
+            "clientId": generate_client_id(),
+        });
+        let scope = if thread_type == ThreadType::User { "message" } else { "group" };
+        let body = self.post(&format!("{}/{scope}/photo_original/send", self.base_url), &params, cookie).await?;
+        Ok(message_id(&body, thread_id))
+    }
+
+    /// Upload the file at `file_path`, then send it as an attachment.
+    pub async fn send_file(
+        &self,
+        to_id: &str,
+        thread_type: ThreadType,
+        file_path: &Path,
+        cookie: &str,
+    ) -> Result<String> {
+        let uploaded = self.upload(to_id, thread_type, file_path, MessageType::File, cookie).await?;
+        let file_id = uploaded.file_id
+            .ok_or_else(|| BizClawError::Channel("File upload returned no file ID".into()))?;
+        let params = serde_json::json!({
+            "toid": to_id,
+            "fileId": file_id,
+            "fileUrl": uploaded.file_url.unwrap_or_default(),
+            "fileName": file_name(file_path),
+            "totalSize": std::fs::metadata(file_path).map(|m| m.len()).unwrap_or(0),
+            "clientId": generate_client_id(),
+        });
+        let scope = if thread_type == ThreadType::User { "message" } else { "group" };
+        let body = self.post(&format!("{}/{scope}/asyncfile/msg", self.base_url), &params, cookie).await?;
+        Ok(message_id(&body, to_id))
+    }
+
+    /// Send sticker `sticker_id` from category `category_id`.
+    pub async fn send_sticker(
+        &self,
+        to_id: &str,
+        thread_type: ThreadType,
+        sticker_id: u64,
+        category_id: u64,
+        cookie: &str,
+    ) -> Result<String> {
+        let params = serde_json::json!({
+            "toid": to_id,
+            "stickerId": sticker_id,
+            "cateId": category_id,
+            "type": 7,
+            "clientId": generate_client_id(),
+        });
+        let scope = if thread_type == ThreadType::User { "message" } else { "group" };
+        let body = self.post(&format!("{}/{scope}/sticker", self.base_url), &params, cookie).await?;
+        Ok(message_id(&body, to_id))
+    }
+
+    /// Upload a photo or file to Zalo's CDN in one chunk.
+    async fn upload(
+        &self,
+        thread_id: &str,
+        thread_type: ThreadType,
+        path: &Path,
+        kind: MessageType,
+        cookie: &str,
+    ) -> Result<UploadedMedia> {
+        let bytes = tokio::fs::read(path).await
+            .map_err(|e| BizClawError::Channel(format!("Cannot read {}: {e}", path.display())))?;
+        let name = file_name(path);
+        let (mime, _) = media_type(path)?;
+        let (scope, target) = if thread_type == ThreadType::User { ("message", "toid") } else { ("group", "grid") };
+        let endpoint = match kind {
+            MessageType::Image => format!("{}/{scope}/photo_original/upload", self.files_url),
+            _ => format!("{}/{scope}/asyncfile/upload", self.files_url),
+        };
+        let part = reqwest::multipart::Part::bytes(bytes.clone())
+            .file_name(name.clone())
+            .mime_str(&mime)
+            .map_err(|e| BizClawError::Channel(format!("Invalid MIME type {mime}: {e}")))?;
+        let form = reqwest::multipart::Form::new()
+            .text(target, thread_id.to_string())
+            .text("fileName", name)
+            .text("totalSize", bytes.len().to_string())
+            .text("chunkId", "1")
+            .text("totalChunk", "1")
+            .text("clientId", generate_client_id())
+            .part("chunkContent", part);
+
         let response = self.client
             .post(&endpoint)
             .header("cookie", cookie)
-            .form(&params)
+            .multipart(form)
             .send()
             .await
-            .map_err(|e| BizClawError::Channel(format!("Send message failed: {e}")))?;
+            .map_err(|e| BizClawError::Channel(format!("Upload failed: {e}")))?;
+        let body: serde_json::Value = response.json().await
+            .map_err(|e| BizClawError::Channel(format!("Invalid upload response: {e}")))?;
+        check_error(&body, "Upload")?;
+        serde_json::from_value(body["data"].clone())
+            .map_err(|e| BizClawError::Channel(format!("Invalid upload response: {e}")))
+    }
 
+    /// Post a form and check Zalo's error code.
+    async fn post(&self, endpoint: &str, params: &serde_json::Value, cookie: &str) -> Result<serde_json::Value> {
+        let response = self.client
+            .post(endpoint)
+            .header("cookie", cookie)
+            .form(params)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Channel(format!("Send message failed: {e}")))?;
         let body: serde_json::Value = response.json().await
             .map_err(|e| BizClawError::Channel(format!("Invalid send response: {e}")))?;
-
-        let error_code = body["error_code"].as_i64().unwrap_or(-1);
-        if error_code != 0 {
-            return Err(BizClawError::Channel(format!(
-                "Send failed: {} - {}",
-                error_code,
-                body["error_message"].as_str().unwrap_or("unknown")
-            )));
-        }
-
-        let msg_id = body["data"]["msgId"]
-            .as_str()
-            .unwrap_or("unknown")
-            .to_string();
-
-        tracing::debug!("Sent message {} to {}", msg_id, thread_id);
-        Ok(msg_id)
+        check_error(&body, "Send")?;
+        Ok(body)
     }
 
     /// Send a reaction to a message.
@@ -119,7 +256,7 @@ impl ZaloMessaging {
         });
 
         self.client
-            .post(&format!("{}/message/reaction", self.base_url))
+            .post(format!("{}/message/reaction", self.base_url))
             .header("cookie", cookie)
             .form(&params)
             .send()
@@ -142,7 +279,7 @@ impl ZaloMessaging {
         });
 
         self.client
-            .post(&format!("{}/message/undo", self.base_url))
+            .post(format!("{}/message/undo", self.base_url))
             .header("cookie", cookie)
             .form(&params)
             .send()
@@ -157,6 +294,43 @@ impl Default for ZaloMessaging {
     fn default() -> Self { Self::new() }
 }
 
+/// MIME type of the file at `path` from its contents, and whether it is
+/// an image Zalo shows inline. Unknown types are `application/octet-stream`.
+pub fn media_type(path: &Path) -> Result<(String, bool)> {
+    let kind = infer::get_from_path(path)
+        .map_err(|e| BizClawError::Channel(format!("Cannot read {}: {e}", path.display())))?;
+    Ok(match kind {
+        Some(kind) => {
+            let image = matches!(kind.mime_type(), "image/jpeg" | "image/png" | "image/gif" | "image/webp");
+            (kind.mime_type().to_string(), image)
+        }
+        None => ("application/octet-stream".to_string(), false),
+    })
+}
+
+/// Fail on a non-zero `error_code` in a Zalo response.
+fn check_error(body: &serde_json::Value, what: &str) -> Result<()> {
+    let error_code = body["error_code"].as_i64().unwrap_or(-1);
+    if error_code != 0 {
+        return Err(BizClawError::Channel(format!(
+            "{what} failed: {} - {}",
+            error_code,
+            body["error_message"].as_str().unwrap_or("unknown")
+        )));
+    }
+    Ok(())
+}
+
+fn message_id(body: &serde_json::Value, thread_id: &str) -> String {
+    let msg_id = body["data"]["msgId"].as_str().unwrap_or("unknown").to_string();
+    tracing::debug!("Sent message {} to {}", msg_id, thread_id);
+    msg_id
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().and_then(|n| n.to_str()).unwrap_or("file").to_string()
+}
+
 /// Generate a client-side message ID.
 fn generate_client_id() -> String {
     use rand::Rng;
@@ -164,3 +338,26 @@ fn generate_client_id() -> String {
     let id: u64 = rng.r#gen::<u64>() % 9_999_999_999;
     format!("cli_{}", id)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_type_sniffs_contents() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-zalo-media-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // Named .txt, but a PNG.
+        let png = dir.join("chart.txt");
+        std::fs::write(&png, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        assert_eq!(media_type(&png).unwrap(), ("image/png".to_string(), true));
+        let pdf = dir.join("quote.pdf");
+        std::fs::write(&pdf, b"%PDF-1.7\n").unwrap();
+        assert_eq!(media_type(&pdf).unwrap(), ("application/pdf".to_string(), false));
+        let text = dir.join("notes.txt");
+        std::fs::write(&text, "hello").unwrap();
+        assert_eq!(media_type(&text).unwrap(), ("application/octet-stream".to_string(), false));
+        assert!(media_type(&dir.join("missing.png")).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
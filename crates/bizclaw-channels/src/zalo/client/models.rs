@@ -23,11 +23,38 @@ pub struct ZaloMessage {
     pub msg_id: String,
     pub thread_id: String,
     pub sender_id: String,
+    /// Sender's display name (`dName`).
+    pub sender_name: Option<String>,
+    /// `webchat` for text, `chat.photo`, `chat.sticker`, ... for media.
+    pub msg_type: String,
     pub content: ZaloMessageContent,
     pub timestamp: u64,
     pub is_self: bool,
 }
 
+impl ZaloMessage {
+    /// The text, or a placeholder such as "[photo] title" for media, as
+    /// kept for group summaries.
+    pub fn summary_text(&self) -> String {
+        let attachment = match &self.content {
+            ZaloMessageContent::Text(text) => return text.clone(),
+            ZaloMessageContent::Attachment(attachment) => attachment,
+        };
+        let kind = match self.msg_type.as_str() {
+            "chat.photo" => "photo",
+            "chat.sticker" => "sticker",
+            "chat.gif" => "GIF",
+            "chat.video.msg" => "video",
+            "chat.voice" => "voice message",
+            "share.file" => "file",
+            "chat.link" | "chat.recommended" => "link",
+            "chat.location.new" => "location",
+            _ => "attachment",
+        };
+        crate::group_monitor::with_placeholder(kind, attachment["title"].as_str())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ZaloMessageContent {
@@ -4,7 +4,7 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 
 /// Zalo session state.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ZaloSession {
     /// User ID
     pub uid: String,
@@ -20,20 +20,8 @@ pub struct ZaloSession {
     pub last_heartbeat: u64,
 }
 
-impl Default for ZaloSession {
-    fn default() -> Self {
-        Self {
-            uid: String::new(),
-            zpw_enk: None,
-            zpw_key: None,
-            ws_url: None,
-            active: false,
-            last_heartbeat: 0,
-        }
-    }
-}
-
-/// Thread-safe session manager.
+/// Thread-safe session manager; clones share the session.
+#[derive(Clone)]
 pub struct SessionManager {
     session: Arc<RwLock<ZaloSession>>,
 }
@@ -55,6 +43,11 @@ impl SessionManager {
         session.last_heartbeat = current_timestamp();
     }
 
+    /// Set the WebSocket URL to listen on.
+    pub async fn set_ws_url(&self, ws_url: Option<String>) {
+        self.session.write().await.ws_url = ws_url;
+    }
+
     /// Check if session is active.
     pub async fn is_active(&self) -> bool {
         let session = self.session.read().await;
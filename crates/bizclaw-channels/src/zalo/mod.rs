@@ -4,17 +4,33 @@
 pub mod client;
 pub mod personal;
 pub mod official;
+mod reconnect;
+pub mod throttle;
 
 use async_trait::async_trait;
 use bizclaw_core::config::ZaloChannelConfig;
 use bizclaw_core::error::{BizClawError, Result};
-use bizclaw_core::traits::Channel;
-use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
+use bizclaw_core::group_buffer::MessageBuffer;
+use bizclaw_core::traits::{Channel, ChannelHealth};
+use bizclaw_core::types::{IncomingMessage, OutgoingMessage, ThreadType};
+use bizclaw_security::allowlist::Allowlist;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::mpsc::UnboundedSender;
 use tokio_stream::Stream;
 
+use crate::format::{Dialect, format_message};
+use crate::group_monitor::GroupMonitor;
+use crate::manager::ChannelStatusStore;
+
 use self::client::auth::{ZaloAuth, ZaloCredentials};
 use self::client::messaging::{ZaloMessaging, ThreadType as ZaloThreadType};
 use self::client::session::SessionManager;
+use self::throttle::ZaloThrottle;
+
+/// Longest text sent in one Zalo message.
+const MAX_MESSAGE_CHARS: usize = 2000;
 
 /// Zalo channel implementation — routes to Personal or OA mode.
 pub struct ZaloChannel {
@@ -23,7 +39,24 @@ pub struct ZaloChannel {
     messaging: ZaloMessaging,
     session: SessionManager,
     connected: bool,
-    cookie: Option<String>,
+    /// Shared with the reconnect loop, which swaps in a reloaded cookie.
+    cookie: Arc<RwLock<Option<String>>>,
+    /// Why the listener is down, once reconnecting has failed or is off.
+    down: Arc<Mutex<Option<String>>>,
+    /// Where connection changes are recorded (the platform's `tenant_channels`).
+    status: Option<Arc<dyn ChannelStatusStore>>,
+    /// Groups in `summarize_groups` whose messages go to the group summarizer.
+    groups: Option<Arc<GroupMonitor>>,
+    /// Enforces `rate_limit`, pausing for `cooldown_on_error_ms` after a failed send.
+    throttle: Arc<ZaloThrottle>,
+    /// Members of each group, as a group message counts once per member.
+    group_sizes: Arc<RwLock<HashMap<String, u32>>>,
+    /// Where the reconnect loop tells an operator the channel is down.
+    alert: Option<(Arc<dyn Channel>, String)>,
+    /// Wakes the reconnect loop for `force_reconnect`.
+    reconnect: Arc<tokio::sync::Notify>,
+    /// Paths of images a reply may send in place of naming them, if any.
+    outgoing_files: Option<Allowlist>,
 }
 
 impl ZaloChannel {
@@ -38,13 +71,175 @@ impl ZaloChannel {
                 config.personal.user_agent.clone()
             },
         };
+        let throttle = Arc::new(ZaloThrottle::new(&config.rate_limit));
         Self {
             config,
             auth: ZaloAuth::new(creds),
             messaging: ZaloMessaging::new(),
             session: SessionManager::new(),
             connected: false,
-            cookie: None,
+            cookie: Arc::new(RwLock::new(None)),
+            down: Arc::new(Mutex::new(None)),
+            status: None,
+            groups: None,
+            throttle,
+            group_sizes: Arc::new(RwLock::new(HashMap::new())),
+            alert: None,
+            reconnect: Arc::new(tokio::sync::Notify::new()),
+            outgoing_files: None,
+        }
+    }
+
+    /// Buffer messages from the groups in `summarize_groups` into `buffer`.
+    /// Group names are looked up when connecting. The account's own
+    /// messages are kept with `self_listen` (or `include_own_messages`).
+    pub fn with_group_buffer(mut self, buffer: MessageBuffer) -> Self {
+        let mut config = self.config.summarize_groups.clone();
+        config.include_own_messages |= self.config.personal.self_listen;
+        self.groups = Some(Arc::new(GroupMonitor::new("zalo", buffer, config)));
+        self
+    }
+
+    /// Record connection changes, such as giving up on reconnecting, in `store`.
+    pub fn with_status_store(mut self, store: Arc<dyn ChannelStatusStore>) -> Self {
+        self.status = Some(store);
+        self
+    }
+
+    /// Tell `thread_id` on `channel` when the account needs pairing again or
+    /// reconnecting has given up (`personal.alert`).
+    pub fn with_alert(mut self, channel: Arc<dyn Channel>, thread_id: &str) -> Self {
+        self.alert = Some((channel, thread_id.to_string()));
+        self
+    }
+
+    /// Send images named in replies that `allowlist` permits as pictures,
+    /// instead of their paths as text.
+    pub fn with_outgoing_files(mut self, allowlist: Allowlist) -> Self {
+        self.outgoing_files = Some(allowlist);
+        self
+    }
+
+    /// Split a reply into its text and the images it names by absolute (or
+    /// `~/`, `./`) path, such as one made by the image generation tool.
+    /// Only existing images `outgoing_files` permits are taken out.
+    fn reply_images(&self, text: &str) -> (String, Vec<PathBuf>) {
+        let Some(allowlist) = &self.outgoing_files else {
+            return (text.to_string(), Vec::new());
+        };
+        let mut images = Vec::new();
+        let mut rest = text.to_string();
+        let words = text.split(|c: char| c.is_whitespace() || matches!(c, '`' | '"' | '\'' | '(' | ')' | '[' | ']' | '<' | '>'));
+        for word in words {
+            let word = word.trim_end_matches(['.', ',', ';', ':', '!', '?']);
+            if !(word.starts_with('/') || word.starts_with("~/") || word.starts_with("./")) {
+                continue;
+            }
+            let Some(path) = allowlist.check_path(word).ok().filter(|p| p.is_file()) else { continue };
+            if matches!(client::messaging::media_type(&path), Ok((_, true))) {
+                rest = rest.replace(word, "");
+                if !images.contains(&path) {
+                    images.push(path);
+                }
+            }
+        }
+        if images.is_empty() {
+            return (text.to_string(), images);
+        }
+        // Drop what is left of Markdown images, links and code spans around the paths.
+        let leftovers = regex::Regex::new(r"!?\[[^\]]*\]\(\s*\)|``").expect("valid regex");
+        let rest = leftovers.replace_all(&rest, "");
+        (rest.lines().map(str::trim_end).collect::<Vec<_>>().join("\n").trim().to_string(), images)
+    }
+
+    /// Take a send from the rate limit, send with `send`, and pause the
+    /// limit for `cooldown_on_error_ms` if Zalo refuses it.
+    async fn throttled<T>(&self, recipients: u32, send: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        let acquired = self.throttle.acquire(recipients).await;
+        if acquired.is_err() {
+            self.report_throttle();
+        }
+        acquired?;
+        let sent = send.await;
+        if sent.is_err() {
+            self.throttle.pause(std::time::Duration::from_millis(self.config.rate_limit.cooldown_on_error_ms));
+        }
+        sent
+    }
+
+    /// Send the image or file at `path`, as a picture if it is an image.
+    async fn send_path(&self, thread_id: &str, thread_type: &ThreadType, path: &Path, cookie: &str) -> Result<()> {
+        let (_, image) = client::messaging::media_type(path)?;
+        let recipients = self.recipients(thread_id, thread_type, cookie).await;
+        let sent = async {
+            match (image, thread_type) {
+                (true, ThreadType::Direct) => self.messaging.send_image(thread_id, path, cookie).await,
+                (true, ThreadType::Group) => self.messaging.send_image_to_group(thread_id, path, cookie).await,
+                (false, ThreadType::Direct) => self.messaging.send_file(thread_id, ZaloThreadType::User, path, cookie).await,
+                (false, ThreadType::Group) => self.messaging.send_file(thread_id, ZaloThreadType::Group, path, cookie).await,
+            }
+        };
+        self.throttled(recipients, sent).await?;
+        tracing::debug!("Zalo: {} sent to {}", path.display(), thread_id);
+        Ok(())
+    }
+
+    /// Listener for this account's events, buffering monitored group messages.
+    pub fn listener(&self, ws_url: &str) -> client::listener::ZaloListener {
+        let listener = client::listener::ZaloListener::new(ws_url);
+        match &self.groups {
+            Some(groups) => listener.with_group_monitor(groups.clone()),
+            None => listener,
+        }
+    }
+
+    /// Learn the account's group sizes, for the rate limit, and the names
+    /// of the monitored groups, for their summaries.
+    async fn load_groups(&self, cookie: &str) {
+        match client::groups::ZaloGroups::new().get_groups(cookie).await {
+            Ok(list) => {
+                self.group_sizes.write().unwrap()
+                    .extend(list.iter().map(|g| (g.id.clone(), g.member_count)));
+                let Some(groups) = &self.groups else { return };
+                for group in list.iter().filter(|g| groups.monitors(&g.id) && !g.name.is_empty()) {
+                    groups.set_group_name(&group.id, &group.name);
+                }
+            }
+            Err(e) => tracing::warn!("Zalo: could not load groups: {e}"),
+        }
+    }
+
+    /// People a message to `thread_id` reaches: the group's members, looked
+    /// up once, or 1 for a direct chat or a group whose size is unknown.
+    async fn recipients(&self, thread_id: &str, thread_type: &ThreadType, cookie: &str) -> u32 {
+        if *thread_type != ThreadType::Group {
+            return 1;
+        }
+        if let Some(&size) = self.group_sizes.read().unwrap().get(thread_id) {
+            return size.max(1);
+        }
+        match client::groups::ZaloGroups::new().get_group_info(thread_id, cookie).await {
+            Ok(group) => {
+                self.group_sizes.write().unwrap().insert(thread_id.to_string(), group.member_count);
+                group.member_count.max(1)
+            }
+            Err(e) => {
+                tracing::warn!("Zalo: could not get the size of group {thread_id}: {e}");
+                1
+            }
+        }
+    }
+
+    /// Show the rate limit's buckets in the channel status, unless the
+    /// channel is down (its status then says why).
+    fn report_throttle(&self) {
+        let Some(store) = &self.status else { return };
+        if self.down.lock().unwrap().is_some() {
+            return;
+        }
+        let summary = self.throttle.state().summary();
+        if let Err(e) = store.set_status("zalo", "connected", Some(&summary)) {
+            tracing::warn!("Zalo: could not record the rate limit state: {e}");
         }
     }
 
@@ -53,10 +248,11 @@ impl ZaloChannel {
         let login_data = self.auth.login_with_cookie(cookie).await?;
         self.session.set_session(
             login_data.uid.clone(),
-            login_data.zpw_enk,
-            login_data.zpw_key,
+            login_data.zpw_enk.clone(),
+            login_data.zpw_key.clone(),
         ).await;
-        self.cookie = Some(cookie.to_string());
+        self.session.set_ws_url(login_data.ws_url()).await;
+        *self.cookie.write().unwrap() = Some(cookie.to_string());
         tracing::info!("Zalo logged in: uid={}", login_data.uid);
         Ok(())
     }
@@ -79,9 +275,10 @@ impl Channel for ZaloChannel {
                 tracing::warn!("⚠️  Zalo Personal API is unofficial. Use at your own risk.");
 
                 // Try cookie login: from cookie_path file first, then raw cookie
-                let cookie = self.try_load_cookie()?;
+                let cookie = load_cookie(&self.config.personal.cookie_path)?;
                 if let Some(cookie) = cookie {
                     self.login_cookie(&cookie).await?;
+                    self.load_groups(&cookie).await;
                     self.connected = true;
                     tracing::info!("Zalo Personal: connected via cookie auth");
                 } else {
@@ -121,65 +318,179 @@ impl Channel for ZaloChannel {
         Ok(Box::new(futures::stream::pending::<IncomingMessage>()))
     }
 
+    /// Listen on the account's WebSocket, reconnecting with backoff when it
+    /// drops if `auto_reconnect` is set.
+    async fn start(&mut self, agent_tx: UnboundedSender<IncomingMessage>) -> Result<()> {
+        if self.config.mode == "personal" {
+            let link = reconnect::ZaloLink {
+                cookie_path: self.config.personal.cookie_path.clone(),
+                auth: ZaloAuth::new(self.auth.credentials().clone()),
+                session: self.session.clone(),
+                cookie: self.cookie.clone(),
+                groups: self.groups.clone(),
+            };
+            let supervisor = reconnect::Supervisor {
+                config: self.config.personal.clone(),
+                link: Box::new(link),
+                down: self.down.clone(),
+                status: self.status.clone(),
+                alert: self.alert.clone().map(|(channel, thread_id)| reconnect::Alert { channel, thread_id }),
+                force: self.reconnect.clone(),
+            };
+            tokio::spawn(supervisor.run());
+        }
+        bizclaw_core::traits::channel::forward(self.listen().await?, agent_tx);
+        Ok(())
+    }
+
+    /// Drop the listener and log in again now, also after an expired cookie
+    /// has been replaced by a new QR login.
+    fn force_reconnect(&self) -> Result<()> {
+        if self.config.mode != "personal" {
+            return Err(BizClawError::Channel("Zalo OA has no connection to reconnect".into()));
+        }
+        tracing::info!("Zalo: reconnect requested");
+        self.reconnect.notify_one();
+        Ok(())
+    }
+
+    fn health(&self) -> ChannelHealth {
+        if let Some(reason) = self.down.lock().unwrap().clone() {
+            ChannelHealth::Down(reason)
+        } else if self.connected {
+            ChannelHealth::Healthy
+        } else {
+            ChannelHealth::Down("not connected".into())
+        }
+    }
+
     async fn send(&self, message: OutgoingMessage) -> Result<()> {
-        let cookie = self.cookie.as_ref()
+        let cookie = self.cookie.read().unwrap().clone()
             .ok_or_else(|| BizClawError::Channel("Zalo not logged in".into()))?;
 
-        self.messaging.send_text(
-            &message.thread_id,
-            ZaloThreadType::User,
-            &message.content,
-            cookie,
-        ).await?;
+        let (text, images) = self.reply_images(&message.content);
+        let recipients = self.recipients(&message.thread_id, &message.thread_type, &cookie).await;
+        let thread_type = match message.thread_type {
+            ThreadType::Group => ZaloThreadType::Group,
+            ThreadType::Direct => ZaloThreadType::User,
+        };
+        // Zalo shows no markup, so the model's Markdown goes as plain text.
+        for part in format_message(&text, Dialect::Plain, MAX_MESSAGE_CHARS) {
+            self.throttled(recipients, self.messaging.send_text(&message.thread_id, thread_type, &part, &cookie)).await?;
+        }
+        for image in images {
+            self.send_path(&message.thread_id, &message.thread_type, &image, &cookie).await?;
+        }
+        self.report_throttle();
 
         tracing::debug!("Zalo: message sent to {}", message.thread_id);
         Ok(())
     }
 
+    async fn send_media(&self, thread_id: &str, thread_type: ThreadType, path: &Path) -> Result<()> {
+        let cookie = self.cookie.read().unwrap().clone()
+            .ok_or_else(|| BizClawError::Channel("Zalo not logged in".into()))?;
+        self.send_path(thread_id, &thread_type, path, &cookie).await?;
+        self.report_throttle();
+        Ok(())
+    }
+
     async fn send_typing(&self, thread_id: &str) -> Result<()> {
         tracing::debug!("Zalo: typing indicator to {} (not supported by API)", thread_id);
         Ok(())
     }
 }
 
-impl ZaloChannel {
-    /// Try to load cookie from cookie_path file.
-    fn try_load_cookie(&self) -> Result<Option<String>> {
-        let path = &self.config.personal.cookie_path;
-        if path.is_empty() {
+/// Read the cookie from the file at `path`: either `{"cookie": "..."}` or
+/// the raw cookie string. `None` when there is no file or it is empty.
+fn load_cookie(path: &str) -> Result<Option<String>> {
+    if path.is_empty() {
+        return Ok(None);
+    }
+
+    // Expand ~ to home dir
+    let expanded = if let Some(rest) = path.strip_prefix("~/") {
+        std::env::var("HOME").ok()
+            .map(|h| std::path::PathBuf::from(h).join(rest))
+            .unwrap_or_else(|| std::path::PathBuf::from(path))
+    } else {
+        std::path::PathBuf::from(path)
+    };
+
+    if expanded.exists() {
+        let content = std::fs::read_to_string(&expanded)
+            .map_err(|e| BizClawError::Config(format!("Failed to read cookie file: {e}")))?;
+
+        let trimmed = content.trim();
+        if trimmed.is_empty() {
             return Ok(None);
         }
 
-        // Expand ~ to home dir
-        let expanded = if path.starts_with("~/") {
-            std::env::var("HOME").ok()
-                .map(|h| std::path::PathBuf::from(h).join(&path[2..]))
-                .unwrap_or_else(|| std::path::PathBuf::from(path))
-        } else {
-            std::path::PathBuf::from(path)
-        };
+        // Support JSON format {"cookie": "..."} or raw cookie string
+        if trimmed.starts_with('{')
+            && let Ok(json) = serde_json::from_str::<serde_json::Value>(trimmed)
+            && let Some(cookie) = json["cookie"].as_str() {
+            return Ok(Some(cookie.to_string()));
+        }
 
-        if expanded.exists() {
-            let content = std::fs::read_to_string(&expanded)
-                .map_err(|e| BizClawError::Config(format!("Failed to read cookie file: {e}")))?;
+        Ok(Some(trimmed.to_string()))
+    } else {
+        Ok(None)
+    }
+}
 
-            let trimmed = content.trim();
-            if trimmed.is_empty() {
-                return Ok(None);
-            }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::config::SummarizeGroupsConfig;
 
-            // Support JSON format {"cookie": "..."} or raw cookie string
-            if trimmed.starts_with('{') {
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(trimmed) {
-                    if let Some(cookie) = json["cookie"].as_str() {
-                        return Ok(Some(cookie.to_string()));
-                    }
-                }
-            }
+    fn channel(self_listen: bool, buffer: &MessageBuffer) -> ZaloChannel {
+        let mut config = ZaloChannelConfig {
+            summarize_groups: SummarizeGroupsConfig { group_ids: vec!["g1".into()], include_own_messages: false },
+            ..ZaloChannelConfig::default()
+        };
+        config.personal.self_listen = self_listen;
+        ZaloChannel::new(config).with_group_buffer(buffer.clone())
+    }
 
-            Ok(Some(trimmed.to_string()))
-        } else {
-            Ok(None)
+    #[test]
+    fn test_own_group_messages_need_self_listen() {
+        let now = chrono::Utc::now();
+        for self_listen in [false, true] {
+            let buffer = MessageBuffer::new();
+            let groups = channel(self_listen, &buffer).groups.unwrap();
+            assert!(groups.record("g1", None, "Minh", "Hàng về chưa?", now, false));
+            assert_eq!(groups.record("g1", None, "Shop", "Có rồi", now, true), self_listen);
+            assert!(!groups.record("g2", None, "Minh", "other group", now, false));
+            assert_eq!(buffer.drain_group("g1").len(), 1 + self_listen as usize);
         }
     }
+
+    #[test]
+    fn test_reply_images_taken_out_of_the_text() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-zalo-reply-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let chart = dir.join("chart.png");
+        std::fs::write(&chart, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let quote = dir.join("quote.pdf");
+        std::fs::write(&quote, b"%PDF-1.7\n").unwrap();
+        let reply = format!(
+            "Doanh thu tuần này:\n![chart]({})\nBáo giá ở `{}`, gọi main() để xem.",
+            chart.display(), quote.display(),
+        );
+
+        // Without an allowlist nothing is sent as a picture.
+        let channel = ZaloChannel::new(ZaloChannelConfig::default());
+        assert_eq!(channel.reply_images(&reply), (reply.clone(), Vec::new()));
+
+        let autonomy = bizclaw_core::config::AutonomyConfig { workspace_only: false, ..Default::default() };
+        let channel = channel.with_outgoing_files(Allowlist::new(&autonomy));
+        let (text, images) = channel.reply_images(&reply);
+        assert_eq!(images, vec![chart.canonicalize().unwrap()]);
+        assert_eq!(text, format!("Doanh thu tuần này:\n\nBáo giá ở `{}`, gọi main() để xem.", quote.display()));
+
+        let (text, images) = channel.reply_images(&format!("{}.", chart.display()));
+        assert_eq!((text.as_str(), images.len()), (".", 1));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
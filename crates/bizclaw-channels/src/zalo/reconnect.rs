@@ -0,0 +1,399 @@
+//! Keeps the Zalo Personal listener running: when the WebSocket drops, log
+//! in again from the cookie file, backing off exponentially between tries.
+//!
+//! A dropped connection is retried on its own. An expired cookie can't be,
+//! so the channel is marked "error" with a request to pair the account again
+//! (and the alert chat, if any, is told) until `force_reconnect` is called,
+//! as it is after a new QR login. Giving up after `max_reconnect_attempts`
+//! failures waits for `force_reconnect` the same way.
+
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use bizclaw_core::config::ZaloPersonalConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Channel;
+use bizclaw_core::types::{OutgoingMessage, ThreadType};
+use tokio::sync::Notify;
+
+use super::client::auth::ZaloAuth;
+use super::client::listener::ZaloListener;
+use super::client::session::SessionManager;
+use crate::group_monitor::GroupMonitor;
+use crate::manager::ChannelStatusStore;
+
+/// How the supervisor reaches Zalo; scripted in tests.
+#[async_trait]
+pub(super) trait Link: Send + Sync {
+    /// Listen for events until the connection drops (`Ok` on a clean close).
+    async fn listen(&self) -> Result<()>;
+
+    /// Log in again. `AuthFailed` means the cookie has expired.
+    async fn login(&self) -> Result<()>;
+}
+
+/// The account's real WebSocket listener and cookie login.
+pub(super) struct ZaloLink {
+    pub(super) cookie_path: String,
+    pub(super) auth: ZaloAuth,
+    pub(super) session: SessionManager,
+    pub(super) cookie: Arc<RwLock<Option<String>>>,
+    pub(super) groups: Option<Arc<GroupMonitor>>,
+}
+
+#[async_trait]
+impl Link for ZaloLink {
+    async fn listen(&self) -> Result<()> {
+        let Some(ws_url) = self.session.get_session().await.ws_url else {
+            return Err(BizClawError::Channel("login returned no WebSocket server".into()));
+        };
+        let mut listener = ZaloListener::new(&ws_url);
+        if let Some(groups) = &self.groups {
+            listener = listener.with_group_monitor(groups.clone());
+        }
+        let listened = listener.connect().await;
+        self.session.invalidate().await;
+        listened
+    }
+
+    /// Log in with the cookie file, which a QR login may have refreshed
+    /// since, falling back to the cookie in use.
+    async fn login(&self) -> Result<()> {
+        let cookie = super::load_cookie(&self.cookie_path)?
+            .or_else(|| self.cookie.read().unwrap().clone())
+            .ok_or_else(|| BizClawError::AuthFailed("no cookie found".into()))?;
+        let login_data = self.auth.login_with_cookie(&cookie).await?;
+        self.session.set_session(
+            login_data.uid.clone(),
+            login_data.zpw_enk.clone(),
+            login_data.zpw_key.clone(),
+        ).await;
+        self.session.set_ws_url(login_data.ws_url()).await;
+        *self.cookie.write().unwrap() = Some(cookie);
+        Ok(())
+    }
+}
+
+/// Chat an operator is told in when the channel goes down for good.
+pub(super) struct Alert {
+    pub(super) channel: Arc<dyn Channel>,
+    pub(super) thread_id: String,
+}
+
+/// Runs the event listener of a logged-in Zalo account.
+pub(super) struct Supervisor {
+    pub(super) config: ZaloPersonalConfig,
+    pub(super) link: Box<dyn Link>,
+    pub(super) down: Arc<Mutex<Option<String>>>,
+    pub(super) status: Option<Arc<dyn ChannelStatusStore>>,
+    pub(super) alert: Option<Alert>,
+    /// Notified by `force_reconnect`.
+    pub(super) force: Arc<Notify>,
+}
+
+impl Supervisor {
+    /// Listen until the connection drops (or a reconnect is forced), then
+    /// reconnect, for as long as the channel runs.
+    pub(super) async fn run(self) {
+        self.report("connected", None);
+        loop {
+            let (reason, forced) = tokio::select! {
+                listened = self.link.listen() => match listened {
+                    Ok(()) => ("connection closed".to_string(), false),
+                    Err(e) => (e.to_string(), false),
+                },
+                _ = self.force.notified() => ("reconnect requested".to_string(), true),
+            };
+            let since = Instant::now();
+            tracing::warn!("Zalo listener disconnected: {reason}");
+            self.report("disconnected", Some(&reason));
+
+            if !forced && !self.config.auto_reconnect {
+                self.give_up(format!("{reason} (auto_reconnect is off)")).await;
+                self.force.notified().await;
+            }
+            self.reconnect(since, forced || !self.config.auto_reconnect).await;
+        }
+    }
+
+    /// Log in again until it works, backing off between tries, `now` for
+    /// the first try. An expired cookie, or `max_reconnect_attempts`
+    /// failures in a row, marks the channel down until a forced reconnect.
+    async fn reconnect(&self, since: Instant, mut now: bool) {
+        let mut attempt = 0;
+        loop {
+            if !now {
+                attempt += 1;
+                let delay = reconnect_delay(&self.config, attempt);
+                tracing::info!("Zalo: reconnecting in {:?} (attempt {attempt})", delay);
+                tokio::select! {
+                    _ = tokio::time::sleep(delay) => {}
+                    _ = self.force.notified() => attempt = 0,
+                }
+            }
+            now = false;
+
+            let outage = match self.link.login().await {
+                Ok(()) => {
+                    tracing::info!("Zalo: reconnected after {}s offline", since.elapsed().as_secs());
+                    *self.down.lock().unwrap() = None;
+                    self.report("connected", None);
+                    return;
+                }
+                Err(BizClawError::AuthFailed(e)) => {
+                    tracing::error!(
+                        "Zalo cookie has expired ({e}). Log in again with a QR code from the admin dashboard; \
+                         the next attempt reads the new cookie from {}",
+                        self.config.cookie_path
+                    );
+                    Some(format!("cookie expired, pair the account again by scanning a QR code in the admin dashboard: {e}"))
+                }
+                Err(e) => {
+                    tracing::warn!("Zalo: reconnect attempt {attempt} failed: {e}");
+                    (self.config.max_reconnect_attempts != 0 && attempt >= self.config.max_reconnect_attempts)
+                        .then(|| format!("reconnecting failed {attempt} times: {e}"))
+                }
+            };
+            if let Some(reason) = outage {
+                self.give_up(reason).await;
+                self.force.notified().await;
+                attempt = 0;
+                now = true;
+            }
+        }
+    }
+
+    /// Mark the channel down until a forced reconnect, and say so in the
+    /// alert chat.
+    async fn give_up(&self, reason: String) {
+        tracing::error!("Zalo channel is down: {reason}");
+        self.report("error", Some(&reason));
+        *self.down.lock().unwrap() = Some(reason.clone());
+        if let Some(alert) = &self.alert {
+            let message = OutgoingMessage {
+                thread_id: alert.thread_id.clone(),
+                content: format!("⚠️ Zalo channel is down: {reason}"),
+                thread_type: ThreadType::Direct,
+                reply_to: None,
+            };
+            if let Err(e) = alert.channel.send(message).await {
+                tracing::warn!("Zalo: could not send the alert to {}: {e}", alert.channel.name());
+            }
+        }
+    }
+
+    fn report(&self, status: &str, message: Option<&str>) {
+        if let Some(store) = &self.status
+            && let Err(e) = store.set_status("zalo", status, message)
+        {
+            tracing::warn!("Zalo: could not record channel status '{status}': {e}");
+        }
+    }
+}
+
+/// Wait before reconnect `attempt` (from 1): `reconnect_delay_ms`, doubled
+/// for each earlier attempt, up to `max_reconnect_delay_ms`.
+fn reconnect_delay(config: &ZaloPersonalConfig, attempt: u32) -> Duration {
+    let factor = 1u64 << attempt.saturating_sub(1).min(32);
+    Duration::from_millis(config.reconnect_delay_ms.saturating_mul(factor).min(config.max_reconnect_delay_ms))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::types::IncomingMessage;
+    use std::collections::VecDeque;
+    use tokio_stream::Stream;
+
+    #[test]
+    fn test_reconnect_delay_doubles_up_to_cap() {
+        let config = ZaloPersonalConfig {
+            reconnect_delay_ms: 5_000,
+            max_reconnect_delay_ms: 60_000,
+            ..ZaloPersonalConfig::default()
+        };
+        let delays: Vec<_> = (1..=6).map(|n| reconnect_delay(&config, n).as_secs()).collect();
+        assert_eq!(delays, [5, 10, 20, 40, 60, 60]);
+        assert_eq!(reconnect_delay(&config, 200), Duration::from_secs(60));
+    }
+
+    #[derive(Default)]
+    struct Recorded(Mutex<Vec<(String, Option<String>)>>);
+
+    impl Recorded {
+        fn statuses(&self) -> Vec<String> {
+            self.0.lock().unwrap().iter().map(|(status, _)| status.clone()).collect()
+        }
+    }
+
+    impl ChannelStatusStore for Recorded {
+        fn set_status(&self, channel: &str, status: &str, message: Option<&str>) -> Result<()> {
+            assert_eq!(channel, "zalo");
+            self.0.lock().unwrap().push((status.into(), message.map(String::from)));
+            Ok(())
+        }
+    }
+
+    /// Listens and logins that fail as scripted; once the script runs out,
+    /// listening lasts forever and logging in works.
+    #[derive(Default)]
+    struct Scripted {
+        listens: Mutex<VecDeque<Result<()>>>,
+        logins: Mutex<VecDeque<Result<()>>>,
+        login_calls: Arc<Mutex<u32>>,
+    }
+
+    #[async_trait]
+    impl Link for Scripted {
+        async fn listen(&self) -> Result<()> {
+            let next = self.listens.lock().unwrap().pop_front();
+            match next {
+                Some(result) => result,
+                None => std::future::pending().await,
+            }
+        }
+
+        async fn login(&self) -> Result<()> {
+            *self.login_calls.lock().unwrap() += 1;
+            self.logins.lock().unwrap().pop_front().unwrap_or(Ok(()))
+        }
+    }
+
+    /// Alert chat that keeps what it is sent.
+    #[derive(Default)]
+    struct Inbox(Mutex<Vec<String>>);
+
+    #[async_trait]
+    impl Channel for Inbox {
+        fn name(&self) -> &str { "telegram" }
+        async fn connect(&mut self) -> Result<()> { Ok(()) }
+        async fn disconnect(&mut self) -> Result<()> { Ok(()) }
+        fn is_connected(&self) -> bool { true }
+        async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>> {
+            Ok(Box::new(futures::stream::pending()))
+        }
+        async fn send(&self, message: OutgoingMessage) -> Result<()> {
+            self.0.lock().unwrap().push(format!("{}: {}", message.thread_id, message.content));
+            Ok(())
+        }
+    }
+
+    struct Harness {
+        store: Arc<Recorded>,
+        down: Arc<Mutex<Option<String>>>,
+        inbox: Arc<Inbox>,
+        force: Arc<Notify>,
+        login_calls: Arc<Mutex<u32>>,
+    }
+
+    impl Harness {
+        /// Run a supervisor over `listens` and `logins`.
+        fn start(config: ZaloPersonalConfig, listens: Vec<Result<()>>, logins: Vec<Result<()>>) -> Self {
+            let link = Scripted { listens: Mutex::new(listens.into()), logins: Mutex::new(logins.into()), ..Default::default() };
+            let harness = Self {
+                store: Default::default(),
+                down: Default::default(),
+                inbox: Default::default(),
+                force: Default::default(),
+                login_calls: link.login_calls.clone(),
+            };
+            let supervisor = Supervisor {
+                config: ZaloPersonalConfig { reconnect_delay_ms: 1, max_reconnect_delay_ms: 2, ..config },
+                link: Box::new(link),
+                down: harness.down.clone(),
+                status: Some(harness.store.clone()),
+                alert: Some(Alert { channel: harness.inbox.clone(), thread_id: "ops".into() }),
+                force: harness.force.clone(),
+            };
+            tokio::spawn(supervisor.run());
+            harness
+        }
+
+        /// Wait until the channel statuses recorded are `expected`.
+        async fn until_statuses(&self, expected: &[&str]) {
+            for _ in 0..500 {
+                if self.store.statuses() == expected {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            }
+            panic!("statuses {:?}, expected {expected:?}", self.store.statuses());
+        }
+
+        fn last_message(&self) -> Option<String> {
+            self.store.0.lock().unwrap().last().and_then(|(_, message)| message.clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_dropped_connection_is_retried() {
+        let harness = Harness::start(
+            ZaloPersonalConfig::default(),
+            vec![Err(BizClawError::Channel("connection reset".into()))],
+            vec![Err(BizClawError::Channel("dns error".into()))],
+        );
+        harness.until_statuses(&["connected", "disconnected", "connected"]).await;
+        assert_eq!(*harness.login_calls.lock().unwrap(), 2);
+        assert_eq!(harness.store.0.lock().unwrap()[1].1.as_deref(), Some("Channel error: connection reset"));
+        assert!(harness.down.lock().unwrap().is_none());
+        assert!(harness.inbox.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expired_cookie_waits_for_pairing() {
+        let harness = Harness::start(
+            ZaloPersonalConfig::default(),
+            vec![Ok(())],
+            vec![Err(BizClawError::AuthFailed("cookie rejected".into()))],
+        );
+        harness.until_statuses(&["connected", "disconnected", "error"]).await;
+        let reason = harness.last_message().unwrap();
+        assert!(reason.contains("pair the account again") && reason.contains("cookie rejected"), "{reason}");
+        assert_eq!(harness.down.lock().unwrap().as_deref(), Some(reason.as_str()));
+        assert_eq!(harness.inbox.0.lock().unwrap().as_slice(), [format!("ops: ⚠️ Zalo channel is down: {reason}")]);
+
+        // No retries until the account is paired again.
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(*harness.login_calls.lock().unwrap(), 1);
+        harness.force.notify_one();
+        harness.until_statuses(&["connected", "disconnected", "error", "connected"]).await;
+        assert!(harness.down.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_attempts() {
+        let failures = (0..3).map(|_| Err(BizClawError::Channel("timeout".into()))).collect();
+        let harness = Harness::start(
+            ZaloPersonalConfig { max_reconnect_attempts: 3, ..ZaloPersonalConfig::default() },
+            vec![Ok(())],
+            failures,
+        );
+        harness.until_statuses(&["connected", "disconnected", "error"]).await;
+        let reason = harness.down.lock().unwrap().clone().unwrap();
+        assert_eq!(reason, "reconnecting failed 3 times: Channel error: timeout");
+        assert_eq!(harness.last_message().as_deref(), Some(reason.as_str()));
+        assert_eq!(*harness.login_calls.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_force_reconnect() {
+        let harness = Harness::start(ZaloPersonalConfig::default(), Vec::new(), Vec::new());
+        harness.until_statuses(&["connected"]).await;
+        harness.force.notify_one();
+        harness.until_statuses(&["connected", "disconnected", "connected"]).await;
+        assert_eq!(harness.store.0.lock().unwrap()[1].1.as_deref(), Some("reconnect requested"));
+        assert_eq!(*harness.login_calls.lock().unwrap(), 1);
+
+        // With auto_reconnect off, a drop waits for a forced reconnect.
+        let harness = Harness::start(
+            ZaloPersonalConfig { auto_reconnect: false, ..ZaloPersonalConfig::default() },
+            vec![Ok(())],
+            Vec::new(),
+        );
+        harness.until_statuses(&["connected", "disconnected", "error"]).await;
+        assert_eq!(harness.last_message().as_deref(), Some("connection closed (auto_reconnect is off)"));
+        harness.force.notify_one();
+        harness.until_statuses(&["connected", "disconnected", "error", "connected"]).await;
+    }
+}
@@ -0,0 +1,412 @@
+//! Token-bucket limits on what the Zalo account sends.
+//!
+//! Zalo flags personal accounts that send in bursts, so every send takes
+//! tokens from two buckets: one holding `max_messages_per_minute` tokens and
+//! refilled over a minute, one holding `max_messages_per_hour` and refilled
+//! over an hour. A group message takes a token per member. Sends wait their
+//! turn in a queue of at most `max_queue`; when it is full the oldest waiting
+//! send is dropped. Consecutive sends are also spaced by `min_delay_ms` plus
+//! up to `jitter_ms` at random, so they don't go out at machine pace.
+
+use bizclaw_core::config::ZaloRateLimitConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+const MINUTE: Duration = Duration::from_secs(60);
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// Where the throttle reads the time, so tests can move it by hand.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> Instant;
+}
+
+/// The real monotonic clock.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Tokens for `capacity` messages per `window`, refilled continuously.
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    per_sec: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    /// `None` when `limit` is 0 (no limit).
+    fn new(limit: u32, window: Duration, now: Instant) -> Option<Self> {
+        (limit > 0).then(|| Self {
+            capacity: limit as f64,
+            tokens: limit as f64,
+            per_sec: limit as f64 / window.as_secs_f64(),
+            updated: now,
+        })
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// How long until `cost` tokens are in the bucket.
+    fn wait_for(&self, cost: f64) -> Duration {
+        let missing = cost - self.tokens;
+        // Allow for rounding in the refill.
+        if missing <= 1e-6 { Duration::ZERO } else { Duration::from_secs_f64(missing / self.per_sec) }
+    }
+}
+
+/// What the buckets hold, for the channel's status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct BucketState {
+    pub minute_tokens: u32,
+    pub minute_capacity: u32,
+    pub hour_tokens: u32,
+    pub hour_capacity: u32,
+    /// Sends waiting for tokens.
+    pub queued: usize,
+    /// Sends dropped because the queue was full.
+    pub dropped: u64,
+}
+
+impl BucketState {
+    /// One line for the channel status, e.g.
+    /// `rate limit: 18/20 per minute, 190/200 per hour, 0 queued`.
+    /// Buckets with no limit are left out.
+    pub fn summary(&self) -> String {
+        let mut summary = String::from("rate limit: ");
+        if self.minute_capacity > 0 {
+            summary.push_str(&format!("{}/{} per minute, ", self.minute_tokens, self.minute_capacity));
+        }
+        if self.hour_capacity > 0 {
+            summary.push_str(&format!("{}/{} per hour, ", self.hour_tokens, self.hour_capacity));
+        }
+        summary.push_str(&format!("{} queued", self.queued));
+        if self.dropped > 0 {
+            summary.push_str(&format!(", {} dropped", self.dropped));
+        }
+        summary
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    minute: Option<Bucket>,
+    hour: Option<Bucket>,
+    /// Tickets of the waiting sends, first in line first.
+    queue: VecDeque<u64>,
+    next_ticket: u64,
+    /// No send before then: the spacing after the last one, or a pause
+    /// after a failed send.
+    next_send: Instant,
+    dropped: u64,
+}
+
+/// Where a queued send stands.
+#[derive(Debug, PartialEq)]
+enum Turn {
+    /// Tokens taken; send now.
+    Go,
+    /// Pushed out of a full queue.
+    Dropped,
+    /// Check again after this long, or when the queue moves.
+    Wait(Duration),
+}
+
+/// Token buckets and send queue for one Zalo account.
+pub struct ZaloThrottle {
+    config: ZaloRateLimitConfig,
+    clock: Arc<dyn Clock>,
+    state: Mutex<State>,
+    moved: Notify,
+}
+
+impl ZaloThrottle {
+    pub fn new(config: &ZaloRateLimitConfig) -> Self {
+        Self::with_clock(config, Arc::new(SystemClock))
+    }
+
+    pub fn with_clock(config: &ZaloRateLimitConfig, clock: Arc<dyn Clock>) -> Self {
+        let now = clock.now();
+        let state = State {
+            minute: Bucket::new(config.max_messages_per_minute, MINUTE, now),
+            hour: Bucket::new(config.max_messages_per_hour, HOUR, now),
+            queue: VecDeque::new(),
+            next_ticket: 0,
+            next_send: now,
+            dropped: 0,
+        };
+        Self { config: config.clone(), clock, state: Mutex::new(state), moved: Notify::new() }
+    }
+
+    /// Wait in line until there are tokens for a message to `recipients`
+    /// people, and take them. Fails if the send is dropped from a full queue.
+    pub async fn acquire(&self, recipients: u32) -> Result<()> {
+        let ticket = self.enqueue();
+        // Leave the queue if the send is given up while waiting.
+        let _place = Place { throttle: self, ticket };
+        loop {
+            let moved = self.moved.notified();
+            match self.turn(ticket, recipients) {
+                Turn::Go => {
+                    self.moved.notify_waiters();
+                    return Ok(());
+                }
+                Turn::Dropped => {
+                    return Err(BizClawError::RateLimited(
+                        "zalo: send queue full, message dropped".into(),
+                    ));
+                }
+                Turn::Wait(wait) => {
+                    tokio::select! {
+                        _ = tokio::time::sleep(wait) => {}
+                        _ = moved => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hold all sends for `duration`, e.g. after Zalo refused one.
+    pub fn pause(&self, duration: Duration) {
+        let until = self.clock.now() + duration;
+        let mut state = self.state.lock().unwrap();
+        state.next_send = state.next_send.max(until);
+    }
+
+    /// Current bucket levels and queue length.
+    pub fn state(&self) -> BucketState {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let level = |bucket: &mut Option<Bucket>| match bucket {
+            Some(b) => {
+                b.refill(now);
+                (b.tokens.floor() as u32, b.capacity as u32)
+            }
+            None => (0, 0),
+        };
+        let (minute_tokens, minute_capacity) = level(&mut state.minute);
+        let (hour_tokens, hour_capacity) = level(&mut state.hour);
+        BucketState {
+            minute_tokens, minute_capacity, hour_tokens, hour_capacity,
+            queued: state.queue.len(),
+            dropped: state.dropped,
+        }
+    }
+
+    /// Join the queue, pushing out the oldest waiting send when it is full.
+    fn enqueue(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        let ticket = state.next_ticket;
+        state.next_ticket += 1;
+        if state.queue.len() >= self.config.max_queue.max(1) {
+            state.queue.pop_front();
+            state.dropped += 1;
+            tracing::warn!("Zalo: send queue full ({} waiting), dropping the oldest message", self.config.max_queue);
+            self.moved.notify_waiters();
+        }
+        state.queue.push_back(ticket);
+        ticket
+    }
+
+    fn turn(&self, ticket: u64, recipients: u32) -> Turn {
+        let now = self.clock.now();
+        let mut state = self.state.lock().unwrap();
+        let state = &mut *state;
+        match state.queue.iter().position(|&t| t == ticket) {
+            None => return Turn::Dropped,
+            // Behind others: woken when the queue moves.
+            Some(p) if p > 0 => return Turn::Wait(MINUTE),
+            Some(_) => {}
+        }
+
+        let mut wait = state.next_send.saturating_duration_since(now);
+        for bucket in [&mut state.minute, &mut state.hour].into_iter().flatten() {
+            bucket.refill(now);
+            // A group bigger than the bucket would never fit; it takes it all.
+            wait = wait.max(bucket.wait_for((recipients.max(1) as f64).min(bucket.capacity)));
+        }
+        if !wait.is_zero() {
+            return Turn::Wait(wait);
+        }
+
+        for bucket in [&mut state.minute, &mut state.hour].into_iter().flatten() {
+            bucket.tokens -= (recipients.max(1) as f64).min(bucket.capacity);
+        }
+        state.queue.pop_front();
+        let jitter = if self.config.jitter_ms > 0 { rand::random::<u64>() % (self.config.jitter_ms + 1) } else { 0 };
+        state.next_send = now + Duration::from_millis(self.config.min_delay_ms + jitter);
+        Turn::Go
+    }
+}
+
+/// A send's place in the queue, given up when dropped.
+struct Place<'a> {
+    throttle: &'a ZaloThrottle,
+    ticket: u64,
+}
+
+impl Drop for Place<'_> {
+    fn drop(&mut self) {
+        let mut state = self.throttle.state.lock().unwrap();
+        if state.queue.front() == Some(&self.ticket) {
+            self.throttle.moved.notify_waiters();
+        }
+        state.queue.retain(|&t| t != self.ticket);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct MockClock(Mutex<Instant>);
+
+    impl MockClock {
+        fn advance(&self, by: Duration) {
+            *self.0.lock().unwrap() += by;
+        }
+    }
+
+    impl Clock for MockClock {
+        fn now(&self) -> Instant {
+            *self.0.lock().unwrap()
+        }
+    }
+
+    fn limits(per_minute: u32, per_hour: u32, max_queue: usize) -> (ZaloThrottle, Arc<MockClock>) {
+        let clock = Arc::new(MockClock(Mutex::new(Instant::now())));
+        let config = ZaloRateLimitConfig {
+            max_messages_per_minute: per_minute,
+            max_messages_per_hour: per_hour,
+            max_queue,
+            min_delay_ms: 0,
+            jitter_ms: 0,
+            ..ZaloRateLimitConfig::default()
+        };
+        (ZaloThrottle::with_clock(&config, clock.clone()), clock)
+    }
+
+    /// Queue a send and see whether it may go now.
+    fn send(throttle: &ZaloThrottle, recipients: u32) -> Turn {
+        let ticket = throttle.enqueue();
+        let turn = throttle.turn(ticket, recipients);
+        if turn != Turn::Go {
+            throttle.state.lock().unwrap().queue.retain(|&t| t != ticket);
+        }
+        turn
+    }
+
+    #[test]
+    fn test_per_minute_ceiling() {
+        let (throttle, clock) = limits(3, 0, 10);
+        for _ in 0..3 {
+            assert_eq!(send(&throttle, 1), Turn::Go);
+        }
+        // A token comes back every 20 seconds.
+        assert_eq!(send(&throttle, 1), Turn::Wait(Duration::from_secs(20)));
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(send(&throttle, 1), Turn::Go);
+        assert_eq!(send(&throttle, 1), Turn::Wait(Duration::from_secs(20)));
+    }
+
+    #[test]
+    fn test_per_hour_ceiling() {
+        let (throttle, clock) = limits(3, 4, 10);
+        for _ in 0..3 {
+            assert_eq!(send(&throttle, 1), Turn::Go);
+        }
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(send(&throttle, 1), Turn::Go);
+        // The minute bucket has refilled, but the hour one is nearly empty:
+        // a token comes back every 15 minutes.
+        clock.advance(Duration::from_secs(60));
+        let Turn::Wait(wait) = send(&throttle, 1) else { panic!("hour ceiling not enforced") };
+        assert!(wait > Duration::from_secs(800) && wait < Duration::from_secs(900), "{wait:?}");
+        clock.advance(wait);
+        assert_eq!(send(&throttle, 1), Turn::Go);
+
+        let state = throttle.state();
+        assert_eq!((state.minute_capacity, state.hour_capacity, state.hour_tokens), (3, 4, 0));
+    }
+
+    #[test]
+    fn test_group_counts_each_recipient() {
+        let (throttle, clock) = limits(5, 0, 10);
+        assert_eq!(send(&throttle, 4), Turn::Go);
+        assert_eq!(send(&throttle, 2), Turn::Wait(Duration::from_secs(12)));
+        assert_eq!(send(&throttle, 1), Turn::Go);
+        // Bigger than the bucket: waits for a full one.
+        clock.advance(Duration::from_secs(48));
+        assert_eq!(send(&throttle, 50), Turn::Wait(Duration::from_secs(12)));
+        clock.advance(Duration::from_secs(12));
+        assert_eq!(send(&throttle, 50), Turn::Go);
+    }
+
+    #[test]
+    fn test_full_queue_drops_oldest() {
+        let (throttle, _clock) = limits(1, 0, 2);
+        let first = throttle.enqueue();
+        let second = throttle.enqueue();
+        let third = throttle.enqueue();
+        assert_eq!(throttle.turn(first, 1), Turn::Dropped);
+        assert_eq!(throttle.turn(second, 1), Turn::Go);
+        assert!(matches!(throttle.turn(third, 1), Turn::Wait(_)));
+
+        let state = throttle.state();
+        assert_eq!((state.queued, state.dropped), (1, 1));
+        assert_eq!(state.summary(), "rate limit: 0/1 per minute, 1 queued, 1 dropped");
+    }
+
+    #[test]
+    fn test_pause_and_spacing() {
+        let (throttle, clock) = limits(0, 0, 10);
+        throttle.pause(Duration::from_secs(30));
+        assert_eq!(send(&throttle, 1), Turn::Wait(Duration::from_secs(30)));
+        clock.advance(Duration::from_secs(30));
+        assert_eq!(send(&throttle, 1), Turn::Go);
+
+        let (mut spaced, clock) = limits(0, 0, 10);
+        spaced.config.min_delay_ms = 1000;
+        spaced.config.jitter_ms = 500;
+        assert_eq!(send(&spaced, 1), Turn::Go);
+        let Turn::Wait(gap) = send(&spaced, 1) else { panic!("sends not spaced") };
+        assert!(gap >= Duration::from_millis(1000) && gap <= Duration::from_millis(1500), "{gap:?}");
+        clock.advance(gap);
+        assert_eq!(send(&spaced, 1), Turn::Go);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_in_line() {
+        let (throttle, _clock) = limits(1, 0, 1);
+        let throttle = Arc::new(throttle);
+        throttle.acquire(1).await.unwrap();
+        // The next send waits on the frozen clock, until a third pushes it
+        // out of the one-place queue.
+        let waiting = tokio::spawn({
+            let throttle = throttle.clone();
+            async move { throttle.acquire(1).await }
+        });
+        while throttle.state().queued == 0 {
+            tokio::task::yield_now().await;
+        }
+        let pushing = tokio::spawn({
+            let throttle = throttle.clone();
+            async move { throttle.acquire(1).await }
+        });
+        let err = waiting.await.unwrap().unwrap_err();
+        assert!(matches!(err, BizClawError::RateLimited(_)), "{err}");
+        assert_eq!(throttle.state().dropped, 1);
+        pushing.abort();
+    }
+}
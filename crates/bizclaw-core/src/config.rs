@@ -17,6 +17,12 @@ pub struct BizClawConfig {
     pub default_model: String,
     #[serde(default = "default_temperature")]
     pub default_temperature: f32,
+    /// HTTP(S) proxy for provider API calls, e.g. `http://proxy.corp:3128`.
+    #[serde(default)]
+    pub proxy: Option<String>,
+    /// Headers added to every provider API call (e.g. for an enterprise API gateway).
+    #[serde(default)]
+    pub extra_headers: std::collections::HashMap<String, String>,
     #[serde(default)]
     pub brain: BrainConfig,
     #[serde(default)]
@@ -26,6 +32,8 @@ pub struct BizClawConfig {
     #[serde(default)]
     pub autonomy: AutonomyConfig,
     #[serde(default)]
+    pub safety: SafetyConfig,
+    #[serde(default)]
     pub runtime: RuntimeConfig,
     #[serde(default)]
     pub tunnel: TunnelConfig,
@@ -35,6 +43,11 @@ pub struct BizClawConfig {
     pub identity: Identity,
     #[serde(default)]
     pub channel: ChannelConfig,
+    #[serde(default)]
+    pub tools: ToolsConfig,
+    /// Directory of tool plugins (`.so` / `.dylib`) loaded at startup.
+    #[serde(default)]
+    pub plugin_dir: Option<std::path::PathBuf>,
 }
 
 fn default_api_key() -> String { String::new() }
@@ -49,15 +62,20 @@ impl Default for BizClawConfig {
             default_provider: default_provider(),
             default_model: default_model(),
             default_temperature: default_temperature(),
+            proxy: None,
+            extra_headers: Default::default(),
             brain: BrainConfig::default(),
             memory: MemoryConfig::default(),
             gateway: GatewayConfig::default(),
             autonomy: AutonomyConfig::default(),
+            safety: SafetyConfig::default(),
             runtime: RuntimeConfig::default(),
             tunnel: TunnelConfig::default(),
             secrets: SecretsConfig::default(),
             identity: Identity::default(),
             channel: ChannelConfig::default(),
+            tools: ToolsConfig::default(),
+            plugin_dir: None,
         }
     }
 }
@@ -108,6 +126,280 @@ impl BizClawConfig {
             .unwrap_or_else(|| PathBuf::from("."))
             .join(".bizclaw")
     }
+
+    /// Directory for runtime data (job store, etc.): `BIZCLAW_DATA_DIR` if
+    /// set (per-tenant on the platform), otherwise the home directory.
+    pub fn data_dir() -> PathBuf {
+        std::env::var_os("BIZCLAW_DATA_DIR")
+            .filter(|d| !d.is_empty())
+            .map(PathBuf::from)
+            .unwrap_or_else(Self::home_dir)
+    }
+
+    /// Load the TOML at `path` (defaults if `None`), then apply `BIZCLAW_*`
+    /// environment overrides.
+    pub fn load_with_env(path: Option<&Path>) -> Result<Self> {
+        let mut config = match path {
+            Some(path) => Self::load_from(path)?,
+            None => Self::default(),
+        };
+        let applied = config.apply_env(std::env::vars())?;
+        if !applied.is_empty() {
+            tracing::info!("Config overridden from environment: {}", applied.join(", "));
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Reject settings that contradict each other.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(telegram) = self.channel.telegram.as_ref().filter(|t| t.enabled) {
+            telegram.validate()?;
+        }
+        if let Some(whatsapp) = self.channel.whatsapp.as_ref().filter(|w| w.enabled) {
+            whatsapp.validate()?;
+        }
+        if let Some(email) = self.channel.email.as_ref().filter(|e| e.enabled) {
+            email.validate()?;
+        }
+        if let Some(webhook) = self.channel.webhook.as_ref().filter(|w| w.enabled) {
+            webhook.validate()?;
+        }
+        if self.tools.web_search.enabled {
+            self.tools.web_search.validate()?;
+        }
+        if self.safety.enabled {
+            crate::safety::SafetyFilter::from_config(&self.safety)?;
+        }
+        Ok(())
+    }
+
+    /// Apply `BIZCLAW_*` overrides from `vars`; returns the names that were used.
+    ///
+    /// A field is addressed by its path with `__` between sections
+    /// (`BIZCLAW_MEMORY__BACKEND` → `memory.backend`), or by one of the short
+    /// names in `ENV_ALIASES` (`BIZCLAW_TELEGRAM_BOT_TOKEN`). Variables that
+    /// don't name a field (e.g. `BIZCLAW_CONFIG`) are ignored. Lists are
+    /// comma-separated or JSON arrays.
+    pub fn apply_env(&mut self, vars: impl IntoIterator<Item = (String, String)>) -> Result<Vec<String>> {
+        let template = self.env_template();
+        let fields = env_fields(&template);
+        let mut value = serde_json::to_value(&*self)?;
+        let mut applied = Vec::new();
+
+        let mut vars: Vec<(String, String)> = vars.into_iter()
+            .filter(|(name, _)| name.starts_with(ENV_PREFIX))
+            .collect();
+        vars.sort();
+        for (name, raw) in vars {
+            let Some(path) = env_path(&name[ENV_PREFIX.len()..]) else { continue };
+            let Some(kind) = fields.iter().find(|(p, _)| *p == path).map(|(_, v)| v) else {
+                tracing::debug!("Ignoring {name}: no config field '{path}'");
+                continue;
+            };
+            let parsed = parse_env_value(&raw, kind)
+                .map_err(|e| crate::error::BizClawError::Config(format!("{name}: {e}")))?;
+            set_path(&mut value, &template, &path, parsed);
+            applied.push(name);
+        }
+
+        *self = serde_json::from_value(value)
+            .map_err(|e| crate::error::BizClawError::Config(format!("Invalid environment override: {e}")))?;
+        Ok(applied)
+    }
+
+    /// Every environment variable `apply_env` understands, with the current value.
+    pub fn env_vars(&self) -> Vec<EnvVar> {
+        let template = self.env_template();
+        let current = serde_json::to_value(self).unwrap_or_default();
+        env_fields(&template)
+            .into_iter()
+            .map(|(path, _)| {
+                let value = path.split('.')
+                    .try_fold(&current, |v, key| v.get(key))
+                    .map(|v| match v {
+                        serde_json::Value::String(s) => s.clone(),
+                        serde_json::Value::Null => String::new(),
+                        other => other.to_string(),
+                    })
+                    .unwrap_or_default();
+                let last = path.rsplit('.').next().unwrap_or(&path);
+                EnvVar {
+                    name: format!("{ENV_PREFIX}{}", path.replace('.', "__").to_uppercase()),
+                    alias: ENV_ALIASES.iter()
+                        .find(|(_, p)| *p == path)
+                        .map(|(alias, _)| format!("{ENV_PREFIX}{alias}")),
+                    secret: is_secret_field(last),
+                    path,
+                    value,
+                }
+            })
+            .collect()
+    }
+
+    /// The config as JSON with optional sections filled in, so their fields
+    /// can be set from the environment too.
+    fn env_template(&self) -> serde_json::Value {
+        let mut value = serde_json::to_value(self).unwrap_or_default();
+        let channel = &mut value["channel"];
+        if channel["telegram"].is_null() {
+            channel["telegram"] = serde_json::to_value(TelegramChannelConfig {
+                enabled: false,
+                bot_token: String::new(),
+                allowed_chat_ids: Vec::new(),
+                approval_chat_ids: Vec::new(),
+                summarize_groups: SummarizeGroupsConfig::default(),
+                mode: default_telegram_mode(),
+                webhook: None,
+                parse_mode: default_telegram_parse_mode(),
+                media: TelegramMediaConfig::default(),
+                respond_to_all: false,
+                rate_limit: ChannelRateLimitConfig::default(),
+                presence: PresenceConfig::default(),
+            }).unwrap_or_default();
+        }
+        if channel["discord"].is_null() {
+            channel["discord"] = serde_json::to_value(DiscordChannelConfig::default()).unwrap_or_default();
+        }
+        if channel["zalo"].is_null() {
+            channel["zalo"] = serde_json::to_value(ZaloChannelConfig::default()).unwrap_or_default();
+        }
+        if channel["whatsapp"].is_null() {
+            channel["whatsapp"] = serde_json::to_value(WhatsAppChannelConfig::default()).unwrap_or_default();
+        }
+        if channel["email"].is_null() {
+            channel["email"] = serde_json::to_value(EmailChannelConfig::default()).unwrap_or_default();
+        }
+        if channel["webhook"].is_null() {
+            channel["webhook"] = serde_json::to_value(WebhookChannelConfig::default()).unwrap_or_default();
+        }
+        value
+    }
+}
+
+/// Prefix of every config environment variable.
+const ENV_PREFIX: &str = "BIZCLAW_";
+
+/// Short names (after `BIZCLAW_`) for commonly deployed settings.
+const ENV_ALIASES: &[(&str, &str)] = &[
+    ("GATEWAY_HOST", "gateway.host"),
+    ("GATEWAY_PORT", "gateway.port"),
+    ("TELEGRAM_ENABLED", "channel.telegram.enabled"),
+    ("TELEGRAM_BOT_TOKEN", "channel.telegram.bot_token"),
+    ("DISCORD_ENABLED", "channel.discord.enabled"),
+    ("DISCORD_BOT_TOKEN", "channel.discord.bot_token"),
+    ("WHATSAPP_ENABLED", "channel.whatsapp.enabled"),
+    ("WHATSAPP_ACCESS_TOKEN", "channel.whatsapp.access_token"),
+    ("WHATSAPP_APP_SECRET", "channel.whatsapp.app_secret"),
+    ("EMAIL_ENABLED", "channel.email.enabled"),
+    ("EMAIL_PASSWORD", "channel.email.password"),
+    ("WEBHOOK_ENABLED", "channel.webhook.enabled"),
+    ("WEBHOOK_SECRET", "channel.webhook.secret"),
+];
+
+/// One supported environment variable, for `bizclaw config env-vars`.
+#[derive(Debug, Clone)]
+pub struct EnvVar {
+    /// Canonical name, e.g. `BIZCLAW_GATEWAY__PORT`.
+    pub name: String,
+    /// Short name, if there is one, e.g. `BIZCLAW_GATEWAY_PORT`.
+    pub alias: Option<String>,
+    /// Dotted config path, e.g. `gateway.port`.
+    pub path: String,
+    pub value: String,
+    /// Value should be masked when displayed.
+    pub secret: bool,
+}
+
+/// Config path for a variable name without the prefix.
+fn env_path(name: &str) -> Option<String> {
+    if let Some((_, path)) = ENV_ALIASES.iter().find(|(alias, _)| *alias == name) {
+        return Some(path.to_string());
+    }
+    let segments: Vec<String> = name.split("__").map(str::to_lowercase).collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return None;
+    }
+    Some(segments.join("."))
+}
+
+/// Leaf fields of `template` as (dotted path, example value).
+fn env_fields(template: &serde_json::Value) -> Vec<(String, serde_json::Value)> {
+    fn walk(prefix: &str, value: &serde_json::Value, out: &mut Vec<(String, serde_json::Value)>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, child) in map {
+                    let path = if prefix.is_empty() { key.clone() } else { format!("{prefix}.{key}") };
+                    walk(&path, child, out);
+                }
+            }
+            leaf => out.push((prefix.to_string(), leaf.clone())),
+        }
+    }
+    let mut out = Vec::new();
+    walk("", template, &mut out);
+    out
+}
+
+/// Convert an environment string to the JSON type of the field it overrides.
+fn parse_env_value(raw: &str, kind: &serde_json::Value) -> std::result::Result<serde_json::Value, String> {
+    use serde_json::Value;
+    let raw = raw.trim();
+    match kind {
+        Value::Bool(_) => match raw.to_lowercase().as_str() {
+            "true" | "1" | "yes" | "on" => Ok(Value::Bool(true)),
+            "false" | "0" | "no" | "off" => Ok(Value::Bool(false)),
+            _ => Err(format!("expected true/false, got '{raw}'")),
+        },
+        Value::Number(n) if n.is_f64() => raw.parse::<f64>().ok()
+            .and_then(serde_json::Number::from_f64)
+            .map(Value::Number)
+            .ok_or_else(|| format!("expected a number, got '{raw}'")),
+        Value::Number(_) => raw.parse::<i64>()
+            .map(Value::from)
+            .map_err(|_| format!("expected an integer, got '{raw}'")),
+        Value::Array(example) => {
+            if raw.starts_with('[') {
+                return serde_json::from_str(raw).map_err(|e| format!("invalid JSON array: {e}"));
+            }
+            let strings = example.first().is_some_and(Value::is_string);
+            Ok(Value::Array(raw.split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| match serde_json::from_str::<Value>(item) {
+                    Ok(v) if !strings && !v.is_object() && !v.is_array() => v,
+                    _ => Value::String(item.to_string()),
+                })
+                .collect()))
+        }
+        _ => Ok(Value::String(raw.to_string())),
+    }
+}
+
+/// Set `path` in `value`, copying missing (optional) sections from `template`.
+fn set_path(value: &mut serde_json::Value, template: &serde_json::Value, path: &str, new: serde_json::Value) {
+    let mut target = value;
+    let mut tmpl = template;
+    let mut keys = path.split('.').peekable();
+    while let Some(key) = keys.next() {
+        tmpl = &tmpl[key];
+        if keys.peek().is_none() {
+            target[key] = new;
+            return;
+        }
+        if target[key].is_null() {
+            target[key] = tmpl.clone();
+        }
+        target = &mut target[key];
+    }
+}
+
+fn is_secret_field(name: &str) -> bool {
+    name.ends_with("api_key")
+        || name.ends_with("token")
+        || name.contains("password")
+        || name.contains("secret")
+        || name == "cookie"
 }
 
 /// Brain (local LLM) configuration.
@@ -121,8 +413,14 @@ pub struct BrainConfig {
     pub threads: u32,
     #[serde(default = "default_max_tokens")]
     pub max_tokens: u32,
-    #[serde(default = "default_context_length")]
+    /// Context window in tokens; 0 uses the model's own (`context_length`
+    /// in its GGUF metadata).
+    #[serde(default)]
     pub context_length: u32,
+    /// Read the model's hyperparameters (layers, heads, context length,
+    /// RoPE base, vocabulary) from its GGUF metadata.
+    #[serde(default = "bool_true")]
+    pub auto_detect_params: bool,
     #[serde(default = "default_cache_dir")]
     pub cache_dir: String,
     #[serde(default = "bool_true")]
@@ -131,8 +429,48 @@ pub struct BrainConfig {
     pub temperature: f32,
     #[serde(default = "default_top_p")]
     pub top_p: f32,
+    /// Sample only from the `top_k` most likely tokens; 0 disables.
+    #[serde(default = "default_top_k")]
+    pub top_k: u32,
+    /// Drop tokens less likely than `min_p` times the top token; 0 disables.
+    #[serde(default)]
+    pub min_p: f32,
+    /// Mirostat v2 sampling, which keeps the text's surprise near `tau`
+    /// instead of using top-k, top-p and min-p.
+    #[serde(default)]
+    pub mirostat_v2: Option<MirostatConfig>,
+    /// Divide the logits of recently generated tokens by this (llama.cpp
+    /// style) to keep the model out of loops; 1.0 disables.
+    #[serde(default = "default_repetition_penalty")]
+    pub repetition_penalty: f32,
+    /// Lower a recent token's logit by this per occurrence (OpenAI style).
+    #[serde(default)]
+    pub frequency_penalty: f32,
+    /// Lower the logit of any recent token by this once (OpenAI style).
+    #[serde(default)]
+    pub presence_penalty: f32,
+    /// How many of the latest generated tokens the penalties look at.
+    #[serde(default = "default_penalty_context_tokens")]
+    pub penalty_context_tokens: usize,
+    /// Fixed sampling seed, for reproducible output.
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// Generation stops when the model writes one of these (not included
+    /// in the reply), e.g. `"\nHuman:"`.
+    #[serde(default = "default_stop_sequences")]
+    pub stop_sequences: Vec<String>,
     #[serde(default)]
     pub json_mode: bool,
+    /// GBNF grammar the local model's output must match, e.g. to force a
+    /// JSON object.
+    #[serde(default)]
+    pub grammar: Option<String>,
+    /// File to read the grammar from when `grammar` isn't set.
+    #[serde(default)]
+    pub grammar_file: Option<String>,
+    /// What the local model forgets once the context window is full.
+    #[serde(default)]
+    pub kv_eviction: KvEvictionConfig,
     #[serde(default)]
     pub fallback: Option<BrainFallback>,
 }
@@ -141,9 +479,12 @@ fn bool_true() -> bool { true }
 fn default_model_path() -> String { "~/.bizclaw/models/tinyllama-1.1b-chat-v1.0.Q4_K_M.gguf".into() }
 fn default_threads() -> u32 { 4 }
 fn default_max_tokens() -> u32 { 256 }
-fn default_context_length() -> u32 { 2048 }
 fn default_cache_dir() -> String { "~/.bizclaw/cache".into() }
 fn default_top_p() -> f32 { 0.9 }
+fn default_top_k() -> u32 { 40 }
+fn default_repetition_penalty() -> f32 { 1.1 }
+fn default_penalty_context_tokens() -> usize { 64 }
+fn default_stop_sequences() -> Vec<String> { vec!["</s>".into(), "<|eot_id|>".into()] }
 
 impl Default for BrainConfig {
     fn default() -> Self {
@@ -152,23 +493,69 @@ impl Default for BrainConfig {
             model_path: default_model_path(),
             threads: default_threads(),
             max_tokens: default_max_tokens(),
-            context_length: default_context_length(),
+            context_length: 0,
+            auto_detect_params: true,
             cache_dir: default_cache_dir(),
             auto_download: true,
             temperature: default_temperature(),
             top_p: default_top_p(),
+            top_k: default_top_k(),
+            min_p: 0.0,
+            mirostat_v2: None,
+            repetition_penalty: default_repetition_penalty(),
+            frequency_penalty: 0.0,
+            presence_penalty: 0.0,
+            penalty_context_tokens: default_penalty_context_tokens(),
+            seed: None,
+            stop_sequences: default_stop_sequences(),
             json_mode: false,
+            grammar: None,
+            grammar_file: None,
+            kv_eviction: KvEvictionConfig::default(),
             fallback: None,
         }
     }
 }
 
+/// `[brain.kv_eviction]` settings, e.g. `policy = "sink_tokens"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "policy", rename_all = "snake_case")]
+pub enum KvEvictionConfig {
+    /// Stop with an error.
+    #[default]
+    Error,
+    /// Keep only the last `window_size` tokens.
+    SlidingWindow { window_size: usize },
+    /// Keep the first `sink_count` tokens and the last `window_size`.
+    SinkTokens { sink_count: usize, window_size: usize },
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BrainFallback {
     pub provider: String,
     pub model: String,
 }
 
+/// `[brain.mirostat_v2]` settings.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MirostatConfig {
+    /// Target surprise in bits; higher gives more varied text.
+    #[serde(default = "default_mirostat_tau")]
+    pub tau: f32,
+    /// How fast the cutoff follows the observed surprise.
+    #[serde(default = "default_mirostat_eta")]
+    pub eta: f32,
+}
+
+fn default_mirostat_tau() -> f32 { 5.0 }
+fn default_mirostat_eta() -> f32 { 0.1 }
+
+impl Default for MirostatConfig {
+    fn default() -> Self {
+        Self { tau: default_mirostat_tau(), eta: default_mirostat_eta() }
+    }
+}
+
 /// Memory configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryConfig {
@@ -210,10 +597,85 @@ pub struct GatewayConfig {
     pub host: String,
     #[serde(default = "bool_true")]
     pub require_pairing: bool,
+    /// Largest file accepted by `POST /api/v1/upload`, in MB.
+    #[serde(default = "default_max_upload_mb")]
+    pub max_upload_mb: u64,
+    /// Browser origins (e.g. `https://admin.example.com`) allowed to call the
+    /// API cross-origin. Empty means same-origin only.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    #[serde(default)]
+    pub rate_limit: GatewayRateLimitConfig,
+    /// Also accept the pairing code as `?code=` on GET requests, for older
+    /// clients. Off by default: query strings end up in proxy and access logs.
+    #[serde(default)]
+    pub allow_query_pairing_code: bool,
+    /// Seconds between ping frames on `/ws` connections; 0 turns them off.
+    /// Keeps idle connections open through proxies that drop quiet sockets.
+    #[serde(default = "default_ws_ping_interval_secs")]
+    pub ws_ping_interval_secs: u64,
+    /// Grace period for the reply to a ping: a connection silent for longer
+    /// than the interval plus this is closed at the next ping.
+    #[serde(default = "default_ws_pong_timeout_secs")]
+    pub ws_pong_timeout_secs: u64,
+    /// On SIGINT/SIGTERM, how long to wait for in-flight requests before
+    /// exiting anyway.
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// Directory of dashboard files (`index.html`, CSS, JS, images) served
+    /// instead of the built-in dashboard. Unset uses the built-in one.
+    #[serde(default)]
+    pub static_dir: Option<String>,
+    /// How long browsers may cache files from `static_dir`, in seconds.
+    /// HTML is always revalidated so a new build shows up on reload.
+    #[serde(default = "default_static_max_age_secs")]
+    pub static_max_age_secs: u64,
 }
 
 fn default_port() -> u16 { 3000 }
+fn default_ws_ping_interval_secs() -> u64 { 30 }
+fn default_ws_pong_timeout_secs() -> u64 { 10 }
+fn default_shutdown_timeout_secs() -> u64 { 30 }
+fn default_static_max_age_secs() -> u64 { 3600 }
 fn default_host() -> String { "127.0.0.1".into() }
+fn default_max_upload_mb() -> u64 { 20 }
+
+/// Per-client-IP limits on the gateway. A limit of 0 turns it off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GatewayRateLimitConfig {
+    /// Sustained requests per minute for any endpoint.
+    #[serde(default = "default_requests_per_minute")]
+    pub requests_per_minute: u32,
+    /// Requests allowed in a burst above the sustained rate.
+    #[serde(default = "default_burst")]
+    pub burst: u32,
+    /// Attempts per minute on `/api/v1/verify-pairing`.
+    #[serde(default = "default_pairing_per_minute")]
+    pub pairing_per_minute: u32,
+    /// Wrong pairing codes before the client is locked out.
+    #[serde(default = "default_pairing_max_failures")]
+    pub pairing_max_failures: u32,
+    #[serde(default = "default_pairing_lockout_secs")]
+    pub pairing_lockout_secs: u64,
+}
+
+fn default_requests_per_minute() -> u32 { 120 }
+fn default_burst() -> u32 { 30 }
+fn default_pairing_per_minute() -> u32 { 5 }
+fn default_pairing_max_failures() -> u32 { 5 }
+fn default_pairing_lockout_secs() -> u64 { 900 }
+
+impl Default for GatewayRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: default_requests_per_minute(),
+            burst: default_burst(),
+            pairing_per_minute: default_pairing_per_minute(),
+            pairing_max_failures: default_pairing_max_failures(),
+            pairing_lockout_secs: default_pairing_lockout_secs(),
+        }
+    }
+}
 
 impl Default for GatewayConfig {
     fn default() -> Self {
@@ -221,6 +683,15 @@ impl Default for GatewayConfig {
             port: default_port(),
             host: default_host(),
             require_pairing: true,
+            max_upload_mb: default_max_upload_mb(),
+            allowed_origins: Vec::new(),
+            rate_limit: GatewayRateLimitConfig::default(),
+            allow_query_pairing_code: false,
+            ws_ping_interval_secs: default_ws_ping_interval_secs(),
+            ws_pong_timeout_secs: default_ws_pong_timeout_secs(),
+            shutdown_timeout_secs: default_shutdown_timeout_secs(),
+            static_dir: None,
+            static_max_age_secs: default_static_max_age_secs(),
         }
     }
 }
@@ -228,17 +699,31 @@ impl Default for GatewayConfig {
 /// Autonomy / security configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutonomyConfig {
+    /// "supervised" (refuse what the allowlist doesn't permit), "approval"
+    /// (ask a human instead of refusing), or "full" (no command restrictions).
     #[serde(default = "default_autonomy_level")]
     pub level: String,
     #[serde(default = "bool_true")]
     pub workspace_only: bool,
+    /// Directory the agent works in; relative tool paths resolve against it.
+    /// The current directory when not set.
+    #[serde(default)]
+    pub workspace: Option<String>,
     #[serde(default = "default_allowed_commands")]
     pub allowed_commands: Vec<String>,
     #[serde(default = "default_forbidden_paths")]
     pub forbidden_paths: Vec<String>,
+    /// How long an "approval" request waits for a decision before it is denied.
+    #[serde(default = "default_approval_timeout_secs")]
+    pub approval_timeout_secs: u64,
+    /// How many tool calls from one model response run at the same time.
+    #[serde(default = "default_max_parallel_tools")]
+    pub max_parallel_tools: usize,
 }
 
 fn default_autonomy_level() -> String { "supervised".into() }
+fn default_approval_timeout_secs() -> u64 { 120 }
+fn default_max_parallel_tools() -> usize { 5 }
 fn default_allowed_commands() -> Vec<String> {
     vec!["git", "npm", "cargo", "ls", "cat", "grep"]
         .into_iter().map(String::from).collect()
@@ -253,24 +738,82 @@ impl Default for AutonomyConfig {
         Self {
             level: default_autonomy_level(),
             workspace_only: true,
+            workspace: None,
             allowed_commands: default_allowed_commands(),
             forbidden_paths: default_forbidden_paths(),
+            approval_timeout_secs: default_approval_timeout_secs(),
+            max_parallel_tools: default_max_parallel_tools(),
+        }
+    }
+}
+
+impl AutonomyConfig {
+    /// `workspace` with `~` expanded, or the current directory.
+    pub fn workspace_dir(&self) -> std::path::PathBuf {
+        match &self.workspace {
+            Some(dir) => std::path::PathBuf::from(shellexpand::tilde(dir).to_string()),
+            None => std::env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from(".")),
         }
     }
 }
 
+/// Response safety rules (`[safety]`), checked before a response is sent.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SafetyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Applied in order; see [`crate::safety`].
+    #[serde(default)]
+    pub rules: Vec<SafetyRuleConfig>,
+    /// Sent instead of a response that a `block` rule matched.
+    #[serde(default = "default_safety_block_message")]
+    pub block_message: String,
+}
+
+fn default_safety_block_message() -> String {
+    "Sorry, I can't share that response.".into()
+}
+
+impl Default for SafetyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rules: Vec::new(),
+            block_message: default_safety_block_message(),
+        }
+    }
+}
+
+/// One `[[safety.rules]]` entry.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SafetyRuleConfig {
+    /// Regex matched against the response; `(?i)` makes it case-insensitive.
+    pub pattern: String,
+    /// "block", "redact" or "warn".
+    pub action: crate::safety::SafetyAction,
+    /// Name reported when the rule matches, e.g. "card_number".
+    pub category: String,
+}
+
 /// Runtime configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuntimeConfig {
     #[serde(default = "default_runtime_kind")]
     pub kind: String,
+    /// Sandbox for the code_exec tool: "docker" or "wasm".
+    #[serde(default = "default_code_exec_backend")]
+    pub code_exec_backend: String,
 }
 
 fn default_runtime_kind() -> String { "native".into() }
+fn default_code_exec_backend() -> String { "docker".into() }
 
 impl Default for RuntimeConfig {
     fn default() -> Self {
-        Self { kind: default_runtime_kind() }
+        Self {
+            kind: default_runtime_kind(),
+            code_exec_backend: default_code_exec_backend(),
+        }
     }
 }
 
@@ -303,14 +846,63 @@ impl Default for SecretsConfig {
 }
 
 /// Channel configuration.
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelConfig {
+    /// Show responses as they are generated on channels that can edit
+    /// messages (Telegram, Discord), instead of sending them when complete.
+    #[serde(default = "bool_true")]
+    pub streaming: bool,
+    /// How long received message ids are remembered, so a redelivered
+    /// message is answered only once; 0 turns this off.
+    #[serde(default = "default_dedup_window_secs")]
+    pub dedup_window_secs: u64,
     #[serde(default)]
     pub zalo: Option<ZaloChannelConfig>,
     #[serde(default)]
     pub telegram: Option<TelegramChannelConfig>,
     #[serde(default)]
     pub discord: Option<DiscordChannelConfig>,
+    #[serde(default)]
+    pub email: Option<EmailChannelConfig>,
+    #[serde(default)]
+    pub whatsapp: Option<WhatsAppChannelConfig>,
+    #[serde(default)]
+    pub webhook: Option<WebhookChannelConfig>,
+}
+
+fn default_dedup_window_secs() -> u64 { 86_400 }
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self {
+            streaming: true,
+            dedup_window_secs: default_dedup_window_secs(),
+            zalo: None,
+            telegram: None,
+            discord: None,
+            email: None,
+            whatsapp: None,
+            webhook: None,
+        }
+    }
+}
+
+impl ChannelConfig {
+    /// Presence settings of the channel named `channel`.
+    pub fn presence(&self, channel: &str) -> PresenceConfig {
+        match channel {
+            "telegram" => self.telegram.as_ref().map(|c| c.presence.clone()),
+            "discord" => self.discord.as_ref().map(|c| c.presence.clone()),
+            "zalo" => self.zalo.as_ref().map(|c| c.presence.clone()),
+            _ => None,
+        }
+        .unwrap_or_default()
+    }
+
+    /// Whether replies on `channel` are streamed into an edited message.
+    pub fn streams(&self, channel: &str) -> bool {
+        self.presence(channel).streaming.unwrap_or(self.streaming)
+    }
 }
 
 /// Zalo channel configuration.
@@ -326,6 +918,10 @@ pub struct ZaloChannelConfig {
     pub rate_limit: ZaloRateLimitConfig,
     #[serde(default)]
     pub allowlist: ZaloAllowlistConfig,
+    #[serde(default)]
+    pub summarize_groups: SummarizeGroupsConfig,
+    #[serde(default)]
+    pub presence: PresenceConfig,
 }
 
 fn default_zalo_mode() -> String { "personal".into() }
@@ -338,6 +934,8 @@ impl Default for ZaloChannelConfig {
             personal: ZaloPersonalConfig::default(),
             rate_limit: ZaloRateLimitConfig::default(),
             allowlist: ZaloAllowlistConfig::default(),
+            summarize_groups: SummarizeGroupsConfig::default(),
+            presence: PresenceConfig::default(),
         }
     }
 }
@@ -354,14 +952,35 @@ pub struct ZaloPersonalConfig {
     pub self_listen: bool,
     #[serde(default = "bool_true")]
     pub auto_reconnect: bool,
+    /// First wait before reconnecting, doubled on each failed attempt.
     #[serde(default = "default_reconnect_delay")]
     pub reconnect_delay_ms: u64,
+    /// Longest wait between reconnect attempts.
+    #[serde(default = "default_max_reconnect_delay")]
+    pub max_reconnect_delay_ms: u64,
+    /// Failed reconnect attempts before the channel is marked as errored
+    /// and left down. 0 retries forever.
+    #[serde(default = "default_max_reconnect_attempts")]
+    pub max_reconnect_attempts: u32,
     #[serde(default)]
     pub proxy: String,
+    /// Chat told when the cookie expires and the account needs pairing again.
+    #[serde(default)]
+    pub alert: Option<ChannelAlertConfig>,
+}
+
+/// Chat on another channel (`telegram` or `discord`) where an operator is
+/// told that a channel needs them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelAlertConfig {
+    pub channel: String,
+    pub thread_id: String,
 }
 
 fn default_cookie_path() -> String { "~/.bizclaw/zalo/cookie.json".into() }
 fn default_reconnect_delay() -> u64 { 5000 }
+fn default_max_reconnect_delay() -> u64 { 300_000 }
+fn default_max_reconnect_attempts() -> u32 { 10 }
 
 impl Default for ZaloPersonalConfig {
     fn default() -> Self {
@@ -372,7 +991,10 @@ impl Default for ZaloPersonalConfig {
             self_listen: false,
             auto_reconnect: true,
             reconnect_delay_ms: default_reconnect_delay(),
+            max_reconnect_delay_ms: default_max_reconnect_delay(),
+            max_reconnect_attempts: default_max_reconnect_attempts(),
             proxy: String::new(),
+            alert: None,
         }
     }
 }
@@ -385,11 +1007,23 @@ pub struct ZaloRateLimitConfig {
     pub max_messages_per_hour: u32,
     #[serde(default = "default_cooldown")]
     pub cooldown_on_error_ms: u64,
+    /// Sends waiting for the limits; past this the oldest is dropped.
+    #[serde(default = "default_zalo_max_queue")]
+    pub max_queue: usize,
+    /// Shortest gap between two sends.
+    #[serde(default = "default_zalo_min_delay")]
+    pub min_delay_ms: u64,
+    /// Up to this much is added to each gap at random.
+    #[serde(default = "default_zalo_jitter")]
+    pub jitter_ms: u64,
 }
 
 fn default_max_per_minute() -> u32 { 20 }
 fn default_max_per_hour() -> u32 { 200 }
 fn default_cooldown() -> u64 { 30000 }
+fn default_zalo_max_queue() -> usize { 100 }
+fn default_zalo_min_delay() -> u64 { 1000 }
+fn default_zalo_jitter() -> u64 { 2000 }
 
 impl Default for ZaloRateLimitConfig {
     fn default() -> Self {
@@ -397,6 +1031,75 @@ impl Default for ZaloRateLimitConfig {
             max_messages_per_minute: default_max_per_minute(),
             max_messages_per_hour: default_max_per_hour(),
             cooldown_on_error_ms: default_cooldown(),
+            max_queue: default_zalo_max_queue(),
+            min_delay_ms: default_zalo_min_delay(),
+            jitter_ms: default_zalo_jitter(),
+        }
+    }
+}
+
+/// Limits on the messages a channel sends, so a busy bot doesn't get its
+/// account throttled or banned. 0 turns a limit off.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelRateLimitConfig {
+    /// Messages per minute over all chats.
+    #[serde(default)]
+    pub max_messages_per_minute: u32,
+    /// Messages per hour over all chats.
+    #[serde(default)]
+    pub max_messages_per_hour: u32,
+    /// Messages per minute to any one chat.
+    #[serde(default = "default_max_per_chat_per_minute")]
+    pub max_per_chat_per_minute: u32,
+    /// How long a send waits for room under the limits before failing.
+    #[serde(default = "default_rate_limit_max_wait")]
+    pub max_wait_secs: u64,
+}
+
+fn default_max_per_chat_per_minute() -> u32 { 20 }
+fn default_rate_limit_max_wait() -> u64 { 10 }
+
+impl Default for ChannelRateLimitConfig {
+    fn default() -> Self {
+        Self {
+            max_messages_per_minute: 0,
+            max_messages_per_hour: 0,
+            max_per_chat_per_minute: default_max_per_chat_per_minute(),
+            max_wait_secs: default_rate_limit_max_wait(),
+        }
+    }
+}
+
+/// What a chat sees while the agent works on a reply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PresenceConfig {
+    /// Keep the typing indicator up until the reply is sent.
+    #[serde(default = "bool_true")]
+    pub typing: bool,
+    /// Seconds between typing indicators; Telegram shows one for 5 seconds,
+    /// Discord for 10.
+    #[serde(default = "default_typing_interval")]
+    pub typing_interval_secs: u64,
+    /// Stream replies into an edited placeholder message. Unset follows
+    /// `channel.streaming`.
+    #[serde(default)]
+    pub streaming: Option<bool>,
+    /// Least time between edits of a streamed reply, to stay under the
+    /// chat's edit rate limits.
+    #[serde(default = "default_edit_interval")]
+    pub edit_interval_ms: u64,
+}
+
+fn default_typing_interval() -> u64 { 4 }
+fn default_edit_interval() -> u64 { 1000 }
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self {
+            typing: true,
+            typing_interval_secs: default_typing_interval(),
+            streaming: None,
+            edit_interval_ms: default_edit_interval(),
         }
     }
 }
@@ -427,56 +1130,1323 @@ pub struct TelegramChannelConfig {
     pub bot_token: String,
     #[serde(default)]
     pub allowed_chat_ids: Vec<i64>,
+    /// Chats prompted to approve or deny tool calls at autonomy level
+    /// "approval"; only their button presses count.
+    #[serde(default)]
+    pub approval_chat_ids: Vec<i64>,
+    #[serde(default)]
+    pub summarize_groups: SummarizeGroupsConfig,
+    /// "polling" (long-poll `getUpdates`, the default) or "webhook"
+    /// (Telegram pushes updates to `webhook.url`).
+    #[serde(default = "default_telegram_mode")]
+    pub mode: String,
+    #[serde(default)]
+    pub webhook: Option<TelegramWebhookConfig>,
+    /// How replies are formatted: "MarkdownV2" (the model's Markdown,
+    /// escaped for Telegram) or "none" (plain text).
+    #[serde(default = "default_telegram_parse_mode")]
+    pub parse_mode: String,
+    #[serde(default)]
+    pub media: TelegramMediaConfig,
+    /// Answer every message in group chats. By default the bot only
+    /// answers commands, @mentions and replies to its own messages there.
+    #[serde(default)]
+    pub respond_to_all: bool,
+    #[serde(default)]
+    pub rate_limit: ChannelRateLimitConfig,
+    #[serde(default)]
+    pub presence: PresenceConfig,
+}
+
+fn default_telegram_mode() -> String { "polling".into() }
+fn default_telegram_parse_mode() -> String { "MarkdownV2".into() }
+
+/// Where Telegram pushes updates in webhook mode.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelegramWebhookConfig {
+    /// Public HTTPS URL registered with `setWebhook`; its path is the route served.
+    pub url: String,
+    /// Sent back by Telegram in `X-Telegram-Bot-Api-Secret-Token`; 1–256 of `A-Z a-z 0-9 _ -`.
+    pub secret_token: String,
+    /// Local address the webhook server listens on (behind the HTTPS proxy).
+    #[serde(default = "default_telegram_webhook_listen")]
+    pub listen: String,
+}
+
+fn default_telegram_webhook_listen() -> String { "0.0.0.0:8443".into() }
+
+/// Photos, documents and voice messages sent to the bot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TelegramMediaConfig {
+    #[serde(default = "bool_true")]
+    pub photos: bool,
+    #[serde(default = "bool_true")]
+    pub documents: bool,
+    #[serde(default = "bool_true")]
+    pub voice: bool,
+    /// Larger files are not downloaded. The Bot API serves at most 20 MB.
+    #[serde(default = "default_telegram_max_download_mb")]
+    pub max_download_mb: u64,
+    /// Where downloads are stored; defaults to `media/` in the data directory.
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// Transcribes voice messages; without it they reach the agent as files.
+    #[serde(default)]
+    pub stt: Option<SpeechToTextConfig>,
+}
+
+fn default_telegram_max_download_mb() -> u64 { 20 }
+
+impl Default for TelegramMediaConfig {
+    fn default() -> Self {
+        Self {
+            photos: true,
+            documents: true,
+            voice: true,
+            max_download_mb: default_telegram_max_download_mb(),
+            dir: None,
+            stt: None,
+        }
+    }
+}
+
+/// An OpenAI-compatible `/audio/transcriptions` endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeechToTextConfig {
+    #[serde(default = "default_stt_api_base")]
+    pub api_base: String,
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default = "default_stt_model")]
+    pub model: String,
+}
+
+fn default_stt_api_base() -> String { "https://api.openai.com/v1".into() }
+fn default_stt_model() -> String { "whisper-1".into() }
+
+impl TelegramChannelConfig {
+    /// Check that exactly one way of receiving updates is configured, and
+    /// that `parse_mode` is known.
+    pub fn validate(&self) -> Result<()> {
+        let err = |msg: String| Err(crate::error::BizClawError::Config(format!("channel.telegram: {msg}")));
+        if !matches!(self.parse_mode.as_str(), "MarkdownV2" | "none") {
+            return err(format!("parse_mode must be \"MarkdownV2\" or \"none\", not \"{}\"", self.parse_mode));
+        }
+        match (self.mode.as_str(), &self.webhook) {
+            ("polling", None) => Ok(()),
+            ("polling", Some(_)) => err(
+                "mode = \"polling\" but [channel.telegram.webhook] is also set; polling and webhook \
+                 can't both run, so set mode = \"webhook\" or remove the webhook section".into(),
+            ),
+            ("webhook", None) => err("mode = \"webhook\" needs a [channel.telegram.webhook] section with url and secret_token".into()),
+            ("webhook", Some(webhook)) => {
+                if !webhook.url.starts_with("https://") {
+                    return err(format!("webhook.url must be an https:// URL, got '{}'", webhook.url));
+                }
+                let secret = &webhook.secret_token;
+                if secret.is_empty() || secret.len() > 256
+                    || !secret.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    return err("webhook.secret_token must be 1-256 characters of A-Z, a-z, 0-9, _ and -".into());
+                }
+                Ok(())
+            }
+            (other, _) => err(format!("mode must be \"polling\" or \"webhook\", not \"{other}\"")),
+        }
+    }
+}
+
+/// Group chats whose messages a channel keeps for the group summarizer
+/// (`tools.group_summarizer`).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SummarizeGroupsConfig {
+    /// Group/chat IDs to buffer. Empty buffers none.
+    #[serde(default)]
+    pub group_ids: Vec<String>,
+    /// Also buffer what the bot itself posts in those groups.
+    #[serde(default)]
+    pub include_own_messages: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DiscordChannelConfig {
     pub enabled: bool,
     pub bot_token: String,
+    /// Channels the bot answers; empty means all.
     #[serde(default)]
     pub allowed_channel_ids: Vec<u64>,
+    /// Gateway intents the bot identifies with. The default includes
+    /// MESSAGE_CONTENT, which must also be enabled for the bot in the
+    /// Discord developer portal.
+    #[serde(default = "default_discord_intents")]
+    pub intents: u64,
+    #[serde(default)]
+    pub rate_limit: ChannelRateLimitConfig,
+    #[serde(default)]
+    pub presence: PresenceConfig,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+fn default_discord_intents() -> u64 {
+    // GUILDS | GUILD_MESSAGES | DIRECT_MESSAGES | MESSAGE_CONTENT
+    (1 << 0) | (1 << 9) | (1 << 12) | (1 << 15)
+}
 
-    #[test]
-    fn test_default_config() {
-        let config = BizClawConfig::default();
-        assert_eq!(config.default_provider, "openai");
-        assert_eq!(config.default_model, "gpt-4o-mini");
-        assert!((config.default_temperature - 0.7).abs() < 0.01);
-        assert_eq!(config.identity.name, "BizClaw");
+impl Default for DiscordChannelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bot_token: String::new(),
+            allowed_channel_ids: Vec::new(),
+            intents: default_discord_intents(),
+            rate_limit: ChannelRateLimitConfig::default(),
+            presence: PresenceConfig::default(),
+        }
     }
+}
 
-    #[test]
-    fn test_config_from_toml() {
-        let toml_str = r#"
-            default_provider = "ollama"
-            default_model = "llama3.2"
-            default_temperature = 0.5
+/// WhatsApp Business Cloud API channel configuration. Messages arrive on a
+/// webhook registered in the Meta app dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WhatsAppChannelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Permanent (system user) access token.
+    #[serde(default)]
+    pub access_token: String,
+    #[serde(default)]
+    pub phone_number_id: String,
+    /// Token entered as "Verify token" when registering the webhook.
+    #[serde(default)]
+    pub verify_token: String,
+    /// App secret, for checking `X-Hub-Signature-256` on webhook requests.
+    #[serde(default)]
+    pub app_secret: String,
+    /// Local address the webhook server listens on (behind the HTTPS proxy).
+    #[serde(default = "default_whatsapp_listen")]
+    pub listen: String,
+    /// Path of the callback URL registered with Meta.
+    #[serde(default = "default_whatsapp_path")]
+    pub path: String,
+    /// Phone numbers (international format, digits only) the bot answers;
+    /// empty means all.
+    #[serde(default)]
+    pub allowed_numbers: Vec<String>,
+    #[serde(default)]
+    pub rate_limit: ChannelRateLimitConfig,
+}
 
-            [identity]
-            name = "TestBot"
-            persona = "A test assistant"
-            system_prompt = "You are a test bot."
-        "#;
+fn default_whatsapp_listen() -> String { "0.0.0.0:8444".into() }
+fn default_whatsapp_path() -> String { "/whatsapp/webhook".into() }
 
-        let config: BizClawConfig = toml::from_str(toml_str).unwrap();
-        assert_eq!(config.default_provider, "ollama");
-        assert_eq!(config.default_model, "llama3.2");
-        assert_eq!(config.identity.name, "TestBot");
+impl Default for WhatsAppChannelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            access_token: String::new(),
+            phone_number_id: String::new(),
+            verify_token: String::new(),
+            app_secret: String::new(),
+            listen: default_whatsapp_listen(),
+            path: default_whatsapp_path(),
+            allowed_numbers: Vec::new(),
+            rate_limit: ChannelRateLimitConfig::default(),
+        }
     }
+}
 
-    #[test]
-    fn test_config_missing_fields_use_defaults() {
-        let toml_str = "";
+impl WhatsAppChannelConfig {
+    /// Check that sending and the webhook are both set up.
+    pub fn validate(&self) -> Result<()> {
+        let missing = [
+            ("access_token", &self.access_token),
+            ("phone_number_id", &self.phone_number_id),
+            ("verify_token", &self.verify_token),
+            ("app_secret", &self.app_secret),
+        ];
+        if let Some((name, _)) = missing.iter().find(|(_, v)| v.is_empty()) {
+            return Err(crate::error::BizClawError::Config(format!("channel.whatsapp: {name} is required")));
+        }
+        if !self.path.starts_with('/') {
+            return Err(crate::error::BizClawError::Config(format!(
+                "channel.whatsapp: path must start with '/', got '{}'", self.path
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Email channel configuration (IMAP in, SMTP out). The SMTP settings are
+/// also used by the `send_email` tool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailChannelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_imap_host")]
+    pub imap_host: String,
+    #[serde(default = "default_imap_port")]
+    pub imap_port: u16,
+    #[serde(default = "default_smtp_host")]
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    /// "starttls" (default), "tls" (implicit TLS, usually port 465), or
+    /// "none" for a local relay.
+    #[serde(default = "default_smtp_security")]
+    pub smtp_security: String,
+    /// Account address; also the sender and the login.
+    pub email: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub display_name: Option<String>,
+    /// IMAP folder polled for unread mail.
+    #[serde(default = "default_email_mailbox")]
+    pub mailbox: String,
+    #[serde(default = "default_email_poll_interval")]
+    pub poll_interval_secs: u64,
+    /// Wait for new mail with IMAP IDLE when the server supports it,
+    /// instead of polling every `poll_interval_secs`.
+    #[serde(default = "bool_true")]
+    pub idle: bool,
+    /// Log in with an OAuth2 access token (XOAUTH2) instead of `password`,
+    /// as Gmail and Outlook require for most accounts.
+    #[serde(default)]
+    pub oauth2: Option<EmailOAuth2Config>,
+    /// Senders answered: addresses or `@domain`s. Everyone when empty.
+    #[serde(default)]
+    pub allowed_senders: Vec<String>,
+    /// Attachments up to this size are saved and given to the agent; 0
+    /// ignores attachments.
+    #[serde(default = "default_email_max_attachment_mb")]
+    pub max_attachment_mb: u64,
+    /// Where attachments are saved (under `email/`); defaults to `media/`
+    /// in the data directory.
+    #[serde(default)]
+    pub attachments_dir: Option<String>,
+    #[serde(default)]
+    pub rate_limit: ChannelRateLimitConfig,
+}
+
+fn default_email_mailbox() -> String { "INBOX".into() }
+fn default_email_poll_interval() -> u64 { 30 }
+
+/// OAuth2 credentials for XOAUTH2. Either a fixed `access_token`, or a
+/// `refresh_token` with the app's `client_id` (and `client_secret`) to get
+/// fresh access tokens from `token_url`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmailOAuth2Config {
+    #[serde(default)]
+    pub access_token: Option<String>,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub client_id: String,
+    #[serde(default)]
+    pub client_secret: String,
+    /// Token endpoint; Google's by default. Outlook uses
+    /// `https://login.microsoftonline.com/common/oauth2/v2.0/token`.
+    #[serde(default = "default_email_token_url")]
+    pub token_url: String,
+}
+
+fn default_email_token_url() -> String { "https://oauth2.googleapis.com/token".into() }
+fn default_imap_host() -> String { "imap.gmail.com".into() }
+fn default_imap_port() -> u16 { 993 }
+fn default_smtp_host() -> String { "smtp.gmail.com".into() }
+fn default_smtp_port() -> u16 { 587 }
+fn default_smtp_security() -> String { "starttls".into() }
+
+impl Default for EmailChannelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            imap_host: default_imap_host(),
+            imap_port: default_imap_port(),
+            smtp_host: default_smtp_host(),
+            smtp_port: default_smtp_port(),
+            smtp_security: default_smtp_security(),
+            email: String::new(),
+            password: String::new(),
+            display_name: None,
+            mailbox: default_email_mailbox(),
+            poll_interval_secs: default_email_poll_interval(),
+            idle: true,
+            oauth2: None,
+            allowed_senders: Vec::new(),
+            max_attachment_mb: default_email_max_attachment_mb(),
+            attachments_dir: None,
+            rate_limit: ChannelRateLimitConfig::default(),
+        }
+    }
+}
+
+impl EmailChannelConfig {
+    /// Check that the account can both poll and reply.
+    pub fn validate(&self) -> Result<()> {
+        let err = |msg: String| crate::error::BizClawError::Config(format!("channel.email: {msg}"));
+        if self.email.is_empty() {
+            return Err(err("email is required".into()));
+        }
+        match &self.oauth2 {
+            None if self.password.is_empty() => return Err(err("password (or [channel.email.oauth2]) is required".into())),
+            Some(oauth2) if oauth2.refresh_token.is_some() && oauth2.client_id.is_empty() => {
+                return Err(err("oauth2.refresh_token needs oauth2.client_id".into()));
+            }
+            Some(oauth2) if oauth2.access_token.is_none() && oauth2.refresh_token.is_none() => {
+                return Err(err("oauth2 needs an access_token or a refresh_token".into()));
+            }
+            _ => {}
+        }
+        if let Some(sender) = self.allowed_senders.iter().find(|s| !s.contains('@')) {
+            return Err(err(format!("allowed_senders entries are addresses or @domains, got '{sender}'")));
+        }
+        if !["starttls", "tls", "none"].contains(&self.smtp_security.as_str()) {
+            return Err(err(format!(
+                "smtp_security must be \"starttls\", \"tls\" or \"none\", got '{}'", self.smtp_security
+            )));
+        }
+        if self.poll_interval_secs == 0 {
+            return Err(err("poll_interval_secs must be at least 1".into()));
+        }
+        Ok(())
+    }
+}
+
+/// Generic HTTP webhook channel: signed JSON in, signed JSON out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookChannelConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Shared secret for the HMAC-SHA256 signatures in both directions.
+    #[serde(default)]
+    pub secret: String,
+    /// Header carrying the signature, `sha256=<hex>`.
+    #[serde(default = "default_webhook_signature_header")]
+    pub signature_header: String,
+    /// Local address the inbound server listens on.
+    #[serde(default = "default_webhook_listen")]
+    pub listen: String,
+    /// Route inbound payloads are POSTed to.
+    #[serde(default = "default_webhook_path")]
+    pub path: String,
+    /// Where the agent's replies are POSTed; replies are dropped without it.
+    #[serde(default)]
+    pub outbound_url: Option<String>,
+    /// Where the message fields are in inbound payloads.
+    #[serde(default)]
+    pub fields: WebhookFieldMap,
+    /// Shape of outbound payloads: any JSON whose strings may contain
+    /// `{{content}}`, `{{thread_id}}` and `{{reply_to}}`. Defaults to
+    /// `{"thread_id", "content", "reply_to"}`.
+    #[serde(default)]
+    pub outbound_template: Option<serde_json::Value>,
+    /// JMESPath expression applied to inbound payloads before the message
+    /// is read from them; see `content_field`.
+    #[serde(default)]
+    pub input_transform: Option<String>,
+    /// JMESPath expression applied to outbound payloads before they are
+    /// POSTed.
+    #[serde(default)]
+    pub output_transform: Option<String>,
+    /// Field of the transformed inbound payload holding the message, used
+    /// instead of `fields.content` when `input_transform` is set. A
+    /// transform that yields a string is the message itself.
+    #[serde(default = "default_webhook_transformed_content_field")]
+    pub content_field: String,
+    /// Header carrying the Unix time a `/webhook/in` request was signed at.
+    #[serde(default = "default_webhook_timestamp_header")]
+    pub timestamp_header: String,
+    /// Oldest `/webhook/in` request accepted, in seconds; older requests and
+    /// repeats of a signature seen within this window are rejected as replays.
+    #[serde(default = "default_webhook_max_age")]
+    pub max_age_secs: u64,
+    /// Where replies to `/webhook/in` are POSTed; without it the reply is
+    /// the response to the request.
+    #[serde(default)]
+    pub callback_url: Option<String>,
+    /// Retries of a failed callback, waiting `callback_backoff_ms` before
+    /// the first and twice as long before each next one.
+    #[serde(default = "default_webhook_callback_retries")]
+    pub callback_retries: u32,
+    #[serde(default = "default_webhook_callback_backoff_ms")]
+    pub callback_backoff_ms: u64,
+    /// How long a request without a callback waits for the reply.
+    #[serde(default = "default_webhook_reply_timeout")]
+    pub reply_timeout_secs: u64,
+    /// More endpoints, served at `/webhook/in/<name>`, each with its own
+    /// secret and callback.
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpointConfig>,
+}
+
+fn default_webhook_signature_header() -> String { "X-BizClaw-Signature".into() }
+fn default_webhook_listen() -> String { "0.0.0.0:8445".into() }
+fn default_webhook_path() -> String { "/webhook".into() }
+fn default_webhook_timestamp_header() -> String { "X-BizClaw-Timestamp".into() }
+fn default_webhook_max_age() -> u64 { 300 }
+fn default_webhook_callback_retries() -> u32 { 5 }
+fn default_webhook_callback_backoff_ms() -> u64 { 1000 }
+fn default_webhook_reply_timeout() -> u64 { 120 }
+fn default_webhook_transformed_content_field() -> String { "message".into() }
+
+/// A named inbound endpoint, `/webhook/in/<name>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookEndpointConfig {
+    pub name: String,
+    /// Shared secret for this endpoint's requests and callbacks.
+    pub secret: String,
+    /// Where replies are POSTed; without it the reply is the response.
+    #[serde(default)]
+    pub callback_url: Option<String>,
+}
+
+impl Default for WebhookChannelConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            secret: String::new(),
+            signature_header: default_webhook_signature_header(),
+            listen: default_webhook_listen(),
+            path: default_webhook_path(),
+            outbound_url: None,
+            fields: WebhookFieldMap::default(),
+            outbound_template: None,
+            input_transform: None,
+            output_transform: None,
+            content_field: default_webhook_transformed_content_field(),
+            timestamp_header: default_webhook_timestamp_header(),
+            max_age_secs: default_webhook_max_age(),
+            callback_url: None,
+            callback_retries: default_webhook_callback_retries(),
+            callback_backoff_ms: default_webhook_callback_backoff_ms(),
+            reply_timeout_secs: default_webhook_reply_timeout(),
+            endpoints: Vec::new(),
+        }
+    }
+}
+
+/// Dotted paths (`data.customer.id`, `items.0.text`) to the message fields
+/// of an inbound payload.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WebhookFieldMap {
+    #[serde(default = "default_webhook_content_field")]
+    pub content: String,
+    #[serde(default = "default_webhook_sender_id_field")]
+    pub sender_id: String,
+    #[serde(default = "default_webhook_sender_name_field")]
+    pub sender_name: String,
+    /// Conversation key; the sender when the payload has none.
+    #[serde(default = "default_webhook_thread_id_field")]
+    pub thread_id: String,
+    /// The sender's id for the event, so a retried delivery is recognised.
+    #[serde(default = "default_webhook_message_id_field")]
+    pub message_id: String,
+}
+
+fn default_webhook_content_field() -> String { "content".into() }
+fn default_webhook_sender_id_field() -> String { "sender_id".into() }
+fn default_webhook_sender_name_field() -> String { "sender_name".into() }
+fn default_webhook_thread_id_field() -> String { "thread_id".into() }
+fn default_webhook_message_id_field() -> String { "message_id".into() }
+
+impl Default for WebhookFieldMap {
+    fn default() -> Self {
+        Self {
+            content: default_webhook_content_field(),
+            sender_id: default_webhook_sender_id_field(),
+            sender_name: default_webhook_sender_name_field(),
+            thread_id: default_webhook_thread_id_field(),
+            message_id: default_webhook_message_id_field(),
+        }
+    }
+}
+
+impl WebhookChannelConfig {
+    /// Check that inbound requests can be verified and the URLs are usable.
+    pub fn validate(&self) -> Result<()> {
+        let err = |msg: String| Err(crate::error::BizClawError::Config(format!("channel.webhook: {msg}")));
+        if self.secret.is_empty() {
+            return err("secret is required".into());
+        }
+        if !self.path.starts_with('/') || self.path.starts_with("/webhook/in") {
+            return err(format!("path must start with '/' and not be under /webhook/in, got '{}'", self.path));
+        }
+        let is_token = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        for header in [&self.signature_header, &self.timestamp_header] {
+            if !is_token(header) {
+                return err(format!("'{header}' is not a valid header name"));
+            }
+        }
+        let is_http = |url: &str| url.starts_with("https://") || url.starts_with("http://");
+        if let Some(url) = &self.outbound_url
+            && !is_http(url)
+        {
+            return err(format!("outbound_url must be an http(s) URL, got '{url}'"));
+        }
+        if self.max_age_secs == 0 || self.reply_timeout_secs == 0 {
+            return err("max_age_secs and reply_timeout_secs must be at least 1".into());
+        }
+        let mut names = std::collections::HashSet::new();
+        for endpoint in &self.endpoints {
+            if !is_token(&endpoint.name) || endpoint.name == "default" || !names.insert(endpoint.name.as_str()) {
+                return err(format!("endpoint name '{}' must be unique, not 'default', and only A-Z, a-z, 0-9, _ and -", endpoint.name));
+            }
+            if endpoint.secret.is_empty() {
+                return err(format!("endpoint '{}' needs a secret", endpoint.name));
+            }
+        }
+        let callbacks = self.endpoints.iter().map(|e| &e.callback_url).chain([&self.callback_url]);
+        if let Some(url) = callbacks.flatten().find(|url| !is_http(url)) {
+            return err(format!("callback_url must be an http(s) URL, got '{url}'"));
+        }
+        let fields = &self.fields;
+        if [&fields.content, &fields.sender_id, &fields.sender_name, &fields.thread_id].iter().any(|f| f.is_empty()) {
+            return err("fields can't be empty".into());
+        }
+        for (name, expr) in [("input_transform", &self.input_transform), ("output_transform", &self.output_transform)] {
+            if let Some(expr) = expr
+                && let Err(e) = jmespath::compile(expr)
+            {
+                return err(format!("{name} is not a valid JMESPath expression: {e}"));
+            }
+        }
+        if self.input_transform.is_some() && self.content_field.is_empty() {
+            return err("content_field can't be empty".into());
+        }
+        Ok(())
+    }
+}
+
+/// Built-in tool configuration (`[tools.<name>]`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolsConfig {
+    /// Wall-clock limit for a single tool call.
+    #[serde(default = "default_tool_timeout")]
+    pub timeout_secs: u64,
+    /// Per-tool overrides of `timeout_secs`, keyed by tool name.
+    #[serde(default)]
+    pub timeouts: std::collections::BTreeMap<String, u64>,
+    /// Tool output beyond this many bytes is cut off with a marker.
+    #[serde(default = "default_tool_max_output")]
+    pub max_output_bytes: usize,
+    #[serde(default)]
+    pub shell: ShellToolConfig,
+    #[serde(default)]
+    pub file: SimpleToolConfig,
+    #[serde(default)]
+    pub web_search: WebSearchToolConfig,
+    #[serde(default)]
+    pub group_summarizer: GroupSummarizerToolConfig,
+    #[serde(default)]
+    pub calendar: CalendarToolConfig,
+    #[serde(default)]
+    pub document_reader: SimpleToolConfig,
+    #[serde(default)]
+    pub code_exec: OptInToolConfig,
+    #[serde(default)]
+    pub git: OptInToolConfig,
+    #[serde(default)]
+    pub jira: JiraToolConfig,
+    #[serde(default)]
+    pub linear: LinearToolConfig,
+    #[serde(default)]
+    pub slack: SlackToolConfig,
+    #[serde(default)]
+    pub notion: NotionToolConfig,
+    #[serde(default)]
+    pub http_request: HttpRequestToolConfig,
+    #[serde(default)]
+    pub web_fetch: WebFetchToolConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerToolConfig,
+    #[serde(default)]
+    pub notes: OptInToolConfig,
+    #[serde(default)]
+    pub send_email: SendEmailToolConfig,
+    /// Sending images and files to Zalo chats; added for Zalo conversations.
+    #[serde(default)]
+    pub zalo_media: SimpleToolConfig,
+    /// Sections for tool names this build doesn't know about.
+    #[serde(flatten)]
+    pub unknown: std::collections::BTreeMap<String, toml::Value>,
+}
+
+fn default_tool_timeout() -> u64 { 30 }
+fn default_tool_max_output() -> usize { 64 * 1024 }
+
+impl Default for ToolsConfig {
+    fn default() -> Self {
+        Self {
+            timeout_secs: default_tool_timeout(),
+            timeouts: Default::default(),
+            max_output_bytes: default_tool_max_output(),
+            shell: ShellToolConfig::default(),
+            file: SimpleToolConfig::default(),
+            web_search: WebSearchToolConfig::default(),
+            group_summarizer: GroupSummarizerToolConfig::default(),
+            calendar: CalendarToolConfig::default(),
+            document_reader: SimpleToolConfig::default(),
+            code_exec: OptInToolConfig::default(),
+            git: OptInToolConfig::default(),
+            jira: JiraToolConfig::default(),
+            linear: LinearToolConfig::default(),
+            slack: SlackToolConfig::default(),
+            notion: NotionToolConfig::default(),
+            http_request: HttpRequestToolConfig::default(),
+            web_fetch: WebFetchToolConfig::default(),
+            scheduler: SchedulerToolConfig::default(),
+            notes: OptInToolConfig::default(),
+            send_email: SendEmailToolConfig::default(),
+            zalo_media: SimpleToolConfig::default(),
+            unknown: Default::default(),
+        }
+    }
+}
+
+/// Tool with no settings beyond on/off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimpleToolConfig {
+    #[serde(default = "bool_true")]
+    pub enabled: bool,
+}
+
+impl Default for SimpleToolConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Tool with no settings beyond on/off that stays off until enabled, for
+/// tools that run code or change files.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OptInToolConfig {
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// Shell tool configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShellToolConfig {
+    #[serde(default = "bool_true")]
+    pub enabled: bool,
+    /// Working directory when a call doesn't specify one.
+    #[serde(default)]
+    pub workdir: Option<String>,
+}
+
+impl Default for ShellToolConfig {
+    fn default() -> Self {
+        Self { enabled: true, workdir: None }
+    }
+}
+
+/// Web search tool configuration.
+///
+/// Searches `backend`; if it fails (error, rate limit, bad response), each
+/// `[[tools.web_search.fallbacks]]` entry is tried in order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSearchToolConfig {
+    #[serde(default = "bool_true")]
+    pub enabled: bool,
+    /// "duckduckgo" (no key), "brave", "searxng" or "serpapi".
+    #[serde(default = "default_search_backend")]
+    pub backend: String,
+    /// API key for Brave Search or SerpAPI.
+    #[serde(default)]
+    pub api_key: String,
+    /// Instance URL for SearxNG; overrides the API endpoint for the others.
+    #[serde(default)]
+    pub base_url: String,
+    /// Results returned when a call doesn't ask for a number.
+    #[serde(default = "default_search_max_results")]
+    pub max_results: usize,
+    #[serde(default = "bool_true")]
+    pub safe_search: bool,
+    /// Language/region of results, e.g. "vi-VN". Empty leaves it to the backend.
+    #[serde(default)]
+    pub locale: String,
+    #[serde(default = "default_search_timeout")]
+    pub timeout_secs: u64,
+    #[serde(default)]
+    pub fallbacks: Vec<WebSearchBackendConfig>,
+}
+
+/// A search backend tried when the ones before it fail.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebSearchBackendConfig {
+    pub backend: String,
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub base_url: String,
+}
+
+fn default_search_backend() -> String { "duckduckgo".into() }
+fn default_search_max_results() -> usize { 5 }
+fn default_search_timeout() -> u64 { 10 }
+
+impl Default for WebSearchToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            backend: default_search_backend(),
+            api_key: String::new(),
+            base_url: String::new(),
+            max_results: default_search_max_results(),
+            safe_search: true,
+            locale: String::new(),
+            timeout_secs: default_search_timeout(),
+            fallbacks: Vec::new(),
+        }
+    }
+}
+
+impl WebSearchToolConfig {
+    /// The primary backend followed by the fallbacks.
+    pub fn backends(&self) -> Vec<WebSearchBackendConfig> {
+        let primary = WebSearchBackendConfig {
+            backend: self.backend.clone(),
+            api_key: self.api_key.clone(),
+            base_url: self.base_url.clone(),
+        };
+        std::iter::once(primary).chain(self.fallbacks.iter().cloned()).collect()
+    }
+
+    /// Check every backend is known and has the settings it needs.
+    pub fn validate(&self) -> Result<()> {
+        let err = |msg: String| Err(crate::error::BizClawError::Config(format!("tools.web_search: {msg}")));
+        for b in self.backends() {
+            match b.backend.as_str() {
+                "duckduckgo" => {}
+                "brave" | "serpapi" if b.api_key.is_empty() => {
+                    return err(format!("backend \"{}\" needs an api_key", b.backend));
+                }
+                "brave" | "serpapi" => {}
+                "searxng" if b.base_url.is_empty() => {
+                    return err("backend \"searxng\" needs the base_url of the instance".into());
+                }
+                "searxng" => {}
+                other => {
+                    return err(format!(
+                        "unknown backend \"{other}\" (expected \"duckduckgo\", \"brave\", \"searxng\" or \"serpapi\")"
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// HTTP request tool configuration; off by default.
+///
+/// Private, loopback, and link-local addresses are refused unless the host is
+/// listed explicitly in `allowed_hosts`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpRequestToolConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// If non-empty, only these hosts (exact, or `*.example.com`) may be called.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+    /// Hosts that are always refused (same patterns as `allowed_hosts`).
+    #[serde(default)]
+    pub denied_hosts: Vec<String>,
+    #[serde(default = "default_http_max_response_bytes")]
+    pub max_response_bytes: usize,
+    #[serde(default = "default_http_max_redirects")]
+    pub max_redirects: usize,
+    /// Upper bound for the per-call `timeout` parameter.
+    #[serde(default = "default_http_timeout")]
+    pub timeout_secs: u64,
+}
+
+fn default_http_max_response_bytes() -> usize { 1024 * 1024 }
+fn default_http_max_redirects() -> usize { 5 }
+fn default_http_timeout() -> u64 { 30 }
+
+impl Default for HttpRequestToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_hosts: Vec::new(),
+            denied_hosts: Vec::new(),
+            max_response_bytes: default_http_max_response_bytes(),
+            max_redirects: default_http_max_redirects(),
+            timeout_secs: default_http_timeout(),
+        }
+    }
+}
+
+/// Web fetch tool configuration; off by default.
+///
+/// Uses the host rules from `[tools.http_request]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebFetchToolConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Extracted text beyond this many (estimated) tokens is cut off.
+    #[serde(default = "default_fetch_max_tokens")]
+    pub max_tokens: usize,
+    #[serde(default = "default_fetch_max_download_bytes")]
+    pub max_download_bytes: usize,
+    /// How long a fetched page is reused before downloading it again.
+    #[serde(default = "default_fetch_cache_ttl")]
+    pub cache_ttl_secs: u64,
+    /// Extract text from PDFs instead of only reporting their size.
+    #[serde(default)]
+    pub extract_pdf: bool,
+}
+
+fn default_fetch_max_tokens() -> usize { 4000 }
+fn default_fetch_max_download_bytes() -> usize { 5 * 1024 * 1024 }
+fn default_fetch_cache_ttl() -> u64 { 300 }
+
+impl Default for WebFetchToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_tokens: default_fetch_max_tokens(),
+            max_download_bytes: default_fetch_max_download_bytes(),
+            cache_ttl_secs: default_fetch_cache_ttl(),
+            extract_pdf: false,
+        }
+    }
+}
+
+/// Scheduler tool configuration; off by default. Jobs are stored in
+/// `<data dir>/scheduler.db`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerToolConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// IANA timezone that cron expressions and local times are read in.
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// How often due jobs are checked.
+    #[serde(default = "default_scheduler_tick")]
+    pub tick_secs: u64,
+}
+
+fn default_scheduler_tick() -> u64 { 30 }
+
+impl Default for SchedulerToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timezone: default_timezone(),
+            tick_secs: default_scheduler_tick(),
+        }
+    }
+}
+
+/// Send-email tool configuration. Sends through the `[channel.email]` SMTP
+/// account; off by default.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SendEmailToolConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Recipient domains (`example.com` also covers its subdomains). Unless
+    /// `autonomy.level` is "full", every recipient must be in one of them.
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+    /// Most emails sent in any hour, to stop a runaway or injected loop.
+    #[serde(default = "default_email_max_per_hour")]
+    pub max_per_hour: usize,
+    #[serde(default = "default_email_max_attachment_mb")]
+    pub max_attachment_mb: u64,
+}
+
+fn default_email_max_per_hour() -> usize { 10 }
+fn default_email_max_attachment_mb() -> u64 { 10 }
+
+impl Default for SendEmailToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            allowed_domains: Vec::new(),
+            max_per_hour: default_email_max_per_hour(),
+            max_attachment_mb: default_email_max_attachment_mb(),
+        }
+    }
+}
+
+/// Group summarizer tool configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupSummarizerToolConfig {
+    #[serde(default = "bool_true")]
+    pub enabled: bool,
+    #[serde(default = "default_buffer_window")]
+    pub buffer_window_secs: u64,
+    #[serde(default = "default_summarizer_max_messages")]
+    pub max_messages_per_group: usize,
+    #[serde(default = "default_summary_language")]
+    pub language: String,
+    #[serde(default = "default_summary_style")]
+    pub summary_style: String,
+    #[serde(default = "default_summary_prompt_tokens")]
+    pub max_prompt_tokens: usize,
+    #[serde(default)]
+    pub model: String,
+    /// Summarize groups automatically when their window elapses or they
+    /// reach `summary_after_messages`, instead of only when asked.
+    #[serde(default)]
+    pub auto_summary: bool,
+    /// Buffered messages that trigger a summary before the window elapses.
+    #[serde(default = "default_summary_after_messages")]
+    pub summary_after_messages: usize,
+    /// Send automatic summaries back to the group they summarize.
+    #[serde(default = "bool_true")]
+    pub deliver_to_group: bool,
+    /// Also send every summary to this chat, e.g. `{ channel = "telegram", thread_id = "-100123" }`.
+    #[serde(default)]
+    pub digest: Option<SummaryDigestConfig>,
+}
+
+/// Chat that collects the summaries of all groups.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryDigestConfig {
+    pub channel: String,
+    pub thread_id: String,
+}
+
+fn default_buffer_window() -> u64 { 3600 }
+fn default_summary_after_messages() -> usize { 100 }
+fn default_summarizer_max_messages() -> usize { 200 }
+fn default_summary_language() -> String { "vi".into() }
+fn default_summary_style() -> String { "bullet_points".into() }
+fn default_summary_prompt_tokens() -> usize { 6000 }
+
+impl Default for GroupSummarizerToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            buffer_window_secs: default_buffer_window(),
+            max_messages_per_group: default_summarizer_max_messages(),
+            language: default_summary_language(),
+            summary_style: default_summary_style(),
+            max_prompt_tokens: default_summary_prompt_tokens(),
+            model: String::new(),
+            auto_summary: false,
+            summary_after_messages: default_summary_after_messages(),
+            deliver_to_group: true,
+            digest: None,
+        }
+    }
+}
+
+/// Google Calendar tool configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarToolConfig {
+    #[serde(default = "bool_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub api_key: Option<String>,
+    #[serde(default = "default_calendar_id")]
+    pub calendar_id: String,
+    #[serde(default)]
+    pub access_token: Option<String>,
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Have Google email invitations to attendees of created events.
+    #[serde(default)]
+    pub send_invites: bool,
+}
+
+fn default_calendar_id() -> String { "primary".into() }
+fn default_timezone() -> String { "Asia/Ho_Chi_Minh".into() }
+
+impl Default for CalendarToolConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            api_key: None,
+            calendar_id: default_calendar_id(),
+            access_token: None,
+            timezone: default_timezone(),
+            send_invites: false,
+        }
+    }
+}
+
+/// Jira tool configuration (off until credentials are set).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct JiraToolConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Site URL, e.g. https://yourteam.atlassian.net
+    #[serde(default)]
+    pub base_url: String,
+    /// Account email used with the API token.
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub api_token: String,
+    #[serde(default)]
+    pub project_key: String,
+}
+
+/// Linear tool configuration (off until credentials are set).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LinearToolConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub api_key: String,
+    #[serde(default)]
+    pub team_id: String,
+}
+
+/// Slack tool configuration (off until a bot token is set).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SlackToolConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub bot_token: String,
+    /// Channel used when a call doesn't name one.
+    #[serde(default)]
+    pub default_channel: Option<String>,
+}
+
+/// Notion tool configuration (registered once a token is set).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct NotionToolConfig {
+    /// Internal integration token.
+    #[serde(default)]
+    pub token: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_config() {
+        let config = BizClawConfig::default();
+        assert_eq!(config.default_provider, "openai");
+        assert_eq!(config.default_model, "gpt-4o-mini");
+        assert!((config.default_temperature - 0.7).abs() < 0.01);
+        assert_eq!(config.identity.name, "BizClaw");
+    }
+
+    #[test]
+    fn test_config_from_toml() {
+        let toml_str = r#"
+            default_provider = "ollama"
+            default_model = "llama3.2"
+            default_temperature = 0.5
+
+            [identity]
+            name = "TestBot"
+            persona = "A test assistant"
+            system_prompt = "You are a test bot."
+        "#;
+
+        let config: BizClawConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.default_provider, "ollama");
+        assert_eq!(config.default_model, "llama3.2");
+        assert_eq!(config.identity.name, "TestBot");
+    }
+
+    #[test]
+    fn test_apply_env_overrides() {
+        let mut config = BizClawConfig::default();
+        let vars = [
+            ("BIZCLAW_DEFAULT_PROVIDER", "anthropic"),
+            ("BIZCLAW_GATEWAY_PORT", "8080"),
+            ("BIZCLAW_MEMORY__BACKEND", "none"),
+            ("BIZCLAW_TELEGRAM_BOT_TOKEN", "123:abc"),
+            ("BIZCLAW_TELEGRAM_ENABLED", "true"),
+            ("BIZCLAW_CHANNEL__TELEGRAM__ALLOWED_CHAT_IDS", "1, -2"),
+            ("BIZCLAW_AUTONOMY__ALLOWED_COMMANDS", "ls,git"),
+            ("BIZCLAW_CONFIG", "/etc/bizclaw.toml"),
+            ("OTHER_VAR", "x"),
+        ].map(|(k, v)| (k.to_string(), v.to_string()));
+        let applied = config.apply_env(vars).unwrap();
+
+        assert_eq!(applied.len(), 7);
+        assert_eq!(config.default_provider, "anthropic");
+        assert_eq!(config.gateway.port, 8080);
+        assert_eq!(config.memory.backend, "none");
+        let telegram = config.channel.telegram.as_ref().unwrap();
+        assert!(telegram.enabled);
+        assert_eq!(telegram.bot_token, "123:abc");
+        assert_eq!(telegram.allowed_chat_ids, vec![1, -2]);
+        assert_eq!(config.autonomy.allowed_commands, vec!["ls", "git"]);
+        assert!(config.channel.discord.is_none());
+
+        let bad = [("BIZCLAW_GATEWAY__PORT".to_string(), "eighty".to_string())];
+        assert!(BizClawConfig::default().apply_env(bad).is_err());
+    }
+
+    #[test]
+    fn test_load_with_env() {
+        let path = std::env::temp_dir().join(format!("bizclaw-env-{}.toml", uuid::Uuid::new_v4().simple()));
+        std::fs::write(&path, "default_model = \"from-file\"\napi_key = \"file-key\"\n").unwrap();
+        // SAFETY: this test is the only reader or writer of this variable.
+        unsafe { std::env::set_var("BIZCLAW_API_KEY", "env-key") };
+        let config = BizClawConfig::load_with_env(Some(&path));
+        unsafe { std::env::remove_var("BIZCLAW_API_KEY") };
+        std::fs::remove_file(&path).ok();
+
+        let config = config.unwrap();
+        assert_eq!(config.default_model, "from-file");
+        assert_eq!(config.api_key, "env-key");
+    }
+
+    #[test]
+    fn test_env_vars_listing() {
+        let vars = BizClawConfig::default().env_vars();
+        let port = vars.iter().find(|v| v.path == "gateway.port").unwrap();
+        assert_eq!(port.name, "BIZCLAW_GATEWAY__PORT");
+        assert_eq!(port.alias.as_deref(), Some("BIZCLAW_GATEWAY_PORT"));
+        assert_eq!(port.value, "3000");
+        assert!(vars.iter().find(|v| v.path == "api_key").unwrap().secret);
+        assert!(vars.iter().any(|v| v.name == "BIZCLAW_CHANNEL__TELEGRAM__BOT_TOKEN" && v.secret));
+        assert!(!vars.iter().find(|v| v.path == "brain.max_tokens").unwrap().secret);
+    }
+
+    #[test]
+    fn test_config_missing_fields_use_defaults() {
+        let toml_str = "";
         let config: BizClawConfig = toml::from_str(toml_str).unwrap();
         assert_eq!(config.default_provider, "openai");
         assert_eq!(config.gateway.port, 3000);
     }
 
+    #[test]
+    fn test_tools_config_from_toml() {
+        let toml_str = r#"
+            [tools.shell]
+            enabled = false
+
+            [tools.calendar]
+            api_key = "cal-key"
+
+            [tools.teleport]
+            enabled = true
+        "#;
+
+        let config: BizClawConfig = toml::from_str(toml_str).unwrap();
+        assert!(!config.tools.shell.enabled);
+        assert!(config.tools.calendar.enabled);
+        assert_eq!(config.tools.calendar.api_key.as_deref(), Some("cal-key"));
+        assert_eq!(config.tools.calendar.calendar_id, "primary");
+        assert!(config.tools.web_search.enabled);
+        assert!(config.tools.unknown.contains_key("teleport"));
+    }
+
+    #[test]
+    fn test_telegram_mode_validation() {
+        let telegram = |extra: &str| {
+            let toml_str = format!("[channel.telegram]\nenabled = true\nbot_token = \"t\"\n{extra}");
+            toml::from_str::<BizClawConfig>(&toml_str).unwrap().validate()
+        };
+        let webhook = "[channel.telegram.webhook]\nurl = \"https://bot.example.com/tg\"\nsecret_token = \"s3cret\"\n";
+
+        assert!(telegram("").is_ok());
+        assert!(telegram(&format!("mode = \"webhook\"\n{webhook}")).is_ok());
+        let both = telegram(webhook).unwrap_err().to_string();
+        assert!(both.contains("can't both run"), "{both}");
+        assert!(telegram("mode = \"webhook\"\n").is_err());
+        assert!(telegram("mode = \"push\"\n").is_err());
+        assert!(telegram(&format!("mode = \"webhook\"\n{}", webhook.replace("https", "http"))).is_err());
+        assert!(telegram(&format!("mode = \"webhook\"\n{}", webhook.replace("s3cret", "bad secret!"))).is_err());
+        assert!(telegram("parse_mode = \"none\"\n").is_ok());
+        assert!(telegram("parse_mode = \"HTML\"\n").unwrap_err().to_string().contains("parse_mode"));
+    }
+
+    #[test]
+    fn test_whatsapp_validation() {
+        let whatsapp = |body: &str| {
+            toml::from_str::<BizClawConfig>(&format!("[channel.whatsapp]\nenabled = true\n{body}")).unwrap().validate()
+        };
+        let full = "access_token = \"t\"\nphone_number_id = \"1\"\nverify_token = \"v\"\napp_secret = \"s\"\n";
+        assert!(whatsapp(full).is_ok());
+        let err = whatsapp(&full.replace("app_secret = \"s\"\n", "")).unwrap_err().to_string();
+        assert!(err.contains("app_secret is required"), "{err}");
+        assert!(whatsapp(&format!("{full}path = \"hook\"\n")).is_err());
+    }
+
+    #[test]
+    fn test_email_validation() {
+        let email = |body: &str| {
+            toml::from_str::<BizClawConfig>(&format!("[channel.email]\nenabled = true\nemail = \"support@shop.vn\"\n{body}")).unwrap().validate()
+        };
+        assert!(email("password = \"p\"\n").is_ok());
+        assert!(email("").unwrap_err().to_string().contains("password (or [channel.email.oauth2]) is required"));
+        assert!(email("[channel.email.oauth2]\naccess_token = \"ya29\"\n").is_ok());
+        assert!(email("[channel.email.oauth2]\nrefresh_token = \"1//r\"\n").is_err());
+        assert!(email("[channel.email.oauth2]\n").is_err());
+        assert!(email("password = \"p\"\nallowed_senders = [\"@customer.vn\", \"boss@shop.vn\"]\n").is_ok());
+        assert!(email("password = \"p\"\nallowed_senders = [\"customer.vn\"]\n").is_err());
+        assert!(email("password = \"p\"\nsmtp_security = \"ssl\"\n").is_err());
+        assert!(email("password = \"p\"\npoll_interval_secs = 0\n").is_err());
+    }
+
+    #[test]
+    fn test_webhook_validation() {
+        let webhook = |body: &str| {
+            toml::from_str::<BizClawConfig>(&format!("[channel.webhook]\nenabled = true\n{body}")).unwrap().validate()
+        };
+        assert!(webhook("secret = \"s\"\n").is_ok());
+        assert!(webhook("").unwrap_err().to_string().contains("secret is required"));
+        assert!(webhook("secret = \"s\"\noutbound_url = \"crm.local/hook\"\n").is_err());
+        assert!(webhook("secret = \"s\"\nsignature_header = \"X Sig\"\n").is_err());
+        assert!(webhook("secret = \"s\"\n[channel.webhook.fields]\ncontent = \"\"\n").is_err());
+        let endpoint = |name: &str, secret: &str| format!("secret = \"s\"\n[[channel.webhook.endpoints]]\nname = \"{name}\"\nsecret = \"{secret}\"\n");
+        assert!(webhook(&endpoint("crm", "c")).is_ok());
+        assert!(webhook(&endpoint("default", "c")).is_err());
+        assert!(webhook(&endpoint("crm", "")).unwrap_err().to_string().contains("needs a secret"));
+        assert!(webhook(&format!("{}\n[[channel.webhook.endpoints]]\nname = \"crm\"\nsecret = \"d\"\n", endpoint("crm", "c"))).is_err());
+        assert!(webhook("secret = \"s\"\ncallback_url = \"ftp://crm.local\"\n").is_err());
+        assert!(webhook("secret = \"s\"\ninput_transform = \"{message: data.text}\"\n").is_ok());
+        assert!(webhook("secret = \"s\"\noutput_transform = \"{text: \"\n").unwrap_err().to_string().contains("output_transform"));
+
+        let config: BizClawConfig = toml::from_str(
+            "[channel.webhook]\nsecret = \"s\"\n[channel.webhook.fields]\ncontent = \"data.text\"\n\
+             [channel.webhook.outbound_template]\nnote = { body = \"{{content}}\" }\n",
+        ).unwrap();
+        let webhook = config.channel.webhook.unwrap();
+        assert_eq!((webhook.fields.content.as_str(), webhook.fields.sender_id.as_str()), ("data.text", "sender_id"));
+        assert_eq!(webhook.outbound_template, Some(serde_json::json!({ "note": { "body": "{{content}}" } })));
+    }
+
+    #[test]
+    fn test_web_search_backends() {
+        let search = |body: &str| toml::from_str::<BizClawConfig>(&format!("[tools.web_search]\n{body}")).unwrap();
+
+        let config = search(
+            "backend = \"brave\"\napi_key = \"k\"\n\
+             [[tools.web_search.fallbacks]]\nbackend = \"searxng\"\nbase_url = \"http://searx.local\"\n",
+        );
+        assert!(config.validate().is_ok());
+        let backends: Vec<_> = config.tools.web_search.backends().into_iter().map(|b| b.backend).collect();
+        assert_eq!(backends, ["brave", "searxng"]);
+
+        assert!(search("").validate().is_ok());
+        let no_key = search("backend = \"serpapi\"\n").validate().unwrap_err().to_string();
+        assert!(no_key.contains("needs an api_key"), "{no_key}");
+        assert!(search("[[tools.web_search.fallbacks]]\nbackend = \"searxng\"\n").validate().is_err());
+        assert!(search("backend = \"bing\"\n").validate().unwrap_err().to_string().contains("unknown backend"));
+    }
+
+    #[test]
+    fn test_safety_config() {
+        let config: BizClawConfig = toml::from_str(
+            "[safety]\nenabled = true\n\
+             [[safety.rules]]\npattern = '\\d{9,12}'\naction = \"redact\"\ncategory = \"phone\"\n",
+        ).unwrap();
+        assert!(config.validate().is_ok());
+        assert_eq!(config.safety.rules[0].action, crate::safety::SafetyAction::Redact);
+        assert_eq!(config.safety.block_message, "Sorry, I can't share that response.");
+
+        let bad = "[safety]\nenabled = true\n[[safety.rules]]\npattern = \"[\"\naction = \"block\"\ncategory = \"x\"\n";
+        assert!(toml::from_str::<BizClawConfig>(bad).unwrap().validate().is_err());
+        assert!(toml::from_str::<BizClawConfig>(&bad.replace("enabled = true", "enabled = false")).unwrap().validate().is_ok());
+        assert!(toml::from_str::<BizClawConfig>(&bad.replace("\"block\"", "\"delete\"")).is_err());
+    }
+
     #[test]
     fn test_home_dir() {
         let home = BizClawConfig::home_dir();
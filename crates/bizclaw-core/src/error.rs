@@ -88,6 +88,9 @@ pub enum BizClawError {
     #[error("Rate limited: {0}")]
     RateLimited(String),
 
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
     #[error("{0}")]
     Other(String),
 }
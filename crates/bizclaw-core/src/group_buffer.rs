@@ -0,0 +1,121 @@
+//! Group chat messages kept for the group summarizer.
+//!
+//! Channels push messages from the groups they monitor; the summarizer tool
+//! and the scheduled digest drain them. All of them share one buffer.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// A message from a group chat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferedMessage {
+    pub sender_name: String,
+    pub content: String,
+    pub timestamp: DateTime<Utc>,
+    pub group_id: String,
+    pub group_name: String,
+    /// Channel the message arrived on (e.g. "telegram"); summaries of the
+    /// group are sent back through it.
+    #[serde(default)]
+    pub channel: String,
+}
+
+impl BufferedMessage {
+    /// Buffer entry for a group message received on a channel.
+    pub fn from_incoming(msg: &crate::types::IncomingMessage) -> Self {
+        Self {
+            sender_name: msg.sender_name.clone().unwrap_or_else(|| msg.sender_id.clone()),
+            content: msg.content.clone(),
+            timestamp: msg.timestamp,
+            group_id: msg.thread_id.clone(),
+            group_name: msg.thread_id.clone(),
+            channel: msg.channel.clone(),
+        }
+    }
+}
+
+/// Message buffer — stores messages per group.
+#[derive(Debug, Clone, Default)]
+pub struct MessageBuffer {
+    /// group_id -> Vec<BufferedMessage>
+    groups: Arc<Mutex<HashMap<String, Vec<BufferedMessage>>>>,
+}
+
+impl MessageBuffer {
+    pub fn new() -> Self {
+        Self {
+            groups: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Process-wide buffer shared by the channels, the tool and the summary runner.
+    pub fn global() -> Self {
+        static GLOBAL: std::sync::OnceLock<MessageBuffer> = std::sync::OnceLock::new();
+        GLOBAL.get_or_init(Self::new).clone()
+    }
+
+    /// Add a message to the buffer.
+    pub fn push(&self, msg: BufferedMessage) {
+        let mut groups = self.groups.lock().unwrap();
+        groups.entry(msg.group_id.clone())
+            .or_default()
+            .push(msg);
+    }
+
+    /// Get and clear messages for a specific group.
+    pub fn drain_group(&self, group_id: &str) -> Vec<BufferedMessage> {
+        let mut groups = self.groups.lock().unwrap();
+        groups.remove(group_id).unwrap_or_default()
+    }
+
+    /// Put drained messages back in front of any that arrived since.
+    pub fn requeue(&self, group_id: &str, mut messages: Vec<BufferedMessage>) {
+        if messages.is_empty() {
+            return;
+        }
+        let mut groups = self.groups.lock().unwrap();
+        let newer = groups.remove(group_id).unwrap_or_default();
+        messages.extend(newer);
+        groups.insert(group_id.to_string(), messages);
+    }
+
+    /// Time of the oldest buffered message in a group.
+    pub fn oldest(&self, group_id: &str) -> Option<DateTime<Utc>> {
+        self.groups.lock().unwrap()
+            .get(group_id)
+            .and_then(|v| v.iter().map(|m| m.timestamp).min())
+    }
+
+    /// Get all group IDs with buffered messages.
+    pub fn group_ids(&self) -> Vec<String> {
+        self.groups.lock().unwrap().keys().cloned().collect()
+    }
+
+    /// Get message count for a group.
+    pub fn count(&self, group_id: &str) -> usize {
+        self.groups.lock().unwrap()
+            .get(group_id)
+            .map(|v| v.len())
+            .unwrap_or(0)
+    }
+
+    /// Get total message count across all groups.
+    pub fn total_count(&self) -> usize {
+        self.groups.lock().unwrap()
+            .values()
+            .map(|v| v.len())
+            .sum()
+    }
+
+    /// Prune old messages beyond the buffer window.
+    pub fn prune(&self, max_age_secs: u64) {
+        let cutoff = Utc::now() - chrono::Duration::seconds(max_age_secs as i64);
+        let mut groups = self.groups.lock().unwrap();
+        for messages in groups.values_mut() {
+            messages.retain(|m| m.timestamp > cutoff);
+        }
+        groups.retain(|_, v| !v.is_empty());
+    }
+}
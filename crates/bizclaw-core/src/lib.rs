@@ -5,6 +5,10 @@
 
 pub mod config;
 pub mod error;
+pub mod group_buffer;
+pub mod quota;
+pub mod safety;
+pub mod tokens;
 pub mod traits;
 pub mod types;
 
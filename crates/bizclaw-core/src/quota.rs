@@ -0,0 +1,51 @@
+//! Daily message quotas.
+//!
+//! The platform keeps each tenant's per-day message count; the gateway
+//! checks the quota before answering a user message and counts it after.
+
+use serde::Serialize;
+
+use crate::error::Result;
+
+/// A tenant's usage of today's quota.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Remaining {
+    /// Messages counted today.
+    pub used: u32,
+    /// Messages allowed per day; 0 means unlimited.
+    pub limit: u32,
+}
+
+impl Remaining {
+    /// Messages left today, `None` when unlimited.
+    pub fn remaining(&self) -> Option<u32> {
+        (self.limit > 0).then(|| self.limit.saturating_sub(self.used))
+    }
+
+    pub fn exceeded(&self) -> bool {
+        self.remaining() == Some(0)
+    }
+}
+
+/// Where a tenant's daily message quota is kept, such as the platform
+/// database.
+pub trait MessageQuota: Send + Sync {
+    /// Today's usage, or `BizClawError::QuotaExceeded` once the limit is reached.
+    fn check_quota(&self) -> Result<Remaining>;
+    /// Count one user message against today's quota.
+    fn record_message(&self) -> Result<()>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remaining() {
+        assert_eq!(Remaining { used: 3, limit: 100 }.remaining(), Some(97));
+        assert!(Remaining { used: 100, limit: 100 }.exceeded());
+        assert!(Remaining { used: 120, limit: 100 }.exceeded());
+        let unlimited = Remaining { used: 5000, limit: 0 };
+        assert_eq!((unlimited.remaining(), unlimited.exceeded()), (None, false));
+    }
+}
@@ -0,0 +1,137 @@
+//! Response safety filtering (`[safety]`).
+//!
+//! Each rule is a regex with an action: `block` replaces the whole response
+//! with the configured block message, `redact` replaces the matched text, and
+//! `warn` only logs. Rules run in order over the (possibly redacted) response.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::config::SafetyConfig;
+use crate::error::{BizClawError, Result};
+
+/// What a rule does when its pattern matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SafetyAction {
+    Block,
+    Redact,
+    Warn,
+}
+
+/// Replaces text matched by a `redact` rule.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// A compiled rule.
+#[derive(Debug, Clone)]
+pub struct SafetyRule {
+    pub pattern: Regex,
+    pub action: SafetyAction,
+    /// Reported in `SafetyResult::triggered_rules` and the logs.
+    pub category: String,
+}
+
+/// The outcome of checking one response.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SafetyResult {
+    /// False when a `block` rule matched.
+    pub passed: bool,
+    /// Categories of the rules that matched, in rule order.
+    pub triggered_rules: Vec<String>,
+    /// The response to send: redacted, or the block message.
+    pub cleaned_response: String,
+}
+
+/// Checks responses against the configured rules.
+#[derive(Debug, Clone)]
+pub struct SafetyFilter {
+    pub rules: Vec<SafetyRule>,
+    pub block_message: String,
+}
+
+impl SafetyFilter {
+    /// Compile the rules in `config`, naming the first invalid pattern.
+    pub fn from_config(config: &SafetyConfig) -> Result<Self> {
+        let rules = config.rules.iter()
+            .map(|rule| {
+                let pattern = Regex::new(&rule.pattern).map_err(|e| BizClawError::Config(format!(
+                    "safety: rule '{}' has an invalid pattern: {e}", rule.category
+                )))?;
+                Ok(SafetyRule { pattern, action: rule.action, category: rule.category.clone() })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { rules, block_message: config.block_message.clone() })
+    }
+
+    /// Apply every rule in order.
+    pub fn check(&self, response: &str) -> SafetyResult {
+        let mut cleaned = response.to_string();
+        let mut triggered_rules = Vec::new();
+        let mut blocked = false;
+        for rule in &self.rules {
+            if !rule.pattern.is_match(&cleaned) {
+                continue;
+            }
+            triggered_rules.push(rule.category.clone());
+            match rule.action {
+                SafetyAction::Block => blocked = true,
+                SafetyAction::Redact => cleaned = rule.pattern.replace_all(&cleaned, REDACTED).into_owned(),
+                SafetyAction::Warn => tracing::warn!("Safety rule '{}' matched a response", rule.category),
+            }
+        }
+        if blocked {
+            tracing::warn!("Response blocked by safety rules: {}", triggered_rules.join(", "));
+            cleaned = self.block_message.clone();
+        }
+        SafetyResult { passed: !blocked, triggered_rules, cleaned_response: cleaned }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SafetyRuleConfig;
+
+    fn rule(pattern: &str, action: SafetyAction, category: &str) -> SafetyRuleConfig {
+        SafetyRuleConfig { pattern: pattern.into(), action, category: category.into() }
+    }
+
+    fn filter(rules: Vec<SafetyRuleConfig>) -> SafetyFilter {
+        SafetyFilter::from_config(&SafetyConfig { enabled: true, rules, ..Default::default() }).unwrap()
+    }
+
+    #[test]
+    fn test_redact_and_warn_pass() {
+        let filter = filter(vec![
+            rule(r"\b\d{4}-\d{4}-\d{4}-\d{4}\b", SafetyAction::Redact, "card_number"),
+            rule(r"(?i)competitor", SafetyAction::Warn, "competitor"),
+            rule(r"(?i)password", SafetyAction::Block, "secrets"),
+        ]);
+        let result = filter.check("Card 4111-1111-1111-1111 is cheaper than Competitor's");
+        assert!(result.passed);
+        assert_eq!(result.triggered_rules, ["card_number", "competitor"]);
+        assert_eq!(result.cleaned_response, "Card [REDACTED] is cheaper than Competitor's");
+
+        let clean = filter.check("Xin chào!");
+        assert_eq!((clean.passed, clean.triggered_rules.len(), clean.cleaned_response.as_str()), (true, 0, "Xin chào!"));
+    }
+
+    #[test]
+    fn test_block_replaces_response() {
+        let filter = filter(vec![
+            rule(r"(?i)password:\s*\S+", SafetyAction::Block, "secrets"),
+            rule(r"\d+", SafetyAction::Redact, "numbers"),
+        ]);
+        let result = filter.check("The admin password: hunter2 for server 42");
+        assert!(!result.passed);
+        assert_eq!(result.triggered_rules, ["secrets", "numbers"]);
+        assert_eq!(result.cleaned_response, SafetyConfig::default().block_message);
+    }
+
+    #[test]
+    fn test_invalid_pattern_names_rule() {
+        let config = SafetyConfig { enabled: true, rules: vec![rule("(unclosed", SafetyAction::Block, "broken")], ..Default::default() };
+        let err = SafetyFilter::from_config(&config).unwrap_err().to_string();
+        assert!(err.contains("'broken'"), "{err}");
+    }
+}
@@ -0,0 +1,244 @@
+//! Local token counting for context-budget management.
+//!
+//! OpenAI-family models are counted exactly with their tiktoken BPE encoding.
+//! Every other model falls back to a heuristic: ~4 characters per token for
+//! Latin text, one token per CJK character.
+
+use tiktoken_rs::CoreBPE;
+use tiktoken_rs::tokenizer::{Tokenizer, get_tokenizer};
+
+use crate::types::{Message, ModelInfo, Role};
+
+/// Tokens added per message for role/formatting (OpenAI chat format).
+const TOKENS_PER_MESSAGE: usize = 3;
+/// Extra token when a message carries a `name`.
+const TOKENS_PER_NAME: usize = 1;
+/// Every reply is primed with `<|start|>assistant<|message|>`.
+const REPLY_PRIMING_TOKENS: usize = 3;
+/// Context window assumed when the provider does not report one for the model.
+pub const DEFAULT_CONTEXT_LENGTH: usize = 4096;
+
+/// How to shrink a conversation that exceeds its token budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TruncationStrategy {
+    /// Drop the oldest non-system messages until the conversation fits.
+    #[default]
+    DropOldest,
+    /// Drop the oldest messages and leave a marker noting how many were removed,
+    /// so the model knows earlier context existed.
+    SummarizeOldest,
+}
+
+/// Look up the exact BPE encoding for a model, if it is an OpenAI-family model.
+fn bpe_for_model(model: &str) -> Option<&'static CoreBPE> {
+    // Strip provider routing prefixes like "openai/gpt-4o" (OpenRouter).
+    let model = model.rsplit('/').next().unwrap_or(model);
+    let bpe = match get_tokenizer(model)? {
+        Tokenizer::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        Tokenizer::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+        Tokenizer::P50kBase => tiktoken_rs::p50k_base_singleton(),
+        Tokenizer::P50kEdit => tiktoken_rs::p50k_edit_singleton(),
+        Tokenizer::R50kBase | Tokenizer::Gpt2 => tiktoken_rs::r50k_base_singleton(),
+    };
+    Some(bpe)
+}
+
+/// Whether a character belongs to a CJK script (roughly one token each).
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF     // Hiragana, Katakana
+        | 0x3400..=0x4DBF   // CJK Extension A
+        | 0x4E00..=0x9FFF   // CJK Unified Ideographs
+        | 0xAC00..=0xD7AF   // Hangul syllables
+        | 0xF900..=0xFAFF   // CJK Compatibility Ideographs
+        | 0x20000..=0x2FFFF // CJK Extensions B+
+    )
+}
+
+/// Heuristic token estimate: chars/4 for most text, one token per CJK character.
+pub fn estimate_tokens(text: &str) -> usize {
+    let mut cjk = 0usize;
+    let mut other = 0usize;
+    for c in text.chars() {
+        if is_cjk(c) { cjk += 1; } else { other += 1; }
+    }
+    cjk + other.div_ceil(4)
+}
+
+/// Count tokens in a piece of text for the given model.
+pub fn count_tokens(model: &str, text: &str) -> usize {
+    match bpe_for_model(model) {
+        Some(bpe) => bpe.encode_ordinary(text).len(),
+        None => estimate_tokens(text),
+    }
+}
+
+/// Count the tokens a single message occupies, including per-message overhead.
+fn message_tokens(model: &str, message: &Message) -> usize {
+    let mut total = TOKENS_PER_MESSAGE
+        + count_tokens(model, &message.role.to_string())
+        + count_tokens(model, &message.content);
+    if let Some(name) = &message.name {
+        total += TOKENS_PER_NAME + count_tokens(model, name);
+    }
+    if let Some(tool_calls) = &message.tool_calls {
+        for tc in tool_calls {
+            total += count_tokens(model, &tc.function.name)
+                + count_tokens(model, &tc.function.arguments);
+        }
+    }
+    total
+}
+
+/// Count the tokens a conversation occupies when sent to `model`.
+pub fn count_message_tokens(model: &str, messages: &[Message]) -> usize {
+    messages.iter().map(|m| message_tokens(model, m)).sum::<usize>() + REPLY_PRIMING_TOKENS
+}
+
+/// `model`'s context window as listed in `models`, or
+/// [`DEFAULT_CONTEXT_LENGTH`] when it isn't.
+pub fn context_length(models: &[ModelInfo], model: &str) -> usize {
+    models.iter()
+        .find(|m| m.id == model && m.context_length > 0)
+        .map_or(DEFAULT_CONTEXT_LENGTH, |m| m.context_length as usize)
+}
+
+/// Token budget for the prompt — the context window minus room for the reply.
+pub fn prompt_budget(context_length: usize) -> usize {
+    context_length - (context_length / 4).min(4096)
+}
+
+/// Trim a conversation so it fits within `budget` tokens.
+///
+/// Leading system messages and the latest message are always kept; the oldest
+/// messages in between are removed first. If even the kept messages exceed the
+/// budget they are returned as-is — the caller decides whether to reject.
+pub fn truncate_to_budget(
+    model: &str,
+    messages: Vec<Message>,
+    budget: usize,
+    strategy: TruncationStrategy,
+) -> Vec<Message> {
+    if count_message_tokens(model, &messages) <= budget {
+        return messages;
+    }
+
+    let n_system = messages.iter().take_while(|m| m.role == Role::System).count();
+    let mut messages = messages;
+    let rest = messages.split_off(n_system);
+    let system = messages;
+
+    let fixed: usize = system.iter().map(|m| message_tokens(model, m)).sum::<usize>()
+        + REPLY_PRIMING_TOKENS;
+
+    // Walk backwards from the newest message, keeping as many as fit.
+    let mut kept_tokens = 0usize;
+    let mut keep_from = rest.len();
+    for (i, m) in rest.iter().enumerate().rev() {
+        let t = message_tokens(model, m);
+        let marker_reserve = match strategy {
+            TruncationStrategy::SummarizeOldest if i > 0 => {
+                message_tokens(model, &omitted_marker(i))
+            }
+            _ => 0,
+        };
+        let is_latest = i + 1 == rest.len();
+        if !is_latest && fixed + kept_tokens + t + marker_reserve > budget {
+            break;
+        }
+        kept_tokens += t;
+        keep_from = i;
+    }
+
+    let dropped = keep_from;
+    let mut out = system;
+    if dropped > 0 && strategy == TruncationStrategy::SummarizeOldest {
+        out.push(omitted_marker(dropped));
+    }
+    out.extend(rest.into_iter().skip(keep_from));
+    out
+}
+
+/// Marker inserted in place of messages removed by `SummarizeOldest`.
+fn omitted_marker(dropped: usize) -> Message {
+    Message::system(format!(
+        "[Earlier conversation truncated: {dropped} older message(s) omitted to fit the context window]"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bpe_count_openai() {
+        // "hello world" is two tokens in both cl100k_base and o200k_base.
+        assert_eq!(count_tokens("gpt-4", "hello world"), 2);
+        assert_eq!(count_tokens("gpt-4o-mini", "hello world"), 2);
+        assert_eq!(count_tokens("openai/gpt-4o", "hello world"), 2);
+    }
+
+    #[test]
+    fn test_heuristic_cjk() {
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert_eq!(estimate_tokens("你好世界"), 4);
+        assert_eq!(count_tokens("llama3.2", "abcd你好"), 3);
+    }
+
+    #[test]
+    fn test_message_overhead() {
+        let msgs = vec![Message::user("hello world")];
+        // 3 per message + "user" (1) + content (2) + 3 reply priming
+        assert_eq!(count_message_tokens("gpt-4", &msgs), 9);
+    }
+
+    #[test]
+    fn test_truncate_drop_oldest_keeps_system_and_latest() {
+        let mut msgs = vec![Message::system("sys")];
+        for i in 0..50 {
+            msgs.push(Message::user(format!("message number {i} with some padding text")));
+        }
+        let budget = 120;
+        let out = truncate_to_budget("gpt-4", msgs, budget, TruncationStrategy::DropOldest);
+
+        assert_eq!(out[0].role, Role::System);
+        assert_eq!(out[0].content, "sys");
+        assert!(out.last().unwrap().content.starts_with("message number 49"));
+        assert!(count_message_tokens("gpt-4", &out) <= budget);
+        assert!(out.len() < 51);
+    }
+
+    #[test]
+    fn test_context_length_and_budget() {
+        let model = |id: &str, context_length| ModelInfo {
+            id: id.into(), name: id.into(), provider: "openai".into(), context_length, max_output_tokens: None,
+        };
+        let models = [model("gpt-4o", 128_000), model("unsized", 0)];
+        assert_eq!(context_length(&models, "gpt-4o"), 128_000);
+        assert_eq!(context_length(&models, "unsized"), DEFAULT_CONTEXT_LENGTH);
+        assert_eq!(context_length(&models, "other"), DEFAULT_CONTEXT_LENGTH);
+        // A quarter is left for the reply, at most 4096 tokens.
+        assert_eq!(prompt_budget(4096), 3072);
+        assert_eq!(prompt_budget(128_000), 128_000 - 4096);
+    }
+
+    #[test]
+    fn test_truncate_summarize_inserts_marker() {
+        let mut msgs = vec![Message::system("sys")];
+        for i in 0..20 {
+            msgs.push(Message::user(format!("xin chào lần thứ {i}")));
+        }
+        let budget = 100;
+        let out = truncate_to_budget("qwen3", msgs, budget, TruncationStrategy::SummarizeOldest);
+
+        assert!(out[1].content.starts_with("[Earlier conversation truncated"));
+        assert!(count_message_tokens("qwen3", &out) <= budget);
+    }
+
+    #[test]
+    fn test_truncate_noop_when_within_budget() {
+        let msgs = vec![Message::system("sys"), Message::user("hi")];
+        let out = truncate_to_budget("gpt-4", msgs, 1000, TruncationStrategy::SummarizeOldest);
+        assert_eq!(out.len(), 2);
+    }
+}
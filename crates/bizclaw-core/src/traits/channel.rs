@@ -1,10 +1,16 @@
 //! Communication Channel trait — swappable messaging interfaces.
 
+use std::path::Path;
+use std::sync::Arc;
+
 use async_trait::async_trait;
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedSender;
 use tokio_stream::Stream;
 
 use crate::error::Result;
-use crate::types::{IncomingMessage, OutgoingMessage};
+use crate::traits::provider::TokenStream;
+use crate::types::{IncomingMessage, OutgoingMessage, ThreadType};
 
 /// Channel trait — every communication interface implements this.
 #[async_trait]
@@ -25,12 +31,113 @@ pub trait Channel: Send + Sync {
     /// Returns a stream of incoming messages.
     async fn listen(&self) -> Result<Box<dyn Stream<Item = IncomingMessage> + Send + Unpin>>;
 
+    /// Start receiving messages and hand them to `agent_tx` until it is
+    /// closed. The default forwards [`Channel::listen`].
+    async fn start(&mut self, agent_tx: UnboundedSender<IncomingMessage>) -> Result<()> {
+        forward(self.listen().await?, agent_tx);
+        Ok(())
+    }
+
+    /// How the channel is doing, for status pages.
+    fn health(&self) -> ChannelHealth {
+        if self.is_connected() {
+            ChannelHealth::Healthy
+        } else {
+            ChannelHealth::Down("not connected".into())
+        }
+    }
+
+    /// What to call with a received message that is dropped before it
+    /// reaches the agent, e.g. as a repeat. Channels that hold something for
+    /// each message, such as a request waiting for its reply, release it
+    /// there. None by default.
+    fn on_dropped(&self) -> Option<DroppedMessage> {
+        None
+    }
+
     /// Send a message to a thread.
     async fn send(&self, message: OutgoingMessage) -> Result<()>;
 
+    /// Send a response while it is being generated.
+    ///
+    /// Channels that can edit sent messages show the text as it arrives; the
+    /// default waits for the whole response and sends it with `send`.
+    async fn send_streaming(&self, thread_id: &str, thread_type: ThreadType, mut tokens: TokenStream) -> Result<()> {
+        use tokio_stream::StreamExt;
+
+        let mut content = String::new();
+        while let Some(token) = tokens.next().await {
+            content.push_str(&token?);
+        }
+        self.send(OutgoingMessage {
+            thread_id: thread_id.to_string(),
+            content,
+            thread_type,
+            reply_to: None,
+        }).await
+    }
+
+    /// Send the file at `path` to a thread, images as pictures and other
+    /// files as attachments. Channels that can't send files fail by default.
+    async fn send_media(&self, thread_id: &str, thread_type: ThreadType, path: &Path) -> Result<()> {
+        let _ = (thread_id, thread_type, path);
+        Err(crate::error::BizClawError::Channel(format!("{} cannot send files", self.name())))
+    }
+
     /// Send a typing indicator.
     async fn send_typing(&self, thread_id: &str) -> Result<()> {
         let _ = thread_id;
         Ok(()) // Default no-op
     }
+
+    /// Drop the connection and connect again now, skipping any backoff.
+    /// Channels that can't fail by default.
+    fn force_reconnect(&self) -> Result<()> {
+        Err(crate::error::BizClawError::Channel(format!("{} cannot be reconnected on demand", self.name())))
+    }
+
+    /// Show what the agent is doing on a reply to `thread_id`. The default
+    /// sends a typing indicator.
+    async fn send_progress(&self, thread_id: &str, progress: &AgentProgress) -> Result<()> {
+        let _ = progress;
+        self.send_typing(thread_id).await
+    }
+}
+
+/// Called by [`Channel::on_dropped`] wrappers with each message they drop.
+pub type DroppedMessage = Arc<dyn Fn(&IncomingMessage) + Send + Sync>;
+
+/// A step of the agent's turn, reported while a reply is in flight.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AgentProgress {
+    /// Waiting on the model.
+    Thinking,
+    /// Running a tool.
+    ToolCall { name: String },
+}
+
+/// State reported by [`Channel::health`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", content = "detail", rename_all = "snake_case")]
+pub enum ChannelHealth {
+    Healthy,
+    /// Not receiving or sending, with why.
+    Down(String),
+}
+
+/// Hand every message of `messages` to `agent_tx`, in the background,
+/// until either ends.
+pub fn forward(
+    mut messages: impl Stream<Item = IncomingMessage> + Send + Unpin + 'static,
+    agent_tx: UnboundedSender<IncomingMessage>,
+) {
+    use tokio_stream::StreamExt;
+
+    tokio::spawn(async move {
+        while let Some(message) = messages.next().await {
+            if agent_tx.send(message).is_err() {
+                break;
+            }
+        }
+    });
 }
@@ -12,7 +12,7 @@ pub mod security;
 pub mod tool;
 pub mod tunnel;
 
-pub use channel::Channel;
+pub use channel::{AgentProgress, Channel, ChannelHealth, DroppedMessage};
 pub use memory::MemoryBackend;
 pub use provider::Provider;
 pub use security::SecurityPolicy;
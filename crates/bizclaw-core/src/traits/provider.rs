@@ -1,6 +1,8 @@
 //! LLM Provider trait — swappable AI backends.
 
 use async_trait::async_trait;
+use std::pin::Pin;
+use tokio_stream::Stream;
 
 use crate::error::Result;
 use crate::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
@@ -27,6 +29,9 @@ impl Default for GenerateParams {
     }
 }
 
+/// Text of a response as it is generated, one chunk per item.
+pub type TokenStream = Pin<Box<dyn Stream<Item = Result<String>> + Send>>;
+
 /// Provider trait — every LLM backend implements this.
 #[async_trait]
 pub trait Provider: Send + Sync {
@@ -41,9 +46,39 @@ pub trait Provider: Send + Sync {
         params: &GenerateParams,
     ) -> Result<ProviderResponse>;
 
+    /// Stream a chat completion (without tools) as it is generated.
+    ///
+    /// Providers without a streaming API yield the whole response as one chunk.
+    async fn chat_stream(
+        &self,
+        messages: &[Message],
+        params: &GenerateParams,
+    ) -> Result<TokenStream> {
+        let response = self.chat(messages, &[], params).await?;
+        let content = response.content.unwrap_or_default();
+        Ok(Box::pin(tokio_stream::once(Ok(content))))
+    }
+
     /// List available models for this provider.
     async fn list_models(&self) -> Result<Vec<ModelInfo>>;
 
     /// Check if the provider is available and configured.
     async fn health_check(&self) -> Result<bool>;
+
+    /// Check that the API key is accepted, with an authenticated request to
+    /// the provider. A refused key is `AuthFailed`. Providers without keys
+    /// (local models) fall back to `health_check`.
+    async fn verify_key(&self) -> Result<()> {
+        if self.health_check().await? {
+            Ok(())
+        } else {
+            Err(crate::error::BizClawError::AuthFailed(format!("{} is not configured", self.name())))
+        }
+    }
+
+    /// Whether this provider passes tool definitions through to the model.
+    fn supports_tools(&self) -> bool { false }
+
+    /// Whether this provider sends `Message::images` to the model.
+    fn supports_vision(&self) -> bool { false }
 }
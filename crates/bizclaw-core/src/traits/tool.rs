@@ -16,4 +16,11 @@ pub trait Tool: Send + Sync {
 
     /// Execute the tool with given arguments.
     async fn execute(&self, arguments: &str) -> Result<ToolResult>;
+
+    /// Whether calls may overlap with other calls to this tool. Tools that
+    /// share state between calls (the shell's working directory) say no, and
+    /// a batch runs their calls one after another.
+    fn concurrency_safe(&self) -> bool {
+        true
+    }
 }
@@ -34,6 +34,19 @@ pub struct Message {
     pub tool_call_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<super::ToolCall>>,
+    /// Images sent with a user message, for providers that take them
+    /// (`Provider::supports_vision`). Each provider puts them on the wire
+    /// in its own format.
+    #[serde(skip)]
+    pub images: Vec<ImagePart>,
+}
+
+/// An image attached to a message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImagePart {
+    /// e.g. "image/jpeg"
+    pub mime_type: String,
+    pub data: Vec<u8>,
 }
 
 impl Message {
@@ -44,6 +57,7 @@ impl Message {
             name: None,
             tool_call_id: None,
             tool_calls: None,
+            images: Vec::new(),
         }
     }
 
@@ -54,6 +68,7 @@ impl Message {
             name: None,
             tool_call_id: None,
             tool_calls: None,
+            images: Vec::new(),
         }
     }
 
@@ -64,6 +79,7 @@ impl Message {
             name: None,
             tool_call_id: None,
             tool_calls: None,
+            images: Vec::new(),
         }
     }
 
@@ -74,8 +90,14 @@ impl Message {
             name: None,
             tool_call_id: Some(tool_call_id.into()),
             tool_calls: None,
+            images: Vec::new(),
         }
     }
+
+    pub fn with_images(mut self, images: Vec<ImagePart>) -> Self {
+        self.images = images;
+        self
+    }
 }
 
 /// Incoming message from a channel.
@@ -89,6 +111,45 @@ pub struct IncomingMessage {
     pub thread_type: ThreadType,
     pub timestamp: chrono::DateTime<chrono::Utc>,
     pub reply_to: Option<String>,
+    /// Files that came with the message, saved locally by the channel.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attachments: Vec<Attachment>,
+    /// The channel's own id for the message, when it has a stable one;
+    /// a redelivery carries the same id.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_id: Option<String>,
+}
+
+/// A file received with a message.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Attachment {
+    pub kind: AttachmentKind,
+    /// Where the channel saved it.
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mime_type: Option<String>,
+    /// Name the sender gave the file, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_name: Option<String>,
+}
+
+/// What an attachment is.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentKind {
+    Image,
+    Document,
+    Voice,
+}
+
+impl std::fmt::Display for AttachmentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachmentKind::Image => write!(f, "image"),
+            AttachmentKind::Document => write!(f, "document"),
+            AttachmentKind::Voice => write!(f, "voice message"),
+        }
+    }
 }
 
 /// Outgoing message to a channel.
@@ -115,6 +176,9 @@ pub struct ProviderResponse {
     pub tool_calls: Vec<super::ToolCall>,
     pub finish_reason: Option<String>,
     pub usage: Option<Usage>,
+    /// Provider-specific extras (e.g. which backend won a race).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
 }
 
 impl ProviderResponse {
@@ -124,6 +188,7 @@ impl ProviderResponse {
             tool_calls: vec![],
             finish_reason: Some("stop".into()),
             usage: None,
+            metadata: None,
         }
     }
 
@@ -133,6 +198,7 @@ impl ProviderResponse {
             tool_calls,
             finish_reason: Some("tool_calls".into()),
             usage: None,
+            metadata: None,
         }
     }
 }
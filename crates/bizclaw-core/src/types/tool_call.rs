@@ -26,9 +26,67 @@ pub struct ToolDefinition {
 }
 
 /// Result of tool execution.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// `output` is the text the model sees; the other fields are for callers
+/// that want more than text (the gateway, scheduled jobs) and are left out
+/// of the serialized form when unset, so older readers see the same JSON.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ToolResult {
     pub tool_call_id: String,
     pub output: String,
     pub success: bool,
+    /// Machine-readable payload, e.g. the events a calendar query returned.
+    #[serde(default, skip_serializing_if = "serde_json::Value::is_null")]
+    pub data: serde_json::Value,
+    /// `output` was cut to the registry's size limit.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Wall time of the call, set by the tool registry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    /// Why a failed call failed: `permission_denied`, `approval_denied`,
+    /// `timeout`, `exit_status`, `http_status`, ...
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+}
+
+impl ToolResult {
+    /// A successful result with only text output.
+    pub fn ok(output: impl Into<String>) -> Self {
+        Self { output: output.into(), success: true, ..Default::default() }
+    }
+
+    /// A failed result, tagged with the kind of failure.
+    pub fn failure(kind: &str, output: impl Into<String>) -> Self {
+        Self { output: output.into(), error_kind: Some(kind.to_string()), ..Default::default() }
+    }
+
+    /// Attach a machine-readable payload.
+    pub fn with_data(mut self, data: serde_json::Value) -> Self {
+        self.data = data;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_result_serialization_compatible() {
+        let plain = ToolResult::ok("done");
+        assert_eq!(
+            serde_json::to_value(&plain).unwrap(),
+            serde_json::json!({"tool_call_id": "", "output": "done", "success": true})
+        );
+        let old: ToolResult = serde_json::from_str(r#"{"tool_call_id":"1","output":"x","success":false}"#).unwrap();
+        assert!(old.data.is_null() && !old.truncated && old.error_kind.is_none());
+
+        let full = ToolResult { truncated: true, duration_ms: Some(12), ..ToolResult::failure("timeout", "slow") }
+            .with_data(serde_json::json!({"n": 1}));
+        let json = serde_json::to_value(&full).unwrap();
+        assert_eq!(json["error_kind"], "timeout");
+        assert_eq!(json["data"]["n"], 1);
+        assert_eq!(serde_json::from_value::<ToolResult>(json).unwrap(), full);
+    }
 }
@@ -0,0 +1,187 @@
+//! Live events for dashboards, so they don't have to poll.
+//!
+//! Events are published on `AppState::events` and reach every WebSocket
+//! client and every `GET /api/v1/events` stream (Server-Sent Events, one
+//! `data: <json>` per event, optionally narrowed with
+//! `?filter=alert_fired,channel_connected`).
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::extract::{Query, State};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use bizclaw_core::traits::ChannelHealth;
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use crate::metrics::MetricsSnapshot;
+use crate::server::AppState;
+
+/// Events kept for a subscriber that is behind; older ones are skipped.
+pub const EVENT_CAPACITY: usize = 256;
+/// Quiet streams get a `ping` event this often, to keep proxies from
+/// closing them.
+pub const PING_INTERVAL: Duration = Duration::from_secs(30);
+/// How often metrics are published and channels checked.
+pub const MONITOR_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Something that happened, for dashboards.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PlatformEvent {
+    /// The tenant this gateway serves is, for example, `stopping`.
+    TenantStatusChanged { tenant_id: String, status: String },
+    MetricUpdate(MetricsSnapshot),
+    /// Something needs an operator, such as a used up message quota.
+    AlertFired { alert: String, message: String },
+    ChannelConnected { channel: String },
+    ChannelDisconnected { channel: String, reason: String },
+    /// An action that is also written to the audit log.
+    AuditEvent { event: String, details: String },
+}
+
+/// The `type` of each event, for `?filter=`.
+pub const EVENT_TYPES: [&str; 6] = [
+    "tenant_status_changed",
+    "metric_update",
+    "alert_fired",
+    "channel_connected",
+    "channel_disconnected",
+    "audit_event",
+];
+
+impl PlatformEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::TenantStatusChanged { .. } => "tenant_status_changed",
+            Self::MetricUpdate(_) => "metric_update",
+            Self::AlertFired { .. } => "alert_fired",
+            Self::ChannelConnected { .. } => "channel_connected",
+            Self::ChannelDisconnected { .. } => "channel_disconnected",
+            Self::AuditEvent { .. } => "audit_event",
+        }
+    }
+
+    pub fn audit(event: &str, details: impl Into<String>) -> Self {
+        Self::AuditEvent { event: event.to_string(), details: details.into() }
+    }
+}
+
+/// The sender for `AppState::events`.
+pub fn channel() -> broadcast::Sender<PlatformEvent> {
+    broadcast::channel(EVENT_CAPACITY).0
+}
+
+/// `GET /api/v1/events`: the event stream, until the server shuts down.
+/// 400 for a `filter` naming an unknown event type.
+pub async fn events(
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<HashMap<String, String>>,
+) -> Response {
+    let filter: Option<HashSet<String>> = params.get("filter")
+        .map(|f| f.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect());
+    if let Some(unknown) = filter.iter().flatten().find(|t| !EVENT_TYPES.contains(&t.as_str())) {
+        return (
+            axum::http::StatusCode::BAD_REQUEST,
+            axum::Json(serde_json::json!({"ok": false, "error": format!("Unknown event type '{unknown}'"), "types": EVENT_TYPES})),
+        ).into_response();
+    }
+
+    let rx = state.events.subscribe();
+    let shutdown = state.shutdown.clone();
+    let stream = futures::stream::unfold((rx, filter), move |(mut rx, filter)| {
+        let shutdown = shutdown.clone();
+        async move {
+            loop {
+                let event = tokio::select! {
+                    event = rx.recv() => event,
+                    _ = shutdown.cancelled() => return None,
+                };
+                match event {
+                    Ok(event) if filter.as_ref().is_none_or(|f| f.contains(event.kind())) => {
+                        let Ok(sse) = Event::default().event(event.kind()).json_data(&event) else { continue };
+                        return Some((Ok::<_, Infallible>(sse), (rx, filter)));
+                    }
+                    Ok(_) => continue,
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        tracing::debug!("Event stream fell behind; skipped {skipped} event(s)");
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }
+    });
+    let ping = Event::default().event("ping").data(r#"{"type":"ping"}"#);
+    Sse::new(stream).keep_alive(KeepAlive::new().interval(PING_INTERVAL).event(ping)).into_response()
+}
+
+/// Publish metrics every [`MONITOR_INTERVAL`] while anyone is listening, and
+/// channels connecting and disconnecting, until shutdown.
+pub fn spawn_monitor(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut connected = HashMap::new();
+        let mut interval = tokio::time::interval(MONITOR_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = state.shutdown.cancelled() => return,
+            }
+            if state.events.receiver_count() > 0 {
+                state.publish_event(PlatformEvent::MetricUpdate(state.metrics_snapshot()));
+            }
+            if let Some(channels) = &state.channels {
+                for event in channel_changes(&mut connected, channels.health()) {
+                    state.publish_event(event);
+                }
+            }
+        }
+    });
+}
+
+/// Events for channels whose health changed since `connected` was last
+/// updated; the first time a channel is seen only a disconnect is news.
+fn channel_changes(connected: &mut HashMap<String, bool>, health: Vec<(String, ChannelHealth)>) -> Vec<PlatformEvent> {
+    let mut events = Vec::new();
+    for (channel, health) in health {
+        let up = health == ChannelHealth::Healthy;
+        let was = connected.insert(channel.clone(), up);
+        if was == Some(up) || (was.is_none() && up) {
+            continue;
+        }
+        events.push(match health {
+            ChannelHealth::Healthy => PlatformEvent::ChannelConnected { channel },
+            ChannelHealth::Down(reason) => PlatformEvent::ChannelDisconnected { channel, reason },
+        });
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_changes() {
+        let mut connected = HashMap::new();
+        let down = |reason: &str| ChannelHealth::Down(reason.into());
+        let events = channel_changes(&mut connected, vec![("telegram".into(), ChannelHealth::Healthy), ("zalo".into(), down("cookie expired"))]);
+        assert_eq!(events, [PlatformEvent::ChannelDisconnected { channel: "zalo".into(), reason: "cookie expired".into() }]);
+        assert!(channel_changes(&mut connected, vec![("telegram".into(), ChannelHealth::Healthy), ("zalo".into(), down("still"))]).is_empty());
+
+        let events = channel_changes(&mut connected, vec![("telegram".into(), down("409 Conflict")), ("zalo".into(), ChannelHealth::Healthy)]);
+        assert_eq!(events.iter().map(PlatformEvent::kind).collect::<Vec<_>>(), ["channel_disconnected", "channel_connected"]);
+    }
+
+    #[test]
+    fn test_event_json() {
+        let event = PlatformEvent::audit("api_key_rotated", "provider=openai");
+        assert_eq!(serde_json::to_value(&event).unwrap(), serde_json::json!({
+            "type": "audit_event", "event": "api_key_rotated", "details": "provider=openai",
+        }));
+        assert!(EVENT_TYPES.contains(&event.kind()));
+    }
+}
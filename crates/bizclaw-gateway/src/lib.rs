@@ -4,11 +4,21 @@
 pub mod server;
 pub mod routes;
 pub mod ws;
+pub mod protocol;
 pub mod dashboard;
+pub mod metrics;
+pub mod uploads;
+pub mod rate_limit;
+pub mod events;
 
 use bizclaw_core::config::GatewayConfig;
 
-/// Start the gateway HTTP server.
-pub async fn start_server(config: &GatewayConfig) -> anyhow::Result<()> {
-    server::start(config).await
+/// Start the gateway HTTP server, enforcing `quota` on chat messages when
+/// given, with `channels` running alongside it.
+pub async fn start_server(
+    config: &GatewayConfig,
+    quota: Option<std::sync::Arc<dyn bizclaw_core::quota::MessageQuota>>,
+    channels: Option<std::sync::Arc<bizclaw_channels::manager::ChannelManager>>,
+) -> anyhow::Result<()> {
+    server::start(config, quota, channels).await
 }
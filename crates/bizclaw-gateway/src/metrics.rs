@@ -0,0 +1,134 @@
+//! Gateway metrics — request and provider counters for `/api/v1/metrics`.
+//!
+//! Served as JSON by default, or in the Prometheus text exposition format
+//! when the client sends `Accept: text/plain`.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Call and error counts for one provider.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProviderStats {
+    pub calls: u64,
+    pub errors: u64,
+}
+
+/// Live counters shared through `AppState`.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    requests_total: AtomicU64,
+    providers: Mutex<BTreeMap<String, ProviderStats>>,
+}
+
+/// Point-in-time copy of the metrics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub requests_total: u64,
+    pub uptime_secs: u64,
+    pub providers: BTreeMap<String, ProviderStats>,
+    /// Resident set size of the gateway process, where the OS reports it.
+    pub memory_rss_bytes: Option<u64>,
+    /// Inbound channel messages dropped because they were delivered again.
+    #[serde(default)]
+    pub inbound_duplicates_total: u64,
+}
+
+impl Metrics {
+    /// Count one HTTP request.
+    pub fn record_request(&self) {
+        self.requests_total.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count one provider call and whether it failed.
+    pub fn record_provider_call(&self, provider: &str, ok: bool) {
+        let mut providers = self.providers.lock().unwrap();
+        let stats = providers.entry(provider.to_string()).or_default();
+        stats.calls += 1;
+        if !ok {
+            stats.errors += 1;
+        }
+    }
+
+    pub fn snapshot(&self, uptime: std::time::Duration) -> MetricsSnapshot {
+        MetricsSnapshot {
+            requests_total: self.requests_total.load(Ordering::Relaxed),
+            uptime_secs: uptime.as_secs(),
+            providers: self.providers.lock().unwrap().clone(),
+            memory_rss_bytes: process_rss_bytes(),
+            inbound_duplicates_total: 0,
+        }
+    }
+}
+
+impl MetricsSnapshot {
+    /// Render in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# HELP bizclaw_requests_total HTTP requests handled by the gateway.");
+        let _ = writeln!(out, "# TYPE bizclaw_requests_total counter");
+        let _ = writeln!(out, "bizclaw_requests_total {}", self.requests_total);
+
+        let _ = writeln!(out, "# HELP bizclaw_uptime_seconds Seconds since the gateway started.");
+        let _ = writeln!(out, "# TYPE bizclaw_uptime_seconds gauge");
+        let _ = writeln!(out, "bizclaw_uptime_seconds {}", self.uptime_secs);
+
+        let _ = writeln!(out, "# HELP bizclaw_provider_calls_total LLM provider calls.");
+        let _ = writeln!(out, "# TYPE bizclaw_provider_calls_total counter");
+        for (name, stats) in &self.providers {
+            let _ = writeln!(out, "bizclaw_provider_calls_total{{provider=\"{name}\"}} {}", stats.calls);
+        }
+        let _ = writeln!(out, "# HELP bizclaw_provider_errors_total Failed LLM provider calls.");
+        let _ = writeln!(out, "# TYPE bizclaw_provider_errors_total counter");
+        for (name, stats) in &self.providers {
+            let _ = writeln!(out, "bizclaw_provider_errors_total{{provider=\"{name}\"}} {}", stats.errors);
+        }
+
+        let _ = writeln!(out, "# HELP bizclaw_inbound_duplicates_total Inbound channel messages dropped as redeliveries.");
+        let _ = writeln!(out, "# TYPE bizclaw_inbound_duplicates_total counter");
+        let _ = writeln!(out, "bizclaw_inbound_duplicates_total {}", self.inbound_duplicates_total);
+
+        if let Some(rss) = self.memory_rss_bytes {
+            let _ = writeln!(out, "# HELP bizclaw_memory_rss_bytes Resident memory of the gateway process.");
+            let _ = writeln!(out, "# TYPE bizclaw_memory_rss_bytes gauge");
+            let _ = writeln!(out, "bizclaw_memory_rss_bytes {rss}");
+        }
+        out
+    }
+}
+
+/// Resident set size from `/proc/self/status` (Linux only).
+fn process_rss_bytes() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmRSS:"))?;
+    let kb: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counters_and_prometheus() {
+        let metrics = Metrics::default();
+        metrics.record_request();
+        metrics.record_request();
+        metrics.record_provider_call("openai", true);
+        metrics.record_provider_call("openai", false);
+        metrics.record_provider_call("ollama", true);
+
+        let snap = metrics.snapshot(std::time::Duration::from_secs(42));
+        assert_eq!(snap.requests_total, 2);
+        assert_eq!(snap.providers["openai"], ProviderStats { calls: 2, errors: 1 });
+
+        let text = snap.to_prometheus();
+        assert!(text.contains("bizclaw_requests_total 2\n"));
+        assert!(text.contains("bizclaw_uptime_seconds 42\n"));
+        assert!(text.contains("bizclaw_provider_errors_total{provider=\"openai\"} 1\n"));
+        assert!(text.contains("bizclaw_provider_calls_total{provider=\"ollama\"} 1\n"));
+        assert!(text.contains("bizclaw_inbound_duplicates_total 0\n"));
+    }
+}
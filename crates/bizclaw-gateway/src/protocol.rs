@@ -0,0 +1,230 @@
+//! WebSocket message protocol for `/ws`.
+//!
+//! Every frame is a JSON text message `{"type": "...", "payload": {...}}`;
+//! `payload` may be omitted when it has no fields.
+//!
+//! Client → server commands:
+//! - `auth` `{"code": "..."}` must be the first message when the gateway
+//!   requires pairing and the code wasn't in the handshake (`X-Pairing-Code`
+//!   header or `bizclaw-pairing.<code>` subprotocol). Without it the socket is
+//!   closed with code 1008 (policy violation) after a few seconds.
+//! - `chat` `{"content": "...", "request_id"?: "...", "stream"?: true, "file_ids"?: ["..."]}`
+//!   starts a response. One response runs at a time per connection.
+//! - `cancel` `{"request_id"?: "..."}` stops the response in progress.
+//! - `ping`, `status`
+//!
+//! Server → client events:
+//! - `connected` once, on connect (after `auth`, if one is needed)
+//! - `token` `{"request_id", "content", "index"}` for each piece of a streamed response
+//! - `tool_call` `{"request_id", "name", "arguments"}` when the model calls a tool
+//! - `tool_result` `{"request_id", "name", "tool_call_id", "output", "success",
+//!   "data"?, "truncated"?, "duration_ms"?, "error_kind"?}` when that call returns
+//! - `done` `{"request_id", "content", "tokens", "cancelled"}` with the full text
+//!   (partial if cancelled); always the last event of a response
+//! - `error` `{"request_id"?, "message"}`; with a `request_id` it ends that response
+//! - `config_reloaded`, `pong`, `status`
+
+use serde::{Deserialize, Serialize};
+
+/// Command sent by the client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum ClientCommand {
+    Auth(AuthCommand),
+    Chat(ChatCommand),
+    Cancel(Option<CancelCommand>),
+    Ping,
+    Status,
+}
+
+/// Prove the pairing code for this connection.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuthCommand {
+    pub code: String,
+}
+
+impl std::fmt::Debug for AuthCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthCommand").field("code", &"***").finish()
+    }
+}
+
+/// Start a response to `content`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatCommand {
+    pub content: String,
+    /// Echoed in the response's events; assigned by the server if omitted.
+    #[serde(default)]
+    pub request_id: Option<String>,
+    /// Send `token` events as the response is generated; otherwise only `done`.
+    #[serde(default = "default_stream")]
+    pub stream: bool,
+    /// Files from `/api/v1/upload` to reference in the prompt.
+    #[serde(default)]
+    pub file_ids: Vec<String>,
+}
+
+fn default_stream() -> bool { true }
+
+/// Stop the response in progress, if its id matches (or no id is given).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CancelCommand {
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+/// Event sent by the server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "payload", rename_all = "snake_case")]
+pub enum ServerEvent {
+    Connected(Connected),
+    Token(Token),
+    ToolCall(ToolCall),
+    ToolResult(ToolResult),
+    Done(Done),
+    Error(Error),
+    ConfigReloaded(ConfigReloaded),
+    Pong(Pong),
+    Status(Status),
+    /// A live event; see [`crate::events`].
+    Event(crate::events::PlatformEvent),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Connected {
+    pub version: String,
+    pub provider: String,
+    pub model: String,
+    pub commands: Vec<String>,
+}
+
+/// A piece of a streamed response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Token {
+    pub request_id: String,
+    pub content: String,
+    /// Position of this piece in the response, from 0.
+    pub index: u64,
+}
+
+/// The model called a tool while producing the response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub request_id: String,
+    pub name: String,
+    /// JSON-encoded arguments, as produced by the model.
+    pub arguments: String,
+}
+
+/// A tool the model called returned. Carries the whole result, structured
+/// fields included, not just the text the model sees.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolResult {
+    pub request_id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub result: bizclaw_core::types::ToolResult,
+}
+
+/// A response finished or was cancelled.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Done {
+    pub request_id: String,
+    pub content: String,
+    /// Number of `token` pieces the response arrived in.
+    pub tokens: u64,
+    pub cancelled: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Error {
+    /// Set when the error ended a response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConfigReloaded {
+    pub provider: String,
+    pub model: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pong {
+    /// Server time, Unix milliseconds.
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Status {
+    pub requests_processed: u64,
+    pub uptime_secs: u64,
+    pub provider: String,
+    pub model: String,
+    /// Id of the response in progress, if any.
+    pub active_request: Option<String>,
+}
+
+impl ServerEvent {
+    /// An error not tied to a response.
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::Error(Error { request_id: None, message: message.into() })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_round_trip() {
+        let cmd: ClientCommand = serde_json::from_str(
+            r#"{"type":"chat","payload":{"content":"Xin chào","file_ids":["f1"]}}"#,
+        ).unwrap();
+        assert_eq!(cmd, ClientCommand::Chat(ChatCommand {
+            content: "Xin chào".into(),
+            request_id: None,
+            stream: true,
+            file_ids: vec!["f1".into()],
+        }));
+        // Payload-less commands.
+        assert_eq!(serde_json::from_str::<ClientCommand>(r#"{"type":"ping"}"#).unwrap(), ClientCommand::Ping);
+        assert_eq!(
+            serde_json::from_str::<ClientCommand>(r#"{"type":"cancel"}"#).unwrap(),
+            ClientCommand::Cancel(None),
+        );
+        assert_eq!(
+            serde_json::from_str::<ClientCommand>(r#"{"type":"cancel","payload":{"request_id":"r1"}}"#).unwrap(),
+            ClientCommand::Cancel(Some(CancelCommand { request_id: Some("r1".into()) })),
+        );
+        assert!(serde_json::from_str::<ClientCommand>(r#"{"type":"launch"}"#).is_err());
+        let auth = serde_json::from_str::<ClientCommand>(r#"{"type":"auth","payload":{"code":"123456"}}"#).unwrap();
+        assert_eq!(auth, ClientCommand::Auth(AuthCommand { code: "123456".into() }));
+        assert!(!format!("{auth:?}").contains("123456"));
+
+        let event = ServerEvent::Token(Token { request_id: "req_1".into(), content: "Hi".into(), index: 0 });
+        assert_eq!(
+            serde_json::to_value(&event).unwrap(),
+            serde_json::json!({"type": "token", "payload": {"request_id": "req_1", "content": "Hi", "index": 0}}),
+        );
+        let event = ServerEvent::ToolResult(ToolResult {
+            request_id: "req_1".into(),
+            name: "shell".into(),
+            result: bizclaw_core::types::ToolResult {
+                duration_ms: Some(5),
+                ..bizclaw_core::types::ToolResult::ok("hi").with_data(serde_json::json!({"exit_code": 0}))
+            },
+        });
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json, serde_json::json!({"type": "tool_result", "payload": {
+            "request_id": "req_1", "name": "shell", "tool_call_id": "", "output": "hi", "success": true,
+            "data": {"exit_code": 0}, "duration_ms": 5,
+        }}));
+        assert_eq!(serde_json::from_value::<ServerEvent>(json).unwrap(), event);
+        assert_eq!(
+            serde_json::to_value(ServerEvent::error("bad")).unwrap(),
+            serde_json::json!({"type": "error", "payload": {"message": "bad"}}),
+        );
+    }
+}
@@ -0,0 +1,224 @@
+//! Per-client-IP rate limiting and pairing-code lockout.
+//!
+//! Every request draws from a token bucket keyed by the client IP; the
+//! public pairing endpoint has a much smaller bucket of its own. Wrong
+//! pairing codes are counted separately, and after too many the client is
+//! refused outright until the lockout expires, so a 6-digit code can't be
+//! brute-forced at the request rate.
+
+use bizclaw_core::config::GatewayRateLimitConfig;
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Client IP from the connection; unspecified when the server wasn't started
+/// with connect info (e.g. in tests), so such requests share one bucket.
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub IpAddr);
+
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for ClientIp {
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut axum::http::request::Parts, _: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::from_extensions(&parts.extensions))
+    }
+}
+
+impl ClientIp {
+    pub fn from_extensions(extensions: &axum::http::Extensions) -> Self {
+        Self(extensions.get::<axum::extract::ConnectInfo<SocketAddr>>()
+            .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |info| info.0.ip()))
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per IP: `capacity` requests at once, refilled at `per_minute`.
+pub struct RateLimiter {
+    per_sec: f64,
+    capacity: f64,
+    buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+impl RateLimiter {
+    /// `None` when `per_minute` is 0 (no limit).
+    pub fn new(per_minute: u32, burst: u32) -> Option<Self> {
+        (per_minute > 0).then(|| Self {
+            per_sec: per_minute as f64 / 60.0,
+            capacity: burst.max(1) as f64,
+            buckets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Take a token for `ip`, or return how long until one is available.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(ip).or_insert(Bucket { tokens: self.capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.per_sec).min(self.capacity);
+        bucket.updated = now;
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.per_sec))
+        }
+    }
+
+    /// Forget buckets that have refilled completely.
+    fn prune(&self, now: Instant) {
+        self.buckets.lock().unwrap().retain(|_, b| {
+            b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * self.per_sec < self.capacity
+        });
+    }
+}
+
+struct Failures {
+    count: u32,
+    first: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Locks an IP out after `max_failures` wrong pairing codes within `lockout`.
+pub struct PairingLockout {
+    max_failures: u32,
+    lockout: Duration,
+    failures: Mutex<HashMap<IpAddr, Failures>>,
+}
+
+impl PairingLockout {
+    /// `None` when `max_failures` is 0 (no lockout).
+    pub fn new(max_failures: u32, lockout: Duration) -> Option<Self> {
+        (max_failures > 0).then(|| Self { max_failures, lockout, failures: Mutex::new(HashMap::new()) })
+    }
+
+    /// `Err(remaining)` while `ip` is locked out.
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        self.check_at(ip, Instant::now())
+    }
+
+    fn check_at(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        match self.failures.lock().unwrap().get(&ip).and_then(|f| f.locked_until) {
+            Some(until) if until > now => Err(until - now),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count a wrong code; returns true if this one triggered the lockout.
+    pub fn record_failure(&self, ip: IpAddr) -> bool {
+        self.record_failure_at(ip, Instant::now())
+    }
+
+    fn record_failure_at(&self, ip: IpAddr, now: Instant) -> bool {
+        let mut failures = self.failures.lock().unwrap();
+        let entry = failures.entry(ip).or_insert(Failures { count: 0, first: now, locked_until: None });
+        // Failures spread over more than a lockout period start a new count.
+        if now.saturating_duration_since(entry.first) > self.lockout {
+            *entry = Failures { count: 0, first: now, locked_until: None };
+        }
+        entry.count += 1;
+        if entry.count >= self.max_failures {
+            entry.locked_until = Some(now + self.lockout);
+            entry.count = 0;
+            entry.first = now;
+            return true;
+        }
+        false
+    }
+
+    /// A correct code clears the count.
+    pub fn record_success(&self, ip: IpAddr) {
+        self.failures.lock().unwrap().remove(&ip);
+    }
+
+    fn prune(&self, now: Instant) {
+        self.failures.lock().unwrap().retain(|_, f| {
+            f.locked_until.is_some_and(|until| until > now) || now.saturating_duration_since(f.first) <= self.lockout
+        });
+    }
+}
+
+/// The gateway's limiters, built from `gateway.rate_limit`.
+#[derive(Default)]
+pub struct RateLimits {
+    pub requests: Option<RateLimiter>,
+    pub pairing: Option<RateLimiter>,
+    pub lockout: Option<PairingLockout>,
+}
+
+impl RateLimits {
+    pub fn from_config(config: &GatewayRateLimitConfig) -> Self {
+        Self {
+            requests: RateLimiter::new(config.requests_per_minute, config.burst),
+            pairing: RateLimiter::new(config.pairing_per_minute, config.pairing_per_minute),
+            lockout: PairingLockout::new(config.pairing_max_failures, Duration::from_secs(config.pairing_lockout_secs)),
+        }
+    }
+
+    /// Drop state for idle clients.
+    pub fn prune(&self) {
+        let now = Instant::now();
+        for limiter in [&self.requests, &self.pairing].into_iter().flatten() {
+            limiter.prune(now);
+        }
+        if let Some(lockout) = &self.lockout {
+            lockout.prune(now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+    const B: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(60, 3).unwrap();
+        let t0 = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check_at(A, t0).is_ok());
+        }
+        let wait = limiter.check_at(A, t0).unwrap_err();
+        assert_eq!(wait.as_secs_f64().ceil(), 1.0);
+        // Other clients have their own bucket.
+        assert!(limiter.check_at(B, t0).is_ok());
+
+        assert!(limiter.check_at(A, t0 + Duration::from_millis(1100)).is_ok());
+        assert!(limiter.check_at(A, t0 + Duration::from_millis(1100)).is_err());
+
+        limiter.prune(t0 + Duration::from_secs(10));
+        assert!(limiter.buckets.lock().unwrap().is_empty());
+        assert!(RateLimiter::new(0, 10).is_none());
+    }
+
+    #[test]
+    fn test_pairing_lockout() {
+        let lockout = PairingLockout::new(3, Duration::from_secs(60)).unwrap();
+        let t0 = Instant::now();
+        assert!(!lockout.record_failure_at(A, t0));
+        assert!(!lockout.record_failure_at(A, t0));
+        assert!(lockout.record_failure_at(A, t0));
+        assert!(lockout.check_at(A, t0 + Duration::from_secs(30)).is_err());
+        assert!(lockout.check_at(B, t0).is_ok());
+        assert!(lockout.check_at(A, t0 + Duration::from_secs(61)).is_ok());
+
+        // Success resets; old failures expire.
+        lockout.record_failure_at(B, t0);
+        lockout.record_failure_at(B, t0);
+        lockout.record_success(B);
+        assert!(!lockout.record_failure_at(B, t0));
+        assert!(!lockout.record_failure_at(B, t0 + Duration::from_secs(120)));
+        assert!(!lockout.record_failure_at(B, t0 + Duration::from_secs(121)));
+    }
+}
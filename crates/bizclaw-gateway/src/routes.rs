@@ -19,7 +19,7 @@ pub async fn system_info(
     State(state): State<Arc<AppState>>,
 ) -> Json<serde_json::Value> {
     let uptime = state.start_time.elapsed();
-    let cfg = state.full_config.lock().unwrap();
+    let cfg = state.full_config.read().await;
     Json(serde_json::json!({
         "name": cfg.identity.name,
         "version": env!("CARGO_PKG_VERSION"),
@@ -35,11 +35,32 @@ pub async fn system_info(
     }))
 }
 
+/// Gateway metrics as JSON, or Prometheus text when `Accept: text/plain`.
+pub async fn metrics(
+    State(state): State<Arc<AppState>>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let snapshot = state.metrics_snapshot();
+    let wants_text = headers.get(axum::http::header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("text/plain"));
+    if wants_text {
+        (
+            [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+            snapshot.to_prometheus(),
+        ).into_response()
+    } else {
+        Json(snapshot).into_response()
+    }
+}
+
 /// Get current configuration (sanitized — no API keys).
 pub async fn get_config(
     State(state): State<Arc<AppState>>,
 ) -> Json<serde_json::Value> {
-    let cfg = state.full_config.lock().unwrap();
+    let cfg = state.full_config.read().await;
     Json(serde_json::json!({
         "default_provider": cfg.default_provider,
         "default_model": cfg.default_model,
@@ -79,6 +100,7 @@ pub async fn get_config(
         },
         "runtime": {
             "kind": cfg.runtime.kind,
+            "code_exec_backend": cfg.runtime.code_exec_backend,
         },
         "tunnel": {
             "provider": cfg.tunnel.provider,
@@ -109,6 +131,34 @@ pub async fn get_config(
                 "bot_token_set": !d.bot_token.is_empty(),
                 "allowed_channel_ids": d.allowed_channel_ids,
             })),
+            "whatsapp": cfg.channel.whatsapp.as_ref().map(|w| serde_json::json!({
+                "enabled": w.enabled,
+                "phone_number_id": w.phone_number_id,
+                "access_token_set": !w.access_token.is_empty(),
+                "app_secret_set": !w.app_secret.is_empty(),
+                "listen": w.listen,
+                "path": w.path,
+                "allowed_numbers": w.allowed_numbers,
+            })),
+            "email": cfg.channel.email.as_ref().map(|e| serde_json::json!({
+                "enabled": e.enabled,
+                "email": e.email,
+                "password_set": !e.password.is_empty(),
+                "imap_host": e.imap_host,
+                "imap_port": e.imap_port,
+                "smtp_host": e.smtp_host,
+                "smtp_port": e.smtp_port,
+                "mailbox": e.mailbox,
+                "poll_interval_secs": e.poll_interval_secs,
+            })),
+            "webhook": cfg.channel.webhook.as_ref().map(|w| serde_json::json!({
+                "enabled": w.enabled,
+                "secret_set": !w.secret.is_empty(),
+                "listen": w.listen,
+                "path": w.path,
+                "outbound_url": w.outbound_url,
+                "fields": w.fields,
+            })),
         },
     }))
 }
@@ -117,7 +167,7 @@ pub async fn get_config(
 pub async fn get_full_config(
     State(state): State<Arc<AppState>>,
 ) -> Json<serde_json::Value> {
-    let cfg = state.full_config.lock().unwrap();
+    let cfg = state.full_config.read().await;
     let toml_str = toml::to_string_pretty(&*cfg).unwrap_or_default();
     Json(serde_json::json!({
         "ok": true,
@@ -126,71 +176,150 @@ pub async fn get_full_config(
     }))
 }
 
-/// Update config fields via JSON body.
-pub async fn update_config(
+/// Re-read the config file and broadcast it to subscribers.
+///
+/// Hot-reloadable: `default_provider`, `default_model`, `default_temperature`,
+/// `api_key`, `identity`, `autonomy`, and `tools` — they are read per request,
+/// and open WebSocket sessions switch provider/model on the reload event.
+/// `gateway`, `channel`, `memory`, and `brain` are captured at startup; changes
+/// to them are saved but reported under `restart_required`.
+pub async fn reload_config(
     State(state): State<Arc<AppState>>,
-    Json(req): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
-    let mut cfg = state.full_config.lock().unwrap();
-
-    // Update top-level fields
-    if let Some(v) = req.get("default_provider").and_then(|v| v.as_str()) {
-        cfg.default_provider = v.to_string();
-    }
-    if let Some(v) = req.get("default_model").and_then(|v| v.as_str()) {
-        cfg.default_model = v.to_string();
+    match reload_from_disk(&state).await {
+        Ok(restart_required) => Json(serde_json::json!({
+            "ok": true,
+            "message": "Config reloaded",
+            "restart_required": restart_required,
+        })),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
     }
-    if let Some(v) = req.get("default_temperature").and_then(|v| v.as_f64()) {
-        cfg.default_temperature = v as f32;
+}
+
+/// Load the config file into `state` and broadcast it; returns the sections
+/// that need a restart. Shared by the reload endpoint and SIGUSR1.
+pub(crate) async fn reload_from_disk(state: &AppState) -> bizclaw_core::error::Result<Vec<&'static str>> {
+    let new_cfg = bizclaw_core::config::BizClawConfig::load_with_env(Some(&state.config_path))?;
+
+    let restart_required = {
+        let mut cfg = state.full_config.write().await;
+        let changed = restart_required_changes(&cfg, &new_cfg);
+        *cfg = new_cfg.clone();
+        changed
+    };
+    state.publish_config(new_cfg);
+    tracing::info!("🔄 Config reloaded from {}", state.config_path.display());
+    Ok(restart_required)
+}
+
+/// Replace the provider API key without a restart.
+///
+/// The new key is checked with an authenticated request to the provider
+/// before anything changes. It is then written to disk and swapped into the shared config,
+/// which requests read per call, so the old key stops being used at once.
+pub async fn rotate_key(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let provider = req["provider"].as_str().unwrap_or("").trim();
+    let new_key = req["new_key"].as_str().unwrap_or("").trim();
+    if provider.is_empty() || new_key.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "'provider' and 'new_key' are required"}));
     }
-    if let Some(v) = req.get("api_key").and_then(|v| v.as_str()) {
-        cfg.api_key = v.to_string();
+
+    let mut candidate = state.full_config.read().await.clone();
+    if candidate.default_provider != provider {
+        return Json(serde_json::json!({
+            "ok": false,
+            "error": format!("'{provider}' is not the active provider ('{}')", candidate.default_provider),
+        }));
     }
+    candidate.api_key = new_key.to_string();
 
-    // Update identity
-    if let Some(id) = req.get("identity") {
-        if let Some(v) = id.get("name").and_then(|v| v.as_str()) {
-            cfg.identity.name = v.to_string();
-        }
-        if let Some(v) = id.get("persona").and_then(|v| v.as_str()) {
-            cfg.identity.persona = v.to_string();
-        }
-        if let Some(v) = id.get("system_prompt").and_then(|v| v.as_str()) {
-            cfg.identity.system_prompt = v.to_string();
+    let verified = match bizclaw_providers::create_provider(&candidate) {
+        Ok(p) => p.verify_key().await,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    match verified {
+        Ok(()) => {}
+        Err(bizclaw_core::error::BizClawError::AuthFailed(_)) => {
+            return Json(serde_json::json!({"ok": false, "error": format!("{provider} rejected the new key")}));
         }
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Could not check the new key: {e}")})),
     }
 
-    // Update memory
-    if let Some(mem) = req.get("memory") {
-        if let Some(v) = mem.get("backend").and_then(|v| v.as_str()) {
-            cfg.memory.backend = v.to_string();
-        }
-        if let Some(v) = mem.get("auto_save").and_then(|v| v.as_bool()) {
-            cfg.memory.auto_save = v;
-        }
+    // Holding the write lock across the save keeps memory and disk in step;
+    // if the save fails, the old key stays in both.
+    let mut cfg = state.full_config.write().await;
+    let mut updated = cfg.clone();
+    updated.api_key = candidate.api_key;
+    let content = toml::to_string_pretty(&updated).unwrap_or_default();
+    let tmp = state.config_path.with_extension("toml.tmp");
+    if let Err(e) = std::fs::write(&tmp, &content).and_then(|_| std::fs::rename(&tmp, &state.config_path)) {
+        std::fs::remove_file(&tmp).ok();
+        return Json(serde_json::json!({"ok": false, "error": format!("Failed to save config: {e}")}));
     }
+    *cfg = updated;
+    state.publish_config(cfg.clone());
+    drop(cfg);
 
-    // Update autonomy
-    if let Some(auto) = req.get("autonomy") {
-        if let Some(v) = auto.get("level").and_then(|v| v.as_str()) {
-            cfg.autonomy.level = v.to_string();
-        }
-        if let Some(v) = auto.get("workspace_only").and_then(|v| v.as_bool()) {
-            cfg.autonomy.workspace_only = v;
-        }
+    tracing::info!(target: "bizclaw::audit", event = "api_key_rotated", actor = "gateway", provider, "API key rotated");
+    state.publish_event(crate::events::PlatformEvent::audit("api_key_rotated", format!("provider={provider}")));
+    if std::env::var_os("BIZCLAW_API_KEY").is_some() {
+        tracing::warn!("BIZCLAW_API_KEY is set and will override the rotated key on the next restart or reload");
     }
 
-    // Update brain
-    if let Some(brain) = req.get("brain") {
-        if let Some(v) = brain.get("enabled").and_then(|v| v.as_bool()) {
-            cfg.brain.enabled = v;
-        }
-        if let Some(v) = brain.get("threads").and_then(|v| v.as_u64()) {
-            cfg.brain.threads = v as u32;
-        }
-        if let Some(v) = brain.get("temperature").and_then(|v| v.as_f64()) {
-            cfg.brain.temperature = v as f32;
+    Json(serde_json::json!({"ok": true, "provider": provider, "healthy": true}))
+}
+
+/// Config sections that changed but only take effect after a restart.
+fn restart_required_changes(
+    old: &bizclaw_core::config::BizClawConfig,
+    new: &bizclaw_core::config::BizClawConfig,
+) -> Vec<&'static str> {
+    let differs = |a: serde_json::Result<serde_json::Value>, b: serde_json::Result<serde_json::Value>| {
+        a.ok() != b.ok()
+    };
+    let mut changed = Vec::new();
+    if differs(serde_json::to_value(&old.gateway), serde_json::to_value(&new.gateway)) {
+        changed.push("gateway");
+    }
+    if differs(serde_json::to_value(&old.channel), serde_json::to_value(&new.channel)) {
+        changed.push("channel");
+    }
+    if differs(serde_json::to_value(&old.memory), serde_json::to_value(&new.memory)) {
+        changed.push("memory");
+    }
+    if differs(serde_json::to_value(&old.brain), serde_json::to_value(&new.brain)) {
+        changed.push("brain");
+    }
+    changed
+}
+
+/// Update config fields via JSON body.
+///
+/// The body is deep-merged into the current config: objects merge key by key,
+/// anything else replaces the existing value. Unknown keys and `null` values
+/// are ignored, so posting back a partial or sanitized config keeps every
+/// field it doesn't mention.
+pub async fn update_config(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let mut cfg = state.full_config.write().await;
+
+    let mut merged = match serde_json::to_value(&*cfg) {
+        Ok(v) => v,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    merge_json(&mut merged, req);
+    match serde_json::from_value::<bizclaw_core::config::BizClawConfig>(merged) {
+        Ok(mut updated) => {
+            // Unknown `[tools.*]` keys would otherwise be captured by the flatten map.
+            updated.tools.unknown = cfg.tools.unknown.clone();
+            *cfg = updated;
         }
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Invalid config: {e}")})),
     }
 
     // Save to disk
@@ -198,12 +327,34 @@ pub async fn update_config(
     match std::fs::write(&state.config_path, &content) {
         Ok(_) => {
             tracing::info!("✅ Config saved to {}", state.config_path.display());
+            state.publish_config(cfg.clone());
             Json(serde_json::json!({"ok": true, "message": "Config saved"}))
         }
         Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
     }
 }
 
+/// Deep-merge `patch` into `target`: objects merge recursively, `null` is skipped.
+fn merge_json(target: &mut serde_json::Value, patch: serde_json::Value) {
+    match (target, patch) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                if value.is_null() {
+                    continue;
+                }
+                match target.get_mut(&key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        target.insert(key, value);
+                    }
+                }
+            }
+        }
+        (_, serde_json::Value::Null) => {}
+        (target, patch) => *target = patch,
+    }
+}
+
 /// Update channel config.
 pub async fn update_channel(
     State(state): State<Arc<AppState>>,
@@ -211,7 +362,7 @@ pub async fn update_channel(
 ) -> Json<serde_json::Value> {
     let channel_type = req.get("channel_type").and_then(|v| v.as_str()).unwrap_or("");
     let enabled = req.get("enabled").and_then(|v| v.as_bool()).unwrap_or(false);
-    let mut cfg = state.full_config.lock().unwrap();
+    let mut cfg = state.full_config.write().await;
 
     match channel_type {
         "telegram" => {
@@ -222,8 +373,19 @@ pub async fn update_channel(
                 .split(',')
                 .filter_map(|s| s.trim().parse().ok())
                 .collect();
+            let existing = cfg.channel.telegram.as_ref();
+            let summarize_groups = existing.map(|t| t.summarize_groups.clone()).unwrap_or_default();
+            let mode = existing.map_or_else(|| "polling".to_string(), |t| t.mode.clone());
+            let webhook = existing.and_then(|t| t.webhook.clone());
+            let parse_mode = existing.map_or_else(|| "MarkdownV2".to_string(), |t| t.parse_mode.clone());
+            let media = existing.map(|t| t.media.clone()).unwrap_or_default();
+            let respond_to_all = existing.is_some_and(|t| t.respond_to_all);
+            let rate_limit = existing.map(|t| t.rate_limit.clone()).unwrap_or_default();
+            let presence = existing.map(|t| t.presence.clone()).unwrap_or_default();
+            let approval_chat_ids = existing.map(|t| t.approval_chat_ids.clone()).unwrap_or_default();
             cfg.channel.telegram = Some(bizclaw_core::config::TelegramChannelConfig {
-                enabled, bot_token: token, allowed_chat_ids: chat_ids,
+                enabled, bot_token: token, allowed_chat_ids: chat_ids, approval_chat_ids, summarize_groups, mode, webhook, parse_mode, media,
+                respond_to_all, rate_limit, presence,
             });
         }
         "zalo" => {
@@ -252,8 +414,47 @@ pub async fn update_channel(
                 .collect();
             cfg.channel.discord = Some(bizclaw_core::config::DiscordChannelConfig {
                 enabled, bot_token: token, allowed_channel_ids: ids,
+                ..cfg.channel.discord.clone().unwrap_or_default()
             });
         }
+        "whatsapp" => {
+            let mut wa_cfg = cfg.channel.whatsapp.clone().unwrap_or_default();
+            wa_cfg.enabled = enabled;
+            let field = |name: &str| req.get(name).and_then(|v| v.as_str()).map(String::from);
+            if let Some(v) = field("access_token") { wa_cfg.access_token = v; }
+            if let Some(v) = field("phone_number_id") { wa_cfg.phone_number_id = v; }
+            if let Some(v) = field("verify_token") { wa_cfg.verify_token = v; }
+            if let Some(v) = field("app_secret") { wa_cfg.app_secret = v; }
+            if let Some(v) = field("allowed_numbers") {
+                wa_cfg.allowed_numbers = v.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect();
+            }
+            cfg.channel.whatsapp = Some(wa_cfg);
+        }
+        "email" => {
+            let mut em_cfg = cfg.channel.email.clone().unwrap_or_default();
+            em_cfg.enabled = enabled;
+            let field = |name: &str| req.get(name).and_then(|v| v.as_str()).map(String::from);
+            if let Some(v) = field("email") { em_cfg.email = v; }
+            if let Some(v) = field("password") { em_cfg.password = v; }
+            if let Some(v) = field("imap_host") { em_cfg.imap_host = v; }
+            if let Some(v) = field("smtp_host") { em_cfg.smtp_host = v; }
+            if let Some(v) = field("mailbox") { em_cfg.mailbox = v; }
+            let port = |name: &str| req.get(name).and_then(|v| v.as_u64()).and_then(|p| u16::try_from(p).ok());
+            if let Some(p) = port("imap_port") { em_cfg.imap_port = p; }
+            if let Some(p) = port("smtp_port") { em_cfg.smtp_port = p; }
+            cfg.channel.email = Some(em_cfg);
+        }
+        "webhook" => {
+            let mut wh_cfg = cfg.channel.webhook.clone().unwrap_or_default();
+            wh_cfg.enabled = enabled;
+            let field = |name: &str| req.get(name).and_then(|v| v.as_str()).map(String::from);
+            if let Some(v) = field("secret") { wh_cfg.secret = v; }
+            if let Some(v) = field("path") { wh_cfg.path = v; }
+            if let Some(v) = field("outbound_url") {
+                wh_cfg.outbound_url = Some(v).filter(|v| !v.is_empty());
+            }
+            cfg.channel.webhook = Some(wh_cfg);
+        }
         _ => {
             return Json(serde_json::json!({"ok": false, "error": format!("Unknown channel: {channel_type}")}));
         }
@@ -262,16 +463,111 @@ pub async fn update_channel(
     // Save to disk
     let content = toml::to_string_pretty(&*cfg).unwrap_or_default();
     match std::fs::write(&state.config_path, &content) {
-        Ok(_) => Json(serde_json::json!({"ok": true, "message": format!("{channel_type} config saved")})),
+        Ok(_) => {
+            state.publish_config(cfg.clone());
+            Json(serde_json::json!({"ok": true, "message": format!("{channel_type} config saved")}))
+        }
         Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
     }
 }
 
+/// Tool calls waiting for approval, oldest first.
+pub async fn list_approvals(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "approvals": state.approvals.pending() }))
+}
+
+/// Approve or deny a pending tool call: `{"decision": "approve"|"deny", "approver": "..."}`.
+///
+/// The approver name is recorded in the audit log (defaults to "dashboard").
+pub async fn resolve_approval(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Json(req): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    let approve = match req["decision"].as_str() {
+        Some("approve") => true,
+        Some("deny") => false,
+        other => {
+            return Json(serde_json::json!({
+                "ok": false,
+                "error": format!("decision must be 'approve' or 'deny', got {other:?}"),
+            }));
+        }
+    };
+    let approver = format!("gateway:{}", req["approver"].as_str().unwrap_or("dashboard"));
+    if state.approvals.resolve(&id, approve, &approver) {
+        Json(serde_json::json!({"ok": true, "id": id, "decision": req["decision"]}))
+    } else {
+        Json(serde_json::json!({"ok": false, "error": format!("No pending approval {id}")}))
+    }
+}
+
+/// Accept a `multipart/form-data` upload with a `file` field and optional `tenant_id`.
+pub async fn upload_file(
+    State(state): State<Arc<AppState>>,
+    mut multipart: axum::extract::Multipart,
+) -> Json<serde_json::Value> {
+    let max_bytes = state.gateway_config.max_upload_mb * 1024 * 1024;
+    let mut tenant_id = None;
+    let mut file: Option<(String, Vec<u8>)> = None;
+
+    loop {
+        let field = match multipart.next_field().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => return Json(serde_json::json!({"ok": false, "error": e.body_text()})),
+        };
+        match field.name() {
+            Some("file") => {
+                let filename = field.file_name().unwrap_or("upload").to_string();
+                match field.bytes().await {
+                    Ok(bytes) => file = Some((filename, bytes.to_vec())),
+                    Err(e) => return Json(serde_json::json!({"ok": false, "error": e.body_text()})),
+                }
+            }
+            Some("tenant_id") => tenant_id = field.text().await.ok().filter(|t| !t.is_empty()),
+            _ => {}
+        }
+    }
+
+    let Some((filename, bytes)) = file else {
+        return Json(serde_json::json!({"ok": false, "error": "Missing 'file' field"}));
+    };
+    if bytes.len() as u64 > max_bytes {
+        return Json(serde_json::json!({
+            "ok": false,
+            "error": format!("File exceeds {} MB limit", state.gateway_config.max_upload_mb),
+        }));
+    }
+    match state.uploads.save(&filename, tenant_id, &bytes).await {
+        Ok(uploaded) => {
+            tracing::info!("📎 Uploaded {} as {}", uploaded.filename, uploaded.path.display());
+            let mut json = serde_json::to_value(&uploaded).unwrap_or_default();
+            json["ok"] = true.into();
+            Json(json)
+        }
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e})),
+    }
+}
+
+/// Delete an uploaded file.
+pub async fn delete_upload(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(file_id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    match state.uploads.remove(&file_id) {
+        Some(_) => Json(serde_json::json!({"ok": true, "file_id": file_id})),
+        None => Json(serde_json::json!({"ok": false, "error": format!("No upload {file_id}")})),
+    }
+}
+
 /// List available providers.
 pub async fn list_providers(
     State(state): State<Arc<AppState>>,
 ) -> Json<serde_json::Value> {
-    let cfg = state.full_config.lock().unwrap();
+    let cfg = state.full_config.read().await;
     let active = &cfg.default_provider;
     Json(serde_json::json!({
         "providers": [
@@ -291,20 +587,381 @@ pub async fn list_providers(
 pub async fn list_channels(
     State(state): State<Arc<AppState>>,
 ) -> Json<serde_json::Value> {
-    let cfg = state.full_config.lock().unwrap();
+    let cfg = state.full_config.read().await;
     Json(serde_json::json!({
         "channels": [
             {"name": "cli", "type": "interactive", "status": "active", "configured": true},
-            {"name": "telegram", "type": "messaging", "status": if cfg.channel.telegram.as_ref().map_or(false, |t| t.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.telegram.is_some()},
-            {"name": "zalo", "type": "messaging", "status": if cfg.channel.zalo.as_ref().map_or(false, |z| z.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.zalo.is_some()},
-            {"name": "discord", "type": "messaging", "status": if cfg.channel.discord.as_ref().map_or(false, |d| d.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.discord.is_some()},
-            {"name": "email", "type": "messaging", "status": "available", "configured": false},
-            {"name": "webhook", "type": "api", "status": "available", "configured": false},
-            {"name": "whatsapp", "type": "messaging", "status": "available", "configured": false},
+            {"name": "telegram", "type": "messaging", "status": if cfg.channel.telegram.as_ref().is_some_and(|t| t.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.telegram.is_some()},
+            {"name": "zalo", "type": "messaging", "status": if cfg.channel.zalo.as_ref().is_some_and(|z| z.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.zalo.is_some()},
+            {"name": "discord", "type": "messaging", "status": if cfg.channel.discord.as_ref().is_some_and(|d| d.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.discord.is_some()},
+            {"name": "email", "type": "messaging", "status": if cfg.channel.email.as_ref().is_some_and(|e| e.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.email.is_some()},
+            {"name": "webhook", "type": "api", "status": if cfg.channel.webhook.as_ref().is_some_and(|w| w.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.webhook.is_some()},
+            {"name": "whatsapp", "type": "messaging", "status": if cfg.channel.whatsapp.as_ref().is_some_and(|w| w.enabled) { "active" } else { "disabled" }, "configured": cfg.channel.whatsapp.is_some()},
         ]
     }))
 }
 
+/// List the tools enabled by the current `[tools]` config.
+pub async fn list_tools(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    let cfg = state.full_config.read().await;
+    let registry = bizclaw_tools::ToolRegistry::from_config(&cfg);
+    let tools: Vec<serde_json::Value> = registry.list().into_iter()
+        .map(|d| serde_json::json!({"name": d.name, "description": d.description}))
+        .collect();
+    Json(serde_json::json!({ "tools": tools }))
+}
+
+/// Check the enabled tools' parameter schemas against JSON Schema draft-07.
+pub async fn validate_tools(
+    State(state): State<Arc<AppState>>,
+) -> Json<serde_json::Value> {
+    let cfg = state.full_config.read().await;
+    let registry = bizclaw_tools::ToolRegistry::from_config(&cfg);
+    let invalid: Vec<serde_json::Value> = registry.validate_all().into_iter()
+        .map(|(tool, errors)| serde_json::json!({"tool": tool, "errors": errors}))
+        .collect();
+    Json(serde_json::json!({"ok": invalid.is_empty(), "tools": registry.list().len(), "invalid": invalid}))
+}
+
+/// Notes saved by the `notes` tool (`?namespace=` to filter, `?q=` to search).
+pub async fn list_notes(
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let store = match bizclaw_tools::notes::NoteStore::open_default() {
+        Ok(store) => store,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    let namespace = params.get("namespace").map(String::as_str).filter(|n| !n.is_empty());
+    let notes = match params.get("q").filter(|q| !q.is_empty()) {
+        Some(q) => store.search(namespace, q, None, 100)
+            .map(|found| found.into_iter().map(|m| m.note).collect()),
+        None => store.list(namespace),
+    };
+    match notes {
+        Ok(notes) => Json(serde_json::json!({"ok": true, "notes": notes})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Recent webhook delivery attempts, newest first (`?limit=`, default 50).
+pub async fn list_webhook_deliveries(
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let log = match bizclaw_channels::webhook::WebhookDeliveryLog::open_default() {
+        Ok(log) => log,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(50).min(1000);
+    match log.recent(limit) {
+        Ok(deliveries) => Json(serde_json::json!({"ok": true, "deliveries": deliveries})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Today's message usage against the tenant's daily quota; `limit` 0 and
+/// `remaining` null mean unlimited, as does running outside the platform.
+pub async fn get_quota(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let Some(quota) = &state.quota else {
+        return Json(serde_json::json!({"ok": true, "used": 0, "limit": 0, "remaining": null}));
+    };
+    match quota.check_quota() {
+        Ok(usage) => Json(serde_json::json!({
+            "ok": true, "used": usage.used, "limit": usage.limit, "remaining": usage.remaining(),
+        })),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Drop a channel's connection and connect again now, e.g. after pairing
+/// a Zalo account again.
+pub async fn force_reconnect(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let Some(channels) = &state.channels else {
+        return Json(serde_json::json!({"ok": false, "error": "No channels run with this server (start it with --channels)"}));
+    };
+    match channels.force_reconnect(&name) {
+        Ok(()) => Json(serde_json::json!({"ok": true, "channel": name})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// The text and file of a `/api/v1/messages` push; the file must be one
+/// the autonomy policy lets the agent read.
+async fn outbound_content(state: &AppState, req: &serde_json::Value) -> Result<(Option<String>, Option<std::path::PathBuf>), String> {
+    let text = req["text"].as_str().filter(|t| !t.trim().is_empty()).map(String::from);
+    let file = match req["file"].as_str().filter(|f| !f.is_empty()) {
+        Some(file) => {
+            let allowlist = bizclaw_security::allowlist::Allowlist::new(&state.full_config.read().await.autonomy);
+            let path = allowlist.check_path(file).map_err(|denial| {
+                denial.audit("messages_send");
+                denial.to_string()
+            })?;
+            if !path.is_file() {
+                return Err(format!("No file at '{file}'"));
+            }
+            Some(path)
+        }
+        None => None,
+    };
+    if text.is_none() && file.is_none() {
+        return Err("text or file is required".into());
+    }
+    Ok((text, file))
+}
+
+/// Push a message to one destination: `{"channel", "to", "thread_type",
+/// "text", "file"}`. Unknown channels are an error, not dropped.
+pub async fn send_message(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    use bizclaw_channels::router::Destination;
+
+    let to: Destination = match serde_json::from_value(req.clone()) {
+        Ok(to) => to,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("channel and to are required: {e}")})),
+    };
+    let (text, file) = match outbound_content(&state, &req).await {
+        Ok(content) => content,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+    };
+    let result = state.router.send(&to, text.as_deref(), file.as_deref()).await;
+    tracing::info!(target: "bizclaw::audit", channel = %to.channel, to = %to.to, ok = result.ok, "Message pushed via gateway");
+    state.publish_event(crate::events::PlatformEvent::audit("message_pushed", format!("channel={} ok={}", to.channel, result.ok)));
+    Json(serde_json::json!({"ok": result.ok, "result": result}))
+}
+
+/// Push a message to several destinations at once: `{"destinations":
+/// [{"channel", "to"}, …], "text", "file"}`. `ok` is whether all were
+/// delivered; `results` has each one's outcome, in order.
+pub async fn broadcast_message(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    use bizclaw_channels::router::Destination;
+
+    let destinations: Vec<Destination> = match serde_json::from_value(req["destinations"].clone()) {
+        Ok(destinations) => destinations,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("destinations must be a list of {{channel, to}}: {e}")})),
+    };
+    if destinations.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "destinations is empty"}));
+    }
+    let (text, file) = match outbound_content(&state, &req).await {
+        Ok(content) => content,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e})),
+    };
+    let results = state.router.broadcast(&destinations, text.as_deref(), file.as_deref()).await;
+    let delivered = results.iter().filter(|r| r.ok).count();
+    tracing::info!(target: "bizclaw::audit", delivered, total = results.len(), "Message broadcast via gateway");
+    state.publish_event(crate::events::PlatformEvent::audit("message_broadcast", format!("delivered={delivered} total={}", results.len())));
+    Json(serde_json::json!({"ok": delivered == results.len(), "results": results}))
+}
+
+/// Recent WhatsApp messages sent by the channel (or through
+/// `/api/v1/channels/whatsapp/send`) with their delivery status.
+pub async fn list_whatsapp_deliveries(
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let store = match bizclaw_channels::whatsapp::WhatsAppStore::open_default() {
+        Ok(store) => store,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(50).min(1000);
+    match store.recent(limit) {
+        Ok(deliveries) => Json(serde_json::json!({"ok": true, "deliveries": deliveries})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Send a WhatsApp message: `{"to", "text"}` for a free-form message, which
+/// only works within 24 hours of the customer's last message, or
+/// `{"to", "template", "language", "params"}` for an approved template.
+pub async fn send_whatsapp(
+    State(state): State<Arc<AppState>>,
+    Json(req): Json<serde_json::Value>,
+) -> Json<serde_json::Value> {
+    use bizclaw_channels::whatsapp::{WhatsAppChannel, WhatsAppStore};
+
+    let Some(config) = state.full_config.read().await.channel.whatsapp.clone().filter(|w| w.enabled) else {
+        return Json(serde_json::json!({"ok": false, "error": "WhatsApp channel is not enabled"}));
+    };
+    let Some(to) = req["to"].as_str().filter(|t| !t.is_empty()) else {
+        return Json(serde_json::json!({"ok": false, "error": "to is required"}));
+    };
+    let store = match WhatsAppStore::open_default() {
+        Ok(store) => Arc::new(store),
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    let channel = WhatsAppChannel::new((&config).into()).with_store(store);
+    let sent = match (req["template"].as_str(), req["text"].as_str()) {
+        (Some(template), _) => {
+            let language = req["language"].as_str().unwrap_or("vi");
+            let params: Vec<String> = req["params"].as_array().into_iter().flatten()
+                .map(|p| p.as_str().map_or_else(|| p.to_string(), String::from))
+                .collect();
+            channel.send_template(to, template, language, &params).await
+        }
+        (None, Some(text)) => channel.send_text(to, text).await,
+        (None, None) => return Json(serde_json::json!({"ok": false, "error": "text or template is required"})),
+    };
+    match sent {
+        Ok(message_id) => {
+            tracing::info!(target: "bizclaw::audit", to, "WhatsApp message sent via gateway");
+            Json(serde_json::json!({"ok": true, "message_id": message_id}))
+        }
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// The brain model's tokenizer, read from the GGUF metadata once per model path.
+async fn brain_tokenizer(state: &AppState) -> bizclaw_core::error::Result<Arc<bizclaw_brain::tokenizer::Tokenizer>> {
+    type Cached = Option<(std::path::PathBuf, Arc<bizclaw_brain::tokenizer::Tokenizer>)>;
+    static CACHE: std::sync::Mutex<Cached> = std::sync::Mutex::new(None);
+
+    let path = bizclaw_providers::brain::model_path(&*state.full_config.read().await);
+    if let Some((cached, tokenizer)) = CACHE.lock().unwrap().as_ref()
+        && *cached == path
+    {
+        return Ok(tokenizer.clone());
+    }
+    let tokenizer = tokio::task::spawn_blocking({
+        let path = path.clone();
+        move || bizclaw_brain::tokenizer::Tokenizer::from_gguf(&path)
+    })
+    .await
+    .map_err(|e| bizclaw_core::error::BizClawError::Brain(e.to_string()))??;
+    let tokenizer = Arc::new(tokenizer);
+    *CACHE.lock().unwrap() = Some((path, tokenizer.clone()));
+    Ok(tokenizer)
+}
+
+/// Token IDs and pieces for `?text=`, using the brain model's tokenizer.
+pub async fn tokenize(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let tokenizer = match brain_tokenizer(&state).await {
+        Ok(tokenizer) => tokenizer,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    let tokens = tokenizer.encode(params.get("text").map_or("", |t| t.as_str()));
+    let pieces: Vec<&str> = tokens.iter().map(|&id| tokenizer.decode_token(id)).collect();
+    Json(serde_json::json!({"ok": true, "count": tokens.len(), "tokens": tokens, "pieces": pieces}))
+}
+
+/// How many tokens `?text=` is for the brain model.
+pub async fn count_tokens(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    match brain_tokenizer(&state).await {
+        Ok(tokenizer) => Json(serde_json::json!({
+            "ok": true,
+            "count": tokenizer.count_tokens(params.get("text").map_or("", |t| t.as_str())),
+        })),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Check `?text=` against the `[safety]` rules (even while they are off), or
+/// try one rule before adding it with `?pattern=`, `?action=` (default
+/// "block") and `?category=`.
+pub async fn test_safety(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    use bizclaw_core::config::SafetyRuleConfig;
+    use bizclaw_core::safety::{SafetyAction, SafetyFilter};
+
+    let mut config = state.full_config.read().await.safety.clone();
+    let enabled = config.enabled;
+    if let Some(pattern) = params.get("pattern") {
+        let action = params.get("action").map_or("block", |a| a.as_str());
+        let Ok(action) = serde_json::from_value::<SafetyAction>(serde_json::json!(action)) else {
+            return Json(serde_json::json!({"ok": false, "error": format!("action must be block, redact or warn, not '{action}'")}));
+        };
+        let category = params.get("category").cloned().unwrap_or_else(|| "test".into());
+        config.rules = vec![SafetyRuleConfig { pattern: pattern.clone(), action, category }];
+    }
+    match SafetyFilter::from_config(&config) {
+        Ok(filter) => {
+            let result = filter.check(params.get("text").map_or("", |t| t.as_str()));
+            Json(serde_json::json!({"ok": true, "enabled": enabled, "result": result}))
+        }
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Put `payload` through the JMESPath `transform`, as the webhook channel
+/// does with `input_transform` and `output_transform`, without the agent.
+pub async fn test_webhook_transform(Json(req): Json<serde_json::Value>) -> Json<serde_json::Value> {
+    let Some(transform) = req["transform"].as_str() else {
+        return Json(serde_json::json!({"ok": false, "error": "transform is required"}));
+    };
+    match bizclaw_channels::webhook::apply_transform(transform, &req["payload"]) {
+        Ok(result) => Json(serde_json::json!({"ok": true, "result": result})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Groups with buffered messages, and every group with its own settings.
+pub async fn list_groups(State(state): State<Arc<AppState>>) -> Json<serde_json::Value> {
+    let Some(digest) = &state.group_digest else {
+        return Json(serde_json::json!({"ok": false, "error": "Group summarizer is not enabled"}));
+    };
+    let settings = match digest.settings().list() {
+        Ok(settings) => settings,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    let mut ids = digest.buffer().group_ids();
+    ids.extend(settings.iter().map(|s| s.group_id.clone()));
+    ids.sort();
+    ids.dedup();
+    let groups: Vec<_> = ids.iter().map(|id| serde_json::json!({
+        "group_id": id,
+        "buffered": digest.buffer().count(id),
+        "oldest": digest.buffer().oldest(id),
+        "settings": settings.iter().find(|s| &s.group_id == id),
+    })).collect();
+    Json(serde_json::json!({"ok": true, "groups": groups}))
+}
+
+/// Summarize a group's buffered messages now and deliver the summary.
+pub async fn summarize_group(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(group_id): axum::extract::Path<String>,
+) -> Json<serde_json::Value> {
+    let Some(digest) = &state.group_digest else {
+        return Json(serde_json::json!({"ok": false, "error": "Group summarizer is not enabled"}));
+    };
+    match digest.summarize(&group_id).await {
+        Ok(Some(summary)) => Json(serde_json::json!({"ok": true, "summary": summary})),
+        Ok(None) => Json(serde_json::json!({"ok": false, "error": format!("No buffered messages for group {group_id}")})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Override the summary settings for one group.
+pub async fn update_group_settings(
+    State(state): State<Arc<AppState>>,
+    axum::extract::Path(group_id): axum::extract::Path<String>,
+    Json(mut settings): Json<bizclaw_tools::group_digest::GroupSettings>,
+) -> Json<serde_json::Value> {
+    let Some(digest) = &state.group_digest else {
+        return Json(serde_json::json!({"ok": false, "error": "Group summarizer is not enabled"}));
+    };
+    settings.group_id = group_id;
+    match digest.settings().set(&settings) {
+        Ok(()) => Json(serde_json::json!({"ok": true, "settings": settings})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
 /// Generate Zalo QR code for login.
 pub async fn zalo_qr_code(
     State(_state): State<Arc<AppState>>,
@@ -340,15 +997,39 @@ pub async fn zalo_qr_code(
 mod tests {
     use super::*;
     use crate::server::AppState;
-    use std::sync::Mutex;
 
     fn test_state() -> State<Arc<AppState>> {
+        test_state_at("/tmp/test_config.toml".into())
+    }
+
+    fn test_state_at(config_path: std::path::PathBuf) -> State<Arc<AppState>> {
+        test_state_with_quota(config_path, None)
+    }
+
+    fn test_state_with_quota(
+        config_path: std::path::PathBuf,
+        quota: Option<Arc<dyn bizclaw_core::quota::MessageQuota>>,
+    ) -> State<Arc<AppState>> {
         State(Arc::new(AppState {
             gateway_config: bizclaw_core::config::GatewayConfig::default(),
-            full_config: Arc::new(Mutex::new(bizclaw_core::config::BizClawConfig::default())),
-            config_path: std::path::PathBuf::from("/tmp/test_config.toml"),
+            full_config: Arc::new(tokio::sync::RwLock::new(bizclaw_core::config::BizClawConfig::default())),
+            config_path,
             start_time: std::time::Instant::now(),
             pairing_code: None,
+            metrics: Default::default(),
+            config_tx: Arc::new(tokio::sync::watch::channel(Default::default()).0),
+            approvals: Default::default(),
+            uploads: Arc::new(crate::uploads::UploadRegistry::new(
+                std::env::temp_dir().join(format!("bizclaw-test-uploads-{}", uuid::Uuid::new_v4().simple())),
+            )),
+            rate_limits: Default::default(),
+            group_digest: None,
+            shutdown: Default::default(),
+            quota,
+            channels: None,
+            router: Default::default(),
+            events: crate::events::channel(),
+            context_lengths: Default::default(),
         }))
     }
 
@@ -381,4 +1062,688 @@ mod tests {
         let json = result.0;
         assert!(json["channels"].is_array());
     }
+
+    #[tokio::test]
+    async fn test_list_tools_reflects_config() {
+        let state = test_state();
+        state.full_config.write().await.tools.shell.enabled = false;
+        let json = list_tools(state).await.0;
+        let names: Vec<&str> = json["tools"].as_array().unwrap().iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert!(!names.contains(&"shell"));
+        assert!(names.contains(&"calendar"));
+    }
+
+    #[tokio::test]
+    async fn test_tokenize_and_count_tokens() {
+        fn string(out: &mut Vec<u8>, s: &str) {
+            out.extend((s.len() as u64).to_le_bytes());
+            out.extend(s.as_bytes());
+        }
+        let state = test_state();
+        let query = |text: &str| axum::extract::Query(std::collections::HashMap::from([("text".to_string(), text.to_string())]));
+
+        let path = std::env::temp_dir().join(format!("bizclaw-tokenize-{}.gguf", uuid::Uuid::new_v4().simple()));
+        state.full_config.write().await.brain.model_path = path.display().to_string();
+        let json = count_tokens(state.clone(), query("hi")).await.0;
+        assert_eq!(json["ok"], false);
+
+        // A GGUF header holding only a vocabulary.
+        let tokens = ["<unk>", "<s>", "</s>", "▁", "h", "i", "▁h", "▁hi"];
+        let mut gguf = b"GGUF".to_vec();
+        gguf.extend(3u32.to_le_bytes());
+        gguf.extend(0u64.to_le_bytes());
+        gguf.extend(1u64.to_le_bytes());
+        string(&mut gguf, "tokenizer.ggml.tokens");
+        gguf.extend(9u32.to_le_bytes());
+        gguf.extend(8u32.to_le_bytes());
+        gguf.extend((tokens.len() as u64).to_le_bytes());
+        for t in tokens {
+            string(&mut gguf, t);
+        }
+        std::fs::write(&path, gguf).unwrap();
+
+        let json = tokenize(state.clone(), query("hi hi")).await.0;
+        std::fs::remove_file(&path).ok();
+        assert_eq!(json["ok"], true);
+        assert_eq!(json["tokens"], serde_json::json!([7, 7]));
+        assert_eq!(json["pieces"], serde_json::json!(["▁hi", "▁hi"]));
+        // Cached, so the file is no longer needed.
+        let json = count_tokens(state, query("hi hi hi")).await.0;
+        assert_eq!(json["count"], 3);
+    }
+
+    #[tokio::test]
+    async fn test_quota_endpoint() {
+        use bizclaw_core::quota::{MessageQuota, Remaining};
+
+        struct FixedQuota(Remaining);
+        impl MessageQuota for FixedQuota {
+            fn check_quota(&self) -> bizclaw_core::error::Result<Remaining> {
+                Ok(self.0)
+            }
+            fn record_message(&self) -> bizclaw_core::error::Result<()> {
+                Ok(())
+            }
+        }
+
+        let Json(unlimited) = get_quota(test_state()).await;
+        assert_eq!((unlimited["limit"].as_u64(), unlimited["remaining"].is_null()), (Some(0), true));
+
+        let quota = Arc::new(FixedQuota(Remaining { used: 40, limit: 100 }));
+        let Json(usage) = get_quota(test_state_with_quota("/tmp/test_config.toml".into(), Some(quota))).await;
+        assert_eq!((usage["used"].as_u64(), usage["remaining"].as_u64()), (Some(40), Some(60)));
+    }
+
+    #[tokio::test]
+    async fn test_force_reconnect_needs_running_channel() {
+        let Json(no_channels) = force_reconnect(test_state(), axum::extract::Path("zalo".into())).await;
+        assert!(no_channels["error"].as_str().unwrap().contains("--channels"));
+
+        let State(state) = test_state();
+        let mut state = Arc::try_unwrap(state).ok().unwrap();
+        state.channels = Some(Default::default());
+        let Json(not_running) = force_reconnect(State(Arc::new(state)), axum::extract::Path("zalo".into())).await;
+        assert_eq!(not_running["ok"], false);
+        assert!(not_running["error"].as_str().unwrap().contains("'zalo' is not running"));
+    }
+
+    #[tokio::test]
+    async fn test_send_whatsapp_validates_request() {
+        let state = test_state();
+        let send = |body: serde_json::Value| send_whatsapp(state.clone(), Json(body));
+        let Json(disabled) = send(serde_json::json!({"to": "84901", "text": "Chào anh"})).await;
+        assert_eq!(disabled["error"], "WhatsApp channel is not enabled");
+
+        state.full_config.write().await.channel.whatsapp = Some(bizclaw_core::config::WhatsAppChannelConfig {
+            enabled: true,
+            ..Default::default()
+        });
+        let Json(missing) = send(serde_json::json!({"text": "Chào anh"})).await;
+        assert_eq!((missing["ok"].as_bool(), missing["error"].as_str()), (Some(false), Some("to is required")));
+    }
+
+    #[tokio::test]
+    async fn test_send_and_broadcast_messages() {
+        use bizclaw_channels::router::{ChannelRouter, Destination, Sender};
+
+        struct Recorder(std::sync::Mutex<Vec<String>>);
+        #[async_trait::async_trait]
+        impl Sender for Recorder {
+            fn channel(&self) -> &str { "telegram" }
+            async fn send_text(&self, to: &Destination, text: &str) -> bizclaw_core::error::Result<()> {
+                self.0.lock().unwrap().push(format!("{}: {text}", to.to));
+                Ok(())
+            }
+            async fn send_file(&self, _to: &Destination, _path: &std::path::Path) -> bizclaw_core::error::Result<()> {
+                Err(bizclaw_core::error::BizClawError::Channel("telegram cannot send files".into()))
+            }
+        }
+
+        let telegram = Arc::new(Recorder(Default::default()));
+        let mut router = ChannelRouter::default();
+        router.register(telegram.clone());
+        let State(base) = test_state();
+        let state = State(Arc::new(AppState { router: Arc::new(router), ..(*base).clone() }));
+
+        let json = send_message(state.clone(), Json(serde_json::json!({"channel": "telegram", "to": "42", "text": "Kho sắp hết hàng"}))).await.0;
+        assert_eq!(json["ok"], true, "{json}");
+        let json = send_message(state.clone(), Json(serde_json::json!({"channel": "sms", "to": "0901", "text": "x"}))).await.0;
+        assert_eq!(json["ok"], false);
+        assert!(json["result"]["error"].as_str().unwrap().contains("Unknown channel 'sms'"));
+        let json = send_message(state.clone(), Json(serde_json::json!({"channel": "telegram", "to": "42"}))).await.0;
+        assert_eq!(json["error"], "text or file is required");
+        let json = send_message(state.clone(), Json(serde_json::json!({"channel": "telegram", "to": "42", "file": "/etc/shadow"}))).await.0;
+        assert_eq!(json["ok"], false);
+
+        let json = broadcast_message(state.clone(), Json(serde_json::json!({
+            "destinations": [{"channel": "telegram", "to": "1"}, {"channel": "email", "to": "admin@shop.vn"}, {"channel": "telegram", "to": "2"}],
+            "text": "Bảo trì lúc 22h",
+        }))).await.0;
+        assert_eq!(json["ok"], false);
+        let outcome: Vec<bool> = json["results"].as_array().unwrap().iter().map(|r| r["ok"].as_bool().unwrap()).collect();
+        assert_eq!(outcome, [true, false, true]);
+        assert_eq!(*telegram.0.lock().unwrap(), ["42: Kho sắp hết hàng", "1: Bảo trì lúc 22h", "2: Bảo trì lúc 22h"]);
+        let json = broadcast_message(state, Json(serde_json::json!({"destinations": [], "text": "x"}))).await.0;
+        assert_eq!(json["error"], "destinations is empty");
+    }
+
+    #[tokio::test]
+    async fn test_webhook_transform_endpoint() {
+        let json = test_webhook_transform(Json(serde_json::json!({
+            "payload": { "entry": [{ "msg": { "body": "Xin chào" } }] },
+            "transform": "{message: entry[0].msg.body}",
+        }))).await.0;
+        assert_eq!(json["result"], serde_json::json!({ "message": "Xin chào" }));
+        let json = test_webhook_transform(Json(serde_json::json!({ "payload": {}, "transform": "[" }))).await.0;
+        assert_eq!(json["ok"], false);
+        let json = test_webhook_transform(Json(serde_json::json!({ "payload": {} }))).await.0;
+        assert_eq!(json["error"], "transform is required");
+    }
+
+    #[tokio::test]
+    async fn test_safety_test_endpoint() {
+        use bizclaw_core::config::SafetyRuleConfig;
+        use bizclaw_core::safety::SafetyAction;
+
+        let state = test_state();
+        let query = |pairs: &[(&str, &str)]| axum::extract::Query(
+            pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<std::collections::HashMap<_, _>>(),
+        );
+        state.full_config.write().await.safety.rules.push(SafetyRuleConfig {
+            pattern: r"\d{10}".into(),
+            action: SafetyAction::Redact,
+            category: "phone".into(),
+        });
+
+        let json = test_safety(state.clone(), query(&[("text", "Gọi 0901234567 nhé")])).await.0;
+        assert_eq!(json["enabled"], false);
+        assert_eq!(json["result"]["cleaned_response"], "Gọi [REDACTED] nhé");
+        assert_eq!(json["result"]["triggered_rules"], serde_json::json!(["phone"]));
+
+        let json = test_safety(state.clone(), query(&[("text", "Gọi 0901234567"), ("pattern", "(?i)gọi")])).await.0;
+        assert_eq!(json["result"]["passed"], false);
+        assert_eq!(json["result"]["triggered_rules"], serde_json::json!(["test"]));
+
+        let json = test_safety(state.clone(), query(&[("text", "x"), ("pattern", "(")])).await.0;
+        assert_eq!(json["ok"], false);
+        let json = test_safety(state, query(&[("text", "x"), ("pattern", "x"), ("action", "drop")])).await.0;
+        assert!(json["error"].as_str().unwrap().contains("'drop'"));
+    }
+
+    #[tokio::test]
+    async fn test_static_dir_dashboard() {
+        use axum::http::{Request, header};
+        use tower::ServiceExt;
+
+        let dir = std::env::temp_dir().join(format!("bizclaw-static-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(dir.join("assets")).unwrap();
+        std::fs::write(dir.join("index.html"), "<html>custom</html>").unwrap();
+        std::fs::write(dir.join("assets/app.css"), "body{}").unwrap();
+
+        let mut state = (*test_state().0).clone();
+        state.gateway_config.static_dir = Some(dir.display().to_string());
+        let app = crate::server::build_router(state);
+        let get = |path: &str| app.clone().oneshot(Request::get(path).body(axum::body::Body::empty()).unwrap());
+
+        let resp = get("/assets/app.css").await.unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.headers()[header::CONTENT_TYPE], "text/css");
+        assert_eq!(resp.headers()[header::CACHE_CONTROL], "public, max-age=3600");
+
+        // The index page, for the root and for SPA routes, is always revalidated.
+        for path in ["/", "/chat"] {
+            let resp = get(path).await.unwrap();
+            assert_eq!(resp.status(), 200);
+            assert_eq!(resp.headers()[header::CACHE_CONTROL], "no-cache");
+            let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+            assert_eq!(&body[..], b"<html>custom</html>");
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Without a static dir, the embedded dashboard is served.
+        let app = crate::server::build_router((*test_state().0).clone());
+        let resp = app.oneshot(Request::get("/chat").body(axum::body::Body::empty()).unwrap()).await.unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], crate::dashboard::dashboard_html().as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_validate_tools() {
+        let json = validate_tools(test_state()).await.0;
+        assert_eq!(json["ok"], true, "{json}");
+        assert!(json["tools"].as_u64().unwrap() >= 5);
+        assert_eq!(json["invalid"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_json_and_prometheus() {
+        let state = test_state();
+        state.metrics.record_request();
+        state.metrics.record_provider_call("ollama", false);
+
+        let resp = metrics(state.clone(), axum::http::HeaderMap::new()).await;
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["requests_total"], 1);
+        assert_eq!(json["providers"]["ollama"]["errors"], 1);
+        assert_eq!(json["inbound_duplicates_total"], 0);
+
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(axum::http::header::ACCEPT, "text/plain".parse().unwrap());
+        let resp = metrics(state, headers).await;
+        assert!(resp.headers()[axum::http::header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert!(String::from_utf8_lossy(&body).contains("bizclaw_requests_total 1"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_broadcasts() {
+        let path = std::env::temp_dir().join(format!("bizclaw-reload-{}.toml", std::process::id()));
+        let mut on_disk = bizclaw_core::config::BizClawConfig {
+            api_key: "sk-new".into(),
+            default_model: "gpt-4o".into(),
+            ..Default::default()
+        };
+        on_disk.gateway.port += 1;
+        std::fs::write(&path, toml::to_string_pretty(&on_disk).unwrap()).unwrap();
+
+        let state = test_state_at(path.clone());
+        let mut rx = state.subscribe_config();
+        let json = reload_config(state.clone()).await.0;
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(json["ok"], true);
+        assert_eq!(json["restart_required"], serde_json::json!(["gateway"]));
+        assert_eq!(state.full_config.read().await.api_key, "sk-new");
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(rx.borrow_and_update().default_model, "gpt-4o");
+    }
+
+    /// OpenAI-compatible `/v1/models` that accepts only `Bearer <good_key>`.
+    async fn key_checking_server(good_key: &'static str) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let (status, body) = if request.contains(&format!("authorization: bearer {good_key}")) {
+                    ("200 OK", r#"{"data":[]}"#)
+                } else {
+                    ("401 Unauthorized", r#"{"error":"invalid_api_key"}"#)
+                };
+                let response = format!(
+                    "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len(),
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        base
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_replaces_old_key() {
+        let provider = format!("custom:{}", key_checking_server("sk-new").await);
+        let path = std::env::temp_dir().join(format!("bizclaw-rotate-{}.toml", uuid::Uuid::new_v4().simple()));
+        let state = test_state_at(path.clone());
+        {
+            let mut cfg = state.full_config.write().await;
+            cfg.default_provider = provider.clone();
+            cfg.api_key = "sk-old".into();
+        }
+        let mut rx = state.subscribe_config();
+
+        for bad in [
+            serde_json::json!({"provider": provider, "new_key": ""}),
+            serde_json::json!({"provider": "anthropic", "new_key": "sk-other"}),
+        ] {
+            assert_eq!(rotate_key(state.clone(), Json(bad)).await.0["ok"], false);
+        }
+        // A key the provider refuses is not saved.
+        let json = rotate_key(state.clone(), Json(serde_json::json!({"provider": provider, "new_key": "sk-revoked"}))).await.0;
+        assert_eq!(json["ok"], false);
+        assert!(json["error"].as_str().unwrap().contains("rejected the new key"), "{json}");
+        assert_eq!(state.full_config.read().await.api_key, "sk-old");
+        assert!(!path.exists());
+
+        let json = rotate_key(state.clone(), Json(serde_json::json!({"provider": provider, "new_key": "sk-new"}))).await.0;
+        assert_eq!(json["ok"], true, "{json}");
+        assert_eq!(json["healthy"], true);
+
+        // Requests read the key from the shared config, which no longer has the old one.
+        assert_eq!(state.full_config.read().await.api_key, "sk-new");
+        assert_eq!(rx.borrow_and_update().api_key, "sk-new");
+        let on_disk = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert!(on_disk.contains("sk-new"));
+        assert!(!on_disk.contains("sk-old"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_missing_file() {
+        let json = reload_config(test_state_at("/nonexistent/bizclaw.toml".into())).await.0;
+        assert_eq!(json["ok"], false);
+    }
+
+    #[tokio::test]
+    async fn test_approvals_list_and_resolve() {
+        let state = test_state();
+        let broker = state.approvals.clone();
+        let mut events = broker.subscribe();
+        let waiter = tokio::spawn(async move {
+            broker.request("shell", r#"{"command":"rm x"}"#, "not allowed", std::time::Duration::from_secs(5)).await
+        });
+        let pending = events.recv().await.unwrap();
+
+        let json = list_approvals(state.clone()).await.0;
+        assert_eq!(json["approvals"][0]["tool"], "shell");
+
+        let bad = resolve_approval(
+            state.clone(), axum::extract::Path(pending.id.clone()), Json(serde_json::json!({"decision": "maybe"})),
+        ).await.0;
+        assert_eq!(bad["ok"], false);
+
+        let json = resolve_approval(
+            state.clone(),
+            axum::extract::Path(pending.id.clone()),
+            Json(serde_json::json!({"decision": "deny", "approver": "lan"})),
+        ).await.0;
+        assert_eq!(json["ok"], true);
+        assert_eq!(
+            waiter.await.unwrap(),
+            bizclaw_security::approval::ApprovalOutcome::Denied { approver: "gateway:lan".into() }
+        );
+        assert!(list_approvals(state).await.0["approvals"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_upload_and_delete() {
+        use tower::ServiceExt;
+
+        let state = test_state();
+        let app = crate::server::build_router((*state.0).clone());
+        let boundary = "XBOUNDARY";
+        let body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"tenant_id\"\r\n\r\nacme\r\n\
+             --{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"sales.csv\"\r\n\
+             Content-Type: text/csv\r\n\r\nmonth,total\n1,100\n\r\n--{boundary}--\r\n"
+        );
+        let req = axum::http::Request::post("/api/v1/upload")
+            .header("Content-Type", format!("multipart/form-data; boundary={boundary}"))
+            .body(axum::body::Body::from(body))
+            .unwrap();
+        let resp = app.oneshot(req).await.unwrap();
+        let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(json["ok"], true, "{json}");
+        assert_eq!(json["filename"], "sales.csv");
+        assert_eq!(json["mime_type"], "text/csv");
+        assert_eq!(json["size_bytes"], 18);
+        assert_eq!(json["tenant_id"], "acme");
+
+        let file_id = json["file_id"].as_str().unwrap().to_string();
+        let path = std::path::PathBuf::from(json["path"].as_str().unwrap());
+        assert!(path.exists());
+        let json = delete_upload(state.clone(), axum::extract::Path(file_id.clone())).await.0;
+        assert_eq!(json["ok"], true);
+        assert!(!path.exists());
+        assert_eq!(delete_upload(state.clone(), axum::extract::Path(file_id)).await.0["ok"], false);
+        std::fs::remove_dir_all(state.uploads.dir()).ok();
+    }
+
+    #[tokio::test]
+    async fn test_pairing_header_and_origin_checks() {
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let mut state = (*test_state().0).clone();
+        state.pairing_code = Some("123456".into());
+        state.gateway_config.allowed_origins = vec!["https://admin.example.com".into()];
+        let app = crate::server::build_router(state);
+        let send = |req: Request<axum::body::Body>| app.clone().oneshot(req);
+        let post = |uri: &str| Request::post(uri).header("Host", "localhost:3000");
+
+        // Query-string codes are off unless opted into.
+        let resp = send(Request::get("/api/v1/info?code=123456").body(Default::default()).unwrap()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = send(post("/api/v1/config/reload").header("X-Pairing-Code", "123456").body(Default::default()).unwrap()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = send(post("/api/v1/config/reload").header("X-Pairing-Code", "123457").body(Default::default()).unwrap()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        // A WebSocket may carry the code as a subprotocol; without one it
+        // authenticates by its first message instead.
+        let ws = |protocols: &str| Request::get("/ws").header("Sec-WebSocket-Protocol", protocols).body(Default::default()).unwrap();
+        let resp = send(ws("bizclaw, bizclaw-pairing.123456")).await.unwrap();
+        assert_ne!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = send(ws("bizclaw")).await.unwrap();
+        assert_ne!(resp.status(), StatusCode::UNAUTHORIZED);
+        let resp = send(ws("bizclaw, bizclaw-pairing.000000")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Another site's page can't post, even with the code.
+        for (origin, expected) in [
+            ("https://evil.example", StatusCode::FORBIDDEN),
+            ("http://localhost:3000", StatusCode::OK),
+            ("https://admin.example.com", StatusCode::OK),
+        ] {
+            let req = post("/api/v1/config/reload")
+                .header("Origin", origin)
+                .header("X-Pairing-Code", "123456")
+                .body(Default::default())
+                .unwrap();
+            assert_eq!(send(req).await.unwrap().status(), expected, "{origin}");
+        }
+
+        // CORS headers only for listed origins.
+        let preflight = |origin: &str| Request::options("/api/v1/config/update")
+            .header("Origin", origin)
+            .header("Access-Control-Request-Method", "POST")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let resp = send(preflight("https://admin.example.com")).await.unwrap();
+        assert_eq!(resp.headers()["access-control-allow-origin"], "https://admin.example.com");
+        let resp = send(preflight("https://evil.example")).await.unwrap();
+        assert!(!resp.headers().contains_key("access-control-allow-origin"));
+    }
+
+    #[tokio::test]
+    async fn test_query_pairing_code_opt_in() {
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let mut state = (*test_state().0).clone();
+        state.pairing_code = Some("123456".into());
+        state.gateway_config.allow_query_pairing_code = true;
+        let app = crate::server::build_router(state);
+
+        // Even when enabled, query codes only work for GET.
+        let resp = app.clone().oneshot(Request::get("/api/v1/info?code=123456").body(axum::body::Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let req = Request::post("/api/v1/config/reload?code=123456").header("Host", "localhost:3000").body(axum::body::Body::empty()).unwrap();
+        assert_eq!(app.oneshot(req).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+
+        let uri: axum::http::Uri = "/ws?lang=vi&code=123456".parse().unwrap();
+        assert_eq!(crate::server::redacted_uri(&uri), "/ws?lang=vi&code=***");
+    }
+
+    #[tokio::test]
+    async fn test_ws_auth_message() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::{Message, protocol::frame::coding::CloseCode};
+
+        let mut state = (*test_state().0).clone();
+        state.pairing_code = Some("123456".into());
+        let app = crate::server::build_router(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await
+        });
+
+        // Send `first`, then return the type of the first event, or the close code and reason.
+        let first_reply = |first: serde_json::Value| {
+            let url = url.clone();
+            async move {
+                let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+                socket.send(Message::Text(first.to_string())).await.unwrap();
+                match socket.next().await.unwrap().unwrap() {
+                    Message::Text(text) => {
+                        let event: serde_json::Value = serde_json::from_str(&text).unwrap();
+                        event["type"].as_str().unwrap().to_string()
+                    }
+                    Message::Close(Some(frame)) => {
+                        assert_eq!(frame.code, CloseCode::Policy);
+                        frame.reason.to_string()
+                    }
+                    other => panic!("unexpected {other:?}"),
+                }
+            }
+        };
+
+        let auth = |code: &str| serde_json::json!({"type": "auth", "payload": {"code": code}});
+        assert_eq!(first_reply(auth("123456")).await, "connected");
+        assert_eq!(first_reply(auth("000000")).await, "invalid pairing code");
+        assert_eq!(first_reply(serde_json::json!({"type": "ping"})).await, "authentication required");
+    }
+
+    #[tokio::test]
+    async fn test_event_stream() {
+        use crate::events::PlatformEvent;
+        use futures::StreamExt;
+
+        let mut state = (*test_state().0).clone();
+        state.pairing_code = Some("123456".into());
+        let events = state.events.clone();
+        let app = crate::server::build_router(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let root = format!("http://{}", listener.local_addr().unwrap());
+        let base = format!("{root}/api/v1/events");
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await
+        });
+        let client = reqwest::Client::new();
+
+        assert_eq!(client.get(&base).send().await.unwrap().status(), 401);
+        // The cookie only opens the event stream, not the rest of the API.
+        let cookie = "theme=dark; bizclaw_pairing=123456";
+        assert_eq!(client.get(format!("{root}/api/v1/info")).header("Cookie", cookie).send().await.unwrap().status(), 401);
+        assert_eq!(client.post(format!("{root}/api/v1/config/reload")).header("Cookie", cookie).send().await.unwrap().status(), 401);
+        let unknown = client.get(format!("{base}?filter=tenant_deleted")).bearer_auth("123456").send().await.unwrap();
+        assert_eq!(unknown.status(), 400);
+
+        let response = client.get(format!("{base}?filter=alert_fired,channel_connected"))
+            .header("Cookie", "theme=dark; bizclaw_pairing=123456")
+            .send().await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        while events.receiver_count() == 0 {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        events.send(PlatformEvent::audit("api_key_rotated", "provider=openai")).unwrap();
+        events.send(PlatformEvent::AlertFired { alert: "quota_exhausted".into(), message: "100/100".into() }).unwrap();
+
+        let mut body = response.bytes_stream();
+        let mut received = String::new();
+        while !received.contains("\n\n") {
+            received.push_str(&String::from_utf8_lossy(&body.next().await.unwrap().unwrap()));
+        }
+        let frame = received.split("\n\n").next().unwrap();
+        assert!(frame.contains("event: alert_fired"), "{frame}");
+        let data = frame.lines().find_map(|l| l.strip_prefix("data: ")).unwrap();
+        assert_eq!(serde_json::from_str::<PlatformEvent>(data).unwrap(), PlatformEvent::AlertFired {
+            alert: "quota_exhausted".into(), message: "100/100".into(),
+        });
+    }
+
+    #[tokio::test]
+    async fn test_ws_closed_on_shutdown() {
+        use futures::StreamExt;
+        use tokio_tungstenite::tungstenite::{Message, protocol::frame::coding::CloseCode};
+
+        let state = (*test_state().0).clone();
+        let shutdown = state.shutdown.clone();
+        let app = crate::server::build_router(state);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}/ws", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await
+        });
+
+        let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+        assert!(matches!(socket.next().await, Some(Ok(Message::Text(_)))));
+        shutdown.cancel();
+        match socket.next().await {
+            Some(Ok(Message::Close(Some(frame)))) => {
+                assert_eq!(frame.code, CloseCode::Away);
+                assert_eq!(frame.reason, "server shutting down");
+            }
+            other => panic!("expected close, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_and_pairing_lockout() {
+        use axum::http::{Request, StatusCode};
+        use tower::ServiceExt;
+
+        let mut state = (*test_state().0).clone();
+        state.pairing_code = Some("123456".into());
+        state.rate_limits = Arc::new(crate::rate_limit::RateLimits::from_config(
+            &bizclaw_core::config::GatewayRateLimitConfig {
+                requests_per_minute: 60,
+                burst: 20,
+                pairing_per_minute: 3,
+                pairing_max_failures: 4,
+                pairing_lockout_secs: 600,
+            },
+        ));
+        let app = crate::server::build_router(state);
+        let send = |req: Request<axum::body::Body>| app.clone().oneshot(req);
+        let verify = |code: &str| Request::post("/api/v1/verify-pairing")
+            .header("Content-Type", "application/json")
+            .body(axum::body::Body::from(serde_json::json!({"code": code}).to_string()))
+            .unwrap();
+        let info = |code: &str| Request::get("/api/v1/info").header("X-Pairing-Code", code).body(Default::default()).unwrap();
+
+        // The pairing endpoint allows 3 attempts, then 429 with Retry-After.
+        for _ in 0..3 {
+            assert_eq!(send(verify("000000")).await.unwrap().status(), StatusCode::OK);
+        }
+        let resp = send(verify("123456")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(resp.headers()[axum::http::header::RETRY_AFTER].to_str().unwrap().parse::<u64>().unwrap() >= 1);
+
+        // The fourth wrong code, through a protected endpoint, locks the client out,
+        // so even the right code is refused.
+        assert_eq!(send(info("111111")).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        let resp = send(info("123456")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry: u64 = resp.headers()[axum::http::header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!(retry > 500 && retry <= 600, "{retry}");
+
+        // The general limit covers public routes too.
+        let mut statuses = Vec::new();
+        for _ in 0..20 {
+            statuses.push(send(Request::get("/health").body(Default::default()).unwrap()).await.unwrap().status());
+        }
+        assert_eq!(statuses.last(), Some(&StatusCode::TOO_MANY_REQUESTS));
+    }
+
+    #[tokio::test]
+    async fn test_update_config_deep_merge() {
+        let path = std::env::temp_dir().join(format!("bizclaw-update-{}.toml", uuid::Uuid::new_v4().simple()));
+        let state = test_state_at(path.clone());
+        {
+            let mut cfg = state.full_config.write().await;
+            cfg.memory.vector_weight = 0.6;
+            cfg.memory.keyword_weight = 0.4;
+        }
+
+        let json = update_config(state.clone(), Json(serde_json::json!({
+            "memory": { "embedding_provider": "ollama", "not_a_field": 1 },
+            "autonomy": { "allowed_commands": ["ls"] },
+            "api_key_set": true,
+            "channels": null,
+        }))).await.0;
+        assert_eq!(json["ok"], true, "{json}");
+        {
+            let cfg = state.full_config.read().await;
+            assert_eq!(cfg.memory.embedding_provider, "ollama");
+            assert!((cfg.memory.vector_weight - 0.6).abs() < 1e-6);
+            assert!((cfg.memory.keyword_weight - 0.4).abs() < 1e-6);
+            assert_eq!(cfg.autonomy.allowed_commands, ["ls"]);
+            assert!(cfg.autonomy.workspace_only);
+        }
+
+        let json = update_config(state.clone(), Json(serde_json::json!({ "gateway": { "port": "abc" } }))).await.0;
+        assert_eq!(json["ok"], false);
+        assert_eq!(state.full_config.read().await.gateway.port, 3000);
+        std::fs::remove_file(&path).ok();
+    }
 }
@@ -1,9 +1,10 @@
 //! HTTP server implementation using Axum.
 
-use axum::{Router, Json, routing::{get, post}, extract::State};
+use axum::{Router, Json, routing::{delete, get, post, put}, extract::State};
 use axum::response::Html;
+use super::rate_limit::ClientIp;
 use bizclaw_core::config::{GatewayConfig, BizClawConfig};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::path::PathBuf;
 use tower_http::cors::CorsLayer;
 use tower_http::trace::TraceLayer;
@@ -12,10 +13,61 @@ use tower_http::trace::TraceLayer;
 #[derive(Clone)]
 pub struct AppState {
     pub gateway_config: GatewayConfig,
-    pub full_config: Arc<Mutex<BizClawConfig>>,
+    /// Read by most handlers, written only by config updates and reloads.
+    pub full_config: Arc<tokio::sync::RwLock<BizClawConfig>>,
     pub config_path: PathBuf,
     pub start_time: std::time::Instant,
     pub pairing_code: Option<String>,
+    pub metrics: Arc<super::metrics::Metrics>,
+    /// Broadcasts the config whenever it is saved or reloaded.
+    pub config_tx: Arc<tokio::sync::watch::Sender<BizClawConfig>>,
+    /// Tool calls waiting for a human decision (autonomy level "approval").
+    pub approvals: Arc<bizclaw_security::approval::ApprovalBroker>,
+    /// Files uploaded through `/api/v1/upload`.
+    pub uploads: Arc<super::uploads::UploadRegistry>,
+    /// Per-client-IP request limits and pairing lockout.
+    pub rate_limits: Arc<super::rate_limit::RateLimits>,
+    /// Group summaries; `None` when the group summarizer is off or can't start.
+    pub group_digest: Option<Arc<bizclaw_tools::group_digest::GroupDigest>>,
+    /// Cancelled when the server starts shutting down.
+    pub shutdown: tokio_util::sync::CancellationToken,
+    /// Daily message quota, when running as a platform tenant.
+    pub quota: Option<Arc<dyn bizclaw_core::quota::MessageQuota>>,
+    /// Channels running in this process (`serve --channels`).
+    pub channels: Option<Arc<bizclaw_channels::manager::ChannelManager>>,
+    /// Where `/api/v1/messages` pushes go: the running channels, or else
+    /// those that can send without running (Telegram, Discord).
+    pub router: Arc<bizclaw_channels::router::ChannelRouter>,
+    /// Live events for WebSocket clients and `/api/v1/events`.
+    pub events: tokio::sync::broadcast::Sender<super::events::PlatformEvent>,
+    /// Models' context windows, looked up once per config.
+    pub context_lengths: Arc<super::ws::ContextLengths>,
+}
+
+impl AppState {
+    /// Receive the new config each time it changes.
+    pub fn subscribe_config(&self) -> tokio::sync::watch::Receiver<BizClawConfig> {
+        self.config_tx.subscribe()
+    }
+
+    /// Notify subscribers of a new config. Cached context windows are
+    /// dropped first, so they are looked up again for the new config.
+    pub fn publish_config(&self, config: BizClawConfig) {
+        self.context_lengths.clear();
+        self.config_tx.send_replace(config);
+    }
+
+    /// The metrics now, with the running channels' dropped redeliveries.
+    pub fn metrics_snapshot(&self) -> super::metrics::MetricsSnapshot {
+        let mut snapshot = self.metrics.snapshot(self.start_time.elapsed());
+        snapshot.inbound_duplicates_total = self.channels.as_ref().map_or(0, |channels| channels.duplicates());
+        snapshot
+    }
+
+    /// Send `event` to whoever is listening, if anyone.
+    pub fn publish_event(&self, event: super::events::PlatformEvent) {
+        let _ = self.events.send(event);
+    }
 }
 
 /// Serve the dashboard HTML page.
@@ -23,7 +75,116 @@ async fn dashboard_page() -> Html<&'static str> {
     Html(super::dashboard::dashboard_html())
 }
 
-/// Pairing code auth middleware — validates X-Pairing-Code header or ?code= query.
+/// The dashboard: files from `static_dir` when it is set and exists,
+/// otherwise the embedded page. Either way, unknown paths get the index page
+/// so the SPA's path-based routes (/dashboard, /chat, ...) work.
+fn frontend(config: &GatewayConfig) -> Router<Arc<AppState>> {
+    let dir = config.static_dir.as_deref().filter(|d| !d.is_empty()).map(PathBuf::from);
+    let Some(dir) = dir.filter(|d| {
+        let exists = d.is_dir();
+        if !exists {
+            tracing::warn!("Dashboard static_dir '{}' not found; using the built-in dashboard", d.display());
+        }
+        exists
+    }) else {
+        return Router::new().route("/", get(dashboard_page)).fallback(get(dashboard_page));
+    };
+
+    let index = dir.join("index.html");
+    let spa_index = get(move || {
+        let index = index.clone();
+        async move {
+            match tokio::fs::read_to_string(&index).await {
+                Ok(html) => Html(std::borrow::Cow::Owned(html)),
+                Err(_) => Html(std::borrow::Cow::Borrowed(super::dashboard::dashboard_html())),
+            }
+        }
+    });
+    let max_age = config.static_max_age_secs;
+    let files = tower::ServiceBuilder::new()
+        .layer(tower_http::set_header::SetResponseHeaderLayer::overriding(
+            axum::http::header::CACHE_CONTROL,
+            move |resp: &axum::http::Response<_>| Some(cache_control(resp, max_age)),
+        ))
+        .service(tower_http::services::ServeDir::new(dir).fallback(spa_index));
+    Router::new().fallback_service(files)
+}
+
+/// HTML must be revalidated so a new build is picked up; other assets can be
+/// cached for `max_age` seconds.
+fn cache_control<B>(resp: &axum::http::Response<B>, max_age: u64) -> axum::http::HeaderValue {
+    let is_html = resp.headers().get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.starts_with("text/html"));
+    if is_html {
+        axum::http::HeaderValue::from_static("no-cache")
+    } else {
+        axum::http::HeaderValue::from_str(&format!("public, max-age={max_age}"))
+            .expect("cache-control value is ASCII")
+    }
+}
+
+/// WebSocket subprotocol the dashboard offers, and the server selects.
+pub const WS_PROTOCOL: &str = "bizclaw";
+/// Prefix of the subprotocol entry carrying the pairing code, since a
+/// browser WebSocket can't set headers: `bizclaw-pairing.<code>`.
+const WS_PAIRING_PREFIX: &str = "bizclaw-pairing.";
+/// Cookie that may carry the pairing code, for `EventSource`, which can't
+/// set headers either. Only `GET` on [`PAIRING_COOKIE_PATH`] accepts it, so a
+/// cross-site form can't ride on it to change anything.
+const PAIRING_COOKIE: &str = "bizclaw_pairing";
+const PAIRING_COOKIE_PATH: &str = "/api/v1/events";
+
+/// Compare pairing codes in constant time.
+///
+/// Only the length can leak, and every code has the same length.
+pub(crate) fn code_matches(presented: &str, expected: &str) -> bool {
+    use subtle::ConstantTimeEq;
+    presented.as_bytes().ct_eq(expected.as_bytes()).into()
+}
+
+/// Pairing codes presented with a request: the `X-Pairing-Code` header,
+/// `Authorization: Bearer`, the `bizclaw_pairing` cookie on
+/// `GET /api/v1/events`, the WebSocket subprotocol entry, and `?code=` on GET
+/// when `gateway.allow_query_pairing_code` is set.
+fn presented_codes(req: &axum::http::Request<axum::body::Body>, allow_query: bool) -> Vec<&str> {
+    let headers = req.headers();
+    let mut codes: Vec<&str> = headers.get("X-Pairing-Code")
+        .and_then(|v| v.to_str().ok())
+        .into_iter()
+        .collect();
+    codes.extend(headers.get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer ")));
+    if req.method() == axum::http::Method::GET && req.uri().path() == PAIRING_COOKIE_PATH {
+        codes.extend(headers.get_all(axum::http::header::COOKIE)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(';'))
+            .filter_map(|c| c.trim().strip_prefix(PAIRING_COOKIE).and_then(|c| c.strip_prefix('='))));
+    }
+    codes.extend(headers.get_all(axum::http::header::SEC_WEBSOCKET_PROTOCOL)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .filter_map(|p| p.trim().strip_prefix(WS_PAIRING_PREFIX)));
+    if allow_query
+        && req.method() == axum::http::Method::GET
+        && let Some(query) = req.uri().query()
+    {
+        codes.extend(query.split('&').filter_map(|pair| pair.strip_prefix("code=")));
+    }
+    codes.retain(|c| !c.is_empty());
+    codes
+}
+
+/// Pairing code auth middleware.
+///
+/// The code is accepted from the `X-Pairing-Code` header, an
+/// `Authorization: Bearer` header, the dashboard's WebSocket subprotocol
+/// entry, the `bizclaw_pairing` cookie (on `GET /api/v1/events` only, for
+/// `EventSource`), and `?code=` on GET requests when
+/// `gateway.allow_query_pairing_code` is set (see [`presented_codes`]).
 async fn require_pairing(
     State(state): State<Arc<AppState>>,
     req: axum::http::Request<axum::body::Body>,
@@ -34,51 +195,234 @@ async fn require_pairing(
         return next.run(req).await;
     };
 
-    // Check header first
-    let from_header = req.headers()
-        .get("X-Pairing-Code")
-        .and_then(|v| v.to_str().ok())
-        .unwrap_or("");
-    if from_header == expected {
+    let ClientIp(ip) = ClientIp::from_extensions(req.extensions());
+    if let Some(lockout) = &state.rate_limits.lockout
+        && let Err(wait) = lockout.check(ip)
+    {
+        return too_many_requests(wait, "Too many wrong pairing codes — try again later");
+    }
+
+    let codes = presented_codes(&req, state.gateway_config.allow_query_pairing_code);
+    // Check every candidate so the time taken doesn't depend on which matched.
+    let matched = codes.iter().fold(false, |ok, code| code_matches(code, expected) | ok);
+    if matched {
         return next.run(req).await;
     }
 
-    // Check query param ?code=
-    if let Some(query) = req.uri().query() {
-        for pair in query.split('&') {
-            if let Some(code) = pair.strip_prefix("code=") {
-                if code == expected {
-                    return next.run(req).await;
-                }
+    // Only wrong guesses count towards the lockout, not requests without a code.
+    if !codes.is_empty() {
+        record_pairing_failure(&state, ip);
+    }
+    error_response(axum::http::StatusCode::UNAUTHORIZED, "Unauthorized — invalid or missing pairing code")
+}
+
+/// Whether a `/ws` connection presented the pairing code in its handshake.
+/// If not, the socket must authenticate with an `auth` message first.
+#[derive(Debug, Clone, Copy)]
+pub struct WsPaired(pub bool);
+
+/// Pairing for `/ws` upgrades.
+///
+/// Browsers can't set headers on a WebSocket, so an upgrade without any code
+/// is let through and authenticated by its first message instead (see
+/// [`crate::ws`]). A wrong code in the handshake is still refused here.
+async fn ws_pairing(
+    State(state): State<Arc<AppState>>,
+    mut req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let paired = match &state.pairing_code {
+        None => true,
+        Some(expected) => {
+            let ClientIp(ip) = ClientIp::from_extensions(req.extensions());
+            if let Some(lockout) = &state.rate_limits.lockout
+                && let Err(wait) = lockout.check(ip)
+            {
+                return too_many_requests(wait, "Too many wrong pairing codes — try again later");
+            }
+            let codes = presented_codes(&req, state.gateway_config.allow_query_pairing_code);
+            let matched = codes.iter().fold(false, |ok, code| code_matches(code, expected) | ok);
+            if !matched && !codes.is_empty() {
+                record_pairing_failure(&state, ip);
+                return error_response(axum::http::StatusCode::UNAUTHORIZED, "Unauthorized — invalid pairing code");
             }
+            matched
         }
+    };
+    req.extensions_mut().insert(WsPaired(paired));
+    next.run(req).await
+}
+
+/// The request URI for logs, with `code=` query values masked.
+pub(crate) fn redacted_uri(uri: &axum::http::Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.to_string();
+    };
+    let query: Vec<String> = query.split('&')
+        .map(|pair| if pair.starts_with("code=") { "code=***".to_string() } else { pair.to_string() })
+        .collect();
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+pub(crate) fn record_pairing_failure(state: &AppState, ip: std::net::IpAddr) {
+    if let Some(lockout) = &state.rate_limits.lockout
+        && lockout.record_failure(ip)
+    {
+        tracing::warn!(target: "bizclaw::audit", %ip, "pairing locked out after repeated wrong codes");
+        state.publish_event(super::events::PlatformEvent::audit("pairing_locked_out", format!("ip={ip}")));
+    }
+}
+
+/// Per-IP limit on every request.
+async fn rate_limit(
+    State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    if let Some(limiter) = &state.rate_limits.requests
+        && let Err(wait) = limiter.check(ip)
+    {
+        return too_many_requests(wait, "Too many requests");
+    }
+    next.run(req).await
+}
+
+/// Tighter per-IP limit for `/api/v1/verify-pairing`.
+async fn rate_limit_pairing(
+    State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    if let Some(limiter) = &state.rate_limits.pairing
+        && let Err(wait) = limiter.check(ip)
+    {
+        return too_many_requests(wait, "Too many pairing attempts");
+    }
+    next.run(req).await
+}
+
+/// CSRF guard — refuse state-changing requests and WebSocket upgrades sent by
+/// another site's page.
+///
+/// Browsers attach `Origin` to these requests; it must match the gateway's
+/// own host or be listed in `gateway.allowed_origins`. Requests without an
+/// `Origin` (curl, server-to-server) are not browser-initiated and pass.
+async fn check_origin(
+    State(state): State<Arc<AppState>>,
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::http::{Method, header};
+
+    let is_upgrade = req.headers().contains_key(header::UPGRADE);
+    let safe = matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS) && !is_upgrade;
+    let Some(origin) = req.headers().get(header::ORIGIN).and_then(|v| v.to_str().ok()) else {
+        return next.run(req).await;
+    };
+    let host = req.headers().get(header::HOST).and_then(|v| v.to_str().ok()).unwrap_or("");
+    if safe || origin_allowed(origin, host, &state.gateway_config.allowed_origins) {
+        return next.run(req).await;
+    }
+
+    tracing::warn!(target: "bizclaw::audit", origin, path = %req.uri().path(), "cross-origin request refused");
+    error_response(axum::http::StatusCode::FORBIDDEN, "Forbidden — cross-origin request")
+}
+
+/// Whether `origin` is the gateway itself (`host`) or explicitly allowed.
+fn origin_allowed(origin: &str, host: &str, allowed: &[String]) -> bool {
+    let origin = origin.trim_end_matches('/');
+    let authority = origin.split_once("://").map_or(origin, |(_, rest)| rest);
+    (!host.is_empty() && authority.eq_ignore_ascii_case(host))
+        || allowed.iter().any(|a| a == "*" || a.trim_end_matches('/').eq_ignore_ascii_case(origin))
+}
+
+/// CORS for `gateway.allowed_origins`; `None` (no CORS headers) keeps the
+/// browser's same-origin default.
+fn cors_layer(allowed: &[String]) -> Option<CorsLayer> {
+    use axum::http::{HeaderName, HeaderValue, Method, header};
+    use tower_http::cors::AllowOrigin;
+
+    if allowed.is_empty() {
+        return None;
     }
+    let origins = if allowed.iter().any(|a| a == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(allowed.iter().filter_map(|a| HeaderValue::from_str(a.trim_end_matches('/')).ok()))
+    };
+    Some(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([header::CONTENT_TYPE, HeaderName::from_static("x-pairing-code")]))
+}
 
+/// 429 with `Retry-After` in whole seconds.
+fn too_many_requests(wait: std::time::Duration, message: &str) -> axum::response::Response {
+    let mut resp = error_response(axum::http::StatusCode::TOO_MANY_REQUESTS, message);
+    let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    resp.headers_mut().insert(axum::http::header::RETRY_AFTER, secs.max(1).into());
+    resp
+}
+
+fn error_response(status: axum::http::StatusCode, message: &str) -> axum::response::Response {
     axum::response::Response::builder()
-        .status(axum::http::StatusCode::UNAUTHORIZED)
+        .status(status)
         .header("Content-Type", "application/json")
         .body(axum::body::Body::from(
-            serde_json::json!({"ok": false, "error": "Unauthorized — invalid or missing pairing code"}).to_string()
+            serde_json::json!({"ok": false, "error": message}).to_string()
         ))
         .unwrap()
 }
 
+/// Count every request for `/api/v1/metrics`.
+async fn count_requests(
+    State(state): State<Arc<AppState>>,
+    req: axum::http::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    state.metrics.record_request();
+    next.run(req).await
+}
+
 /// Verify pairing code endpoint (public).
 async fn verify_pairing(
     State(state): State<Arc<AppState>>,
+    ClientIp(ip): ClientIp,
     Json(body): Json<serde_json::Value>,
-) -> Json<serde_json::Value> {
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
     let code = body["code"].as_str().unwrap_or("");
-    match &state.pairing_code {
-        Some(expected) if code == expected => Json(serde_json::json!({"ok": true})),
-        Some(_) => Json(serde_json::json!({"ok": false, "error": "Invalid pairing code"})),
-        None => Json(serde_json::json!({"ok": true})), // no code required
+    let Some(expected) = &state.pairing_code else {
+        return Json(serde_json::json!({"ok": true})).into_response(); // no code required
+    };
+    if let Some(lockout) = &state.rate_limits.lockout
+        && let Err(wait) = lockout.check(ip)
+    {
+        return too_many_requests(wait, "Too many wrong pairing codes — try again later");
+    }
+    if code_matches(code, expected) {
+        if let Some(lockout) = &state.rate_limits.lockout {
+            lockout.record_success(ip);
+        }
+        Json(serde_json::json!({"ok": true})).into_response()
+    } else {
+        record_pairing_failure(&state, ip);
+        Json(serde_json::json!({"ok": false, "error": "Invalid pairing code"})).into_response()
     }
 }
 
 /// Build the Axum router with all routes.
 pub fn build_router(state: AppState) -> Router {
-    let shared = Arc::new(state);
+    router(Arc::new(state))
+}
+
+fn router(shared: Arc<AppState>) -> Router {
+    // Room for the multipart envelope around a maximum-size file.
+    let upload_limit = (shared.gateway_config.max_upload_mb * 1024 * 1024 + 64 * 1024) as usize;
+    let cors = cors_layer(&shared.gateway_config.allowed_origins);
 
     // Protected routes — require valid pairing code
     let protected = Router::new()
@@ -86,69 +430,264 @@ pub fn build_router(state: AppState) -> Router {
         .route("/api/v1/config", get(super::routes::get_config))
         .route("/api/v1/config/update", post(super::routes::update_config))
         .route("/api/v1/config/full", get(super::routes::get_full_config))
+        .route("/api/v1/config/reload", post(super::routes::reload_config))
+        .route("/api/v1/config/rotate-key", post(super::routes::rotate_key))
         .route("/api/v1/providers", get(super::routes::list_providers))
         .route("/api/v1/channels", get(super::routes::list_channels))
+        .route("/api/v1/tools", get(super::routes::list_tools))
+        .route("/api/v1/tools/validate", get(super::routes::validate_tools))
+        .route("/api/v1/notes", get(super::routes::list_notes))
+        .route("/api/v1/metrics", get(super::routes::metrics))
+        .route("/api/v1/approvals", get(super::routes::list_approvals))
+        .route("/api/v1/approvals/{id}", post(super::routes::resolve_approval))
+        .route(
+            "/api/v1/upload",
+            post(super::routes::upload_file).layer(axum::extract::DefaultBodyLimit::max(upload_limit)),
+        )
+        .route("/api/v1/upload/{file_id}", delete(super::routes::delete_upload))
         .route("/api/v1/channels/update", post(super::routes::update_channel))
+        .route("/api/v1/channels/webhook/deliveries", get(super::routes::list_webhook_deliveries))
+        .route("/api/v1/webhooks/test-transform", get(super::routes::test_webhook_transform))
+        .route("/api/v1/channels/whatsapp/deliveries", get(super::routes::list_whatsapp_deliveries))
+        .route("/api/v1/channels/whatsapp/send", post(super::routes::send_whatsapp))
+        .route("/api/v1/channels/{name}/reconnect", post(super::routes::force_reconnect))
+        .route("/api/v1/events", get(super::events::events))
+        .route("/api/v1/messages/send", post(super::routes::send_message))
+        .route("/api/v1/messages/broadcast", post(super::routes::broadcast_message))
+        .route("/api/v1/brain/tokenize", get(super::routes::tokenize))
+        .route("/api/v1/brain/count-tokens", get(super::routes::count_tokens))
+        .route("/api/v1/safety/test", get(super::routes::test_safety))
+        .route("/api/v1/quota", get(super::routes::get_quota))
         .route("/api/v1/zalo/qr", post(super::routes::zalo_qr_code))
-        .route("/ws", get(super::ws::ws_handler))
+        .route("/api/v1/groups", get(super::routes::list_groups))
+        .route("/api/v1/groups/{group_id}/summarize", post(super::routes::summarize_group))
+        .route("/api/v1/groups/{group_id}/settings", put(super::routes::update_group_settings))
         .route_layer(axum::middleware::from_fn_with_state(shared.clone(), require_pairing));
 
+    // WebSocket — pairing checked in the handshake or by the first message
+    let websocket = Router::new()
+        .route("/ws", get(super::ws::ws_handler))
+        .route_layer(axum::middleware::from_fn_with_state(shared.clone(), ws_pairing));
+
     // Public routes — no auth
     let public = Router::new()
-        .route("/", get(dashboard_page))
         .route("/health", get(super::routes::health_check))
-        .route("/api/v1/verify-pairing", post(verify_pairing));
+        .route(
+            "/api/v1/verify-pairing",
+            post(verify_pairing).layer(axum::middleware::from_fn_with_state(shared.clone(), rate_limit_pairing)),
+        );
 
-    // SPA fallback — serve dashboard HTML for all frontend routes
-    // so that /dashboard, /chat, /settings etc. all work with path-based routing
-    let spa_fallback = Router::new()
-        .fallback(get(dashboard_page));
-
-    protected.merge(public).merge(spa_fallback)
-        .layer(CorsLayer::permissive())
-        .layer(TraceLayer::new_for_http())
+    protected.merge(websocket).merge(public).merge(frontend(&shared.gateway_config))
+        .layer(axum::middleware::from_fn_with_state(shared.clone(), check_origin))
+        .layer(axum::middleware::from_fn_with_state(shared.clone(), rate_limit))
+        .layer(axum::middleware::from_fn_with_state(shared.clone(), count_requests))
+        .layer(tower::util::option_layer(cors))
+        .layer(TraceLayer::new_for_http().make_span_with(|req: &axum::http::Request<axum::body::Body>| {
+            tracing::debug_span!("request", method = %req.method(), uri = %redacted_uri(req.uri()), version = ?req.version())
+        }))
         .with_state(shared)
 }
 
-/// Start the HTTP server.
-pub async fn start(config: &GatewayConfig) -> anyhow::Result<()> {
+/// Reload the config file on SIGUSR1, e.g. after the platform rotates a tenant's key.
+#[cfg(unix)]
+fn spawn_reload_on_signal(state: Arc<AppState>) {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut usr1 = match signal(SignalKind::user_defined1()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Cannot listen for SIGUSR1: {e}");
+            return;
+        }
+    };
+    tokio::spawn(async move {
+        while usr1.recv().await.is_some() {
+            if let Err(e) = super::routes::reload_from_disk(&state).await {
+                tracing::warn!("SIGUSR1 reload failed: {e}");
+            }
+        }
+    });
+}
+
+#[cfg(not(unix))]
+fn spawn_reload_on_signal(_state: Arc<AppState>) {}
+
+/// Resolve on Ctrl-C (SIGINT) or SIGTERM.
+async fn shutdown_signal() {
+    let interrupt = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Cannot listen for Ctrl-C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{SignalKind, signal};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => { term.recv().await; }
+            Err(e) => {
+                tracing::warn!("Cannot listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = interrupt => {}
+        _ = terminate => {}
+    }
+}
+
+/// How often groups are checked for a due summary.
+const GROUP_SUMMARY_TICK: std::time::Duration = std::time::Duration::from_secs(60);
+
+/// Group summaries through the configured provider, delivered via the
+/// configured Telegram/Discord bots.
+fn build_group_digest(config: &BizClawConfig) -> Option<bizclaw_tools::group_digest::GroupDigest> {
+    let summarizer = &config.tools.group_summarizer;
+    if !summarizer.enabled {
+        return None;
+    }
+    let built = bizclaw_tools::group_digest::GroupSettingsStore::from_config().and_then(|settings| {
+        let provider = bizclaw_providers::create_provider(config)?;
+        let model = Some(summarizer.model.clone())
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| config.default_model.clone());
+        Ok(bizclaw_tools::group_digest::GroupDigest::new(
+            bizclaw_tools::group_summarizer::MessageBuffer::global(),
+            Arc::new(settings),
+            summarizer.into(),
+            Arc::from(provider),
+            model,
+            bizclaw_channels::senders(&config.channel),
+        ))
+    });
+    built.inspect_err(|e| tracing::warn!("Group summaries disabled: {e}")).ok()
+}
+
+/// Start the HTTP server. With a `quota`, chat messages are refused once
+/// the tenant's daily limit is reached; `channels` can be reconnected
+/// through the API.
+pub async fn start(
+    config: &GatewayConfig,
+    quota: Option<Arc<dyn bizclaw_core::quota::MessageQuota>>,
+    channels: Option<Arc<bizclaw_channels::manager::ChannelManager>>,
+) -> anyhow::Result<()> {
     // Load full config for settings UI
     let config_path = std::env::var("BIZCLAW_CONFIG")
         .map(PathBuf::from)
         .unwrap_or_else(|_| BizClawConfig::default_path());
-    let full_config = if config_path.exists() {
-        BizClawConfig::load_from(&config_path).unwrap_or_default()
-    } else {
-        BizClawConfig::default()
-    };
+    let full_config = BizClawConfig::load_with_env(Some(&config_path).filter(|p| p.exists()).map(|p| p.as_path()))
+        .unwrap_or_default();
 
+    let group_digest = build_group_digest(&full_config).map(Arc::new);
+    let channel_router = match &channels {
+        Some(manager) => bizclaw_channels::router::ChannelRouter::from(manager.as_ref()),
+        None => bizclaw_channels::router::ChannelRouter::from(bizclaw_channels::senders(&full_config.channel)),
+    };
+    let auto_summary = full_config.tools.group_summarizer.auto_summary;
+    let uploads_dir = full_config.autonomy.workspace_dir().join("uploads");
+    let (config_tx, _) = tokio::sync::watch::channel(full_config.clone());
     let state = AppState {
         gateway_config: config.clone(),
-        full_config: Arc::new(Mutex::new(full_config)),
+        full_config: Arc::new(tokio::sync::RwLock::new(full_config)),
         config_path: config_path.clone(),
         start_time: std::time::Instant::now(),
         pairing_code: if config.require_pairing {
             // Read pairing code from platform DB or generate one
-            let code = std::env::var("BIZCLAW_PAIRING_CODE").ok()
+            std::env::var("BIZCLAW_PAIRING_CODE").ok()
                 .or_else(|| {
                     // Try to extract from config directory
                     config_path.parent().and_then(|d| {
                         let pc = d.join(".pairing_code");
                         std::fs::read_to_string(pc).ok().map(|s| s.trim().to_string())
                     })
-                });
-            code
+                })
         } else {
             None
         },
+        metrics: Default::default(),
+        config_tx: Arc::new(config_tx),
+        approvals: bizclaw_security::approval::ApprovalBroker::global(),
+        uploads: Arc::new(super::uploads::UploadRegistry::new(uploads_dir)),
+        rate_limits: Arc::new(super::rate_limit::RateLimits::from_config(&config.rate_limit)),
+        group_digest,
+        shutdown: Default::default(),
+        quota,
+        channels,
+        router: Arc::new(channel_router),
+        events: super::events::channel(),
+        context_lengths: Default::default(),
     };
 
-    let app = build_router(state);
+    // Summarize groups on their window without being asked.
+    if let Some(digest) = &state.group_digest
+        && auto_summary
+    {
+        bizclaw_tools::group_digest::spawn_runner(digest.clone(), GROUP_SUMMARY_TICK);
+    }
+
+    // Delete uploads once they pass their TTL.
+    let uploads = state.uploads.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            let removed = uploads.remove_expired(super::uploads::UPLOAD_TTL);
+            if removed > 0 {
+                tracing::info!("🧹 Removed {removed} expired upload(s)");
+            }
+        }
+    });
+
+    let state = Arc::new(state);
+    spawn_reload_on_signal(state.clone());
+    super::events::spawn_monitor(state.clone());
+
+    // Forget idle clients' rate-limit state.
+    let rate_limits = state.rate_limits.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(300));
+        loop {
+            interval.tick().await;
+            rate_limits.prune();
+        }
+    });
+
+    let shutdown = state.shutdown.clone();
+    tokio::spawn({
+        let state = state.clone();
+        async move {
+            shutdown_signal().await;
+            tracing::info!("🛑 Shutting down; waiting for in-flight requests");
+            state.publish_event(super::events::PlatformEvent::TenantStatusChanged {
+                tenant_id: std::env::var("BIZCLAW_TENANT_ID").unwrap_or_default(),
+                status: "stopping".into(),
+            });
+            state.shutdown.cancel();
+        }
+    });
+
+    let app = router(state);
     let addr = format!("{}:{}", config.host, config.port);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
 
     tracing::info!("🌐 Gateway server listening on http://{}", addr);
 
-    axum::serve(listener, app).await?;
+    // Once signalled, stop accepting connections and let open ones finish
+    // (WebSockets are closed by their handlers), up to the timeout.
+    let server = axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+        .with_graceful_shutdown(shutdown.clone().cancelled_owned());
+    let grace = std::time::Duration::from_secs(config.shutdown_timeout_secs);
+    tokio::select! {
+        result = std::future::IntoFuture::into_future(server) => result?,
+        _ = async {
+            shutdown.cancelled().await;
+            tokio::time::sleep(grace).await;
+        } => tracing::warn!("Requests still running after {}s; exiting anyway", grace.as_secs()),
+    }
+    tracing::info!("Gateway stopped");
     Ok(())
 }
@@ -0,0 +1,187 @@
+//! Uploaded files — stored under `<workspace>/uploads/` so the agent can
+//! reference them, and deleted after `UPLOAD_TTL`.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// How long an upload is kept before the cleanup task deletes it.
+pub const UPLOAD_TTL: std::time::Duration = std::time::Duration::from_secs(24 * 3600);
+
+/// Text included inline when an upload is attached to a chat message.
+const MAX_INLINE_TEXT_BYTES: usize = 16 * 1024;
+
+/// Metadata for one stored upload.
+#[derive(Debug, Clone, Serialize)]
+pub struct UploadedFile {
+    pub file_id: String,
+    pub path: PathBuf,
+    /// Original client-side file name.
+    pub filename: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    pub tenant_id: Option<String>,
+    pub uploaded_at: DateTime<Utc>,
+}
+
+/// In-memory index of uploads, shared through `AppState`.
+#[derive(Debug)]
+pub struct UploadRegistry {
+    dir: PathBuf,
+    files: Mutex<HashMap<String, UploadedFile>>,
+}
+
+impl UploadRegistry {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into(), files: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Validate and store an upload as `<dir>/<uuid>.<ext>`.
+    pub async fn save(&self, filename: &str, tenant_id: Option<String>, bytes: &[u8]) -> Result<UploadedFile, String> {
+        let ext = Path::new(filename).extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_lowercase)
+            .filter(|e| !e.is_empty() && e.len() <= 10 && e.chars().all(|c| c.is_ascii_alphanumeric()))
+            .ok_or_else(|| format!("'{filename}' has no usable file extension"))?;
+        let mime_type = detect_mime(&ext, bytes)?;
+
+        let file_id = uuid::Uuid::new_v4().to_string();
+        let path = self.dir.join(format!("{file_id}.{ext}"));
+        tokio::fs::create_dir_all(&self.dir).await
+            .map_err(|e| format!("Cannot create {}: {e}", self.dir.display()))?;
+        tokio::fs::write(&path, bytes).await
+            .map_err(|e| format!("Cannot write {}: {e}", path.display()))?;
+
+        let file = UploadedFile {
+            file_id: file_id.clone(),
+            path,
+            filename: filename.to_string(),
+            mime_type,
+            size_bytes: bytes.len() as u64,
+            tenant_id,
+            uploaded_at: Utc::now(),
+        };
+        self.files.lock().unwrap().insert(file_id, file.clone());
+        Ok(file)
+    }
+
+    pub fn get(&self, file_id: &str) -> Option<UploadedFile> {
+        self.files.lock().unwrap().get(file_id).cloned()
+    }
+
+    /// Forget an upload and delete its file.
+    pub fn remove(&self, file_id: &str) -> Option<UploadedFile> {
+        let file = self.files.lock().unwrap().remove(file_id)?;
+        if let Err(e) = std::fs::remove_file(&file.path) {
+            tracing::warn!("Failed to delete upload {}: {e}", file.path.display());
+        }
+        Some(file)
+    }
+
+    /// Delete uploads older than `max_age`; returns how many were removed.
+    pub fn remove_expired(&self, max_age: std::time::Duration) -> usize {
+        let max_age = chrono::Duration::from_std(max_age).unwrap_or(chrono::Duration::MAX);
+        let cutoff = Utc::now() - max_age;
+        let expired: Vec<String> = self.files.lock().unwrap()
+            .values()
+            .filter(|f| f.uploaded_at <= cutoff)
+            .map(|f| f.file_id.clone())
+            .collect();
+        for id in &expired {
+            self.remove(id);
+        }
+        expired.len()
+    }
+
+    /// Prompt text describing an upload, with the content inlined for small text files.
+    pub fn describe_for_prompt(&self, file_id: &str) -> Option<String> {
+        let file = self.get(file_id)?;
+        let mut out = format!(
+            "[Attached file: {} ({}, {} bytes) at {}]",
+            file.filename, file.mime_type, file.size_bytes, file.path.display()
+        );
+        if is_text_mime(&file.mime_type)
+            && file.size_bytes as usize <= MAX_INLINE_TEXT_BYTES
+            && let Ok(text) = std::fs::read_to_string(&file.path)
+        {
+            out.push_str(&format!("\n```\n{text}\n```"));
+        }
+        Some(out)
+    }
+}
+
+/// MIME type for `bytes`, which must agree with the extension.
+///
+/// Binary formats are identified by their magic bytes; files without a
+/// signature are accepted only for text extensions and must be UTF-8.
+fn detect_mime(ext: &str, bytes: &[u8]) -> Result<String, String> {
+    let guesses: Vec<String> = mime_guess::from_ext(ext).iter().map(|m| m.essence_str().to_string()).collect();
+    let Some(expected) = guesses.first().cloned() else {
+        return Err(format!("Unsupported file type: .{ext}"));
+    };
+
+    match infer::get(bytes) {
+        Some(kind) if kind.extension() == ext || guesses.iter().any(|g| g == kind.mime_type()) => {
+            Ok(kind.mime_type().to_string())
+        }
+        Some(kind) => Err(format!(
+            "File content is {} but the extension .{ext} implies {expected}",
+            kind.mime_type()
+        )),
+        None if is_text_mime(&expected) && std::str::from_utf8(bytes).is_ok() => Ok(expected),
+        None => Err(format!("File content does not match the extension .{ext}")),
+    }
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/")
+        || matches!(mime, "application/json" | "application/xml" | "application/toml" | "application/x-yaml")
+        || mime.ends_with("+xml")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 0x0D, b'I', b'H', b'D', b'R'];
+
+    fn registry() -> UploadRegistry {
+        UploadRegistry::new(std::env::temp_dir().join(format!("bizclaw-uploads-{}", uuid::Uuid::new_v4().simple())))
+    }
+
+    #[test]
+    fn test_detect_mime() {
+        assert_eq!(detect_mime("png", PNG).unwrap(), "image/png");
+        assert_eq!(detect_mime("csv", b"a,b\n1,2\n").unwrap(), "text/csv");
+        assert!(detect_mime("jpg", PNG).is_err());
+        assert!(detect_mime("csv", PNG).is_err());
+        assert!(detect_mime("png", b"not an image").is_err());
+        assert!(detect_mime("zzz", b"x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_save_remove_and_expire() {
+        let reg = registry();
+        let file = reg.save("report.CSV", Some("t1".into()), b"a,b\n").await.unwrap();
+        assert_eq!(file.path, reg.dir().join(format!("{}.csv", file.file_id)));
+        assert_eq!(file.size_bytes, 4);
+        assert!(file.path.exists());
+        assert!(reg.describe_for_prompt(&file.file_id).unwrap().contains("a,b"));
+
+        assert!(reg.save("noext", None, b"x").await.is_err());
+        assert!(reg.save("../../x.png", None, b"text").await.is_err());
+
+        let other = reg.save("pixel.png", None, PNG).await.unwrap();
+        assert_eq!(reg.remove_expired(UPLOAD_TTL), 0);
+        assert_eq!(reg.remove_expired(std::time::Duration::ZERO), 2);
+        assert!(!file.path.exists());
+        assert!(reg.get(&other.file_id).is_none());
+        std::fs::remove_dir_all(reg.dir()).ok();
+    }
+}
@@ -1,175 +1,390 @@
 //! WebSocket handler for real-time streaming chat via gateway.
 //!
-//! Protocol:
-//! → Client sends: {"type":"chat","content":"...","stream":true}
-//! ← Server sends: {"type":"chat_start","request_id":"..."}
-//! ← Server sends: {"type":"chat_chunk","request_id":"...","content":"token","index":0}
-//! ← Server sends: {"type":"chat_done","request_id":"...","total_tokens":42}
+//! Messages follow the envelope protocol in [`crate::protocol`]: the client
+//! sends `chat`/`cancel` commands and receives `token`, `done` and `error`
+//! events. The response is generated in a background task so a `cancel` can
+//! stop it mid-stream.
+//!
+//! When the gateway requires pairing and the upgrade request didn't carry the
+//! code, the first message must be `auth`; see [`crate::protocol`].
+//!
+//! The server pings every `gateway.ws_ping_interval_secs` and closes
+//! connections that stay silent past `gateway.ws_pong_timeout_secs`, so
+//! sockets from abandoned tabs or dead proxies don't linger.
 
 use axum::{
-    extract::{State, ws::{Message, WebSocket, WebSocketUpgrade}},
+    extract::{State, ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade, close_code}},
     response::IntoResponse,
 };
+use bizclaw_core::error::Result;
+use bizclaw_core::tokens::{self, TruncationStrategy};
+use bizclaw_core::traits::Provider;
+use bizclaw_core::traits::provider::GenerateParams;
+use bizclaw_core::types::Message as ChatMessage;
+use futures::StreamExt;
+use std::collections::HashMap;
 use std::sync::Arc;
-use super::server::AppState;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use super::protocol::{self, ClientCommand, ServerEvent};
+use super::rate_limit::ClientIp;
+use super::server::{AppState, WsPaired, code_matches, record_pairing_failure};
+use std::net::IpAddr;
+
+/// How long a connection has to send its `auth` message.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
 
 /// WebSocket upgrade handler.
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
     State(state): State<Arc<AppState>>,
+    axum::Extension(WsPaired(paired)): axum::Extension<WsPaired>,
+    ClientIp(ip): ClientIp,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+    // A client may offer the pairing code as a second subprotocol; only the
+    // plain one is echoed back.
+    ws.protocols([super::server::WS_PROTOCOL])
+        .on_upgrade(move |mut socket| async move {
+            if paired || authenticate(&mut socket, &state, ip).await {
+                handle_socket(socket, state).await;
+            }
+        })
 }
 
-/// Resolve Ollama URL from config or env.
-fn ollama_url(_state: &AppState) -> String {
-    // Check env first
-    if let Ok(url) = std::env::var("OLLAMA_HOST") {
-        return url;
-    }
-    "http://localhost:11434".to_string()
+/// Wait for the `auth` message of a connection that didn't present the
+/// pairing code in its handshake. If it is missing, late or wrong, the socket
+/// is closed with a policy-violation code and `false` returned.
+async fn authenticate(socket: &mut WebSocket, state: &AppState, ip: IpAddr) -> bool {
+    let Some(expected) = &state.pairing_code else {
+        return true;
+    };
+    let deadline = Instant::now() + AUTH_TIMEOUT;
+    let reason = loop {
+        let msg = match tokio::time::timeout_at(deadline, socket.recv()).await {
+            Err(_) => break "authentication timed out",
+            Ok(Some(Ok(msg))) => msg,
+            // Gone before authenticating.
+            Ok(_) => return false,
+        };
+        match msg {
+            Message::Text(text) => match serde_json::from_str::<ClientCommand>(&text) {
+                Ok(ClientCommand::Auth(auth)) if code_matches(&auth.code, expected) => {
+                    if let Some(lockout) = &state.rate_limits.lockout {
+                        lockout.record_success(ip);
+                    }
+                    return true;
+                }
+                Ok(ClientCommand::Auth(_)) => {
+                    record_pairing_failure(state, ip);
+                    break "invalid pairing code";
+                }
+                _ => break "authentication required",
+            },
+            Message::Ping(_) | Message::Pong(_) => continue,
+            Message::Close(_) => return false,
+            Message::Binary(_) => break "authentication required",
+        }
+    };
+    tracing::info!("WebSocket client from {ip} refused: {reason}");
+    let _ = socket.send(Message::Close(Some(CloseFrame {
+        code: close_code::POLICY,
+        reason: reason.into(),
+    }))).await;
+    false
 }
 
 /// Get the active model from config.
-fn active_model(state: &AppState) -> String {
-    let config = state.full_config.lock().unwrap();
+async fn active_model(state: &AppState) -> String {
+    let config = state.full_config.read().await;
     let model = config.default_model.clone();
     if model.is_empty() { "tinyllama".to_string() } else { model }
 }
 
 /// Get the active provider from config.
-fn active_provider(state: &AppState) -> String {
-    let config = state.full_config.lock().unwrap();
+async fn active_provider(state: &AppState) -> String {
+    let config = state.full_config.read().await;
     let provider = config.default_provider.clone();
     if provider.is_empty() { "openai".to_string() } else { provider }
 }
 
+/// Context windows from the providers' `ModelInfo`, by provider and model,
+/// so connecting doesn't cost a `list_models` call each time. Cleared
+/// whenever the config is published.
+#[derive(Default)]
+pub struct ContextLengths(std::sync::Mutex<HashMap<(String, String), usize>>);
+
+impl ContextLengths {
+    /// The model's context window, asking `provider` the first time. A failed
+    /// lookup isn't remembered, so the next connection asks again.
+    pub async fn get(&self, provider_name: &str, provider: Option<&dyn Provider>, model: &str) -> usize {
+        let key = (provider_name.to_string(), model.to_string());
+        let cached = self.0.lock().unwrap().get(&key).copied();
+        if let Some(length) = cached {
+            return length;
+        }
+        let Some(provider) = provider else {
+            return tokens::DEFAULT_CONTEXT_LENGTH;
+        };
+        let Ok(models) = provider.list_models().await else {
+            return tokens::DEFAULT_CONTEXT_LENGTH;
+        };
+        let length = tokens::context_length(&models, model);
+        self.0.lock().unwrap().insert(key, length);
+        length
+    }
+
+    /// Forget every cached window (the config changed).
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+/// Build the configured provider; the error is reported on each chat.
+async fn build_provider(state: &AppState) -> std::result::Result<Arc<dyn Provider>, String> {
+    let config = state.full_config.read().await.clone();
+    bizclaw_providers::create_provider(&config)
+        .map(Arc::from)
+        .map_err(|e| e.to_string())
+}
+
+/// The `[safety]` rules responses are checked against, when enabled.
+async fn safety_filter(state: &AppState) -> Option<bizclaw_core::safety::SafetyFilter> {
+    let config = state.full_config.read().await;
+    if !config.safety.enabled {
+        return None;
+    }
+    bizclaw_core::safety::SafetyFilter::from_config(&config.safety)
+        .inspect_err(|e| tracing::warn!("Safety rules not applied: {e}"))
+        .ok()
+}
+
+/// Tracks whether the client is still there.
+struct Heartbeat {
+    interval: Duration,
+    timeout: Duration,
+    last_seen: Instant,
+}
+
+impl Heartbeat {
+    /// `None` when pings are turned off.
+    fn new(config: &bizclaw_core::config::GatewayConfig, now: Instant) -> Option<Self> {
+        (config.ws_ping_interval_secs > 0).then(|| Self {
+            interval: Duration::from_secs(config.ws_ping_interval_secs),
+            timeout: Duration::from_secs(config.ws_pong_timeout_secs),
+            last_seen: now,
+        })
+    }
+
+    /// Any frame from the client, pong or otherwise, shows it is alive.
+    fn seen(&mut self, now: Instant) {
+        self.last_seen = now;
+    }
+
+    /// The client missed the last ping's reply window.
+    fn expired(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_seen) > self.interval + self.timeout
+    }
+}
+
+/// Wait for the next heartbeat tick; never resolves when pings are off.
+async fn next_ping(ticker: &mut Option<tokio::time::Interval>) {
+    match ticker {
+        Some(ticker) => { ticker.tick().await; }
+        None => std::future::pending().await,
+    }
+}
+
+/// Response being generated for this connection.
+struct Generation {
+    request_id: String,
+    stream: bool,
+    content: String,
+    tokens: u64,
+    events: mpsc::Receiver<Result<String>>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl Generation {
+    /// Generate a response to `history` in the background.
+    fn start(provider: Arc<dyn Provider>, history: Vec<ChatMessage>, params: GenerateParams, request_id: String, stream: bool) -> Self {
+        let (tx, events) = mpsc::channel(64);
+        let task = tokio::spawn(async move {
+            let mut tokens = match provider.chat_stream(&history, &params).await {
+                Ok(tokens) => tokens,
+                Err(e) => {
+                    let _ = tx.send(Err(e)).await;
+                    return;
+                }
+            };
+            while let Some(token) = tokens.next().await {
+                let failed = token.is_err();
+                if tx.send(token).await.is_err() || failed {
+                    return;
+                }
+            }
+        });
+        Self { request_id, stream, content: String::new(), tokens: 0, events, task }
+    }
+
+    fn done(&self, cancelled: bool) -> ServerEvent {
+        ServerEvent::Done(protocol::Done {
+            request_id: self.request_id.clone(),
+            content: self.content.clone(),
+            tokens: self.tokens,
+            cancelled,
+        })
+    }
+}
+
+impl Drop for Generation {
+    /// Cancelling or disconnecting stops the provider request.
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Next piece of the response in progress; `None` once it has finished.
+async fn next_token(generation: &mut Option<Generation>) -> Option<Result<String>> {
+    match generation {
+        Some(g) => g.events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
 /// Handle a WebSocket connection.
 async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
     tracing::info!("WebSocket client connected");
 
-    let provider = active_provider(&state);
-    let model = active_model(&state);
+    let mut provider_name = active_provider(&state).await;
+    let mut model = active_model(&state).await;
+    let mut provider = build_provider(&state).await;
+    let mut safety = safety_filter(&state).await;
+    let mut config_rx = state.subscribe_config();
+    let mut events_rx = state.events.subscribe();
 
     // Send welcome
-    let welcome = serde_json::json!({
-        "type": "connected",
-        "message": "BizClaw Gateway — WebSocket connected",
-        "version": env!("CARGO_PKG_VERSION"),
-        "provider": &provider,
-        "model": &model,
-        "capabilities": ["chat", "stream", "ping"],
+    let welcome = ServerEvent::Connected(protocol::Connected {
+        version: env!("CARGO_PKG_VERSION").into(),
+        provider: provider_name.clone(),
+        model: model.clone(),
+        commands: ["chat", "cancel", "ping", "status"].map(String::from).to_vec(),
     });
-    if send_json(&mut socket, &welcome).await.is_err() {
+    if send_event(&mut socket, &welcome).await.is_err() {
         return;
     }
 
+    let mut budget = tokens::prompt_budget(
+        state.context_lengths.get(&provider_name, provider.as_deref().ok(), &model).await,
+    );
+
     let mut request_counter: u64 = 0;
-    let mut history: Vec<serde_json::Value> = vec![
-        serde_json::json!({"role": "system", "content": "Bạn là BizClaw AI Assistant. Trả lời ngắn gọn, hữu ích bằng tiếng Việt. Nếu user nói tiếng Anh thì trả lời tiếng Anh."})
+    let mut history: Vec<ChatMessage> = vec![
+        ChatMessage::system("Bạn là BizClaw AI Assistant. Trả lời ngắn gọn, hữu ích bằng tiếng Việt. Nếu user nói tiếng Anh thì trả lời tiếng Anh.")
     ];
+    let mut generation: Option<Generation> = None;
 
-    // Message loop
-    while let Some(msg) = socket.recv().await {
-        match msg {
-            Ok(Message::Text(text)) => {
-                let json = match serde_json::from_str::<serde_json::Value>(&text) {
-                    Ok(j) => j,
-                    Err(e) => {
-                        send_error(&mut socket, &format!("Invalid JSON: {e}")).await;
-                        continue;
-                    }
-                };
-
-                let msg_type = json["type"].as_str().unwrap_or("unknown");
-
-                match msg_type {
-                    "chat" => {
-                        request_counter += 1;
-                        let request_id = format!("req_{request_counter}");
-                        let content = json["content"].as_str().unwrap_or("").to_string();
-                        let stream = json["stream"].as_bool().unwrap_or(true);
+    let mut heartbeat = Heartbeat::new(&state.full_config.read().await.gateway, Instant::now());
+    let mut ping_ticker = heartbeat.as_ref().map(|h| {
+        let mut ticker = tokio::time::interval_at(Instant::now() + h.interval, h.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ticker
+    });
 
-                        if content.is_empty() {
-                            send_error(&mut socket, "Empty message").await;
+    // Message loop — also relays the response in progress and picks up
+    // provider/model changes from config reloads
+    loop {
+        let msg = tokio::select! {
+            msg = socket.recv() => match msg {
+                Some(msg) => {
+                    if let Some(h) = heartbeat.as_mut() {
+                        h.seen(Instant::now());
+                    }
+                    msg
+                }
+                None => break,
+            },
+            _ = next_ping(&mut ping_ticker) => {
+                if heartbeat.as_ref().is_some_and(|h| h.expired(Instant::now())) {
+                    tracing::info!("WebSocket client stopped responding; closing");
+                    let _ = socket.send(Message::Close(Some(CloseFrame {
+                        code: close_code::AWAY,
+                        reason: "heartbeat timeout".into(),
+                    }))).await;
+                    break;
+                }
+                if socket.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+                continue;
+            }
+            _ = state.shutdown.cancelled() => {
+                if let Some(g) = generation.take() {
+                    let _ = send_event(&mut socket, &g.done(true)).await;
+                }
+                let _ = socket.send(Message::Close(Some(CloseFrame {
+                    code: close_code::AWAY,
+                    reason: "server shutting down".into(),
+                }))).await;
+                break;
+            }
+            token = next_token(&mut generation) => {
+                let event = match token {
+                    Some(Ok(token)) => {
+                        let Some(g) = generation.as_mut() else { continue };
+                        g.content.push_str(&token);
+                        g.tokens += 1;
+                        if !g.stream {
                             continue;
                         }
-
-                        // Add user message to history
-                        history.push(serde_json::json!({"role": "user", "content": &content}));
-
-                        // Keep history manageable (last 20 messages + system)
-                        if history.len() > 21 {
-                            let system = history[0].clone();
-                            let skip = history.len() - 20;
-                            let tail: Vec<_> = history.drain(skip..).collect();
-                            history.clear();
-                            history.push(system);
-                            history.extend(tail);
-                        }
-
-                        tracing::info!("Chat req={request_id}: provider={provider}, model={model}, stream={stream}, len={}", content.len());
-
-                        // Route to provider
-                        let result = match provider.as_str() {
-                            "ollama" | "brain" => {
-                                chat_ollama(&mut socket, &state, &request_id, &history, &model, stream).await
-                            }
-                            "openai" => {
-                                chat_openai(&mut socket, &state, &request_id, &history, &model, stream).await
-                            }
-                            _ => {
-                                // Fallback: try Ollama first, then OpenAI
-                                let r = chat_ollama(&mut socket, &state, &request_id, &history, &model, stream).await;
-                                if r.is_err() {
-                                    chat_openai(&mut socket, &state, &request_id, &history, "gpt-4o-mini", stream).await
-                                } else {
-                                    r
-                                }
-                            }
-                        };
-
-                        match result {
-                            Ok(response) => {
-                                // Add assistant response to history
-                                history.push(serde_json::json!({"role": "assistant", "content": &response}));
-                            }
-                            Err(e) => {
-                                let _ = send_json(&mut socket, &serde_json::json!({
-                                    "type": "chat_error",
-                                    "request_id": &request_id,
-                                    "error": e,
-                                })).await;
-                            }
-                        }
+                        ServerEvent::Token(protocol::Token {
+                            request_id: g.request_id.clone(),
+                            content: token,
+                            index: g.tokens - 1,
+                        })
                     }
-
-                    "ping" => {
-                        let pong = serde_json::json!({
-                            "type": "pong",
-                            "timestamp": chrono::Utc::now().timestamp_millis(),
-                        });
-                        let _ = send_json(&mut socket, &pong).await;
+                    Some(Err(e)) => {
+                        let Some(g) = generation.take() else { continue };
+                        state.metrics.record_provider_call(&provider_name, false);
+                        ServerEvent::Error(protocol::Error { request_id: Some(g.request_id.clone()), message: e.to_string() })
                     }
-
-                    "status" => {
-                        let status = serde_json::json!({
-                            "type": "status",
-                            "requests_processed": request_counter,
-                            "uptime_secs": state.start_time.elapsed().as_secs(),
-                            "provider": &provider,
-                            "model": &model,
-                        });
-                        let _ = send_json(&mut socket, &status).await;
-                    }
-
-                    _ => {
-                        send_error(&mut socket, &format!("Unknown message type: {msg_type}")).await;
+                    None => {
+                        let Some(mut g) = generation.take() else { continue };
+                        state.metrics.record_provider_call(&provider_name, true);
+                        if let Some(filter) = &safety {
+                            g.content = filter.check(&g.content).cleaned_response;
+                        }
+                        history.push(ChatMessage::assistant(&g.content));
+                        g.done(false)
                     }
-                }
+                };
+                let _ = send_event(&mut socket, &event).await;
+                continue;
+            }
+            Ok(event) = events_rx.recv() => {
+                let _ = send_event(&mut socket, &ServerEvent::Event(event)).await;
+                continue;
             }
+            Ok(()) = config_rx.changed() => {
+                provider_name = active_provider(&state).await;
+                model = active_model(&state).await;
+                provider = build_provider(&state).await;
+                safety = safety_filter(&state).await;
+                budget = tokens::prompt_budget(
+                    state.context_lengths.get(&provider_name, provider.as_deref().ok(), &model).await,
+                );
+                let _ = send_event(&mut socket, &ServerEvent::ConfigReloaded(protocol::ConfigReloaded {
+                    provider: provider_name.clone(),
+                    model: model.clone(),
+                })).await;
+                continue;
+            }
+        };
+        let text = match msg {
+            Ok(Message::Text(text)) => text,
             Ok(Message::Ping(data)) => {
                 let _ = socket.send(Message::Pong(data)).await;
+                continue;
             }
             Ok(Message::Close(_)) => {
                 tracing::info!("WebSocket client disconnected (close frame)");
@@ -179,259 +394,183 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
                 tracing::error!("WebSocket error: {e}");
                 break;
             }
-            _ => {}
-        }
-    }
-
-    tracing::info!("WebSocket connection closed (total requests: {request_counter})");
-}
-
-// ═══════════════════════════════════════════════════════════
-// OLLAMA PROVIDER
-// ═══════════════════════════════════════════════════════════
+            _ => continue,
+        };
 
-async fn chat_ollama(
-    socket: &mut WebSocket,
-    state: &AppState,
-    request_id: &str,
-    messages: &[serde_json::Value],
-    model: &str,
-    stream: bool,
-) -> Result<String, String> {
-    let url = ollama_url(state);
-    let client = reqwest::Client::new();
-
-    if stream {
-        // Streaming response
-        let _ = send_json(socket, &serde_json::json!({
-            "type": "chat_start",
-            "request_id": request_id,
-            "provider": "ollama",
-            "model": model,
-        })).await;
-
-        let body = serde_json::json!({
-            "model": model,
-            "messages": messages,
-            "stream": true,
-        });
-
-        let resp = client
-            .post(format!("{url}/api/chat"))
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Ollama connection failed ({}): {}", url, e))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let text = resp.text().await.unwrap_or_default();
-            return Err(format!("Ollama error {status}: {text}"));
-        }
+        let command = match serde_json::from_str::<ClientCommand>(&text) {
+            Ok(command) => command,
+            Err(e) => {
+                let _ = send_event(&mut socket, &ServerEvent::error(format!("Invalid message: {e}"))).await;
+                continue;
+            }
+        };
+
+        let reply = match command {
+            ClientCommand::Chat(chat) => {
+                request_counter += 1;
+                let request_id = chat.request_id.unwrap_or_else(|| format!("req_{request_counter}"));
+                let fail = |message: String| ServerEvent::Error(protocol::Error { request_id: Some(request_id.clone()), message });
+
+                if let Some(g) = &generation {
+                    fail(format!("Response {} is still in progress; cancel it first", g.request_id))
+                } else if chat.content.trim().is_empty() {
+                    fail("Empty message".into())
+                } else if let Some(quota) = &state.quota
+                    && let Err(e) = quota.check_quota().and_then(|_| quota.record_message())
+                {
+                    tracing::info!("Chat req={request_id} refused: {e}");
+                    state.publish_event(crate::events::PlatformEvent::AlertFired {
+                        alert: "quota_exhausted".into(),
+                        message: e.to_string(),
+                    });
+                    fail(e.to_string())
+                } else {
+                    match &provider {
+                        Err(e) => fail(e.clone()),
+                        Ok(provider) => {
+                            let mut content = chat.content;
+                            // Reference uploaded files from `file_ids`
+                            for id in &chat.file_ids {
+                                match state.uploads.describe_for_prompt(id) {
+                                    Some(note) => content.push_str(&format!("\n\n{note}")),
+                                    None => {
+                                        let _ = send_event(&mut socket, &ServerEvent::error(format!("Unknown file_id: {id}"))).await;
+                                    }
+                                }
+                            }
 
-        let mut full_content = String::new();
-        let mut chunk_idx: u64 = 0;
-        let stream_body = resp;
-
-        // Read streaming NDJSON response
-        let bytes = stream_body.bytes().await.map_err(|e| e.to_string())?;
-        let text = String::from_utf8_lossy(&bytes);
-
-        for line in text.lines() {
-            if line.trim().is_empty() { continue; }
-            if let Ok(json) = serde_json::from_str::<serde_json::Value>(line) {
-                if let Some(content) = json["message"]["content"].as_str() {
-                    if !content.is_empty() {
-                        full_content.push_str(content);
-                        let _ = send_json(socket, &serde_json::json!({
-                            "type": "chat_chunk",
-                            "request_id": request_id,
-                            "content": content,
-                            "index": chunk_idx,
-                        })).await;
-                        chunk_idx += 1;
+                            // Add user message to history
+                            history.push(ChatMessage::user(&content));
+
+                            // Keep history within the model's context window
+                            history = tokens::truncate_to_budget(
+                                &model, std::mem::take(&mut history), budget, TruncationStrategy::SummarizeOldest,
+                            );
+                            let prompt_tokens = tokens::count_message_tokens(&model, &history);
+
+                            tracing::info!("Chat req={request_id}: provider={provider_name}, model={model}, stream={}, len={}, prompt_tokens={prompt_tokens}", chat.stream, content.len());
+
+                            let params = GenerateParams {
+                                model: model.clone(),
+                                temperature: state.full_config.read().await.default_temperature,
+                                ..Default::default()
+                            };
+                            // Safety rules can only check a complete response, so nothing is streamed.
+                            let stream = chat.stream && safety.is_none();
+                            generation = Some(Generation::start(provider.clone(), history.clone(), params, request_id, stream));
+                            continue;
+                        }
                     }
                 }
             }
-        }
-
-        let _ = send_json(socket, &serde_json::json!({
-            "type": "chat_done",
-            "request_id": request_id,
-            "total_tokens": chunk_idx,
-            "full_content": &full_content,
-        })).await;
-
-        Ok(full_content)
-    } else {
-        // Non-streaming
-        let body = serde_json::json!({
-            "model": model,
-            "messages": messages,
-            "stream": false,
-        });
 
-        let resp = client
-            .post(format!("{url}/api/chat"))
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("Ollama connection failed: {e}"))?;
-
-        let json: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
-        let content = json["message"]["content"].as_str().unwrap_or("").to_string();
-
-        let _ = send_json(socket, &serde_json::json!({
-            "type": "chat_response",
-            "request_id": request_id,
-            "content": &content,
-            "provider": "ollama",
-            "model": model,
-        })).await;
-
-        Ok(content)
-    }
-}
-
-// ═══════════════════════════════════════════════════════════
-// OPENAI PROVIDER
-// ═══════════════════════════════════════════════════════════
-
-async fn chat_openai(
-    socket: &mut WebSocket,
-    state: &AppState,
-    request_id: &str,
-    messages: &[serde_json::Value],
-    model: &str,
-    stream: bool,
-) -> Result<String, String> {
-    let api_key = {
-        let config = state.full_config.lock().unwrap();
-        config.api_key.clone()
-    };
-    let api_key = if api_key.is_empty() {
-        std::env::var("OPENAI_API_KEY")
-            .map_err(|_| "OpenAI API key not configured. Set in Settings → API Key or OPENAI_API_KEY env var".to_string())?
-    } else {
-        api_key
-    };
-
-    let client = reqwest::Client::new();
-
-    if stream {
-        // Streaming SSE mode
-        let _ = send_json(socket, &serde_json::json!({
-            "type": "chat_start",
-            "request_id": request_id,
-            "provider": "openai",
-            "model": model,
-        })).await;
-
-        let body = serde_json::json!({
-            "model": model,
-            "messages": messages,
-            "stream": true,
-        });
-
-        let resp = client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {api_key}"))
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("OpenAI request failed: {e}"))?;
-
-        if !resp.status().is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            return Err(format!("OpenAI error: {text}"));
-        }
-
-        // Read SSE stream
-        let bytes = resp.bytes().await.map_err(|e| e.to_string())?;
-        let text = String::from_utf8_lossy(&bytes);
-        let mut full_content = String::new();
-        let mut chunk_idx: u64 = 0;
-
-        for line in text.lines() {
-            let line = line.trim();
-            if line.is_empty() || line == "data: [DONE]" { continue; }
-            if let Some(data) = line.strip_prefix("data: ") {
-                if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
-                    if let Some(content) = json["choices"][0]["delta"]["content"].as_str() {
-                        if !content.is_empty() {
-                            full_content.push_str(content);
-                            let _ = send_json(socket, &serde_json::json!({
-                                "type": "chat_chunk",
-                                "request_id": request_id,
-                                "content": content,
-                                "index": chunk_idx,
-                            })).await;
-                            chunk_idx += 1;
+            ClientCommand::Cancel(cancel) => {
+                let wanted = cancel.and_then(|c| c.request_id);
+                let matches = generation.as_ref()
+                    .is_some_and(|g| wanted.as_ref().is_none_or(|id| *id == g.request_id));
+                match generation.take_if(|_| matches) {
+                    Some(mut g) => {
+                        tracing::info!("Chat req={} cancelled after {} tokens", g.request_id, g.tokens);
+                        if let Some(filter) = &safety {
+                            g.content = filter.check(&g.content).cleaned_response;
                         }
+                        // Keep what was shown, so the conversation reads as the user saw it.
+                        if !g.content.is_empty() {
+                            history.push(ChatMessage::assistant(&g.content));
+                        }
+                        g.done(true)
                     }
+                    None => ServerEvent::error("No matching response in progress"),
                 }
             }
-        }
-
-        let _ = send_json(socket, &serde_json::json!({
-            "type": "chat_done",
-            "request_id": request_id,
-            "total_tokens": chunk_idx,
-            "full_content": &full_content,
-        })).await;
-
-        Ok(full_content)
-    } else {
-        // Non-streaming mode
-        let body = serde_json::json!({
-            "model": model,
-            "messages": messages,
-        });
-
-        let resp = client
-            .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {api_key}"))
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| format!("OpenAI request failed: {e}"))?;
-
-        if !resp.status().is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            return Err(format!("OpenAI error: {text}"));
-        }
 
-        let json: serde_json::Value = resp.json().await.map_err(|e| e.to_string())?;
-        let content = json["choices"][0]["message"]["content"].as_str().unwrap_or("").to_string();
-
-        let _ = send_json(socket, &serde_json::json!({
-            "type": "chat_response",
-            "request_id": request_id,
-            "content": &content,
-            "provider": "openai",
-            "model": model,
-        })).await;
-
-        Ok(content)
+            ClientCommand::Auth(_) => ServerEvent::error("Already authenticated"),
+
+            ClientCommand::Ping => ServerEvent::Pong(protocol::Pong {
+                timestamp: chrono::Utc::now().timestamp_millis(),
+            }),
+
+            ClientCommand::Status => ServerEvent::Status(protocol::Status {
+                requests_processed: request_counter,
+                uptime_secs: state.start_time.elapsed().as_secs(),
+                provider: provider_name.clone(),
+                model: model.clone(),
+                active_request: generation.as_ref().map(|g| g.request_id.clone()),
+            }),
+        };
+        let _ = send_event(&mut socket, &reply).await;
     }
-}
 
-// ═══════════════════════════════════════════════════════════
-// HELPERS
-// ═══════════════════════════════════════════════════════════
+    tracing::info!("WebSocket connection closed (total requests: {request_counter})");
+}
 
-async fn send_json(socket: &mut WebSocket, value: &serde_json::Value) -> Result<(), ()> {
-    socket.send(Message::Text(value.to_string().into()))
+async fn send_event(socket: &mut WebSocket, event: &ServerEvent) -> std::result::Result<(), ()> {
+    let text = serde_json::to_string(event).unwrap_or_default();
+    socket.send(Message::Text(text.into()))
         .await
         .map_err(|e| {
             tracing::error!("WS send failed: {e}");
         })
 }
 
-async fn send_error(socket: &mut WebSocket, message: &str) {
-    let error = serde_json::json!({
-        "type": "error",
-        "message": message,
-    });
-    let _ = send_json(socket, &error).await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_expiry() {
+        let t0 = Instant::now();
+        let config = bizclaw_core::config::GatewayConfig::default();
+        let mut heartbeat = Heartbeat::new(&config, t0).unwrap();
+        // A ping goes out at 30s; the client has until 40s to answer.
+        assert!(!heartbeat.expired(t0 + Duration::from_secs(40)));
+        assert!(heartbeat.expired(t0 + Duration::from_secs(41)));
+
+        heartbeat.seen(t0 + Duration::from_secs(35));
+        assert!(!heartbeat.expired(t0 + Duration::from_secs(60)));
+
+        let off = bizclaw_core::config::GatewayConfig { ws_ping_interval_secs: 0, ..config };
+        assert!(Heartbeat::new(&off, t0).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_context_length_cached_until_cleared() {
+        use bizclaw_core::types::{ModelInfo, ProviderResponse, ToolDefinition};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Counts `list_models` calls.
+        #[derive(Default)]
+        struct Counting(AtomicUsize);
+
+        #[async_trait::async_trait]
+        impl Provider for Counting {
+            fn name(&self) -> &str { "counting" }
+            async fn chat(&self, _: &[ChatMessage], _: &[ToolDefinition], _: &GenerateParams) -> Result<ProviderResponse> {
+                unimplemented!()
+            }
+            async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(vec![ModelInfo {
+                    id: "big".into(),
+                    name: "big".into(),
+                    provider: "counting".into(),
+                    context_length: 128_000,
+                    max_output_tokens: None,
+                }])
+            }
+            async fn health_check(&self) -> Result<bool> { Ok(true) }
+        }
+
+        let provider = Counting::default();
+        let lengths = ContextLengths::default();
+        assert_eq!(lengths.get("counting", Some(&provider), "big").await, 128_000);
+        assert_eq!(lengths.get("counting", Some(&provider), "big").await, 128_000);
+        assert_eq!(provider.0.load(Ordering::SeqCst), 1);
+        assert_eq!(lengths.get("counting", Some(&provider), "small").await, tokens::DEFAULT_CONTEXT_LENGTH);
+        assert_eq!(provider.0.load(Ordering::SeqCst), 2);
+
+        lengths.clear();
+        lengths.get("counting", Some(&provider), "big").await;
+        assert_eq!(provider.0.load(Ordering::SeqCst), 3);
+    }
 }
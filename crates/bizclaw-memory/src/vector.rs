@@ -61,8 +61,8 @@ impl Default for VectorStore {
     fn default() -> Self { Self::new() }
 }
 
-/// Compute cosine similarity between two vectors.
-fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+/// Compute cosine similarity between two vectors (0 if their lengths differ).
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
     if a.len() != b.len() || a.is_empty() {
         return 0.0;
     }
@@ -1,10 +1,10 @@
 //! Admin HTTP server — REST API for the admin control plane.
 
-use axum::{Router, Json, routing::{get, post, delete}, extract::{State, Path}};
+use axum::{Router, Json, routing::{get, post, put, delete}, extract::{State, Path}};
 use axum::middleware;
 use std::sync::{Arc, Mutex};
 use crate::db::PlatformDb;
-use crate::tenant::TenantManager;
+use crate::tenant::{RestartPolicy, TenantManager};
 
 /// Shared application state for the admin server.
 pub struct AdminState {
@@ -13,6 +13,10 @@ pub struct AdminState {
     pub jwt_secret: String,
     pub bizclaw_bin: String,
     pub base_port: u16,
+    /// Tenants are served at `<slug>.<domain>`; empty turns subdomain routing off.
+    pub domain: String,
+    /// Outbound webhook deliveries, used to retry failed ones.
+    pub webhooks: bizclaw_channels::webhook::WebhookDeliveryQueue,
 }
 
 /// JWT auth middleware — validates Authorization: Bearer <token>.
@@ -26,10 +30,9 @@ async fn require_auth(
         .and_then(|v| v.to_str().ok())
         .unwrap_or("");
 
-    if let Some(token) = auth_header.strip_prefix("Bearer ") {
-        if crate::auth::validate_token(token, &state.jwt_secret).is_ok() {
-            return next.run(req).await;
-        }
+    if let Some(token) = auth_header.strip_prefix("Bearer ")
+        && crate::auth::validate_token(token, &state.jwt_secret).is_ok() {
+        return next.run(req).await;
     }
 
     axum::response::Response::builder()
@@ -61,11 +64,21 @@ impl AdminServer {
             .route("/api/admin/tenants/{id}/stop", post(stop_tenant))
             .route("/api/admin/tenants/{id}/restart", post(restart_tenant))
             .route("/api/admin/tenants/{id}/pairing", post(reset_pairing))
+            .route("/api/admin/tenants/{id}/rotate-key", post(rotate_tenant_key))
+            .route("/api/admin/tenants/{id}/backup", post(backup_tenant))
+            .route("/api/admin/tenants/restore", post(restore_tenant))
             // Channel Configuration
             .route("/api/admin/tenants/{id}/channels", get(list_channels))
             .route("/api/admin/tenants/{id}/channels", post(upsert_channel))
             .route("/api/admin/tenants/{id}/channels/{channel_id}", delete(delete_channel))
+            .route("/api/admin/tenants/{id}/channels/{channel}/history", get(channel_history))
+            .route("/api/admin/tenants/{id}/users/link", post(link_users))
             .route("/api/admin/tenants/{id}/channels/zalo/qr", post(zalo_get_qr))
+            // Feature flags
+            .route("/api/admin/tenants/{id}/flags", get(list_tenant_flags))
+            .route("/api/admin/tenants/{id}/flags/{flag}", put(set_tenant_flag))
+            .route("/api/admin/flags", get(list_global_flags))
+            .route("/api/admin/flags/{flag}", put(set_global_flag))
             // Ollama / Brain Engine
             .route("/api/admin/ollama/models", get(ollama_list_models))
             .route("/api/admin/ollama/pull", post(ollama_pull_model))
@@ -73,6 +86,11 @@ impl AdminServer {
             .route("/api/admin/ollama/health", get(ollama_health))
             // Users
             .route("/api/admin/users", get(list_users))
+            .route("/api/admin/users/export", get(export_users))
+            .route("/api/admin/users/import", post(import_users))
+            // Webhooks that failed every delivery attempt
+            .route("/api/admin/webhooks/failed", get(list_failed_webhooks))
+            .route("/api/admin/webhooks/failed/{id}/retry", post(retry_failed_webhook))
             .route_layer(middleware::from_fn_with_state(state.clone(), require_auth));
 
         // Public routes — no auth required
@@ -81,7 +99,9 @@ impl AdminServer {
             .route("/api/admin/pairing/validate", post(validate_pairing))
             .route("/", get(admin_dashboard_page));
 
-        protected.merge(public).with_state(state)
+        protected.merge(public)
+            .layer(middleware::from_fn_with_state(state.clone(), crate::proxy::route_subdomains))
+            .with_state(state)
     }
 
     /// Start the admin server.
@@ -98,6 +118,28 @@ impl AdminServer {
 
         Ok(())
     }
+
+    /// Check tenants every `policy.check_interval`, restarting crashed ones
+    /// (see [`TenantManager::check_tenants`]).
+    pub fn spawn_supervisor(state: Arc<AdminState>, policy: RestartPolicy) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(policy.check_interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let (state, policy) = (state.clone(), policy.clone());
+                let checked = tokio::task::spawn_blocking(move || {
+                    let mut manager = state.manager.lock().unwrap();
+                    manager.check_tenants(&state.db, &state.bizclaw_bin, &policy, std::time::Instant::now())
+                }).await;
+                match checked {
+                    Ok(Ok(())) => {}
+                    Ok(Err(e)) => tracing::warn!("Tenant health check failed: {e}"),
+                    Err(e) => tracing::warn!("Tenant health check panicked: {e}"),
+                }
+            }
+        })
+    }
 }
 
 // ── API Handlers ────────────────────────────────────
@@ -116,6 +158,7 @@ async fn get_activity(State(state): State<Arc<AdminState>>) -> Json<serde_json::
     Json(serde_json::json!({ "events": events }))
 }
 
+/// `GET /api/admin/tenants` — every tenant with its status and pid.
 async fn list_tenants(State(state): State<Arc<AdminState>>) -> Json<serde_json::Value> {
     let tenants = state.db.lock().unwrap().list_tenants().unwrap_or_default();
     Json(serde_json::json!({ "tenants": tenants }))
@@ -130,8 +173,10 @@ struct CreateTenantReq {
     plan: Option<String>,
 }
 
+/// `POST /api/admin/tenants` — create a stopped tenant on the next free port.
 async fn create_tenant(
     State(state): State<Arc<AdminState>>,
+    headers: axum::http::HeaderMap,
     Json(req): Json<CreateTenantReq>,
 ) -> Json<serde_json::Value> {
     let port = {
@@ -144,14 +189,15 @@ async fn create_tenant(
         port
     };
 
-    match state.db.lock().unwrap().create_tenant(
+    let created = state.db.lock().unwrap().create_tenant(
         &req.name, &req.slug, port,
         req.provider.as_deref().unwrap_or("openai"),
         req.model.as_deref().unwrap_or("gpt-4o-mini"),
         req.plan.as_deref().unwrap_or("free"),
-    ) {
+    );
+    match created {
         Ok(tenant) => {
-            state.db.lock().unwrap().log_event("tenant_created", "admin", &tenant.id, Some(&format!("slug={}", req.slug))).ok();
+            audit_tenant(&state, &headers, "tenant_created", &tenant.id, &format!("slug={} port={port}", req.slug));
             Json(serde_json::json!({"ok": true, "tenant": tenant}))
         }
         Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
@@ -168,81 +214,361 @@ async fn get_tenant(
     }
 }
 
+/// `DELETE /api/admin/tenants/{id}` — stop the tenant if running and remove it.
 async fn delete_tenant(
     State(state): State<Arc<AdminState>>,
     Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
 ) -> Json<serde_json::Value> {
-    state.manager.lock().unwrap().stop_tenant(&id).ok();
-    match state.db.lock().unwrap().delete_tenant(&id) {
-        Ok(()) => {
-            state.db.lock().unwrap().log_event("tenant_deleted", "admin", &id, None).ok();
-            Json(serde_json::json!({"ok": true}))
-        }
-        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    if let Err(e) = state.manager.lock().unwrap().stop_tenant(&id) {
+        return Json(serde_json::json!({"ok": false, "error": e.to_string()}));
     }
+    if let Err(e) = state.db.lock().unwrap().delete_tenant(&id) {
+        return Json(serde_json::json!({"ok": false, "error": e.to_string()}));
+    }
+    audit_tenant(&state, &headers, "tenant_deleted", &id, "");
+    Json(serde_json::json!({"ok": true}))
 }
 
+/// `POST /api/admin/tenants/{id}/start` — spawn the tenant's process.
 async fn start_tenant(
     State(state): State<Arc<AdminState>>,
     Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
 ) -> Json<serde_json::Value> {
     let tenant = match state.db.lock().unwrap().get_tenant(&id) {
         Ok(t) => t,
         Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
     };
+    let started = {
+        let mut mgr = state.manager.lock().unwrap();
+        let db = state.db.lock().unwrap();
+        mgr.start_tenant(&tenant, &state.bizclaw_bin, &db)
+    };
+    record_start(&state, &headers, &id, "tenant_started", started)
+}
 
-    let mut mgr = state.manager.lock().unwrap();
-    let db = state.db.lock().unwrap();
-    match mgr.start_tenant(&tenant, &state.bizclaw_bin, &db) {
+/// `POST /api/admin/tenants/{id}/stop` — kill the tenant's process.
+async fn stop_tenant(
+    State(state): State<Arc<AdminState>>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> Json<serde_json::Value> {
+    if let Err(e) = state.manager.lock().unwrap().stop_tenant(&id) {
+        return Json(serde_json::json!({"ok": false, "error": e.to_string()}));
+    }
+    state.db.lock().unwrap().update_tenant_status(&id, "stopped", None).ok();
+    audit_tenant(&state, &headers, "tenant_stopped", &id, "");
+    Json(serde_json::json!({"ok": true}))
+}
+
+/// `POST /api/admin/tenants/{id}/restart` — stop, then start with a fresh config.
+async fn restart_tenant(
+    State(state): State<Arc<AdminState>>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> Json<serde_json::Value> {
+    let tenant = match state.db.lock().unwrap().get_tenant(&id) {
+        Ok(t) => t,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    let started = {
+        let mut mgr = state.manager.lock().unwrap();
+        let db = state.db.lock().unwrap();
+        mgr.restart_tenant(&tenant, &state.bizclaw_bin, &db)
+    };
+    record_start(&state, &headers, &id, "tenant_restarted", started)
+}
+
+/// Store the outcome of starting a tenant: "running" with its pid, or
+/// "error" (audited as `tenant_start_failed`).
+fn record_start(
+    state: &AdminState,
+    headers: &axum::http::HeaderMap,
+    id: &str,
+    event: &str,
+    started: bizclaw_core::error::Result<u32>,
+) -> Json<serde_json::Value> {
+    match started {
         Ok(pid) => {
-            drop(db);
-            state.db.lock().unwrap().update_tenant_status(&id, "running", Some(pid)).ok();
-            state.db.lock().unwrap().log_event("tenant_started", "admin", &id, None).ok();
+            state.db.lock().unwrap().update_tenant_status(id, "running", Some(pid)).ok();
+            audit_tenant(state, headers, event, id, &format!("pid={pid}"));
             Json(serde_json::json!({"ok": true, "pid": pid}))
         }
         Err(e) => {
-            drop(db);
-            state.db.lock().unwrap().update_tenant_status(&id, "error", None).ok();
+            state.db.lock().unwrap().update_tenant_status(id, "error", None).ok();
+            audit_tenant(state, headers, "tenant_start_failed", id, &format!("error={e}"));
             Json(serde_json::json!({"ok": false, "error": e.to_string()}))
         }
     }
 }
 
-async fn stop_tenant(
+/// Record a tenant lifecycle change in the audit log, as done by the
+/// requesting admin.
+fn audit_tenant(state: &AdminState, headers: &axum::http::HeaderMap, event: &str, tenant_id: &str, details: &str) {
+    let actor = request_actor(state, headers);
+    let details = format!("tenant={tenant_id} {details}");
+    let details = details.trim_end();
+    state.db.lock().unwrap().log_event(event, "admin", &actor, Some(details)).ok();
+    tracing::info!(target: "bizclaw::audit", event, actor, tenant = tenant_id, "{details}");
+}
+
+async fn reset_pairing(
     State(state): State<Arc<AdminState>>,
     Path(id): Path<String>,
 ) -> Json<serde_json::Value> {
-    state.manager.lock().unwrap().stop_tenant(&id).ok();
-    state.db.lock().unwrap().update_tenant_status(&id, "stopped", None).ok();
-    state.db.lock().unwrap().log_event("tenant_stopped", "admin", &id, None).ok();
-    Json(serde_json::json!({"ok": true}))
+    match state.db.lock().unwrap().reset_pairing_code(&id) {
+        Ok(code) => {
+            state.db.lock().unwrap().log_event("tenant_pairing_reset", "admin", &id, None).ok();
+            Json(serde_json::json!({"ok": true, "pairing_code": code}))
+        }
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
 }
 
-async fn restart_tenant(
+/// Replace a tenant's provider API key (`{"new_key": ..., "provider"?: ...}`).
+///
+/// The key is checked with an authenticated request to the tenant's provider
+/// first, then written to the tenant config; a running tenant reloads it on
+/// SIGUSR1 without a restart.
+async fn rotate_tenant_key(
     State(state): State<Arc<AdminState>>,
     Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<serde_json::Value>,
 ) -> Json<serde_json::Value> {
     let tenant = match state.db.lock().unwrap().get_tenant(&id) {
         Ok(t) => t,
         Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
     };
+    let new_key = req["new_key"].as_str().unwrap_or("").trim();
+    if new_key.is_empty() {
+        return Json(serde_json::json!({"ok": false, "error": "'new_key' is required"}));
+    }
+    if let Some(provider) = req["provider"].as_str().filter(|p| *p != tenant.provider) {
+        return Json(serde_json::json!({
+            "ok": false,
+            "error": format!("Tenant uses '{}', not '{provider}'", tenant.provider),
+        }));
+    }
 
-    let mut mgr = state.manager.lock().unwrap();
+    let candidate = bizclaw_core::config::BizClawConfig {
+        default_provider: tenant.provider.clone(),
+        default_model: tenant.model.clone(),
+        api_key: new_key.to_string(),
+        ..Default::default()
+    };
+    let verified = match bizclaw_providers::create_provider(&candidate) {
+        Ok(p) => p.verify_key().await,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    match verified {
+        Ok(()) => {}
+        Err(bizclaw_core::error::BizClawError::AuthFailed(_)) => {
+            return Json(serde_json::json!({"ok": false, "error": format!("{} rejected the new key", tenant.provider)}));
+        }
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Could not check the new key: {e}")})),
+    }
+
+    let reloaded = {
+        let mgr = state.manager.lock().unwrap();
+        let db = state.db.lock().unwrap();
+        mgr.set_api_key(&tenant, new_key, &db)
+    };
+    let reloaded = match reloaded {
+        Ok(signalled) => signalled,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+
+    let actor = request_actor(&state, &headers);
+    state.db.lock().unwrap().log_event(
+        "api_key_rotated", "admin", &actor,
+        Some(&format!("tenant={} provider={}", tenant.id, tenant.provider)),
+    ).ok();
+    tracing::info!(target: "bizclaw::audit", event = "api_key_rotated", actor, tenant = %tenant.slug, provider = %tenant.provider, "API key rotated");
+
+    Json(serde_json::json!({"ok": true, "provider": tenant.provider, "healthy": true, "reloaded": reloaded}))
+}
+
+/// Claims of the JWT the request was authorized with.
+fn request_claims(state: &AdminState, headers: &axum::http::HeaderMap) -> Option<crate::auth::Claims> {
+    headers.get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .and_then(|token| crate::auth::validate_token(token, &state.jwt_secret).ok())
+}
+
+/// Email of the admin making the request, for the audit log.
+fn request_actor(state: &AdminState, headers: &axum::http::HeaderMap) -> String {
+    request_claims(state, headers)
+        .map(|claims| claims.email)
+        .unwrap_or_else(|| "admin".into())
+}
+
+/// Email of the requester if they have the admin role.
+fn admin_email(state: &AdminState, headers: &axum::http::HeaderMap) -> Option<String> {
+    request_claims(state, headers)
+        .filter(|claims| claims.role == "admin")
+        .map(|claims| claims.email)
+}
+
+fn admin_role_required() -> axum::response::Response {
+    use axum::response::IntoResponse;
+    (
+        axum::http::StatusCode::FORBIDDEN,
+        Json(serde_json::json!({"ok": false, "error": "Admin role required"})),
+    ).into_response()
+}
+
+/// Tenant-specific flags plus the effective value of every known flag.
+async fn list_tenant_flags(
+    State(state): State<Arc<AdminState>>,
+    Path(id): Path<String>,
+) -> Json<serde_json::Value> {
     let db = state.db.lock().unwrap();
-    match mgr.restart_tenant(&tenant, &state.bizclaw_bin, &db) {
-        Ok(pid) => Json(serde_json::json!({"ok": true, "pid": pid})),
+    if let Err(e) = db.get_tenant(&id) {
+        return Json(serde_json::json!({"ok": false, "error": e.to_string()}));
+    }
+    let result = db.list_flags(&id).and_then(|own| {
+        let global = db.list_flags(crate::db::ALL_TENANTS)?;
+        let mut effective = serde_json::Map::new();
+        for name in global.iter().chain(&own).map(|f| &f.flag_name) {
+            effective.insert(name.clone(), db.flag_enabled(&id, name)?.into());
+        }
+        Ok((own, effective))
+    });
+    match result {
+        Ok((own, effective)) => Json(serde_json::json!({"ok": true, "flags": own, "effective": effective})),
         Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
     }
 }
 
-async fn reset_pairing(
+async fn list_global_flags(State(state): State<Arc<AdminState>>) -> Json<serde_json::Value> {
+    match state.db.lock().unwrap().list_flags(crate::db::ALL_TENANTS) {
+        Ok(flags) => Json(serde_json::json!({"ok": true, "flags": flags})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct SetFlagReq {
+    enabled: bool,
+}
+
+async fn set_tenant_flag(
+    State(state): State<Arc<AdminState>>,
+    Path((id, flag)): Path<(String, String)>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<SetFlagReq>,
+) -> Json<serde_json::Value> {
+    if let Err(e) = state.db.lock().unwrap().get_tenant(&id) {
+        return Json(serde_json::json!({"ok": false, "error": e.to_string()}));
+    }
+    set_flag(&state, &id, &flag, req.enabled, &headers)
+}
+
+async fn set_global_flag(
+    State(state): State<Arc<AdminState>>,
+    Path(flag): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<SetFlagReq>,
+) -> Json<serde_json::Value> {
+    set_flag(&state, crate::db::ALL_TENANTS, &flag, req.enabled, &headers)
+}
+
+/// Store a flag and audit the change. Running tenants pick it up on restart.
+fn set_flag(state: &AdminState, tenant_id: &str, flag: &str, enabled: bool, headers: &axum::http::HeaderMap) -> Json<serde_json::Value> {
+    let valid = !flag.is_empty() && flag.len() <= 64
+        && flag.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '.');
+    if !valid {
+        return Json(serde_json::json!({"ok": false, "error": "Flag names use a-z, 0-9, '_' and '.'"}));
+    }
+    if let Err(e) = state.db.lock().unwrap().set_flag(tenant_id, flag, enabled) {
+        return Json(serde_json::json!({"ok": false, "error": e.to_string()}));
+    }
+
+    let actor = request_actor(state, headers);
+    state.db.lock().unwrap().log_event(
+        "flag_changed", "admin", &actor,
+        Some(&format!("tenant={tenant_id} flag={flag} enabled={enabled}")),
+    ).ok();
+    tracing::info!(target: "bizclaw::audit", event = "flag_changed", actor, tenant = tenant_id, flag, enabled, "Feature flag changed");
+
+    Json(serde_json::json!({"ok": true, "tenant_id": tenant_id, "flag": flag, "enabled": enabled}))
+}
+
+/// Create a backup archive and return it as a download.
+async fn backup_tenant(
     State(state): State<Arc<AdminState>>,
     Path(id): Path<String>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let data_dir = state.manager.lock().unwrap().data_dir().to_path_buf();
+    let result = {
+        let db = state.db.lock().unwrap();
+        crate::backup::TenantBackup::create_backup(&id, &db, &data_dir)
+    };
+    let path = match result {
+        Ok(path) => path,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})).into_response(),
+    };
+    let bytes = match tokio::fs::read(&path).await {
+        Ok(bytes) => bytes,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})).into_response(),
+    };
+
+    let filename = path.file_name().and_then(|f| f.to_str()).unwrap_or("backup.tar.gz").to_string();
+    state.db.lock().unwrap().log_event("tenant_backup", "admin", &id, Some(&filename)).ok();
+    (
+        [
+            (axum::http::header::CONTENT_TYPE, "application/gzip".to_string()),
+            (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        bytes,
+    ).into_response()
+}
+
+/// Restore a tenant from an uploaded archive (multipart: `file`, optional `slug`).
+async fn restore_tenant(
+    State(state): State<Arc<AdminState>>,
+    mut multipart: axum::extract::Multipart,
 ) -> Json<serde_json::Value> {
-    match state.db.lock().unwrap().reset_pairing_code(&id) {
-        Ok(code) => {
-            state.db.lock().unwrap().log_event("tenant_pairing_reset", "admin", &id, None).ok();
-            Json(serde_json::json!({"ok": true, "pairing_code": code}))
+    let mut archive = None;
+    let mut slug = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) => match field.name() {
+                Some("file") => match field.bytes().await {
+                    Ok(bytes) => archive = Some(bytes),
+                    Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+                },
+                Some("slug") => slug = field.text().await.ok().filter(|s| !s.trim().is_empty()),
+                _ => {}
+            },
+            Ok(None) => break,
+            Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+        }
+    }
+    let Some(archive) = archive else {
+        return Json(serde_json::json!({"ok": false, "error": "Missing 'file' field"}));
+    };
+
+    let data_dir = state.manager.lock().unwrap().data_dir().to_path_buf();
+    let tmp = std::env::temp_dir().join(format!("bizclaw-restore-{}.tar.gz", uuid::Uuid::new_v4().simple()));
+    if let Err(e) = tokio::fs::write(&tmp, &archive).await {
+        return Json(serde_json::json!({"ok": false, "error": e.to_string()}));
+    }
+    let result = {
+        let db = state.db.lock().unwrap();
+        crate::backup::TenantBackup::restore_backup(&tmp, &db, &data_dir, slug)
+    };
+    tokio::fs::remove_file(&tmp).await.ok();
+
+    match result {
+        Ok(tenant) => {
+            state.db.lock().unwrap().log_event("tenant_restored", "admin", &tenant.id, Some(&format!("slug={}", tenant.slug))).ok();
+            Json(serde_json::json!({"ok": true, "tenant": tenant}))
         }
         Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
     }
@@ -253,6 +579,82 @@ async fn list_users(State(state): State<Arc<AdminState>>) -> Json<serde_json::Va
     Json(serde_json::json!({"users": users}))
 }
 
+#[derive(serde::Deserialize)]
+struct ExportQuery {
+    #[serde(default)]
+    format: Option<String>,
+}
+
+/// Download all users as CSV (`email,role,tenant_id,created_at`).
+async fn export_users(
+    State(state): State<Arc<AdminState>>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Query(query): axum::extract::Query<ExportQuery>,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    if admin_email(&state, &headers).is_none() {
+        return admin_role_required();
+    }
+    if query.format.as_deref().is_some_and(|f| f != "csv") {
+        return Json(serde_json::json!({"ok": false, "error": "Only format=csv is supported"})).into_response();
+    }
+    let result = crate::user_csv::export_users(&state.db.lock().unwrap());
+    match result {
+        Ok(csv) => (
+            [
+                (axum::http::header::CONTENT_TYPE, "text/csv; charset=utf-8"),
+                (axum::http::header::CONTENT_DISPOSITION, "attachment; filename=\"users.csv\""),
+            ],
+            csv,
+        ).into_response(),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})).into_response(),
+    }
+}
+
+/// Invite users from an uploaded CSV (multipart: `file`).
+async fn import_users(
+    State(state): State<Arc<AdminState>>,
+    headers: axum::http::HeaderMap,
+    mut multipart: axum::extract::Multipart,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    let Some(actor) = admin_email(&state, &headers) else {
+        return admin_role_required();
+    };
+    let mut data = None;
+    loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("file") => match field.bytes().await {
+                Ok(bytes) => data = Some(bytes),
+                Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})).into_response(),
+            },
+            Ok(Some(_)) => {}
+            Ok(None) => break,
+            Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})).into_response(),
+        }
+    }
+    let Some(data) = data else {
+        return Json(serde_json::json!({"ok": false, "error": "Missing 'file' field"})).into_response();
+    };
+
+    let db = state.db.lock().unwrap();
+    match crate::user_csv::import_users(&db, &data) {
+        Ok(report) => {
+            db.log_event(
+                "bulk_user_import", "admin", &actor,
+                Some(&format!("created={} skipped={} errors={}", report.created, report.skipped, report.errors.len())),
+            ).ok();
+            tracing::info!(target: "bizclaw::audit", event = "bulk_user_import", actor, created = report.created, "Bulk user import");
+            Json(serde_json::json!({
+                "ok": true, "created": report.created, "skipped": report.skipped, "errors": report.errors,
+            })).into_response()
+        }
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})).into_response(),
+    }
+}
+
 #[derive(serde::Deserialize)]
 struct LoginReq { email: String, password: String }
 
@@ -326,6 +728,49 @@ async fn list_channels(
     }
 }
 
+/// A tenant's messages on one channel, newest first (`?limit=50&offset=0`).
+async fn channel_history(
+    State(state): State<Arc<AdminState>>,
+    Path((id, channel)): Path<(String, String)>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> Json<serde_json::Value> {
+    let limit = params.get("limit").and_then(|l| l.parse().ok()).unwrap_or(50).min(1000);
+    let offset = params.get("offset").and_then(|o| o.parse().ok()).unwrap_or(0);
+    match state.db.lock().unwrap().channel_message_history(&id, &channel, limit, offset) {
+        Ok(messages) => Json(serde_json::json!({"ok": true, "messages": messages, "limit": limit, "offset": offset})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct LinkUsersReq {
+    primary_channel: String,
+    primary_id: String,
+    secondary_channel: String,
+    secondary_id: String,
+}
+
+/// Make two channel users one, so they share a conversation.
+async fn link_users(
+    State(state): State<Arc<AdminState>>,
+    Path(id): Path<String>,
+    Json(req): Json<LinkUsersReq>,
+) -> Json<serde_json::Value> {
+    let db = state.db.lock().unwrap();
+    let linked = db.link_users(&id, &req.primary_channel, &req.primary_id, &req.secondary_channel, &req.secondary_id)
+        .and_then(|canonical_id| Ok((db.user_identities(&id, &canonical_id)?, canonical_id)));
+    match linked {
+        Ok((identities, canonical_id)) => {
+            db.log_event(
+                "users_linked", "admin", &id,
+                Some(&format!("{}:{} <- {}:{}", req.primary_channel, req.primary_id, req.secondary_channel, req.secondary_id)),
+            ).ok();
+            Json(serde_json::json!({"ok": true, "canonical_id": canonical_id, "identities": identities}))
+        }
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
 #[derive(serde::Deserialize)]
 struct UpsertChannelReq {
     channel_type: String,
@@ -533,3 +978,138 @@ async fn ollama_delete_model(
         Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
     }
 }
+
+/// Webhooks that failed every delivery attempt, newest first.
+async fn list_failed_webhooks(State(state): State<Arc<AdminState>>) -> Json<serde_json::Value> {
+    match state.db.lock().unwrap().list_failed_webhooks() {
+        Ok(failed) => Json(serde_json::json!({"ok": true, "webhooks": failed})),
+        Err(e) => Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    }
+}
+
+/// Queue a failed webhook for delivery again, with a fresh set of retries.
+/// It leaves the failed list; if it fails again it comes back.
+async fn retry_failed_webhook(
+    State(state): State<Arc<AdminState>>,
+    Path(id): Path<String>,
+    headers: axum::http::HeaderMap,
+) -> Json<serde_json::Value> {
+    let failed = match state.db.lock().unwrap().get_failed_webhook(&id) {
+        Ok(failed) => failed,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": e.to_string()})),
+    };
+    let payload = match serde_json::from_str(&failed.payload_json) {
+        Ok(payload) => payload,
+        Err(e) => return Json(serde_json::json!({"ok": false, "error": format!("Stored payload is not JSON: {e}")})),
+    };
+    let delivery = bizclaw_channels::webhook::WebhookDelivery {
+        id: failed.id, tenant_id: failed.tenant_id, url: failed.url, payload, headers: Vec::new(),
+    };
+    if let Err(e) = state.webhooks.enqueue(delivery).await {
+        return Json(serde_json::json!({"ok": false, "error": e.to_string()}));
+    }
+    let db = state.db.lock().unwrap();
+    db.delete_failed_webhook(&id).ok();
+    db.log_event("webhook_retried", "admin", &request_actor(&state, &headers), Some(&format!("id={id}"))).ok();
+    Json(serde_json::json!({"ok": true}))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(data_dir: &std::path::Path) -> Arc<AdminState> {
+        Arc::new(AdminState {
+            db: Mutex::new(PlatformDb::open(std::path::Path::new(":memory:")).unwrap()),
+            manager: Mutex::new(TenantManager::new(data_dir)),
+            jwt_secret: "secret".into(),
+            // `true` stands in for the bizclaw binary.
+            bizclaw_bin: "true".into(),
+            base_port: 10001,
+            domain: "bizclaw.vn".into(),
+            webhooks: bizclaw_channels::webhook::WebhookDeliveryQueue::start(None),
+        })
+    }
+
+    fn admin_headers() -> axum::http::HeaderMap {
+        let token = crate::auth::create_token("u1", "ops@shop.vn", "admin", "secret").unwrap();
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(axum::http::header::AUTHORIZATION, format!("Bearer {token}").parse().unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_tenant_lifecycle_updates_status_and_audits() {
+        let data_dir = std::env::temp_dir().join(format!("bizclaw-admin-{}", uuid::Uuid::new_v4().simple()));
+        let state = state(&data_dir);
+        let req = CreateTenantReq { name: "Shop".into(), slug: "shop".into(), provider: None, model: None, plan: None };
+        let Json(created) = create_tenant(State(state.clone()), admin_headers(), Json(req)).await;
+        let id = created["tenant"]["id"].as_str().unwrap().to_string();
+
+        let Json(started) = start_tenant(State(state.clone()), Path(id.clone()), admin_headers()).await;
+        assert_eq!(started["ok"], true, "{started}");
+        let tenant = state.db.lock().unwrap().get_tenant(&id).unwrap();
+        assert_eq!((tenant.status.as_str(), tenant.pid), ("running", started["pid"].as_u64().map(|p| p as u32)));
+
+        let Json(stopped) = stop_tenant(State(state.clone()), Path(id.clone()), admin_headers()).await;
+        assert_eq!(stopped["ok"], true);
+        let tenant = state.db.lock().unwrap().get_tenant(&id).unwrap();
+        assert_eq!((tenant.status.as_str(), tenant.pid), ("stopped", None));
+
+        let Json(deleted) = delete_tenant(State(state.clone()), Path(id.clone()), admin_headers()).await;
+        assert_eq!(deleted["ok"], true);
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        let events = state.db.lock().unwrap().recent_events(10).unwrap();
+        let types: Vec<_> = events.iter().rev().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(types, ["tenant_created", "tenant_started", "tenant_stopped", "tenant_deleted"]);
+        assert!(events.iter().all(|e| e.actor_id == "ops@shop.vn"));
+        assert_eq!(events[0].details.as_deref(), Some(format!("tenant={id}").as_str()));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_key_checks_with_provider() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // OpenAI-compatible `/v1/models` that accepts only `sk-good`.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let provider = format!("custom:http://{}/v1", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let status = if request.contains("authorization: bearer sk-good") { "200 OK" } else { "401 Unauthorized" };
+                let response = format!("HTTP/1.1 {status}\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{{}}");
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+
+        let data_dir = std::env::temp_dir().join(format!("bizclaw-admin-{}", uuid::Uuid::new_v4().simple()));
+        let state = state(&data_dir);
+        let tenant = state.db.lock().unwrap().create_tenant("Shop", "shop", 10001, &provider, "m", "pro").unwrap();
+        let rotate = |key: &str| rotate_tenant_key(
+            State(state.clone()), Path(tenant.id.clone()), admin_headers(), Json(serde_json::json!({"new_key": key})),
+        );
+
+        let Json(refused) = rotate("sk-revoked").await;
+        assert_eq!(refused["ok"], false);
+        assert!(refused["error"].as_str().unwrap().contains("rejected the new key"), "{refused}");
+        assert_eq!(state.db.lock().unwrap().provider_api_key(&tenant.id).unwrap(), None);
+
+        let Json(rotated) = rotate("sk-good").await;
+        std::fs::remove_dir_all(&data_dir).ok();
+        assert_eq!(rotated["ok"], true, "{rotated}");
+        assert_eq!(state.db.lock().unwrap().provider_api_key(&tenant.id).unwrap().as_deref(), Some("sk-good"));
+    }
+
+    #[tokio::test]
+    async fn test_lifecycle_routes_require_jwt() {
+        use tower::ServiceExt;
+
+        let data_dir = std::env::temp_dir().join(format!("bizclaw-admin-{}", uuid::Uuid::new_v4().simple()));
+        let request = axum::http::Request::post("/api/admin/tenants/t1/start").body(axum::body::Body::empty()).unwrap();
+        let response = AdminServer::router(state(&data_dir)).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+}
@@ -0,0 +1,387 @@
+//! Tenant backup and restore — one `.tar.gz` per tenant.
+//!
+//! An archive holds:
+//! - `tenant.json` — the `Tenant` record
+//! - `config.toml` — the tenant's config, secrets blanked
+//! - `messages.ndjson` / `memory.ndjson` — rows from the tenant's data DB
+//!   (`<data_dir>/<slug>/memory.db`), one JSON object per line
+//! - `channels.json` — channel configs, secrets blanked
+//!
+//! Secrets never leave the server, so restored channels come back disabled
+//! and need their credentials re-entered.
+
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use bizclaw_core::error::{BizClawError, Result};
+use rusqlite::{Connection, types::Value as SqlValue};
+
+use crate::db::{PlatformDb, Tenant, TenantChannel};
+
+/// Config and channel keys whose values are blanked in a backup.
+const SECRET_KEYS: &[&str] = &[
+    "api_key", "bot_token", "token", "access_token", "app_secret", "secret",
+    "password", "cookie", "webhook_secret", "verify_token", "secret_token",
+];
+
+/// Tables copied from the tenant data DB, with the archive entry for each.
+const DATA_TABLES: &[(&str, &str)] = &[
+    ("messages", "messages.ndjson"),
+    ("memories", "memory.ndjson"),
+];
+
+/// Schema of the memory backend's table, used when restoring into a fresh DB.
+const MEMORIES_SCHEMA: &str = "CREATE TABLE IF NOT EXISTS memories (
+    id TEXT PRIMARY KEY,
+    content TEXT NOT NULL,
+    metadata TEXT DEFAULT '{}',
+    embedding BLOB,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);";
+
+/// Tenant backup/restore operations.
+pub struct TenantBackup;
+
+impl TenantBackup {
+    /// Write a backup of `tenant_id` to `<data_dir>/backups/<slug>-<timestamp>.tar.gz`.
+    pub fn create_backup(tenant_id: &str, db: &PlatformDb, data_dir: &Path) -> Result<PathBuf> {
+        let tenant = db.get_tenant(tenant_id)?;
+        let tenant_dir = data_dir.join(&tenant.slug);
+
+        let backups_dir = data_dir.join("backups");
+        std::fs::create_dir_all(&backups_dir)?;
+        let archive_path = backups_dir.join(format!(
+            "{}-{}.tar.gz",
+            tenant.slug,
+            chrono::Utc::now().format("%Y%m%d-%H%M%S")
+        ));
+
+        let file = std::fs::File::create(&archive_path)?;
+        let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+
+        append(&mut archive, "tenant.json", &serde_json::to_vec_pretty(&tenant)?)?;
+
+        let config = match std::fs::read_to_string(tenant_dir.join("config.toml")) {
+            Ok(text) => scrub_toml(&text)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        append(&mut archive, "config.toml", config.as_bytes())?;
+
+        let data_db = tenant_dir.join("memory.db");
+        let conn = if data_db.exists() {
+            Some(Connection::open(&data_db).map_err(|e| BizClawError::Memory(format!("DB open error: {e}")))?)
+        } else {
+            None
+        };
+        for (table, entry) in DATA_TABLES {
+            let rows = match &conn {
+                Some(conn) => export_table(conn, table)?,
+                None => Vec::new(),
+            };
+            let mut ndjson = Vec::new();
+            for row in rows {
+                serde_json::to_writer(&mut ndjson, &row)?;
+                ndjson.push(b'\n');
+            }
+            append(&mut archive, entry, &ndjson)?;
+        }
+
+        let channels: Vec<TenantChannel> = db.list_channels(tenant_id)?
+            .into_iter()
+            .map(|mut ch| {
+                ch.config_json = scrub_json_str(&ch.config_json);
+                ch
+            })
+            .collect();
+        append(&mut archive, "channels.json", &serde_json::to_vec_pretty(&channels)?)?;
+
+        archive.into_inner()?.finish()?.flush()?;
+        tracing::info!("💾 Backed up tenant '{}' to {}", tenant.slug, archive_path.display());
+        Ok(archive_path)
+    }
+
+    /// Restore a backup archive.
+    ///
+    /// Without `new_slug`, an existing tenant with the archived slug is updated
+    /// in place; otherwise a new tenant is created under `new_slug` (or the
+    /// archived slug) on a free port.
+    pub fn restore_backup(archive_path: &Path, db: &PlatformDb, data_dir: &Path, new_slug: Option<String>) -> Result<Tenant> {
+        let entries = read_archive(archive_path)?;
+        let entry = |name: &str| entries.iter().find(|(n, _)| n == name).map(|(_, data)| data.as_slice());
+
+        let archived: Tenant = serde_json::from_slice(
+            entry("tenant.json").ok_or_else(|| BizClawError::Config("Backup is missing tenant.json".into()))?,
+        )?;
+        crate::db::validate_slug(new_slug.as_deref().unwrap_or(&archived.slug))?;
+
+        let existing = match &new_slug {
+            None => db.get_tenant_by_slug(&archived.slug)?,
+            Some(_) => None,
+        };
+        let tenant = match existing {
+            Some(current) => current,
+            None => {
+                let slug = new_slug.unwrap_or_else(|| archived.slug.clone());
+                let used = db.used_ports()?;
+                let port = if used.contains(&archived.port) {
+                    used.iter().max().map_or(archived.port, |p| p + 1)
+                } else {
+                    archived.port
+                };
+                db.create_tenant(&archived.name, &slug, port, &archived.provider, &archived.model, &archived.plan)?
+            }
+        };
+        db.update_tenant_profile(&Tenant { id: tenant.id.clone(), slug: tenant.slug.clone(), ..archived })?;
+
+        let tenant_dir = data_dir.join(&tenant.slug);
+        std::fs::create_dir_all(&tenant_dir)?;
+        if let Some(config) = entry("config.toml").filter(|c| !c.is_empty()) {
+            std::fs::write(tenant_dir.join("config.toml"), config)?;
+        }
+
+        let conn = Connection::open(tenant_dir.join("memory.db"))
+            .map_err(|e| BizClawError::Memory(format!("DB open error: {e}")))?;
+        conn.execute_batch(MEMORIES_SCHEMA)
+            .map_err(|e| BizClawError::Memory(format!("Migration error: {e}")))?;
+        for (table, name) in DATA_TABLES {
+            let Some(ndjson) = entry(name) else { continue };
+            let rows = ndjson.split(|b| *b == b'\n')
+                .filter(|line| !line.is_empty())
+                .map(serde_json::from_slice)
+                .collect::<std::result::Result<Vec<serde_json::Map<String, serde_json::Value>>, _>>()?;
+            import_table(&conn, table, &rows)?;
+        }
+
+        if let Some(channels) = entry("channels.json") {
+            let channels: Vec<TenantChannel> = serde_json::from_slice(channels)?;
+            for ch in channels {
+                db.upsert_channel(&tenant.id, &ch.channel_type, false, &ch.config_json)?;
+            }
+        }
+
+        tracing::info!("♻️ Restored tenant '{}' from {}", tenant.slug, archive_path.display());
+        db.get_tenant(&tenant.id)
+    }
+}
+
+fn append<W: Write>(archive: &mut tar::Builder<W>, name: &str, data: &[u8]) -> Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o600);
+    header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)?;
+    Ok(())
+}
+
+/// Read every regular file in the archive into memory.
+fn read_archive(path: &Path) -> Result<Vec<(String, Vec<u8>)>> {
+    let file = std::fs::File::open(path)?;
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    let mut entries = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = entry.path()?.to_string_lossy().into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data)?;
+        entries.push((name, data));
+    }
+    Ok(entries)
+}
+
+/// All rows of `table` as JSON objects; empty if the table doesn't exist.
+fn export_table(conn: &Connection, table: &str) -> Result<Vec<serde_json::Map<String, serde_json::Value>>> {
+    let exists: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type='table' AND name=?1",
+        [table],
+        |row| row.get(0),
+    ).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?;
+    if !exists {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn.prepare(&format!("SELECT * FROM \"{table}\""))
+        .map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let rows = stmt.query_map([], |row| {
+        let mut obj = serde_json::Map::new();
+        for (i, col) in columns.iter().enumerate() {
+            obj.insert(col.clone(), sql_to_json(row.get(i)?));
+        }
+        Ok(obj)
+    }).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .map_err(|e| BizClawError::Memory(format!("Query: {e}")))?;
+    Ok(rows)
+}
+
+/// Insert (or replace) rows into `table`, creating it untyped if it doesn't exist.
+fn import_table(conn: &Connection, table: &str, rows: &[serde_json::Map<String, serde_json::Value>]) -> Result<()> {
+    for row in rows {
+        let columns: Vec<&String> = row.keys().collect();
+        if columns.is_empty() {
+            continue;
+        }
+        let quoted: Vec<String> = columns.iter().map(|c| format!("\"{}\"", c.replace('"', "\"\""))).collect();
+        conn.execute(&format!("CREATE TABLE IF NOT EXISTS \"{table}\" ({})", quoted.join(", ")), [])
+            .map_err(|e| BizClawError::Memory(format!("Create {table}: {e}")))?;
+        let placeholders: Vec<String> = (1..=columns.len()).map(|i| format!("?{i}")).collect();
+        let values: Vec<SqlValue> = row.values().map(json_to_sql).collect();
+        conn.execute(
+            &format!("INSERT OR REPLACE INTO \"{table}\" ({}) VALUES ({})", quoted.join(", "), placeholders.join(", ")),
+            rusqlite::params_from_iter(values),
+        ).map_err(|e| BizClawError::Memory(format!("Restore {table}: {e}")))?;
+    }
+    Ok(())
+}
+
+/// Blobs (e.g. embeddings) are stored as `{"blob": [bytes]}`.
+fn sql_to_json(value: SqlValue) -> serde_json::Value {
+    match value {
+        SqlValue::Null => serde_json::Value::Null,
+        SqlValue::Integer(i) => i.into(),
+        SqlValue::Real(f) => f.into(),
+        SqlValue::Text(s) => s.into(),
+        SqlValue::Blob(b) => serde_json::json!({ "blob": b }),
+    }
+}
+
+fn json_to_sql(value: &serde_json::Value) -> SqlValue {
+    match value {
+        serde_json::Value::Null => SqlValue::Null,
+        serde_json::Value::Bool(b) => SqlValue::Integer(*b as i64),
+        serde_json::Value::Number(n) => n.as_i64().map(SqlValue::Integer)
+            .unwrap_or_else(|| SqlValue::Real(n.as_f64().unwrap_or_default())),
+        serde_json::Value::String(s) => SqlValue::Text(s.clone()),
+        serde_json::Value::Object(obj) if obj.len() == 1 && obj["blob"].is_array() => SqlValue::Blob(
+            obj["blob"].as_array().into_iter().flatten().filter_map(|b| b.as_u64()).map(|b| b as u8).collect(),
+        ),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+pub(crate) fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    SECRET_KEYS.contains(&key.as_str())
+}
+
+fn scrub_toml(text: &str) -> Result<String> {
+    fn scrub(value: &mut toml::Value) {
+        match value {
+            toml::Value::Table(table) => {
+                for (key, v) in table.iter_mut() {
+                    if is_secret_key(key) && v.is_str() {
+                        *v = toml::Value::String(String::new());
+                    } else {
+                        scrub(v);
+                    }
+                }
+            }
+            toml::Value::Array(items) => items.iter_mut().for_each(scrub),
+            _ => {}
+        }
+    }
+    let mut value: toml::Value = toml::from_str(text)
+        .map_err(|e| BizClawError::Config(format!("Invalid tenant config.toml: {e}")))?;
+    scrub(&mut value);
+    toml::to_string_pretty(&value).map_err(|e| BizClawError::Config(format!("Serialize config: {e}")))
+}
+
+fn scrub_json_str(text: &str) -> String {
+    let Ok(serde_json::Value::Object(mut obj)) = serde_json::from_str(text) else {
+        return "{}".into();
+    };
+    for (key, value) in obj.iter_mut() {
+        if is_secret_key(key) {
+            *value = serde_json::Value::String(String::new());
+        }
+    }
+    serde_json::Value::Object(obj).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bizclaw-backup-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_scrub_secrets() {
+        let toml = scrub_toml("api_key = \"sk-1\"\n[channel.telegram]\nenabled = true\nbot_token = \"123:abc\"\n").unwrap();
+        assert!(!toml.contains("sk-1"));
+        assert!(!toml.contains("123:abc"));
+        assert!(toml.contains("enabled = true"));
+
+        let json = scrub_json_str(r#"{"bot_token":"123:abc","allowed_chat_ids":"1,2"}"#);
+        assert!(!json.contains("123:abc"));
+        assert!(json.contains("1,2"));
+    }
+
+    #[test]
+    fn test_backup_and_restore_roundtrip() {
+        let data_dir = temp_dir();
+        let db = PlatformDb::open(Path::new(":memory:")).unwrap();
+        let tenant = db.create_tenant("Shop", "shop", 10001, "openai", "gpt-4o", "pro").unwrap();
+        db.upsert_channel(&tenant.id, "telegram", true, r#"{"bot_token":"123:abc"}"#).unwrap();
+
+        let tenant_dir = data_dir.join("shop");
+        std::fs::create_dir_all(&tenant_dir).unwrap();
+        std::fs::write(tenant_dir.join("config.toml"), "default_provider = \"openai\"\napi_key = \"sk-secret\"\n").unwrap();
+        let conn = Connection::open(tenant_dir.join("memory.db")).unwrap();
+        conn.execute_batch(MEMORIES_SCHEMA).unwrap();
+        conn.execute(
+            "INSERT INTO memories (id, content, embedding, created_at, updated_at) VALUES ('m1', 'likes tea', x'0102', 't', 't')",
+            [],
+        ).unwrap();
+        conn.execute_batch("CREATE TABLE messages (id INTEGER PRIMARY KEY, role TEXT, content TEXT);
+                            INSERT INTO messages (role, content) VALUES ('user', 'hi');").unwrap();
+        drop(conn);
+
+        let archive = TenantBackup::create_backup(&tenant.id, &db, &data_dir).unwrap();
+        assert!(archive.starts_with(data_dir.join("backups")));
+        let entries = read_archive(&archive).unwrap();
+        for name in ["tenant.json", "config.toml", "messages.ndjson", "memory.ndjson", "channels.json"] {
+            assert!(entries.iter().any(|(n, _)| n == name), "missing {name}");
+        }
+        assert!(entries.iter().all(|(_, data)| !String::from_utf8_lossy(data).contains("sk-secret")));
+        assert!(entries.iter().all(|(_, data)| !String::from_utf8_lossy(data).contains("123:abc")));
+
+        let copy = TenantBackup::restore_backup(&archive, &db, &data_dir, Some("shop-copy".into())).unwrap();
+        assert_ne!(copy.id, tenant.id);
+        assert_eq!(copy.slug, "shop-copy");
+        assert_eq!(copy.plan, "pro");
+        assert_ne!(copy.port, tenant.port);
+
+        let conn = Connection::open(data_dir.join("shop-copy").join("memory.db")).unwrap();
+        let embedding: Vec<u8> = conn.query_row("SELECT embedding FROM memories WHERE id='m1'", [], |r| r.get(0)).unwrap();
+        assert_eq!(embedding, vec![1, 2]);
+        let content: String = conn.query_row("SELECT content FROM messages", [], |r| r.get(0)).unwrap();
+        assert_eq!(content, "hi");
+        let channels = db.list_channels(&copy.id).unwrap();
+        assert_eq!(channels.len(), 1);
+        assert!(!channels[0].enabled);
+
+        // Without a new slug the existing tenant is updated in place.
+        let same = TenantBackup::restore_backup(&archive, &db, &data_dir, None).unwrap();
+        assert_eq!(same.id, tenant.id);
+        assert_eq!(db.list_tenants().unwrap().len(), 2);
+
+        // A slug that isn't `[a-z0-9-]+` could write outside the data directory.
+        let err = TenantBackup::restore_backup(&archive, &db, &data_dir, Some("../escape".into())).unwrap_err();
+        assert!(err.to_string().contains("Invalid tenant slug"), "{err}");
+        assert!(!data_dir.join("../escape").exists());
+        assert_eq!(db.list_tenants().unwrap().len(), 2);
+
+        std::fs::remove_dir_all(&data_dir).ok();
+    }
+}
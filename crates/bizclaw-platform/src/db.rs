@@ -1,14 +1,39 @@
 //! Platform database — SQLite schema for multi-tenant management.
 
 use rusqlite::{Connection, params};
+use bizclaw_channels::history::{MessageDirection, MessageHistory};
+use bizclaw_channels::identity::UserIdentities;
+use bizclaw_channels::manager::ChannelStatusStore;
+use bizclaw_channels::webhook::{DeadLetterStore, FailedDelivery};
 use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::quota::{MessageQuota, Remaining};
+use bizclaw_security::secrets::{SecretCipher, is_encrypted};
 use std::path::Path;
 
 /// Platform database manager.
 pub struct PlatformDb {
     conn: Connection,
+    /// Daily message counts roll over at midnight here.
+    quota_timezone: chrono_tz::Tz,
+    /// Encrypts the secret fields of channel configs and tenant secrets,
+    /// when set.
+    secrets: Option<SecretCipher>,
 }
 
+/// Check that `slug` is `[a-z0-9-]+`. Slugs name the tenant's data
+/// directory, so anything else (`..`, `/`) could point outside it.
+pub fn validate_slug(slug: &str) -> Result<()> {
+    if slug.is_empty() || !slug.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-') {
+        return Err(BizClawError::Config(format!(
+            "Invalid tenant slug '{slug}': use lowercase letters, digits and '-'"
+        )));
+    }
+    Ok(())
+}
+
+/// Default for [`PlatformDb::with_quota_timezone`].
+pub const DEFAULT_QUOTA_TIMEZONE: chrono_tz::Tz = chrono_tz::Asia::Ho_Chi_Minh;
+
 /// Tenant record.
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Tenant {
@@ -67,16 +92,171 @@ pub struct TenantChannel {
     pub updated_at: String,
 }
 
+/// A tenant's user on one channel, and the canonical user they are.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct UserIdentity {
+    pub id: String,
+    pub tenant_id: String,
+    pub canonical_id: String,
+    pub channel: String,
+    pub channel_user_id: String,
+    /// Contact details seen for the user; identities sharing one are merged.
+    pub email: Option<String>,
+    pub phone: Option<String>,
+}
+
+/// Pending invitation for someone without an account yet.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Invitation {
+    pub id: String,
+    pub email: String,
+    pub role: String,
+    pub tenant_id: Option<String>,
+    /// Secret the invitee presents to set up their account.
+    #[serde(skip_serializing)]
+    pub token: String,
+    pub created_at: String,
+}
+
+/// Outbound webhook that failed every delivery attempt.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FailedWebhook {
+    pub id: String,
+    pub tenant_id: Option<String>,
+    pub url: String,
+    pub payload_json: String,
+    pub attempts: u32,
+    pub last_error: String,
+    pub created_at: String,
+}
+
+/// Messages kept per tenant and channel; older ones are purged.
+pub const CHANNEL_HISTORY_LIMIT: i64 = 10_000;
+
+/// A message a tenant's channel received (`inbound`) or sent (`outbound`).
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ChannelMessage {
+    pub id: i64,
+    pub tenant_id: String,
+    pub channel: String,
+    /// The chat the message belongs to.
+    pub thread_id: String,
+    /// The channel's own ID for the message, when it reports one.
+    pub channel_message_id: Option<String>,
+    pub direction: String,
+    pub sender: String,
+    pub content: String,
+    pub created_at: String,
+}
+
+/// `tenant_id` of platform-wide feature flags.
+pub const ALL_TENANTS: &str = "*";
+
+/// Flag for showing responses as they are generated (`channel.streaming`).
+pub const FLAG_STREAMING: &str = "streaming";
+
+/// Name of a tenant's provider API key in `tenant_secrets`.
+const SECRET_PROVIDER_API_KEY: &str = "provider_api_key";
+
+/// A feature flag setting, for one tenant or all of them.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct FeatureFlag {
+    pub tenant_id: String,
+    pub flag_name: String,
+    pub enabled: bool,
+}
+
 impl PlatformDb {
     /// Open or create the platform database.
     pub fn open(path: &Path) -> Result<Self> {
         let conn = Connection::open(path)
             .map_err(|e| BizClawError::Memory(format!("DB open error: {e}")))?;
-        let db = Self { conn };
+        let db = Self { conn, quota_timezone: DEFAULT_QUOTA_TIMEZONE, secrets: None };
         db.migrate()?;
         Ok(db)
     }
 
+    /// Count messages per day in `timezone` rather than the default.
+    pub fn with_quota_timezone(mut self, timezone: chrono_tz::Tz) -> Self {
+        self.quota_timezone = timezone;
+        self
+    }
+
+    /// Keep the secret fields of channel configs (`bot_token`, `cookie`, …)
+    /// and tenant secrets encrypted with `cipher`, encrypting any stored in
+    /// plaintext now. Other fields stay readable in the database.
+    pub fn with_secret_cipher(mut self, cipher: SecretCipher) -> Result<Self> {
+        self.secrets = Some(cipher);
+        let rows: Vec<(String, String)> = {
+            let mut stmt = self.conn.prepare("SELECT id, config_json FROM tenant_channels")
+                .map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+                .map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+                .filter_map(|r| r.ok())
+                .collect()
+        };
+        let mut encrypted = 0;
+        for (id, config_json) in rows {
+            let sealed = self.seal(&config_json)?;
+            if sealed != config_json {
+                self.conn.execute("UPDATE tenant_channels SET config_json=?1 WHERE id=?2", params![sealed, id])
+                    .map_err(|e| BizClawError::Memory(format!("Encrypt channel secrets: {e}")))?;
+                encrypted += 1;
+            }
+        }
+        if encrypted > 0 {
+            tracing::info!("🔐 Encrypted the secrets of {encrypted} channel config(s)");
+        }
+        let rows: Vec<(String, String, String)> = {
+            let mut stmt = self.conn.prepare("SELECT tenant_id, name, value FROM tenant_secrets")
+                .map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))
+                .map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+                .filter_map(|r| r.ok())
+                .filter(|(_, _, value): &(String, String, String)| !is_encrypted(value))
+                .collect()
+        };
+        for (tenant_id, name, value) in &rows {
+            self.set_tenant_secret(tenant_id, name, value)?;
+        }
+        if !rows.is_empty() {
+            tracing::info!("🔐 Encrypted {} tenant secret(s)", rows.len());
+        }
+        Ok(self)
+    }
+
+    /// `config_json` with its secret fields encrypted, if there is a cipher.
+    fn seal(&self, config_json: &str) -> Result<String> {
+        let Some(cipher) = &self.secrets else {
+            return Ok(config_json.to_string());
+        };
+        map_secret_fields(config_json, |value| {
+            if value.is_empty() || is_encrypted(value) {
+                Ok(None)
+            } else {
+                cipher.encrypt(value).map(Some)
+            }
+        })
+    }
+
+    /// `config_json` with its encrypted fields decrypted.
+    fn unseal(&self, config_json: &str) -> Result<String> {
+        if !config_json.contains(bizclaw_security::secrets::ENCRYPTED_PREFIX) {
+            return Ok(config_json.to_string());
+        }
+        map_secret_fields(config_json, |value| {
+            if !is_encrypted(value) {
+                return Ok(None);
+            }
+            match &self.secrets {
+                Some(cipher) => cipher.decrypt(value).map(Some),
+                None => Err(BizClawError::Security(
+                    "Channel secrets are encrypted; set BIZCLAW_MASTER_KEY to read them".into(),
+                )),
+            }
+        })
+    }
+
     /// Run schema migrations.
     fn migrate(&self) -> Result<()> {
         self.conn.execute_batch("
@@ -140,6 +320,74 @@ impl PlatformDb {
                 updated_at TEXT DEFAULT (datetime('now')),
                 UNIQUE(tenant_id, channel_type)
             );
+
+            CREATE TABLE IF NOT EXISTS tenant_secrets (
+                tenant_id TEXT NOT NULL,
+                name TEXT NOT NULL,
+                value TEXT NOT NULL,
+                updated_at TEXT DEFAULT (datetime('now')),
+                PRIMARY KEY (tenant_id, name)
+            );
+
+            CREATE TABLE IF NOT EXISTS feature_flags (
+                tenant_id TEXT NOT NULL,
+                flag_name TEXT NOT NULL,
+                enabled INTEGER NOT NULL,
+                PRIMARY KEY (tenant_id, flag_name)
+            );
+
+            CREATE TABLE IF NOT EXISTS invitations (
+                id TEXT PRIMARY KEY,
+                email TEXT UNIQUE NOT NULL,
+                role TEXT NOT NULL,
+                tenant_id TEXT,
+                token TEXT UNIQUE NOT NULL,
+                created_at TEXT DEFAULT (datetime('now'))
+            );
+
+            CREATE TABLE IF NOT EXISTS user_identities (
+                id TEXT PRIMARY KEY,
+                tenant_id TEXT NOT NULL,
+                canonical_id TEXT NOT NULL,
+                channel TEXT NOT NULL,
+                channel_user_id TEXT NOT NULL,
+                email TEXT,
+                phone TEXT,
+                UNIQUE(tenant_id, channel, channel_user_id)
+            );
+            CREATE INDEX IF NOT EXISTS idx_user_identities_canonical
+                ON user_identities (tenant_id, canonical_id);
+
+            CREATE TABLE IF NOT EXISTS channel_messages (
+                id INTEGER PRIMARY KEY,
+                tenant_id TEXT NOT NULL,
+                channel TEXT NOT NULL,
+                thread_id TEXT NOT NULL DEFAULT '',
+                channel_message_id TEXT,
+                direction TEXT NOT NULL,
+                sender TEXT NOT NULL,
+                content TEXT NOT NULL,
+                created_at TEXT DEFAULT (datetime('now'))
+            );
+            CREATE INDEX IF NOT EXISTS idx_channel_messages_tenant_channel
+                ON channel_messages (tenant_id, channel, id);
+
+            CREATE TABLE IF NOT EXISTS tenant_message_counts (
+                tenant_id TEXT NOT NULL,
+                day TEXT NOT NULL,
+                count INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY (tenant_id, day)
+            );
+
+            CREATE TABLE IF NOT EXISTS failed_webhooks (
+                id TEXT PRIMARY KEY,
+                tenant_id TEXT,
+                url TEXT NOT NULL,
+                payload_json TEXT NOT NULL,
+                attempts INTEGER NOT NULL,
+                last_error TEXT NOT NULL,
+                created_at TEXT DEFAULT (datetime('now'))
+            );
         ").map_err(|e| BizClawError::Memory(format!("Migration error: {e}")))?;
         Ok(())
     }
@@ -148,6 +396,7 @@ impl PlatformDb {
 
     /// Create a new tenant.
     pub fn create_tenant(&self, name: &str, slug: &str, port: u16, provider: &str, model: &str, plan: &str) -> Result<Tenant> {
+        validate_slug(slug)?;
         let id = uuid::Uuid::new_v4().to_string();
         let pairing_code = format!("{:06}", rand_code());
 
@@ -202,10 +451,30 @@ impl PlatformDb {
         Ok(())
     }
 
+    /// Get a tenant by slug, if one exists.
+    pub fn get_tenant_by_slug(&self, slug: &str) -> Result<Option<Tenant>> {
+        match self.conn.query_row("SELECT id FROM tenants WHERE slug=?1", params![slug], |row| row.get::<_, String>(0)) {
+            Ok(id) => self.get_tenant(&id).map(Some),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(BizClawError::Memory(format!("Get tenant: {e}"))),
+        }
+    }
+
+    /// Update a tenant's name, plan, provider, model, and limits.
+    pub fn update_tenant_profile(&self, tenant: &Tenant) -> Result<()> {
+        self.conn.execute(
+            "UPDATE tenants SET name=?1, plan=?2, provider=?3, model=?4, max_messages_day=?5, max_channels=?6, max_members=?7, updated_at=datetime('now') WHERE id=?8",
+            params![tenant.name, tenant.plan, tenant.provider, tenant.model, tenant.max_messages_day, tenant.max_channels, tenant.max_members, tenant.id],
+        ).map_err(|e| BizClawError::Memory(format!("Update tenant: {e}")))?;
+        Ok(())
+    }
+
     /// Delete a tenant.
     pub fn delete_tenant(&self, id: &str) -> Result<()> {
         self.conn.execute("DELETE FROM tenants WHERE id=?1", params![id])
             .map_err(|e| BizClawError::Memory(format!("Delete tenant: {e}")))?;
+        self.conn.execute("DELETE FROM tenant_secrets WHERE tenant_id=?1", params![id])
+            .map_err(|e| BizClawError::Memory(format!("Delete tenant secrets: {e}")))?;
         Ok(())
     }
 
@@ -277,6 +546,87 @@ impl PlatformDb {
         Ok(users)
     }
 
+    /// Whether `email` already has an account or a pending invitation.
+    pub fn email_exists(&self, email: &str) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM users WHERE email=?1 COLLATE NOCASE)
+                 OR EXISTS(SELECT 1 FROM invitations WHERE email=?1 COLLATE NOCASE)",
+            params![email],
+            |row| row.get(0),
+        ).map_err(|e| BizClawError::Memory(format!("Email exists: {e}")))
+    }
+
+    /// Invite `email` to sign up with `role`, optionally in a tenant.
+    pub fn create_invitation(&self, email: &str, role: &str, tenant_id: Option<&str>) -> Result<Invitation> {
+        let id = uuid::Uuid::new_v4().to_string();
+        let token = uuid::Uuid::new_v4().simple().to_string();
+        self.conn.execute(
+            "INSERT INTO invitations (id, email, role, tenant_id, token) VALUES (?1,?2,?3,?4,?5)",
+            params![id, email, role, tenant_id, token],
+        ).map_err(|e| BizClawError::Memory(format!("Create invitation: {e}")))?;
+        self.conn.query_row(
+            "SELECT id,email,role,tenant_id,token,created_at FROM invitations WHERE id=?1", params![id],
+            |row| Ok(Invitation {
+                id: row.get(0)?, email: row.get(1)?, role: row.get(2)?,
+                tenant_id: row.get(3)?, token: row.get(4)?, created_at: row.get(5)?,
+            }),
+        ).map_err(|e| BizClawError::Memory(format!("Get invitation: {e}")))
+    }
+
+    /// List pending invitations, newest first.
+    pub fn list_invitations(&self) -> Result<Vec<Invitation>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id,email,role,tenant_id,token,created_at FROM invitations ORDER BY created_at DESC"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+
+        let invitations = stmt.query_map([], |row| Ok(Invitation {
+            id: row.get(0)?, email: row.get(1)?, role: row.get(2)?,
+            tenant_id: row.get(3)?, token: row.get(4)?, created_at: row.get(5)?,
+        })).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(invitations)
+    }
+
+    // ── Failed Webhooks ────────────────────────────────
+
+    /// Keep a delivery that ran out of retries.
+    pub fn record_failed_webhook(&self, failed: &FailedDelivery) -> Result<()> {
+        let delivery = &failed.delivery;
+        self.conn.execute(
+            "INSERT OR REPLACE INTO failed_webhooks (id, tenant_id, url, payload_json, attempts, last_error) VALUES (?1,?2,?3,?4,?5,?6)",
+            params![delivery.id, delivery.tenant_id, delivery.url, delivery.payload.to_string(), failed.attempts, failed.last_error],
+        ).map_err(|e| BizClawError::Memory(format!("Record failed webhook: {e}")))?;
+        Ok(())
+    }
+
+    /// Failed webhooks, newest first.
+    pub fn list_failed_webhooks(&self) -> Result<Vec<FailedWebhook>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id,tenant_id,url,payload_json,attempts,last_error,created_at FROM failed_webhooks ORDER BY created_at DESC"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+
+        let failed = stmt.query_map([], failed_webhook_from_row)
+            .map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(failed)
+    }
+
+    pub fn get_failed_webhook(&self, id: &str) -> Result<FailedWebhook> {
+        self.conn.query_row(
+            "SELECT id,tenant_id,url,payload_json,attempts,last_error,created_at FROM failed_webhooks WHERE id=?1",
+            params![id],
+            failed_webhook_from_row,
+        ).map_err(|e| BizClawError::Memory(format!("Failed webhook not found: {e}")))
+    }
+
+    pub fn delete_failed_webhook(&self, id: &str) -> Result<()> {
+        self.conn.execute("DELETE FROM failed_webhooks WHERE id=?1", params![id])
+            .map_err(|e| BizClawError::Memory(format!("Delete failed webhook: {e}")))?;
+        Ok(())
+    }
+
     // ── Audit Log ────────────────────────────────────
 
     /// Log an audit event.
@@ -332,6 +682,7 @@ impl PlatformDb {
     /// Save or update a channel configuration for a tenant.
     pub fn upsert_channel(&self, tenant_id: &str, channel_type: &str, enabled: bool, config_json: &str) -> Result<TenantChannel> {
         let id = format!("{}-{}", tenant_id, channel_type);
+        let config_json = self.seal(config_json)?;
         self.conn.execute(
             "INSERT INTO tenant_channels (id, tenant_id, channel_type, enabled, config_json, updated_at)
              VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'))
@@ -344,7 +695,7 @@ impl PlatformDb {
 
     /// Get a single channel config by ID.
     pub fn get_channel(&self, id: &str) -> Result<TenantChannel> {
-        self.conn.query_row(
+        let mut channel = self.conn.query_row(
             "SELECT id, tenant_id, channel_type, enabled, config_json, status, status_message, created_at, updated_at FROM tenant_channels WHERE id=?1",
             params![id],
             |row| Ok(TenantChannel {
@@ -353,7 +704,9 @@ impl PlatformDb {
                 config_json: row.get(4)?, status: row.get(5)?,
                 status_message: row.get(6)?, created_at: row.get(7)?, updated_at: row.get(8)?,
             }),
-        ).map_err(|e| BizClawError::Memory(format!("Get channel: {e}")))
+        ).map_err(|e| BizClawError::Memory(format!("Get channel: {e}")))?;
+        channel.config_json = self.unseal(&channel.config_json)?;
+        Ok(channel)
     }
 
     /// List all channels for a tenant.
@@ -362,7 +715,7 @@ impl PlatformDb {
             "SELECT id, tenant_id, channel_type, enabled, config_json, status, status_message, created_at, updated_at FROM tenant_channels WHERE tenant_id=?1 ORDER BY channel_type"
         ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
 
-        let channels = stmt.query_map(params![tenant_id], |row| Ok(TenantChannel {
+        let channels: Vec<TenantChannel> = stmt.query_map(params![tenant_id], |row| Ok(TenantChannel {
             id: row.get(0)?, tenant_id: row.get(1)?, channel_type: row.get(2)?,
             enabled: row.get::<_, i32>(3)? != 0,
             config_json: row.get(4)?, status: row.get(5)?,
@@ -370,7 +723,12 @@ impl PlatformDb {
         })).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
             .filter_map(|r| r.ok())
             .collect();
-        Ok(channels)
+        channels.into_iter()
+            .map(|mut ch| {
+                ch.config_json = self.unseal(&ch.config_json)?;
+                Ok(ch)
+            })
+            .collect()
     }
 
     /// Update channel connection status.
@@ -388,6 +746,457 @@ impl PlatformDb {
             .map_err(|e| BizClawError::Memory(format!("Delete channel: {e}")))?;
         Ok(())
     }
+
+    // ── Channel History ────────────────────────────────────
+
+    /// Record a channel message (its `id` and `created_at` are assigned
+    /// here), purging the oldest beyond [`CHANNEL_HISTORY_LIMIT`] for the
+    /// same tenant and channel.
+    pub fn log_channel_message(&self, message: &ChannelMessage) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO channel_messages (tenant_id, channel, thread_id, channel_message_id, direction, sender, content)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![message.tenant_id, message.channel, message.thread_id, message.channel_message_id,
+                message.direction, message.sender, message.content],
+        ).map_err(|e| BizClawError::Memory(format!("Log channel message: {e}")))?;
+        self.conn.execute(
+            "DELETE FROM channel_messages WHERE tenant_id=?1 AND channel=?2 AND id <= (
+                SELECT id FROM channel_messages WHERE tenant_id=?1 AND channel=?2
+                ORDER BY id DESC LIMIT 1 OFFSET ?3)",
+            params![message.tenant_id, message.channel, CHANNEL_HISTORY_LIMIT],
+        ).map_err(|e| BizClawError::Memory(format!("Purge channel messages: {e}")))?;
+        Ok(())
+    }
+
+    /// A tenant's messages on `channel`, newest first.
+    pub fn channel_message_history(&self, tenant_id: &str, channel: &str, limit: usize, offset: usize) -> Result<Vec<ChannelMessage>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, tenant_id, channel, thread_id, channel_message_id, direction, sender, content, created_at
+             FROM channel_messages WHERE tenant_id=?1 AND channel=?2 ORDER BY id DESC LIMIT ?3 OFFSET ?4"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+
+        let messages = stmt.query_map(params![tenant_id, channel, limit as i64, offset as i64], |row| Ok(ChannelMessage {
+            id: row.get(0)?, tenant_id: row.get(1)?, channel: row.get(2)?, thread_id: row.get(3)?,
+            channel_message_id: row.get(4)?, direction: row.get(5)?, sender: row.get(6)?,
+            content: row.get(7)?, created_at: row.get(8)?,
+        })).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(messages)
+    }
+
+    // ── User Identities ────────────────────────────────────
+
+    /// The canonical user `channel_user_id` on `channel` is, creating one if
+    /// it's new. Email senders and WhatsApp numbers are recorded as the
+    /// user's email or phone, merging them with identities that share it.
+    pub fn resolve_user(&self, tenant_id: &str, channel: &str, channel_user_id: &str) -> Result<String> {
+        if let Some(canonical_id) = self.find_user(tenant_id, channel, channel_user_id)? {
+            return Ok(canonical_id);
+        }
+        let canonical_id = uuid::Uuid::new_v4().to_string();
+        self.conn.execute(
+            "INSERT INTO user_identities (id, tenant_id, canonical_id, channel, channel_user_id) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![uuid::Uuid::new_v4().to_string(), tenant_id, canonical_id, channel, channel_user_id],
+        ).map_err(|e| BizClawError::Memory(format!("Create user identity: {e}")))?;
+        match channel {
+            "email" => self.set_user_contact(tenant_id, channel, channel_user_id, Some(channel_user_id), None),
+            "whatsapp" => self.set_user_contact(tenant_id, channel, channel_user_id, None, Some(channel_user_id)),
+            _ => Ok(canonical_id),
+        }
+    }
+
+    fn find_user(&self, tenant_id: &str, channel: &str, channel_user_id: &str) -> Result<Option<String>> {
+        match self.conn.query_row(
+            "SELECT canonical_id FROM user_identities WHERE tenant_id=?1 AND channel=?2 AND channel_user_id=?3",
+            params![tenant_id, channel, channel_user_id],
+            |row| row.get(0),
+        ) {
+            Ok(canonical_id) => Ok(Some(canonical_id)),
+            Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
+            Err(e) => Err(BizClawError::Memory(format!("Find user identity: {e}"))),
+        }
+    }
+
+    /// Record an email or phone detected for a channel user. If other users
+    /// of the tenant have the same one, they and this user become one.
+    /// Returns the user's canonical ID.
+    pub fn set_user_contact(
+        &self, tenant_id: &str, channel: &str, channel_user_id: &str, email: Option<&str>, phone: Option<&str>,
+    ) -> Result<String> {
+        let canonical_id = self.resolve_user(tenant_id, channel, channel_user_id)?;
+        let email = email.map(|e| e.trim().to_lowercase()).filter(|e| !e.is_empty());
+        let phone = phone.map(normalize_phone).filter(|p| !p.is_empty());
+        self.conn.execute(
+            "UPDATE user_identities SET email=COALESCE(?1, email), phone=COALESCE(?2, phone)
+             WHERE tenant_id=?3 AND channel=?4 AND channel_user_id=?5",
+            params![email, phone, tenant_id, channel, channel_user_id],
+        ).map_err(|e| BizClawError::Memory(format!("Set user contact: {e}")))?;
+
+        let mut stmt = self.conn.prepare(
+            "SELECT DISTINCT canonical_id FROM user_identities
+             WHERE tenant_id=?1 AND canonical_id != ?2 AND ((?3 IS NOT NULL AND email=?3) OR (?4 IS NOT NULL AND phone=?4))"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+        let others: Vec<String> = stmt.query_map(params![tenant_id, canonical_id, email, phone], |row| row.get(0))
+            .map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .collect();
+        // Join the user already known by this contact.
+        let Some((keep, rest)) = others.split_first() else { return Ok(canonical_id) };
+        for merged in rest.iter().chain([&canonical_id]) {
+            self.merge_users(tenant_id, keep, merged)?;
+        }
+        Ok(keep.clone())
+    }
+
+    /// Make the secondary channel user the same user as the primary one,
+    /// along with every identity already linked to it. Returns the
+    /// primary's canonical ID.
+    pub fn link_users(
+        &self, tenant_id: &str, primary_channel: &str, primary_id: &str, secondary_channel: &str, secondary_id: &str,
+    ) -> Result<String> {
+        let primary = self.resolve_user(tenant_id, primary_channel, primary_id)?;
+        let secondary = self.resolve_user(tenant_id, secondary_channel, secondary_id)?;
+        if primary != secondary {
+            self.merge_users(tenant_id, &primary, &secondary)?;
+        }
+        Ok(primary)
+    }
+
+    fn merge_users(&self, tenant_id: &str, keep: &str, merged: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE user_identities SET canonical_id=?1 WHERE tenant_id=?2 AND canonical_id=?3",
+            params![keep, tenant_id, merged],
+        ).map_err(|e| BizClawError::Memory(format!("Merge users: {e}")))?;
+        Ok(())
+    }
+
+    /// The channel identities of a canonical user.
+    pub fn user_identities(&self, tenant_id: &str, canonical_id: &str) -> Result<Vec<UserIdentity>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, tenant_id, canonical_id, channel, channel_user_id, email, phone
+             FROM user_identities WHERE tenant_id=?1 AND canonical_id=?2 ORDER BY channel, channel_user_id"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+
+        let identities = stmt.query_map(params![tenant_id, canonical_id], |row| Ok(UserIdentity {
+            id: row.get(0)?, tenant_id: row.get(1)?, canonical_id: row.get(2)?, channel: row.get(3)?,
+            channel_user_id: row.get(4)?, email: row.get(5)?, phone: row.get(6)?,
+        })).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(identities)
+    }
+
+    // ── Message Quota ────────────────────────────────────
+
+    /// The quota day (`YYYY-MM-DD` in the quota timezone) that `at` falls on.
+    pub fn quota_day(&self, at: chrono::DateTime<chrono::Utc>) -> String {
+        at.with_timezone(&self.quota_timezone).format("%Y-%m-%d").to_string()
+    }
+
+    /// Count one message for today; returns today's count.
+    pub fn increment_message_count(&self, tenant_id: &str) -> Result<u32> {
+        let day = self.quota_day(chrono::Utc::now());
+        self.conn.query_row(
+            "INSERT INTO tenant_message_counts (tenant_id, day, count) VALUES (?1, ?2, 1)
+             ON CONFLICT(tenant_id, day) DO UPDATE SET count = count + 1
+             RETURNING count",
+            params![tenant_id, day],
+            |row| row.get(0),
+        ).map_err(|e| BizClawError::Memory(format!("Increment message count: {e}")))
+    }
+
+    /// Messages counted for `tenant_id` on `day`.
+    pub fn message_count(&self, tenant_id: &str, day: &str) -> Result<u32> {
+        self.conn.query_row(
+            "SELECT count FROM tenant_message_counts WHERE tenant_id=?1 AND day=?2",
+            params![tenant_id, day],
+            |row| row.get(0),
+        ).or_else(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => Ok(0),
+            e => Err(BizClawError::Memory(format!("Message count: {e}"))),
+        })
+    }
+
+    /// Today's usage against the tenant's `max_messages_day` (0 = unlimited);
+    /// `QuotaExceeded` once the limit is reached.
+    pub fn check_quota(&self, tenant_id: &str) -> Result<Remaining> {
+        let limit = self.get_tenant(tenant_id)?.max_messages_day;
+        let used = self.message_count(tenant_id, &self.quota_day(chrono::Utc::now()))?;
+        let remaining = Remaining { used, limit };
+        if remaining.exceeded() {
+            return Err(BizClawError::QuotaExceeded(format!(
+                "the daily limit of {limit} messages is reached; it resets at midnight ({})", self.quota_timezone
+            )));
+        }
+        Ok(remaining)
+    }
+
+    // ── Tenant Secrets ────────────────────────────────────
+
+    /// Give a tenant its own API key for its provider, used instead of the
+    /// platform's; an empty key removes it.
+    pub fn set_provider_api_key(&self, tenant_id: &str, api_key: &str) -> Result<()> {
+        if api_key.is_empty() {
+            self.conn.execute(
+                "DELETE FROM tenant_secrets WHERE tenant_id=?1 AND name=?2",
+                params![tenant_id, SECRET_PROVIDER_API_KEY],
+            ).map_err(|e| BizClawError::Memory(format!("Delete tenant secret: {e}")))?;
+            return Ok(());
+        }
+        self.set_tenant_secret(tenant_id, SECRET_PROVIDER_API_KEY, api_key)
+    }
+
+    /// The tenant's own provider API key, if it has one.
+    pub fn provider_api_key(&self, tenant_id: &str) -> Result<Option<String>> {
+        self.tenant_secret(tenant_id, SECRET_PROVIDER_API_KEY)
+    }
+
+    /// Store a secret, encrypted if there is a cipher.
+    fn set_tenant_secret(&self, tenant_id: &str, name: &str, value: &str) -> Result<()> {
+        let value = match &self.secrets {
+            Some(cipher) => cipher.encrypt(value)?,
+            None => value.to_string(),
+        };
+        self.conn.execute(
+            "INSERT INTO tenant_secrets (tenant_id, name, value, updated_at) VALUES (?1, ?2, ?3, datetime('now'))
+             ON CONFLICT(tenant_id, name) DO UPDATE SET value = ?3, updated_at = datetime('now')",
+            params![tenant_id, name, value],
+        ).map_err(|e| BizClawError::Memory(format!("Set tenant secret: {e}")))?;
+        Ok(())
+    }
+
+    fn tenant_secret(&self, tenant_id: &str, name: &str) -> Result<Option<String>> {
+        let value: String = match self.conn.query_row(
+            "SELECT value FROM tenant_secrets WHERE tenant_id=?1 AND name=?2",
+            params![tenant_id, name],
+            |row| row.get(0),
+        ) {
+            Ok(value) => value,
+            Err(rusqlite::Error::QueryReturnedNoRows) => return Ok(None),
+            Err(e) => return Err(BizClawError::Memory(format!("Get tenant secret: {e}"))),
+        };
+        if !is_encrypted(&value) {
+            return Ok(Some(value));
+        }
+        match &self.secrets {
+            Some(cipher) => cipher.decrypt(&value).map(Some),
+            None => Err(BizClawError::Security(
+                "Tenant secrets are encrypted; set BIZCLAW_MASTER_KEY to read them".into(),
+            )),
+        }
+    }
+
+    // ── Feature Flags ────────────────────────────────────
+
+    /// Turn a flag on or off for a tenant, or for all tenants with [`ALL_TENANTS`].
+    pub fn set_flag(&self, tenant_id: &str, flag: &str, enabled: bool) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO feature_flags (tenant_id, flag_name, enabled) VALUES (?1, ?2, ?3)
+             ON CONFLICT(tenant_id, flag_name) DO UPDATE SET enabled = ?3",
+            params![tenant_id, flag, enabled as i32],
+        ).map_err(|e| BizClawError::Memory(format!("Set flag: {e}")))?;
+        Ok(())
+    }
+
+    /// Whether `flag` is on for a tenant: its own setting, else the
+    /// platform-wide one, else off.
+    pub fn flag_enabled(&self, tenant_id: &str, flag: &str) -> Result<bool> {
+        self.conn.query_row(
+            "SELECT enabled FROM feature_flags WHERE flag_name=?2 AND tenant_id IN (?1, '*')
+             ORDER BY tenant_id = '*' LIMIT 1",
+            params![tenant_id, flag],
+            |row| row.get::<_, i32>(0),
+        ).map(|enabled| enabled != 0)
+            .or_else(|e| match e {
+                rusqlite::Error::QueryReturnedNoRows => Ok(false),
+                e => Err(BizClawError::Memory(format!("Flag enabled: {e}"))),
+            })
+    }
+
+    /// Flags set for exactly `tenant_id` (use [`ALL_TENANTS`] for platform-wide ones).
+    pub fn list_flags(&self, tenant_id: &str) -> Result<Vec<FeatureFlag>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT tenant_id, flag_name, enabled FROM feature_flags WHERE tenant_id=?1 ORDER BY flag_name"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+
+        let flags = stmt.query_map(params![tenant_id], |row| Ok(FeatureFlag {
+            tenant_id: row.get(0)?, flag_name: row.get(1)?,
+            enabled: row.get::<_, i32>(2)? != 0,
+        })).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(flags)
+    }
+}
+
+/// `config_json` with each string under a secret key (see
+/// [`crate::backup`]), at any depth, replaced by what `f` returns for it.
+/// Unchanged, byte for byte, when `f` replaces nothing or it isn't JSON.
+fn map_secret_fields(config_json: &str, mut f: impl FnMut(&str) -> Result<Option<String>>) -> Result<String> {
+    fn walk(value: &mut serde_json::Value, f: &mut dyn FnMut(&str) -> Result<Option<String>>) -> Result<bool> {
+        let mut changed = false;
+        match value {
+            serde_json::Value::Object(map) => for (key, v) in map.iter_mut() {
+                match v {
+                    serde_json::Value::String(s) if crate::backup::is_secret_key(key) => {
+                        if let Some(replaced) = f(s)? {
+                            *s = replaced;
+                            changed = true;
+                        }
+                    }
+                    _ => changed |= walk(v, f)?,
+                }
+            },
+            serde_json::Value::Array(items) => for v in items {
+                changed |= walk(v, f)?;
+            },
+            _ => {}
+        }
+        Ok(changed)
+    }
+    let Ok(mut value) = serde_json::from_str::<serde_json::Value>(config_json) else {
+        return Ok(config_json.to_string());
+    };
+    Ok(if walk(&mut value, &mut f)? { value.to_string() } else { config_json.to_string() })
+}
+
+fn failed_webhook_from_row(row: &rusqlite::Row) -> rusqlite::Result<FailedWebhook> {
+    Ok(FailedWebhook {
+        id: row.get(0)?, tenant_id: row.get(1)?, url: row.get(2)?, payload_json: row.get(3)?,
+        attempts: row.get(4)?, last_error: row.get(5)?, created_at: row.get(6)?,
+    })
+}
+
+/// Saves webhooks that ran out of retries to `failed_webhooks`, with a
+/// `webhook_delivery_failed` audit event. Uses its own connection so the
+/// delivery worker doesn't contend with API handlers.
+pub struct FailedWebhookStore {
+    db: std::sync::Mutex<PlatformDb>,
+}
+
+impl FailedWebhookStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        Ok(Self { db: std::sync::Mutex::new(PlatformDb::open(path)?) })
+    }
+}
+
+impl DeadLetterStore for FailedWebhookStore {
+    fn store(&self, failed: &FailedDelivery) -> Result<()> {
+        let db = self.db.lock().unwrap();
+        db.record_failed_webhook(failed)?;
+        let (actor_type, actor_id) = match &failed.delivery.tenant_id {
+            Some(tenant_id) => ("tenant", tenant_id.as_str()),
+            None => ("platform", "platform"),
+        };
+        db.log_event(
+            "webhook_delivery_failed", actor_type, actor_id,
+            Some(&format!("id={} url={} attempts={} error={}",
+                failed.delivery.id, failed.delivery.url, failed.attempts, failed.last_error)),
+        )
+    }
+}
+
+/// Records a tenant's channel status in `tenant_channels`, from the tenant
+/// process, so the admin dashboard shows channels that went down.
+pub struct TenantChannelStatusStore {
+    db: std::sync::Mutex<PlatformDb>,
+    tenant_id: String,
+}
+
+impl TenantChannelStatusStore {
+    pub fn open(path: &Path, tenant_id: &str) -> Result<Self> {
+        Ok(Self { db: std::sync::Mutex::new(PlatformDb::open(path)?), tenant_id: tenant_id.to_string() })
+    }
+}
+
+impl ChannelStatusStore for TenantChannelStatusStore {
+    fn set_status(&self, channel: &str, status: &str, message: Option<&str>) -> Result<()> {
+        let id = format!("{}-{}", self.tenant_id, channel);
+        self.db.lock().unwrap().update_channel_status(&id, status, message)
+    }
+}
+
+/// Records a tenant's channel messages in `channel_messages`, from the
+/// tenant process, so conversations outlive it.
+pub struct TenantChannelHistory {
+    db: std::sync::Mutex<PlatformDb>,
+    tenant_id: String,
+}
+
+impl TenantChannelHistory {
+    pub fn open(path: &Path, tenant_id: &str) -> Result<Self> {
+        Ok(Self { db: std::sync::Mutex::new(PlatformDb::open(path)?), tenant_id: tenant_id.to_string() })
+    }
+}
+
+impl MessageHistory for TenantChannelHistory {
+    fn record(&self, channel: &str, thread_id: &str, direction: MessageDirection, sender: &str, content: &str) -> Result<()> {
+        self.db.lock().unwrap().log_channel_message(&ChannelMessage {
+            id: 0,
+            tenant_id: self.tenant_id.clone(),
+            channel: channel.into(),
+            thread_id: thread_id.into(),
+            channel_message_id: None,
+            direction: direction.as_str().into(),
+            sender: sender.into(),
+            content: content.into(),
+            created_at: String::new(),
+        })
+    }
+}
+
+/// Matches a tenant's channel users to canonical users in
+/// `user_identities`, from the tenant process.
+pub struct TenantUserIdentities {
+    db: std::sync::Mutex<PlatformDb>,
+    tenant_id: String,
+}
+
+impl TenantUserIdentities {
+    pub fn open(path: &Path, tenant_id: &str) -> Result<Self> {
+        Ok(Self { db: std::sync::Mutex::new(PlatformDb::open(path)?), tenant_id: tenant_id.to_string() })
+    }
+}
+
+impl UserIdentities for TenantUserIdentities {
+    fn resolve_user(&self, channel: &str, channel_user_id: &str) -> Result<String> {
+        self.db.lock().unwrap().resolve_user(&self.tenant_id, channel, channel_user_id)
+    }
+}
+
+/// Digits of a phone number, with a Vietnamese leading 0 as country code 84,
+/// so `0912 345 678` and `+84912345678` match.
+fn normalize_phone(phone: &str) -> String {
+    let digits: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+    match digits.strip_prefix('0') {
+        Some(rest) if !rest.is_empty() => format!("84{rest}"),
+        _ => digits,
+    }
+}
+
+/// Enforces a tenant's daily message quota from the tenant process, with
+/// its own connection to the platform database.
+pub struct TenantMessageQuota {
+    db: std::sync::Mutex<PlatformDb>,
+    tenant_id: String,
+}
+
+impl TenantMessageQuota {
+    pub fn open(path: &Path, tenant_id: &str, timezone: chrono_tz::Tz) -> Result<Self> {
+        let db = PlatformDb::open(path)?.with_quota_timezone(timezone);
+        Ok(Self { db: std::sync::Mutex::new(db), tenant_id: tenant_id.to_string() })
+    }
+}
+
+impl MessageQuota for TenantMessageQuota {
+    fn check_quota(&self) -> Result<Remaining> {
+        self.db.lock().unwrap().check_quota(&self.tenant_id)
+    }
+
+    fn record_message(&self) -> Result<()> {
+        self.db.lock().unwrap().increment_message_count(&self.tenant_id).map(|_| ())
+    }
 }
 
 fn rand_code() -> u32 {
@@ -416,6 +1225,11 @@ mod tests {
 
         let tenants = db.list_tenants().unwrap();
         assert_eq!(tenants.len(), 1);
+
+        for slug in ["", "../etc", "a/b", "Shop", "shop_1", "shop.bak"] {
+            assert!(db.create_tenant("Bad", slug, 10009, "openai", "gpt-4o-mini", "free").is_err(), "{slug:?}");
+        }
+        assert_eq!(db.list_tenants().unwrap().len(), 1);
     }
 
     #[test]
@@ -471,6 +1285,228 @@ mod tests {
         assert_eq!(users.len(), 1);
     }
 
+    #[test]
+    fn test_failed_webhooks() {
+        use bizclaw_channels::webhook::WebhookDelivery;
+
+        let path = std::env::temp_dir().join(format!("bizclaw-webhooks-{}.db", uuid::Uuid::new_v4().simple()));
+        let store = FailedWebhookStore::open(&path).unwrap();
+        let delivery = WebhookDelivery::new(Some("t1".into()), "https://hooks.example.com/x", serde_json::json!({"content": "hi"}));
+        store.store(&FailedDelivery { delivery: delivery.clone(), attempts: 6, last_error: "HTTP 500".into() }).unwrap();
+
+        let db = PlatformDb::open(&path).unwrap();
+        let failed = db.list_failed_webhooks().unwrap();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].id, delivery.id);
+        assert_eq!(failed[0].tenant_id.as_deref(), Some("t1"));
+        assert_eq!((failed[0].attempts, failed[0].last_error.as_str()), (6, "HTTP 500"));
+        assert_eq!(serde_json::from_str::<serde_json::Value>(&failed[0].payload_json).unwrap(), delivery.payload);
+        let events = db.recent_events(10).unwrap();
+        assert!(events.iter().any(|e| e.event_type == "webhook_delivery_failed" && e.actor_id == "t1"));
+
+        db.delete_failed_webhook(&delivery.id).unwrap();
+        assert!(db.get_failed_webhook(&delivery.id).is_err());
+        drop((db, store));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_tenant_channel_status() {
+        let path = std::env::temp_dir().join(format!("bizclaw-status-{}.db", uuid::Uuid::new_v4().simple()));
+        let db = PlatformDb::open(&path).unwrap();
+        let channel = db.upsert_channel("t1", "zalo", true, "{}").unwrap();
+        assert_eq!(channel.status, "disconnected");
+
+        let store = TenantChannelStatusStore::open(&path, "t1").unwrap();
+        store.set_status("zalo", "error", Some("reconnecting failed 10 times")).unwrap();
+        let channel = db.get_channel(&channel.id).unwrap();
+        assert_eq!(channel.status, "error");
+        assert_eq!(channel.status_message.as_deref(), Some("reconnecting failed 10 times"));
+        drop((db, store));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_channel_secrets_encrypted_at_rest() {
+        let raw = |db: &PlatformDb, id: &str| -> String {
+            db.conn.query_row("SELECT config_json FROM tenant_channels WHERE id=?1", params![id], |r| r.get(0)).unwrap()
+        };
+        let db = temp_db();
+        // Stored in plaintext before a master secret was set.
+        let old = db.upsert_channel("t1", "zalo", true, r#"{"cookie":"zpw_sek=abc","imei":"imei-1"}"#).unwrap();
+        let cipher = SecretCipher::from_master_secret("master").unwrap();
+        let db = db.with_secret_cipher(cipher.clone()).unwrap();
+        let stored = raw(&db, &old.id);
+        assert!(!stored.contains("zpw_sek") && stored.contains("imei-1"), "{stored}");
+        assert_eq!(db.get_channel(&old.id).unwrap().config_json, old.config_json);
+
+        let config = r#"{"bot_token":"123:abc","allowed_chat_ids":"1,2","webhook":{"secret_token":"s","secret":"nested"}}"#;
+        let channel = db.upsert_channel("t1", "telegram", true, config).unwrap();
+        let stored = raw(&db, &channel.id);
+        assert!(!stored.contains("123:abc") && !stored.contains("nested") && !stored.contains(r#""s""#), "{stored}");
+        assert!(stored.contains(r#""allowed_chat_ids":"1,2""#), "{stored}");
+        let read: serde_json::Value = serde_json::from_str(&channel.config_json).unwrap();
+        assert_eq!(read, serde_json::from_str::<serde_json::Value>(config).unwrap());
+        assert_eq!(db.list_channels("t1").unwrap().len(), 2);
+
+        // Without the master secret, or with another, secrets can't be read.
+        let other = PlatformDb { conn: db.conn, quota_timezone: DEFAULT_QUOTA_TIMEZONE, secrets: None };
+        assert!(other.get_channel(&channel.id).is_err());
+        let other = other.with_secret_cipher(SecretCipher::from_master_secret("wrong").unwrap()).unwrap();
+        assert!(other.list_channels("t1").is_err());
+    }
+
+    #[test]
+    fn test_provider_api_key_encrypted() {
+        let db = temp_db();
+        assert_eq!(db.provider_api_key("t1").unwrap(), None);
+        db.set_provider_api_key("t1", "sk-plain").unwrap();
+        let db = db.with_secret_cipher(SecretCipher::from_master_secret("master").unwrap()).unwrap();
+        db.set_provider_api_key("t2", "sk-ant-premium").unwrap();
+        let stored: Vec<String> = db.conn.prepare("SELECT value FROM tenant_secrets").unwrap()
+            .query_map([], |r| r.get(0)).unwrap().map(|r| r.unwrap()).collect();
+        assert!(stored.iter().all(|v| is_encrypted(v)), "{stored:?}");
+        assert_eq!(db.provider_api_key("t1").unwrap().as_deref(), Some("sk-plain"));
+        assert_eq!(db.provider_api_key("t2").unwrap().as_deref(), Some("sk-ant-premium"));
+
+        db.set_provider_api_key("t2", "").unwrap();
+        assert_eq!(db.provider_api_key("t2").unwrap(), None);
+        let other = PlatformDb { conn: db.conn, quota_timezone: DEFAULT_QUOTA_TIMEZONE, secrets: None };
+        assert!(other.provider_api_key("t1").is_err());
+    }
+
+    #[test]
+    fn test_resolve_and_link_users() {
+        let db = temp_db();
+        let minh = db.resolve_user("t1", "telegram", "42").unwrap();
+        assert_eq!(db.resolve_user("t1", "telegram", "42").unwrap(), minh);
+        assert_ne!(db.resolve_user("t2", "telegram", "42").unwrap(), minh);
+
+        // Linking pulls in everything already linked to the secondary.
+        let discord = db.resolve_user("t1", "discord", "minh#1").unwrap();
+        db.link_users("t1", "zalo", "z9", "discord", "minh#1").unwrap();
+        assert_ne!(db.resolve_user("t1", "discord", "minh#1").unwrap(), discord);
+        assert_eq!(db.link_users("t1", "telegram", "42", "zalo", "z9").unwrap(), minh);
+        for (channel, id) in [("discord", "minh#1"), ("zalo", "z9")] {
+            assert_eq!(db.resolve_user("t1", channel, id).unwrap(), minh);
+        }
+        let channels: Vec<_> = db.user_identities("t1", &minh).unwrap().into_iter().map(|i| i.channel).collect();
+        assert_eq!(channels, ["discord", "telegram", "zalo"]);
+    }
+
+    #[test]
+    fn test_same_contact_merges_users() {
+        let db = temp_db();
+        let lan = db.set_user_contact("t1", "telegram", "7", Some("Lan@Shop.vn "), Some("0912 345 678")).unwrap();
+        // Email senders and WhatsApp numbers are their own contact details.
+        assert_eq!(db.resolve_user("t1", "email", "lan@shop.vn").unwrap(), lan);
+        assert_eq!(db.resolve_user("t1", "whatsapp", "84912345678").unwrap(), lan);
+        assert_ne!(db.resolve_user("t1", "whatsapp", "84900000000").unwrap(), lan);
+        assert_ne!(db.resolve_user("t2", "email", "lan@shop.vn").unwrap(), lan);
+
+        let store = TenantUserIdentities { db: std::sync::Mutex::new(db), tenant_id: "t1".into() };
+        assert_eq!(store.resolve_user("email", "lan@shop.vn").unwrap(), lan);
+    }
+
+    #[test]
+    fn test_channel_history() {
+        let db = temp_db();
+        let message = |tenant: &str, channel: &str, content: &str| ChannelMessage {
+            id: 0, tenant_id: tenant.into(), channel: channel.into(), thread_id: "chat-1".into(),
+            channel_message_id: None, direction: "inbound".into(), sender: "u1".into(),
+            content: content.into(), created_at: String::new(),
+        };
+        for i in 0..5 {
+            db.log_channel_message(&message("t1", "telegram", &format!("tin {i}"))).unwrap();
+        }
+        db.log_channel_message(&message("t1", "zalo", "khác kênh")).unwrap();
+        db.log_channel_message(&message("t2", "telegram", "khác tenant")).unwrap();
+
+        let page = db.channel_message_history("t1", "telegram", 2, 1).unwrap();
+        assert_eq!(page.iter().map(|m| m.content.as_str()).collect::<Vec<_>>(), ["tin 3", "tin 2"]);
+        assert_eq!(db.channel_message_history("t1", "telegram", 50, 0).unwrap().len(), 5);
+
+        let store = TenantChannelHistory { db: std::sync::Mutex::new(temp_db()), tenant_id: "t1".into() };
+        store.record("zalo", "chat-9", MessageDirection::Outbound, "bot", "chào").unwrap();
+        let logged = &store.db.lock().unwrap().channel_message_history("t1", "zalo", 1, 0).unwrap()[0];
+        assert_eq!((logged.thread_id.as_str(), logged.direction.as_str(), logged.sender.as_str()), ("chat-9", "outbound", "bot"));
+    }
+
+    #[test]
+    fn test_channel_history_purges_oldest() {
+        let db = temp_db();
+        let insert = "INSERT INTO channel_messages (tenant_id, channel, direction, sender, content) VALUES (?1, 'zalo', 'inbound', 'u1', ?2)";
+        for i in 0..CHANNEL_HISTORY_LIMIT {
+            db.conn.execute(insert, params!["t1", format!("m{i}")]).unwrap();
+        }
+        db.conn.execute(insert, params!["t2", "other tenant"]).unwrap();
+
+        db.log_channel_message(&ChannelMessage {
+            id: 0, tenant_id: "t1".into(), channel: "zalo".into(), thread_id: String::new(),
+            channel_message_id: None, direction: "outbound".into(), sender: "bot".into(),
+            content: "newest".into(), created_at: String::new(),
+        }).unwrap();
+        let count = |tenant: &str| db.conn.query_row(
+            "SELECT COUNT(*) FROM channel_messages WHERE tenant_id=?1", [tenant], |r| r.get::<_, i64>(0),
+        ).unwrap();
+        assert_eq!((count("t1"), count("t2")), (CHANNEL_HISTORY_LIMIT, 1));
+        let oldest = db.channel_message_history("t1", "zalo", 1, CHANNEL_HISTORY_LIMIT as usize - 1).unwrap();
+        assert_eq!(oldest[0].content, "m1");
+    }
+
+    #[test]
+    fn test_message_quota() {
+        let db = temp_db();
+        let t = db.create_tenant("Bot", "bot", 10002, "openai", "gpt-4o-mini", "free").unwrap();
+        db.update_tenant_profile(&Tenant { max_messages_day: 2, ..t.clone() }).unwrap();
+
+        assert_eq!(db.check_quota(&t.id).unwrap().remaining(), Some(2));
+        assert_eq!(db.increment_message_count(&t.id).unwrap(), 1);
+        assert_eq!(db.increment_message_count(&t.id).unwrap(), 2);
+        let err = db.check_quota(&t.id).unwrap_err();
+        assert!(matches!(err, BizClawError::QuotaExceeded(_)), "{err}");
+        assert!(err.to_string().contains("Asia/Ho_Chi_Minh"), "{err}");
+
+        // Yesterday's count doesn't carry over.
+        let yesterday = db.quota_day(chrono::Utc::now() - chrono::Duration::days(1));
+        db.conn.execute("UPDATE tenant_message_counts SET day=?1", [&yesterday]).unwrap();
+        assert_eq!(db.check_quota(&t.id).unwrap(), Remaining { used: 0, limit: 2 });
+
+        // 0 means unlimited.
+        db.update_tenant_profile(&Tenant { max_messages_day: 0, ..t.clone() }).unwrap();
+        assert_eq!(db.check_quota(&t.id).unwrap().remaining(), None);
+    }
+
+    #[test]
+    fn test_quota_day_follows_timezone() {
+        let at = chrono::DateTime::parse_from_rfc3339("2026-10-17T18:30:00Z").unwrap().with_timezone(&chrono::Utc);
+        // 01:30 the next morning in Ho Chi Minh City (UTC+7).
+        assert_eq!(temp_db().quota_day(at), "2026-10-18");
+        assert_eq!(temp_db().with_quota_timezone(chrono_tz::UTC).quota_day(at), "2026-10-17");
+        assert_eq!(temp_db().with_quota_timezone(chrono_tz::America::New_York).quota_day(at), "2026-10-17");
+    }
+
+    #[test]
+    fn test_feature_flags() {
+        let db = temp_db();
+        assert!(!db.flag_enabled("t1", FLAG_STREAMING).unwrap());
+
+        // Platform-wide setting applies until a tenant has its own.
+        db.set_flag(ALL_TENANTS, FLAG_STREAMING, true).unwrap();
+        assert!(db.flag_enabled("t1", FLAG_STREAMING).unwrap());
+        db.set_flag("t1", FLAG_STREAMING, false).unwrap();
+        assert!(!db.flag_enabled("t1", FLAG_STREAMING).unwrap());
+        assert!(db.flag_enabled("t2", FLAG_STREAMING).unwrap());
+
+        db.set_flag("t1", FLAG_STREAMING, true).unwrap();
+        db.set_flag("t1", "tool_cache", false).unwrap();
+        let flags = db.list_flags("t1").unwrap();
+        assert_eq!(flags.len(), 2);
+        assert_eq!(flags[0].flag_name, FLAG_STREAMING);
+        assert!(flags[0].enabled);
+        assert_eq!(db.list_flags(ALL_TENANTS).unwrap().len(), 1);
+    }
+
     #[test]
     fn test_tenant_stats() {
         let db = temp_db();
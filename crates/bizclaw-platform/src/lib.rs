@@ -9,7 +9,11 @@ pub mod tenant;
 pub mod auth;
 pub mod admin;
 pub mod config;
+pub mod backup;
+pub mod user_csv;
+pub mod proxy;
 
 pub use db::PlatformDb;
 pub use tenant::TenantManager;
 pub use admin::AdminServer;
+pub use backup::TenantBackup;
@@ -0,0 +1,222 @@
+//! Subdomain routing — `acme.bizclaw.vn` is proxied to tenant `acme`'s
+//! gateway on `127.0.0.1:<port>`, WebSocket upgrades included.
+//!
+//! Hosts that aren't a tenant subdomain (the bare domain, `admin.`, `www.`,
+//! `localhost`) fall through to the admin routes.
+
+use std::sync::Arc;
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::response::Response;
+use hyper_util::rt::TokioIo;
+
+use crate::admin::AdminState;
+
+/// Subdomains that stay on the admin server.
+const RESERVED: &[&str] = &["admin", "www"];
+
+/// Hop-by-hop headers, which apply to one connection and aren't forwarded.
+const HOP_BY_HOP: &[&str] = &["keep-alive", "proxy-connection", "proxy-authorization", "te", "trailer"];
+
+/// The tenant slug in `host` (with or without a port) when it is a
+/// subdomain of `domain`, e.g. `acme` for `acme.bizclaw.vn:3000`.
+pub fn tenant_slug(host: &str, domain: &str) -> Option<String> {
+    if domain.is_empty() {
+        return None;
+    }
+    let host = host.rsplit_once(':')
+        .filter(|(_, port)| port.parse::<u16>().is_ok())
+        .map_or(host, |(name, _)| name)
+        .to_ascii_lowercase();
+    let slug = host.strip_suffix(&domain.to_ascii_lowercase())?.strip_suffix('.')?;
+    (!slug.is_empty() && !slug.contains('.') && !RESERVED.contains(&slug)).then(|| slug.to_string())
+}
+
+/// Middleware: requests for a tenant subdomain go to the tenant's gateway,
+/// everything else to the admin routes.
+pub async fn route_subdomains(
+    State(state): State<Arc<AdminState>>,
+    req: Request,
+    next: axum::middleware::Next,
+) -> Response {
+    let host = req.headers().get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .or_else(|| req.uri().host());
+    let Some(slug) = host.and_then(|h| tenant_slug(h, &state.domain)) else {
+        return next.run(req).await;
+    };
+
+    // The database lock can be held by a slow admin request; wait for it
+    // off the async workers.
+    let lookup = {
+        let (state, slug) = (state.clone(), slug.clone());
+        tokio::task::spawn_blocking(move || state.db.lock().unwrap_or_else(|e| e.into_inner()).get_tenant_by_slug(&slug)).await
+    };
+    let tenant = match lookup {
+        Ok(tenant) => tenant,
+        Err(e) => return error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    };
+    match tenant {
+        Ok(Some(tenant)) if tenant.status == "running" => proxy(req, tenant.port).await,
+        Ok(Some(tenant)) => error(StatusCode::BAD_GATEWAY, &format!("Tenant '{slug}' is {}", tenant.status)),
+        Ok(None) => error(StatusCode::NOT_FOUND, &format!("No tenant '{slug}'")),
+        Err(e) => error(StatusCode::INTERNAL_SERVER_ERROR, &e.to_string()),
+    }
+}
+
+/// Forward `req` to `127.0.0.1:port` and stream the response back. When the
+/// tenant accepts a protocol upgrade (WebSocket), both connections are
+/// spliced together until either side closes.
+pub async fn proxy(mut req: Request, port: u16) -> Response {
+    let upgrade = req.headers().contains_key(header::UPGRADE).then(|| hyper::upgrade::on(&mut req));
+
+    let stream = match tokio::net::TcpStream::connect(("127.0.0.1", port)).await {
+        Ok(stream) => stream,
+        Err(e) => return error(StatusCode::BAD_GATEWAY, &format!("Tenant on port {port} is unreachable: {e}")),
+    };
+    let (mut sender, conn) = match hyper::client::conn::http1::handshake::<_, Body>(TokioIo::new(stream)).await {
+        Ok(pair) => pair,
+        Err(e) => return error(StatusCode::BAD_GATEWAY, &format!("Tenant on port {port}: {e}")),
+    };
+    tokio::spawn(async move {
+        if let Err(e) = conn.with_upgrades().await {
+            tracing::debug!("Tenant proxy connection to port {port}: {e}");
+        }
+    });
+
+    // The tenant sees the original Host, so its origin checks still match.
+    let path = req.uri().path_and_query().map_or("/", |p| p.as_str());
+    *req.uri_mut() = path.parse().unwrap_or_default();
+    let headers = req.headers_mut();
+    if let Some(host) = headers.get(header::HOST).cloned() {
+        headers.insert("x-forwarded-host", host);
+    }
+    headers.insert("x-forwarded-proto", HeaderValue::from_static("http"));
+    for name in HOP_BY_HOP {
+        headers.remove(*name);
+    }
+    if upgrade.is_none() {
+        headers.remove(header::CONNECTION);
+    }
+
+    let mut response = match sender.send_request(req).await {
+        Ok(response) => response,
+        Err(e) => return error(StatusCode::BAD_GATEWAY, &format!("Tenant on port {port}: {e}")),
+    };
+
+    if response.status() == StatusCode::SWITCHING_PROTOCOLS
+        && let Some(client) = upgrade
+    {
+        let tenant = hyper::upgrade::on(&mut response);
+        tokio::spawn(async move {
+            match tokio::try_join!(client, tenant) {
+                Ok((client, tenant)) => {
+                    let (mut client, mut tenant) = (TokioIo::new(client), TokioIo::new(tenant));
+                    if let Err(e) = tokio::io::copy_bidirectional(&mut client, &mut tenant).await {
+                        tracing::debug!("Tenant proxy upgrade on port {port} closed: {e}");
+                    }
+                }
+                Err(e) => tracing::warn!("Tenant proxy upgrade on port {port} failed: {e}"),
+            }
+        });
+    }
+
+    response.map(Body::new)
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Body::from(serde_json::json!({"ok": false, "error": message}).to_string()))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use tokio_tungstenite::tungstenite::{Message, client::IntoClientRequest};
+
+    #[test]
+    fn test_tenant_slug() {
+        assert_eq!(tenant_slug("acme.bizclaw.vn", "bizclaw.vn").as_deref(), Some("acme"));
+        assert_eq!(tenant_slug("ACME.BizClaw.vn:3000", "bizclaw.vn").as_deref(), Some("acme"));
+        for host in ["bizclaw.vn", "admin.bizclaw.vn", "www.bizclaw.vn", "a.b.bizclaw.vn", "acmebizclaw.vn", "localhost:3000"] {
+            assert_eq!(tenant_slug(host, "bizclaw.vn"), None, "{host}");
+        }
+        assert_eq!(tenant_slug("acme.bizclaw.vn", ""), None);
+    }
+
+    /// A stand-in tenant gateway with an HTTP route and a WebSocket echo.
+    async fn tenant_gateway() -> u16 {
+        use axum::extract::ws::{WebSocket, WebSocketUpgrade};
+        use axum::routing::get;
+
+        async fn echo(mut socket: WebSocket) {
+            while let Some(Ok(msg)) = socket.recv().await {
+                if socket.send(msg).await.is_err() {
+                    break;
+                }
+            }
+        }
+        let app = axum::Router::new()
+            .route("/api/v1/info", get(|headers: axum::http::HeaderMap| async move {
+                format!("host={}", headers[header::HOST].to_str().unwrap())
+            }))
+            .route("/ws", get(|ws: WebSocketUpgrade| async { ws.on_upgrade(echo) }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_proxies_http_and_websocket_by_subdomain() {
+        let state = Arc::new(AdminState {
+            db: std::sync::Mutex::new(crate::PlatformDb::open(std::path::Path::new(":memory:")).unwrap()),
+            manager: std::sync::Mutex::new(crate::TenantManager::new(std::env::temp_dir())),
+            jwt_secret: "secret".into(),
+            bizclaw_bin: "true".into(),
+            base_port: 10001,
+            domain: "bizclaw.vn".into(),
+            webhooks: bizclaw_channels::webhook::WebhookDeliveryQueue::start(None),
+        });
+        let port = tenant_gateway().await;
+        let id = {
+            let db = state.db.lock().unwrap();
+            let acme = db.create_tenant("Acme", "acme", port, "openai", "gpt-4o-mini", "free").unwrap();
+            db.update_tenant_status(&acme.id, "running", Some(1)).unwrap();
+            db.create_tenant("Idle", "idle", port + 1, "openai", "gpt-4o-mini", "free").unwrap();
+            acme.id
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let admin = listener.local_addr().unwrap();
+        tokio::spawn(axum::serve(listener, crate::AdminServer::router(state.clone())).into_future());
+
+        let client = reqwest::Client::new();
+        let get = |host: &str| client.get(format!("http://{admin}/api/v1/info")).header("Host", host).send();
+
+        let response = get("acme.bizclaw.vn").await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.text().await.unwrap(), "host=acme.bizclaw.vn");
+        assert_eq!(get("idle.bizclaw.vn").await.unwrap().status(), 502);
+        assert_eq!(get("nobody.bizclaw.vn").await.unwrap().status(), 404);
+        // The admin server itself is still reachable on other hosts.
+        assert_eq!(get("admin.bizclaw.vn").await.unwrap().status(), 404);
+        let dashboard = client.get(format!("http://{admin}/")).header("Host", "bizclaw.vn").send().await.unwrap();
+        assert_eq!(dashboard.status(), 200);
+
+        let mut request = format!("ws://{admin}/ws").into_client_request().unwrap();
+        request.headers_mut().insert(header::HOST, HeaderValue::from_static("acme.bizclaw.vn"));
+        let (mut ws, _) = tokio_tungstenite::connect_async(request).await.unwrap();
+        ws.send(Message::Text("xin chào".into())).await.unwrap();
+        assert_eq!(ws.next().await.unwrap().unwrap(), Message::Text("xin chào".into()));
+
+        // Stopped after the fact, the tenant gets a 502 rather than a refused connection.
+        state.db.lock().unwrap().update_tenant_status(&id, "stopped", None).unwrap();
+        assert_eq!(get("acme.bizclaw.vn").await.unwrap().status(), 502);
+    }
+}
@@ -1,22 +1,211 @@
 //! Tenant process manager — start/stop/restart BizClaw agent instances.
 
 use std::collections::HashMap;
+use std::sync::Mutex;
 use std::process::Command;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use bizclaw_core::error::{BizClawError, Result};
-use crate::db::{PlatformDb, Tenant};
+use bizclaw_core::config::BizClawConfig;
+use crate::db::{PlatformDb, Tenant, TenantChannel};
 
 /// A running tenant process.
 pub struct TenantProcess {
     pub pid: u32,
     pub port: u16,
     pub started_at: Instant,
+    /// `None` for a process adopted from an earlier platform run.
+    child: Option<std::process::Child>,
+}
+
+impl TenantProcess {
+    /// Whether the process is still alive; an exited child is reaped.
+    fn alive(&mut self) -> bool {
+        match &mut self.child {
+            Some(child) => matches!(child.try_wait(), Ok(None)),
+            None => pid_alive(self.pid),
+        }
+    }
+}
+
+/// The full config a tenant runs with: its provider, model and port, the
+/// provider `api_key`, and each enabled row of `tenant_channels` that has
+/// its credentials. Channels whose settings don't make a valid config are
+/// left out with a warning. A Zalo cookie is written to `tenant_dir`.
+pub fn tenant_config(
+    tenant: &Tenant,
+    channels: &[TenantChannel],
+    api_key: String,
+    streaming: bool,
+    tenant_dir: &std::path::Path,
+) -> Result<BizClawConfig> {
+    let mut config = BizClawConfig {
+        api_key,
+        default_provider: tenant.provider.clone(),
+        default_model: tenant.model.clone(),
+        ..BizClawConfig::default()
+    };
+    config.identity.name = tenant.name.clone();
+    config.gateway.port = tenant.port;
+    config.channel.streaming = streaming;
+
+    for ch in channels.iter().filter(|ch| ch.enabled) {
+        let Ok(serde_json::Value::Object(mut cfg)) = serde_json::from_str(&ch.config_json) else {
+            tracing::warn!("Tenant {}: {} channel config is not a JSON object, skipped", tenant.slug, ch.channel_type);
+            continue;
+        };
+        let has = |key: &str| cfg.get(key).and_then(|v| v.as_str()).is_some_and(|v| !v.is_empty());
+        let configured = match ch.channel_type.as_str() {
+            "telegram" if has("bot_token") => {
+                split_list(&mut cfg, "allowed_chat_ids", true);
+                channel_config(cfg).map(|c| config.channel.telegram = Some(c))
+            }
+            "discord" if has("bot_token") => {
+                split_list(&mut cfg, "allowed_channel_ids", true);
+                channel_config(cfg).map(|c| config.channel.discord = Some(c))
+            }
+            "email" if has("email") && has("password") => {
+                numbers(&mut cfg, &["imap_port", "smtp_port", "poll_interval_secs", "max_attachment_mb"]);
+                split_list(&mut cfg, "allowed_senders", false);
+                channel_config(cfg).map(|c| config.channel.email = Some(c))
+            }
+            "whatsapp" if has("access_token") && has("phone_number_id") => {
+                split_list(&mut cfg, "allowed_numbers", false);
+                channel_config(cfg).map(|c| config.channel.whatsapp = Some(c))
+            }
+            "webhook" if has("secret") => {
+                // The dashboard calls the outbound URL `url`.
+                if let Some(url) = cfg.remove("url").filter(|u| u.as_str().is_some_and(|u| !u.is_empty())) {
+                    cfg.entry("outbound_url").or_insert(url);
+                }
+                numbers(&mut cfg, &["max_age_secs", "callback_retries", "callback_backoff_ms", "reply_timeout_secs"]);
+                channel_config(cfg).map(|c| config.channel.webhook = Some(c))
+            }
+            "zalo" if has("cookie") => {
+                let cookie_path = tenant_dir.join("zalo_cookie.txt");
+                std::fs::write(&cookie_path, cfg["cookie"].as_str().unwrap_or_default())?;
+                let personal = serde_json::json!({
+                    "cookie_path": cookie_path.to_string_lossy(),
+                    "imei": cfg.get("imei").and_then(|v| v.as_str()).unwrap_or_default(),
+                });
+                let zalo = serde_json::json!({"mode": "personal", "personal": personal});
+                channel_config(zalo.as_object().cloned().unwrap_or_default()).map(|c| config.channel.zalo = Some(c))
+            }
+            "telegram" | "discord" | "email" | "whatsapp" | "webhook" | "zalo" => {
+                tracing::warn!("Tenant {}: {} channel has no credentials, skipped", tenant.slug, ch.channel_type);
+                continue;
+            }
+            other => {
+                tracing::warn!("Tenant {}: unknown channel type '{other}', skipped", tenant.slug);
+                continue;
+            }
+        };
+        if let Err(e) = configured {
+            tracing::warn!("Tenant {}: invalid {} channel config, skipped: {e}", tenant.slug, ch.channel_type);
+        }
+    }
+    Ok(config)
+}
+
+/// A channel's typed config from the dashboard's JSON, enabled.
+fn channel_config<T: serde::de::DeserializeOwned>(mut cfg: serde_json::Map<String, serde_json::Value>) -> serde_json::Result<T> {
+    cfg.insert("enabled".into(), serde_json::Value::Bool(true));
+    serde_json::from_value(serde_json::Value::Object(cfg))
+}
+
+/// The dashboard sends lists as comma-separated text; make `key` an array
+/// (of numbers with `numeric`).
+fn split_list(cfg: &mut serde_json::Map<String, serde_json::Value>, key: &str, numeric: bool) {
+    let Some(text) = cfg.get(key).and_then(|v| v.as_str()) else { return };
+    let items = text.split(',').map(str::trim).filter(|s| !s.is_empty())
+        .map(|item| match item.parse::<i64>() {
+            Ok(n) if numeric => serde_json::Value::from(n),
+            _ => serde_json::Value::from(item),
+        })
+        .collect();
+    cfg.insert(key.into(), serde_json::Value::Array(items));
+}
+
+/// The dashboard sends numbers as text; parse the `keys` that are.
+fn numbers(cfg: &mut serde_json::Map<String, serde_json::Value>, keys: &[&str]) {
+    for key in keys {
+        if let Some(n) = cfg.get(*key).and_then(|v| v.as_str()).and_then(|v| v.trim().parse::<u64>().ok()) {
+            cfg.insert((*key).into(), n.into());
+        }
+    }
+}
+
+/// The `api_key` in an existing tenant config, unless it is empty or just
+/// the platform's own key.
+fn legacy_api_key(config_path: &std::path::Path, default_api_key: &str) -> Option<String> {
+    let content = std::fs::read_to_string(config_path).ok()?;
+    let table: toml::Table = content.parse().ok()?;
+    table.get("api_key")?.as_str()
+        .filter(|key| !key.is_empty() && *key != default_api_key)
+        .map(String::from)
+}
+
+fn pid_alive(pid: u32) -> bool {
+    Command::new("kill").args(["-0", &pid.to_string()]).output().is_ok_and(|o| o.status.success())
+}
+
+/// When the supervisor restarts a tenant whose process died.
+#[derive(Debug, Clone)]
+pub struct RestartPolicy {
+    /// How often tenants marked running are checked.
+    pub check_interval: Duration,
+    /// Plans whose tenants are restarted; empty covers every plan.
+    pub plans: Vec<String>,
+    /// Restarts in a row before the tenant is marked "error".
+    pub max_restarts: u32,
+    /// Wait after a restart before the next one, doubled each time.
+    pub backoff: Duration,
+    pub max_backoff: Duration,
+    /// Once a restarted tenant stays up this long, its count starts over.
+    pub stable_after: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(30),
+            plans: Vec::new(),
+            max_restarts: 5,
+            backoff: Duration::from_secs(10),
+            max_backoff: Duration::from_secs(300),
+            stable_after: Duration::from_secs(600),
+        }
+    }
+}
+
+impl RestartPolicy {
+    fn covers(&self, plan: &str) -> bool {
+        self.plans.is_empty() || self.plans.iter().any(|p| p == plan)
+    }
+
+    /// How long to wait after the `count`th restart.
+    fn backoff_after(&self, count: u32) -> Duration {
+        self.backoff.saturating_mul(1 << count.saturating_sub(1).min(16)).min(self.max_backoff)
+    }
+}
+
+/// Restarts of one tenant since it last stayed up.
+struct Restarts {
+    count: u32,
+    last: Instant,
 }
 
 /// Manages tenant lifecycle across the platform.
 pub struct TenantManager {
     processes: HashMap<String, TenantProcess>,
     data_dir: std::path::PathBuf,
+    /// Platform database, handed to tenants so they can report channel status.
+    db_path: Option<std::path::PathBuf>,
+    /// Where tenants' daily message quotas roll over, when not the default.
+    quota_timezone: Option<chrono_tz::Tz>,
+    /// Supervisor restarts, by tenant ID.
+    restarts: HashMap<String, Restarts>,
+    /// Provider API key of tenants without their own.
+    default_api_key: String,
 }
 
 impl TenantManager {
@@ -24,9 +213,36 @@ impl TenantManager {
         Self {
             processes: HashMap::new(),
             data_dir: data_dir.into(),
+            db_path: None,
+            quota_timezone: None,
+            restarts: HashMap::new(),
+            default_api_key: String::new(),
         }
     }
 
+    /// Run tenants without their own provider API key with `api_key`.
+    pub fn with_default_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.default_api_key = api_key.into();
+        self
+    }
+
+    /// Let tenants record their channels' status in the database at `path`.
+    pub fn with_db_path(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.db_path = Some(path.into());
+        self
+    }
+
+    /// Reset tenants' daily message counts at midnight in `timezone`.
+    pub fn with_quota_timezone(mut self, timezone: chrono_tz::Tz) -> Self {
+        self.quota_timezone = Some(timezone);
+        self
+    }
+
+    /// Root directory holding one subdirectory per tenant slug.
+    pub fn data_dir(&self) -> &std::path::Path {
+        &self.data_dir
+    }
+
     /// Start a tenant as a child process.
     pub fn start_tenant(&mut self, tenant: &Tenant, bizclaw_bin: &str, db: &crate::db::PlatformDb) -> Result<u32> {
         if self.processes.contains_key(&tenant.id) {
@@ -38,104 +254,52 @@ impl TenantManager {
 
         // Write tenant-specific config (including channel configs from DB)
         let config_path = tenant_dir.join("config.toml");
-        let mut config_content = format!(
-            r#"default_provider = "{}"
-default_model = "{}"
-api_key = ""
-
-[identity]
-name = "{}"
-
-[gateway]
-port = {}
-"#,
-            tenant.provider, tenant.model, tenant.name, tenant.port
-        );
-
-        // Load channel configs from database and inject into config.toml
-        if let Ok(channels) = db.list_channels(&tenant.id) {
-            for ch in &channels {
-                if !ch.enabled { continue; }
-                if let Ok(cfg) = serde_json::from_str::<serde_json::Value>(&ch.config_json) {
-                    match ch.channel_type.as_str() {
-                        "telegram" => {
-                            let token = cfg["bot_token"].as_str().unwrap_or("");
-                            if !token.is_empty() {
-                                config_content.push_str(&format!(
-                                    "\n[channel.telegram]\nenabled = true\nbot_token = \"{}\"\n",
-                                    token
-                                ));
-                                if let Some(ids) = cfg["allowed_chat_ids"].as_str() {
-                                    let parsed: Vec<&str> = ids.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()).collect();
-                                    if !parsed.is_empty() {
-                                        config_content.push_str(&format!("allowed_chat_ids = [{}]\n", parsed.join(", ")));
-                                    }
-                                }
-                            }
-                        }
-                        "zalo" => {
-                            let cookie = cfg["cookie"].as_str().unwrap_or("");
-                            if !cookie.is_empty() {
-                                let imei = cfg["imei"].as_str().unwrap_or("");
-                                config_content.push_str(&format!(
-                                    "\n[channel.zalo]\nenabled = true\nmode = \"personal\"\n\n[channel.zalo.personal]\ncookie_path = \"{}\"\nimei = \"{}\"\n",
-                                    tenant_dir.join("zalo_cookie.txt").display(),
-                                    imei
-                                ));
-                                // Save the actual cookie to a file
-                                std::fs::write(tenant_dir.join("zalo_cookie.txt"), cookie).ok();
-                            }
-                        }
-                        "discord" => {
-                            let token = cfg["bot_token"].as_str().unwrap_or("");
-                            if !token.is_empty() {
-                                config_content.push_str(&format!(
-                                    "\n[channel.discord]\nenabled = true\nbot_token = \"{}\"\n",
-                                    token
-                                ));
-                            }
-                        }
-                        "email" => {
-                            let email = cfg["email"].as_str().unwrap_or("");
-                            let password = cfg["password"].as_str().unwrap_or("");
-                            if !email.is_empty() && !password.is_empty() {
-                                config_content.push_str(&format!(
-                                    "\n[channel.email]\nimap_host = \"{}\"\nimap_port = {}\nsmtp_host = \"{}\"\nsmtp_port = {}\nemail = \"{}\"\npassword = \"{}\"\n",
-                                    cfg["imap_host"].as_str().unwrap_or("imap.gmail.com"),
-                                    cfg["imap_port"].as_str().unwrap_or("993"),
-                                    cfg["smtp_host"].as_str().unwrap_or("smtp.gmail.com"),
-                                    cfg["smtp_port"].as_str().unwrap_or("587"),
-                                    email, password
-                                ));
-                            }
-                        }
-                        "webhook" => {
-                            let url = cfg["url"].as_str().unwrap_or("");
-                            if !url.is_empty() {
-                                config_content.push_str(&format!(
-                                    "\n[channel.webhook]\nurl = \"{}\"\nsecret = \"{}\"\n",
-                                    url,
-                                    cfg["secret"].as_str().unwrap_or("")
-                                ));
-                            }
-                        }
-                        _ => {}
-                    }
+        // The tenant's own key (see `set_api_key`), else the platform's.
+        // Tenants set up before keys were stored in the database have theirs
+        // only in the config file; move it there before the file is rewritten.
+        let api_key = match db.provider_api_key(&tenant.id)? {
+            Some(key) => key,
+            None => match legacy_api_key(&config_path, &self.default_api_key) {
+                Some(key) => {
+                    db.set_provider_api_key(&tenant.id, &key)?;
+                    tracing::info!("Moved tenant {}'s API key from config.toml into the database", tenant.slug);
+                    key
                 }
-            }
-        }
-
-        std::fs::write(&config_path, config_content).ok();
+                None => self.default_api_key.clone(),
+            },
+        };
+        // Capabilities still being rolled out are gated by feature flags.
+        let streaming = db.flag_enabled(&tenant.id, crate::db::FLAG_STREAMING)?;
+        let channels = db.list_channels(&tenant.id)?;
+        let config = tenant_config(tenant, &channels, api_key, streaming, &tenant_dir)?;
+        let config_content = toml::to_string(&config)
+            .map_err(|e| BizClawError::Config(format!("Tenant {} config: {e}", tenant.slug)))?;
+        std::fs::write(&config_path, config_content)?;
+        let c = &config.channel;
+        let has_channels = c.zalo.is_some() || c.telegram.is_some() || c.discord.is_some()
+            || c.email.is_some() || c.whatsapp.is_some() || c.webhook.is_some();
 
         // Write pairing code for gateway auth
         if let Some(ref code) = tenant.pairing_code {
             std::fs::write(tenant_dir.join(".pairing_code"), code).ok();
         }
 
-        let child = Command::new(bizclaw_bin)
-            .args(["serve", "--port", &tenant.port.to_string()])
+        let mut command = Command::new(bizclaw_bin);
+        command.args(["serve", "--port", &tenant.port.to_string()]);
+        if has_channels {
+            command.arg("--channels");
+        }
+        command
             .env("BIZCLAW_CONFIG", config_path.to_str().unwrap_or(""))
             .env("BIZCLAW_DATA_DIR", tenant_dir.to_str().unwrap_or(""))
+            .env("BIZCLAW_TENANT_ID", &tenant.id);
+        if let Some(db_path) = &self.db_path {
+            command.env("BIZCLAW_PLATFORM_DB", db_path);
+        }
+        if let Some(timezone) = self.quota_timezone {
+            command.env("BIZCLAW_QUOTA_TIMEZONE", timezone.name());
+        }
+        let child = command
             .stdout(std::process::Stdio::null())
             .stderr(std::process::Stdio::null())
             .spawn()
@@ -146,6 +310,7 @@ port = {}
             pid,
             port: tenant.port,
             started_at: Instant::now(),
+            child: Some(child),
         });
 
         tracing::info!("🚀 Started tenant '{}' (pid={}, port={})", tenant.slug, pid, tenant.port);
@@ -154,22 +319,129 @@ port = {}
 
     /// Stop a tenant process.
     pub fn stop_tenant(&mut self, tenant_id: &str) -> Result<()> {
-        if let Some(proc) = self.processes.remove(tenant_id) {
+        self.restarts.remove(tenant_id);
+        if let Some(mut proc) = self.processes.remove(tenant_id) {
             // Send kill signal
             Command::new("kill").arg(proc.pid.to_string()).output().ok();
+            if let Some(child) = proc.child.as_mut() {
+                child.wait().ok();
+            }
             tracing::info!("⏹ Stopped tenant pid={}", proc.pid);
         }
         Ok(())
     }
 
-    /// Restart a tenant.
+    /// Restart a tenant, returning the new pid.
     pub fn restart_tenant(&mut self, tenant: &Tenant, bizclaw_bin: &str, db: &PlatformDb) -> Result<u32> {
         self.stop_tenant(&tenant.id)?;
         std::thread::sleep(std::time::Duration::from_millis(500));
-        let pid = self.start_tenant(tenant, bizclaw_bin, db)?;
-        db.update_tenant_status(&tenant.id, "running", Some(pid)).ok();
-        db.log_event("tenant_restarted", "system", &tenant.id, None).ok();
-        Ok(pid)
+        self.start_tenant(tenant, bizclaw_bin, db)
+    }
+
+    /// Reconcile tenants marked "running" in `db` with their processes.
+    ///
+    /// Live processes this manager didn't start (from an earlier platform
+    /// run) are adopted. A dead tenant whose plan the policy covers is
+    /// restarted, waiting `backoff` (doubling) between restarts; after
+    /// `max_restarts` in a row, or if its plan isn't covered, it is marked
+    /// "error". Every restart and give-up is audited. `db` is locked only
+    /// for each read and write, so requests aren't held up meanwhile.
+    pub fn check_tenants(&mut self, db: &Mutex<PlatformDb>, bizclaw_bin: &str, policy: &RestartPolicy, now: Instant) -> Result<()> {
+        let db = || db.lock().unwrap_or_else(|e| e.into_inner());
+        let tenants = db().list_tenants()?;
+        for tenant in tenants.into_iter().filter(|t| t.status == "running") {
+            let alive = match self.processes.get_mut(&tenant.id) {
+                Some(process) => process.alive(),
+                None => tenant.pid.is_some_and(pid_alive),
+            };
+            if alive {
+                if let Some(pid) = tenant.pid
+                    && !self.processes.contains_key(&tenant.id)
+                {
+                    self.processes.insert(tenant.id.clone(), TenantProcess { pid, port: tenant.port, started_at: now, child: None });
+                }
+                if self.restarts.get(&tenant.id).is_some_and(|r| now.saturating_duration_since(r.last) >= policy.stable_after) {
+                    self.restarts.remove(&tenant.id);
+                }
+                continue;
+            }
+
+            // Forget the dead process, so restarting doesn't signal a reused pid.
+            self.processes.remove(&tenant.id);
+            let audit = |db: &PlatformDb, event: &str, details: String| {
+                let details = format!("tenant={} {details}", tenant.id);
+                db.log_event(event, "system", "supervisor", Some(&details)).ok();
+                tracing::warn!(target: "bizclaw::audit", event, tenant = tenant.slug, "{details}");
+            };
+            let restarts = self.restarts.get(&tenant.id).map_or(0, |r| r.count);
+            if !policy.covers(&tenant.plan) {
+                let db = db();
+                db.update_tenant_status(&tenant.id, "error", None)?;
+                audit(&db, "tenant_crashed", format!("plan={}", tenant.plan));
+                continue;
+            }
+            if restarts >= policy.max_restarts {
+                self.restarts.remove(&tenant.id);
+                let db = db();
+                db.update_tenant_status(&tenant.id, "error", None)?;
+                audit(&db, "tenant_restart_gave_up", format!("restarts={restarts}"));
+                continue;
+            }
+            if let Some(r) = self.restarts.get(&tenant.id)
+                && now < r.last + policy.backoff_after(r.count)
+            {
+                continue;
+            }
+
+            // The process is gone, so there is nothing to stop or wait for.
+            let attempt = restarts + 1;
+            let restarted = self.start_tenant(&tenant, bizclaw_bin, &db());
+            self.restarts.insert(tenant.id.clone(), Restarts { count: attempt, last: now });
+            let db = db();
+            match restarted {
+                Ok(pid) => {
+                    db.update_tenant_status(&tenant.id, "running", Some(pid))?;
+                    audit(&db, "tenant_auto_restarted", format!("pid={pid} attempt={attempt}"));
+                }
+                Err(e) => {
+                    db.update_tenant_status(&tenant.id, "running", None)?;
+                    audit(&db, "tenant_restart_failed", format!("attempt={attempt} error={e}"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Store the tenant's own API key in `db` and write it into its config
+    /// file and, if the tenant is running, send it SIGUSR1 to reload.
+    /// Returns whether it was signalled.
+    pub fn set_api_key(&self, tenant: &Tenant, api_key: &str, db: &crate::db::PlatformDb) -> Result<bool> {
+        db.set_provider_api_key(&tenant.id, api_key)?;
+        let tenant_dir = self.data_dir.join(&tenant.slug);
+        std::fs::create_dir_all(&tenant_dir)?;
+        let config_path = tenant_dir.join("config.toml");
+        let mut config: toml::Table = match std::fs::read_to_string(&config_path) {
+            Ok(content) => content.parse()
+                .map_err(|e| BizClawError::Config(format!("{}: {e}", config_path.display())))?,
+            Err(_) => toml::Table::new(),
+        };
+        config.insert("api_key".into(), toml::Value::String(api_key.to_string()));
+
+        let tmp = config_path.with_extension("toml.tmp");
+        std::fs::write(&tmp, config.to_string())?;
+        std::fs::rename(&tmp, &config_path)?;
+
+        let Some(proc) = self.processes.get(&tenant.id) else {
+            return Ok(false);
+        };
+        let signalled = Command::new("kill")
+            .args(["-USR1", &proc.pid.to_string()])
+            .output()
+            .is_ok_and(|o| o.status.success());
+        if !signalled {
+            tracing::warn!("Could not signal tenant '{}' (pid={}) to reload", tenant.slug, proc.pid);
+        }
+        Ok(signalled)
     }
 
     /// Get list of running tenant IDs.
@@ -183,8 +455,8 @@ port = {}
     }
 
     /// Check if tenant is actually running (process exists).
-    pub fn is_running(&self, tenant_id: &str) -> bool {
-        self.processes.contains_key(tenant_id)
+    pub fn is_running(&mut self, tenant_id: &str) -> bool {
+        self.processes.get_mut(tenant_id).is_some_and(TenantProcess::alive)
     }
 
     /// Get next available port.
@@ -208,8 +480,192 @@ mod tests {
         assert_eq!(mgr.next_port(10001), 10001);
 
         mgr.processes.insert("t1".into(), TenantProcess {
-            pid: 1, port: 10001, started_at: Instant::now(),
+            pid: 1, port: 10001, started_at: Instant::now(), child: None,
         });
         assert_eq!(mgr.next_port(10001), 10002);
     }
+
+    #[test]
+    fn test_set_api_key_survives_restart() {
+        let data_dir = std::env::temp_dir().join(format!("bizclaw-tenant-{}", uuid::Uuid::new_v4().simple()));
+        let mut mgr = TenantManager::new(&data_dir);
+        let db = PlatformDb::open(std::path::Path::new(":memory:")).unwrap();
+        let tenant = db.create_tenant("Shop", "shop", 10001, "openai", "gpt-4o", "pro").unwrap();
+        let config_path = data_dir.join("shop").join("config.toml");
+
+        assert!(!mgr.set_api_key(&tenant, "sk-old", &db).unwrap());
+        assert!(!mgr.set_api_key(&tenant, "sk-\"new\"", &db).unwrap());
+        // `true` stands in for the bizclaw binary; only the written config matters here.
+        mgr.start_tenant(&tenant, "true", &db).unwrap();
+        let config = std::fs::read_to_string(&config_path).unwrap();
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        let parsed: toml::Table = config.parse().unwrap();
+        assert_eq!(parsed["api_key"].as_str(), Some("sk-\"new\""));
+        assert_eq!(parsed["default_model"].as_str(), Some("gpt-4o"));
+        assert!(!config.contains("sk-old"));
+        // Streaming stays off until its flag is turned on.
+        assert_eq!(parsed["channel"]["streaming"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn test_key_in_old_config_moves_to_database() {
+        let data_dir = std::env::temp_dir().join(format!("bizclaw-tenant-{}", uuid::Uuid::new_v4().simple()));
+        let mut mgr = TenantManager::new(&data_dir).with_default_api_key("sk-platform");
+        let db = PlatformDb::open(std::path::Path::new(":memory:")).unwrap();
+        let upgraded = db.create_tenant("Shop", "shop", 10001, "openai", "gpt-4o", "pro").unwrap();
+        let platform = db.create_tenant("Free", "free", 10002, "openai", "gpt-4o-mini", "free").unwrap();
+        // Configs written before keys were kept in `tenant_secrets`.
+        for (slug, key) in [("shop", "sk-tenant-own"), ("free", "sk-platform")] {
+            std::fs::create_dir_all(data_dir.join(slug)).unwrap();
+            std::fs::write(data_dir.join(slug).join("config.toml"), format!("api_key = \"{key}\"\n")).unwrap();
+        }
+
+        mgr.start_tenant(&upgraded, "true", &db).unwrap();
+        mgr.start_tenant(&platform, "true", &db).unwrap();
+        let config: BizClawConfig = toml::from_str(
+            &std::fs::read_to_string(data_dir.join("shop").join("config.toml")).unwrap(),
+        ).unwrap();
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        assert_eq!(config.api_key, "sk-tenant-own");
+        assert_eq!(db.provider_api_key(&upgraded.id).unwrap().as_deref(), Some("sk-tenant-own"));
+        // The platform's key written into an old config stays the platform's.
+        assert_eq!(db.provider_api_key(&platform.id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_tenant_key_else_platform_key() {
+        let data_dir = std::env::temp_dir().join(format!("bizclaw-tenant-{}", uuid::Uuid::new_v4().simple()));
+        let mut mgr = TenantManager::new(&data_dir).with_default_api_key("sk-platform");
+        let db = PlatformDb::open(std::path::Path::new(":memory:")).unwrap();
+        let free = db.create_tenant("Free", "free", 10001, "ollama", "qwen3", "free").unwrap();
+        let premium = db.create_tenant("Premium", "premium", 10002, "anthropic", "claude-sonnet", "pro").unwrap();
+        db.set_provider_api_key(&premium.id, "sk-ant-premium").unwrap();
+        mgr.start_tenant(&free, "true", &db).unwrap();
+        mgr.start_tenant(&premium, "true", &db).unwrap();
+        let config = |slug: &str| -> BizClawConfig {
+            toml::from_str(&std::fs::read_to_string(data_dir.join(slug).join("config.toml")).unwrap()).unwrap()
+        };
+        let (free, premium) = (config("free"), config("premium"));
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        assert_eq!((free.default_provider.as_str(), free.api_key.as_str()), ("ollama", "sk-platform"));
+        assert_eq!((premium.default_provider.as_str(), premium.api_key.as_str()), ("anthropic", "sk-ant-premium"));
+    }
+
+    #[test]
+    fn test_started_tenant_gets_its_channels() {
+        let data_dir = std::env::temp_dir().join(format!("bizclaw-tenant-{}", uuid::Uuid::new_v4().simple()));
+        let mut mgr = TenantManager::new(&data_dir);
+        let db = PlatformDb::open(std::path::Path::new(":memory:")).unwrap();
+        let tenant = db.create_tenant("Shop", "shop", 10001, "openai", "gpt-4o", "pro").unwrap();
+        let channels = [
+            ("telegram", true, r#"{"bot_token": "123:abc", "allowed_chat_ids": "42, -100"}"#),
+            ("email", true, r#"{"email": "cskh@shop.vn", "password": "app-pass", "imap_port": "993", "smtp_port": "465"}"#),
+            ("zalo", true, r#"{"cookie": "zpw_sek=1", "imei": "imei-1"}"#),
+            ("webhook", true, r#"{"url": "https://crm.shop.vn/hook", "secret": "s3"}"#),
+            ("discord", false, r#"{"bot_token": "disabled"}"#),
+            ("whatsapp", true, r#"{"access_token": ""}"#),
+        ];
+        for (channel_type, enabled, config) in channels {
+            db.upsert_channel(&tenant.id, channel_type, enabled, config).unwrap();
+        }
+        mgr.set_api_key(&tenant, "sk-live", &db).unwrap();
+        mgr.start_tenant(&tenant, "true", &db).unwrap();
+        let config = std::fs::read_to_string(data_dir.join("shop").join("config.toml")).unwrap();
+        let cookie = std::fs::read_to_string(data_dir.join("shop").join("zalo_cookie.txt"));
+        std::fs::remove_dir_all(&data_dir).ok();
+
+        // The child reads it back as a full config.
+        let config: BizClawConfig = toml::from_str(&config).unwrap();
+        assert_eq!((config.api_key.as_str(), config.gateway.port, config.identity.name.as_str()), ("sk-live", 10001, "Shop"));
+        let telegram = config.channel.telegram.unwrap();
+        assert!(telegram.enabled);
+        assert_eq!((telegram.bot_token.as_str(), telegram.allowed_chat_ids), ("123:abc", vec![42, -100]));
+        let email = config.channel.email.unwrap();
+        assert_eq!((email.enabled, email.imap_port, email.smtp_port), (true, 993, 465));
+        let zalo = config.channel.zalo.unwrap();
+        assert!(zalo.enabled && zalo.personal.cookie_path.ends_with("zalo_cookie.txt"));
+        assert_eq!(cookie.unwrap(), "zpw_sek=1");
+        assert_eq!(config.channel.webhook.unwrap().outbound_url.as_deref(), Some("https://crm.shop.vn/hook"));
+        // Disabled channels and those missing credentials are left out.
+        assert!(config.channel.discord.is_none() && config.channel.whatsapp.is_none());
+    }
+
+    #[test]
+    fn test_supervisor_restarts_with_backoff_then_gives_up() {
+        let data_dir = std::env::temp_dir().join(format!("bizclaw-tenant-{}", uuid::Uuid::new_v4().simple()));
+        let mut mgr = TenantManager::new(&data_dir);
+        let db = PlatformDb::open(std::path::Path::new(":memory:")).unwrap();
+        let pro = db.create_tenant("Shop", "shop", 10001, "openai", "gpt-4o", "pro").unwrap();
+        let free = db.create_tenant("Free", "free", 10002, "openai", "gpt-4o", "free").unwrap();
+        let db = Mutex::new(db);
+        let policy = RestartPolicy { plans: vec!["pro".into()], max_restarts: 3, ..Default::default() };
+
+        // `true` exits at once, so each start leaves a crashed tenant behind.
+        let crash = |mgr: &mut TenantManager, tenant: &Tenant| {
+            let db = db.lock().unwrap();
+            let pid = mgr.start_tenant(tenant, "true", &db).unwrap();
+            db.update_tenant_status(&tenant.id, "running", Some(pid)).unwrap();
+        };
+        let wait_for_exit = |mgr: &mut TenantManager| {
+            for _ in 0..50 {
+                if !mgr.is_running(&pro.id) {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(20));
+            }
+            panic!("tenant process didn't exit");
+        };
+        crash(&mut mgr, &pro);
+        crash(&mut mgr, &free);
+        wait_for_exit(&mut mgr);
+        std::thread::sleep(Duration::from_millis(100));
+
+        let t0 = Instant::now();
+        mgr.check_tenants(&db, "true", &policy, t0).unwrap();
+        assert_eq!(db.lock().unwrap().get_tenant(&free.id).unwrap().status, "error");
+        assert_eq!(db.lock().unwrap().get_tenant(&pro.id).unwrap().status, "running");
+
+        // Within the backoff, nothing happens; after it, the second restart.
+        wait_for_exit(&mut mgr);
+        mgr.check_tenants(&db, "true", &policy, t0 + Duration::from_secs(5)).unwrap();
+        mgr.check_tenants(&db, "true", &policy, t0 + Duration::from_secs(10)).unwrap();
+        wait_for_exit(&mut mgr);
+        // The next wait is doubled.
+        mgr.check_tenants(&db, "true", &policy, t0 + Duration::from_secs(15)).unwrap();
+        assert_eq!(db.lock().unwrap().recent_events(10).unwrap().len(), 3);
+        mgr.check_tenants(&db, "true", &policy, t0 + Duration::from_secs(30)).unwrap();
+        wait_for_exit(&mut mgr);
+        // Out of restarts, the tenant is given up on.
+        mgr.check_tenants(&db, "true", &policy, t0 + Duration::from_secs(90)).unwrap();
+        std::fs::remove_dir_all(&data_dir).ok();
+        let tenant = db.lock().unwrap().get_tenant(&pro.id).unwrap();
+        assert_eq!((tenant.status.as_str(), tenant.pid), ("error", None));
+
+        let events = db.lock().unwrap().recent_events(10).unwrap();
+        assert!(events.iter().all(|e| e.actor_id == "supervisor"));
+        let about = |id: &str| events.iter().rev()
+            .filter(|e| e.details.as_deref().unwrap().starts_with(&format!("tenant={id} ")))
+            .map(|e| e.event_type.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(about(&free.id), ["tenant_crashed"]);
+        assert_eq!(about(&pro.id), ["tenant_auto_restarted", "tenant_auto_restarted", "tenant_auto_restarted", "tenant_restart_gave_up"]);
+        assert!(events[1].details.as_deref().unwrap().ends_with("attempt=3"));
+    }
+
+    #[test]
+    fn test_supervisor_adopts_live_process() {
+        let mut mgr = TenantManager::new("/tmp/bizclaw-test");
+        let db = PlatformDb::open(std::path::Path::new(":memory:")).unwrap();
+        let tenant = db.create_tenant("Shop", "shop", 10001, "openai", "gpt-4o", "pro").unwrap();
+        // This test process stands in for a tenant started by an earlier run.
+        db.update_tenant_status(&tenant.id, "running", Some(std::process::id())).unwrap();
+        let db = Mutex::new(db);
+
+        mgr.check_tenants(&db, "true", &RestartPolicy::default(), Instant::now()).unwrap();
+        assert_eq!(mgr.get_process(&tenant.id).map(|p| p.pid), Some(std::process::id()));
+        assert!(db.lock().unwrap().recent_events(10).unwrap().is_empty());
+    }
 }
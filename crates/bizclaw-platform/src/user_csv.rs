@@ -0,0 +1,219 @@
+//! Bulk user management — CSV export of users and CSV import as invitations.
+//!
+//! Both use the columns `email,role,tenant_id`; exports add `created_at`,
+//! which imports ignore, so an export can be edited and imported elsewhere.
+//! Imports never create accounts directly: each new email gets an invitation.
+
+use bizclaw_core::error::{BizClawError, Result};
+
+use crate::db::PlatformDb;
+
+/// Most data rows accepted in one import.
+pub const MAX_IMPORT_ROWS: usize = 500;
+
+/// Roles a user can be given.
+pub const ROLES: &[&str] = &["admin", "user", "viewer"];
+
+/// Outcome of an import.
+#[derive(Debug, Default, serde::Serialize)]
+pub struct ImportReport {
+    /// Invitations created.
+    pub created: usize,
+    /// Rows whose email already has an account or invitation.
+    pub skipped: usize,
+    pub errors: Vec<RowError>,
+}
+
+/// A row that was rejected.
+#[derive(Debug, serde::Serialize)]
+pub struct RowError {
+    /// Line in the uploaded file; the header is line 1.
+    pub row: u64,
+    pub message: String,
+}
+
+#[derive(serde::Deserialize)]
+struct ImportRow {
+    email: String,
+    #[serde(default)]
+    role: Option<String>,
+    #[serde(default)]
+    tenant_id: Option<String>,
+}
+
+/// All users as CSV. Password hashes are never included.
+pub fn export_users(db: &PlatformDb) -> Result<String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    let csv_err = |e: csv::Error| BizClawError::Other(format!("CSV export: {e}"));
+    writer.write_record(["email", "role", "tenant_id", "created_at"]).map_err(csv_err)?;
+    for user in db.list_users()? {
+        writer.write_record([
+            user.email.as_str(),
+            user.role.as_str(),
+            user.tenant_id.as_deref().unwrap_or(""),
+            user.created_at.as_str(),
+        ]).map_err(csv_err)?;
+    }
+    let bytes = writer.into_inner().map_err(|e| BizClawError::Other(format!("CSV export: {e}")))?;
+    String::from_utf8(bytes).map_err(|e| BizClawError::Other(format!("CSV export: {e}")))
+}
+
+/// Create invitations for the new emails in `data`.
+///
+/// Bad rows are reported and skipped; the rest still go through. The whole
+/// file is refused if it can't be read or has more than [`MAX_IMPORT_ROWS`].
+pub fn import_users(db: &PlatformDb, data: &[u8]) -> Result<ImportReport> {
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::All)
+        .flexible(true)
+        .from_reader(data);
+
+    let headers = reader.headers()
+        .map_err(|e| BizClawError::Other(format!("Invalid CSV: {e}")))?;
+    if !headers.iter().any(|h| h.eq_ignore_ascii_case("email")) {
+        return Err(BizClawError::Other("CSV needs a header row with an 'email' column".into()));
+    }
+    let headers = csv::StringRecord::from_iter(headers.iter().map(|h| h.to_ascii_lowercase()));
+    reader.set_headers(headers.clone());
+
+    let mut records = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| BizClawError::Other(format!("Invalid CSV: {e}")))?;
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+        if records.len() == MAX_IMPORT_ROWS {
+            return Err(BizClawError::Other(format!("Too many rows; import at most {MAX_IMPORT_ROWS} at a time")));
+        }
+        records.push(record);
+    }
+
+    let mut report = ImportReport::default();
+    for record in records {
+        let row = record.position().map_or(0, |p| p.line());
+        let parsed = record.deserialize::<ImportRow>(Some(&headers))
+            .map_err(|e| e.to_string())
+            .and_then(|r| validate(db, r).map_err(|e| e.to_string()));
+        let (email, role, tenant_id) = match parsed {
+            Ok(valid) => valid,
+            Err(message) => {
+                report.errors.push(RowError { row, message });
+                continue;
+            }
+        };
+        if db.email_exists(&email)? {
+            report.skipped += 1;
+            continue;
+        }
+        db.create_invitation(&email, &role, tenant_id.as_deref())?;
+        report.created += 1;
+    }
+    Ok(report)
+}
+
+/// Check a row, returning its email, role (default `user`) and tenant.
+fn validate(db: &PlatformDb, row: ImportRow) -> Result<(String, String, Option<String>)> {
+    let email = row.email.to_lowercase();
+    if !is_valid_email(&email) {
+        return Err(BizClawError::Other(format!("Invalid email '{}'", row.email)));
+    }
+    let role = row.role.filter(|r| !r.is_empty()).map_or("user".to_string(), |r| r.to_lowercase());
+    if !ROLES.contains(&role.as_str()) {
+        return Err(BizClawError::Other(format!("Invalid role '{role}'; use one of {}", ROLES.join(", "))));
+    }
+    let tenant_id = row.tenant_id.filter(|t| !t.is_empty());
+    if let Some(id) = &tenant_id
+        && db.get_tenant(id).is_err()
+    {
+        return Err(BizClawError::Other(format!("Tenant '{id}' not found")));
+    }
+    Ok((email, role, tenant_id))
+}
+
+/// `local@domain.tld`, without spaces.
+fn is_valid_email(email: &str) -> bool {
+    let Some((local, domain)) = email.split_once('@') else {
+        return false;
+    };
+    !local.is_empty()
+        && email.len() <= 254
+        && !email.chars().any(char::is_whitespace)
+        && !domain.contains('@')
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|part| !part.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn temp_db() -> PlatformDb {
+        PlatformDb::open(&PathBuf::from(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn test_import_valid_csv() {
+        let db = temp_db();
+        let tenant = db.create_tenant("Shop", "shop", 10001, "openai", "gpt-4o-mini", "free").unwrap();
+        db.create_user("owner@shop.vn", "$2b$12$fake", "admin").unwrap();
+
+        let csv = format!(
+            "email,role,tenant_id\nAn@Shop.vn,viewer,{}\nbinh@shop.vn,,\nowner@shop.vn,admin,\n\nan@shop.vn,user,\n",
+            tenant.id
+        );
+        let report = import_users(&db, csv.as_bytes()).unwrap();
+        assert_eq!((report.created, report.skipped), (2, 2), "{report:?}");
+        assert!(report.errors.is_empty());
+
+        let invitations = db.list_invitations().unwrap();
+        let an = invitations.iter().find(|i| i.email == "an@shop.vn").unwrap();
+        assert_eq!(an.role, "viewer");
+        assert_eq!(an.tenant_id.as_deref(), Some(tenant.id.as_str()));
+        let binh = invitations.iter().find(|i| i.email == "binh@shop.vn").unwrap();
+        assert_eq!((binh.role.as_str(), binh.tenant_id.as_deref()), ("user", None));
+        // Invitations, not accounts.
+        assert_eq!(db.list_users().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_import_reports_bad_rows() {
+        let db = temp_db();
+        let csv = "email,role,tenant_id\nnot-an-email,user,\nok@shop.vn,owner,\nlan@shop.vn,user,missing-tenant\nmai@shop.vn,user,\n";
+        let report = import_users(&db, csv.as_bytes()).unwrap();
+        assert_eq!(report.created, 1);
+        let errors: Vec<_> = report.errors.iter().map(|e| (e.row, e.message.as_str())).collect();
+        assert_eq!(errors, vec![
+            (2, "Invalid email 'not-an-email'"),
+            (3, "Invalid role 'owner'; use one of admin, user, viewer"),
+            (4, "Tenant 'missing-tenant' not found"),
+        ]);
+    }
+
+    #[test]
+    fn test_import_limits_and_export() {
+        let db = temp_db();
+        let mut csv = String::from("email\n");
+        for i in 0..=MAX_IMPORT_ROWS {
+            csv.push_str(&format!("u{i}@shop.vn\n"));
+        }
+        assert!(import_users(&db, csv.as_bytes()).is_err());
+        assert!(db.list_invitations().unwrap().is_empty());
+        assert!(import_users(&db, b"name,role\nAn,user\n").is_err());
+
+        db.create_user("owner@shop.vn", "$2b$12$secret", "admin").unwrap();
+        let exported = export_users(&db).unwrap();
+        let mut lines = exported.lines();
+        assert_eq!(lines.next(), Some("email,role,tenant_id,created_at"));
+        assert!(lines.next().unwrap().starts_with("owner@shop.vn,admin,,"));
+        assert!(!exported.contains("secret"));
+    }
+
+    #[test]
+    fn test_email_validation() {
+        assert!(is_valid_email("an.nguyen@shop.com.vn"));
+        for bad in ["", "an", "@shop.vn", "an@shop", "an@@shop.vn", "an @shop.vn", "an@shop..vn"] {
+            assert!(!is_valid_email(bad), "{bad}");
+        }
+    }
+}
@@ -21,7 +21,7 @@ impl AnthropicProvider {
 
         Ok(Self {
             api_key,
-            client: reqwest::Client::new(),
+            client: crate::client::build_http_client(config),
         })
     }
 
@@ -169,6 +169,7 @@ impl Provider for AnthropicProvider {
             tool_calls,
             finish_reason: json["stop_reason"].as_str().map(String::from),
             usage,
+            metadata: None,
         })
     }
 
@@ -183,4 +184,13 @@ impl Provider for AnthropicProvider {
     async fn health_check(&self) -> Result<bool> {
         Ok(!self.api_key.is_empty())
     }
+
+    async fn verify_key(&self) -> Result<()> {
+        let request = self.client.get("https://api.anthropic.com/v1/models")
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", "2023-06-01");
+        crate::client::verify_key("anthropic", &self.api_key, request).await
+    }
+
+    fn supports_tools(&self) -> bool { true }
 }
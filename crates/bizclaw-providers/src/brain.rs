@@ -1,5 +1,6 @@
 use async_trait::async_trait;
-use bizclaw_core::config::BizClawConfig;
+use bizclaw_brain::kv_cache::KvCacheEviction;
+use bizclaw_core::config::{BizClawConfig, KvEvictionConfig};
 use bizclaw_core::error::Result;
 use bizclaw_core::traits::provider::{GenerateParams, Provider};
 use bizclaw_core::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
@@ -9,6 +10,16 @@ pub struct BrainProvider {
     engine: Mutex<bizclaw_brain::BrainEngine>,
 }
 
+fn kv_eviction(config: KvEvictionConfig) -> KvCacheEviction {
+    match config {
+        KvEvictionConfig::Error => KvCacheEviction::Error,
+        KvEvictionConfig::SlidingWindow { window_size } => KvCacheEviction::SlidingWindow { window_size },
+        KvEvictionConfig::SinkTokens { sink_count, window_size } => {
+            KvCacheEviction::SinkTokens { sink_count, window_size }
+        }
+    }
+}
+
 impl BrainProvider {
     pub fn new(config: &BizClawConfig) -> Result<Self> {
         let brain_config = bizclaw_brain::BrainConfig {
@@ -17,19 +28,26 @@ impl BrainProvider {
             context_length: config.brain.context_length,
             temperature: config.brain.temperature,
             top_p: config.brain.top_p,
+            top_k: config.brain.top_k,
+            min_p: config.brain.min_p,
+            mirostat_v2: config.brain.mirostat_v2.map(|m| bizclaw_brain::sampler::MirostatParams { tau: m.tau, eta: m.eta }),
+            repetition_penalty: config.brain.repetition_penalty,
+            frequency_penalty: config.brain.frequency_penalty,
+            presence_penalty: config.brain.presence_penalty,
+            penalty_context_tokens: config.brain.penalty_context_tokens,
+            seed: config.brain.seed,
+            stop_sequences: config.brain.stop_sequences.clone(),
             json_mode: config.brain.json_mode,
+            grammar: config.brain.grammar.clone(),
+            grammar_file: config.brain.grammar_file.as_ref().map(std::path::PathBuf::from),
+            kv_eviction: kv_eviction(config.brain.kv_eviction),
+            auto_detect_params: config.brain.auto_detect_params,
         };
 
         let mut engine = bizclaw_brain::BrainEngine::new(brain_config);
 
         // Try to load model from configured path
-        let model_dir = bizclaw_core::config::BizClawConfig::home_dir().join("models");
-        let model_path = if !config.brain.model_path.is_empty() {
-            std::path::PathBuf::from(&config.brain.model_path)
-        } else {
-            // Auto-detect: find first .gguf file in models directory
-            find_gguf_model(&model_dir).unwrap_or_else(|| model_dir.join("model.gguf"))
-        };
+        let model_path = model_path(config);
 
         if model_path.exists() {
             match engine.load_model(&model_path) {
@@ -48,6 +66,22 @@ impl BrainProvider {
 }
 
 /// Find the first .gguf file in a directory.
+/// The configured `brain.model_path`, or the first `.gguf` file in
+/// `~/.bizclaw/models` when it is empty.
+pub fn model_path(config: &BizClawConfig) -> std::path::PathBuf {
+    let model_dir = BizClawConfig::home_dir().join("models");
+    if let Some(rest) = config.brain.model_path.strip_prefix("~/") {
+        std::env::var("HOME").ok()
+            .map(|h| std::path::PathBuf::from(h).join(rest))
+            .unwrap_or_else(|| std::path::PathBuf::from(&config.brain.model_path))
+    } else if !config.brain.model_path.is_empty() {
+        std::path::PathBuf::from(&config.brain.model_path)
+    } else {
+        // Auto-detect: find first .gguf file in models directory
+        find_gguf_model(&model_dir).unwrap_or_else(|| model_dir.join("model.gguf"))
+    }
+}
+
 fn find_gguf_model(dir: &std::path::Path) -> Option<std::path::PathBuf> {
     if !dir.exists() {
         return None;
@@ -88,8 +122,11 @@ impl Provider for BrainProvider {
             256
         };
 
-        let response = self.engine.lock().await.generate(&prompt, max_tokens)?;
-        Ok(ProviderResponse::text(response))
+        let completion = self.engine.lock().await.complete(&prompt, max_tokens)?;
+        Ok(ProviderResponse {
+            finish_reason: Some(completion.finish_reason.into()),
+            ..ProviderResponse::text(completion.text)
+        })
     }
 
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
@@ -107,27 +144,26 @@ impl Provider for BrainProvider {
 
         // List available models in ~/.bizclaw/models/
         let model_dir = bizclaw_core::config::BizClawConfig::home_dir().join("models");
-        if model_dir.exists() {
-            if let Ok(entries) = std::fs::read_dir(&model_dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    if path.extension().and_then(|e| e.to_str()) == Some("gguf") {
-                        let name = path.file_name()
-                            .map(|f| f.to_string_lossy().to_string())
-                            .unwrap_or_default();
-                        let size_mb = std::fs::metadata(&path)
-                            .map(|m| m.len() / 1024 / 1024)
-                            .unwrap_or(0);
-
-                        if !models.iter().any(|m: &ModelInfo| m.id == name) {
-                            models.push(ModelInfo {
-                                id: name.clone(),
-                                name: format!("{} ({}MB)", name, size_mb),
-                                provider: "brain".into(),
-                                context_length: 2048,
-                                max_output_tokens: Some(256),
-                            });
-                        }
+        if model_dir.exists()
+            && let Ok(entries) = std::fs::read_dir(&model_dir) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().and_then(|e| e.to_str()) == Some("gguf") {
+                    let name = path.file_name()
+                        .map(|f| f.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    let size_mb = std::fs::metadata(&path)
+                        .map(|m| m.len() / 1024 / 1024)
+                        .unwrap_or(0);
+
+                    if !models.iter().any(|m: &ModelInfo| m.id == name) {
+                        models.push(ModelInfo {
+                            id: name.clone(),
+                            name: format!("{} ({}MB)", name, size_mb),
+                            provider: "brain".into(),
+                            context_length: 2048,
+                            max_output_tokens: Some(256),
+                        });
                     }
                 }
             }
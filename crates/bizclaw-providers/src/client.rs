@@ -0,0 +1,115 @@
+//! Shared HTTP client for provider API calls.
+
+use bizclaw_core::config::BizClawConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+/// Client for outbound LLM API calls: sends `extra_headers` with every
+/// request and goes through `proxy` when set (hosts in `NO_PROXY` bypass it).
+///
+/// Invalid header names/values or proxy URLs are logged and skipped, so a
+/// typo in config doesn't stop the provider from being created.
+pub fn build_http_client(config: &BizClawConfig) -> reqwest::Client {
+    let mut headers = HeaderMap::new();
+    for (name, value) in &config.extra_headers {
+        match (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            (Ok(name), Ok(mut value)) => {
+                // Often auth tokens; keep them out of debug output.
+                value.set_sensitive(true);
+                headers.insert(name, value);
+            }
+            _ => tracing::warn!("Ignoring invalid extra header '{name}'"),
+        }
+    }
+
+    let mut builder = reqwest::Client::builder().default_headers(headers);
+    if let Some(proxy) = config.proxy.as_deref().filter(|p| !p.is_empty()) {
+        match reqwest::Proxy::all(proxy) {
+            Ok(proxy) => builder = builder.proxy(proxy.no_proxy(reqwest::NoProxy::from_env())),
+            Err(e) => tracing::warn!("Ignoring invalid proxy '{proxy}': {e}"),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        tracing::warn!("Falling back to default HTTP client: {e}");
+        reqwest::Client::new()
+    })
+}
+
+/// Send an authenticated request (usually `GET /models`) to check a key:
+/// 401 and 403 mean the provider refused it.
+pub(crate) async fn verify_key(provider: &str, api_key: &str, request: reqwest::RequestBuilder) -> Result<()> {
+    if api_key.is_empty() {
+        return Err(BizClawError::ApiKeyMissing(provider.into()));
+    }
+    let resp = request.send().await
+        .map_err(|e| BizClawError::Provider(format!("{provider} key check failed: {e}")))?;
+    match resp.status() {
+        status if status.is_success() => Ok(()),
+        status @ (reqwest::StatusCode::UNAUTHORIZED | reqwest::StatusCode::FORBIDDEN) => {
+            Err(BizClawError::AuthFailed(format!("{provider} rejected the API key ({status})")))
+        }
+        status => Err(BizClawError::Provider(format!("{provider} key check failed: {status}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::traits::provider::GenerateParams;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Accept one request, answer with a minimal chat completion, and return the raw request head.
+    async fn capture_one_request() -> (String, tokio::task::JoinHandle<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    break;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"choices":[{"message":{"content":"hi"},"finish_reason":"stop"}]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).to_lowercase()
+        });
+        (url, server)
+    }
+
+    #[tokio::test]
+    async fn test_extra_headers_sent_to_provider() {
+        let (url, server) = capture_one_request().await;
+        let config = BizClawConfig {
+            default_provider: format!("custom:{url}"),
+            api_key: "sk-test".into(),
+            extra_headers: [
+                ("X-Custom-Header".to_string(), "test".to_string()),
+                ("bad header".to_string(), "x".to_string()),
+            ].into(),
+            ..Default::default()
+        };
+        let provider = crate::create_provider(&config).unwrap();
+        let params = GenerateParams {
+            model: "m".into(),
+            temperature: 0.0,
+            max_tokens: 8,
+            top_p: 1.0,
+            stop: vec![],
+        };
+        let response = provider.chat(&[bizclaw_core::types::Message::user("hello")], &[], &params).await.unwrap();
+        assert_eq!(response.content.as_deref(), Some("hi"));
+
+        let request = server.await.unwrap();
+        assert!(request.contains("x-custom-header: test\r\n"), "{request}");
+        // Per-request headers still apply alongside the defaults.
+        assert!(request.contains("authorization: bearer sk-test\r\n"));
+    }
+}
@@ -5,7 +5,7 @@
 use async_trait::async_trait;
 use bizclaw_core::config::BizClawConfig;
 use bizclaw_core::error::{BizClawError, Result};
-use bizclaw_core::traits::provider::{GenerateParams, Provider};
+use bizclaw_core::traits::provider::{GenerateParams, Provider, TokenStream};
 use bizclaw_core::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
 
 pub struct CustomProvider {
@@ -26,7 +26,7 @@ impl CustomProvider {
         Ok(Self {
             api_url,
             api_key,
-            client: reqwest::Client::new(),
+            client: crate::client::build_http_client(config),
         })
     }
 }
@@ -113,9 +113,38 @@ impl Provider for CustomProvider {
                 completion_tokens: u["completion_tokens"].as_u64().unwrap_or(0) as u32,
                 total_tokens: u["total_tokens"].as_u64().unwrap_or(0) as u32,
             }),
+            metadata: None,
         })
     }
 
+    async fn chat_stream(&self, messages: &[Message], params: &GenerateParams) -> Result<TokenStream> {
+        let body = serde_json::json!({
+            "model": params.model,
+            "messages": messages,
+            "temperature": params.temperature,
+            "max_tokens": params.max_tokens,
+            "stream": true,
+        });
+
+        let mut req = self.client.post(format!("{}/chat/completions", self.api_url));
+        if !self.api_key.is_empty() {
+            req = req.header("Authorization", format!("Bearer {}", self.api_key));
+        }
+
+        let resp = req
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Http(format!("Custom provider connection failed ({}): {}", self.api_url, e)))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(BizClawError::Provider(format!("Custom API error {status}: {text}")));
+        }
+        Ok(crate::streaming::token_stream(resp, crate::streaming::Format::OpenAiSse))
+    }
+
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         let resp = self.client
             .get(format!("{}/models", self.api_url))
@@ -151,4 +180,11 @@ impl Provider for CustomProvider {
             .await;
         Ok(resp.is_ok())
     }
+
+    async fn verify_key(&self) -> Result<()> {
+        let request = self.client.get(format!("{}/models", self.api_url)).bearer_auth(&self.api_key);
+        crate::client::verify_key("custom", &self.api_key, request).await
+    }
+
+    fn supports_tools(&self) -> bool { true }
 }
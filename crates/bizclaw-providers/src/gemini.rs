@@ -20,7 +20,7 @@ impl GeminiProvider {
         } else {
             config.api_key.clone()
         };
-        Ok(Self { api_key, client: reqwest::Client::new() })
+        Ok(Self { api_key, client: crate::client::build_http_client(config) })
     }
 }
 
@@ -67,6 +67,7 @@ impl Provider for GeminiProvider {
             tool_calls: vec![],
             finish_reason: Some("stop".into()),
             usage: None,
+            metadata: None,
         })
     }
 
@@ -80,4 +81,10 @@ impl Provider for GeminiProvider {
     async fn health_check(&self) -> Result<bool> {
         Ok(!self.api_key.is_empty())
     }
+
+    async fn verify_key(&self) -> Result<()> {
+        let request = self.client.get("https://generativelanguage.googleapis.com/v1beta/openai/models")
+            .bearer_auth(&self.api_key);
+        crate::client::verify_key("gemini", &self.api_key, request).await
+    }
 }
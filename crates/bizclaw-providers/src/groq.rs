@@ -16,7 +16,7 @@ impl GroqProvider {
         let api_key = if config.api_key.is_empty() {
             std::env::var("GROQ_API_KEY").unwrap_or_default()
         } else { config.api_key.clone() };
-        Ok(Self { api_key, client: reqwest::Client::new() })
+        Ok(Self { api_key, client: crate::client::build_http_client(config) })
     }
 }
 
@@ -36,7 +36,7 @@ impl Provider for GroqProvider {
         if !status.is_success() { return Err(BizClawError::Provider(format!("Groq {status}: {text}"))); }
         let json: serde_json::Value = serde_json::from_str(&text).map_err(|e| BizClawError::Provider(format!("JSON: {e}")))?;
 
-        Ok(ProviderResponse { content: json["choices"][0]["message"]["content"].as_str().map(String::from), tool_calls: vec![], finish_reason: Some("stop".into()), usage: None })
+        Ok(ProviderResponse { content: json["choices"][0]["message"]["content"].as_str().map(String::from), tool_calls: vec![], finish_reason: Some("stop".into()), usage: None, metadata: None })
     }
 
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
@@ -48,4 +48,9 @@ impl Provider for GroqProvider {
     }
 
     async fn health_check(&self) -> Result<bool> { Ok(!self.api_key.is_empty()) }
+
+    async fn verify_key(&self) -> Result<()> {
+        let request = self.client.get("https://api.groq.com/openai/v1/models").bearer_auth(&self.api_key);
+        crate::client::verify_key("groq", &self.api_key, request).await
+    }
 }
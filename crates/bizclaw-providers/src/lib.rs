@@ -2,6 +2,7 @@
 //!
 //! LLM provider implementations: OpenAI, Anthropic, Ollama, LlamaCpp, Brain, Gemini, DeepSeek, Groq.
 
+pub mod client;
 pub mod openai;
 pub mod anthropic;
 pub mod ollama;
@@ -11,6 +12,8 @@ pub mod custom;
 pub mod gemini;
 pub mod deepseek;
 pub mod groq;
+pub mod racing;
+mod streaming;
 
 use bizclaw_core::config::BizClawConfig;
 use bizclaw_core::traits::Provider;
@@ -30,11 +33,14 @@ pub fn create_provider(config: &BizClawConfig) -> Result<Box<dyn Provider>> {
         other if other.starts_with("custom:") => {
             Ok(Box::new(custom::CustomProvider::new(config, other)?))
         }
+        other if other.starts_with("race:") => {
+            Ok(Box::new(racing::RacingProvider::from_spec(config, other)?))
+        }
         other => Err(bizclaw_core::error::BizClawError::ProviderNotFound(other.into())),
     }
 }
 
 /// List all available provider names.
 pub fn available_providers() -> Vec<&'static str> {
-    vec!["openai", "anthropic", "ollama", "llamacpp", "brain", "gemini", "deepseek", "groq", "openrouter", "custom"]
+    vec!["openai", "anthropic", "ollama", "llamacpp", "brain", "gemini", "deepseek", "groq", "openrouter", "custom", "race"]
 }
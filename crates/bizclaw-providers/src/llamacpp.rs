@@ -21,7 +21,7 @@ impl LlamaCppProvider {
 
         Ok(Self {
             api_url,
-            client: reqwest::Client::new(),
+            client: crate::client::build_http_client(config),
         })
     }
 }
@@ -118,6 +118,7 @@ impl Provider for LlamaCppProvider {
                 completion_tokens: u["completion_tokens"].as_u64().unwrap_or(0) as u32,
                 total_tokens: u["total_tokens"].as_u64().unwrap_or(0) as u32,
             }),
+            metadata: None,
         })
     }
 
@@ -165,4 +166,6 @@ impl Provider for LlamaCppProvider {
             .await;
         Ok(resp.is_ok())
     }
+
+    fn supports_tools(&self) -> bool { true }
 }
@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use bizclaw_core::config::BizClawConfig;
 use bizclaw_core::error::{BizClawError, Result};
-use bizclaw_core::traits::provider::{GenerateParams, Provider};
+use bizclaw_core::traits::provider::{GenerateParams, Provider, TokenStream};
 use bizclaw_core::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
 
 pub struct OllamaProvider {
@@ -20,7 +20,7 @@ impl OllamaProvider {
 
         Ok(Self {
             api_url,
-            client: reqwest::Client::new(),
+            client: crate::client::build_http_client(config),
         })
     }
 }
@@ -123,9 +123,50 @@ impl Provider for OllamaProvider {
             tool_calls,
             finish_reason: Some("stop".into()),
             usage,
+            metadata: None,
         })
     }
 
+    async fn chat_stream(&self, messages: &[Message], params: &GenerateParams) -> Result<TokenStream> {
+        let formatted_messages: Vec<serde_json::Value> = messages.iter().map(|m| {
+            serde_json::json!({
+                "role": m.role.to_string(),
+                "content": m.content,
+            })
+        }).collect();
+
+        let model = if params.model.is_empty() {
+            "llama3.2"
+        } else {
+            &params.model
+        };
+
+        let body = serde_json::json!({
+            "model": model,
+            "messages": formatted_messages,
+            "stream": true,
+            "options": {
+                "temperature": params.temperature,
+                "top_p": params.top_p,
+                "num_predict": params.max_tokens,
+            }
+        });
+
+        let resp = self.client
+            .post(format!("{}/api/chat", self.api_url))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Http(format!("Ollama connection failed ({}): {}", self.api_url, e)))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(BizClawError::Provider(format!("Ollama API error {status}: {text}")));
+        }
+        Ok(crate::streaming::token_stream(resp, crate::streaming::Format::OllamaNdjson))
+    }
+
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         // Call Ollama's /api/tags endpoint to list installed models
         let resp = self.client
@@ -165,4 +206,6 @@ impl Provider for OllamaProvider {
             .await;
         Ok(resp.is_ok())
     }
+
+    fn supports_tools(&self) -> bool { true }
 }
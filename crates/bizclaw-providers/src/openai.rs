@@ -3,7 +3,7 @@
 use async_trait::async_trait;
 use bizclaw_core::config::BizClawConfig;
 use bizclaw_core::error::{BizClawError, Result};
-use bizclaw_core::traits::provider::{GenerateParams, Provider};
+use bizclaw_core::traits::provider::{GenerateParams, Provider, TokenStream};
 use bizclaw_core::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
 
 pub struct OpenAiProvider {
@@ -32,7 +32,7 @@ impl OpenAiProvider {
         Ok(Self {
             api_key,
             api_url,
-            client: reqwest::Client::new(),
+            client: crate::client::build_http_client(config),
         })
     }
 }
@@ -53,7 +53,7 @@ impl Provider for OpenAiProvider {
 
         let mut body = serde_json::json!({
             "model": params.model,
-            "messages": messages,
+            "messages": wire_messages(messages),
             "temperature": params.temperature,
             "max_tokens": params.max_tokens,
         });
@@ -118,9 +118,39 @@ impl Provider for OpenAiProvider {
                 completion_tokens: u["completion_tokens"].as_u64().unwrap_or(0) as u32,
                 total_tokens: u["total_tokens"].as_u64().unwrap_or(0) as u32,
             }),
+            metadata: None,
         })
     }
 
+    async fn chat_stream(&self, messages: &[Message], params: &GenerateParams) -> Result<TokenStream> {
+        if self.api_key.is_empty() {
+            return Err(BizClawError::ApiKeyMissing("openai".into()));
+        }
+
+        let body = serde_json::json!({
+            "model": params.model,
+            "messages": wire_messages(messages),
+            "temperature": params.temperature,
+            "max_tokens": params.max_tokens,
+            "stream": true,
+        });
+
+        let resp = self.client
+            .post(format!("{}/chat/completions", self.api_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Http(e.to_string()))?;
+
+        if !resp.status().is_success() {
+            let status = resp.status();
+            let text = resp.text().await.unwrap_or_default();
+            return Err(BizClawError::Provider(format!("OpenAI API error {status}: {text}")));
+        }
+        Ok(crate::streaming::token_stream(resp, crate::streaming::Format::OpenAiSse))
+    }
+
     async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         Ok(vec![
             ModelInfo { id: "gpt-4o".into(), name: "GPT-4o".into(), provider: "openai".into(), context_length: 128000, max_output_tokens: Some(4096) },
@@ -131,4 +161,58 @@ impl Provider for OpenAiProvider {
     async fn health_check(&self) -> Result<bool> {
         Ok(!self.api_key.is_empty())
     }
+
+    async fn verify_key(&self) -> Result<()> {
+        let request = self.client.get(format!("{}/models", self.api_url)).bearer_auth(&self.api_key);
+        crate::client::verify_key("openai", &self.api_key, request).await
+    }
+
+    fn supports_tools(&self) -> bool { true }
+
+    fn supports_vision(&self) -> bool { true }
+}
+
+/// Messages in the Chat Completions format: a message with images gets its
+/// text and images as content parts.
+pub(crate) fn wire_messages(messages: &[Message]) -> Vec<serde_json::Value> {
+    use base64::Engine;
+
+    messages.iter().map(|message| {
+        let mut value = serde_json::to_value(message).unwrap_or_default();
+        if !message.images.is_empty() {
+            let mut parts = vec![serde_json::json!({ "type": "text", "text": message.content })];
+            parts.extend(message.images.iter().map(|image| serde_json::json!({
+                "type": "image_url",
+                "image_url": {
+                    "url": format!(
+                        "data:{};base64,{}",
+                        image.mime_type, base64::engine::general_purpose::STANDARD.encode(&image.data)
+                    ),
+                },
+            })));
+            value["content"] = parts.into();
+        }
+        value
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::types::ImagePart;
+
+    #[test]
+    fn test_wire_messages_with_images() {
+        let messages = [
+            Message::system("Be brief."),
+            Message::user("What is this?").with_images(vec![ImagePart { mime_type: "image/png".into(), data: b"png".to_vec() }]),
+        ];
+        let wire = wire_messages(&messages);
+        assert_eq!(wire[0]["content"], "Be brief.");
+        assert_eq!(wire[1]["content"], serde_json::json!([
+            { "type": "text", "text": "What is this?" },
+            { "type": "image_url", "image_url": { "url": "data:image/png;base64,cG5n" } },
+        ]));
+        assert!(wire[1].get("images").is_none());
+    }
 }
@@ -0,0 +1,317 @@
+//! Racing provider — send the same prompt to several providers, keep the fastest.
+//!
+//! Selected with `default_provider = "race:groq,gemini"`. Each entry may pin a
+//! model as `provider/model` (e.g. `race:groq/llama-3.1-8b-instant,gemini/gemini-2.5-flash`);
+//! entries without one use the request's model.
+//!
+//! All calls share a cancellation token: the first successful response wins,
+//! the token is cancelled, and the losers are dropped mid-flight. The winner and
+//! per-provider latencies are recorded under `metadata.race` in the response.
+
+use async_trait::async_trait;
+use bizclaw_core::config::BizClawConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::provider::{GenerateParams, Provider};
+use bizclaw_core::types::{Message, ModelInfo, ProviderResponse, ToolDefinition};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// One racer: a provider plus an optional pinned model.
+pub struct RaceEntry {
+    pub provider: Box<dyn Provider>,
+    pub model: Option<String>,
+}
+
+/// How a racer finished.
+enum Outcome {
+    Won(ProviderResponse),
+    Failed(BizClawError),
+    Cancelled,
+}
+
+pub struct RacingProvider {
+    entries: Vec<RaceEntry>,
+}
+
+impl RacingProvider {
+    pub fn new(entries: Vec<RaceEntry>) -> Self {
+        Self { entries }
+    }
+
+    /// Build from a `race:a,b/model,...` provider spec.
+    ///
+    /// Each racer is created from a copy of `config` with its own provider name.
+    /// The shared `api_key` cannot belong to every backend, so racers read
+    /// their keys from the usual per-provider environment variables; a
+    /// hosted racer without one is an error here rather than a failed call
+    /// on every race.
+    pub fn from_spec(config: &BizClawConfig, spec: &str) -> Result<Self> {
+        let list = spec.strip_prefix("race:").unwrap_or(spec);
+        let mut entries = Vec::new();
+        for item in list.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, model) = match item.split_once('/') {
+                Some((name, model)) => (name, Some(model.to_string())),
+                None => (item, None),
+            };
+            if name.starts_with("race:") {
+                return Err(BizClawError::Config("Nested race providers are not supported".into()));
+            }
+            if let Some(vars) = missing_key(name, |var| std::env::var(var).ok()) {
+                return Err(BizClawError::Config(format!(
+                    "race provider '{name}' needs its API key in {vars}; the shared api_key is not used for racers"
+                )));
+            }
+            let mut sub = config.clone();
+            sub.default_provider = name.to_string();
+            sub.api_key.clear();
+            entries.push(RaceEntry { provider: crate::create_provider(&sub)?, model });
+        }
+        if entries.len() < 2 {
+            return Err(BizClawError::Config(format!(
+                "race provider needs at least two providers, got '{list}'"
+            )));
+        }
+        Ok(Self::new(entries))
+    }
+}
+
+/// The environment variables `provider` reads its key from, joined with
+/// "or", when none of them is set. Local providers need no key.
+fn missing_key(provider: &str, env: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let vars: &[&str] = match provider {
+        "openai" | "openrouter" => &["OPENAI_API_KEY", "OPENROUTER_API_KEY"],
+        "anthropic" => &["ANTHROPIC_API_KEY"],
+        "gemini" | "google" => &["GEMINI_API_KEY", "GOOGLE_API_KEY"],
+        "deepseek" => &["DEEPSEEK_API_KEY"],
+        "groq" => &["GROQ_API_KEY"],
+        _ => return None,
+    };
+    let set = vars.iter().any(|var| env(var).is_some_and(|key| !key.is_empty()));
+    (!set).then(|| vars.join(" or "))
+}
+
+#[async_trait]
+impl Provider for RacingProvider {
+    fn name(&self) -> &str { "race" }
+
+    async fn chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDefinition],
+        params: &GenerateParams,
+    ) -> Result<ProviderResponse> {
+        // Tool calls only make sense from providers that forward tool definitions.
+        let racers: Vec<&RaceEntry> = self.entries.iter()
+            .filter(|e| tools.is_empty() || e.provider.supports_tools())
+            .collect();
+        if racers.is_empty() {
+            return Err(BizClawError::Provider("No raced provider supports tool calls".into()));
+        }
+
+        let token = CancellationToken::new();
+        let start = Instant::now();
+
+        let mut race: FuturesUnordered<_> = racers.iter().map(|entry| {
+            let token = token.clone();
+            let mut params = params.clone();
+            if let Some(model) = &entry.model {
+                params.model = model.clone();
+            }
+            async move {
+                let outcome = tokio::select! {
+                    _ = token.cancelled() => Outcome::Cancelled,
+                    result = entry.provider.chat(messages, tools, &params) => match result {
+                        Ok(resp) => Outcome::Won(resp),
+                        Err(e) => Outcome::Failed(e),
+                    },
+                };
+                (entry.provider.name().to_string(), outcome, start.elapsed().as_millis() as u64)
+            }
+        }).collect();
+
+        let mut winner: Option<(String, ProviderResponse, u64)> = None;
+        let mut latencies = serde_json::Map::new();
+        let mut errors = serde_json::Map::new();
+        let mut cancelled = Vec::new();
+
+        while let Some((name, outcome, elapsed_ms)) = race.next().await {
+            latencies.insert(name.clone(), elapsed_ms.into());
+            match outcome {
+                Outcome::Won(resp) if winner.is_none() => {
+                    tracing::debug!("race: {name} won in {elapsed_ms}ms");
+                    winner = Some((name, resp, elapsed_ms));
+                    token.cancel();
+                }
+                // A second success that slipped in before cancellation took effect.
+                Outcome::Won(_) | Outcome::Cancelled => cancelled.push(serde_json::Value::from(name)),
+                Outcome::Failed(e) => {
+                    tracing::warn!("race: {name} failed after {elapsed_ms}ms: {e}");
+                    errors.insert(name, e.to_string().into());
+                }
+            }
+        }
+
+        let Some((name, mut resp, latency_ms)) = winner else {
+            return Err(BizClawError::Provider(format!(
+                "All raced providers failed: {}",
+                serde_json::Value::Object(errors)
+            )));
+        };
+
+        let race_meta = serde_json::json!({
+            "winner": name,
+            "winner_latency_ms": latency_ms,
+            "latencies_ms": latencies,
+            "cancelled": cancelled,
+            "errors": errors,
+        });
+        match resp.metadata.as_mut().and_then(|m| m.as_object_mut()) {
+            Some(meta) => { meta.insert("race".into(), race_meta); }
+            None => resp.metadata = Some(serde_json::json!({ "race": race_meta })),
+        }
+        Ok(resp)
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>> {
+        let mut models = Vec::new();
+        for entry in &self.entries {
+            if let Ok(list) = entry.provider.list_models().await {
+                models.extend(list);
+            }
+        }
+        Ok(models)
+    }
+
+    async fn health_check(&self) -> Result<bool> {
+        for entry in &self.entries {
+            if entry.provider.health_check().await.unwrap_or(false) {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn supports_tools(&self) -> bool {
+        self.entries.iter().any(|e| e.provider.supports_tools())
+    }
+
+    fn supports_vision(&self) -> bool {
+        self.entries.iter().all(|e| e.provider.supports_vision())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::Duration;
+
+    struct MockProvider {
+        name: &'static str,
+        delay: Duration,
+        fail: bool,
+        tools: bool,
+        finished: Arc<AtomicBool>,
+    }
+
+    impl MockProvider {
+        fn new(name: &'static str, delay_ms: u64) -> Self {
+            Self {
+                name,
+                delay: Duration::from_millis(delay_ms),
+                fail: false,
+                tools: false,
+                finished: Arc::new(AtomicBool::new(false)),
+            }
+        }
+
+        fn entry(self) -> RaceEntry {
+            RaceEntry { provider: Box::new(self), model: None }
+        }
+    }
+
+    #[async_trait]
+    impl Provider for MockProvider {
+        fn name(&self) -> &str { self.name }
+
+        async fn chat(&self, _: &[Message], _: &[ToolDefinition], _: &GenerateParams) -> Result<ProviderResponse> {
+            tokio::time::sleep(self.delay).await;
+            self.finished.store(true, Ordering::SeqCst);
+            if self.fail {
+                return Err(BizClawError::Provider(format!("{} failed", self.name)));
+            }
+            Ok(ProviderResponse::text(format!("from {}", self.name)))
+        }
+
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> { Ok(vec![]) }
+        async fn health_check(&self) -> Result<bool> { Ok(true) }
+        fn supports_tools(&self) -> bool { self.tools }
+    }
+
+    #[tokio::test]
+    async fn test_fastest_wins_and_slow_is_cancelled() {
+        let slow = MockProvider::new("slow", 400);
+        let slow_finished = slow.finished.clone();
+        let racer = RacingProvider::new(vec![slow.entry(), MockProvider::new("fast", 10).entry()]);
+
+        let started = Instant::now();
+        let resp = racer.chat(&[Message::user("hi")], &[], &GenerateParams::default()).await.unwrap();
+        assert!(started.elapsed() < Duration::from_millis(300));
+        assert_eq!(resp.content.as_deref(), Some("from fast"));
+
+        let race = &resp.metadata.unwrap()["race"];
+        assert_eq!(race["winner"], "fast");
+        assert_eq!(race["cancelled"][0], "slow");
+        assert!(race["latencies_ms"]["slow"].is_u64());
+
+        // The losing call was dropped, so it never completes.
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(!slow_finished.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_failure_falls_through_to_next() {
+        let broken = MockProvider { fail: true, ..MockProvider::new("broken", 5) };
+        let racer = RacingProvider::new(vec![broken.entry(), MockProvider::new("ok", 50).entry()]);
+
+        let resp = racer.chat(&[Message::user("hi")], &[], &GenerateParams::default()).await.unwrap();
+        assert_eq!(resp.content.as_deref(), Some("from ok"));
+        assert!(resp.metadata.unwrap()["race"]["errors"]["broken"].is_string());
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_only_race_tool_capable_providers() {
+        let racer = RacingProvider::new(vec![
+            MockProvider::new("no_tools_fast", 5).entry(),
+            MockProvider { tools: true, ..MockProvider::new("tools_slow", 50) }.entry(),
+        ]);
+        let tool = ToolDefinition { name: "t".into(), description: String::new(), parameters: serde_json::json!({}) };
+
+        let resp = racer.chat(&[Message::user("hi")], &[tool], &GenerateParams::default()).await.unwrap();
+        assert_eq!(resp.content.as_deref(), Some("from tools_slow"));
+    }
+
+    #[test]
+    fn test_spec_requires_two_providers() {
+        let config = BizClawConfig::default();
+        assert!(RacingProvider::from_spec(&config, "race:ollama").is_err());
+        assert!(RacingProvider::from_spec(&config, "race:ollama,llamacpp/qwen2.5").is_ok());
+    }
+
+    #[test]
+    fn test_racers_need_their_own_keys() {
+        let env = |var: &str| (var == "GOOGLE_API_KEY").then(|| "g-key".to_string());
+        assert_eq!(missing_key("groq", env).as_deref(), Some("GROQ_API_KEY"));
+        assert_eq!(missing_key("openai", env).as_deref(), Some("OPENAI_API_KEY or OPENROUTER_API_KEY"));
+        assert_eq!(missing_key("gemini", env), None);
+        assert_eq!(missing_key("ollama", env), None);
+
+        if std::env::var("GROQ_API_KEY").is_err() {
+            let config = BizClawConfig { api_key: "sk-shared".into(), ..Default::default() };
+            let err = RacingProvider::from_spec(&config, "race:groq,ollama").err().unwrap();
+            assert!(err.to_string().contains("GROQ_API_KEY"), "{err}");
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Streaming chat responses (`"stream": true`): server-sent events from
+//! OpenAI-compatible `/chat/completions`, or newline-delimited JSON from
+//! Ollama's `/api/chat`.
+
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::provider::TokenStream;
+use futures::StreamExt;
+use std::collections::VecDeque;
+
+/// Wire format of a streaming response.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Format {
+    OpenAiSse,
+    OllamaNdjson,
+}
+
+/// Splits the response into lines and pulls out the content deltas.
+struct Decoder {
+    format: Format,
+    buf: Vec<u8>,
+    done: bool,
+}
+
+impl Decoder {
+    fn new(format: Format) -> Self {
+        Self { format, buf: Vec::new(), done: false }
+    }
+
+    /// Feed bytes as received; returns the deltas from complete lines.
+    fn push(&mut self, bytes: &[u8]) -> Result<Vec<String>> {
+        self.buf.extend_from_slice(bytes);
+        let mut tokens = Vec::new();
+        while let Some(pos) = self.buf.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.buf.drain(..=pos).collect();
+            if self.done {
+                continue;
+            }
+            let line = String::from_utf8_lossy(&line);
+            if let Some(token) = self.decode_line(line.trim())? {
+                tokens.push(token);
+            }
+        }
+        Ok(tokens)
+    }
+
+    fn decode_line(&mut self, line: &str) -> Result<Option<String>> {
+        let data = match self.format {
+            Format::OpenAiSse => {
+                let Some(data) = line.strip_prefix("data:") else { return Ok(None) };
+                data.trim()
+            }
+            Format::OllamaNdjson => line,
+        };
+        if data.is_empty() {
+            return Ok(None);
+        }
+        if data == "[DONE]" {
+            self.done = true;
+            return Ok(None);
+        }
+        let event: serde_json::Value = serde_json::from_str(data)
+            .map_err(|e| BizClawError::Provider(format!("Invalid stream event: {e}")))?;
+        if let Some(error) = event.get("error") {
+            let message = error["message"].as_str().or(error.as_str()).map(String::from)
+                .unwrap_or_else(|| error.to_string());
+            return Err(BizClawError::Provider(format!("Stream error: {message}")));
+        }
+        let delta = match self.format {
+            Format::OpenAiSse => &event["choices"][0]["delta"]["content"],
+            Format::OllamaNdjson => {
+                self.done = event["done"].as_bool().unwrap_or(false);
+                &event["message"]["content"]
+            }
+        };
+        Ok(delta.as_str().filter(|d| !d.is_empty()).map(String::from))
+    }
+}
+
+/// Content deltas of a successful streaming response.
+pub(crate) fn token_stream(response: reqwest::Response, format: Format) -> TokenStream {
+    let state = (response.bytes_stream(), Decoder::new(format), VecDeque::new());
+    Box::pin(futures::stream::unfold(state, |(mut bytes, mut decoder, mut pending)| async move {
+        loop {
+            if let Some(token) = pending.pop_front() {
+                return Some((Ok(token), (bytes, decoder, pending)));
+            }
+            if decoder.done {
+                return None;
+            }
+            let result = match bytes.next().await {
+                Some(Ok(chunk)) => decoder.push(&chunk),
+                Some(Err(e)) => Err(BizClawError::Http(e.to_string())),
+                None => return None,
+            };
+            match result {
+                Ok(tokens) => pending.extend(tokens),
+                Err(e) => {
+                    decoder.done = true;
+                    return Some((Err(e), (bytes, decoder, pending)));
+                }
+            }
+        }
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decoder_handles_split_events() {
+        let mut decoder = Decoder::new(Format::OpenAiSse);
+        let stream = concat!(
+            ": keep-alive\n\n",
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Xin \"}}]}\r\n\r\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"chào\"}}]}\n\n",
+            "data: [DONE]\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"ignored\"}}]}\n\n",
+        ).as_bytes();
+        // Feed in small pieces, splitting lines and the multi-byte "à".
+        let mut tokens = Vec::new();
+        for chunk in stream.chunks(7) {
+            tokens.extend(decoder.push(chunk).unwrap());
+        }
+        assert_eq!(tokens, vec!["Xin ", "chào"]);
+        assert!(decoder.done);
+
+        let mut decoder = Decoder::new(Format::OpenAiSse);
+        let err = decoder.push(b"data: {\"error\":{\"message\":\"overloaded\"}}\n").unwrap_err();
+        assert!(err.to_string().contains("overloaded"));
+    }
+
+    #[test]
+    fn test_decoder_ollama_ndjson() {
+        let mut decoder = Decoder::new(Format::OllamaNdjson);
+        let tokens = decoder.push(concat!(
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"lo\"},\"done\":false}\n",
+            "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"eval_count\":2}\n",
+        ).as_bytes()).unwrap();
+        assert_eq!(tokens, vec!["Hel", "lo"]);
+        assert!(decoder.done);
+
+        let mut decoder = Decoder::new(Format::OllamaNdjson);
+        let err = decoder.push(b"{\"error\":\"model 'x' not found\"}\n").unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+}
@@ -4,59 +4,181 @@
 //! can be accessed by the agent.
 
 use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
 use bizclaw_core::config::AutonomyConfig;
 
+/// A refused command or path, in a form the agent can relay to the user.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Denial {
+    /// Which rule refused it: "allowed_commands", "forbidden_paths", "workspace_only", "unparseable".
+    pub rule: &'static str,
+    /// The command or path that was refused.
+    pub subject: String,
+    pub reason: String,
+}
+
+impl Denial {
+    fn new(rule: &'static str, subject: impl Into<String>, reason: impl Into<String>) -> Self {
+        Self { rule, subject: subject.into(), reason: reason.into() }
+    }
+
+    /// Structured form returned to the agent as tool output.
+    pub fn to_json(&self, tool: &str) -> serde_json::Value {
+        serde_json::json!({
+            "error": "permission_denied",
+            "tool": tool,
+            "rule": self.rule,
+            "subject": self.subject,
+            "reason": self.reason,
+        })
+    }
+
+    /// Record the denial in the audit log (`bizclaw::audit` tracing target).
+    pub fn audit(&self, tool: &str) {
+        tracing::warn!(
+            target: "bizclaw::audit",
+            tool,
+            rule = self.rule,
+            subject = %self.subject,
+            "denied: {}",
+            self.reason
+        );
+    }
+}
+
+impl std::fmt::Display for Denial {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({}: {})", self.reason, self.rule, self.subject)
+    }
+}
+
 /// Manages command and path allowlists for security enforcement.
+#[derive(Debug, Clone)]
 pub struct Allowlist {
     allowed_commands: HashSet<String>,
     forbidden_paths: Vec<String>,
     workspace_only: bool,
+    /// Canonical workspace root; relative paths resolve against it.
+    workspace: PathBuf,
 }
 
 impl Allowlist {
     /// Create a new allowlist from autonomy configuration.
+    ///
+    /// The workspace is `autonomy.workspace`, or the current directory.
     pub fn new(config: &AutonomyConfig) -> Self {
+        let workspace = config.workspace_dir();
         Self {
             allowed_commands: config.allowed_commands.iter().cloned().collect(),
             forbidden_paths: config.forbidden_paths.clone(),
             workspace_only: config.workspace_only,
+            workspace: workspace.canonicalize().unwrap_or(workspace),
         }
     }
 
-    /// Check if a command is allowed to execute.
-    pub fn is_command_allowed(&self, command: &str) -> bool {
-        let cmd_base = command.split_whitespace().next().unwrap_or("");
-        // Also check the basename (in case full path is given)
-        let cmd_name = std::path::Path::new(cmd_base)
-            .file_name()
-            .and_then(|f| f.to_str())
-            .unwrap_or(cmd_base);
+    /// Use a different workspace root.
+    pub fn with_workspace(mut self, workspace: impl Into<PathBuf>) -> Self {
+        let workspace = workspace.into();
+        self.workspace = workspace.canonicalize().unwrap_or(workspace);
+        self
+    }
 
-        self.allowed_commands.contains(cmd_name) || self.allowed_commands.contains(cmd_base)
+    pub fn workspace(&self) -> &Path {
+        &self.workspace
     }
 
-    /// Check if a path is allowed to access.
-    pub fn is_path_allowed(&self, path: &str) -> bool {
-        let expanded = shellexpand::tilde(path).to_string();
-        let canonical = std::path::Path::new(&expanded);
+    /// `path` relative to the workspace, with `~`, symlinks and `..` resolved
+    /// but no rules applied (for a path a human approved).
+    pub fn resolve(&self, path: &str) -> PathBuf {
+        let expanded = PathBuf::from(shellexpand::tilde(path).to_string());
+        resolve_path(&self.workspace.join(expanded))
+    }
 
-        // Check against forbidden paths
-        for forbidden in &self.forbidden_paths {
-            let exp_forbidden = shellexpand::tilde(forbidden).to_string();
-            if expanded.starts_with(&exp_forbidden) {
-                return false;
+    /// Check every executable a shell command line would start.
+    ///
+    /// Each one must be allowed by name, and — after following symlinks — must
+    /// be the same program as an allowed command (so a `./ls` symlink to `rm`
+    /// is refused).
+    pub fn check_command(&self, command: &str) -> Result<(), Denial> {
+        let executables = crate::command::executables(command)
+            .map_err(|e| Denial::new("unparseable", command, e))?;
+        for exe in executables {
+            if !self.is_executable_allowed(&exe) {
+                return Err(Denial::new(
+                    "allowed_commands",
+                    exe.clone(),
+                    format!("'{exe}' is not in autonomy.allowed_commands"),
+                ));
             }
         }
+        Ok(())
+    }
+
+    fn is_executable_allowed(&self, exe: &str) -> bool {
+        let name = Path::new(exe).file_name().and_then(|f| f.to_str()).unwrap_or(exe);
+        if !self.allowed_commands.contains(name) && !self.allowed_commands.contains(exe) {
+            return false;
+        }
+        // Nothing to resolve (not installed, or a shell builtin like `cd`).
+        let Some(target) = self.resolve_executable(exe) else {
+            return true;
+        };
+        let target_name = target.file_name().and_then(|f| f.to_str()).unwrap_or("");
+        self.allowed_commands.contains(target_name)
+            || self.allowed_commands.iter().any(|a| self.resolve_executable(a).as_ref() == Some(&target))
+    }
+
+    /// Canonical path of the program `exe` would run (PATH lookup for bare names).
+    fn resolve_executable(&self, exe: &str) -> Option<PathBuf> {
+        let expanded = shellexpand::tilde(exe).to_string();
+        if expanded.contains('/') {
+            return self.workspace.join(expanded).canonicalize().ok();
+        }
+        let path = std::env::var_os("PATH")?;
+        std::env::split_paths(&path)
+            .map(|dir| dir.join(&expanded))
+            .find(|p| p.is_file())
+            .and_then(|p| p.canonicalize().ok())
+    }
 
-        // If workspace_only, restrict to workspace directory
-        if self.workspace_only {
-            if let Ok(cwd) = std::env::current_dir() {
-                return canonical.starts_with(&cwd)
-                    || expanded.starts_with(&cwd.to_string_lossy().to_string());
+    /// Resolve `path` (following symlinks and `..`) and check it against the
+    /// forbidden paths and, with `workspace_only`, the workspace root.
+    ///
+    /// Paths that don't exist yet resolve through their deepest existing
+    /// ancestor. Returns the resolved path to operate on.
+    pub fn check_path(&self, path: &str) -> Result<PathBuf, Denial> {
+        let resolved = self.resolve(path);
+
+        for forbidden in &self.forbidden_paths {
+            let forbidden = PathBuf::from(shellexpand::tilde(forbidden).to_string());
+            let forbidden_resolved = resolve_path(&forbidden);
+            if resolved.starts_with(&forbidden) || resolved.starts_with(&forbidden_resolved) {
+                return Err(Denial::new(
+                    "forbidden_paths",
+                    path,
+                    format!("'{}' is under forbidden path {}", resolved.display(), forbidden.display()),
+                ));
             }
         }
 
-        true
+        if self.workspace_only && !resolved.starts_with(&self.workspace) {
+            return Err(Denial::new(
+                "workspace_only",
+                path,
+                format!("'{}' is outside the workspace {}", resolved.display(), self.workspace.display()),
+            ));
+        }
+        Ok(resolved)
+    }
+
+    /// Check if a command is allowed to execute.
+    pub fn is_command_allowed(&self, command: &str) -> bool {
+        self.check_command(command).is_ok()
+    }
+
+    /// Check if a path is allowed to access.
+    pub fn is_path_allowed(&self, path: &str) -> bool {
+        self.check_path(path).is_ok()
     }
 
     /// Add a command to the allowlist.
@@ -74,3 +196,95 @@ impl Allowlist {
         self.forbidden_paths.push(path.to_string());
     }
 }
+
+/// Canonicalize the deepest existing ancestor of `path`, then append the rest
+/// lexically (folding `.`/`..`).
+fn resolve_path(path: &Path) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    for split in (0..=components.len()).rev() {
+        let prefix: PathBuf = components[..split].iter().collect();
+        let Ok(mut resolved) = prefix.canonicalize() else { continue };
+        for component in &components[split..] {
+            match component {
+                Component::ParentDir => { resolved.pop(); }
+                Component::CurDir => {}
+                other => resolved.push(other),
+            }
+        }
+        return resolved;
+    }
+    path.to_path_buf()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowlist(workspace: &Path) -> Allowlist {
+        let config = AutonomyConfig {
+            level: "supervised".into(),
+            workspace_only: true,
+            allowed_commands: vec!["ls".into(), "cat".into(), "git".into()],
+            forbidden_paths: vec![workspace.join("secrets").to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        Allowlist::new(&config).with_workspace(workspace)
+    }
+
+    fn temp_workspace() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bizclaw-allow-{}", std::process::id()))
+            .join(format!("{:?}", std::thread::current().id()).replace(['(', ')'], ""));
+        std::fs::create_dir_all(dir.join("secrets")).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    #[test]
+    fn test_command_allowlist() {
+        let list = allowlist(&temp_workspace());
+        assert!(list.check_command("ls -la | cat").is_ok());
+        assert_eq!(list.check_command("ls; rm -rf /").unwrap_err().subject, "rm");
+        // Wrapping in another shell doesn't hide the inner command.
+        assert_eq!(list.check_command("bash -c 'ls'").unwrap_err().subject, "bash");
+        assert_eq!(list.check_command("env rm x").unwrap_err().subject, "env");
+        assert_eq!(list.check_command("ls $(rm x)").unwrap_err().rule, "unparseable");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_symlink_alias() {
+        let ws = temp_workspace();
+        let Some(rm) = ["/bin/rm", "/usr/bin/rm"].iter().find(|p| Path::new(p).exists()) else { return };
+        let link = ws.join("ls");
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(rm, &link).unwrap();
+
+        let list = allowlist(&ws);
+        let denial = list.check_command("./ls -rf /").unwrap_err();
+        assert_eq!(denial.rule, "allowed_commands");
+        assert!(list.check_command("ls").is_ok());
+    }
+
+    #[test]
+    fn test_path_traversal() {
+        let ws = temp_workspace();
+        let list = allowlist(&ws);
+        assert_eq!(list.check_path("notes.txt").unwrap(), ws.join("notes.txt"));
+        assert_eq!(list.check_path("../../etc/passwd").unwrap_err().rule, "workspace_only");
+        assert_eq!(list.check_path("sub/../../outside").unwrap_err().rule, "workspace_only");
+        assert_eq!(list.check_path("secrets/key.pem").unwrap_err().rule, "forbidden_paths");
+        assert_eq!(list.check_path("./x/../secrets/key.pem").unwrap_err().rule, "forbidden_paths");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_path_symlink_escape() {
+        let ws = temp_workspace();
+        let link = ws.join("escape");
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink("/etc", &link).unwrap();
+
+        let list = allowlist(&ws);
+        assert_eq!(list.check_path("escape/passwd").unwrap_err().rule, "workspace_only");
+        assert_eq!(list.check_path("escape/new-file").unwrap_err().rule, "workspace_only");
+    }
+}
@@ -0,0 +1,285 @@
+//! Human-in-the-loop approval for tool calls.
+//!
+//! At autonomy level "approval", a tool call the allowlist would refuse is not
+//! denied outright: `AutonomyPolicy` flags it, and the `ApprovalBroker` holds
+//! it as a pending request until someone approves or denies it (gateway API,
+//! Telegram prompt) or the timeout passes, which counts as a denial.
+//!
+//! The shell and file tools ask through an [`Approver`] when their own
+//! allowlist refuses a call; the registry's gate covers tools built without
+//! one.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use bizclaw_core::config::AutonomyConfig;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tokio::sync::{broadcast, oneshot};
+
+use crate::allowlist::{Allowlist, Denial};
+
+/// What the policy says about one tool call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyDecision {
+    Allow,
+    /// Ask a human first; carries why the allowlist refused it.
+    RequireApproval(String),
+}
+
+/// Decides which tool calls need a human decision.
+///
+/// Shell commands outside `allowed_commands` and file paths outside the
+/// workspace (or under `forbidden_paths`) require approval; everything else
+/// is allowed.
+#[derive(Debug, Clone)]
+pub struct AutonomyPolicy {
+    allowlist: Allowlist,
+}
+
+impl AutonomyPolicy {
+    pub fn new(config: &AutonomyConfig) -> Self {
+        Self { allowlist: Allowlist::new(config) }
+    }
+
+    /// Use an explicit allowlist (e.g. one with a custom workspace).
+    pub fn with_allowlist(allowlist: Allowlist) -> Self {
+        Self { allowlist }
+    }
+
+    pub fn evaluate(&self, tool: &str, arguments: &str) -> PolicyDecision {
+        let args: serde_json::Value = serde_json::from_str(arguments).unwrap_or_default();
+        let checked = match tool {
+            "shell" => {
+                let command = args["command"].as_str().unwrap_or("");
+                self.allowlist.check_command(command).and_then(|()| match args["workdir"].as_str() {
+                    Some(dir) => self.allowlist.check_path(dir).map(|_| ()),
+                    None => Ok(()),
+                })
+            }
+            "file" => self.allowlist.check_path(args["path"].as_str().unwrap_or("")).map(|_| ()),
+            _ => Ok(()),
+        };
+        match checked {
+            Ok(()) => PolicyDecision::Allow,
+            Err(denial) => PolicyDecision::RequireApproval(denial.to_string()),
+        }
+    }
+}
+
+/// A tool call waiting for a human decision.
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingApproval {
+    pub id: String,
+    pub tool: String,
+    pub arguments: String,
+    pub reason: String,
+    pub requested_at: DateTime<Utc>,
+}
+
+/// How a pending approval ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ApprovalOutcome {
+    Approved { approver: String },
+    Denied { approver: String },
+    TimedOut,
+}
+
+/// Holds pending approvals and hands decisions back to the waiting tool call.
+pub struct ApprovalBroker {
+    pending: Mutex<HashMap<String, (PendingApproval, oneshot::Sender<ApprovalOutcome>)>>,
+    events: broadcast::Sender<PendingApproval>,
+}
+
+impl ApprovalBroker {
+    pub fn new() -> Self {
+        Self {
+            pending: Mutex::new(HashMap::new()),
+            events: broadcast::channel(64).0,
+        }
+    }
+
+    /// Process-wide broker shared by the tool registry and the gateway.
+    pub fn global() -> Arc<Self> {
+        static GLOBAL: OnceLock<Arc<ApprovalBroker>> = OnceLock::new();
+        GLOBAL.get_or_init(|| Arc::new(Self::new())).clone()
+    }
+
+    /// Publish a pending approval and wait up to `timeout` for a decision.
+    pub async fn request(&self, tool: &str, arguments: &str, reason: &str, timeout: Duration) -> ApprovalOutcome {
+        let pending = PendingApproval {
+            id: uuid::Uuid::new_v4().to_string(),
+            tool: tool.to_string(),
+            arguments: arguments.to_string(),
+            reason: reason.to_string(),
+            requested_at: Utc::now(),
+        };
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(pending.id.clone(), (pending.clone(), tx));
+        tracing::info!(target: "bizclaw::audit", id = %pending.id, tool, "approval requested: {reason}");
+        let _ = self.events.send(pending.clone());
+
+        // Drop the entry if the caller gives up (timeout or cancelled tool call).
+        let _cleanup = RemoveOnDrop { broker: self, id: &pending.id };
+        match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(outcome)) => outcome,
+            _ => {
+                tracing::warn!(
+                    target: "bizclaw::audit",
+                    id = %pending.id,
+                    tool,
+                    approver = "timeout",
+                    "approval denied: no decision within {}s",
+                    timeout.as_secs()
+                );
+                ApprovalOutcome::TimedOut
+            }
+        }
+    }
+
+    /// Pending approvals, oldest first.
+    pub fn pending(&self) -> Vec<PendingApproval> {
+        let mut list: Vec<PendingApproval> = self.pending.lock().unwrap()
+            .values()
+            .map(|(p, _)| p.clone())
+            .collect();
+        list.sort_by_key(|p| p.requested_at);
+        list
+    }
+
+    /// Approve or deny a pending request. Returns `false` if `id` is unknown
+    /// or already resolved.
+    pub fn resolve(&self, id: &str, approve: bool, approver: &str) -> bool {
+        let Some((pending, tx)) = self.pending.lock().unwrap().remove(id) else {
+            return false;
+        };
+        tracing::info!(
+            target: "bizclaw::audit",
+            id,
+            tool = %pending.tool,
+            approver,
+            "approval {}",
+            if approve { "approved" } else { "denied" }
+        );
+        let approver = approver.to_string();
+        let outcome = if approve {
+            ApprovalOutcome::Approved { approver }
+        } else {
+            ApprovalOutcome::Denied { approver }
+        };
+        tx.send(outcome).is_ok()
+    }
+
+    /// New pending approvals as they are published (for chat prompts).
+    pub fn subscribe(&self) -> broadcast::Receiver<PendingApproval> {
+        self.events.subscribe()
+    }
+}
+
+impl Default for ApprovalBroker {
+    fn default() -> Self { Self::new() }
+}
+
+/// Asks a human about a call a tool's own allowlist refused.
+#[derive(Clone)]
+pub struct Approver {
+    broker: Arc<ApprovalBroker>,
+    timeout: Duration,
+}
+
+impl Approver {
+    pub fn new(broker: Arc<ApprovalBroker>, timeout: Duration) -> Self {
+        Self { broker, timeout }
+    }
+
+    /// The global broker with `approval_timeout_secs`, at level "approval" only.
+    pub fn from_autonomy(config: &AutonomyConfig) -> Option<Self> {
+        (config.level == "approval")
+            .then(|| Self::new(ApprovalBroker::global(), Duration::from_secs(config.approval_timeout_secs)))
+    }
+
+    /// Wait for a decision on a refused call. `Err` says why it was not
+    /// approved ("denied by ...", "no decision before timeout").
+    pub async fn approve(&self, tool: &str, arguments: &str, denial: &Denial) -> Result<(), String> {
+        match self.broker.request(tool, arguments, &denial.to_string(), self.timeout).await {
+            ApprovalOutcome::Approved { .. } => Ok(()),
+            ApprovalOutcome::Denied { approver } => Err(format!("denied by {approver}")),
+            ApprovalOutcome::TimedOut => Err("no decision before timeout".to_string()),
+        }
+    }
+
+    /// The `approval_denied` failure a tool returns when `approve` fails.
+    pub fn denied_json(tool: &str, denial: &Denial, decision: &str) -> serde_json::Value {
+        serde_json::json!({
+            "error": "approval_denied",
+            "tool": tool,
+            "reason": denial.to_string(),
+            "decision": decision,
+        })
+    }
+}
+
+struct RemoveOnDrop<'a> {
+    broker: &'a ApprovalBroker,
+    id: &'a str,
+}
+
+impl Drop for RemoveOnDrop<'_> {
+    fn drop(&mut self) {
+        self.broker.pending.lock().unwrap().remove(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_requires_approval_outside_allowlist() {
+        let ws = std::env::temp_dir();
+        let config = AutonomyConfig {
+            level: "approval".into(),
+            allowed_commands: vec!["ls".into()],
+            forbidden_paths: vec![],
+            ..Default::default()
+        };
+        let policy = AutonomyPolicy::with_allowlist(Allowlist::new(&config).with_workspace(&ws));
+
+        assert_eq!(policy.evaluate("shell", r#"{"command":"ls -la"}"#), PolicyDecision::Allow);
+        assert!(matches!(policy.evaluate("shell", r#"{"command":"rm -rf x"}"#), PolicyDecision::RequireApproval(_)));
+        assert_eq!(policy.evaluate("file", r#"{"action":"write","path":"notes.txt"}"#), PolicyDecision::Allow);
+        assert!(matches!(
+            policy.evaluate("file", r#"{"action":"write","path":"../../outside.txt"}"#),
+            PolicyDecision::RequireApproval(_)
+        ));
+        assert_eq!(policy.evaluate("web_search", r#"{"query":"x"}"#), PolicyDecision::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_broker_resolve() {
+        let broker = Arc::new(ApprovalBroker::new());
+        let mut events = broker.subscribe();
+        let waiter = {
+            let broker = broker.clone();
+            tokio::spawn(async move {
+                broker.request("shell", r#"{"command":"rm x"}"#, "not allowed", Duration::from_secs(5)).await
+            })
+        };
+
+        let pending = events.recv().await.unwrap();
+        assert_eq!(broker.pending().len(), 1);
+        assert!(broker.resolve(&pending.id, true, "tester"));
+        assert!(!broker.resolve(&pending.id, false, "tester"));
+        assert_eq!(waiter.await.unwrap(), ApprovalOutcome::Approved { approver: "tester".into() });
+        assert!(broker.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_broker_timeout_denies() {
+        let broker = ApprovalBroker::new();
+        let outcome = broker.request("file", "{}", "outside workspace", Duration::from_millis(20)).await;
+        assert_eq!(outcome, ApprovalOutcome::TimedOut);
+        assert!(broker.pending().is_empty());
+    }
+}
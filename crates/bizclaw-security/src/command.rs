@@ -0,0 +1,216 @@
+//! Shell command-line inspection.
+//!
+//! Finds every executable a `sh -c` command line would start, so the
+//! allowlist can vet each one: pipelines and lists (`a | b; c && d`),
+//! wrappers (`env`, `nohup`, `timeout 5 ...`), and nested shells
+//! (`bash -c "..."`). Constructs that can't be vetted statically —
+//! command substitution and process substitution — are rejected outright.
+
+/// Programs that run their arguments as another command.
+const WRAPPERS: &[&str] = &[
+    "env", "nohup", "time", "exec", "command", "builtin", "nice", "stdbuf",
+    "timeout", "sudo", "doas", "xargs", "setsid", "strace", "watch",
+];
+
+/// Wrappers whose first positional argument is a value, not the command.
+const WRAPPERS_WITH_VALUE: &[&str] = &["timeout"];
+
+/// Shells whose `-c` argument is itself a command line.
+const SHELLS: &[&str] = &["sh", "bash", "zsh", "dash", "ksh", "fish", "ash"];
+
+/// Nested `sh -c` depth before giving up.
+const MAX_DEPTH: usize = 4;
+
+/// Every executable name (as written) that `command` would run.
+///
+/// Errors describe why the command line can't be inspected.
+pub fn executables(command: &str) -> Result<Vec<String>, String> {
+    let mut out = Vec::new();
+    collect(command, 0, &mut out)?;
+    Ok(out)
+}
+
+fn collect(command: &str, depth: usize, out: &mut Vec<String>) -> Result<(), String> {
+    if depth > MAX_DEPTH {
+        return Err("too many nested shells".into());
+    }
+    for words in split_simple_commands(command)? {
+        let mut words = words.into_iter().filter(|w| !is_redirection(w)).peekable();
+
+        // Leading `VAR=value` assignments.
+        while words.peek().is_some_and(|w| is_assignment(w)) {
+            words.next();
+        }
+
+        while let Some(word) = words.next() {
+            let name = basename(&word).to_string();
+            out.push(word.clone());
+
+            if SHELLS.contains(&name.as_str()) {
+                // `bash -c 'script'`: the script is the real command.
+                let rest: Vec<String> = words.by_ref().collect();
+                if let Some(i) = rest.iter().position(|w| w == "-c" || (w.starts_with('-') && !w.starts_with("--") && w.contains('c')))
+                    && let Some(script) = rest.get(i + 1)
+                {
+                    collect(script, depth + 1, out)?;
+                }
+                break;
+            }
+            if !WRAPPERS.contains(&name.as_str()) {
+                break;
+            }
+
+            // Skip the wrapper's own options/values up to the wrapped command.
+            let mut expect_value = WRAPPERS_WITH_VALUE.contains(&name.as_str());
+            while let Some(next) = words.peek() {
+                if next.starts_with('-') || is_assignment(next) {
+                    // `nice -n 5`: the option value follows.
+                    let needs_arg = next == "-n" || next == "-s" || next == "-k";
+                    words.next();
+                    if needs_arg {
+                        words.next();
+                    }
+                } else if expect_value {
+                    expect_value = false;
+                    words.next();
+                } else {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tokenise a command line and split it into simple commands on
+/// `;`, `&`, `|`, newlines, and parentheses.
+fn split_simple_commands(command: &str) -> Result<Vec<Vec<String>>, String> {
+    let mut commands = Vec::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = command.chars().peekable();
+
+    let flush_word = |word: &mut String, in_word: &mut bool, words: &mut Vec<String>| {
+        if *in_word {
+            words.push(std::mem::take(word));
+            *in_word = false;
+        }
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(ch) => word.push(ch),
+                        None => return Err("unterminated single quote".into()),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => {
+                            if let Some(ch) = chars.next() {
+                                word.push(ch);
+                            }
+                        }
+                        Some('`') => return Err("command substitution is not allowed".into()),
+                        Some('$') if chars.peek() == Some(&'(') => {
+                            return Err("command substitution is not allowed".into());
+                        }
+                        Some(ch) => word.push(ch),
+                        None => return Err("unterminated double quote".into()),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                if let Some(ch) = chars.next() {
+                    word.push(ch);
+                }
+            }
+            '`' => return Err("command substitution is not allowed".into()),
+            '$' if chars.peek() == Some(&'(') => {
+                return Err("command substitution is not allowed".into());
+            }
+            '<' | '>' if chars.peek() == Some(&'(') => {
+                return Err("process substitution is not allowed".into());
+            }
+            ';' | '&' | '|' | '\n' | '(' | ')' => {
+                // `2>&1` / `&>file` are redirections, not separators.
+                if c == '&' && (word.ends_with('>') || word.ends_with('<') || chars.peek() == Some(&'>')) {
+                    in_word = true;
+                    word.push(c);
+                    continue;
+                }
+                flush_word(&mut word, &mut in_word, &mut words);
+                if !words.is_empty() {
+                    commands.push(std::mem::take(&mut words));
+                }
+            }
+            c if c.is_whitespace() => flush_word(&mut word, &mut in_word, &mut words),
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    flush_word(&mut word, &mut in_word, &mut words);
+    if !words.is_empty() {
+        commands.push(words);
+    }
+    Ok(commands)
+}
+
+fn is_assignment(word: &str) -> bool {
+    word.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+    })
+}
+
+/// `>out`, `2>&1`, `<in`, `&>log` — the redirection word itself.
+fn is_redirection(word: &str) -> bool {
+    let trimmed = word.trim_start_matches(|c: char| c.is_ascii_digit() || c == '&');
+    trimmed.starts_with('>') || trimmed.starts_with('<')
+}
+
+fn basename(word: &str) -> &str {
+    word.rsplit('/').next().unwrap_or(word)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_and_pipes() {
+        assert_eq!(executables("ls -la | grep foo && cat x; git status").unwrap(), ["ls", "grep", "cat", "git"]);
+        assert_eq!(executables("FOO=1 ls 2>&1 > out.txt").unwrap(), ["ls"]);
+    }
+
+    #[test]
+    fn test_nested_shells_and_wrappers() {
+        assert_eq!(executables("bash -c 'ls; rm -rf /'").unwrap(), ["bash", "ls", "rm"]);
+        assert_eq!(executables("sh -lc \"cat x\"").unwrap(), ["sh", "cat"]);
+        assert_eq!(executables("env FOO=1 timeout 5 nice -n 10 rm x").unwrap(), ["env", "timeout", "nice", "rm"]);
+        assert_eq!(executables("(cd /tmp && ls)").unwrap(), ["cd", "ls"]);
+    }
+
+    #[test]
+    fn test_substitution_rejected() {
+        assert!(executables("ls $(rm -rf /)").is_err());
+        assert!(executables("ls `rm x`").is_err());
+        assert!(executables("cat <(rm x)").is_err());
+        assert!(executables("echo \"$(id)\"").is_err());
+        // Single quotes are literal.
+        assert_eq!(executables("grep '$(x)' f").unwrap(), ["grep"]);
+    }
+}
@@ -3,6 +3,8 @@
 
 pub mod sandbox;
 pub mod allowlist;
+pub mod command;
+pub mod approval;
 pub mod secrets;
 
 use async_trait::async_trait;
@@ -13,35 +15,40 @@ use bizclaw_core::traits::SecurityPolicy;
 /// Default security policy based on configuration.
 pub struct DefaultSecurityPolicy {
     config: AutonomyConfig,
+    allowlist: allowlist::Allowlist,
 }
 
 impl DefaultSecurityPolicy {
     pub fn new(config: AutonomyConfig) -> Self {
-        Self { config }
+        let allowlist = allowlist::Allowlist::new(&config);
+        Self { config, allowlist }
     }
 }
 
 #[async_trait]
 impl SecurityPolicy for DefaultSecurityPolicy {
+    /// Commands are unrestricted at autonomy level "full".
     async fn check_command(&self, command: &str) -> Result<bool> {
-        let cmd_base = command.split_whitespace().next().unwrap_or("");
-        let allowed = self.config.allowed_commands.iter().any(|c| c == cmd_base);
-        if !allowed {
-            tracing::warn!("Security: command '{}' not in allowed list", cmd_base);
+        if self.config.level == "full" {
+            return Ok(true);
+        }
+        match self.allowlist.check_command(command) {
+            Ok(()) => Ok(true),
+            Err(denial) => {
+                denial.audit("shell");
+                Ok(false)
+            }
         }
-        Ok(allowed)
     }
 
     async fn check_path(&self, path: &str) -> Result<bool> {
-        let expanded = shellexpand::tilde(path).to_string();
-        let forbidden = self.config.forbidden_paths.iter().any(|p| {
-            let exp = shellexpand::tilde(p).to_string();
-            expanded.starts_with(&exp)
-        });
-        if forbidden {
-            tracing::warn!("Security: path '{}' is forbidden", path);
+        match self.allowlist.check_path(path) {
+            Ok(_) => Ok(true),
+            Err(denial) => {
+                denial.audit("file");
+                Ok(false)
+            }
         }
-        Ok(!forbidden)
     }
 
     fn autonomy_level(&self) -> &str {
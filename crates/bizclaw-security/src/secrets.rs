@@ -3,8 +3,14 @@
 //! Provides secure storage and retrieval of API keys, tokens, and
 //! other sensitive configuration values using AES-256-ECB encryption
 //! with a machine-specific key derived from hostname + username.
+//!
+//! [`SecretCipher`] encrypts single values (such as the tokens in the
+//! platform's channel configs) with ChaCha20-Poly1305 under a key derived
+//! from a master secret, so they can't be read or altered without it.
 
 use aes::Aes256;
+use chacha20poly1305::aead::{Aead, AeadCore, OsRng};
+use chacha20poly1305::ChaCha20Poly1305;
 use aes::cipher::{BlockEncrypt, BlockDecrypt, KeyInit, generic_array::GenericArray};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use bizclaw_core::error::{BizClawError, Result};
@@ -83,7 +89,7 @@ impl SecretStore {
                 .write(true).create(true).truncate(true).mode(0o600)
                 .open(&self.secrets_path)?;
             file.write_all(content.as_bytes())?;
-            return Ok(());
+            Ok(())
         }
 
         #[cfg(not(unix))]
@@ -126,6 +132,63 @@ impl SecretStore {
     }
 }
 
+/// Prefix of values encrypted by [`SecretCipher`].
+pub const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+/// Encrypts secret values with ChaCha20-Poly1305 under a key derived from a
+/// master secret with HKDF-SHA256. Each value gets a random nonce and is
+/// stored as `enc:v1:<base64 of nonce + ciphertext>`.
+#[derive(Clone)]
+pub struct SecretCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl SecretCipher {
+    /// Key the cipher from `master_secret`, e.g. `BIZCLAW_MASTER_KEY`.
+    pub fn from_master_secret(master_secret: &str) -> Result<Self> {
+        if master_secret.trim().is_empty() {
+            return Err(BizClawError::Security("Master secret is empty".into()));
+        }
+        let mut key = [0u8; 32];
+        hkdf::Hkdf::<Sha256>::new(Some(b"bizclaw"), master_secret.as_bytes())
+            .expand(b"bizclaw::secret-fields::v1", &mut key)
+            .map_err(|e| BizClawError::Security(format!("Key derivation failed: {e}")))?;
+        Ok(Self { cipher: ChaCha20Poly1305::new(GenericArray::from_slice(&key)) })
+    }
+
+    /// Encrypt `plaintext` to an `enc:v1:` value.
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self.cipher.encrypt(&nonce, plaintext.as_bytes())
+            .map_err(|e| BizClawError::Security(format!("Encryption failed: {e}")))?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(format!("{ENCRYPTED_PREFIX}{}", BASE64.encode(sealed)))
+    }
+
+    /// Decrypt an `enc:v1:` value. Fails if it was encrypted under another
+    /// master secret or has been tampered with.
+    pub fn decrypt(&self, value: &str) -> Result<String> {
+        let encoded = value.strip_prefix(ENCRYPTED_PREFIX)
+            .ok_or_else(|| BizClawError::Security("Value is not encrypted".into()))?;
+        let sealed = BASE64.decode(encoded)
+            .map_err(|e| BizClawError::Security(format!("Base64 decode failed: {e}")))?;
+        if sealed.len() < 12 {
+            return Err(BizClawError::Security("Encrypted value is too short".into()));
+        }
+        let (nonce, ciphertext) = sealed.split_at(12);
+        let plaintext = self.cipher.decrypt(GenericArray::from_slice(nonce), ciphertext)
+            .map_err(|_| BizClawError::Security("Cannot decrypt secret: wrong master secret or corrupted value".into()))?;
+        String::from_utf8(plaintext)
+            .map_err(|e| BizClawError::Security(format!("Decryption produced invalid UTF-8: {e}")))
+    }
+}
+
+/// Whether `value` was encrypted by [`SecretCipher`].
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
 /// Derive a machine-specific AES-256 key from hostname + username.
 fn derive_machine_key() -> [u8; 32] {
     let hostname = hostname::get()
@@ -151,7 +214,7 @@ fn encrypt_aes256(data: &[u8], key: &[u8; 32]) -> Vec<u8> {
     // PKCS7 padding
     let padding_len = block_size - (data.len() % block_size);
     let mut padded = data.to_vec();
-    padded.extend(std::iter::repeat(padding_len as u8).take(padding_len));
+    padded.extend(std::iter::repeat_n(padding_len as u8, padding_len));
 
     let mut encrypted = Vec::with_capacity(padded.len());
     for chunk in padded.chunks(block_size) {
@@ -206,6 +269,23 @@ mod tests {
         assert_eq!(decrypted, data);
     }
 
+    #[test]
+    fn test_secret_cipher_roundtrip_and_tamper() {
+        let cipher = SecretCipher::from_master_secret("master-1").unwrap();
+        let sealed = cipher.encrypt("123456:ABC-DEF").unwrap();
+        assert!(is_encrypted(&sealed) && !sealed.contains("ABC"));
+        assert_ne!(sealed, cipher.encrypt("123456:ABC-DEF").unwrap(), "nonces differ");
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), "123456:ABC-DEF");
+
+        let other = SecretCipher::from_master_secret("master-2").unwrap();
+        assert!(other.decrypt(&sealed).is_err());
+        let mut bytes = BASE64.decode(&sealed[ENCRYPTED_PREFIX.len()..]).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        assert!(cipher.decrypt(&format!("{ENCRYPTED_PREFIX}{}", BASE64.encode(bytes))).is_err());
+        assert!(cipher.decrypt("plain").is_err());
+        assert!(SecretCipher::from_master_secret(" ").is_err());
+    }
+
     #[test]
     fn test_secret_store_operations() {
         let mut store = SecretStore::new(false);
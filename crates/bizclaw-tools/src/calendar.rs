@@ -1,14 +1,24 @@
 //! Google Calendar Tool — manage events via Google Calendar API.
 //!
-//! Supports listing today's events, creating events, and checking free/busy.
+//! Supports listing events, creating, updating and deleting events, and
+//! checking free/busy with suggested open slots.
 //! Uses Google Calendar REST API with API key or OAuth2 service account.
 
 use async_trait::async_trait;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
 use bizclaw_core::error::{BizClawError, Result};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
+const API_BASE: &str = "https://www.googleapis.com/calendar/v3";
+
+/// Open-slot suggestions fall within these local hours.
+const WORKDAY_START_HOUR: u32 = 8;
+const WORKDAY_END_HOUR: u32 = 18;
+/// Most open slots suggested by `freebusy`.
+const MAX_SUGGESTED_SLOTS: usize = 5;
+
 /// Calendar event representation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CalendarEvent {
@@ -20,6 +30,9 @@ pub struct CalendarEvent {
     pub end: String,     // ISO 8601
     pub all_day: bool,
     pub attendees: Vec<String>,
+    /// RFC 5545 `RRULE`/`EXRULE`/`RDATE`/`EXDATE` lines for recurring events.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Vec<String>>,
 }
 
 /// Google Calendar Tool configuration.
@@ -35,10 +48,17 @@ pub struct CalendarConfig {
     /// Timezone (e.g., Asia/Ho_Chi_Minh)
     #[serde(default = "default_timezone")]
     pub timezone: String,
+    /// Email invitations to attendees of created events, unless a call says otherwise
+    #[serde(default)]
+    pub send_invites: bool,
+    /// Calendar API base URL
+    #[serde(default = "default_api_base")]
+    pub api_base: String,
 }
 
 fn default_calendar_id() -> String { "primary".into() }
 fn default_timezone() -> String { "Asia/Ho_Chi_Minh".into() }
+fn default_api_base() -> String { API_BASE.into() }
 
 impl Default for CalendarConfig {
     fn default() -> Self {
@@ -47,6 +67,21 @@ impl Default for CalendarConfig {
             calendar_id: "primary".into(),
             access_token: None,
             timezone: "Asia/Ho_Chi_Minh".into(),
+            send_invites: false,
+            api_base: default_api_base(),
+        }
+    }
+}
+
+impl From<&bizclaw_core::config::CalendarToolConfig> for CalendarConfig {
+    fn from(cfg: &bizclaw_core::config::CalendarToolConfig) -> Self {
+        Self {
+            api_key: cfg.api_key.clone(),
+            calendar_id: cfg.calendar_id.clone(),
+            access_token: cfg.access_token.clone(),
+            timezone: cfg.timezone.clone(),
+            send_invites: cfg.send_invites,
+            api_base: default_api_base(),
         }
     }
 }
@@ -70,14 +105,11 @@ impl CalendarTool {
         let base_date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
             .map_err(|e| BizClawError::Tool(format!("Invalid date format: {e}. Use YYYY-MM-DD")))?;
 
-        let time_min = format!("{}T00:00:00+07:00", base_date);
-        let end_date = base_date + chrono::Duration::days(days as i64);
-        let time_max = format!("{}T23:59:59+07:00", end_date);
+        let (start, end) = day_range(&self.timezone(), base_date, days.max(1));
+        let time_min = start.to_rfc3339();
+        let time_max = end.to_rfc3339();
 
-        let mut url = format!(
-            "https://www.googleapis.com/calendar/v3/calendars/{}/events",
-            urlencoding::encode(&self.config.calendar_id)
-        );
+        let mut url = self.events_url();
 
         let mut params = vec![
             format!("timeMin={}", urlencoding::encode(&time_min)),
@@ -113,62 +145,27 @@ impl CalendarTool {
         let body: serde_json::Value = response.json().await
             .map_err(|e| BizClawError::Tool(format!("Parse response failed: {e}")))?;
 
-        let events = body["items"]
-            .as_array()
-            .map(|arr| {
-                arr.iter().filter_map(|item| {
-                    let summary = item["summary"].as_str()?.to_string();
-                    let start = item["start"]["dateTime"]
-                        .as_str()
-                        .or_else(|| item["start"]["date"].as_str())?
-                        .to_string();
-                    let end = item["end"]["dateTime"]
-                        .as_str()
-                        .or_else(|| item["end"]["date"].as_str())?
-                        .to_string();
-                    let all_day = item["start"]["date"].is_string();
-
-                    Some(CalendarEvent {
-                        id: item["id"].as_str().map(String::from),
-                        summary,
-                        description: item["description"].as_str().map(String::from),
-                        location: item["location"].as_str().map(String::from),
-                        start,
-                        end,
-                        all_day,
-                        attendees: item["attendees"]
-                            .as_array()
-                            .map(|a| {
-                                a.iter()
-                                    .filter_map(|att| att["email"].as_str().map(String::from))
-                                    .collect()
-                            })
-                            .unwrap_or_default(),
-                    })
-                }).collect()
-            })
-            .unwrap_or_default();
-
-        Ok(events)
+        Ok(parse_events(&body))
     }
 
-    /// Create a new calendar event.
-    async fn create_event(&self, event: &CalendarEvent) -> Result<String> {
-        let token = self.config.access_token.as_ref()
-            .ok_or_else(|| BizClawError::Tool("OAuth2 access_token required to create events".into()))?;
-
-        let url = format!(
-            "https://www.googleapis.com/calendar/v3/calendars/{}/events",
-            urlencoding::encode(&self.config.calendar_id)
-        );
+    /// Create a new calendar event, optionally emailing invitations to its
+    /// attendees and adding a Google Meet link. Returns the summary text and
+    /// the event as Google sent it back.
+    async fn create_event(&self, event: &CalendarEvent, send_invites: bool, add_meet: bool) -> Result<(String, serde_json::Value)> {
+        let token = self.access_token("create events")?;
+        let mut query = vec![("sendUpdates", if send_invites { "all" } else { "none" })];
+        if add_meet {
+            query.push(("conferenceDataVersion", "1"));
+        }
 
-        let body = if event.all_day {
+        let mut body = if event.all_day {
             serde_json::json!({
                 "summary": event.summary,
                 "description": event.description,
                 "location": event.location,
                 "start": { "date": event.start },
                 "end": { "date": event.end },
+                "attendees": event.attendees.iter().map(|e| serde_json::json!({"email": e})).collect::<Vec<_>>(),
             })
         } else {
             serde_json::json!({
@@ -180,31 +177,160 @@ impl CalendarTool {
                 "attendees": event.attendees.iter().map(|e| serde_json::json!({"email": e})).collect::<Vec<_>>(),
             })
         };
+        if let Some(ref recurrence) = event.recurrence {
+            body["recurrence"] = serde_json::json!(recurrence);
+        }
+        if add_meet {
+            body["conferenceData"] = serde_json::json!({
+                "createRequest": {
+                    "requestId": uuid::Uuid::new_v4().to_string(),
+                    "conferenceSolutionKey": { "type": "hangoutsMeet" },
+                }
+            });
+        }
+
+        let req = self.client.post(self.events_url()).query(&query).json(&body);
+        let result = self.send_authorized(req, token, "Create event").await?;
 
-        let response = self.client.post(&url)
+        let event_id = result["id"].as_str().unwrap_or("unknown").to_string();
+        let html_link = result["htmlLink"].as_str().unwrap_or("");
+
+        let mut output = format!("Event created: {event_id}\nLink: {html_link}");
+        if let Some(meet) = meet_link(&result) {
+            output.push_str(&format!("\nGoogle Meet: {meet}"));
+        } else if add_meet {
+            output.push_str("\nGoogle Meet: link is still being created; check the event shortly");
+        }
+        if send_invites && !event.attendees.is_empty() {
+            output.push_str(&format!("\nInvitations sent to: {}", event.attendees.join(", ")));
+        }
+        Ok((output, result))
+    }
+
+    fn events_url(&self) -> String {
+        format!("{}/calendars/{}/events", self.config.api_base, urlencoding::encode(&self.config.calendar_id))
+    }
+
+    fn event_url(&self, event_id: &str) -> String {
+        format!("{}/{}", self.events_url(), urlencoding::encode(event_id))
+    }
+
+    /// The OAuth2 token, required for anything beyond reading events.
+    fn access_token(&self, purpose: &str) -> Result<&str> {
+        self.config.access_token.as_deref()
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| BizClawError::Tool(format!("OAuth2 access_token required to {purpose}")))
+    }
+
+    /// Send an authorized request and return the JSON body (`Null` for 204).
+    async fn send_authorized(&self, req: reqwest::RequestBuilder, token: &str, what: &str) -> Result<serde_json::Value> {
+        let response = req
             .header("Authorization", format!("Bearer {token}"))
-            .json(&body)
             .send()
             .await
-            .map_err(|e| BizClawError::Tool(format!("Create event failed: {e}")))?;
+            .map_err(|e| BizClawError::Tool(format!("{what} failed: {e}")))?;
 
         if !response.status().is_success() {
             let status = response.status();
             let err_body = response.text().await.unwrap_or_default();
-            return Err(BizClawError::Tool(format!("Create event error {status}: {err_body}")));
+            return Err(BizClawError::Tool(format!("{what} error {status}: {err_body}")));
+        }
+        if response.status() == reqwest::StatusCode::NO_CONTENT {
+            return Ok(serde_json::Value::Null);
         }
+        response.json().await
+            .map_err(|e| BizClawError::Tool(format!("Parse {what} response: {e}")))
+    }
+
+    /// Patch the given fields of an event.
+    async fn update_event(&self, event_id: &str, patch: &serde_json::Value) -> Result<CalendarEvent> {
+        let token = self.access_token("update events")?;
+        let req = self.client.patch(self.event_url(event_id)).json(patch);
+        let body = self.send_authorized(req, token, "Update event").await?;
+        parse_event(&body).ok_or_else(|| BizClawError::Tool("Update event: unexpected response".into()))
+    }
 
-        let result: serde_json::Value = response.json().await
-            .map_err(|e| BizClawError::Tool(format!("Parse create response: {e}")))?;
+    /// Delete an event; returns it as it was, for the confirmation message.
+    /// For an instance of a recurring event, `all_instances` deletes the
+    /// whole series instead of just that occurrence.
+    async fn delete_event(&self, event_id: &str, all_instances: bool) -> Result<CalendarEvent> {
+        let token = self.access_token("delete events")?;
+        let body = self.send_authorized(self.client.get(self.event_url(event_id)), token, "Get event").await?;
+        let event = parse_event(&body)
+            .ok_or_else(|| BizClawError::Tool(format!("Event {event_id} not found")))?;
+        let series_id = body["recurringEventId"].as_str().filter(|_| all_instances);
+        let req = match series_id {
+            // Attendees aren't emailed about each deleted occurrence.
+            Some(series_id) => self.client.delete(self.event_url(series_id)).query(&[("sendUpdates", "none")]),
+            None => self.client.delete(self.event_url(event_id)),
+        };
+        self.send_authorized(req, token, "Delete event").await?;
+        Ok(event)
+    }
 
-        let event_id = result["id"].as_str().unwrap_or("unknown").to_string();
-        let html_link = result["htmlLink"].as_str().unwrap_or("");
+    /// Busy blocks (merged) between `from` and `to`.
+    async fn free_busy(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+        let token = self.access_token("check free/busy")?;
+        let req = self.client.post(format!("{}/freeBusy", self.config.api_base)).json(&serde_json::json!({
+            "timeMin": from.to_rfc3339(),
+            "timeMax": to.to_rfc3339(),
+            "timeZone": self.config.timezone,
+            "items": [{ "id": self.config.calendar_id }],
+        }));
+        let body = self.send_authorized(req, token, "Free/busy query").await?;
+        parse_busy(&body, &self.config.calendar_id).map(merge_busy)
+    }
+
+    fn timezone(&self) -> chrono_tz::Tz {
+        self.config.timezone.parse().unwrap_or(chrono_tz::Asia::Ho_Chi_Minh)
+    }
+
+    /// RFC 3339, or a local `YYYY-MM-DD[THH:MM[:SS]]`. A bare date is the
+    /// start of that day, or its end when `end_of_day` is set.
+    fn parse_time(&self, value: &str, end_of_day: bool) -> Result<DateTime<Utc>> {
+        if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+            return Ok(t.with_timezone(&Utc));
+        }
+        let naive = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+            .or_else(|| {
+                let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+                let date = if end_of_day { date.succ_opt()? } else { date };
+                date.and_hms_opt(0, 0, 0)
+            })
+            .ok_or_else(|| BizClawError::Tool(format!("Invalid time '{value}'. Use YYYY-MM-DD or YYYY-MM-DDTHH:MM")))?;
+        self.timezone().from_local_datetime(&naive).earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .ok_or_else(|| BizClawError::Tool(format!("'{value}' does not exist in {}", self.config.timezone)))
+    }
 
-        Ok(format!("Event created: {event_id}\nLink: {html_link}"))
+    fn format_free_busy(&self, busy: &[(DateTime<Utc>, DateTime<Utc>)], slots: &[DateTime<Utc>], minutes: i64) -> String {
+        let tz = self.timezone();
+        let fmt = |t: &DateTime<Utc>| t.with_timezone(&tz).format("%d/%m %H:%M").to_string();
+        let mut output = if busy.is_empty() {
+            "🟢 Không có lịch bận trong khoảng này.\n".to_string()
+        } else {
+            let mut out = format!("🔴 Bận ({} khoảng):\n", busy.len());
+            for (start, end) in busy {
+                out.push_str(&format!("- {} → {}\n", fmt(start), fmt(end)));
+            }
+            out
+        };
+        if slots.is_empty() {
+            output.push_str(&format!("\nKhông tìm thấy khung trống {minutes} phút trong giờ làm việc."));
+        } else {
+            output.push_str(&format!("\n✅ Gợi ý khung trống {minutes} phút:\n"));
+            for start in slots {
+                output.push_str(&format!("- {} → {}\n", fmt(start), fmt(&(*start + Duration::minutes(minutes)))));
+            }
+        }
+        output
     }
 
-    /// Format events for human-readable output.
+    /// Format events for human-readable output, with times in the configured zone.
     fn format_events(&self, events: &[CalendarEvent], date: &str) -> String {
+        let tz = self.timezone();
         if events.is_empty() {
             return format!("📅 Không có sự kiện nào vào ngày {date}.");
         }
@@ -212,18 +338,7 @@ impl CalendarTool {
         let mut output = format!("📅 Lịch ngày {date} ({} sự kiện):\n\n", events.len());
 
         for (i, event) in events.iter().enumerate() {
-            let time = if event.all_day {
-                "Cả ngày".to_string()
-            } else {
-                // Extract time part from ISO 8601
-                let start_time = event.start.split('T').nth(1)
-                    .and_then(|t| t.split('+').next())
-                    .unwrap_or(&event.start);
-                let end_time = event.end.split('T').nth(1)
-                    .and_then(|t| t.split('+').next())
-                    .unwrap_or(&event.end);
-                format!("{} - {}", &start_time[..5], &end_time[..5.min(end_time.len())])
-            };
+            let time = event_time(event, &tz);
 
             output.push_str(&format!(
                 "{}. ⏰ {} | {}\n",
@@ -243,6 +358,239 @@ impl CalendarTool {
     }
 }
 
+/// The local midnight starting `date`, or the first instant after it when
+/// a DST change skips midnight.
+fn local_midnight<Tz: TimeZone>(tz: &Tz, date: NaiveDate) -> DateTime<Utc> {
+    (0..=2)
+        .find_map(|hour| tz.from_local_datetime(&date.and_hms_opt(hour, 0, 0)?).earliest())
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_else(|| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+}
+
+/// `days` whole local days from the start of `date` (end exclusive).
+fn day_range<Tz: TimeZone>(tz: &Tz, date: NaiveDate, days: u32) -> (DateTime<Utc>, DateTime<Utc>) {
+    let end = date.checked_add_days(chrono::Days::new(days as u64)).unwrap_or(date);
+    (local_midnight(tz, date), local_midnight(tz, end))
+}
+
+/// Today's date in `tz`.
+fn today_in<Tz: TimeZone>(tz: &Tz, now: DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(tz).date_naive()
+}
+
+/// When an event happens, in `tz`: `HH:MM - HH:MM`, with dates when it runs
+/// past midnight, or "Cả ngày" for all-day events (with the date range when
+/// they span several days). Times that don't parse are shown as given.
+fn event_time<Tz: TimeZone>(event: &CalendarEvent, tz: &Tz) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if event.all_day {
+        let start = NaiveDate::parse_from_str(&event.start, "%Y-%m-%d");
+        // All-day end dates are exclusive.
+        let last = NaiveDate::parse_from_str(&event.end, "%Y-%m-%d").ok().and_then(|d| d.pred_opt());
+        return match (start, last) {
+            (Ok(start), Some(last)) if last > start => {
+                format!("Cả ngày ({} - {})", start.format("%d/%m"), last.format("%d/%m"))
+            }
+            _ => "Cả ngày".to_string(),
+        };
+    }
+
+    let local = |value: &str| DateTime::parse_from_rfc3339(value).ok().map(|t| t.with_timezone(tz));
+    match (local(&event.start), local(&event.end)) {
+        (Some(start), Some(end)) if start.date_naive() == end.date_naive() => {
+            format!("{} - {}", start.format("%H:%M"), end.format("%H:%M"))
+        }
+        (Some(start), Some(end)) => {
+            format!("{} - {}", start.format("%d/%m %H:%M"), end.format("%d/%m %H:%M"))
+        }
+        _ => format!("{} - {}", event.start, event.end),
+    }
+}
+
+/// Parse one event resource; `None` if it has no title or times.
+fn parse_event(item: &serde_json::Value) -> Option<CalendarEvent> {
+    let summary = item["summary"].as_str()?.to_string();
+    let start = item["start"]["dateTime"]
+        .as_str()
+        .or_else(|| item["start"]["date"].as_str())?
+        .to_string();
+    let end = item["end"]["dateTime"]
+        .as_str()
+        .or_else(|| item["end"]["date"].as_str())?
+        .to_string();
+    let all_day = item["start"]["date"].is_string();
+
+    Some(CalendarEvent {
+        id: item["id"].as_str().map(String::from),
+        summary,
+        description: item["description"].as_str().map(String::from),
+        location: item["location"].as_str().map(String::from),
+        start,
+        end,
+        all_day,
+        attendees: item["attendees"]
+            .as_array()
+            .map(|a| {
+                a.iter()
+                    .filter_map(|att| att["email"].as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default(),
+        recurrence: item["recurrence"].as_array().map(|lines| {
+            lines.iter().filter_map(|l| l.as_str().map(String::from)).collect()
+        }),
+    })
+}
+
+/// `recurrence` lines from tool arguments, checked to be RFC 5545
+/// RRULE/EXRULE/RDATE/EXDATE properties.
+fn parse_recurrence(value: &serde_json::Value) -> Result<Option<Vec<String>>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    let lines = value.as_array()
+        .ok_or_else(|| BizClawError::Tool("'recurrence' must be a list of RRULE/EXDATE lines".into()))?;
+    lines.iter()
+        .map(|line| {
+            let line = line.as_str().unwrap_or_default().trim();
+            let valid = ["RRULE", "EXRULE", "RDATE", "EXDATE"].iter().any(|prop| {
+                line.strip_prefix(prop).is_some_and(|rest| rest.starts_with(':') || rest.starts_with(';'))
+            });
+            if valid {
+                Ok(line.to_string())
+            } else {
+                Err(BizClawError::Tool(format!(
+                    "Invalid recurrence line '{line}'. Use e.g. RRULE:FREQ=WEEKLY;BYDAY=MO or EXDATE:20260309T090000"
+                )))
+            }
+        })
+        .collect::<Result<Vec<_>>>()
+        .map(Some)
+}
+
+/// The Google Meet link of an event, if it has one.
+fn meet_link(event: &serde_json::Value) -> Option<&str> {
+    event["hangoutLink"].as_str().or_else(|| {
+        event["conferenceData"]["entryPoints"]
+            .as_array()?
+            .iter()
+            .find(|e| e["entryPointType"] == "video")?["uri"]
+            .as_str()
+    })
+}
+
+/// Parse an events list response.
+fn parse_events(body: &serde_json::Value) -> Vec<CalendarEvent> {
+    body["items"]
+        .as_array()
+        .map(|arr| arr.iter().filter_map(parse_event).collect())
+        .unwrap_or_default()
+}
+
+/// Busy blocks for `calendar_id` from a freeBusy response.
+fn parse_busy(body: &serde_json::Value, calendar_id: &str) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>> {
+    let calendar = &body["calendars"][calendar_id];
+    if let Some(reason) = calendar["errors"].as_array().and_then(|e| e.first()).and_then(|e| e["reason"].as_str()) {
+        return Err(BizClawError::Tool(format!("Free/busy unavailable for {calendar_id}: {reason}")));
+    }
+    let time = |v: &serde_json::Value| {
+        v.as_str()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+            .map(|t| t.with_timezone(&Utc))
+    };
+    Ok(calendar["busy"]
+        .as_array()
+        .map(|blocks| blocks.iter().filter_map(|b| Some((time(&b["start"])?, time(&b["end"])?))).collect())
+        .unwrap_or_default())
+}
+
+/// Sort busy blocks and merge the ones that overlap or touch.
+fn merge_busy(mut busy: Vec<(DateTime<Utc>, DateTime<Utc>)>) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    busy.sort();
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::with_capacity(busy.len());
+    for (start, end) in busy {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+/// Start times of free slots of `duration` between `from` and `to`, within
+/// working hours in `tz`: the earliest fitting start per day of each gap.
+fn open_slots<Tz: TimeZone>(
+    busy: &[(DateTime<Utc>, DateTime<Utc>)],
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    duration: Duration,
+    tz: &Tz,
+) -> Vec<DateTime<Utc>> {
+    let mut gaps = Vec::new();
+    let mut cursor = from;
+    for &(start, end) in busy {
+        if start > cursor {
+            gaps.push((cursor, start.min(to)));
+        }
+        cursor = cursor.max(end);
+    }
+    if cursor < to {
+        gaps.push((cursor, to));
+    }
+
+    let mut slots = Vec::new();
+    for (gap_start, gap_end) in gaps {
+        let mut day = gap_start.with_timezone(tz).date_naive();
+        while slots.len() < MAX_SUGGESTED_SLOTS {
+            let local = |hour| day.and_hms_opt(hour, 0, 0)
+                .and_then(|t| tz.from_local_datetime(&t).earliest())
+                .map(|t| t.with_timezone(&Utc));
+            let (Some(work_start), Some(work_end)) = (local(WORKDAY_START_HOUR), local(WORKDAY_END_HOUR)) else { break };
+            if work_start >= gap_end {
+                break;
+            }
+            let start = gap_start.max(work_start);
+            if work_end.min(gap_end) - start >= duration {
+                slots.push(start);
+            }
+            let Some(next) = day.succ_opt() else { break };
+            day = next;
+        }
+    }
+    slots
+}
+
+/// Body for patching an event from tool arguments: `partial_event` as
+/// given, overridden by the individual fields.
+fn event_patch(args: &serde_json::Value, timezone: &str) -> Result<serde_json::Value> {
+    let mut patch = match &args["partial_event"] {
+        serde_json::Value::Null => serde_json::Map::new(),
+        serde_json::Value::Object(fields) => fields.clone(),
+        _ => return Err(BizClawError::Tool("'partial_event' must be an object of event fields".into())),
+    };
+    for field in ["summary", "description", "location"] {
+        if let Some(value) = args[field].as_str() {
+            patch.insert(field.into(), value.into());
+        }
+    }
+    for field in ["start", "end"] {
+        if let Some(value) = args[field].as_str() {
+            let time = if value.contains('T') {
+                serde_json::json!({ "dateTime": value, "timeZone": timezone })
+            } else {
+                serde_json::json!({ "date": value })
+            };
+            patch.insert(field.into(), time);
+        }
+    }
+    if let Some(recurrence) = parse_recurrence(&args["recurrence"])? {
+        patch.insert("recurrence".into(), recurrence.into());
+    }
+    Ok(serde_json::Value::Object(patch))
+}
+
 #[async_trait]
 impl Tool for CalendarTool {
     fn name(&self) -> &str { "calendar" }
@@ -250,14 +598,44 @@ impl Tool for CalendarTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "calendar".into(),
-            description: "Quản lý Google Calendar — xem lịch, tạo sự kiện, kiểm tra lịch rảnh.".into(),
+            description: "Quản lý Google Calendar — xem lịch, tạo/sửa/xoá sự kiện, kiểm tra lịch rảnh và gợi ý khung trống.".into(),
             parameters: serde_json::json!({
                 "type": "object",
                 "properties": {
                     "action": {
                         "type": "string",
-                        "enum": ["list", "create", "today"],
-                        "description": "Action: list (xem lịch ngày cụ thể), create (tạo sự kiện), today (xem lịch hôm nay)"
+                        "enum": ["list", "create", "today", "update", "delete", "freebusy"],
+                        "description": "Action: list (xem lịch ngày cụ thể), create (tạo sự kiện), today (xem lịch hôm nay), update (sửa sự kiện), delete (xoá sự kiện), freebusy (lịch bận + gợi ý khung trống)"
+                    },
+                    "event_id": {
+                        "type": "string",
+                        "description": "Event ID from 'list' (for 'update' and 'delete')"
+                    },
+                    "recurrence": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Repeat rules for 'create'/'update' as RFC 5545 lines, e.g. [\"RRULE:FREQ=WEEKLY;BYDAY=MO,WE;COUNT=10\", \"EXDATE;TZID=Asia/Ho_Chi_Minh:20260309T090000\"]"
+                    },
+                    "attendees": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Attendee emails for 'create'"
+                    },
+                    "send_invites": {
+                        "type": "boolean",
+                        "description": "For 'create': email invitations to the attendees (default from config)"
+                    },
+                    "add_meet": {
+                        "type": "boolean",
+                        "description": "For 'create': add a Google Meet video link"
+                    },
+                    "all_instances": {
+                        "type": "boolean",
+                        "description": "For 'delete' on an occurrence of a recurring event: delete the whole series (default false, only this occurrence)"
+                    },
+                    "partial_event": {
+                        "type": "object",
+                        "description": "For 'update': raw Google Calendar event fields to patch (e.g. {\"colorId\": \"5\"})"
                     },
                     "date": {
                         "type": "string",
@@ -269,15 +647,27 @@ impl Tool for CalendarTool {
                     },
                     "summary": {
                         "type": "string",
-                        "description": "Event title (for 'create' action)"
+                        "description": "Event title (for 'create'; new title for 'update')"
                     },
                     "start": {
                         "type": "string",
-                        "description": "Start time ISO 8601 (for 'create', e.g. 2026-02-21T09:00:00)"
+                        "description": "Start time ISO 8601 (for 'create'/'update', e.g. 2026-02-21T09:00:00; a date alone means all-day)"
                     },
                     "end": {
                         "type": "string",
-                        "description": "End time ISO 8601 (for 'create')"
+                        "description": "End time ISO 8601 (for 'create'/'update')"
+                    },
+                    "from": {
+                        "type": "string",
+                        "description": "Range start for 'freebusy' (YYYY-MM-DD or YYYY-MM-DDTHH:MM, default now)"
+                    },
+                    "to": {
+                        "type": "string",
+                        "description": "Range end for 'freebusy' (default 7 days after 'from')"
+                    },
+                    "duration_minutes": {
+                        "type": "integer",
+                        "description": "Length of the open slots to suggest for 'freebusy' (default 30)"
                     },
                     "description": {
                         "type": "string",
@@ -299,19 +689,18 @@ impl Tool for CalendarTool {
 
         let action = args["action"].as_str().unwrap_or("today");
 
-        let output = match action {
+        let (output, data) = match action {
             "today" => {
-                let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+                let today = today_in(&self.timezone(), Utc::now()).format("%Y-%m-%d").to_string();
                 let events = self.list_events(&today, 1).await?;
-                self.format_events(&events, &today)
+                (self.format_events(&events, &today), serde_json::json!(events))
             }
             "list" => {
-                let date = args["date"].as_str()
-                    .unwrap_or(&chrono::Utc::now().format("%Y-%m-%d").to_string())
-                    .to_string();
+                let date = args["date"].as_str().map(String::from)
+                    .unwrap_or_else(|| today_in(&self.timezone(), Utc::now()).format("%Y-%m-%d").to_string());
                 let days = args["days"].as_u64().unwrap_or(1) as u32;
                 let events = self.list_events(&date, days).await?;
-                self.format_events(&events, &date)
+                (self.format_events(&events, &date), serde_json::json!(events))
             }
             "create" => {
                 let summary = args["summary"].as_str()
@@ -329,18 +718,352 @@ impl Tool for CalendarTool {
                     start: start.into(),
                     end: end.into(),
                     all_day: !start.contains('T'),
-                    attendees: vec![],
+                    attendees: args["attendees"].as_array()
+                        .map(|a| a.iter().filter_map(|e| e.as_str().map(String::from)).collect())
+                        .unwrap_or_default(),
+                    recurrence: parse_recurrence(&args["recurrence"])?,
                 };
+                let send_invites = args["send_invites"].as_bool().unwrap_or(self.config.send_invites);
+                let add_meet = args["add_meet"].as_bool().unwrap_or(false);
 
-                self.create_event(&event).await?
+                self.create_event(&event, send_invites, add_meet).await?
+            }
+            "update" => {
+                let event_id = args["event_id"].as_str()
+                    .ok_or_else(|| BizClawError::Tool("Missing 'event_id' for update".into()))?;
+                let patch = event_patch(&args, &self.config.timezone)?;
+                if patch.as_object().is_some_and(|p| p.is_empty()) {
+                    return Err(BizClawError::Tool("Nothing to update: give summary, start, end, location, description, recurrence or partial_event".into()));
+                }
+                let event = self.update_event(event_id, &patch).await?;
+                (format!("✏️ Đã cập nhật sự kiện: {} ({} → {})", event.summary, event.start, event.end), serde_json::json!(event))
+            }
+            "delete" => {
+                let event_id = args["event_id"].as_str()
+                    .ok_or_else(|| BizClawError::Tool("Missing 'event_id' for delete".into()))?;
+                let all_instances = args["all_instances"].as_bool().unwrap_or(false);
+                let event = self.delete_event(event_id, all_instances).await?;
+                let output = if all_instances {
+                    format!("🗑️ Đã xoá toàn bộ chuỗi sự kiện lặp lại \"{}\".", event.summary)
+                } else {
+                    format!("🗑️ Đã xoá sự kiện \"{}\" ({} → {}).", event.summary, event.start, event.end)
+                };
+                (output, serde_json::json!(event))
             }
-            _ => format!("Unknown action: {action}"),
+            "freebusy" => {
+                let from = match args["from"].as_str() {
+                    Some(from) => self.parse_time(from, false)?,
+                    None => chrono::Utc::now(),
+                };
+                let to = match args["to"].as_str() {
+                    Some(to) => self.parse_time(to, true)?,
+                    None => from + Duration::days(7),
+                };
+                if to <= from {
+                    return Err(BizClawError::Tool("'to' must be after 'from'".into()));
+                }
+                let minutes = args["duration_minutes"].as_i64().filter(|m| *m > 0).unwrap_or(30);
+                let busy = self.free_busy(from, to).await?;
+                let slots = open_slots(&busy, from, to, Duration::minutes(minutes), &self.timezone());
+                let data = serde_json::json!({
+                    "busy": busy.iter().map(|(start, end)| serde_json::json!({"start": start, "end": end})).collect::<Vec<_>>(),
+                    "slots": slots,
+                });
+                (self.format_free_busy(&busy, &slots, minutes), data)
+            }
+            _ => (format!("Unknown action: {action}"), serde_json::Value::Null),
         };
 
-        Ok(ToolResult {
-            tool_call_id: String::new(),
-            output,
-            success: true,
-        })
+        Ok(ToolResult::ok(output).with_data(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_events_fixture() {
+        let body = serde_json::json!({
+            "items": [
+                {
+                    "id": "ev1",
+                    "summary": "Họp team",
+                    "location": "Phòng 2",
+                    "start": { "dateTime": "2026-03-02T09:00:00+07:00" },
+                    "end": { "dateTime": "2026-03-02T10:00:00+07:00" },
+                    "attendees": [{ "email": "a@example.com" }, { "displayName": "no email" }]
+                },
+                {
+                    "id": "ev2",
+                    "summary": "Nghỉ lễ",
+                    "start": { "date": "2026-03-03" },
+                    "end": { "date": "2026-03-04" }
+                },
+                { "id": "ev3", "start": { "date": "2026-03-05" }, "end": { "date": "2026-03-06" } }
+            ]
+        });
+        let events = parse_events(&body);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id.as_deref(), Some("ev1"));
+        assert!(!events[0].all_day);
+        assert_eq!(events[0].attendees, vec!["a@example.com"]);
+        assert!(events[1].all_day);
+        assert_eq!(events[1].start, "2026-03-03");
+        assert!(parse_events(&serde_json::json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_parse_busy_merges_overlaps() {
+        let body = serde_json::json!({
+            "calendars": {
+                "primary": {
+                    "busy": [
+                        { "start": "2026-03-02T04:00:00Z", "end": "2026-03-02T05:00:00Z" },
+                        { "start": "2026-03-02T02:00:00Z", "end": "2026-03-02T03:00:00Z" },
+                        { "start": "2026-03-02T04:30:00Z", "end": "2026-03-02T06:00:00Z" },
+                        { "start": "2026-03-02T06:00:00Z", "end": "2026-03-02T06:30:00Z" }
+                    ]
+                }
+            }
+        });
+        let busy = merge_busy(parse_busy(&body, "primary").unwrap());
+        assert_eq!(busy, vec![
+            (utc("2026-03-02T02:00:00Z"), utc("2026-03-02T03:00:00Z")),
+            (utc("2026-03-02T04:00:00Z"), utc("2026-03-02T06:30:00Z")),
+        ]);
+
+        let denied = serde_json::json!({
+            "calendars": { "primary": { "errors": [{ "domain": "global", "reason": "notFound" }] } }
+        });
+        assert!(parse_busy(&denied, "primary").unwrap_err().to_string().contains("notFound"));
+    }
+
+    #[test]
+    fn test_open_slots_within_working_hours() {
+        let tz = chrono_tz::Asia::Ho_Chi_Minh;
+        // 2026-03-02 08:00–18:00 local is 01:00–11:00 UTC.
+        let busy = vec![
+            (utc("2026-03-02T01:00:00Z"), utc("2026-03-02T02:00:00Z")),
+            (utc("2026-03-02T02:20:00Z"), utc("2026-03-02T10:30:00Z")),
+        ];
+        let slots = open_slots(
+            &busy,
+            utc("2026-03-01T17:00:00Z"),
+            utc("2026-03-03T17:00:00Z"),
+            Duration::minutes(30),
+            &tz,
+        );
+        // The 20-minute gap is too short; 10:30–11:00 UTC fits, then the next morning.
+        assert_eq!(slots, vec![utc("2026-03-02T10:30:00Z"), utc("2026-03-03T01:00:00Z")]);
+    }
+
+    #[test]
+    fn test_parse_time_and_token() {
+        let tool = CalendarTool::new(CalendarConfig::default());
+        assert_eq!(tool.parse_time("2026-03-02T09:00", false).unwrap(), utc("2026-03-02T02:00:00Z"));
+        assert_eq!(tool.parse_time("2026-03-02", true).unwrap(), utc("2026-03-02T17:00:00Z"));
+        assert_eq!(tool.parse_time("2026-03-02T09:00:00Z", false).unwrap(), utc("2026-03-02T09:00:00Z"));
+        assert!(tool.parse_time("next tuesday", false).is_err());
+        assert!(tool.access_token("update events").is_err());
+
+        let patch = event_patch(&serde_json::json!({ "summary": "New", "start": "2026-03-02" }), "UTC").unwrap();
+        assert_eq!(patch, serde_json::json!({ "summary": "New", "start": { "date": "2026-03-02" } }));
+    }
+
+    #[test]
+    fn test_recurrence() {
+        let rules = serde_json::json!(["RRULE:FREQ=WEEKLY;BYDAY=MO;COUNT=4", "EXDATE;TZID=UTC:20260309T090000"]);
+        assert_eq!(parse_recurrence(&rules).unwrap().unwrap().len(), 2);
+        assert_eq!(parse_recurrence(&serde_json::Value::Null).unwrap(), None);
+        assert!(parse_recurrence(&serde_json::json!(["FREQ=DAILY"])).is_err());
+        assert!(parse_recurrence(&serde_json::json!("RRULE:FREQ=DAILY")).is_err());
+
+        let patch = event_patch(&serde_json::json!({
+            "partial_event": { "colorId": "5", "summary": "Old" },
+            "summary": "New",
+            "recurrence": ["RRULE:FREQ=DAILY"]
+        }), "UTC").unwrap();
+        assert_eq!(patch, serde_json::json!({
+            "colorId": "5", "summary": "New", "recurrence": ["RRULE:FREQ=DAILY"]
+        }));
+        assert!(event_patch(&serde_json::json!({ "partial_event": "x" }), "UTC").is_err());
+
+        let series = parse_event(&serde_json::json!({
+            "id": "weekly", "summary": "Giao ban",
+            "start": { "dateTime": "2026-03-02T09:00:00+07:00" },
+            "end": { "dateTime": "2026-03-02T10:00:00+07:00" },
+            "recurrence": ["RRULE:FREQ=WEEKLY;BYDAY=MO"]
+        })).unwrap();
+        assert_eq!(series.recurrence, Some(vec!["RRULE:FREQ=WEEKLY;BYDAY=MO".to_string()]));
+        let json = serde_json::to_value(event("2026-03-02", "2026-03-03", true)).unwrap();
+        assert!(json.get("recurrence").is_none());
+    }
+
+    fn event(start: &str, end: &str, all_day: bool) -> CalendarEvent {
+        CalendarEvent {
+            id: None,
+            summary: "Họp".into(),
+            description: None,
+            location: None,
+            start: start.into(),
+            end: end.into(),
+            all_day,
+            attendees: vec![],
+            recurrence: None,
+        }
+    }
+
+    #[test]
+    fn test_day_range_and_today_per_zone() {
+        let date = NaiveDate::from_ymd_opt(2026, 3, 2).unwrap();
+        assert_eq!(
+            day_range(&chrono_tz::Asia::Ho_Chi_Minh, date, 1),
+            (utc("2026-03-01T17:00:00Z"), utc("2026-03-02T17:00:00Z")),
+        );
+        assert_eq!(day_range(&chrono_tz::UTC, date, 2), (utc("2026-03-02T00:00:00Z"), utc("2026-03-04T00:00:00Z")));
+        assert_eq!(
+            day_range(&chrono_tz::America::New_York, date, 1),
+            (utc("2026-03-02T05:00:00Z"), utc("2026-03-03T05:00:00Z")),
+        );
+        // Midnight doesn't exist on DST days in some zones (Havana skips 00:00–01:00).
+        let dst = NaiveDate::from_ymd_opt(2026, 3, 8).unwrap();
+        assert_eq!(day_range(&chrono_tz::America::Havana, dst, 1).0, utc("2026-03-08T05:00:00Z"));
+
+        // 03:30 UTC is already the 3rd in Vietnam and UTC, still the 2nd in New York.
+        let now = utc("2026-03-03T03:30:00Z");
+        assert_eq!(today_in(&chrono_tz::Asia::Ho_Chi_Minh, now).to_string(), "2026-03-03");
+        assert_eq!(today_in(&chrono_tz::UTC, now).to_string(), "2026-03-03");
+        assert_eq!(today_in(&chrono_tz::America::New_York, now).to_string(), "2026-03-02");
+        // Just before midnight UTC it's already tomorrow in Vietnam.
+        let now = utc("2026-03-02T23:59:00Z");
+        assert_eq!(today_in(&chrono_tz::Asia::Ho_Chi_Minh, now).to_string(), "2026-03-03");
+        assert_eq!(today_in(&chrono_tz::UTC, now).to_string(), "2026-03-02");
+    }
+
+    #[test]
+    fn test_event_time_in_zone() {
+        let late = event("2026-03-02T23:30:00-05:00", "2026-03-03T00:30:00-05:00", false);
+        assert_eq!(event_time(&late, &chrono_tz::America::New_York), "02/03 23:30 - 03/03 00:30");
+        assert_eq!(event_time(&late, &chrono_tz::UTC), "04:30 - 05:30");
+        assert_eq!(event_time(&late, &chrono_tz::Asia::Ho_Chi_Minh), "11:30 - 12:30");
+
+        assert_eq!(event_time(&event("2026-03-02", "2026-03-03", true), &chrono_tz::UTC), "Cả ngày");
+        assert_eq!(event_time(&event("2026-03-02", "2026-03-05", true), &chrono_tz::UTC), "Cả ngày (02/03 - 04/03)");
+
+        // Odd values are shown as-is rather than sliced.
+        assert_eq!(event_time(&event("9h", "10", false), &chrono_tz::UTC), "9h - 10");
+        assert_eq!(event_time(&event("", "", true), &chrono_tz::UTC), "Cả ngày");
+    }
+
+    /// Calendar API stand-in: records each request (head and body) and
+    /// answers with `reply`.
+    async fn calendar_api(reply: serde_json::Value) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    request.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&request);
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                        if n == 0 { break } else { continue }
+                    };
+                    let length = head.lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse().unwrap_or(0)))
+                        .unwrap_or(0);
+                    if body.len() >= length || n == 0 {
+                        break;
+                    }
+                }
+                let _ = tx.send(String::from_utf8_lossy(&request).into_owned());
+                let body = reply.to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (base, rx)
+    }
+
+    #[tokio::test]
+    async fn test_list_returns_events_as_data() {
+        let (api_base, mut requests) = calendar_api(serde_json::json!({ "items": [{
+            "id": "ev1",
+            "summary": "Họp team",
+            "start": { "dateTime": "2026-03-02T09:00:00+07:00" },
+            "end": { "dateTime": "2026-03-02T10:00:00+07:00" }
+        }] })).await;
+        let tool = CalendarTool::new(CalendarConfig { api_key: Some("key".into()), api_base, ..Default::default() });
+
+        let result = tool.execute(r#"{"action":"list","date":"2026-03-02"}"#).await.unwrap();
+        assert!(result.output.contains("Họp team"), "{}", result.output);
+        let events = result.data.as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["id"], "ev1");
+        assert_eq!(events[0]["start"], "2026-03-02T09:00:00+07:00");
+        assert!(requests.recv().await.unwrap().starts_with("GET /calendars/primary/events?"));
+    }
+
+    #[tokio::test]
+    async fn test_create_event_invites_and_meet() {
+        let (api_base, mut requests) = calendar_api(serde_json::json!({
+            "id": "ev9",
+            "htmlLink": "https://calendar.google.com/event?eid=ev9",
+            "conferenceData": { "entryPoints": [
+                { "entryPointType": "phone", "uri": "tel:+1-555" },
+                { "entryPointType": "video", "uri": "https://meet.google.com/abc-defg-hij" }
+            ] }
+        })).await;
+        let tool = CalendarTool::new(CalendarConfig {
+            access_token: Some("token".into()),
+            send_invites: true,
+            api_base,
+            ..Default::default()
+        });
+
+        let args = serde_json::json!({
+            "action": "create", "summary": "Demo", "start": "2026-03-02T09:00:00", "end": "2026-03-02T10:00:00",
+            "attendees": ["khach@example.com"], "add_meet": true
+        });
+        let result = tool.execute(&args.to_string()).await.unwrap();
+        assert_eq!(result.data["id"], "ev9");
+        let output = result.output;
+        assert!(output.contains("Google Meet: https://meet.google.com/abc-defg-hij"), "{output}");
+        assert!(output.contains("Invitations sent to: khach@example.com"), "{output}");
+
+        let request = requests.recv().await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("POST /calendars/primary/events?sendUpdates=all&conferenceDataVersion=1 "), "{head}");
+        assert!(head.to_lowercase().contains("authorization: bearer token"));
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(body["attendees"], serde_json::json!([{ "email": "khach@example.com" }]));
+        assert_eq!(body["conferenceData"]["createRequest"]["conferenceSolutionKey"]["type"], "hangoutsMeet");
+        assert_eq!(body["conferenceData"]["createRequest"]["requestId"].as_str().unwrap().len(), 36);
+
+        // Per-call opt-out, and no Meet unless asked.
+        let args = serde_json::json!({
+            "action": "create", "summary": "Nội bộ", "start": "2026-03-03", "end": "2026-03-04",
+            "attendees": ["a@example.com"], "send_invites": false
+        });
+        let output = tool.execute(&args.to_string()).await.unwrap().output;
+        assert!(!output.contains("Invitations sent"), "{output}");
+        let request = requests.recv().await.unwrap();
+        let (head, body) = request.split_once("\r\n\r\n").unwrap();
+        assert!(head.starts_with("POST /calendars/primary/events?sendUpdates=none "), "{head}");
+        let body: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert!(body.get("conferenceData").is_none());
+        assert_eq!(body["start"], serde_json::json!({ "date": "2026-03-03" }));
     }
 }
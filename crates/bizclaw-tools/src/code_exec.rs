@@ -0,0 +1,348 @@
+//! Sandboxed code execution tool — run Python/JavaScript/Rust snippets.
+//!
+//! Two backends:
+//! - `docker`: a disposable `--rm` container per run, no network, capped memory.
+//!   The code is piped in on stdin and written to `/tmp` inside the container.
+//! - `wasm`: the `wasmtime` CLI runs a pre-compiled language runtime
+//!   (`python.wasm`, `javascript.wasm`) with no directories preopened.
+
+use async_trait::async_trait;
+use bizclaw_core::config::BizClawConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+
+/// Maximum bytes of stdout/stderr returned to the agent.
+const MAX_OUTPUT_BYTES: usize = 64 * 1024;
+
+/// Code execution configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CodeExecConfig {
+    /// Backend: "docker" or "wasm"
+    #[serde(default = "default_backend")]
+    pub backend: String,
+    /// Wall-clock limit per run (seconds)
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+    /// Memory limit per run (MB)
+    #[serde(default = "default_memory_mb")]
+    pub memory_mb: u64,
+    /// Reject code that touches the filesystem or spawns processes
+    #[serde(default = "default_true")]
+    pub workspace_only: bool,
+    /// Directory holding `python.wasm` / `javascript.wasm` for the wasm backend
+    #[serde(default = "default_wasm_dir")]
+    pub wasm_dir: String,
+}
+
+fn default_backend() -> String { "docker".into() }
+fn default_timeout_secs() -> u64 { 10 }
+fn default_memory_mb() -> u64 { 50 }
+fn default_true() -> bool { true }
+fn default_wasm_dir() -> String { "~/.bizclaw/wasm".into() }
+
+impl Default for CodeExecConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_backend(),
+            timeout_secs: default_timeout_secs(),
+            memory_mb: default_memory_mb(),
+            workspace_only: true,
+            wasm_dir: default_wasm_dir(),
+        }
+    }
+}
+
+impl CodeExecConfig {
+    /// Derive settings from the root config (`runtime.code_exec_backend`, `autonomy.workspace_only`).
+    pub fn from_config(config: &BizClawConfig) -> Self {
+        Self {
+            backend: config.runtime.code_exec_backend.clone(),
+            workspace_only: config.autonomy.workspace_only,
+            ..Self::default()
+        }
+    }
+}
+
+/// Supported snippet languages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Python,
+    JavaScript,
+    Rust,
+}
+
+impl Language {
+    fn parse(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "python" | "py" => Some(Self::Python),
+            "javascript" | "js" | "node" => Some(Self::JavaScript),
+            "rust" | "rs" => Some(Self::Rust),
+            _ => None,
+        }
+    }
+
+    fn docker_image(self) -> &'static str {
+        match self {
+            Self::Python => "python:3.12-alpine",
+            Self::JavaScript => "node:20-alpine",
+            Self::Rust => "rust:1-alpine",
+        }
+    }
+
+    /// Shell script run inside the container: save stdin as the source file, then run it
+    /// with the user's stdin (passed via `BIZCLAW_STDIN`).
+    fn docker_script(self) -> &'static str {
+        match self {
+            Self::Python => "cat > /tmp/main.py && printf '%s' \"$BIZCLAW_STDIN\" | python3 /tmp/main.py",
+            Self::JavaScript => "cat > /tmp/main.js && printf '%s' \"$BIZCLAW_STDIN\" | node /tmp/main.js",
+            Self::Rust => "cat > /tmp/main.rs && rustc -O -o /tmp/main /tmp/main.rs && printf '%s' \"$BIZCLAW_STDIN\" | /tmp/main",
+        }
+    }
+
+    /// Runtime module and the flag that evaluates inline source.
+    fn wasm_runtime(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Self::Python => Some(("python.wasm", "-c")),
+            Self::JavaScript => Some(("javascript.wasm", "-e")),
+            Self::Rust => None,
+        }
+    }
+
+    /// Patterns that indicate filesystem or process access.
+    fn forbidden_patterns(self) -> &'static [&'static str] {
+        match self {
+            Self::Python => &[
+                r"\bopen\s*\(", r"\bimport\s+(os|shutil|subprocess|pathlib|glob|io)\b",
+                r"\bfrom\s+(os|shutil|subprocess|pathlib|glob|io)\b", r"__import__",
+                r"\b(exec|eval|compile)\s*\(",
+            ],
+            Self::JavaScript => &[
+                r#"require\s*\(\s*['"](node:)?(fs|child_process|path|os)['"]"#,
+                r#"\bfrom\s+['"](node:)?(fs|child_process|path|os)['"]"#,
+                r"\bimport\s*\(", r"\bprocess\.(binding|dlopen)\b", r"\beval\s*\(",
+            ],
+            Self::Rust => &[
+                r"std::fs\b", r"std::process\b", r"\bFile::", r"\bCommand::",
+                r"include_(str|bytes)!", r"\bunsafe\b",
+            ],
+        }
+    }
+}
+
+/// Outcome of a sandboxed run.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExecOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub exit_code: i32,
+    pub duration_ms: u64,
+}
+
+/// Sandboxed code execution tool.
+pub struct CodeExecTool {
+    config: CodeExecConfig,
+}
+
+impl CodeExecTool {
+    pub fn new(config: CodeExecConfig) -> Self {
+        Self { config }
+    }
+
+    /// Static check for filesystem/process access when `workspace_only` is on.
+    fn check_code(&self, language: Language, code: &str) -> Result<()> {
+        if !self.config.workspace_only {
+            return Ok(());
+        }
+        for pattern in language.forbidden_patterns() {
+            let re = regex::Regex::new(pattern)
+                .map_err(|e| BizClawError::Tool(format!("Invalid pattern: {e}")))?;
+            if let Some(m) = re.find(code) {
+                return Err(BizClawError::PermissionDenied(format!(
+                    "code_exec: '{}' is not allowed while autonomy.workspace_only is enabled",
+                    m.as_str().trim()
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    async fn run_docker(&self, language: Language, code: &str, stdin: &str) -> Result<ExecOutput> {
+        let name = format!("bizclaw-exec-{}", uuid::Uuid::new_v4().simple());
+        let mut cmd = tokio::process::Command::new("docker");
+        cmd.args(["run", "--rm", "-i", "--name", &name])
+            .args(["--network", "none"])
+            .arg(format!("--memory={}m", self.config.memory_mb))
+            .arg(format!("--memory-swap={}m", self.config.memory_mb))
+            .args(["--pids-limit", "64", "--cpus", "1"])
+            .args(["--read-only", "--tmpfs", "/tmp:rw,exec,size=64m"])
+            .args(["-e", &format!("BIZCLAW_STDIN={stdin}")])
+            .arg(language.docker_image())
+            .args(["sh", "-c", language.docker_script()]);
+
+        let result = self.run_process(cmd, Some(code)).await;
+        if matches!(result, Err(BizClawError::Timeout(_))) {
+            // The CLI was killed; make sure the container goes too.
+            let _ = tokio::process::Command::new("docker")
+                .args(["kill", &name])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await;
+        }
+        result
+    }
+
+    async fn run_wasm(&self, language: Language, code: &str, stdin: &str) -> Result<ExecOutput> {
+        let (module, eval_flag) = language.wasm_runtime().ok_or_else(|| {
+            BizClawError::Tool("Rust snippets need the docker backend (runtime.code_exec_backend = \"docker\")".into())
+        })?;
+        let module_path = PathBuf::from(shellexpand::tilde(&self.config.wasm_dir).to_string()).join(module);
+        if !module_path.exists() {
+            return Err(BizClawError::Tool(format!("WASM runtime not found: {}", module_path.display())));
+        }
+
+        let mut cmd = tokio::process::Command::new("wasmtime");
+        cmd.arg("run")
+            .arg("-W")
+            .arg(format!("max-memory-size={}", self.config.memory_mb * 1024 * 1024))
+            .arg(&module_path)
+            .args([eval_flag, code]);
+
+        self.run_process(cmd, Some(stdin)).await
+    }
+
+    /// Spawn, feed stdin, and collect output under the configured timeout.
+    async fn run_process(&self, mut cmd: tokio::process::Command, stdin: Option<&str>) -> Result<ExecOutput> {
+        cmd.stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        let start = Instant::now();
+        let mut child = cmd.spawn()
+            .map_err(|e| BizClawError::Tool(format!("Failed to start sandbox: {e}")))?;
+
+        if let Some(mut pipe) = child.stdin.take() {
+            let input = stdin.unwrap_or("").to_string();
+            tokio::spawn(async move {
+                let _ = pipe.write_all(input.as_bytes()).await;
+            });
+        }
+
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| BizClawError::Timeout(format!("code_exec exceeded {}s", self.config.timeout_secs)))?
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+
+        Ok(ExecOutput {
+            stdout: truncate_output(&output.stdout),
+            stderr: truncate_output(&output.stderr),
+            exit_code: output.status.code().unwrap_or(-1),
+            duration_ms: start.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+impl Default for CodeExecTool {
+    fn default() -> Self { Self::new(CodeExecConfig::default()) }
+}
+
+fn truncate_output(bytes: &[u8]) -> String {
+    let s = String::from_utf8_lossy(bytes);
+    if s.len() <= MAX_OUTPUT_BYTES {
+        return s.into_owned();
+    }
+    let mut end = MAX_OUTPUT_BYTES;
+    while !s.is_char_boundary(end) { end -= 1; }
+    format!("{}\n... [truncated {} bytes]", &s[..end], s.len() - end)
+}
+
+#[async_trait]
+impl Tool for CodeExecTool {
+    fn name(&self) -> &str { "code_exec" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "code_exec".into(),
+            description: "Run a Python, JavaScript, or Rust snippet in a sandbox and return stdout, stderr, exit code, and duration.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["run"] },
+                    "language": { "type": "string", "enum": ["python", "javascript", "rust"] },
+                    "code": { "type": "string", "description": "Source code to run" },
+                    "stdin": { "type": "string", "description": "Optional input piped to the program" }
+                },
+                "required": ["action", "language", "code"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+
+        let action = args["action"].as_str().unwrap_or("run");
+        if action != "run" {
+            return Err(BizClawError::Tool(format!("Unknown action: {action}")));
+        }
+        let language_str = args["language"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'language'".into()))?;
+        let language = Language::parse(language_str)
+            .ok_or_else(|| BizClawError::Tool(format!("Unsupported language: {language_str}")))?;
+        let code = args["code"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'code'".into()))?;
+        let stdin = args["stdin"].as_str().unwrap_or("");
+
+        self.check_code(language, code)?;
+
+        let output = match self.config.backend.as_str() {
+            "docker" => self.run_docker(language, code, stdin).await?,
+            "wasm" => self.run_wasm(language, code, stdin).await?,
+            other => return Err(BizClawError::Tool(format!("Unknown code_exec backend: {other}"))),
+        };
+
+        let text = serde_json::to_string(&output).unwrap_or_default();
+        let result = if output.exit_code == 0 {
+            ToolResult::ok(text)
+        } else {
+            ToolResult::failure("exit_status", text)
+        };
+        Ok(result.with_data(serde_json::json!(output)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_only_rejects_fs_access() {
+        let tool = CodeExecTool::default();
+        assert!(tool.check_code(Language::Python, "print(open('/etc/passwd').read())").is_err());
+        assert!(tool.check_code(Language::Python, "import subprocess").is_err());
+        assert!(tool.check_code(Language::JavaScript, "const fs = require('fs')").is_err());
+        assert!(tool.check_code(Language::Rust, "fn main() { std::fs::read(\"x\"); }").is_err());
+        assert!(tool.check_code(Language::Python, "print(sum(range(10)))").is_ok());
+    }
+
+    #[test]
+    fn test_workspace_only_disabled_allows_all() {
+        let tool = CodeExecTool::new(CodeExecConfig { workspace_only: false, ..Default::default() });
+        assert!(tool.check_code(Language::Python, "import os").is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_rust_unsupported_on_wasm() {
+        let tool = CodeExecTool::new(CodeExecConfig { backend: "wasm".into(), ..Default::default() });
+        let args = r#"{"action":"run","language":"rust","code":"fn main(){}"}"#;
+        assert!(tool.execute(args).await.is_err());
+    }
+}
@@ -190,10 +190,6 @@ impl Tool for DocumentReaderTool {
             content.push_str("\n\n[... TEXT TRUNCATED DUE TO LENGTH LIMIT ...]");
         }
 
-        Ok(ToolResult {
-            tool_call_id: String::new(),
-            output: format!("Extracted content from {}:\n\n{}", path.display(), content),
-            success: true,
-        })
+        Ok(ToolResult::ok(format!("Extracted content from {}:\n\n{}", path.display(), content)))
     }
 }
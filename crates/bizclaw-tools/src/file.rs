@@ -1,14 +1,56 @@
 //! File read/write tool.
+//!
+//! With an autonomy policy attached, paths are canonicalized (following
+//! symlinks and `..`) and checked against `forbidden_paths` and, when
+//! `workspace_only` is set, the workspace root. At autonomy level "approval"
+//! a refused path waits for a human to approve it instead.
+//!
+//! Besides whole-file reads and writes, the edit actions (`replace`,
+//! `replace_lines`, `append`) change part of a text file in place and return
+//! a unified diff of what changed. Edits are written to a temp file and
+//! renamed over the original.
 
 use async_trait::async_trait;
+use bizclaw_core::config::AutonomyConfig;
 use bizclaw_core::error::Result;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_security::allowlist::Allowlist;
+use bizclaw_security::approval::Approver;
+use std::ops::Range;
+use std::path::Path;
 
-pub struct FileTool;
+/// Files larger than this are refused by the edit actions.
+const MAX_EDIT_BYTES: u64 = 1024 * 1024;
+/// Unchanged lines shown around each hunk of an edit's diff.
+const DIFF_CONTEXT: usize = 3;
+
+pub struct FileTool {
+    /// Path policy; `None` means unrestricted.
+    allowlist: Option<Allowlist>,
+    /// Asked about paths the allowlist refuses; `None` refuses them.
+    approver: Option<Approver>,
+}
 
 impl FileTool {
-    pub fn new() -> Self { Self }
+    pub fn new() -> Self { Self { allowlist: None, approver: None } }
+
+    /// Enforce `forbidden_paths` and `workspace_only` from the autonomy config.
+    /// At level "approval" refused paths go to the global approval broker.
+    pub fn with_autonomy(autonomy: &AutonomyConfig) -> Self {
+        Self { allowlist: Some(Allowlist::new(autonomy)), approver: Approver::from_autonomy(autonomy) }
+    }
+
+    /// Use an explicit policy (e.g. one with a custom workspace).
+    pub fn with_allowlist(allowlist: Allowlist) -> Self {
+        Self { allowlist: Some(allowlist), approver: None }
+    }
+
+    /// Ask `approver` about refused paths instead of refusing them.
+    pub fn with_approver(mut self, approver: Approver) -> Self {
+        self.approver = Some(approver);
+        self
+    }
 }
 
 impl Default for FileTool {
@@ -22,13 +64,20 @@ impl Tool for FileTool {
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "file".into(),
-            description: "Read or write files.".into(),
+            description: "Read, write or edit files. Prefer the edit actions over rewriting a whole file: \
+                replace swaps one exact occurrence of old_text (or all with replace_all), replace_lines \
+                replaces lines start_line..=end_line, append adds content at the end.".into(),
             parameters: serde_json::json!({
                 "type": "object",
                 "properties": {
-                    "action": { "type": "string", "enum": ["read", "write", "list"] },
+                    "action": { "type": "string", "enum": ["read", "write", "list", "replace", "replace_lines", "append"] },
                     "path": { "type": "string" },
-                    "content": { "type": "string" }
+                    "content": { "type": "string", "description": "New file content (write), replacement lines (replace_lines) or text to add (append)" },
+                    "old_text": { "type": "string", "description": "Exact text to find (replace)" },
+                    "new_text": { "type": "string", "description": "Text to put in its place (replace)" },
+                    "replace_all": { "type": "boolean", "description": "Replace every occurrence instead of requiring exactly one (replace)" },
+                    "start_line": { "type": "integer", "description": "First line to replace, from 1 (replace_lines)" },
+                    "end_line": { "type": "integer", "description": "Last line to replace, inclusive (replace_lines)" }
                 },
                 "required": ["action", "path"]
             }),
@@ -40,9 +89,29 @@ impl Tool for FileTool {
             .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
 
         let action = args["action"].as_str().unwrap_or("read");
-        let path = args["path"].as_str()
+        let requested = args["path"].as_str()
             .ok_or_else(|| bizclaw_core::error::BizClawError::Tool("Missing 'path'".into()))?;
 
+        // Operate on the resolved path so a symlink swapped in later can't redirect us.
+        let resolved = match &self.allowlist {
+            Some(allowlist) => match allowlist.check_path(requested) {
+                Ok(resolved) => resolved,
+                Err(denial) => {
+                    let Some(approver) = &self.approver else {
+                        denial.audit("file");
+                        return Ok(ToolResult::failure("permission_denied", denial.to_json("file").to_string()));
+                    };
+                    if let Err(decision) = approver.approve("file", arguments, &denial).await {
+                        let json = Approver::denied_json("file", &denial, &decision);
+                        return Ok(ToolResult::failure("approval_denied", json.to_string()));
+                    }
+                    allowlist.resolve(requested)
+                }
+            },
+            None => requested.into(),
+        };
+        let path = resolved.as_path();
+
         let result = match action {
             "read" => {
                 tokio::fs::read_to_string(path).await
@@ -52,8 +121,9 @@ impl Tool for FileTool {
                 let content = args["content"].as_str().unwrap_or("");
                 tokio::fs::write(path, content).await
                     .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
-                format!("Written {} bytes to {path}", content.len())
+                format!("Written {} bytes to {requested}", content.len())
             }
+            "replace" | "replace_lines" | "append" => return edit(action, &args, requested, path).await,
             "list" => {
                 let mut entries = tokio::fs::read_dir(path).await
                     .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
@@ -67,10 +137,429 @@ impl Tool for FileTool {
             _ => return Err(bizclaw_core::error::BizClawError::Tool(format!("Unknown action: {action}"))),
         };
 
-        Ok(ToolResult {
-            tool_call_id: String::new(),
-            output: result,
-            success: true,
-        })
+        Ok(ToolResult::ok(result))
+    }
+}
+
+/// One replacement of `old[start..end]` with `text`.
+struct Splice {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+/// Lines (0-based, half-open) an edit replaced in the old text and the lines
+/// that replaced them in the new one.
+#[derive(Debug, Clone, PartialEq)]
+struct Change {
+    old: Range<usize>,
+    new: Range<usize>,
+}
+
+/// Run an edit action on `path` and report the diff.
+async fn edit(action: &str, args: &serde_json::Value, requested: &str, path: &Path) -> Result<ToolResult> {
+    let io_err = |e: std::io::Error| bizclaw_core::error::BizClawError::Tool(format!("{requested}: {e}"));
+
+    let old = match tokio::fs::metadata(path).await {
+        Ok(meta) if meta.len() > MAX_EDIT_BYTES => {
+            return Ok(ToolResult::failure("too_large", format!(
+                "{requested} is {} bytes; files over {MAX_EDIT_BYTES} bytes can't be edited", meta.len()
+            )));
+        }
+        Ok(_) => {
+            let bytes = tokio::fs::read(path).await.map_err(io_err)?;
+            match String::from_utf8(bytes) {
+                Ok(text) if !text.contains('\0') => text,
+                _ => return Ok(ToolResult::failure("binary_file", format!("{requested} is not a UTF-8 text file"))),
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && action == "append" => String::new(),
+        Err(e) => return Err(io_err(e)),
+    };
+
+    let splices = match plan_edit(action, args, &old) {
+        Ok(splices) => splices,
+        Err((kind, message)) => return Ok(ToolResult::failure(kind, message)),
+    };
+    let (new, changes) = apply(&old, &splices);
+    if new == old {
+        return Ok(ToolResult::ok(format!("{requested} already has that content; nothing changed")));
+    }
+
+    write_atomic(path, &new).await.map_err(io_err)?;
+    let diff = unified_diff(requested, &old, &new, &changes);
+    Ok(ToolResult::ok(format!("Edited {requested} ({} change(s))\n\n{diff}", splices.len()))
+        .with_data(serde_json::json!({ "path": requested, "changes": splices.len(), "diff": diff })))
+}
+
+/// Work out what `action` replaces in `old`, or why it can't (error kind and
+/// message). Text arguments get the file's CRLF line endings when it uses them.
+fn plan_edit(action: &str, args: &serde_json::Value, old: &str) -> std::result::Result<Vec<Splice>, (&'static str, String)> {
+    let crlf = old.contains("\r\n");
+    let eol = if crlf { "\r\n" } else { "\n" };
+    let text = |name: &str| {
+        let value = args[name].as_str().unwrap_or("");
+        if crlf && !value.contains('\r') { value.replace('\n', "\r\n") } else { value.to_string() }
+    };
+    let invalid = |msg: String| ("invalid_arguments", msg);
+
+    match action {
+        "replace" => {
+            let needle = text("old_text");
+            if needle.is_empty() {
+                return Err(invalid("'old_text' is required and must not be empty".into()));
+            }
+            let replacement = text("new_text");
+            let starts: Vec<usize> = old.match_indices(&needle).map(|(i, _)| i).collect();
+            if starts.is_empty() {
+                return Err(("no_match", "'old_text' does not appear in the file; read it again and copy the text exactly".into()));
+            }
+            if starts.len() > 1 && !args["replace_all"].as_bool().unwrap_or(false) {
+                let lines: Vec<String> = starts.iter().map(|&i| (old[..i].matches('\n').count() + 1).to_string()).collect();
+                return Err(("ambiguous_match", format!(
+                    "'old_text' appears {} times (lines {}); include more surrounding text, or set replace_all",
+                    starts.len(), lines.join(", ")
+                )));
+            }
+            Ok(starts.into_iter()
+                .map(|start| Splice { start, end: start + needle.len(), text: replacement.clone() })
+                .collect())
+        }
+        "replace_lines" => {
+            let lines: Vec<&str> = old.split_inclusive('\n').collect();
+            let first = args["start_line"].as_u64().unwrap_or(0) as usize;
+            let last = args["end_line"].as_u64().map(|l| l as usize).unwrap_or(first);
+            if first == 0 || last < first || last > lines.len() {
+                return Err(invalid(format!(
+                    "line range {first}..={last} is outside the file's {} line(s) (lines count from 1)", lines.len()
+                )));
+            }
+            let start: usize = lines[..first - 1].iter().map(|l| l.len()).sum();
+            let end = start + lines[first - 1..last].iter().map(|l| l.len()).sum::<usize>();
+            let mut content = text("content");
+            // Keep the line break the replaced block ended with.
+            if !content.is_empty() && !content.ends_with('\n') && old[..end].ends_with('\n') {
+                content.push_str(eol);
+            }
+            Ok(vec![Splice { start, end, text: content }])
+        }
+        "append" => {
+            let mut content = text("content");
+            if !old.is_empty() && !old.ends_with('\n') {
+                content.insert_str(0, eol);
+            }
+            Ok(vec![Splice { start: old.len(), end: old.len(), text: content }])
+        }
+        _ => unreachable!("not an edit action: {action}"),
+    }
+}
+
+/// Apply `splices` (sorted, non-overlapping) to `old`; returns the new text
+/// and the line ranges that changed.
+fn apply(old: &str, splices: &[Splice]) -> (String, Vec<Change>) {
+    let mut new = String::with_capacity(old.len());
+    let mut spans = Vec::with_capacity(splices.len());
+    let mut pos = 0;
+    for splice in splices {
+        new.push_str(&old[pos..splice.start]);
+        let start = new.len();
+        new.push_str(&splice.text);
+        spans.push((splice.start..splice.end, start..new.len()));
+        pos = splice.end;
+    }
+    new.push_str(&old[pos..]);
+
+    let (old_lines, new_lines) = (LineIndex::new(old), LineIndex::new(&new));
+    let mut changes: Vec<Change> = Vec::new();
+    for (old_span, new_span) in spans {
+        let change = Change { old: old_lines.covering(old_span), new: new_lines.covering(new_span) };
+        match changes.last_mut() {
+            // Splices on the same or adjacent lines make one change.
+            Some(last) if change.old.start <= last.old.end => {
+                last.old.end = last.old.end.max(change.old.end);
+                last.new.end = last.new.end.max(change.new.end);
+            }
+            _ => changes.push(change),
+        }
+    }
+
+    // Drop lines a change covers but left as they were.
+    let (a, b): (Vec<&str>, Vec<&str>) = (old.split_inclusive('\n').collect(), new.split_inclusive('\n').collect());
+    for change in &mut changes {
+        while !change.old.is_empty() && !change.new.is_empty() && a[change.old.start] == b[change.new.start] {
+            change.old.start += 1;
+            change.new.start += 1;
+        }
+        while !change.old.is_empty() && !change.new.is_empty() && a[change.old.end - 1] == b[change.new.end - 1] {
+            change.old.end -= 1;
+            change.new.end -= 1;
+        }
+    }
+    changes.retain(|c| !c.old.is_empty() || !c.new.is_empty());
+    (new, changes)
+}
+
+/// Byte offset to line number lookups.
+struct LineIndex {
+    /// Offset just past each `\n`, after a leading 0.
+    starts: Vec<usize>,
+    lines: usize,
+}
+
+impl LineIndex {
+    fn new(text: &str) -> Self {
+        let starts: Vec<usize> = std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+        let lines = text.split_inclusive('\n').count();
+        Self { starts, lines }
+    }
+
+    /// The lines holding `bytes`, including the one its end falls on.
+    fn covering(&self, bytes: Range<usize>) -> Range<usize> {
+        let line_of = |offset: usize| self.starts.partition_point(|&s| s <= offset) - 1;
+        let start = line_of(bytes.start).min(self.lines);
+        start..(line_of(bytes.end) + 1).min(self.lines).max(start)
+    }
+}
+
+/// A unified diff of `changes` between `old` and `new`.
+fn unified_diff(path: &str, old: &str, new: &str, changes: &[Change]) -> String {
+    use std::fmt::Write;
+
+    let (a, b): (Vec<&str>, Vec<&str>) = (old.split_inclusive('\n').collect(), new.split_inclusive('\n').collect());
+    let show = |line: &str| line.trim_end_matches('\n').trim_end_matches('\r').to_string();
+    let position = |start: usize, len: usize| if len == 0 { format!("{start},0") } else { format!("{},{len}", start + 1) };
+
+    let mut out = format!("--- a/{path}\n+++ b/{path}\n");
+    let mut i = 0;
+    while i < changes.len() {
+        // Changes whose context would touch go in one hunk.
+        let mut j = i;
+        while j + 1 < changes.len() && changes[j + 1].old.start <= changes[j].old.end + 2 * DIFF_CONTEXT {
+            j += 1;
+        }
+        let (first, last) = (&changes[i], &changes[j]);
+        let old_start = first.old.start.saturating_sub(DIFF_CONTEXT);
+        let old_end = (last.old.end + DIFF_CONTEXT).min(a.len());
+        let new_start = first.new.start - (first.old.start - old_start);
+        let new_end = last.new.end + (old_end - last.old.end);
+        let _ = writeln!(
+            out, "@@ -{} +{} @@",
+            position(old_start, old_end - old_start), position(new_start, new_end - new_start)
+        );
+
+        let mut line = old_start;
+        for change in &changes[i..=j] {
+            for context in &a[line..change.old.start] { let _ = writeln!(out, " {}", show(context)); }
+            for removed in &a[change.old.clone()] { let _ = writeln!(out, "-{}", show(removed)); }
+            for added in &b[change.new.clone()] { let _ = writeln!(out, "+{}", show(added)); }
+            line = change.old.end;
+        }
+        for context in &a[line..old_end] { let _ = writeln!(out, " {}", show(context)); }
+        i = j + 1;
+    }
+    out
+}
+
+/// Replace `path` with `content` through a temp file in the same directory,
+/// so the file is never seen half-written.
+async fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let tmp = path.with_file_name(format!(".{name}.{}.tmp", uuid::Uuid::new_v4().simple()));
+    tokio::fs::write(&tmp, content).await?;
+    if let Ok(meta) = tokio::fs::metadata(path).await {
+        let _ = tokio::fs::set_permissions(&tmp, meta.permissions()).await;
+    }
+    if let Err(e) = tokio::fs::rename(&tmp, path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(e);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn workspace(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bizclaw-file-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(dir.join("private")).unwrap();
+        dir.canonicalize().unwrap()
+    }
+
+    fn tool(ws: &std::path::Path) -> FileTool {
+        let autonomy = AutonomyConfig {
+            forbidden_paths: vec![ws.join("private").to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        FileTool::with_allowlist(Allowlist::new(&autonomy).with_workspace(ws))
+    }
+
+    async fn run(tool: &FileTool, action: &str, path: &str) -> ToolResult {
+        let args = serde_json::json!({ "action": action, "path": path, "content": "x" });
+        tool.execute(&args.to_string()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_write_and_read_in_workspace() {
+        let ws = workspace("rw");
+        let tool = tool(&ws);
+        assert!(run(&tool, "write", "note.txt").await.success);
+        assert_eq!(run(&tool, "read", "note.txt").await.output, "x");
+    }
+
+    #[tokio::test]
+    async fn test_traversal_and_forbidden_denied() {
+        let ws = workspace("deny");
+        let tool = tool(&ws);
+        for path in ["../../etc/passwd", "/etc/hostname", "private/key", "a/../private/key"] {
+            let result = run(&tool, "read", path).await;
+            assert!(!result.success, "{path} should be denied");
+            assert!(result.output.contains("permission_denied"));
+        }
+    }
+
+    async fn edit(tool: &FileTool, args: serde_json::Value) -> ToolResult {
+        tool.execute(&args.to_string()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_replace_requires_unique_match() {
+        let ws = workspace("replace");
+        let tool = tool(&ws);
+        std::fs::write(ws.join("app.toml"), "name = \"shop\"\nport = 80\n# port = 80\n").unwrap();
+
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace", "path": "app.toml", "old_text": "port = 80", "new_text": "port = 8080"
+        })).await;
+        assert_eq!(result.error_kind.as_deref(), Some("ambiguous_match"));
+        assert!(result.output.contains("lines 2, 3"), "{}", result.output);
+
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace", "path": "app.toml", "old_text": "missing", "new_text": "x"
+        })).await;
+        assert_eq!(result.error_kind.as_deref(), Some("no_match"));
+
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace", "path": "app.toml", "old_text": "\nport = 80", "new_text": "\nport = 8080"
+        })).await;
+        assert!(result.success, "{}", result.output);
+        assert_eq!(std::fs::read_to_string(ws.join("app.toml")).unwrap(), "name = \"shop\"\nport = 8080\n# port = 80\n");
+        assert_eq!(
+            result.data["diff"],
+            "--- a/app.toml\n+++ b/app.toml\n@@ -1,3 +1,3 @@\n name = \"shop\"\n-port = 80\n+port = 8080\n # port = 80\n"
+        );
+
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace", "path": "app.toml", "old_text": "port", "new_text": "listen", "replace_all": true
+        })).await;
+        assert_eq!(result.data["changes"], 2);
+        assert_eq!(std::fs::read_to_string(ws.join("app.toml")).unwrap(), "name = \"shop\"\nlisten = 8080\n# listen = 80\n");
+        assert!(result.output.ends_with("@@ -1,3 +1,3 @@\n name = \"shop\"\n-port = 8080\n-# port = 80\n+listen = 8080\n+# listen = 80\n"), "{}", result.output);
+    }
+
+    #[tokio::test]
+    async fn test_multibyte_utf8() {
+        let ws = workspace("utf8");
+        let tool = tool(&ws);
+        std::fs::write(ws.join("menu.txt"), "Phở bò 🍜\nBún chả\nCà phê sữa đá\n").unwrap();
+
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace", "path": "menu.txt", "old_text": "bò 🍜", "new_text": "gà 🐔"
+        })).await;
+        assert!(result.success, "{}", result.output);
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace_lines", "path": "menu.txt", "start_line": 2, "end_line": 3, "content": "Bánh mì"
+        })).await;
+        assert!(result.success, "{}", result.output);
+        assert_eq!(std::fs::read_to_string(ws.join("menu.txt")).unwrap(), "Phở gà 🐔\nBánh mì\n");
+        assert!(result.output.contains("@@ -1,3 +1,2 @@\n Phở gà 🐔\n-Bún chả\n-Cà phê sữa đá\n+Bánh mì\n"), "{}", result.output);
+
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace_lines", "path": "menu.txt", "start_line": 3, "end_line": 3, "content": "x"
+        })).await;
+        assert_eq!(result.error_kind.as_deref(), Some("invalid_arguments"));
+    }
+
+    #[tokio::test]
+    async fn test_crlf_file() {
+        let ws = workspace("crlf");
+        let tool = tool(&ws);
+        std::fs::write(ws.join("notes.txt"), "one\r\ntwo\r\nthree").unwrap();
+
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace", "path": "notes.txt", "old_text": "one\ntwo", "new_text": "1\n2"
+        })).await;
+        assert!(result.success, "{}", result.output);
+        let result = edit(&tool, serde_json::json!({
+            "action": "replace_lines", "path": "notes.txt", "start_line": 2, "content": "deux"
+        })).await;
+        assert!(result.success, "{}", result.output);
+        edit(&tool, serde_json::json!({ "action": "append", "path": "notes.txt", "content": "four\n" })).await;
+        assert_eq!(std::fs::read_to_string(ws.join("notes.txt")).unwrap(), "1\r\ndeux\r\nthree\r\nfour\r\n");
+    }
+
+    #[tokio::test]
+    async fn test_edit_refuses_binary_large_and_forbidden() {
+        let ws = workspace("refuse");
+        let tool = tool(&ws);
+        std::fs::write(ws.join("logo.png"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        let result = edit(&tool, serde_json::json!({ "action": "append", "path": "logo.png", "content": "x" })).await;
+        assert_eq!(result.error_kind.as_deref(), Some("binary_file"));
+
+        std::fs::write(ws.join("big.log"), "a".repeat(MAX_EDIT_BYTES as usize + 1)).unwrap();
+        let result = edit(&tool, serde_json::json!({ "action": "append", "path": "big.log", "content": "x" })).await;
+        assert_eq!(result.error_kind.as_deref(), Some("too_large"));
+
+        let result = edit(&tool, serde_json::json!({ "action": "append", "path": "private/key", "content": "x" })).await;
+        assert_eq!(result.error_kind.as_deref(), Some("permission_denied"));
+        assert!(!ws.join("private/key").exists());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_escape_denied() {
+        let ws = workspace("link");
+        let link = ws.join("out");
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink("/etc", &link).unwrap();
+
+        let result = run(&tool(&ws), "write", "out/evil.conf").await;
+        assert!(!result.success);
+        assert!(!std::path::Path::new("/etc/evil.conf").exists());
+    }
+
+    #[tokio::test]
+    async fn test_refused_path_waits_for_approval() {
+        use bizclaw_security::approval::ApprovalBroker;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let ws = workspace("approval");
+        let broker = Arc::new(ApprovalBroker::new());
+        let tool = tool(&ws).with_approver(Approver::new(broker.clone(), Duration::from_secs(5)));
+
+        assert!(run(&tool, "write", "note.txt").await.success);
+        assert!(broker.pending().is_empty());
+
+        let mut events = broker.subscribe();
+        let approver = tokio::spawn({
+            let broker = broker.clone();
+            async move {
+                let first = events.recv().await.unwrap();
+                assert!(first.reason.contains("forbidden path"));
+                broker.resolve(&first.id, true, "ops");
+                let second = events.recv().await.unwrap();
+                broker.resolve(&second.id, false, "ops");
+            }
+        });
+        assert!(run(&tool, "write", "private/approved.txt").await.success);
+        assert_eq!(std::fs::read_to_string(ws.join("private/approved.txt")).unwrap(), "x");
+
+        let denied = run(&tool, "write", "private/denied.txt").await;
+        assert_eq!(denied.error_kind.as_deref(), Some("approval_denied"));
+        assert!(denied.output.contains("denied by ops"));
+        assert!(!ws.join("private/denied.txt").exists());
+        approver.await.unwrap();
     }
 }
@@ -0,0 +1,378 @@
+//! Git Tool — clone, inspect, and commit to repositories via libgit2.
+//!
+//! Every `path` is checked like the file tool's: inside the workspace when
+//! `autonomy.workspace_only` is enabled (after following symlinks) and
+//! never under `forbidden_paths`. Only `https://` and `ssh://` (or
+//! `user@host:path`) remotes can be cloned, and `commit` additionally
+//! requires `autonomy.level = "full"`.
+
+use async_trait::async_trait;
+use bizclaw_core::config::{AutonomyConfig, BizClawConfig};
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_security::allowlist::{Allowlist, Denial};
+use git2::{DiffFormat, DiffOptions, Repository, Signature, Status, StatusOptions};
+use std::path::{Path, PathBuf};
+
+/// Git tool configuration.
+#[derive(Debug, Clone, Default)]
+pub struct GitConfig {
+    /// Where repository paths are resolved, and what `workspace_only`
+    /// and `forbidden_paths` allow.
+    pub autonomy: AutonomyConfig,
+    /// Allow the `commit` action.
+    pub allow_commit: bool,
+}
+
+impl GitConfig {
+    /// Derive settings from the root config (`[autonomy]`; commits need
+    /// `autonomy.level = "full"`).
+    pub fn from_config(config: &BizClawConfig) -> Self {
+        Self {
+            autonomy: config.autonomy.clone(),
+            allow_commit: config.autonomy.level == "full",
+        }
+    }
+}
+
+pub struct GitTool {
+    allowlist: Allowlist,
+    allow_commit: bool,
+}
+
+impl GitTool {
+    pub fn new(config: GitConfig) -> Self {
+        Self { allowlist: Allowlist::new(&config.autonomy), allow_commit: config.allow_commit }
+    }
+
+    /// Use an explicit path policy (e.g. one with a custom workspace).
+    pub fn with_allowlist(mut self, allowlist: Allowlist) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    /// Resolve `path` against the workspace, following symlinks, and check
+    /// it against `workspace_only` and `forbidden_paths`.
+    fn resolve(&self, path: &str) -> std::result::Result<PathBuf, Denial> {
+        self.allowlist.check_path(path)
+    }
+}
+
+impl Default for GitTool {
+    fn default() -> Self { Self::new(GitConfig::default()) }
+}
+
+fn git_err(e: git2::Error) -> BizClawError {
+    BizClawError::Tool(format!("git: {}", e.message()))
+}
+
+/// Refuse anything but a network remote: `file://` URLs and local paths
+/// would copy any repository on this host into the workspace.
+fn check_remote(url: &str) -> Result<()> {
+    let lower = url.trim().to_ascii_lowercase();
+    let scp_like = !lower.contains("://")
+        && lower.split_once(':').is_some_and(|(host, _)| host.contains('@') && !host.contains('/'));
+    if lower.starts_with("https://") || lower.starts_with("ssh://") || scp_like {
+        Ok(())
+    } else {
+        Err(BizClawError::PermissionDenied(format!(
+            "git: only https:// and ssh:// remotes can be cloned, not '{url}'"
+        )))
+    }
+}
+
+fn clone_repo(url: &str, path: &Path) -> Result<String> {
+    check_remote(url)?;
+    let repo = Repository::clone(url, path).map_err(git_err)?;
+    let head = repo.head().ok().and_then(|h| h.shorthand().map(String::from));
+    Ok(format!(
+        "Cloned {url} into {} (branch: {})",
+        path.display(),
+        head.as_deref().unwrap_or("none")
+    ))
+}
+
+fn status(path: &Path) -> Result<serde_json::Value> {
+    let repo = Repository::open(path).map_err(git_err)?;
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true).recurse_untracked_dirs(true);
+    let statuses = repo.statuses(Some(&mut opts)).map_err(git_err)?;
+
+    let staged_mask = Status::INDEX_NEW | Status::INDEX_MODIFIED | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED | Status::INDEX_TYPECHANGE;
+    let modified_mask = Status::WT_MODIFIED | Status::WT_DELETED
+        | Status::WT_RENAMED | Status::WT_TYPECHANGE;
+
+    let (mut staged, mut modified, mut untracked) = (vec![], vec![], vec![]);
+    for entry in statuses.iter() {
+        let Some(file) = entry.path().map(String::from) else { continue };
+        let s = entry.status();
+        if s.intersects(staged_mask) {
+            staged.push(file.clone());
+        }
+        if s.intersects(modified_mask) {
+            modified.push(file.clone());
+        }
+        if s.contains(Status::WT_NEW) {
+            untracked.push(file);
+        }
+    }
+
+    let branch = repo.head().ok().and_then(|h| h.shorthand().map(String::from));
+    Ok(serde_json::json!({
+        "branch": branch,
+        "staged": staged,
+        "modified": modified,
+        "untracked": untracked,
+    }))
+}
+
+/// Unified diff of HEAD against the working tree (staged + unstaged).
+fn diff(path: &Path, file: Option<&str>) -> Result<String> {
+    let repo = Repository::open(path).map_err(git_err)?;
+    let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+
+    let mut opts = DiffOptions::new();
+    opts.include_untracked(true).show_untracked_content(true);
+    if let Some(f) = file {
+        opts.pathspec(f);
+    }
+    let diff = repo
+        .diff_tree_to_workdir_with_index(head_tree.as_ref(), Some(&mut opts))
+        .map_err(git_err)?;
+
+    let mut out = String::new();
+    diff.print(DiffFormat::Patch, |_, _, line| {
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            out.push(line.origin());
+        }
+        out.push_str(&String::from_utf8_lossy(line.content()));
+        true
+    })
+    .map_err(git_err)?;
+    Ok(out)
+}
+
+fn commit(path: &Path, message: &str, files: &[String]) -> Result<String> {
+    let repo = Repository::open(path).map_err(git_err)?;
+    let workdir = repo.workdir()
+        .ok_or_else(|| BizClawError::Tool("git: cannot commit in a bare repository".into()))?
+        .to_path_buf();
+
+    let mut index = repo.index().map_err(git_err)?;
+    for file in files {
+        let rel = Path::new(file);
+        if workdir.join(rel).exists() {
+            index.add_path(rel).map_err(git_err)?;
+        } else {
+            index.remove_path(rel).map_err(git_err)?;
+        }
+    }
+    index.write().map_err(git_err)?;
+
+    let tree_id = index.write_tree().map_err(git_err)?;
+    let tree = repo.find_tree(tree_id).map_err(git_err)?;
+    let sig = repo.signature()
+        .or_else(|_| Signature::now("BizClaw", "bizclaw@localhost"))
+        .map_err(git_err)?;
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+
+    let oid = repo.commit(Some("HEAD"), &sig, &sig, message, &tree, &parents)
+        .map_err(git_err)?;
+    Ok(format!("Committed {} file(s) as {}", files.len(), &oid.to_string()[..7]))
+}
+
+fn log(path: &Path, n: usize) -> Result<serde_json::Value> {
+    let repo = Repository::open(path).map_err(git_err)?;
+    let mut walk = repo.revwalk().map_err(git_err)?;
+    walk.push_head().map_err(git_err)?;
+
+    let mut commits = Vec::new();
+    for oid in walk.take(n) {
+        let c = repo.find_commit(oid.map_err(git_err)?).map_err(git_err)?;
+        let date = chrono::DateTime::from_timestamp(c.time().seconds(), 0)
+            .map(|d| d.to_rfc3339())
+            .unwrap_or_default();
+        commits.push(serde_json::json!({
+            "hash": c.id().to_string(),
+            "author": format!("{} <{}>", c.author().name().unwrap_or(""), c.author().email().unwrap_or("")),
+            "date": date,
+            "message": c.message().unwrap_or("").trim(),
+        }));
+    }
+    Ok(serde_json::Value::Array(commits))
+}
+
+fn create_branch(path: &Path, name: &str) -> Result<String> {
+    let repo = Repository::open(path).map_err(git_err)?;
+    let head = repo.head().and_then(|h| h.peel_to_commit()).map_err(git_err)?;
+    repo.branch(name, &head, false).map_err(git_err)?;
+    Ok(format!("Created branch {name} at {}", &head.id().to_string()[..7]))
+}
+
+#[async_trait]
+impl Tool for GitTool {
+    fn name(&self) -> &str { "git" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "git".into(),
+            description: "Git repository operations: clone, status, diff, commit, log, branch.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["clone", "status", "diff", "commit", "log", "branch"],
+                        "description": "Operation to perform"
+                    },
+                    "path": { "type": "string", "description": "Repository path (relative to the workspace)" },
+                    "url": { "type": "string", "description": "Remote URL (clone)" },
+                    "file": { "type": "string", "description": "Limit the diff to one file (diff)" },
+                    "message": { "type": "string", "description": "Commit message (commit)" },
+                    "files": { "type": "array", "items": { "type": "string" }, "description": "Files to stage (commit)" },
+                    "n": { "type": "integer", "description": "Number of commits (log, default 10)" },
+                    "name": { "type": "string", "description": "Branch name (branch)" }
+                },
+                "required": ["action", "path"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let action = args["action"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'action'".into()))?
+            .to_string();
+        let path = match self.resolve(args["path"].as_str().unwrap_or(".")) {
+            Ok(path) => path,
+            Err(denial) => {
+                denial.audit("git");
+                return Ok(ToolResult::failure("permission_denied", denial.to_json("git").to_string()));
+            }
+        };
+
+        if action == "commit" && !self.allow_commit {
+            return Err(BizClawError::PermissionDenied(
+                "git commit requires autonomy.level = \"full\"".into(),
+            ));
+        }
+
+        let output = tokio::task::spawn_blocking(move || -> Result<String> {
+            let str_arg = |key: &str| {
+                args[key].as_str()
+                    .map(String::from)
+                    .ok_or_else(|| BizClawError::Tool(format!("Missing '{key}'")))
+            };
+            match action.as_str() {
+                "clone" => clone_repo(&str_arg("url")?, &path),
+                "status" => Ok(status(&path)?.to_string()),
+                "diff" => diff(&path, args["file"].as_str()),
+                "commit" => {
+                    let files: Vec<String> = args["files"].as_array()
+                        .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+                        .unwrap_or_default();
+                    if files.is_empty() {
+                        return Err(BizClawError::Tool("Missing 'files'".into()));
+                    }
+                    commit(&path, &str_arg("message")?, &files)
+                }
+                "log" => Ok(log(&path, args["n"].as_u64().unwrap_or(10) as usize)?.to_string()),
+                "branch" => create_branch(&path, &str_arg("name")?),
+                other => Err(BizClawError::Tool(format!("Unknown git action: {other}"))),
+            }
+        })
+        .await
+        .map_err(|e| BizClawError::Tool(format!("git task failed: {e}")))??;
+
+        Ok(ToolResult::ok(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_in(dir: &Path, allow_commit: bool) -> GitTool {
+        let autonomy = AutonomyConfig { workspace: Some(dir.to_string_lossy().into_owned()), ..Default::default() };
+        GitTool::new(GitConfig { autonomy, allow_commit })
+    }
+
+    #[test]
+    fn test_resolve_rejects_escape() {
+        let tool = tool_in(Path::new("/srv/workspace"), false);
+        assert!(tool.resolve("repo").is_ok());
+        assert!(tool.resolve("repo/../../etc").is_err());
+        assert!(tool.resolve("/etc/passwd").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_out_of_workspace_is_refused() {
+        let root = std::env::temp_dir().join(format!("bizclaw-git-link-{}", uuid::Uuid::new_v4().simple()));
+        let (ws, outside) = (root.join("ws"), root.join("outside"));
+        std::fs::create_dir_all(&ws).unwrap();
+        std::fs::create_dir_all(&outside).unwrap();
+        Repository::init(&outside).unwrap();
+        std::os::unix::fs::symlink(&outside, ws.join("link")).unwrap();
+
+        let tool = tool_in(&ws, false);
+        assert_eq!(tool.resolve("link").unwrap_err().rule, "workspace_only");
+        let result = tool.execute(r#"{"action":"status","path":"link"}"#).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("permission_denied"));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_clone_only_from_network_remotes() {
+        assert!(check_remote("https://github.com/vutech1990/bizclaw.git").is_ok());
+        assert!(check_remote("ssh://git@github.com/vutech1990/bizclaw.git").is_ok());
+        assert!(check_remote("git@github.com:vutech1990/bizclaw.git").is_ok());
+        for url in ["file:///root/secrets", "/srv/other-repo", "../other-repo", "http://example.com/r.git", "C:/repo"] {
+            assert!(matches!(check_remote(url), Err(BizClawError::PermissionDenied(_))), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_commit_status_log_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-git-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Repository::init(&dir).unwrap();
+        std::fs::write(dir.join("a.txt"), "hello\n").unwrap();
+
+        let tool = tool_in(&dir, true);
+        let status = tool.execute(r#"{"action":"status","path":"."}"#).await.unwrap();
+        assert!(status.output.contains("a.txt"));
+
+        let commit = tool
+            .execute(r#"{"action":"commit","path":".","message":"init","files":["a.txt"]}"#)
+            .await
+            .unwrap();
+        assert!(commit.output.starts_with("Committed 1 file(s)"));
+
+        let log = tool.execute(r#"{"action":"log","path":".","n":5}"#).await.unwrap();
+        let entries: serde_json::Value = serde_json::from_str(&log.output).unwrap();
+        assert_eq!(entries[0]["message"], "init");
+
+        std::fs::write(dir.join("a.txt"), "hello\nworld\n").unwrap();
+        let diff = tool.execute(r#"{"action":"diff","path":".","file":"a.txt"}"#).await.unwrap();
+        assert!(diff.output.contains("+world"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_commit_requires_full_autonomy() {
+        let tool = tool_in(&std::env::temp_dir(), false);
+        let err = tool
+            .execute(r#"{"action":"commit","path":".","message":"x","files":["a"]}"#)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, BizClawError::PermissionDenied(_)));
+    }
+}
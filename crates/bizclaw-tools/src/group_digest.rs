@@ -0,0 +1,395 @@
+//! Scheduled group summaries — summarize buffered group messages without
+//! being asked and send the result back to the group and/or a digest chat.
+//!
+//! A group is due once its oldest buffered message is older than the window
+//! (`buffer_window_secs`) or it has `summary_after_messages` messages. Groups
+//! can be turned off or given their own window and style; those overrides live
+//! in SQLite (`<data dir>/group_summaries.db`). When the provider call fails,
+//! or no chat accepts the summary, the messages go back into the buffer and
+//! are tried again on a later tick.
+
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::{Channel, Provider};
+use bizclaw_core::traits::provider::GenerateParams;
+use bizclaw_core::types::{Message, OutgoingMessage, ThreadType};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::group_summarizer::{MessageBuffer, SummarizerConfig, format_prompt};
+
+/// Per-group overrides of the summarizer config.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupSettings {
+    #[serde(default)]
+    pub group_id: String,
+    /// Never summarize this group automatically.
+    #[serde(default)]
+    pub disabled: bool,
+    /// Window in seconds, instead of `buffer_window_secs`.
+    #[serde(default)]
+    pub window_secs: Option<u64>,
+    /// `brief`, `detailed` or `bullet_points`, instead of `summary_style`.
+    #[serde(default)]
+    pub style: Option<String>,
+}
+
+/// SQLite-backed per-group settings.
+pub struct GroupSettingsStore {
+    conn: Mutex<Connection>,
+}
+
+impl GroupSettingsStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| BizClawError::Memory(format!("DB open error: {e}")))?;
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS group_settings (
+                group_id TEXT PRIMARY KEY,
+                disabled INTEGER NOT NULL DEFAULT 0,
+                window_secs INTEGER,
+                style TEXT
+            );
+        ").map_err(|e| BizClawError::Memory(format!("Migration error: {e}")))?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Open `<data dir>/group_summaries.db`.
+    pub fn from_config() -> Result<Self> {
+        Self::open(&bizclaw_core::config::BizClawConfig::data_dir().join("group_summaries.db"))
+    }
+
+    pub fn get(&self, group_id: &str) -> Result<Option<GroupSettings>> {
+        self.conn.lock().unwrap().query_row(
+            "SELECT group_id, disabled, window_secs, style FROM group_settings WHERE group_id=?1",
+            params![group_id],
+            row_to_settings,
+        ).optional().map_err(|e| BizClawError::Memory(format!("Get group settings: {e}")))
+    }
+
+    /// Save the overrides for a group, replacing any earlier ones.
+    pub fn set(&self, settings: &GroupSettings) -> Result<()> {
+        if let Some(style) = &settings.style
+            && !["brief", "detailed", "bullet_points"].contains(&style.as_str())
+        {
+            return Err(BizClawError::Tool(format!("Unknown summary style '{style}'")));
+        }
+        self.conn.lock().unwrap().execute(
+            "INSERT OR REPLACE INTO group_settings (group_id, disabled, window_secs, style) VALUES (?1,?2,?3,?4)",
+            params![settings.group_id, settings.disabled, settings.window_secs.map(|w| w as i64), settings.style],
+        ).map_err(|e| BizClawError::Memory(format!("Save group settings: {e}")))?;
+        Ok(())
+    }
+
+    pub fn list(&self) -> Result<Vec<GroupSettings>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT group_id, disabled, window_secs, style FROM group_settings ORDER BY group_id"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+        let settings = stmt.query_map([], row_to_settings)
+            .map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(settings)
+    }
+}
+
+fn row_to_settings(row: &rusqlite::Row) -> rusqlite::Result<GroupSettings> {
+    Ok(GroupSettings {
+        group_id: row.get(0)?,
+        disabled: row.get(1)?,
+        window_secs: row.get::<_, Option<i64>>(2)?.map(|w| w.max(0) as u64),
+        style: row.get(3)?,
+    })
+}
+
+/// Summarizes buffered groups and delivers the summaries.
+pub struct GroupDigest {
+    buffer: MessageBuffer,
+    settings: Arc<GroupSettingsStore>,
+    config: SummarizerConfig,
+    provider: Arc<dyn Provider>,
+    model: String,
+    /// Chats summaries can be sent through, matched by `Channel::name`.
+    channels: Vec<Arc<dyn Channel>>,
+}
+
+impl GroupDigest {
+    pub fn new(
+        buffer: MessageBuffer,
+        settings: Arc<GroupSettingsStore>,
+        config: SummarizerConfig,
+        provider: Arc<dyn Provider>,
+        model: String,
+        channels: Vec<Arc<dyn Channel>>,
+    ) -> Self {
+        Self { buffer, settings, config, provider, model, channels }
+    }
+
+    pub fn buffer(&self) -> &MessageBuffer {
+        &self.buffer
+    }
+
+    pub fn settings(&self) -> &GroupSettingsStore {
+        &self.settings
+    }
+
+    /// Groups that should be summarized at `now`.
+    pub fn due_groups(&self, now: DateTime<Utc>) -> Result<Vec<String>> {
+        let mut due = Vec::new();
+        for group_id in self.buffer.group_ids() {
+            let settings = self.settings.get(&group_id)?.unwrap_or_default();
+            if settings.disabled {
+                continue;
+            }
+            let window = settings.window_secs.unwrap_or(self.config.buffer_window_secs);
+            let window_elapsed = self.buffer.oldest(&group_id)
+                .is_some_and(|oldest| now - oldest >= chrono::Duration::seconds(window as i64));
+            let threshold = self.config.summary_after_messages;
+            if window_elapsed || (threshold > 0 && self.buffer.count(&group_id) >= threshold) {
+                due.push(group_id);
+            }
+        }
+        due.sort();
+        Ok(due)
+    }
+
+    /// Summarize a group now and deliver the summary. `Ok(None)` if nothing
+    /// is buffered for it.
+    pub async fn summarize(&self, group_id: &str) -> Result<Option<String>> {
+        let mut config = self.config.clone();
+        if let Some(style) = self.settings.get(group_id)?.and_then(|s| s.style) {
+            config.summary_style = style;
+        }
+
+        let messages = self.buffer.drain_group(group_id);
+        let Some(first) = messages.first() else {
+            return Ok(None);
+        };
+        let group_name = first.group_name.clone();
+        let channel = first.channel.clone();
+        let prompt = format_prompt(&config, &messages, &group_name);
+        let params = GenerateParams { model: self.model.clone(), temperature: 0.3, ..Default::default() };
+        let summary = match self.provider.chat(&[Message::user(&prompt)], &[], &params).await {
+            Ok(response) => response.content.unwrap_or_default(),
+            Err(e) => {
+                self.buffer.requeue(group_id, messages);
+                return Err(e);
+            }
+        };
+
+        let text = format!("📋 Tóm tắt nhóm \"{group_name}\" ({} tin nhắn):\n\n{}", messages.len(), summary.trim());
+        let mut targets = Vec::new();
+        if self.config.deliver_to_group {
+            targets.push((channel, group_id.to_string(), ThreadType::Group));
+        }
+        if let Some(digest) = &self.config.digest {
+            targets.push((digest.channel.clone(), digest.thread_id.clone(), ThreadType::Direct));
+        }
+
+        let mut delivered = 0;
+        let mut last_error = None;
+        for (channel, thread_id, thread_type) in &targets {
+            match self.send(channel, thread_id, thread_type.clone(), &text).await {
+                Ok(()) => delivered += 1,
+                Err(e) => {
+                    tracing::warn!("Group summary for {group_id} not delivered to {channel}/{thread_id}: {e}");
+                    last_error = Some(e);
+                }
+            }
+        }
+        if delivered == 0
+            && let Some(e) = last_error
+        {
+            self.buffer.requeue(group_id, messages);
+            return Err(e);
+        }
+        tracing::info!("Summarized {} messages from group {group_id} ({delivered} deliveries)", messages.len());
+        Ok(Some(summary))
+    }
+
+    async fn send(&self, channel: &str, thread_id: &str, thread_type: ThreadType, text: &str) -> Result<()> {
+        let target = self.channels.iter()
+            .find(|c| c.name() == channel)
+            .ok_or_else(|| BizClawError::Channel(format!("Channel '{channel}' is not available")))?;
+        target.send(OutgoingMessage {
+            thread_id: thread_id.to_string(),
+            content: text.to_string(),
+            thread_type,
+            reply_to: None,
+        }).await
+    }
+
+    /// Summarize every due group.
+    pub async fn run_due(&self, now: DateTime<Utc>) {
+        let due = match self.due_groups(now) {
+            Ok(due) => due,
+            Err(e) => {
+                tracing::warn!("Group summary check failed: {e}");
+                return;
+            }
+        };
+        for group_id in due {
+            if let Err(e) = self.summarize(&group_id).await {
+                tracing::warn!("Group summary for {group_id} failed, will retry: {e}");
+            }
+        }
+    }
+}
+
+/// Check for due groups every `tick`.
+pub fn spawn_runner(digest: Arc<GroupDigest>, tick: std::time::Duration) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tick);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            digest.run_due(Utc::now()).await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::group_summarizer::BufferedMessage;
+    use async_trait::async_trait;
+    use bizclaw_core::config::SummaryDigestConfig;
+    use bizclaw_core::types::{IncomingMessage, ModelInfo, ProviderResponse, ToolDefinition};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct FakeProvider {
+        fail: AtomicBool,
+    }
+
+    #[async_trait]
+    impl Provider for FakeProvider {
+        fn name(&self) -> &str { "fake" }
+
+        async fn chat(&self, messages: &[Message], _: &[ToolDefinition], _: &GenerateParams) -> Result<ProviderResponse> {
+            if self.fail.load(Ordering::SeqCst) {
+                return Err(BizClawError::Provider("down".into()));
+            }
+            let lines = messages[0].content.lines().filter(|l| l.starts_with('[')).count();
+            Ok(ProviderResponse {
+                content: Some(format!("- {lines} messages")),
+                tool_calls: vec![],
+                finish_reason: None,
+                usage: None,
+                metadata: None,
+            })
+        }
+
+        async fn list_models(&self) -> Result<Vec<ModelInfo>> { Ok(vec![]) }
+        async fn health_check(&self) -> Result<bool> { Ok(true) }
+    }
+
+    #[derive(Default)]
+    struct FakeChannel {
+        sent: Mutex<Vec<OutgoingMessage>>,
+    }
+
+    #[async_trait]
+    impl Channel for FakeChannel {
+        fn name(&self) -> &str { "telegram" }
+        async fn connect(&mut self) -> Result<()> { Ok(()) }
+        async fn disconnect(&mut self) -> Result<()> { Ok(()) }
+        fn is_connected(&self) -> bool { true }
+        async fn listen(&self) -> Result<Box<dyn tokio_stream::Stream<Item = IncomingMessage> + Send + Unpin>> {
+            Ok(Box::new(tokio_stream::empty()))
+        }
+        async fn send(&self, message: OutgoingMessage) -> Result<()> {
+            self.sent.lock().unwrap().push(message);
+            Ok(())
+        }
+    }
+
+    fn message(group: &str, minutes_ago: i64) -> BufferedMessage {
+        BufferedMessage {
+            sender_name: "An".into(),
+            content: "Chốt lịch họp thứ 2".into(),
+            timestamp: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            group_id: group.into(),
+            group_name: format!("Nhóm {group}"),
+            channel: "telegram".into(),
+        }
+    }
+
+    fn digest(config: SummarizerConfig) -> (GroupDigest, Arc<FakeChannel>, Arc<FakeProvider>) {
+        let channel = Arc::new(FakeChannel::default());
+        let provider = Arc::new(FakeProvider { fail: AtomicBool::new(false) });
+        let settings = Arc::new(GroupSettingsStore::open(Path::new(":memory:")).unwrap());
+        let digest = GroupDigest::new(
+            MessageBuffer::new(), settings, config, provider.clone(), "m".into(), vec![channel.clone()],
+        );
+        (digest, channel, provider)
+    }
+
+    #[test]
+    fn test_due_groups_window_threshold_and_overrides() {
+        let config = SummarizerConfig { buffer_window_secs: 3600, summary_after_messages: 3, ..Default::default() };
+        let (digest, _, _) = digest(config);
+        digest.buffer.push(message("old", 61));
+        digest.buffer.push(message("recent", 5));
+        for _ in 0..3 {
+            digest.buffer.push(message("busy", 1));
+        }
+        digest.buffer.push(message("muted", 120));
+        digest.buffer.push(message("short", 10));
+        digest.settings.set(&GroupSettings { group_id: "muted".into(), disabled: true, ..Default::default() }).unwrap();
+        digest.settings.set(&GroupSettings { group_id: "short".into(), window_secs: Some(300), ..Default::default() }).unwrap();
+
+        assert_eq!(digest.due_groups(Utc::now()).unwrap(), vec!["busy", "old", "short"]);
+        assert!(digest.settings.set(&GroupSettings { group_id: "x".into(), style: Some("poem".into()), ..Default::default() }).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_summary_delivered_to_group_and_digest() {
+        let config = SummarizerConfig {
+            digest: Some(SummaryDigestConfig { channel: "telegram".into(), thread_id: "999".into() }),
+            ..Default::default()
+        };
+        let (digest, channel, _) = digest(config);
+        digest.buffer.push(message("g1", 2));
+        digest.buffer.push(message("g1", 1));
+
+        assert_eq!(digest.summarize("g1").await.unwrap().as_deref(), Some("- 2 messages"));
+        {
+            let sent = channel.sent.lock().unwrap();
+            let targets: Vec<_> = sent.iter().map(|m| m.thread_id.as_str()).collect();
+            assert_eq!(targets, vec!["g1", "999"]);
+            assert!(sent[0].content.contains("Nhóm g1"));
+        }
+        assert_eq!(digest.buffer.count("g1"), 0);
+        assert_eq!(digest.summarize("g1").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_failures_requeue_messages() {
+        let (digest, channel, provider) = digest(SummarizerConfig::default());
+        digest.buffer.push(message("g1", 2));
+        provider.fail.store(true, Ordering::SeqCst);
+        assert!(digest.summarize("g1").await.is_err());
+        // A message that arrived meanwhile stays after the requeued ones.
+        let mut newer = message("g1", 0);
+        newer.content = "newer".into();
+        digest.buffer.push(newer);
+        assert_eq!(digest.buffer.count("g1"), 2);
+
+        // No channel to deliver through: kept as well.
+        provider.fail.store(false, Ordering::SeqCst);
+        let mut other = message("g2", 1);
+        other.channel = "zalo".into();
+        digest.buffer.push(other);
+        assert!(digest.summarize("g2").await.is_err());
+        assert_eq!(digest.buffer.count("g2"), 1);
+
+        assert!(digest.summarize("g1").await.unwrap().is_some());
+        assert_eq!(channel.sent.lock().unwrap().len(), 1);
+    }
+}
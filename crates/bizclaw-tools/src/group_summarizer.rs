@@ -4,23 +4,14 @@
 //! then uses the AI provider to generate a summary.
 
 use async_trait::async_trait;
+use bizclaw_core::config::SummaryDigestConfig;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
 use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::tokens;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use chrono::{DateTime, Utc};
 
-/// A single buffered message from a Zalo group.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct BufferedMessage {
-    pub sender_name: String,
-    pub content: String,
-    pub timestamp: DateTime<Utc>,
-    pub group_id: String,
-    pub group_name: String,
-}
+pub use bizclaw_core::group_buffer::{BufferedMessage, MessageBuffer};
 
 /// Configuration for the group summarizer.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,12 +28,33 @@ pub struct SummarizerConfig {
     /// Summary style (brief, detailed, bullet_points)
     #[serde(default = "default_style")]
     pub summary_style: String,
+    /// Token budget for the summary prompt; oldest messages are left out beyond it
+    #[serde(default = "default_max_prompt_tokens")]
+    pub max_prompt_tokens: usize,
+    /// Model the prompt is sent to (for token counting; empty = heuristic)
+    #[serde(default)]
+    pub model: String,
+    /// Summarize on the window/threshold without being asked
+    #[serde(default)]
+    pub auto_summary: bool,
+    /// Message count that triggers a summary before the window elapses
+    #[serde(default = "default_summary_after_messages")]
+    pub summary_after_messages: usize,
+    /// Send automatic summaries back to the group
+    #[serde(default = "default_true")]
+    pub deliver_to_group: bool,
+    /// Chat that also receives every summary
+    #[serde(default)]
+    pub digest: Option<SummaryDigestConfig>,
 }
 
 fn default_buffer_window() -> u64 { 3600 } // 1 hour
 fn default_max_messages() -> usize { 200 }
 fn default_language() -> String { "vi".into() }
 fn default_style() -> String { "bullet_points".into() }
+fn default_max_prompt_tokens() -> usize { 6000 }
+fn default_summary_after_messages() -> usize { 100 }
+fn default_true() -> bool { true }
 
 impl Default for SummarizerConfig {
     fn default() -> Self {
@@ -51,67 +63,30 @@ impl Default for SummarizerConfig {
             max_messages_per_group: 200,
             language: "vi".into(),
             summary_style: "bullet_points".into(),
+            max_prompt_tokens: 6000,
+            model: String::new(),
+            auto_summary: false,
+            summary_after_messages: 100,
+            deliver_to_group: true,
+            digest: None,
         }
     }
 }
 
-/// Message buffer — stores messages per group.
-#[derive(Debug, Clone, Default)]
-pub struct MessageBuffer {
-    /// group_id -> Vec<BufferedMessage>
-    groups: Arc<Mutex<HashMap<String, Vec<BufferedMessage>>>>,
-}
-
-impl MessageBuffer {
-    pub fn new() -> Self {
+impl From<&bizclaw_core::config::GroupSummarizerToolConfig> for SummarizerConfig {
+    fn from(cfg: &bizclaw_core::config::GroupSummarizerToolConfig) -> Self {
         Self {
-            groups: Arc::new(Mutex::new(HashMap::new())),
-        }
-    }
-
-    /// Add a message to the buffer.
-    pub fn push(&self, msg: BufferedMessage) {
-        let mut groups = self.groups.lock().unwrap();
-        groups.entry(msg.group_id.clone())
-            .or_default()
-            .push(msg);
-    }
-
-    /// Get and clear messages for a specific group.
-    pub fn drain_group(&self, group_id: &str) -> Vec<BufferedMessage> {
-        let mut groups = self.groups.lock().unwrap();
-        groups.remove(group_id).unwrap_or_default()
-    }
-
-    /// Get all group IDs with buffered messages.
-    pub fn group_ids(&self) -> Vec<String> {
-        self.groups.lock().unwrap().keys().cloned().collect()
-    }
-
-    /// Get message count for a group.
-    pub fn count(&self, group_id: &str) -> usize {
-        self.groups.lock().unwrap()
-            .get(group_id)
-            .map(|v| v.len())
-            .unwrap_or(0)
-    }
-
-    /// Get total message count across all groups.
-    pub fn total_count(&self) -> usize {
-        self.groups.lock().unwrap()
-            .values()
-            .map(|v| v.len())
-            .sum()
-    }
-
-    /// Prune old messages beyond the buffer window.
-    pub fn prune(&self, max_age_secs: u64) {
-        let cutoff = Utc::now() - chrono::Duration::seconds(max_age_secs as i64);
-        let mut groups = self.groups.lock().unwrap();
-        for messages in groups.values_mut() {
-            messages.retain(|m| m.timestamp > cutoff);
+            buffer_window_secs: cfg.buffer_window_secs,
+            max_messages_per_group: cfg.max_messages_per_group,
+            language: cfg.language.clone(),
+            summary_style: cfg.summary_style.clone(),
+            max_prompt_tokens: cfg.max_prompt_tokens,
+            model: cfg.model.clone(),
+            auto_summary: cfg.auto_summary,
+            summary_after_messages: cfg.summary_after_messages,
+            deliver_to_group: cfg.deliver_to_group,
+            digest: cfg.digest.clone(),
         }
-        groups.retain(|_, v| !v.is_empty());
     }
 }
 
@@ -140,36 +115,61 @@ impl GroupSummarizerTool {
 
     /// Format messages into a prompt for the LLM.
     fn format_messages_for_llm(&self, messages: &[BufferedMessage], group_name: &str) -> String {
-        let lang = if self.config.language == "vi" { "tiếng Việt" } else { "English" };
-        let style_instruction = match self.config.summary_style.as_str() {
-            "brief" => "Tóm tắt ngắn gọn trong 2-3 câu.",
-            "detailed" => "Tóm tắt chi tiết, nêu rõ ai nói gì, chủ đề chính.",
-            _ => "Tóm tắt dạng bullet points, mỗi chủ đề 1 gạch đầu dòng.",
-        };
+        format_prompt(&self.config, messages, group_name)
+    }
+}
+
+/// Summary prompt for `messages`, keeping the newest ones that fit in
+/// `config.max_prompt_tokens`.
+pub(crate) fn format_prompt(config: &SummarizerConfig, messages: &[BufferedMessage], group_name: &str) -> String {
+    let lang = if config.language == "vi" { "tiếng Việt" } else { "English" };
+    let style_instruction = match config.summary_style.as_str() {
+        "brief" => "Tóm tắt ngắn gọn trong 2-3 câu.",
+        "detailed" => "Tóm tắt chi tiết, nêu rõ ai nói gì, chủ đề chính.",
+        _ => "Tóm tắt dạng bullet points, mỗi chủ đề 1 gạch đầu dòng.",
+    };
 
-        let mut prompt = format!(
-            "Bạn là trợ lý AI tóm tắt tin nhắn nhóm chat. \
-             Hãy tóm tắt các tin nhắn sau đây từ nhóm \"{group_name}\" bằng {lang}.\n\
-             {style_instruction}\n\n\
-             Chú ý:\n\
-             - Gộp các chủ đề liên quan\n\
-             - Highlight quyết định quan trọng\n\
-             - Bỏ qua tin nhắn không quan trọng (sticker, OK, ...)\n\
-             - Nêu rõ ai đề xuất/quyết định gì\n\n\
-             --- TIN NHẮN ---\n"
-        );
+    let mut prompt = format!(
+        "Bạn là trợ lý AI tóm tắt tin nhắn nhóm chat. \
+         Hãy tóm tắt các tin nhắn sau đây từ nhóm \"{group_name}\" bằng {lang}.\n\
+         {style_instruction}\n\n\
+         Chú ý:\n\
+         - Gộp các chủ đề liên quan\n\
+         - Highlight quyết định quan trọng\n\
+         - Bỏ qua tin nhắn không quan trọng (sticker, OK, ...)\n\
+         - Nêu rõ ai đề xuất/quyết định gì\n\n\
+         --- TIN NHẮN ---\n"
+    );
 
-        for msg in messages.iter().take(self.config.max_messages_per_group) {
-            let time = msg.timestamp.format("%H:%M");
-            prompt.push_str(&format!(
-                "[{time}] {}: {}\n",
-                msg.sender_name, msg.content
-            ));
+    let footer = "--- HẾT TIN NHẮN ---\n\nTÓM TẮT:";
+    let model = &config.model;
+    let fixed = tokens::count_tokens(model, &prompt) + tokens::count_tokens(model, footer);
+
+    // Keep the newest messages that fit in the token budget.
+    let lines: Vec<String> = messages.iter()
+        .take(config.max_messages_per_group)
+        .map(|msg| format!("[{}] {}: {}\n", msg.timestamp.format("%H:%M"), msg.sender_name, msg.content))
+        .collect();
+    let mut used = fixed;
+    let mut keep_from = lines.len();
+    for (i, line) in lines.iter().enumerate().rev() {
+        let t = tokens::count_tokens(model, line);
+        if used + t > config.max_prompt_tokens {
+            break;
         }
+        used += t;
+        keep_from = i;
+    }
+    if keep_from > 0 {
+        tracing::debug!("group_summarizer: {keep_from} oldest messages left out to fit {} tokens", config.max_prompt_tokens);
+    }
 
-        prompt.push_str("--- HẾT TIN NHẮN ---\n\nTÓM TẮT:");
-        prompt
+    for line in &lines[keep_from..] {
+        prompt.push_str(line);
     }
+
+    prompt.push_str(footer);
+    prompt
 }
 
 #[async_trait]
@@ -254,10 +254,35 @@ impl Tool for GroupSummarizerTool {
             _ => format!("Unknown action: {action}"),
         };
 
-        Ok(ToolResult {
-            tool_call_id: String::new(),
-            output,
-            success: true,
-        })
+        Ok(ToolResult::ok(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn message(i: usize) -> BufferedMessage {
+        BufferedMessage {
+            sender_name: format!("user{i}"),
+            content: format!("tin nhắn số {i} về kế hoạch tuần tới"),
+            timestamp: Utc::now(),
+            group_id: "g1".into(),
+            group_name: "Team".into(),
+            channel: "telegram".into(),
+        }
+    }
+
+    #[test]
+    fn test_prompt_respects_token_budget() {
+        let config = SummarizerConfig { max_prompt_tokens: 400, ..Default::default() };
+        let tool = GroupSummarizerTool::new(config);
+        let messages: Vec<_> = (0..100).map(message).collect();
+
+        let prompt = tool.format_messages_for_llm(&messages, "Team");
+        assert!(tokens::count_tokens("", &prompt) <= 400);
+        assert!(prompt.contains("user99:"), "newest message must be kept");
+        assert!(!prompt.contains("user0:"), "oldest message should be dropped");
     }
 }
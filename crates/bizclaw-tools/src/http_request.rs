@@ -0,0 +1,510 @@
+//! HTTP Request Tool — lets the agent call JSON APIs.
+//!
+//! Every URL, including each redirect hop, is checked before connecting:
+//! only http/https, host allow/deny lists, and no private, loopback,
+//! link-local (cloud metadata), or otherwise internal addresses unless the
+//! host is explicitly allowlisted. The connection is pinned to the addresses
+//! that were checked, so a second DNS lookup can't swap in an internal one.
+//! Credentials (`Authorization`, `Cookie`, `Proxy-Authorization`) are not
+//! sent on past a redirect to another origin.
+
+use async_trait::async_trait;
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_core::error::{BizClawError, Result};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+/// Request headers dropped when a redirect leaves the original origin.
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "cookie", "proxy-authorization"];
+
+/// Hostnames of cloud metadata services, refused regardless of resolution.
+const METADATA_HOSTS: &[&str] = &["metadata.google.internal", "metadata.goog", "metadata"];
+
+const METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD"];
+
+/// HTTP request tool configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpRequestConfig {
+    pub allowed_hosts: Vec<String>,
+    pub denied_hosts: Vec<String>,
+    pub max_response_bytes: usize,
+    pub max_redirects: usize,
+    pub timeout_secs: u64,
+}
+
+impl Default for HttpRequestConfig {
+    fn default() -> Self {
+        (&bizclaw_core::config::HttpRequestToolConfig::default()).into()
+    }
+}
+
+impl From<&bizclaw_core::config::HttpRequestToolConfig> for HttpRequestConfig {
+    fn from(cfg: &bizclaw_core::config::HttpRequestToolConfig) -> Self {
+        Self {
+            allowed_hosts: cfg.allowed_hosts.clone(),
+            denied_hosts: cfg.denied_hosts.clone(),
+            max_response_bytes: cfg.max_response_bytes,
+            max_redirects: cfg.max_redirects,
+            timeout_secs: cfg.timeout_secs,
+        }
+    }
+}
+
+/// Generic HTTP client tool.
+pub struct HttpRequestTool {
+    config: HttpRequestConfig,
+}
+
+impl HttpRequestTool {
+    pub fn new(config: HttpRequestConfig) -> Self {
+        Self { config }
+    }
+
+    /// Check `url` against the host rules and return the addresses it may connect to.
+    async fn check_url(&self, url: &Url) -> std::result::Result<Vec<SocketAddr>, String> {
+        if url.scheme() != "http" && url.scheme() != "https" {
+            return Err(format!("scheme '{}' is not allowed", url.scheme()));
+        }
+        let host = url.host_str()
+            .ok_or_else(|| "URL has no host".to_string())?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .trim_end_matches('.')
+            .to_lowercase();
+
+        if METADATA_HOSTS.contains(&host.as_str()) {
+            return Err(format!("'{host}' is a cloud metadata endpoint"));
+        }
+        if self.config.denied_hosts.iter().any(|p| host_matches(p, &host)) {
+            return Err(format!("'{host}' is in denied_hosts"));
+        }
+        if !self.config.allowed_hosts.is_empty() && !self.config.allowed_hosts.iter().any(|p| host_matches(p, &host)) {
+            return Err(format!("'{host}' is not in allowed_hosts"));
+        }
+        // Naming a host exactly is how an internal API is opted in.
+        let explicitly_allowed = self.config.allowed_hosts.iter().any(|p| p.eq_ignore_ascii_case(&host));
+
+        let port = url.port_or_known_default().unwrap_or(80);
+        let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+            Ok(ip) => vec![SocketAddr::new(ip, port)],
+            Err(_) => tokio::net::lookup_host((host.as_str(), port)).await
+                .map_err(|e| format!("cannot resolve '{host}': {e}"))?
+                .collect(),
+        };
+        if addrs.is_empty() {
+            return Err(format!("'{host}' did not resolve"));
+        }
+        if !explicitly_allowed
+            && let Some(addr) = addrs.iter().find(|a| is_internal(a.ip()))
+        {
+            return Err(format!("'{host}' resolves to internal address {}", addr.ip()));
+        }
+        Ok(addrs)
+    }
+
+    /// Send the request, following up to `max_redirects` redirects, each re-checked.
+    pub(crate) async fn send(
+        &self,
+        mut method: reqwest::Method,
+        mut url: Url,
+        headers: &serde_json::Map<String, serde_json::Value>,
+        mut body: Option<serde_json::Value>,
+        timeout: Duration,
+    ) -> std::result::Result<std::result::Result<reqwest::Response, String>, BizClawError> {
+        let origin = url.origin();
+        let mut headers = headers.clone();
+        for hop in 0..=self.config.max_redirects {
+            let addrs = match self.check_url(&url).await {
+                Ok(addrs) => addrs,
+                Err(reason) => return Ok(Err(format!("{url}: {reason}"))),
+            };
+            let mut builder = reqwest::Client::builder()
+                .user_agent("BizClaw/1.0")
+                .redirect(reqwest::redirect::Policy::none())
+                .timeout(timeout);
+            if let Some(host) = url.host_str() {
+                builder = builder.resolve_to_addrs(host, &addrs);
+            }
+            let client = builder.build()
+                .map_err(|e| BizClawError::Tool(format!("HTTP client error: {e}")))?;
+
+            let mut req = client.request(method.clone(), url.clone());
+            for (name, value) in &headers {
+                if let Some(value) = value.as_str() {
+                    req = req.header(name.as_str(), value);
+                }
+            }
+            req = match &body {
+                Some(serde_json::Value::String(text)) => req.body(text.clone()),
+                Some(json) => req.json(json),
+                None => req,
+            };
+            let response = req.send().await
+                .map_err(|e| BizClawError::Tool(format!("HTTP request failed: {e}")))?;
+
+            if !response.status().is_redirection() {
+                return Ok(Ok(response));
+            }
+            let Some(location) = response.headers().get(reqwest::header::LOCATION)
+                .and_then(|l| l.to_str().ok())
+            else {
+                return Ok(Ok(response));
+            };
+            if hop == self.config.max_redirects {
+                return Ok(Err(format!("more than {} redirects", self.config.max_redirects)));
+            }
+            url = url.join(location)
+                .map_err(|e| BizClawError::Tool(format!("Bad redirect location '{location}': {e}")))?;
+            if url.origin() != origin {
+                headers.retain(|name, _| !CREDENTIAL_HEADERS.contains(&name.to_ascii_lowercase().as_str()));
+            }
+            // 303, and 301/302 after POST, turn into a GET without a body.
+            let status = response.status().as_u16();
+            if status == 303 || ((status == 301 || status == 302) && method == reqwest::Method::POST) {
+                method = reqwest::Method::GET;
+                body = None;
+            }
+        }
+        unreachable!("loop returns on the last hop")
+    }
+
+    /// Read at most `max_response_bytes` of the body; the flag reports truncation.
+    pub(crate) async fn read_body(&self, mut response: reqwest::Response) -> Result<(Vec<u8>, bool)> {
+        let max = self.config.max_response_bytes;
+        let mut body = Vec::new();
+        while let Some(chunk) = response.chunk().await
+            .map_err(|e| BizClawError::Tool(format!("Read failed: {e}")))?
+        {
+            if body.len() + chunk.len() > max {
+                body.extend_from_slice(&chunk[..max - body.len()]);
+                return Ok((body, true));
+            }
+            body.extend_from_slice(&chunk);
+        }
+        Ok((body, false))
+    }
+}
+
+/// `pattern` is an exact host or `*.domain` (subdomains of `domain`).
+fn host_matches(pattern: &str, host: &str) -> bool {
+    let pattern = pattern.trim().to_lowercase();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.ends_with(&format!(".{domain}")),
+        None => pattern == "*" || pattern == host,
+    }
+}
+
+/// Addresses an agent must not reach: private, loopback, link-local,
+/// carrier-grade NAT, multicast, and reserved ranges.
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => is_internal_v4(v4),
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_internal_v4(v4);
+            }
+            let seg = v6.segments();
+            // NAT64 (64:ff9b::/96) embeds an IPv4 address.
+            if seg[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+                let [a, b] = seg[6].to_be_bytes();
+                let [c, d] = seg[7].to_be_bytes();
+                return is_internal_v4(Ipv4Addr::new(a, b, c, d));
+            }
+            v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (seg[0] & 0xfe00) == 0xfc00 // unique local
+                || (seg[0] & 0xffc0) == 0xfe80 // link-local
+        }
+    }
+}
+
+fn is_internal_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_multicast()
+        || ip.is_documentation()
+        || a == 0
+        || (a == 100 && (64..128).contains(&b)) // carrier-grade NAT
+        || (a == 192 && b == 0 && ip.octets()[2] == 0)
+        || (a == 198 && (b == 18 || b == 19)) // benchmarking
+        || a >= 240
+}
+
+/// Pretty-print JSON bodies; other bodies are returned as (lossy) text.
+fn format_body(body: &[u8], content_type: &str, truncated: bool) -> String {
+    if !truncated
+        && (content_type.contains("json") || body.first().is_some_and(|b| *b == b'{' || *b == b'['))
+        && let Ok(json) = serde_json::from_slice::<serde_json::Value>(body)
+    {
+        return serde_json::to_string_pretty(&json).unwrap_or_default();
+    }
+    String::from_utf8_lossy(body).into_owned()
+}
+
+#[async_trait]
+impl Tool for HttpRequestTool {
+    fn name(&self) -> &str { "http_request" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "http_request".into(),
+            description: "Call an HTTP/JSON API and return the status, headers, and body.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "method": { "type": "string", "enum": METHODS, "description": "HTTP method (default GET)" },
+                    "url": { "type": "string", "description": "Full http(s) URL" },
+                    "headers": { "type": "object", "description": "Request headers as name → value" },
+                    "body": { "description": "Request body: a string, or JSON (sent as application/json)" },
+                    "timeout": { "type": "integer", "description": "Timeout in seconds" }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+
+        let url_str = args["url"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'url'".into()))?;
+        let url = Url::parse(url_str)
+            .map_err(|e| BizClawError::Tool(format!("Invalid url '{url_str}': {e}")))?;
+        let method_name = args["method"].as_str().unwrap_or("GET").to_uppercase();
+        if !METHODS.contains(&method_name.as_str()) {
+            return Err(BizClawError::Tool(format!("Unsupported method: {method_name}")));
+        }
+        let method = reqwest::Method::from_bytes(method_name.as_bytes())
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let headers = args["headers"].as_object().cloned().unwrap_or_default();
+        let body = Some(args["body"].clone()).filter(|b| !b.is_null());
+        let timeout = Duration::from_secs(
+            args["timeout"].as_u64().unwrap_or(self.config.timeout_secs).clamp(1, self.config.timeout_secs.max(1)),
+        );
+
+        let response = match self.send(method, url, &headers, body, timeout).await? {
+            Ok(response) => response,
+            Err(reason) => {
+                tracing::warn!(target: "bizclaw::audit", tool = "http_request", url = url_str, "denied: {reason}");
+                return Ok(ToolResult::failure("permission_denied", serde_json::json!({
+                    "error": "permission_denied",
+                    "tool": "http_request",
+                    "subject": url_str,
+                    "reason": reason,
+                }).to_string()));
+            }
+        };
+
+        let status = response.status();
+        let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_string();
+        let mut output = format!("HTTP {status}\n");
+        let mut response_headers = serde_json::Map::new();
+        for (name, value) in response.headers() {
+            let value = value.to_str().unwrap_or("<binary>");
+            output.push_str(&format!("{name}: {value}\n"));
+            response_headers.insert(name.to_string(), value.into());
+        }
+        let (body, truncated) = self.read_body(response).await?;
+        output.push('\n');
+        output.push_str(&format_body(&body, &content_type, truncated));
+        if truncated {
+            output.push_str(&format!("\n\n[response truncated at {} bytes]", self.config.max_response_bytes));
+        }
+
+        let data = serde_json::json!({ "status": status.as_u16(), "headers": response_headers });
+        let mut result = if status.is_success() {
+            ToolResult::ok(output)
+        } else {
+            ToolResult::failure("http_status", output)
+        };
+        result.truncated = truncated;
+        Ok(result.with_data(data))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Local HTTP server answering each request with `respond(path)`.
+    async fn serve(respond: fn(&str, u16) -> String) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                let path = request.split_whitespace().nth(1).unwrap_or("/").to_string();
+                let _ = stream.write_all(respond(&path, port).as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    fn reply(status: &str, extra_headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n{extra_headers}\r\n{body}",
+            body.len()
+        )
+    }
+
+    fn local_tool() -> HttpRequestTool {
+        HttpRequestTool::new(HttpRequestConfig {
+            allowed_hosts: vec!["127.0.0.1".into()],
+            ..Default::default()
+        })
+    }
+
+    async fn call(tool: &HttpRequestTool, url: &str) -> ToolResult {
+        tool.execute(&serde_json::json!({ "url": url }).to_string()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_internal_addresses_refused_by_default() {
+        let port = serve(|_, _| reply("200 OK", "", "secret")).await;
+        let tool = HttpRequestTool::new(HttpRequestConfig::default());
+        for url in [
+            format!("http://127.0.0.1:{port}/"),
+            format!("http://localhost:{port}/"),
+            "http://169.254.169.254/latest/meta-data/".into(),
+            "http://[::1]/".into(),
+            "http://10.0.0.5/".into(),
+            "http://metadata.google.internal/".into(),
+            "file:///etc/passwd".into(),
+        ] {
+            let result = call(&tool, &url).await;
+            assert!(!result.success, "{url}");
+            assert!(result.output.contains("permission_denied"), "{url}: {}", result.output);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_host_and_pretty_json() {
+        let port = serve(|_, _| reply("200 OK", "Content-Type: application/json\r\n", r#"{"order":42,"status":"shipped"}"#)).await;
+        let result = call(&local_tool(), &format!("http://127.0.0.1:{port}/orders/42")).await;
+        assert!(result.success, "{}", result.output);
+        assert!(result.output.starts_with("HTTP 200 OK"));
+        assert!(result.output.contains("{\n  \"order\": 42,"));
+
+        let denied = HttpRequestTool::new(HttpRequestConfig {
+            allowed_hosts: vec!["api.example.com".into()],
+            ..Default::default()
+        });
+        assert!(call(&denied, &format!("http://127.0.0.1:{port}/")).await.output.contains("not in allowed_hosts"));
+    }
+
+    #[tokio::test]
+    async fn test_redirects_rechecked() {
+        let port = serve(|path, port| match path {
+            "/to-metadata" => reply("302 Found", "Location: http://169.254.169.254/latest/\r\n", ""),
+            "/to-localhost" => reply("302 Found", &format!("Location: http://localhost:{port}/ok\r\n"), ""),
+            "/to-ok" => reply("301 Moved", "Location: /ok\r\n", ""),
+            "/loop" => reply("302 Found", "Location: /loop\r\n", ""),
+            _ => reply("200 OK", "", "done"),
+        }).await;
+        let tool = local_tool();
+        let base = format!("http://127.0.0.1:{port}");
+
+        let ok = call(&tool, &format!("{base}/to-ok")).await;
+        assert!(ok.success);
+        assert!(ok.output.ends_with("done"));
+        assert!(call(&tool, &format!("{base}/to-metadata")).await.output.contains("permission_denied"));
+        // `localhost` isn't the allowlisted name, so the private-address rule applies.
+        assert!(call(&tool, &format!("{base}/to-localhost")).await.output.contains("permission_denied"));
+        assert!(call(&tool, &format!("{base}/loop")).await.output.contains("redirects"));
+    }
+
+    /// Server that redirects `/go` to `target` (or its own `/echo`) and
+    /// answers anything else with the request it received.
+    async fn echo_server(target: Option<String>) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let target = target.unwrap_or_else(|| format!("http://127.0.0.1:{port}/echo"));
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let n = stream.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..n]).to_lowercase();
+                let response = if request.starts_with("get /go ") {
+                    reply("302 Found", &format!("Location: {target}\r\n"), "")
+                } else {
+                    reply("200 OK", "", &request)
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_credentials_dropped_on_cross_origin_redirect() {
+        let other = echo_server(None).await;
+        let first = echo_server(Some(format!("http://127.0.0.1:{other}/echo"))).await;
+        let tool = local_tool();
+        let call_with_credentials = |port: u16| {
+            let args = serde_json::json!({
+                "url": format!("http://127.0.0.1:{port}/go"),
+                "headers": {
+                    "Authorization": "Bearer sk-secret",
+                    "Cookie": "session=abc",
+                    "Proxy-Authorization": "Basic cHJveHk=",
+                    "X-Trace": "t1",
+                },
+            });
+            let tool = &tool;
+            async move { tool.execute(&args.to_string()).await.unwrap() }
+        };
+
+        // Another port is another origin: only the harmless header follows.
+        let crossed = call_with_credentials(first).await;
+        assert!(crossed.success, "{}", crossed.output);
+        assert!(crossed.output.contains("x-trace: t1"));
+        for header in ["authorization:", "cookie:", "proxy-authorization:"] {
+            assert!(!crossed.output.contains(header), "{header} leaked: {}", crossed.output);
+        }
+
+        // A redirect within the origin keeps them.
+        let same = call_with_credentials(other).await;
+        assert!(same.output.contains("authorization: bearer sk-secret"), "{}", same.output);
+        assert!(same.output.contains("cookie: session=abc"));
+    }
+
+    #[tokio::test]
+    async fn test_response_size_cap() {
+        let port = serve(|_, _| reply("200 OK", "", &"x".repeat(5000))).await;
+        let tool = HttpRequestTool::new(HttpRequestConfig {
+            allowed_hosts: vec!["127.0.0.1".into()],
+            max_response_bytes: 100,
+            ..Default::default()
+        });
+        let result = call(&tool, &format!("http://127.0.0.1:{port}/")).await;
+        assert!(result.output.contains("[response truncated at 100 bytes]"));
+        assert!(!result.output.contains(&"x".repeat(101)));
+    }
+
+    #[test]
+    fn test_host_patterns_and_ranges() {
+        assert!(host_matches("*.example.com", "api.example.com"));
+        assert!(!host_matches("*.example.com", "example.com"));
+        assert!(!host_matches("*.example.com", "evilexample.com"));
+        assert!(is_internal("100.64.1.1".parse().unwrap()));
+        assert!(is_internal("::ffff:127.0.0.1".parse().unwrap()));
+        assert!(is_internal("fd00::1".parse().unwrap()));
+        assert!(!is_internal("93.184.216.34".parse().unwrap()));
+        assert!(!is_internal("2606:4700::1111".parse().unwrap()));
+    }
+}
@@ -0,0 +1,302 @@
+//! Jira Tool — search, create, and transition issues via the Jira Cloud REST API v3.
+//!
+//! Authenticates with HTTP basic auth (account email + API token).
+//! See `linear.rs` for the Linear counterpart.
+
+use async_trait::async_trait;
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_core::error::{BizClawError, Result};
+use serde::{Deserialize, Serialize};
+
+/// Jira tool configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JiraConfig {
+    /// Site URL, e.g. https://yourteam.atlassian.net
+    pub base_url: String,
+    /// Account email
+    pub username: String,
+    pub api_token: String,
+    /// Project new issues are created in (e.g. "OPS")
+    pub project_key: String,
+}
+
+impl From<&bizclaw_core::config::JiraToolConfig> for JiraConfig {
+    fn from(cfg: &bizclaw_core::config::JiraToolConfig) -> Self {
+        Self {
+            base_url: cfg.base_url.clone(),
+            username: cfg.username.clone(),
+            api_token: cfg.api_token.clone(),
+            project_key: cfg.project_key.clone(),
+        }
+    }
+}
+
+/// Jira issue tracker tool.
+pub struct JiraTool {
+    config: JiraConfig,
+    client: reqwest::Client,
+}
+
+impl JiraTool {
+    pub fn new(config: JiraConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/rest/api/3{path}", self.config.base_url.trim_end_matches('/'))
+    }
+
+    /// Send a request and return the JSON body, mapping non-2xx to tool errors.
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<serde_json::Value> {
+        let response = req
+            .basic_auth(&self.config.username, Some(&self.config.api_token))
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| BizClawError::Tool(format!("Jira request failed: {e}")))?;
+
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(BizClawError::Tool(format!("Jira API error {status}: {body}")));
+        }
+        if body.is_empty() {
+            return Ok(serde_json::Value::Null);
+        }
+        serde_json::from_str(&body)
+            .map_err(|e| BizClawError::Tool(format!("Parse Jira response failed: {e}")))
+    }
+
+    async fn search(&self, jql: &str, max_results: u64) -> Result<String> {
+        let req = self.client.get(self.url("/search")).query(&[
+            ("jql", jql.to_string()),
+            ("maxResults", max_results.to_string()),
+            ("fields", "summary,status,priority,assignee,issuetype".into()),
+        ]);
+        let body = self.send(req).await?;
+        let issues = body["issues"].as_array().cloned().unwrap_or_default();
+        if issues.is_empty() {
+            return Ok(format!("No issues match: {jql}"));
+        }
+        let total = body["total"].as_u64().unwrap_or(issues.len() as u64);
+        let mut out = format!("Found {total} issue(s) for \"{jql}\":\n\n");
+        for issue in &issues {
+            out.push_str(&format_issue_line(issue));
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    async fn create_issue(
+        &self,
+        summary: &str,
+        description: Option<&str>,
+        issue_type: &str,
+        priority: Option<&str>,
+    ) -> Result<String> {
+        let mut fields = serde_json::json!({
+            "project": { "key": self.config.project_key },
+            "summary": summary,
+            "issuetype": { "name": issue_type },
+        });
+        if let Some(desc) = description {
+            fields["description"] = to_adf(desc);
+        }
+        if let Some(p) = priority {
+            fields["priority"] = serde_json::json!({ "name": p });
+        }
+
+        let req = self.client.post(self.url("/issue")).json(&serde_json::json!({ "fields": fields }));
+        let body = self.send(req).await?;
+        let key = body["key"].as_str().unwrap_or("unknown");
+        Ok(format!(
+            "Created {key}: {summary}\nLink: {}/browse/{key}",
+            self.config.base_url.trim_end_matches('/')
+        ))
+    }
+
+    async fn get_issue(&self, key: &str) -> Result<String> {
+        let body = self.send(self.client.get(self.url(&format!("/issue/{key}")))).await?;
+        let fields = &body["fields"];
+        let mut out = format_issue_line(&body);
+        if let Some(reporter) = fields["reporter"]["displayName"].as_str() {
+            out.push_str(&format!("\nReporter: {reporter}"));
+        }
+        if let Some(created) = fields["created"].as_str() {
+            out.push_str(&format!("\nCreated: {created}"));
+        }
+        let description = adf_to_text(&fields["description"]);
+        if !description.is_empty() {
+            out.push_str(&format!("\n\n{description}"));
+        }
+        Ok(out)
+    }
+
+    async fn transition(&self, key: &str, status_name: &str) -> Result<String> {
+        let path = format!("/issue/{key}/transitions");
+        let body = self.send(self.client.get(self.url(&path))).await?;
+        let transitions = body["transitions"].as_array().cloned().unwrap_or_default();
+
+        let wanted = status_name.to_lowercase();
+        let found = transitions.iter().find(|t| {
+            t["name"].as_str().is_some_and(|n| n.to_lowercase() == wanted)
+                || t["to"]["name"].as_str().is_some_and(|n| n.to_lowercase() == wanted)
+        });
+        let Some(transition) = found else {
+            let available: Vec<&str> = transitions.iter()
+                .filter_map(|t| t["to"]["name"].as_str())
+                .collect();
+            return Err(BizClawError::Tool(format!(
+                "No transition to '{status_name}' for {key}. Available: {}",
+                available.join(", ")
+            )));
+        };
+
+        let id = transition["id"].as_str().unwrap_or_default();
+        let req = self.client.post(self.url(&path))
+            .json(&serde_json::json!({ "transition": { "id": id } }));
+        self.send(req).await?;
+        let to = transition["to"]["name"].as_str().unwrap_or(status_name);
+        Ok(format!("{key} moved to {to}"))
+    }
+}
+
+/// One-line summary: `KEY [Status] Summary (Type, Priority, Assignee)`.
+fn format_issue_line(issue: &serde_json::Value) -> String {
+    let fields = &issue["fields"];
+    format!(
+        "{} [{}] {} ({}, {}, {})",
+        issue["key"].as_str().unwrap_or("?"),
+        fields["status"]["name"].as_str().unwrap_or("?"),
+        fields["summary"].as_str().unwrap_or(""),
+        fields["issuetype"]["name"].as_str().unwrap_or("?"),
+        fields["priority"]["name"].as_str().unwrap_or("no priority"),
+        fields["assignee"]["displayName"].as_str().unwrap_or("unassigned"),
+    )
+}
+
+/// Wrap plain text in an Atlassian Document Format doc, one paragraph per line.
+fn to_adf(text: &str) -> serde_json::Value {
+    let paragraphs: Vec<serde_json::Value> = text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                serde_json::json!({ "type": "paragraph", "content": [] })
+            } else {
+                serde_json::json!({ "type": "paragraph", "content": [{ "type": "text", "text": line }] })
+            }
+        })
+        .collect();
+    serde_json::json!({ "type": "doc", "version": 1, "content": paragraphs })
+}
+
+/// Flatten an ADF document back to plain text.
+fn adf_to_text(node: &serde_json::Value) -> String {
+    if let Some(text) = node["text"].as_str() {
+        return text.to_string();
+    }
+    let Some(children) = node["content"].as_array() else {
+        return String::new();
+    };
+    let parts: Vec<String> = children.iter().map(adf_to_text).collect();
+    let block = matches!(node["type"].as_str(), Some("doc" | "bulletList" | "orderedList"));
+    parts.join(if block { "\n" } else { "" })
+}
+
+#[async_trait]
+impl Tool for JiraTool {
+    fn name(&self) -> &str { "jira" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "jira".into(),
+            description: "Search, create, view, and transition Jira issues.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["search", "create_issue", "get_issue", "transition"],
+                        "description": "Operation to perform"
+                    },
+                    "jql": { "type": "string", "description": "JQL query (search)" },
+                    "max_results": { "type": "integer", "description": "Max issues (search, default 10)" },
+                    "key": { "type": "string", "description": "Issue key, e.g. OPS-42 (get_issue, transition)" },
+                    "summary": { "type": "string", "description": "Issue title (create_issue)" },
+                    "description": { "type": "string", "description": "Issue body (create_issue)" },
+                    "issue_type": { "type": "string", "description": "Task, Bug, Story... (create_issue, default Task)" },
+                    "priority": { "type": "string", "description": "Highest, High, Medium, Low, Lowest (create_issue)" },
+                    "status_name": { "type": "string", "description": "Target status, e.g. In Progress (transition)" }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let str_arg = |key: &str| {
+            args[key].as_str().ok_or_else(|| BizClawError::Tool(format!("Missing '{key}'")))
+        };
+
+        let output = match str_arg("action")? {
+            "search" => {
+                let max = args["max_results"].as_u64().unwrap_or(10);
+                self.search(str_arg("jql")?, max).await?
+            }
+            "create_issue" => {
+                self.create_issue(
+                    str_arg("summary")?,
+                    args["description"].as_str(),
+                    args["issue_type"].as_str().unwrap_or("Task"),
+                    args["priority"].as_str(),
+                ).await?
+            }
+            "get_issue" => self.get_issue(str_arg("key")?).await?,
+            "transition" => self.transition(str_arg("key")?, str_arg("status_name")?).await?,
+            other => return Err(BizClawError::Tool(format!("Unknown jira action: {other}"))),
+        };
+
+        Ok(ToolResult::ok(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adf_roundtrip() {
+        let doc = to_adf("First line\n\nThird line");
+        assert_eq!(doc["content"].as_array().unwrap().len(), 3);
+        assert_eq!(adf_to_text(&doc), "First line\n\nThird line");
+    }
+
+    #[test]
+    fn test_format_issue_line() {
+        let issue = serde_json::json!({
+            "key": "OPS-7",
+            "fields": {
+                "summary": "Fix login",
+                "status": { "name": "In Progress" },
+                "issuetype": { "name": "Bug" },
+                "priority": { "name": "High" },
+                "assignee": null
+            }
+        });
+        assert_eq!(format_issue_line(&issue), "OPS-7 [In Progress] Fix login (Bug, High, unassigned)");
+    }
+
+    #[test]
+    fn test_url_trims_slash() {
+        let tool = JiraTool::new(JiraConfig {
+            base_url: "https://team.atlassian.net/".into(),
+            ..Default::default()
+        });
+        assert_eq!(tool.url("/search"), "https://team.atlassian.net/rest/api/3/search");
+    }
+}
@@ -6,25 +6,217 @@ pub mod file;
 pub mod registry;
 pub mod web_search;
 pub mod group_summarizer;
+pub mod group_digest;
 pub mod calendar;
 pub mod document_reader;
+pub mod code_exec;
+pub mod git;
+pub mod jira;
+pub mod linear;
+pub mod slack;
+pub mod notion;
+pub mod http_request;
+pub mod web_fetch;
+pub mod scheduler;
+pub mod notes;
+pub mod send_email;
+pub mod zalo_media;
+pub mod plugin;
 
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use bizclaw_core::config::BizClawConfig;
+use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolCall, ToolResult};
+use futures::StreamExt;
+use bizclaw_security::approval::{ApprovalBroker, ApprovalOutcome, AutonomyPolicy, PolicyDecision};
+
+/// Default wall-clock limit for one tool call.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Default cap on tool output returned to the model.
+const DEFAULT_MAX_OUTPUT_BYTES: usize = 64 * 1024;
+/// Default number of calls a batch runs at once.
+const DEFAULT_MAX_PARALLEL: usize = 5;
 
 /// Tool registry — manages available tools.
 pub struct ToolRegistry {
     tools: Vec<Box<dyn Tool>>,
+    default_timeout: Duration,
+    timeouts: HashMap<String, Duration>,
+    max_output_bytes: usize,
+    max_parallel: usize,
+    approval: Option<ApprovalGate>,
+}
+
+/// Holds tool calls the autonomy policy flags until a human decides.
+struct ApprovalGate {
+    policy: AutonomyPolicy,
+    broker: Arc<ApprovalBroker>,
+    timeout: Duration,
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
-        Self { tools: vec![] }
+        Self {
+            tools: vec![],
+            default_timeout: DEFAULT_TIMEOUT,
+            timeouts: HashMap::new(),
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            max_parallel: DEFAULT_MAX_PARALLEL,
+            approval: None,
+        }
+    }
+
+    /// Ask `broker` for a decision on calls `policy` flags, denying after `timeout`.
+    pub fn set_approval(&mut self, policy: AutonomyPolicy, broker: Arc<ApprovalBroker>, timeout: Duration) {
+        self.approval = Some(ApprovalGate { policy, broker, timeout });
+    }
+
+    /// Set the timeout for tools without a per-tool override.
+    pub fn set_default_timeout(&mut self, timeout: Duration) {
+        self.default_timeout = timeout;
+    }
+
+    /// Override the timeout for one tool.
+    pub fn set_timeout(&mut self, name: &str, timeout: Duration) {
+        self.timeouts.insert(name.to_string(), timeout);
+    }
+
+    /// Set the output size above which results are truncated.
+    pub fn set_max_output_bytes(&mut self, max: usize) {
+        self.max_output_bytes = max;
+    }
+
+    /// Set how many calls [`execute_batch`](Self::execute_batch) runs at once.
+    pub fn set_max_parallel(&mut self, max: usize) {
+        self.max_parallel = max.max(1);
+    }
+
+    fn timeout_for(&self, name: &str) -> Duration {
+        self.timeouts.get(name).copied().unwrap_or(self.default_timeout)
+    }
+
+    /// Run a tool by name under its timeout and output cap.
+    ///
+    /// A timeout is reported as a failed `ToolResult` rather than an error, so
+    /// the model sees what happened. The timed-out future is dropped, which is
+    /// what tools with child processes rely on to clean up. With an approval
+    /// gate set, flagged calls wait for a decision first (not counted against
+    /// the tool timeout). The output cap applies to each string in `data`
+    /// too; `truncated` and `duration_ms` are filled in here.
+    pub async fn execute(&self, name: &str, arguments: &str) -> Result<ToolResult> {
+        let tool = self.get(name)
+            .ok_or_else(|| BizClawError::ToolNotFound(name.to_string()))?;
+
+        if let Some(gate) = &self.approval
+            && let PolicyDecision::RequireApproval(reason) = gate.policy.evaluate(name, arguments)
+        {
+            let outcome = gate.broker.request(name, arguments, &reason, gate.timeout).await;
+            let decision = match &outcome {
+                ApprovalOutcome::Approved { .. } => None,
+                ApprovalOutcome::Denied { approver } => Some(format!("denied by {approver}")),
+                ApprovalOutcome::TimedOut => Some("no decision before timeout".to_string()),
+            };
+            if let Some(decision) = decision {
+                return Ok(ToolResult::failure("approval_denied", serde_json::json!({
+                    "error": "approval_denied",
+                    "tool": name,
+                    "reason": reason,
+                    "decision": decision,
+                }).to_string()));
+            }
+        }
+
+        let timeout = self.timeout_for(name);
+        let started = std::time::Instant::now();
+
+        let mut result = match tokio::time::timeout(timeout, tool.execute(arguments)).await {
+            Ok(result) => result?,
+            Err(_) => {
+                tracing::warn!("Tool '{name}' timed out after {}s", timeout.as_secs_f32());
+                ToolResult::failure(
+                    "timeout",
+                    format!("Tool '{name}' timed out after {}s", timeout.as_secs_f32()),
+                )
+            }
+        };
+        result.truncated |= result.output.len() > self.max_output_bytes;
+        result.output = registry::truncate_output(result.output, self.max_output_bytes);
+        result.truncated |= registry::truncate_data(&mut result.data, self.max_output_bytes);
+        result.duration_ms = Some(started.elapsed().as_millis() as u64);
+        Ok(result)
+    }
+
+    /// Run the tool calls from one model response and return one result per
+    /// call, in input order, with `tool_call_id` set.
+    ///
+    /// Calls run concurrently, up to the parallelism cap, each under its own
+    /// tool's timeout. Calls to tools that aren't
+    /// [`concurrency_safe`](Tool::concurrency_safe) run one after another,
+    /// alongside the rest. A call that errors gets a failed result carrying
+    /// the error rather than failing the batch.
+    pub async fn execute_batch(&self, calls: &[ToolCall]) -> Vec<ToolResult> {
+        let (serial, parallel): (Vec<usize>, Vec<usize>) = (0..calls.len())
+            .partition(|&i| self.get(&calls[i].function.name).is_some_and(|t| !t.concurrency_safe()));
+
+        let serial_run = async {
+            let mut done = Vec::with_capacity(serial.len());
+            for i in serial.iter().copied() {
+                done.push((i, self.execute_call(&calls[i]).await));
+            }
+            done
+        };
+        // The serial chain takes one of the slots.
+        let slots = self.max_parallel.saturating_sub(usize::from(!serial.is_empty())).max(1);
+        let parallel_run = futures::stream::iter(parallel)
+            .map(|i| async move { (i, self.execute_call(&calls[i]).await) })
+            .buffer_unordered(slots)
+            .collect::<Vec<_>>();
+
+        let (serial_done, parallel_done) = futures::join!(serial_run, parallel_run);
+        let mut results = vec![ToolResult::default(); calls.len()];
+        for (i, result) in serial_done.into_iter().chain(parallel_done) {
+            results[i] = result;
+        }
+        results
     }
 
+    async fn execute_call(&self, call: &ToolCall) -> ToolResult {
+        let mut result = match self.execute(&call.function.name, &call.function.arguments).await {
+            Ok(result) => result,
+            Err(BizClawError::ToolNotFound(name)) => ToolResult::failure("not_found", format!("Tool not found: {name}")),
+            Err(e) => ToolResult::failure("error", format!("Tool error: {e}")),
+        };
+        result.tool_call_id = call.id.clone();
+        result
+    }
+
+    /// Add a tool. An invalid parameter schema panics in debug builds, so it
+    /// is caught in tests, and is logged in release builds.
     pub fn register(&mut self, tool: Box<dyn Tool>) {
+        let errors = schema_errors(tool.as_ref());
+        if !errors.is_empty() {
+            let details: Vec<String> = errors.iter().map(|e| format!("{}: {}", e.path, e.message)).collect();
+            if cfg!(debug_assertions) {
+                panic!("Tool '{}' has an invalid parameter schema: {}", tool.name(), details.join("; "));
+            }
+            tracing::warn!("Tool '{}' has an invalid parameter schema: {}", tool.name(), details.join("; "));
+        }
         self.tools.push(tool);
     }
 
+    /// Tools whose parameter schema isn't valid JSON Schema draft-07, with
+    /// what is wrong. Empty when every schema is valid.
+    pub fn validate_all(&self) -> Vec<(String, Vec<registry::SchemaError>)> {
+        self.tools.iter()
+            .map(|t| (t.name().to_string(), schema_errors(t.as_ref())))
+            .filter(|(_, errors)| !errors.is_empty())
+            .collect()
+    }
+
     pub fn get(&self, name: &str) -> Option<&dyn Tool> {
         self.tools.iter().find(|t| t.name() == name).map(|t| t.as_ref())
     }
@@ -38,7 +230,7 @@ impl ToolRegistry {
         let mut reg = Self::new();
         reg.register(Box::new(shell::ShellTool::new()));
         reg.register(Box::new(file::FileTool::new()));
-        reg.register(Box::new(web_search::WebSearchTool::new()));
+        reg.register(Box::new(web_search::WebSearchTool::new(web_search::WebSearchConfig::default())));
         reg.register(Box::new(group_summarizer::GroupSummarizerTool::new(
             group_summarizer::SummarizerConfig::default(),
         )));
@@ -46,10 +238,126 @@ impl ToolRegistry {
             calendar::CalendarConfig::default(),
         )));
         reg.register(Box::new(document_reader::DocumentReaderTool::new()));
+        reg.register(Box::new(git::GitTool::new(git::GitConfig::default())));
+        reg.register(Box::new(http_request::HttpRequestTool::new(
+            http_request::HttpRequestConfig::default(),
+        )));
+        reg.register(Box::new(web_fetch::WebFetchTool::new(web_fetch::WebFetchConfig::default())));
+        reg
+    }
+
+    /// Create registry with only the tools enabled under `[tools]`, using their configured settings.
+    pub fn from_config(config: &BizClawConfig) -> Self {
+        let tools = &config.tools;
+        for name in tools.unknown.keys() {
+            tracing::warn!("Ignoring config for unknown tool '{name}'");
+        }
+
+        let mut reg = Self::new();
+        reg.set_default_timeout(Duration::from_secs(tools.timeout_secs));
+        for (name, secs) in &tools.timeouts {
+            reg.set_timeout(name, Duration::from_secs(*secs));
+        }
+        reg.set_max_output_bytes(tools.max_output_bytes);
+        reg.set_max_parallel(config.autonomy.max_parallel_tools);
+        // At level "approval" shell and file ask the global broker themselves
+        // when their allowlist refuses a call, so no registry gate is set.
+        if tools.shell.enabled {
+            reg.register(Box::new(
+                shell::ShellTool::with_workdir(tools.shell.workdir.clone()).with_autonomy(&config.autonomy),
+            ));
+        }
+        if tools.file.enabled {
+            reg.register(Box::new(file::FileTool::with_autonomy(&config.autonomy)));
+        }
+        if tools.web_search.enabled {
+            reg.register(Box::new(web_search::WebSearchTool::new((&tools.web_search).into())));
+        }
+        if tools.group_summarizer.enabled {
+            reg.register(Box::new(group_summarizer::GroupSummarizerTool::with_buffer(
+                group_summarizer::MessageBuffer::global(),
+                (&tools.group_summarizer).into(),
+            )));
+        }
+        if tools.calendar.enabled {
+            reg.register(Box::new(calendar::CalendarTool::new((&tools.calendar).into())));
+        }
+        if tools.document_reader.enabled {
+            reg.register(Box::new(document_reader::DocumentReaderTool::new()));
+        }
+        if tools.code_exec.enabled {
+            reg.register(Box::new(code_exec::CodeExecTool::new(
+                code_exec::CodeExecConfig::from_config(config),
+            )));
+        }
+        if tools.git.enabled {
+            reg.register(Box::new(git::GitTool::new(git::GitConfig::from_config(config))));
+        }
+        if tools.jira.enabled {
+            reg.register(Box::new(jira::JiraTool::new((&tools.jira).into())));
+        }
+        if tools.linear.enabled {
+            reg.register(Box::new(linear::LinearTool::new((&tools.linear).into())));
+        }
+        if tools.slack.enabled {
+            reg.register(Box::new(
+                slack::SlackTool::new((&tools.slack).into())
+                    .with_allowlist(bizclaw_security::allowlist::Allowlist::new(&config.autonomy)),
+            ));
+        }
+        if !tools.notion.token.is_empty() {
+            reg.register(Box::new(notion::NotionTool::new((&tools.notion).into())));
+        }
+        if tools.http_request.enabled {
+            reg.register(Box::new(http_request::HttpRequestTool::new((&tools.http_request).into())));
+        }
+        if tools.web_fetch.enabled {
+            reg.register(Box::new(web_fetch::WebFetchTool::new(web_fetch::WebFetchConfig::from_config(config))));
+        }
+        if tools.notes.enabled {
+            match notes::NoteStore::open_default() {
+                Ok(store) => reg.register(Box::new(notes::NotesTool::new(store, notes::Embedder::from_config(config)))),
+                Err(e) => tracing::warn!("Notes disabled: {e}"),
+            }
+        }
+        if tools.send_email.enabled {
+            match send_email::SendEmailConfig::from_config(config) {
+                Some(email) => reg.register(Box::new(send_email::SendEmailTool::new(
+                    email,
+                    bizclaw_security::allowlist::Allowlist::new(&config.autonomy),
+                ))),
+                None => tracing::warn!("send_email disabled: it needs an SMTP account under [channel.email]"),
+            }
+        }
+        if tools.scheduler.enabled {
+            match scheduler::JobStore::from_config(config) {
+                Ok(store) => reg.register(Box::new(scheduler::SchedulerTool::new(Arc::new(store)))),
+                Err(e) => tracing::warn!("Scheduler disabled: {e}"),
+            }
+        }
+        if let Some(dir) = &config.plugin_dir {
+            match plugin::PluginLoader::load_all(dir) {
+                Ok(plugins) => for tool in plugins {
+                    if reg.get(tool.name()).is_some() {
+                        tracing::warn!("Tool plugin '{}' skipped: a built-in tool has that name", tool.name());
+                    } else {
+                        reg.register(tool);
+                    }
+                },
+                Err(e) => tracing::error!("{e}"),
+            }
+        }
         reg
     }
 }
 
+/// Problems with `tool`'s parameter schema, including one that won't compile.
+fn schema_errors(tool: &dyn Tool) -> Vec<registry::SchemaError> {
+    registry::validate_tool_schema(&tool.definition().parameters).unwrap_or_else(|e| {
+        vec![registry::SchemaError { path: String::new(), message: e.to_string() }]
+    })
+}
+
 impl Default for ToolRegistry {
     fn default() -> Self { Self::with_defaults() }
 }
@@ -67,9 +375,38 @@ mod tests {
         assert!(reg.get("group_summarizer").is_some());
         assert!(reg.get("calendar").is_some());
         assert!(reg.get("document_reader").is_some());
+        assert!(reg.get("git").is_some());
+        assert!(reg.get("http_request").is_some());
+        assert!(reg.get("web_fetch").is_some());
         assert!(reg.get("nonexistent").is_none());
     }
 
+    #[test]
+    fn test_tool_schemas_valid() {
+        assert!(ToolRegistry::with_defaults().validate_all().is_empty());
+
+        // Every tool, including the ones off by default.
+        let config: BizClawConfig = serde_json::from_value(serde_json::json!({
+            "tools": {
+                "jira": { "enabled": true },
+                "linear": { "enabled": true },
+                "slack": { "enabled": true },
+                "notion": { "token": "secret" },
+                "code_exec": { "enabled": true },
+                "git": { "enabled": true },
+                "http_request": { "enabled": true },
+                "web_fetch": { "enabled": true },
+                "send_email": { "enabled": true }
+            },
+            "channel": { "email": { "email": "shop@example.com" } }
+        })).unwrap();
+        let reg = ToolRegistry::from_config(&config);
+        assert!(reg.get("notion").is_some());
+        assert!(reg.get("send_email").is_some());
+        assert!(reg.get("code_exec").is_some());
+        assert_eq!(reg.validate_all(), vec![]);
+    }
+
     #[test]
     fn test_registry_list() {
         let reg = ToolRegistry::with_defaults();
@@ -83,10 +420,244 @@ mod tests {
         assert!(defs.iter().any(|d| d.name == "document_reader"));
     }
 
+    #[test]
+    fn test_registry_from_config() {
+        let config: BizClawConfig = serde_json::from_value(serde_json::json!({
+            "tools": {
+                "shell": { "enabled": false },
+                "calendar": { "api_key": "cal-key" },
+                "jira": { "enabled": true, "base_url": "https://team.atlassian.net", "project_key": "OPS" },
+                "not_a_tool": { "enabled": true }
+            }
+        })).unwrap();
+
+        let reg = ToolRegistry::from_config(&config);
+        assert!(reg.get("shell").is_none());
+        assert!(reg.get("calendar").is_some());
+        assert!(reg.get("file").is_some());
+        assert!(reg.get("jira").is_some());
+        assert!(reg.get("linear").is_none());
+        assert!(reg.get("send_email").is_none());
+        assert!(reg.get("not_a_tool").is_none());
+        // Tools that run code, change files or reach the network are opt-in.
+        for name in ["code_exec", "git", "http_request", "web_fetch", "scheduler", "notes"] {
+            assert!(reg.get(name).is_none(), "{name} should be off by default");
+        }
+
+        let calendar: calendar::CalendarConfig = (&config.tools.calendar).into();
+        assert_eq!(calendar.api_key.as_deref(), Some("cal-key"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_timeout_kills_shell_child() {
+        let marker = std::env::temp_dir().join(format!("bizclaw-timeout-{}", uuid::Uuid::new_v4().simple()));
+        let mut reg = ToolRegistry::new();
+        reg.register(Box::new(shell::ShellTool::new()));
+        reg.set_timeout("shell", Duration::from_millis(200));
+
+        let args = serde_json::json!({ "command": format!("sleep 1 && touch {}", marker.display()) });
+        let result = reg.execute("shell", &args.to_string()).await.unwrap();
+        assert!(!result.success);
+        assert!(result.output.contains("timed out after 0.2s"));
+        assert_eq!(result.error_kind.as_deref(), Some("timeout"));
+        assert!(result.duration_ms.is_some_and(|ms| ms >= 200));
+
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists(), "shell child kept running after timeout");
+    }
+
+    #[tokio::test]
+    async fn test_execute_truncates_output() {
+        let mut reg = ToolRegistry::new();
+        reg.register(Box::new(shell::ShellTool::new()));
+        reg.set_max_output_bytes(100);
+
+        let result = reg.execute("shell", r#"{"command": "head -c 1000 /dev/zero | tr '\\0' a"}"#).await.unwrap();
+        assert!(result.output.starts_with(&"a".repeat(100)));
+        assert!(result.output.contains("truncated: showing 100 of 1000 bytes"));
+        assert!(result.truncated);
+        assert!(result.data["stdout"].as_str().unwrap().contains("truncated: showing 100 of 1000 bytes"));
+        assert!(result.duration_ms.is_some());
+
+        let result = reg.execute("shell", r#"{"command": "echo short"}"#).await.unwrap();
+        assert!(!result.truncated);
+
+        assert!(matches!(reg.execute("nope", "{}").await, Err(BizClawError::ToolNotFound(_))));
+    }
+
+    /// Sleeps for `delay`, counting its calls and how many overlap.
+    struct SleepTool {
+        name: &'static str,
+        delay: Duration,
+        serial: bool,
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+        running: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    }
+
+    impl SleepTool {
+        fn new(name: &'static str, delay_ms: u64) -> Self {
+            Self {
+                name,
+                delay: Duration::from_millis(delay_ms),
+                serial: false,
+                calls: Default::default(),
+                running: Default::default(),
+                peak: Default::default(),
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for SleepTool {
+        fn name(&self) -> &str { self.name }
+
+        fn definition(&self) -> bizclaw_core::types::ToolDefinition {
+            bizclaw_core::types::ToolDefinition {
+                name: self.name.into(),
+                description: "test tool".into(),
+                parameters: serde_json::json!({"type": "object"}),
+            }
+        }
+
+        fn concurrency_safe(&self) -> bool { !self.serial }
+
+        async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+            use std::sync::atomic::Ordering;
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            let now = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.peak.fetch_max(now, Ordering::SeqCst);
+            tokio::time::sleep(self.delay).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            if arguments.contains("fail") {
+                return Err(BizClawError::Tool("boom".into()));
+            }
+            Ok(ToolResult::ok(format!("{} done", self.name)))
+        }
+    }
+
+    fn call(id: &str, name: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.into(),
+            r#type: "function".into(),
+            function: bizclaw_core::types::FunctionCall { name: name.into(), arguments: arguments.into() },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_runs_calls_concurrently() {
+        use std::sync::atomic::Ordering;
+        let (weather, calendar) = (SleepTool::new("weather", 300), SleepTool::new("calendar", 300));
+        let (weather_calls, calendar_calls) = (weather.calls.clone(), calendar.calls.clone());
+        let mut reg = ToolRegistry::new();
+        reg.register(Box::new(weather));
+        reg.register(Box::new(calendar));
+
+        let calls = [call("call_1", "weather", "{}"), call("call_2", "calendar", "{}"), call("call_3", "missing", "{}")];
+        let started = std::time::Instant::now();
+        let results = reg.execute_batch(&calls).await;
+        let elapsed = started.elapsed();
+
+        assert!(elapsed < Duration::from_millis(550), "calls did not overlap: {elapsed:?}");
+        assert_eq!(weather_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(calendar_calls.load(Ordering::SeqCst), 1);
+        let ids: Vec<&str> = results.iter().map(|r| r.tool_call_id.as_str()).collect();
+        assert_eq!(ids, ["call_1", "call_2", "call_3"]);
+        assert_eq!(results[0].output, "weather done");
+        assert_eq!(results[1].output, "calendar done");
+        assert_eq!(results[2].output, "Tool not found: missing");
+        assert_eq!(results[2].error_kind.as_deref(), Some("not_found"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_times_out_per_tool() {
+        let mut reg = ToolRegistry::new();
+        reg.register(Box::new(SleepTool::new("slow", 5_000)));
+        reg.register(Box::new(SleepTool::new("fast", 50)));
+        reg.set_timeout("slow", Duration::from_millis(200));
+
+        let started = std::time::Instant::now();
+        let results = reg.execute_batch(&[call("s", "slow", "{}"), call("f", "fast", "{}")]).await;
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(results[0].error_kind.as_deref(), Some("timeout"));
+        assert_eq!(results[0].tool_call_id, "s");
+        assert!(results[1].success);
+        assert!(results[1].duration_ms.is_some_and(|ms| ms < 200));
+    }
+
+    #[tokio::test]
+    async fn test_execute_batch_cap_and_serial_tools() {
+        use std::sync::atomic::Ordering;
+        let safe = SleepTool::new("safe", 100);
+        let serial = SleepTool { serial: true, ..SleepTool::new("serial", 100) };
+        let (safe_calls, safe_peak, serial_peak) = (safe.calls.clone(), safe.peak.clone(), serial.peak.clone());
+        let mut reg = ToolRegistry::new();
+        reg.register(Box::new(safe));
+        reg.register(Box::new(serial));
+        reg.set_max_parallel(3);
+
+        let mut calls: Vec<ToolCall> = (0..5)
+            .map(|i| call(&format!("c{i}"), "safe", if i == 3 { r#"{"fail":true}"# } else { "{}" }))
+            .collect();
+        calls.extend((5..8).map(|i| call(&format!("c{i}"), "serial", "{}")));
+        let started = std::time::Instant::now();
+        let results = reg.execute_batch(&calls).await;
+
+        // Three serial calls in a row, with the safe ones two at a time beside them.
+        assert!(started.elapsed() >= Duration::from_millis(300));
+        assert!(started.elapsed() < Duration::from_millis(700));
+        assert_eq!(serial_peak.load(Ordering::SeqCst), 1);
+        assert_eq!(safe_peak.load(Ordering::SeqCst), 2);
+        assert_eq!(safe_calls.load(Ordering::SeqCst), 5);
+        assert!(results.iter().enumerate().all(|(i, r)| r.tool_call_id == format!("c{i}")));
+        assert!(!results[3].success);
+        assert!(results[3].output.contains("boom"));
+        assert_eq!(results.iter().filter(|r| r.success).count(), 7);
+    }
+
     #[test]
     fn test_registry_empty() {
         let reg = ToolRegistry::new();
         assert!(reg.list().is_empty());
         assert!(reg.get("shell").is_none());
     }
+
+    #[tokio::test]
+    async fn test_execute_waits_for_approval() {
+        let autonomy = bizclaw_core::config::AutonomyConfig {
+            level: "approval".into(),
+            allowed_commands: vec!["echo".into()],
+            forbidden_paths: vec![],
+            ..Default::default()
+        };
+        let broker = Arc::new(ApprovalBroker::new());
+        let mut reg = ToolRegistry::new();
+        reg.register(Box::new(shell::ShellTool::new()));
+        reg.set_approval(AutonomyPolicy::new(&autonomy), broker.clone(), Duration::from_secs(5));
+
+        // Allowed commands run without asking.
+        let result = reg.execute("shell", r#"{"command":"echo hi"}"#).await.unwrap();
+        assert!(result.success);
+        assert!(broker.pending().is_empty());
+
+        let mut events = broker.subscribe();
+        let approver = tokio::spawn({
+            let broker = broker.clone();
+            async move {
+                let first = events.recv().await.unwrap();
+                broker.resolve(&first.id, true, "ops");
+                let second = events.recv().await.unwrap();
+                broker.resolve(&second.id, false, "ops");
+            }
+        });
+        let approved = reg.execute("shell", r#"{"command":"printf ok"}"#).await.unwrap();
+        assert!(approved.success);
+        assert_eq!(approved.output.trim(), "ok");
+
+        let denied = reg.execute("shell", r#"{"command":"printf no"}"#).await.unwrap();
+        assert!(!denied.success);
+        assert!(denied.output.contains("denied by ops"));
+        assert_eq!(denied.error_kind.as_deref(), Some("approval_denied"));
+        approver.await.unwrap();
+    }
 }
@@ -0,0 +1,261 @@
+//! Linear Tool — search, create, and transition issues via the Linear GraphQL API.
+//!
+//! Same actions as the Jira tool; `status_name` maps to a workflow state of the team.
+
+use async_trait::async_trait;
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_core::error::{BizClawError, Result};
+use serde::{Deserialize, Serialize};
+
+const LINEAR_API: &str = "https://api.linear.app/graphql";
+
+/// Linear tool configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LinearConfig {
+    /// Personal API key
+    pub api_key: String,
+    /// Team new issues are created in and searches are scoped to
+    pub team_id: String,
+}
+
+impl From<&bizclaw_core::config::LinearToolConfig> for LinearConfig {
+    fn from(cfg: &bizclaw_core::config::LinearToolConfig) -> Self {
+        Self {
+            api_key: cfg.api_key.clone(),
+            team_id: cfg.team_id.clone(),
+        }
+    }
+}
+
+/// Linear issue tracker tool.
+pub struct LinearTool {
+    config: LinearConfig,
+    client: reqwest::Client,
+}
+
+const ISSUE_FIELDS: &str = "identifier title url priorityLabel state { name } assignee { name }";
+
+impl LinearTool {
+    pub fn new(config: LinearConfig) -> Self {
+        Self {
+            config,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Run a GraphQL query and return its `data`, surfacing GraphQL errors.
+    async fn graphql(&self, query: &str, variables: serde_json::Value) -> Result<serde_json::Value> {
+        let response = self.client.post(LINEAR_API)
+            .header("Authorization", &self.config.api_key)
+            .json(&serde_json::json!({ "query": query, "variables": variables }))
+            .send()
+            .await
+            .map_err(|e| BizClawError::Tool(format!("Linear request failed: {e}")))?;
+
+        let status = response.status();
+        let body: serde_json::Value = response.json().await
+            .map_err(|e| BizClawError::Tool(format!("Parse Linear response failed: {e}")))?;
+        if let Some(err) = body["errors"].as_array().and_then(|e| e.first()) {
+            return Err(BizClawError::Tool(format!(
+                "Linear API error: {}",
+                err["message"].as_str().unwrap_or("unknown")
+            )));
+        }
+        if !status.is_success() {
+            return Err(BizClawError::Tool(format!("Linear API error {status}: {body}")));
+        }
+        Ok(body["data"].clone())
+    }
+
+    async fn search(&self, term: &str, max_results: u64) -> Result<String> {
+        let query = format!(
+            "query($team: ID!, $term: String!, $first: Int!) {{
+                issues(first: $first, filter: {{
+                    team: {{ id: {{ eq: $team }} }},
+                    or: [{{ title: {{ containsIgnoreCase: $term }} }}, {{ description: {{ containsIgnoreCase: $term }} }}]
+                }}) {{ nodes {{ {ISSUE_FIELDS} }} }}
+            }}"
+        );
+        let data = self.graphql(&query, serde_json::json!({
+            "team": self.config.team_id, "term": term, "first": max_results,
+        })).await?;
+
+        let issues = data["issues"]["nodes"].as_array().cloned().unwrap_or_default();
+        if issues.is_empty() {
+            return Ok(format!("No issues match: {term}"));
+        }
+        let mut out = format!("Found {} issue(s) for \"{term}\":\n\n", issues.len());
+        for issue in &issues {
+            out.push_str(&format_issue_line(issue));
+            out.push('\n');
+        }
+        Ok(out)
+    }
+
+    async fn create_issue(&self, title: &str, description: Option<&str>, priority: Option<&str>) -> Result<String> {
+        let query = format!(
+            "mutation($input: IssueCreateInput!) {{
+                issueCreate(input: $input) {{ success issue {{ {ISSUE_FIELDS} }} }}
+            }}"
+        );
+        let mut input = serde_json::json!({ "teamId": self.config.team_id, "title": title });
+        if let Some(desc) = description {
+            input["description"] = desc.into();
+        }
+        if let Some(p) = priority {
+            input["priority"] = priority_value(p).into();
+        }
+        let data = self.graphql(&query, serde_json::json!({ "input": input })).await?;
+        let issue = &data["issueCreate"]["issue"];
+        Ok(format!(
+            "Created {}: {title}\nLink: {}",
+            issue["identifier"].as_str().unwrap_or("unknown"),
+            issue["url"].as_str().unwrap_or("")
+        ))
+    }
+
+    async fn get_issue(&self, key: &str) -> Result<String> {
+        let query = format!(
+            "query($id: String!) {{ issue(id: $id) {{ {ISSUE_FIELDS} description createdAt creator {{ name }} }} }}"
+        );
+        let data = self.graphql(&query, serde_json::json!({ "id": key })).await?;
+        let issue = &data["issue"];
+        let mut out = format_issue_line(issue);
+        if let Some(creator) = issue["creator"]["name"].as_str() {
+            out.push_str(&format!("\nReporter: {creator}"));
+        }
+        if let Some(created) = issue["createdAt"].as_str() {
+            out.push_str(&format!("\nCreated: {created}"));
+        }
+        if let Some(desc) = issue["description"].as_str().filter(|d| !d.is_empty()) {
+            out.push_str(&format!("\n\n{desc}"));
+        }
+        Ok(out)
+    }
+
+    async fn transition(&self, key: &str, status_name: &str) -> Result<String> {
+        let states = self.graphql(
+            "query($team: ID!) { workflowStates(filter: { team: { id: { eq: $team } } }) { nodes { id name } } }",
+            serde_json::json!({ "team": self.config.team_id }),
+        ).await?;
+        let nodes = states["workflowStates"]["nodes"].as_array().cloned().unwrap_or_default();
+
+        let wanted = status_name.to_lowercase();
+        let Some(state) = nodes.iter().find(|s| s["name"].as_str().is_some_and(|n| n.to_lowercase() == wanted)) else {
+            let available: Vec<&str> = nodes.iter().filter_map(|s| s["name"].as_str()).collect();
+            return Err(BizClawError::Tool(format!(
+                "No workflow state '{status_name}'. Available: {}",
+                available.join(", ")
+            )));
+        };
+
+        self.graphql(
+            "mutation($id: String!, $state: String!) { issueUpdate(id: $id, input: { stateId: $state }) { success } }",
+            serde_json::json!({ "id": key, "state": state["id"] }),
+        ).await?;
+        Ok(format!("{key} moved to {}", state["name"].as_str().unwrap_or(status_name)))
+    }
+}
+
+/// Map a priority name to Linear's numeric scale (0 = none, 1 = urgent … 4 = low).
+fn priority_value(name: &str) -> u8 {
+    match name.to_lowercase().as_str() {
+        "urgent" | "highest" => 1,
+        "high" => 2,
+        "medium" | "normal" => 3,
+        "low" | "lowest" => 4,
+        _ => 0,
+    }
+}
+
+/// One-line summary: `ID [State] Title (Priority, Assignee)`.
+fn format_issue_line(issue: &serde_json::Value) -> String {
+    format!(
+        "{} [{}] {} ({}, {})",
+        issue["identifier"].as_str().unwrap_or("?"),
+        issue["state"]["name"].as_str().unwrap_or("?"),
+        issue["title"].as_str().unwrap_or(""),
+        issue["priorityLabel"].as_str().unwrap_or("No priority"),
+        issue["assignee"]["name"].as_str().unwrap_or("unassigned"),
+    )
+}
+
+#[async_trait]
+impl Tool for LinearTool {
+    fn name(&self) -> &str { "linear" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "linear".into(),
+            description: "Search, create, view, and transition Linear issues.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["search", "create_issue", "get_issue", "transition"],
+                        "description": "Operation to perform"
+                    },
+                    "query": { "type": "string", "description": "Text to search in titles and descriptions (search)" },
+                    "max_results": { "type": "integer", "description": "Max issues (search, default 10)" },
+                    "key": { "type": "string", "description": "Issue identifier, e.g. ENG-42 (get_issue, transition)" },
+                    "summary": { "type": "string", "description": "Issue title (create_issue)" },
+                    "description": { "type": "string", "description": "Issue body in markdown (create_issue)" },
+                    "priority": { "type": "string", "description": "Urgent, High, Medium, Low (create_issue)" },
+                    "status_name": { "type": "string", "description": "Target workflow state, e.g. In Progress (transition)" }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let str_arg = |key: &str| {
+            args[key].as_str().ok_or_else(|| BizClawError::Tool(format!("Missing '{key}'")))
+        };
+
+        let output = match str_arg("action")? {
+            "search" => {
+                let max = args["max_results"].as_u64().unwrap_or(10);
+                self.search(str_arg("query")?, max).await?
+            }
+            "create_issue" => {
+                self.create_issue(str_arg("summary")?, args["description"].as_str(), args["priority"].as_str())
+                    .await?
+            }
+            "get_issue" => self.get_issue(str_arg("key")?).await?,
+            "transition" => self.transition(str_arg("key")?, str_arg("status_name")?).await?,
+            other => return Err(BizClawError::Tool(format!("Unknown linear action: {other}"))),
+        };
+
+        Ok(ToolResult::ok(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_priority_value() {
+        assert_eq!(priority_value("Urgent"), 1);
+        assert_eq!(priority_value("high"), 2);
+        assert_eq!(priority_value("Low"), 4);
+        assert_eq!(priority_value("whenever"), 0);
+    }
+
+    #[test]
+    fn test_format_issue_line() {
+        let issue = serde_json::json!({
+            "identifier": "ENG-12",
+            "title": "Ship it",
+            "priorityLabel": "High",
+            "state": { "name": "Todo" },
+            "assignee": { "name": "Lan" }
+        });
+        assert_eq!(format_issue_line(&issue), "ENG-12 [Todo] Ship it (High, Lan)");
+    }
+}
@@ -0,0 +1,418 @@
+//! Notes Tool — explicit key-value memory ("remember that the office wifi
+//! password is X").
+//!
+//! Notes live in SQLite (`<data dir>/notes.db`), keyed by namespace and key.
+//! The `global` namespace is shared by every conversation; any other
+//! namespace (typically a conversation or thread ID) keeps notes private to
+//! it. A note can expire after a TTL. Search matches substrings, and when
+//! `memory.embedding_provider` is `ollama` or `openai` it also ranks notes by
+//! embedding similarity.
+
+use async_trait::async_trait;
+use bizclaw_core::config::BizClawConfig;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use chrono::{DateTime, Utc};
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Namespace shared by all conversations.
+pub const GLOBAL: &str = "global";
+
+/// Embedding results below this similarity are not reported as matches.
+const MIN_SIMILARITY: f32 = 0.5;
+
+/// A stored note.
+#[derive(Debug, Clone, Serialize)]
+pub struct Note {
+    pub namespace: String,
+    pub key: String,
+    pub value: String,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A search hit; `score` is 1.0 for substring matches, else the similarity.
+#[derive(Debug, Clone, Serialize)]
+pub struct NoteMatch {
+    pub note: Note,
+    pub score: f32,
+}
+
+/// Computes embeddings for semantic search.
+pub enum Embedder {
+    Ollama { url: String, model: String },
+    OpenAi { api_key: String, model: String },
+}
+
+impl Embedder {
+    /// The embedder for `memory.embedding_provider`, if any.
+    pub fn from_config(config: &BizClawConfig) -> Option<Self> {
+        match config.memory.embedding_provider.as_str() {
+            "ollama" => Some(Self::Ollama {
+                url: std::env::var("OLLAMA_HOST").unwrap_or_else(|_| "http://localhost:11434".into()),
+                model: "nomic-embed-text".into(),
+            }),
+            "openai" => {
+                let api_key = Some(config.api_key.clone())
+                    .filter(|k| !k.is_empty() && config.default_provider == "openai")
+                    .or_else(|| std::env::var("OPENAI_API_KEY").ok())?;
+                Some(Self::OpenAi { api_key, model: "text-embedding-3-small".into() })
+            }
+            _ => None,
+        }
+    }
+
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let client = reqwest::Client::new();
+        let (request, pointer) = match self {
+            Self::Ollama { url, model } => (
+                client.post(format!("{}/api/embeddings", url.trim_end_matches('/')))
+                    .json(&serde_json::json!({"model": model, "prompt": text})),
+                "/embedding",
+            ),
+            Self::OpenAi { api_key, model } => (
+                client.post("https://api.openai.com/v1/embeddings")
+                    .bearer_auth(api_key)
+                    .json(&serde_json::json!({"model": model, "input": text})),
+                "/data/0/embedding",
+            ),
+        };
+        let body: serde_json::Value = request
+            .timeout(std::time::Duration::from_secs(15))
+            .send().await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| BizClawError::Tool(format!("Embedding request failed: {e}")))?
+            .json().await
+            .map_err(|e| BizClawError::Tool(format!("Bad embedding response: {e}")))?;
+        body.pointer(pointer)
+            .and_then(|v| v.as_array())
+            .map(|values| values.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect())
+            .filter(|v: &Vec<f32>| !v.is_empty())
+            .ok_or_else(|| BizClawError::Tool("Embedding response has no vector".into()))
+    }
+}
+
+/// SQLite-backed note store.
+pub struct NoteStore {
+    conn: Mutex<Connection>,
+}
+
+impl NoteStore {
+    /// Open (or create) the store at `path`.
+    pub fn open(path: &Path) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| BizClawError::Memory(format!("DB open error: {e}")))?;
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS notes (
+                namespace TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                embedding TEXT,
+                updated_at INTEGER NOT NULL,
+                expires_at INTEGER,
+                PRIMARY KEY (namespace, key)
+            );
+        ").map_err(|e| BizClawError::Memory(format!("Migration error: {e}")))?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    /// Open `<data dir>/notes.db`.
+    pub fn open_default() -> Result<Self> {
+        Self::open(&BizClawConfig::data_dir().join("notes.db"))
+    }
+
+    /// Create or replace a note. `ttl` of `None` keeps it until deleted.
+    pub fn set(&self, namespace: &str, key: &str, value: &str, ttl: Option<chrono::Duration>, embedding: Option<&[f32]>) -> Result<Note> {
+        let now = Utc::now();
+        let expires_at = ttl.map(|ttl| now + ttl);
+        let conn = self.conn.lock().unwrap();
+        Self::purge_expired(&conn, now)?;
+        conn.execute(
+            "INSERT OR REPLACE INTO notes (namespace, key, value, embedding, updated_at, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                namespace, key, value,
+                embedding.map(|e| serde_json::to_string(e).unwrap_or_default()),
+                now.timestamp(), expires_at.map(|t| t.timestamp()),
+            ],
+        ).map_err(|e| BizClawError::Memory(format!("Save note: {e}")))?;
+        Ok(Note {
+            namespace: namespace.into(),
+            key: key.into(),
+            value: value.into(),
+            updated_at: now,
+            expires_at,
+        })
+    }
+
+    pub fn get(&self, namespace: &str, key: &str) -> Result<Option<Note>> {
+        self.conn.lock().unwrap().query_row(
+            "SELECT namespace, key, value, updated_at, expires_at FROM notes
+             WHERE namespace = ?1 AND key = ?2 AND (expires_at IS NULL OR expires_at > ?3)",
+            params![namespace, key, Utc::now().timestamp()],
+            row_to_note,
+        ).optional().map_err(|e| BizClawError::Memory(format!("Get note: {e}")))
+    }
+
+    /// Live notes in `namespace` (all namespaces if `None`), by key.
+    pub fn list(&self, namespace: Option<&str>) -> Result<Vec<Note>> {
+        Ok(self.rows(namespace)?.into_iter().map(|(note, _)| note).collect())
+    }
+
+    /// Notes whose key or value contains `query` (case-insensitive), then,
+    /// given a query embedding, the most similar of the rest.
+    pub fn search(&self, namespace: Option<&str>, query: &str, query_embedding: Option<&[f32]>, limit: usize) -> Result<Vec<NoteMatch>> {
+        let needle = query.to_lowercase();
+        let (matches, rest): (Vec<_>, Vec<_>) = self.rows(namespace)?.into_iter()
+            .partition(|(note, _)| {
+                note.key.to_lowercase().contains(&needle) || note.value.to_lowercase().contains(&needle)
+            });
+        let mut matches: Vec<NoteMatch> = matches.into_iter().map(|(note, _)| NoteMatch { note, score: 1.0 }).collect();
+
+        if let Some(query_embedding) = query_embedding {
+            let mut similar: Vec<NoteMatch> = rest.into_iter()
+                .filter_map(|(note, embedding)| {
+                    let score = bizclaw_memory::vector::cosine_similarity(query_embedding, &embedding?);
+                    (score >= MIN_SIMILARITY).then_some(NoteMatch { note, score })
+                })
+                .collect();
+            similar.sort_by(|a, b| b.score.total_cmp(&a.score));
+            matches.extend(similar);
+        }
+        matches.truncate(limit);
+        Ok(matches)
+    }
+
+    pub fn delete(&self, namespace: &str, key: &str) -> Result<bool> {
+        let n = self.conn.lock().unwrap()
+            .execute("DELETE FROM notes WHERE namespace = ?1 AND key = ?2", params![namespace, key])
+            .map_err(|e| BizClawError::Memory(format!("Delete note: {e}")))?;
+        Ok(n > 0)
+    }
+
+    fn rows(&self, namespace: Option<&str>) -> Result<Vec<(Note, Option<Vec<f32>>)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT namespace, key, value, updated_at, expires_at, embedding FROM notes
+             WHERE (?1 IS NULL OR namespace = ?1) AND (expires_at IS NULL OR expires_at > ?2)
+             ORDER BY namespace, key"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+        let rows = stmt.query_map(params![namespace, Utc::now().timestamp()], |row| {
+            let embedding: Option<String> = row.get(5)?;
+            Ok((row_to_note(row)?, embedding.and_then(|e| serde_json::from_str(&e).ok())))
+        }).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .collect();
+        Ok(rows)
+    }
+
+    fn purge_expired(conn: &Connection, now: DateTime<Utc>) -> Result<()> {
+        conn.execute("DELETE FROM notes WHERE expires_at IS NOT NULL AND expires_at <= ?1", params![now.timestamp()])
+            .map_err(|e| BizClawError::Memory(format!("Purge notes: {e}")))?;
+        Ok(())
+    }
+}
+
+fn row_to_note(row: &rusqlite::Row) -> rusqlite::Result<Note> {
+    let time = |secs: i64| DateTime::from_timestamp(secs, 0).unwrap_or_default();
+    Ok(Note {
+        namespace: row.get(0)?,
+        key: row.get(1)?,
+        value: row.get(2)?,
+        updated_at: time(row.get(3)?),
+        expires_at: row.get::<_, Option<i64>>(4)?.map(time),
+    })
+}
+
+/// Notes tool — lets the agent save and recall facts on request.
+pub struct NotesTool {
+    store: NoteStore,
+    embedder: Option<Embedder>,
+}
+
+impl NotesTool {
+    pub fn new(store: NoteStore, embedder: Option<Embedder>) -> Self {
+        Self { store, embedder }
+    }
+
+    /// Embedding for `text`, or `None` (with a warning) if it can't be computed.
+    async fn try_embed(&self, text: &str) -> Option<Vec<f32>> {
+        match self.embedder.as_ref()?.embed(text).await {
+            Ok(embedding) => Some(embedding),
+            Err(e) => {
+                tracing::warn!("Notes: {e}; falling back to substring search");
+                None
+            }
+        }
+    }
+}
+
+fn describe(note: &Note) -> String {
+    let mut line = format!("[{}] {} = {}", note.namespace, note.key, note.value);
+    if let Some(expires) = note.expires_at {
+        line.push_str(&format!(" (expires {})", expires.format("%Y-%m-%d %H:%M UTC")));
+    }
+    line
+}
+
+#[async_trait]
+impl Tool for NotesTool {
+    fn name(&self) -> &str { "notes" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "notes".into(),
+            description: "Save and recall facts the user explicitly asks you to remember (e.g. 'remember the office wifi \
+                password is X'), and look them up later. Use 'set' to save, 'get' when you know the key, 'search' when \
+                you don't, 'list' to show saved notes, 'delete' to forget one. Notes persist across restarts."
+                .into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["set", "get", "search", "list", "delete"] },
+                    "key": { "type": "string", "description": "Short, stable name for the fact, e.g. 'office_wifi_password' (set, get, delete)" },
+                    "value": { "type": "string", "description": "The fact to remember (set)" },
+                    "query": { "type": "string", "description": "Words to look for in keys and values (search)" },
+                    "namespace": { "type": "string", "description": "'global' (default) for facts that apply everywhere, or this conversation's ID for notes private to it" },
+                    "ttl_secs": { "type": "integer", "description": "Forget the note after this many seconds (set; omit to keep it)" }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let str_arg = |key: &str| args[key].as_str().map(str::trim).filter(|s| !s.is_empty());
+        let required = |key: &str| str_arg(key).ok_or_else(|| BizClawError::Tool(format!("Missing '{key}'")));
+        let namespace = str_arg("namespace").unwrap_or(GLOBAL);
+
+        let output = match args["action"].as_str().unwrap_or("") {
+            "set" => {
+                let (key, value) = (required("key")?, required("value")?);
+                let ttl = match &args["ttl_secs"] {
+                    serde_json::Value::Null => None,
+                    v => match v.as_i64() {
+                        Some(secs) if secs > 0 => Some(chrono::Duration::seconds(secs)),
+                        _ => return Err(BizClawError::Tool("'ttl_secs' must be a positive integer".into())),
+                    },
+                };
+                let embedding = self.try_embed(&format!("{key}: {value}")).await;
+                let note = self.store.set(namespace, key, value, ttl, embedding.as_deref())?;
+                format!("Saved {}", describe(&note))
+            }
+            "get" => match self.store.get(namespace, required("key")?)? {
+                Some(note) => describe(&note),
+                None => format!("No note '{}' in {namespace}", required("key")?),
+            },
+            "search" => {
+                let query = required("query")?;
+                let embedding = self.try_embed(query).await;
+                let found = self.store.search(Some(namespace), query, embedding.as_deref(), 10)?;
+                if found.is_empty() {
+                    format!("No notes matching '{query}' in {namespace}")
+                } else {
+                    found.iter().map(|m| describe(&m.note)).collect::<Vec<_>>().join("\n")
+                }
+            }
+            "list" => {
+                let notes = self.store.list(Some(namespace))?;
+                if notes.is_empty() {
+                    format!("No notes in {namespace}")
+                } else {
+                    notes.iter().map(describe).collect::<Vec<_>>().join("\n")
+                }
+            }
+            "delete" => match self.store.delete(namespace, required("key")?)? {
+                true => format!("Deleted '{}' from {namespace}", required("key")?),
+                false => format!("No note '{}' in {namespace}", required("key")?),
+            },
+            other => return Err(BizClawError::Tool(format!("Unknown notes action: {other}"))),
+        };
+
+        Ok(ToolResult::ok(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> NoteStore {
+        NoteStore::open(Path::new(":memory:")).unwrap()
+    }
+
+    #[test]
+    fn test_ttl_expiry() {
+        let store = store();
+        store.set(GLOBAL, "wifi", "hunter2", None, None).unwrap();
+        store.set(GLOBAL, "door_code", "1234", Some(chrono::Duration::seconds(3600)), None).unwrap();
+        store.set(GLOBAL, "otp", "999", Some(chrono::Duration::seconds(-1)), None).unwrap();
+
+        assert!(store.get(GLOBAL, "otp").unwrap().is_none());
+        assert!(store.get(GLOBAL, "door_code").unwrap().unwrap().expires_at.is_some());
+        let keys: Vec<String> = store.list(None).unwrap().into_iter().map(|n| n.key).collect();
+        assert_eq!(keys, ["door_code", "wifi"]);
+
+        // Expired rows are purged on the next write.
+        store.set(GLOBAL, "x", "y", None, None).unwrap();
+        let count: i64 = store.conn.lock().unwrap()
+            .query_row("SELECT COUNT(*) FROM notes WHERE key = 'otp'", [], |r| r.get(0)).unwrap();
+        assert_eq!(count, 0);
+    }
+
+    #[test]
+    fn test_namespace_isolation_and_search() {
+        let store = store();
+        store.set(GLOBAL, "office_wifi", "BizNet / hunter2", None, None).unwrap();
+        store.set("chat-1", "office_wifi", "Guest / welcome", None, Some(&[1.0, 0.0])).unwrap();
+        store.set("chat-1", "parking", "Level B2", None, Some(&[0.0, 1.0])).unwrap();
+
+        assert_eq!(store.get(GLOBAL, "office_wifi").unwrap().unwrap().value, "BizNet / hunter2");
+        assert_eq!(store.get("chat-1", "office_wifi").unwrap().unwrap().value, "Guest / welcome");
+        assert!(store.get("chat-2", "office_wifi").unwrap().is_none());
+        assert!(store.get(GLOBAL, "parking").unwrap().is_none());
+
+        let hits = store.search(Some("chat-1"), "WIFI", None, 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].note.namespace, "chat-1");
+
+        // With embeddings, non-substring matches are ranked by similarity.
+        let hits = store.search(Some("chat-1"), "where do I park the car", Some(&[0.1, 0.9]), 10).unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].note.key, "parking");
+
+        assert!(store.delete("chat-1", "office_wifi").unwrap());
+        assert!(store.get(GLOBAL, "office_wifi").unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_tool_actions() {
+        let tool = NotesTool::new(store(), None);
+        let run = |args: serde_json::Value| {
+            let tool = &tool;
+            async move { tool.execute(&args.to_string()).await }
+        };
+
+        let saved = run(serde_json::json!({"action": "set", "key": "office_wifi_password", "value": "hunter2"})).await.unwrap();
+        assert!(saved.output.contains("[global] office_wifi_password = hunter2"));
+        assert!(run(serde_json::json!({"action": "set", "key": "k", "value": "v", "ttl_secs": 0})).await.is_err());
+        assert!(run(serde_json::json!({"action": "set", "key": "k"})).await.is_err());
+
+        let found = run(serde_json::json!({"action": "search", "query": "wifi"})).await.unwrap();
+        assert!(found.output.contains("hunter2"));
+        let other = run(serde_json::json!({"action": "get", "key": "office_wifi_password", "namespace": "chat-9"})).await.unwrap();
+        assert!(other.output.starts_with("No note"));
+        let deleted = run(serde_json::json!({"action": "delete", "key": "office_wifi_password"})).await.unwrap();
+        assert!(deleted.output.starts_with("Deleted"));
+        assert_eq!(run(serde_json::json!({"action": "list"})).await.unwrap().output, "No notes in global");
+    }
+}
@@ -0,0 +1,444 @@
+//! Notion Tool — search, read, and create pages and query databases via the Notion API.
+//!
+//! Authenticates with an internal integration token; pages and databases must
+//! be shared with the integration to be visible.
+
+use async_trait::async_trait;
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_core::error::{BizClawError, Result};
+use serde::{Deserialize, Serialize};
+
+const NOTION_API: &str = "https://api.notion.com/v1";
+const NOTION_VERSION: &str = "2022-06-28";
+
+/// Nested block levels fetched by `get_page`.
+const MAX_BLOCK_DEPTH: usize = 5;
+
+/// Notion API caps a rich text object at 2000 characters.
+const MAX_RICH_TEXT_CHARS: usize = 2000;
+
+/// Notion tool configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NotionConfig {
+    /// Internal integration token (`secret_...` / `ntn_...`)
+    pub token: String,
+}
+
+impl From<&bizclaw_core::config::NotionToolConfig> for NotionConfig {
+    fn from(cfg: &bizclaw_core::config::NotionToolConfig) -> Self {
+        Self { token: cfg.token.clone() }
+    }
+}
+
+/// Notion workspace tool.
+pub struct NotionTool {
+    token: String,
+    client: reqwest::Client,
+}
+
+impl NotionTool {
+    pub fn new(config: NotionConfig) -> Self {
+        Self {
+            token: config.token,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Send a request and return the JSON body, mapping API errors to tool errors.
+    async fn send(&self, req: reqwest::RequestBuilder) -> Result<serde_json::Value> {
+        let response = req
+            .bearer_auth(&self.token)
+            .header("Notion-Version", NOTION_VERSION)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Tool(format!("Notion request failed: {e}")))?;
+
+        let status = response.status();
+        let body: serde_json::Value = response.json().await
+            .map_err(|e| BizClawError::Tool(format!("Parse Notion response failed: {e}")))?;
+        if !status.is_success() {
+            return Err(BizClawError::Tool(format!(
+                "Notion API error {status}: {}",
+                body["message"].as_str().unwrap_or("unknown")
+            )));
+        }
+        Ok(body)
+    }
+
+    async fn search(&self, query: &str, filter_type: Option<&str>) -> Result<String> {
+        let mut body = serde_json::json!({ "query": query, "page_size": 20 });
+        if let Some(kind) = filter_type {
+            if kind != "page" && kind != "database" {
+                return Err(BizClawError::Tool(format!("filter_type must be 'page' or 'database', got '{kind}'")));
+            }
+            body["filter"] = serde_json::json!({ "property": "object", "value": kind });
+        }
+        let resp = self.send(self.client.post(format!("{NOTION_API}/search")).json(&body)).await?;
+
+        let results = resp["results"].as_array().cloned().unwrap_or_default();
+        if results.is_empty() {
+            return Ok(format!("Nothing in Notion matches: {query}"));
+        }
+        let mut out = format!("Found {} result(s) for \"{query}\":\n\n", results.len());
+        for item in &results {
+            let icon = if item["object"] == "database" { "🗂️" } else { "📄" };
+            out.push_str(&format!(
+                "{icon} {} ({})\n   {}\n",
+                object_title(item),
+                item["id"].as_str().unwrap_or("?"),
+                item["url"].as_str().unwrap_or(""),
+            ));
+        }
+        Ok(out)
+    }
+
+    async fn get_page(&self, page_id: &str) -> Result<String> {
+        let page = self.send(self.client.get(format!("{NOTION_API}/pages/{page_id}"))).await?;
+        let blocks = self.fetch_children(page_id, 0).await?;
+        Ok(format!("# {}\n\n{}", object_title(&page), render_blocks(&blocks, 0).trim_end()))
+    }
+
+    /// All child blocks of `block_id`, with nested children attached under
+    /// each block's `"children"` key.
+    fn fetch_children<'a>(
+        &'a self,
+        block_id: &'a str,
+        depth: usize,
+    ) -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<Vec<serde_json::Value>>> + Send + 'a>> {
+        Box::pin(async move {
+            let mut blocks = Vec::new();
+            let mut cursor: Option<String> = None;
+            loop {
+                let mut req = self.client.get(format!("{NOTION_API}/blocks/{block_id}/children"))
+                    .query(&[("page_size", "100")]);
+                if let Some(c) = &cursor {
+                    req = req.query(&[("start_cursor", c.as_str())]);
+                }
+                let resp = self.send(req).await?;
+                blocks.extend(resp["results"].as_array().cloned().unwrap_or_default());
+                match resp["next_cursor"].as_str() {
+                    Some(next) if resp["has_more"].as_bool() == Some(true) => cursor = Some(next.to_string()),
+                    _ => break,
+                }
+            }
+
+            if depth + 1 < MAX_BLOCK_DEPTH {
+                for block in &mut blocks {
+                    // Child pages/databases are separate documents; only link them.
+                    let kind = block["type"].as_str().unwrap_or("");
+                    if block["has_children"].as_bool() == Some(true)
+                        && kind != "child_page"
+                        && kind != "child_database"
+                        && let Some(id) = block["id"].as_str().map(str::to_string)
+                    {
+                        block["children"] = self.fetch_children(&id, depth + 1).await?.into();
+                    }
+                }
+            }
+            Ok(blocks)
+        })
+    }
+
+    async fn create_page(&self, parent_id: &str, title: &str, content: &str) -> Result<String> {
+        let body = serde_json::json!({
+            "parent": { "page_id": parent_id },
+            "properties": {
+                "title": { "title": [{ "type": "text", "text": { "content": title } }] }
+            },
+            "children": paragraph_blocks(content),
+        });
+        let page = self.send(self.client.post(format!("{NOTION_API}/pages")).json(&body)).await?;
+        Ok(format!(
+            "Created page \"{title}\" ({})\nLink: {}",
+            page["id"].as_str().unwrap_or("?"),
+            page["url"].as_str().unwrap_or("")
+        ))
+    }
+
+    async fn query_database(
+        &self,
+        database_id: &str,
+        filter: Option<serde_json::Value>,
+        sorts: Option<serde_json::Value>,
+    ) -> Result<String> {
+        let mut body = serde_json::json!({ "page_size": 100 });
+        if let Some(f) = filter {
+            body["filter"] = f;
+        }
+        if let Some(s) = sorts {
+            body["sorts"] = s;
+        }
+        let resp = self.send(
+            self.client.post(format!("{NOTION_API}/databases/{database_id}/query")).json(&body),
+        ).await?;
+        let rows = resp["results"].as_array().cloned().unwrap_or_default();
+        if rows.is_empty() {
+            return Ok("No rows match.".into());
+        }
+        Ok(rows_to_markdown(&rows))
+    }
+}
+
+/// Concatenated plain text of a rich text array.
+fn rich_text(value: &serde_json::Value) -> String {
+    value.as_array()
+        .map(|parts| parts.iter().filter_map(|p| p["plain_text"].as_str()).collect())
+        .unwrap_or_default()
+}
+
+/// Title of a page (its `title` property) or a database (its `title` field).
+fn object_title(object: &serde_json::Value) -> String {
+    let title = if object["object"] == "database" {
+        rich_text(&object["title"])
+    } else {
+        object["properties"].as_object()
+            .and_then(|props| props.values().find(|p| p["type"] == "title"))
+            .map(|p| rich_text(&p["title"]))
+            .unwrap_or_default()
+    };
+    if title.is_empty() { "Untitled".into() } else { title }
+}
+
+/// Render blocks as plain text, indenting nested children.
+fn render_blocks(blocks: &[serde_json::Value], depth: usize) -> String {
+    let indent = "  ".repeat(depth);
+    let mut out = String::new();
+    let mut number = 0;
+    for block in blocks {
+        let kind = block["type"].as_str().unwrap_or("");
+        let data = &block[kind];
+        let text = rich_text(&data["rich_text"]);
+
+        number = if kind == "numbered_list_item" { number + 1 } else { 0 };
+        let line = match kind {
+            "paragraph" => text,
+            "heading_1" => format!("# {text}"),
+            "heading_2" => format!("## {text}"),
+            "heading_3" => format!("### {text}"),
+            "bulleted_list_item" => format!("- {text}"),
+            "numbered_list_item" => format!("{number}. {text}"),
+            "to_do" => {
+                let mark = if data["checked"].as_bool() == Some(true) { "x" } else { " " };
+                format!("[{mark}] {text}")
+            }
+            "toggle" => format!("▸ {text}"),
+            "quote" => format!("> {text}"),
+            "callout" => format!("{} {text}", data["icon"]["emoji"].as_str().unwrap_or("💡")),
+            "code" => format!("```{}\n{text}\n```", data["language"].as_str().unwrap_or("")),
+            "divider" => "---".into(),
+            "child_page" => format!("📄 {}", data["title"].as_str().unwrap_or("Untitled")),
+            "child_database" => format!("🗂️ {}", data["title"].as_str().unwrap_or("Untitled")),
+            "bookmark" | "embed" | "link_preview" => data["url"].as_str().unwrap_or("").to_string(),
+            "table_row" => data["cells"].as_array()
+                .map(|cells| cells.iter().map(rich_text).collect::<Vec<_>>().join(" | "))
+                .unwrap_or_default(),
+            _ => text,
+        };
+        if !line.is_empty() {
+            for l in line.lines() {
+                out.push_str(&indent);
+                out.push_str(l);
+                out.push('\n');
+            }
+        }
+        if let Some(children) = block["children"].as_array() {
+            out.push_str(&render_blocks(children, depth + 1));
+        }
+    }
+    out
+}
+
+/// Paragraph blocks for `content`, one per blank-line-separated paragraph.
+fn paragraph_blocks(content: &str) -> Vec<serde_json::Value> {
+    content.split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(|p| {
+            let chars: Vec<char> = p.chars().collect();
+            let parts: Vec<serde_json::Value> = chars.chunks(MAX_RICH_TEXT_CHARS)
+                .map(|chunk| serde_json::json!({
+                    "type": "text",
+                    "text": { "content": chunk.iter().collect::<String>() }
+                }))
+                .collect();
+            serde_json::json!({
+                "object": "block",
+                "type": "paragraph",
+                "paragraph": { "rich_text": parts }
+            })
+        })
+        .collect()
+}
+
+/// Plain-text value of a database property.
+fn property_text(prop: &serde_json::Value) -> String {
+    let kind = prop["type"].as_str().unwrap_or("");
+    let value = &prop[kind];
+    match kind {
+        "title" | "rich_text" => rich_text(value),
+        "number" => value.as_f64().map(|n| n.to_string()).unwrap_or_default(),
+        "select" | "status" => value["name"].as_str().unwrap_or("").to_string(),
+        "multi_select" => value.as_array()
+            .map(|opts| opts.iter().filter_map(|o| o["name"].as_str()).collect::<Vec<_>>().join(", "))
+            .unwrap_or_default(),
+        "date" => match (value["start"].as_str(), value["end"].as_str()) {
+            (Some(start), Some(end)) => format!("{start} → {end}"),
+            (Some(start), None) => start.to_string(),
+            _ => String::new(),
+        },
+        "checkbox" => if value.as_bool() == Some(true) { "✅".into() } else { String::new() },
+        "people" => value.as_array()
+            .map(|people| people.iter().filter_map(|p| p["name"].as_str()).collect::<Vec<_>>().join(", "))
+            .unwrap_or_default(),
+        "relation" => value.as_array().map(|r| format!("{} linked", r.len())).unwrap_or_default(),
+        "formula" => property_text(value),
+        "string" | "url" | "email" | "phone_number" | "created_time" | "last_edited_time" => {
+            value.as_str().unwrap_or("").to_string()
+        }
+        "boolean" => value.as_bool().map(|b| b.to_string()).unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// Database rows as a Markdown table, title column first.
+fn rows_to_markdown(rows: &[serde_json::Value]) -> String {
+    let Some(first) = rows[0]["properties"].as_object() else {
+        return String::new();
+    };
+    let mut columns: Vec<&String> = first.keys().collect();
+    columns.sort_by_key(|name| first[*name]["type"] != "title");
+
+    let escape = |s: String| s.replace('|', "\\|").replace('\n', " ");
+    let mut out = format!(
+        "| {} |\n|{}\n",
+        columns.iter().map(|c| escape(c.to_string())).collect::<Vec<_>>().join(" | "),
+        " --- |".repeat(columns.len())
+    );
+    for row in rows {
+        let cells: Vec<String> = columns.iter()
+            .map(|c| escape(property_text(&row["properties"][c.as_str()])))
+            .collect();
+        out.push_str(&format!("| {} |\n", cells.join(" | ")));
+    }
+    out
+}
+
+/// Parse an optional argument given either as JSON or as a JSON-encoded string.
+fn json_arg(value: &serde_json::Value, name: &str) -> Result<Option<serde_json::Value>> {
+    match value {
+        serde_json::Value::Null => Ok(None),
+        serde_json::Value::String(s) if s.trim().is_empty() => Ok(None),
+        serde_json::Value::String(s) => serde_json::from_str(s)
+            .map(Some)
+            .map_err(|e| BizClawError::Tool(format!("Invalid {name}: {e}"))),
+        other => Ok(Some(other.clone())),
+    }
+}
+
+#[async_trait]
+impl Tool for NotionTool {
+    fn name(&self) -> &str { "notion" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "notion".into(),
+            description: "Search Notion, read or create pages, and query databases.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["search", "get_page", "create_page", "query_database"],
+                        "description": "Operation to perform"
+                    },
+                    "query": { "type": "string", "description": "Search text (search)" },
+                    "filter_type": { "type": "string", "enum": ["page", "database"], "description": "Restrict search results (search, optional)" },
+                    "page_id": { "type": "string", "description": "Page ID (get_page)" },
+                    "parent_id": { "type": "string", "description": "Parent page ID (create_page)" },
+                    "title": { "type": "string", "description": "Page title (create_page)" },
+                    "content": { "type": "string", "description": "Page text; blank lines separate paragraphs (create_page)" },
+                    "database_id": { "type": "string", "description": "Database ID (query_database)" },
+                    "filter_json": { "type": "string", "description": "Notion filter object as JSON (query_database, optional)" },
+                    "sorts_json": { "type": "string", "description": "Notion sorts array as JSON (query_database, optional)" }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let str_arg = |key: &str| {
+            args[key].as_str().ok_or_else(|| BizClawError::Tool(format!("Missing '{key}'")))
+        };
+
+        let output = match str_arg("action")? {
+            "search" => self.search(str_arg("query")?, args["filter_type"].as_str()).await?,
+            "get_page" => self.get_page(str_arg("page_id")?).await?,
+            "create_page" => {
+                let content = args["content"].as_str().unwrap_or("");
+                self.create_page(str_arg("parent_id")?, str_arg("title")?, content).await?
+            }
+            "query_database" => {
+                let filter = json_arg(&args["filter_json"], "filter_json")?;
+                let sorts = json_arg(&args["sorts_json"], "sorts_json")?;
+                self.query_database(str_arg("database_id")?, filter, sorts).await?
+            }
+            other => return Err(BizClawError::Tool(format!("Unknown notion action: {other}"))),
+        };
+
+        Ok(ToolResult::ok(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(s: &str) -> serde_json::Value {
+        serde_json::json!([{ "plain_text": s }])
+    }
+
+    #[test]
+    fn test_render_nested_blocks() {
+        let blocks = vec![
+            serde_json::json!({ "type": "heading_1", "heading_1": { "rich_text": text("Plan") } }),
+            serde_json::json!({ "type": "numbered_list_item", "numbered_list_item": { "rich_text": text("One") } }),
+            serde_json::json!({
+                "type": "numbered_list_item",
+                "numbered_list_item": { "rich_text": text("Two") },
+                "children": [
+                    { "type": "to_do", "to_do": { "rich_text": text("Sub task"), "checked": true } }
+                ]
+            }),
+            serde_json::json!({ "type": "divider", "divider": {} }),
+        ];
+        assert_eq!(render_blocks(&blocks, 0), "# Plan\n1. One\n2. Two\n  [x] Sub task\n---\n");
+    }
+
+    #[test]
+    fn test_rows_to_markdown() {
+        let rows = vec![serde_json::json!({
+            "properties": {
+                "Status": { "type": "select", "select": { "name": "Done" } },
+                "Name": { "type": "title", "title": text("Q3 | report") },
+                "Tags": { "type": "multi_select", "multi_select": [{ "name": "a" }, { "name": "b" }] }
+            }
+        })];
+        assert_eq!(
+            rows_to_markdown(&rows),
+            "| Name | Status | Tags |\n| --- | --- | --- |\n| Q3 \\| report | Done | a, b |\n"
+        );
+    }
+
+    #[test]
+    fn test_paragraph_blocks_split() {
+        let blocks = paragraph_blocks("First\n\n\nSecond");
+        assert_eq!(blocks.len(), 2);
+        assert_eq!(blocks[1]["paragraph"]["rich_text"][0]["text"]["content"], "Second");
+        let long = "x".repeat(MAX_RICH_TEXT_CHARS + 1);
+        assert_eq!(paragraph_blocks(&long)[0]["paragraph"]["rich_text"].as_array().unwrap().len(), 2);
+    }
+}
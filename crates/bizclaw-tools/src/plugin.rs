@@ -0,0 +1,181 @@
+//! Tool plugins — custom tools loaded from shared libraries at startup.
+//!
+//! Every `.so` (`.dylib` on macOS) in `plugin_dir` is loaded as one tool. A
+//! plugin is a `cdylib` crate that depends on `bizclaw-tools` and calls
+//! [`export_tool!`](crate::export_tool), which exports the two symbols the
+//! loader looks for:
+//!
+//! - `BIZCLAW_PLUGIN_ABI: u32`: the [`PLUGIN_ABI_VERSION`] the plugin was
+//!   built against. The high 16 bits are a magic (`0xB1C1`) marking a BizClaw
+//!   plugin, the low 16 bits the contract version. A library with another
+//!   magic or version is refused with an error saying which.
+//! - `extern "C" fn bizclaw_tool_init() -> *mut ToolHandle`: called once per
+//!   load; returns a `Box<ToolHandle>` the host takes ownership of.
+//!
+//! `dyn Tool` has no stable layout, so a plugin must also be built with the
+//! same Rust compiler and `bizclaw-core` version as the host, and use the
+//! default global allocator. The version check catches contract changes,
+//! not compiler mismatches. Loading a library runs its code: only put
+//! trusted plugins in `plugin_dir`.
+
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use libloading::Library;
+
+pub use bizclaw_core::traits::Tool;
+
+/// Plugin contract version: magic `0xB1C1` in the high 16 bits, version in
+/// the low 16. Bump the version when `Tool` or this contract changes.
+pub const PLUGIN_ABI_VERSION: u32 = 0xB1C1_0001;
+
+const PLUGIN_ABI_MAGIC: u32 = PLUGIN_ABI_VERSION >> 16;
+const ABI_SYMBOL: &[u8] = b"BIZCLAW_PLUGIN_ABI\0";
+const INIT_SYMBOL: &[u8] = b"bizclaw_tool_init\0";
+
+/// What `bizclaw_tool_init` hands over: a thin pointer to the plugin's tool.
+pub struct ToolHandle(pub Box<dyn Tool>);
+
+/// Export a tool from a plugin crate (`crate-type = ["cdylib"]`).
+///
+/// ```ignore
+/// bizclaw_tools::export_tool!(WeatherTool::new());
+/// ```
+#[macro_export]
+macro_rules! export_tool {
+    ($tool:expr) => {
+        #[unsafe(no_mangle)]
+        pub static BIZCLAW_PLUGIN_ABI: u32 = $crate::plugin::PLUGIN_ABI_VERSION;
+
+        #[unsafe(no_mangle)]
+        pub extern "C" fn bizclaw_tool_init() -> *mut $crate::plugin::ToolHandle {
+            let tool: Box<dyn $crate::plugin::Tool> = Box::new($tool);
+            Box::into_raw(Box::new($crate::plugin::ToolHandle(tool)))
+        }
+    };
+}
+
+/// Loads tool plugins.
+pub struct PluginLoader;
+
+impl PluginLoader {
+    /// Load every plugin in `dir`. A plugin that fails to load is logged and
+    /// skipped; only an unreadable directory is an error.
+    pub fn load_all(dir: &Path) -> Result<Vec<Box<dyn Tool>>> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| BizClawError::Tool(format!("Plugin dir {}: {e}", dir.display())))?;
+        let mut paths: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION))
+            .collect();
+        paths.sort();
+
+        let mut tools = Vec::new();
+        for path in paths {
+            match Self::load(&path) {
+                Ok(tool) => {
+                    tracing::debug!("Loaded tool plugin '{}' from {}", tool.name(), path.display());
+                    tools.push(tool);
+                }
+                Err(e) => tracing::error!("{e}"),
+            }
+        }
+        Ok(tools)
+    }
+
+    /// Load one plugin, checking its ABI version first.
+    pub fn load(path: &Path) -> Result<Box<dyn Tool>> {
+        let err = |msg: String| BizClawError::Tool(format!("Plugin {}: {msg}", path.display()));
+
+        // SAFETY: loading runs the library's initializers; the plugin dir is
+        // trusted configuration, as documented above.
+        let library = unsafe { Library::new(path) }.map_err(|e| err(format!("can't load: {e}")))?;
+        // SAFETY: the symbol is a `u32` static by the plugin contract.
+        let abi = unsafe {
+            let symbol = library.get::<*const u32>(ABI_SYMBOL)
+                .map_err(|_| err("no BIZCLAW_PLUGIN_ABI symbol; not a BizClaw tool plugin".into()))?;
+            **symbol
+        };
+        check_abi(abi).map_err(err)?;
+
+        // SAFETY: a plugin with a matching ABI version exports `bizclaw_tool_init`
+        // with this signature, and gives up ownership of the returned box.
+        let handle = unsafe {
+            let init = library.get::<unsafe extern "C" fn() -> *mut ToolHandle>(INIT_SYMBOL)
+                .map_err(|e| err(format!("missing bizclaw_tool_init: {e}")))?;
+            init()
+        };
+        if handle.is_null() {
+            return Err(err("bizclaw_tool_init returned null".into()));
+        }
+        // SAFETY: non-null and produced by `Box::into_raw` in `export_tool!`.
+        let ToolHandle(tool) = *unsafe { Box::from_raw(handle) };
+        Ok(Box::new(PluginTool { tool, _library: library }))
+    }
+}
+
+/// Check a plugin's `BIZCLAW_PLUGIN_ABI` against this build's.
+fn check_abi(found: u32) -> std::result::Result<(), String> {
+    if found >> 16 != PLUGIN_ABI_MAGIC {
+        return Err(format!("BIZCLAW_PLUGIN_ABI is {found:#010x}, which is not a BizClaw plugin ABI version"));
+    }
+    if found != PLUGIN_ABI_VERSION {
+        return Err(format!(
+            "built for plugin ABI v{}, but this bizclaw supports v{}; rebuild the plugin against this version",
+            found & 0xFFFF,
+            PLUGIN_ABI_VERSION & 0xFFFF,
+        ));
+    }
+    Ok(())
+}
+
+/// A plugin's tool, keeping its library loaded for as long as it lives.
+struct PluginTool {
+    // Dropped before `_library`: the tool's code lives in it.
+    tool: Box<dyn Tool>,
+    _library: Library,
+}
+
+#[async_trait]
+impl Tool for PluginTool {
+    fn name(&self) -> &str { self.tool.name() }
+
+    fn definition(&self) -> ToolDefinition { self.tool.definition() }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        self.tool.execute(arguments).await
+    }
+
+    fn concurrency_safe(&self) -> bool { self.tool.concurrency_safe() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_abi() {
+        assert!(check_abi(PLUGIN_ABI_VERSION).is_ok());
+        let newer = check_abi(PLUGIN_ABI_VERSION + 1).unwrap_err();
+        assert!(newer.contains("built for plugin ABI v2, but this bizclaw supports v1"), "{newer}");
+        let foreign = check_abi(0x1234_0001).unwrap_err();
+        assert!(foreign.contains("not a BizClaw plugin"), "{foreign}");
+    }
+
+    #[test]
+    fn test_load_all_skips_bad_files() {
+        let dir = std::env::temp_dir().join(format!("bizclaw-plugins-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("README.txt"), "not a plugin").unwrap();
+        let broken = dir.join(format!("libbroken.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&broken, b"\x7fELF not really").unwrap();
+
+        assert!(PluginLoader::load_all(&dir).unwrap().is_empty());
+        let err = PluginLoader::load(&broken).err().unwrap().to_string();
+        assert!(err.contains("libbroken") && err.contains("can't load"), "{err}");
+        assert!(PluginLoader::load_all(&dir.join("missing")).is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
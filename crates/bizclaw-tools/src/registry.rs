@@ -1,8 +1,17 @@
 //! Tool registry — dynamic tool discovery and execution.
 
+use bizclaw_core::error::{BizClawError, Result};
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::ToolDefinition;
 
+/// A place where a tool's parameter schema breaks the JSON Schema spec.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct SchemaError {
+    /// JSON Pointer to the offending part of the schema (`""` for the root).
+    pub path: String,
+    pub message: String,
+}
+
 /// Find a tool by name from a list.
 pub fn find_tool<'a>(tools: &'a [Box<dyn Tool>], name: &str) -> Option<&'a dyn Tool> {
     tools.iter().find(|t| t.name() == name).map(|t| t.as_ref())
@@ -13,15 +22,62 @@ pub fn list_definitions(tools: &[Box<dyn Tool>]) -> Vec<ToolDefinition> {
     tools.iter().map(|t| t.definition()).collect()
 }
 
+/// Cut `output` to at most `max_bytes` (on a char boundary), appending a
+/// marker with the original length.
+pub fn truncate_output(output: String, max_bytes: usize) -> String {
+    if output.len() <= max_bytes {
+        return output;
+    }
+    let mut end = max_bytes;
+    while !output.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n\n[output truncated: showing {end} of {} bytes]",
+        &output[..end],
+        output.len()
+    )
+}
+
+/// Apply [`truncate_output`] to every string in a tool's `data` payload.
+/// Returns whether anything was cut.
+pub fn truncate_data(data: &mut serde_json::Value, max_bytes: usize) -> bool {
+    match data {
+        serde_json::Value::String(s) if s.len() > max_bytes => {
+            *s = truncate_output(std::mem::take(s), max_bytes);
+            true
+        }
+        serde_json::Value::Array(items) => items.iter_mut().fold(false, |cut, v| truncate_data(v, max_bytes) | cut),
+        serde_json::Value::Object(map) => map.values_mut().fold(false, |cut, v| truncate_data(v, max_bytes) | cut),
+        _ => false,
+    }
+}
+
+/// Check a parameter schema against the JSON Schema draft-07 meta-schema.
+///
+/// Returns every violation found; an empty list means the schema is valid.
+/// A schema that passes the meta-schema but still can't be compiled, such
+/// as one with an unresolvable `$ref`, is an error.
+pub fn validate_tool_schema(schema: &serde_json::Value) -> Result<Vec<SchemaError>> {
+    let errors: Vec<SchemaError> = jsonschema::draft7::meta::VALIDATOR
+        .iter_errors(schema)
+        .map(|e| SchemaError { path: e.instance_path.to_string(), message: e.to_string() })
+        .collect();
+    if errors.is_empty() {
+        jsonschema::draft7::new(schema)
+            .map_err(|e| BizClawError::Tool(format!("Schema does not compile: {e}")))?;
+    }
+    Ok(errors)
+}
+
 /// Validate that a tool call has the required arguments.
-pub fn validate_args(definition: &ToolDefinition, args: &serde_json::Value) -> Result<(), String> {
+pub fn validate_args(definition: &ToolDefinition, args: &serde_json::Value) -> std::result::Result<(), String> {
     let params = &definition.parameters;
     if let Some(required) = params.get("required").and_then(|r| r.as_array()) {
         for req in required {
-            if let Some(key) = req.as_str() {
-                if args.get(key).is_none() {
-                    return Err(format!("Missing required argument: {key}"));
-                }
+            if let Some(key) = req.as_str()
+                && args.get(key).is_none() {
+                return Err(format!("Missing required argument: {key}"));
             }
         }
     }
@@ -54,6 +110,53 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_validate_tool_schema() {
+        let valid = serde_json::json!({
+            "type": "object",
+            "properties": { "cmd": { "type": "string", "pattern": "^[a-z]+$" } },
+            "required": ["cmd"]
+        });
+        assert!(validate_tool_schema(&valid).unwrap().is_empty());
+
+        let invalid = serde_json::json!({
+            "type": "object",
+            "properties": { "cmd": { "type": "text" }, "n": { "minimum": "1" } },
+            "required": "cmd"
+        });
+        let errors = validate_tool_schema(&invalid).unwrap();
+        let paths: Vec<_> = errors.iter().map(|e| e.path.as_str()).collect();
+        assert!(paths.contains(&"/properties/cmd/type"), "{errors:?}");
+        assert!(paths.contains(&"/properties/n/minimum"), "{errors:?}");
+        assert!(paths.contains(&"/required"), "{errors:?}");
+
+        let bad_pattern = serde_json::json!({ "type": "string", "pattern": "([a-z" });
+        assert_eq!(validate_tool_schema(&bad_pattern).unwrap()[0].path, "/pattern");
+
+        // Meta-valid, but the reference goes nowhere.
+        let dangling = serde_json::json!({ "properties": { "a": { "$ref": "#/definitions/missing" } } });
+        assert!(validate_tool_schema(&dangling).is_err());
+    }
+
+    #[test]
+    fn test_truncate_output() {
+        assert_eq!(truncate_output("short".into(), 10), "short");
+        let out = truncate_output("xin chào".into(), 7);
+        // 'à' spans bytes 6..8, so the cut backs up to 6.
+        assert!(out.starts_with("xin ch\n"));
+        assert!(out.ends_with("[output truncated: showing 6 of 9 bytes]"));
+    }
+
+    #[test]
+    fn test_truncate_data() {
+        let mut data = serde_json::json!({"exit_code": 0, "stdout": "a".repeat(20), "lines": ["short", "b".repeat(20)]});
+        assert!(truncate_data(&mut data, 10));
+        assert!(data["stdout"].as_str().unwrap().starts_with("aaaaaaaaaa\n"));
+        assert_eq!(data["lines"][0], "short");
+        assert!(data["lines"][1].as_str().unwrap().ends_with("[output truncated: showing 10 of 20 bytes]"));
+        assert!(!truncate_data(&mut data["lines"][0], 10));
+    }
+
     #[test]
     fn test_validate_args_no_required() {
         let def = ToolDefinition {
@@ -0,0 +1,689 @@
+//! Scheduler Tool — recurring and one-shot jobs the agent runs on its own.
+//!
+//! Jobs live in SQLite (`<data dir>/scheduler.db`) so they survive restarts.
+//! Each job has a schedule (5-field cron expression or a single time), an
+//! action (send a fixed message, run a tool, or run a prompt through the
+//! agent), and the channel/thread its output goes to. `spawn_runner` checks
+//! for due jobs every tick and hands them to the agent loop.
+//!
+//! After downtime, a job whose time passed more than a grace period ago is a
+//! misfire, handled by its explicit policy: `skip` waits for the next
+//! occurrence, `run_once` runs it once (however many occurrences were missed).
+
+use async_trait::async_trait;
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_core::error::{BizClawError, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+use rusqlite::{Connection, params};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// When a job runs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Schedule {
+    /// `minute hour day-of-month month day-of-week`, in the scheduler timezone.
+    Cron { expr: String },
+    Once { at: DateTime<Utc> },
+}
+
+/// What a job does when it fires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum JobAction {
+    /// Send `text` as-is.
+    Message { text: String },
+    /// Run a tool and send its output.
+    Tool { name: String, arguments: String },
+    /// Run `prompt` through the agent and send the reply.
+    Prompt { prompt: String },
+}
+
+/// What to do with a run missed by more than the grace period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MisfirePolicy {
+    Skip,
+    RunOnce,
+}
+
+impl MisfirePolicy {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Skip => "skip",
+            Self::RunOnce => "run_once",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "skip" => Some(Self::Skip),
+            "run_once" => Some(Self::RunOnce),
+            _ => None,
+        }
+    }
+}
+
+/// A stored job.
+#[derive(Debug, Clone, Serialize)]
+pub struct Job {
+    pub id: String,
+    pub name: String,
+    pub schedule: Schedule,
+    pub action: JobAction,
+    /// Channel the output is sent to (e.g. "telegram", "zalo", "cli").
+    pub channel: String,
+    pub thread_id: String,
+    pub is_group: bool,
+    pub misfire: MisfirePolicy,
+    pub paused: bool,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_run: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A job that is due now.
+#[derive(Debug, Clone)]
+pub struct FiredJob {
+    pub job: Job,
+    pub scheduled_for: DateTime<Utc>,
+    /// Fired as a `run_once` catch-up after a misfire.
+    pub late: bool,
+}
+
+/// Parsed 5-field cron expression. Day-of-month and day-of-week combine with
+/// OR when both are restricted, as in classic cron.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+const MONTH_NAMES: &[&str] = &["jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec"];
+const DAY_NAMES: &[&str] = &["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> std::result::Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("'{expr}' must have 5 fields: minute hour day-of-month month day-of-week"));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7, DAY_NAMES, 0)?;
+        // 7 is also Sunday.
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[], 0)?,
+            hours: parse_field(hour, 0, 23, &[], 0)?,
+            days: parse_field(day, 1, 31, &[], 0)?,
+            months: parse_field(month, 1, 12, MONTH_NAMES, 1)?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let dom = self.days & (1 << date.day()) != 0;
+        let dow = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+
+    /// First matching time strictly after `after`, evaluated in `tz`.
+    pub fn next_after(&self, after: DateTime<Utc>, tz: Tz) -> Option<DateTime<Utc>> {
+        let local = after.with_timezone(&tz).naive_local();
+        let mut t = local.date().and_hms_opt(local.hour(), local.minute(), 0)? + Duration::minutes(1);
+        let limit = t + Duration::days(366 * 5);
+        while t < limit {
+            if self.months & (1 << t.month()) == 0 {
+                let (y, m) = if t.month() == 12 { (t.year() + 1, 1) } else { (t.year(), t.month() + 1) };
+                t = NaiveDate::from_ymd_opt(y, m, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(t.date()) {
+                t = t.date().succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << t.hour()) == 0 {
+                t = t.date().and_hms_opt(t.hour(), 0, 0)? + Duration::hours(1);
+            } else if self.minutes & (1 << t.minute()) == 0 {
+                t += Duration::minutes(1);
+            } else {
+                // Times skipped by a DST jump don't exist locally; move on.
+                match tz.from_local_datetime(&t).earliest().map(|d| d.with_timezone(&Utc)) {
+                    Some(at) if at > after => return Some(at),
+                    _ => t += Duration::minutes(1),
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Parse one cron field into a bitset of allowed values.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str], name_base: u32) -> std::result::Result<u64, String> {
+    let value = |s: &str| -> std::result::Result<u32, String> {
+        let lower = s.to_lowercase();
+        if let Some(i) = names.iter().position(|n| *n == lower) {
+            return Ok(i as u32 + name_base);
+        }
+        let v: u32 = s.parse().map_err(|_| format!("invalid value '{s}' in '{field}'"))?;
+        if v < min || v > max {
+            return Err(format!("{v} is out of range {min}-{max} in '{field}'"));
+        }
+        Ok(v)
+    };
+
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step in '{part}'"))?;
+                if step == 0 {
+                    return Err(format!("step can't be 0 in '{part}'"));
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (lo, hi) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((a, b)) => (value(a)?, value(b)?),
+                // `5/15` means from 5 to the end, every 15.
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let v = value(range)?;
+                    (v, v)
+                }
+            },
+        };
+        if lo > hi {
+            return Err(format!("range '{range}' is backwards"));
+        }
+        for v in (lo..=hi).step_by(step as usize) {
+            bits |= 1 << v;
+        }
+    }
+    Ok(bits)
+}
+
+/// SQLite-backed job store.
+pub struct JobStore {
+    conn: Mutex<Connection>,
+    tz: Tz,
+}
+
+impl JobStore {
+    /// Open (or create) the store at `path`.
+    pub fn open(path: &Path, tz: Tz) -> Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let conn = Connection::open(path)
+            .map_err(|e| BizClawError::Memory(format!("DB open error: {e}")))?;
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS jobs (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                schedule TEXT NOT NULL,
+                action TEXT NOT NULL,
+                channel TEXT NOT NULL,
+                thread_id TEXT NOT NULL,
+                is_group INTEGER DEFAULT 0,
+                misfire TEXT NOT NULL,
+                paused INTEGER DEFAULT 0,
+                next_run TEXT,
+                last_run TEXT,
+                created_at TEXT NOT NULL
+            );
+        ").map_err(|e| BizClawError::Memory(format!("Migration error: {e}")))?;
+        Ok(Self { conn: Mutex::new(conn), tz })
+    }
+
+    /// Open the store for `config`: `<data dir>/scheduler.db` in the configured timezone.
+    pub fn from_config(config: &bizclaw_core::config::BizClawConfig) -> Result<Self> {
+        let tz = parse_timezone(&config.tools.scheduler.timezone)?;
+        Self::open(&bizclaw_core::config::BizClawConfig::data_dir().join("scheduler.db"), tz)
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.tz
+    }
+
+    /// Next run of `schedule` strictly after `after`.
+    fn next_run(&self, schedule: &Schedule, after: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+        match schedule {
+            Schedule::Cron { expr } => Ok(CronExpr::parse(expr).map_err(BizClawError::Tool)?.next_after(after, self.tz)),
+            Schedule::Once { at } => Ok(Some(*at).filter(|at| *at > after)),
+        }
+    }
+
+    /// Add a job; its first run is computed from now.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create(
+        &self,
+        name: &str,
+        schedule: Schedule,
+        action: JobAction,
+        channel: &str,
+        thread_id: &str,
+        is_group: bool,
+        misfire: MisfirePolicy,
+    ) -> Result<Job> {
+        let now = Utc::now();
+        let next_run = self.next_run(&schedule, now)?
+            .ok_or_else(|| BizClawError::Tool("Schedule has no future run".into()))?;
+        let job = Job {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            schedule,
+            action,
+            channel: channel.to_string(),
+            thread_id: thread_id.to_string(),
+            is_group,
+            misfire,
+            paused: false,
+            next_run: Some(next_run),
+            last_run: None,
+            created_at: now,
+        };
+        self.conn.lock().unwrap().execute(
+            "INSERT INTO jobs (id, name, schedule, action, channel, thread_id, is_group, misfire, paused, next_run, last_run, created_at)
+             VALUES (?1,?2,?3,?4,?5,?6,?7,?8,0,?9,NULL,?10)",
+            params![
+                job.id, job.name,
+                serde_json::to_string(&job.schedule)?, serde_json::to_string(&job.action)?,
+                job.channel, job.thread_id, job.is_group, job.misfire.as_str(),
+                next_run.to_rfc3339(), now.to_rfc3339(),
+            ],
+        ).map_err(|e| BizClawError::Memory(format!("Insert job: {e}")))?;
+        Ok(job)
+    }
+
+    /// All jobs, soonest first (paused and finished jobs last).
+    pub fn list(&self) -> Result<Vec<Job>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, name, schedule, action, channel, thread_id, is_group, misfire, paused, next_run, last_run, created_at
+             FROM jobs ORDER BY paused, next_run IS NULL, next_run"
+        ).map_err(|e| BizClawError::Memory(format!("Prepare: {e}")))?;
+        let jobs = stmt.query_map([], |row| {
+            let parse_time = |s: Option<String>| s.and_then(|s| DateTime::parse_from_rfc3339(&s).ok()).map(|d| d.with_timezone(&Utc));
+            Ok((
+                row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?, row.get::<_, String>(5)?, row.get::<_, bool>(6)?, row.get::<_, String>(7)?,
+                row.get::<_, bool>(8)?, parse_time(row.get(9)?), parse_time(row.get(10)?),
+                parse_time(row.get(11)?).unwrap_or_default(),
+            ))
+        }).map_err(|e| BizClawError::Memory(format!("Query: {e}")))?
+            .filter_map(|r| r.ok())
+            .filter_map(|(id, name, schedule, action, channel, thread_id, is_group, misfire, paused, next_run, last_run, created_at)| {
+                Some(Job {
+                    id,
+                    name,
+                    schedule: serde_json::from_str(&schedule).ok()?,
+                    action: serde_json::from_str(&action).ok()?,
+                    channel,
+                    thread_id,
+                    is_group,
+                    misfire: MisfirePolicy::parse(&misfire)?,
+                    paused,
+                    next_run,
+                    last_run,
+                    created_at,
+                })
+            })
+            .collect();
+        Ok(jobs)
+    }
+
+    /// Delete a job; returns whether it existed.
+    pub fn delete(&self, id: &str) -> Result<bool> {
+        let n = self.conn.lock().unwrap().execute("DELETE FROM jobs WHERE id=?1", params![id])
+            .map_err(|e| BizClawError::Memory(format!("Delete job: {e}")))?;
+        Ok(n > 0)
+    }
+
+    /// Pause or resume a job. Resuming schedules from now, so runs missed
+    /// while paused are not caught up.
+    pub fn set_paused(&self, id: &str, paused: bool) -> Result<bool> {
+        let Some(job) = self.list()?.into_iter().find(|j| j.id == id) else {
+            return Ok(false);
+        };
+        let next_run = if paused { job.next_run } else { self.next_run(&job.schedule, Utc::now())?.or(job.next_run) };
+        self.conn.lock().unwrap().execute(
+            "UPDATE jobs SET paused=?1, next_run=?2 WHERE id=?3",
+            params![paused, next_run.map(|t| t.to_rfc3339()), id],
+        ).map_err(|e| BizClawError::Memory(format!("Update job: {e}")))?;
+        Ok(true)
+    }
+
+    /// Claim jobs due at `now` and advance their schedules.
+    ///
+    /// A job more than `grace` late is a misfire: with `Skip` it is not run,
+    /// with `RunOnce` it runs once. Either way its next run is the first
+    /// occurrence after `now`; one-shot jobs are removed once handled.
+    pub fn take_due(&self, now: DateTime<Utc>, grace: Duration) -> Result<Vec<FiredJob>> {
+        let mut fired = Vec::new();
+        for job in self.list()? {
+            let Some(scheduled_for) = job.next_run.filter(|at| !job.paused && *at <= now) else { continue };
+            let late = now - scheduled_for > grace;
+            let run = !late || job.misfire == MisfirePolicy::RunOnce;
+            let next_run = self.next_run(&job.schedule, now)?;
+
+            let conn = self.conn.lock().unwrap();
+            match next_run {
+                None => conn.execute("DELETE FROM jobs WHERE id=?1", params![job.id]),
+                Some(next) => conn.execute(
+                    "UPDATE jobs SET next_run=?1, last_run=COALESCE(?2, last_run) WHERE id=?3",
+                    params![next.to_rfc3339(), run.then(|| now.to_rfc3339()), job.id],
+                ),
+            }.map_err(|e| BizClawError::Memory(format!("Update job: {e}")))?;
+            drop(conn);
+
+            if run {
+                fired.push(FiredJob { job, scheduled_for, late });
+            } else {
+                tracing::info!("⏭ Skipped missed run of job '{}' scheduled for {scheduled_for}", job.name);
+            }
+        }
+        Ok(fired)
+    }
+}
+
+pub fn parse_timezone(name: &str) -> Result<Tz> {
+    name.parse::<Tz>().map_err(|_| BizClawError::Config(format!("Unknown timezone '{name}'")))
+}
+
+/// Check for due jobs every `tick` and send them to the returned receiver.
+pub fn spawn_runner(store: Arc<JobStore>, tick: std::time::Duration) -> tokio::sync::mpsc::Receiver<FiredJob> {
+    let (tx, rx) = tokio::sync::mpsc::channel(32);
+    let grace = Duration::from_std(tick * 2).unwrap_or(Duration::minutes(1)).max(Duration::minutes(1));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tick);
+        loop {
+            interval.tick().await;
+            let due = match store.take_due(Utc::now(), grace) {
+                Ok(due) => due,
+                Err(e) => {
+                    tracing::warn!("Scheduler check failed: {e}");
+                    continue;
+                }
+            };
+            for job in due {
+                tracing::info!("⏰ Running job '{}' ({})", job.job.name, job.job.id);
+                if tx.send(job).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    rx
+}
+
+/// Scheduler tool — manage jobs from a conversation.
+pub struct SchedulerTool {
+    store: Arc<JobStore>,
+}
+
+impl SchedulerTool {
+    pub fn new(store: Arc<JobStore>) -> Self {
+        Self { store }
+    }
+
+    /// `at` as RFC 3339, or a local `YYYY-MM-DD HH:MM` in the scheduler timezone.
+    fn parse_at(&self, at: &str) -> Result<DateTime<Utc>> {
+        if let Ok(t) = DateTime::parse_from_rfc3339(at) {
+            return Ok(t.with_timezone(&Utc));
+        }
+        let naive = ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S"]
+            .iter()
+            .find_map(|f| NaiveDateTime::parse_from_str(at, f).ok())
+            .ok_or_else(|| BizClawError::Tool(format!("Invalid time '{at}' (use YYYY-MM-DD HH:MM)")))?;
+        self.store.tz.from_local_datetime(&naive).earliest()
+            .map(|t| t.with_timezone(&Utc))
+            .ok_or_else(|| BizClawError::Tool(format!("'{at}' does not exist in {}", self.store.tz)))
+    }
+
+    fn create(&self, args: &serde_json::Value) -> Result<String> {
+        let str_arg = |key: &str| args[key].as_str().filter(|s| !s.is_empty());
+        let schedule = match (str_arg("cron"), str_arg("at")) {
+            (Some(expr), None) => {
+                CronExpr::parse(expr).map_err(BizClawError::Tool)?;
+                Schedule::Cron { expr: expr.to_string() }
+            }
+            (None, Some(at)) => Schedule::Once { at: self.parse_at(at)? },
+            _ => return Err(BizClawError::Tool("Give exactly one of 'cron' or 'at'".into())),
+        };
+        let action = match str_arg("job_type").unwrap_or("prompt") {
+            "message" => JobAction::Message {
+                text: str_arg("text").ok_or_else(|| BizClawError::Tool("Missing 'text'".into()))?.to_string(),
+            },
+            "tool" => JobAction::Tool {
+                name: str_arg("tool").ok_or_else(|| BizClawError::Tool("Missing 'tool'".into()))?.to_string(),
+                arguments: match &args["arguments"] {
+                    serde_json::Value::String(s) => s.clone(),
+                    serde_json::Value::Null => "{}".into(),
+                    other => other.to_string(),
+                },
+            },
+            "prompt" => JobAction::Prompt {
+                prompt: str_arg("prompt").ok_or_else(|| BizClawError::Tool("Missing 'prompt'".into()))?.to_string(),
+            },
+            other => return Err(BizClawError::Tool(format!("Unknown job_type: {other}"))),
+        };
+        let misfire = str_arg("misfire").and_then(MisfirePolicy::parse)
+            .ok_or_else(|| BizClawError::Tool("'misfire' must be 'skip' or 'run_once'".into()))?;
+        let name = str_arg("name").unwrap_or("job");
+
+        let job = self.store.create(
+            name,
+            schedule,
+            action,
+            str_arg("channel").unwrap_or("cli"),
+            str_arg("thread_id").unwrap_or("cli"),
+            args["is_group"].as_bool().unwrap_or(false),
+            misfire,
+        )?;
+        Ok(format!("Created job '{}' ({}); next run {}", job.name, job.id, self.local(job.next_run)))
+    }
+
+    fn local(&self, t: Option<DateTime<Utc>>) -> String {
+        t.map(|t| t.with_timezone(&self.store.tz).format("%Y-%m-%d %H:%M %Z").to_string())
+            .unwrap_or_else(|| "—".into())
+    }
+
+    fn list(&self) -> Result<String> {
+        let jobs = self.store.list()?;
+        if jobs.is_empty() {
+            return Ok("No scheduled jobs.".into());
+        }
+        let mut out = format!("{} job(s):\n", jobs.len());
+        for job in &jobs {
+            let when = match &job.schedule {
+                Schedule::Cron { expr } => format!("cron '{expr}'"),
+                Schedule::Once { at } => format!("once at {}", self.local(Some(*at))),
+            };
+            let what = match &job.action {
+                JobAction::Message { text } => format!("message: {text}"),
+                JobAction::Tool { name, .. } => format!("tool: {name}"),
+                JobAction::Prompt { prompt } => format!("prompt: {prompt}"),
+            };
+            out.push_str(&format!(
+                "- {} [{}] {when} → {}:{} | {what} | misfire {} | next {}{}\n",
+                job.name, job.id, job.channel, job.thread_id, job.misfire.as_str(),
+                self.local(job.next_run),
+                if job.paused { " (paused)" } else { "" },
+            ));
+        }
+        Ok(out)
+    }
+}
+
+#[async_trait]
+impl Tool for SchedulerTool {
+    fn name(&self) -> &str { "scheduler" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "scheduler".into(),
+            description: format!(
+                "Schedule recurring or one-time jobs: send a message, run a tool, or run a prompt, with the result sent to a channel. Times are in {}.",
+                self.store.tz
+            ),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["list", "create", "delete", "pause", "resume"] },
+                    "id": { "type": "string", "description": "Job ID (delete, pause, resume)" },
+                    "name": { "type": "string", "description": "Short job name (create)" },
+                    "cron": { "type": "string", "description": "5-field cron, e.g. '0 18 * * *' = every day 18:00, '0 9 * * mon' = Mondays 09:00" },
+                    "at": { "type": "string", "description": "One-time run, 'YYYY-MM-DD HH:MM' local time" },
+                    "job_type": { "type": "string", "enum": ["message", "tool", "prompt"], "description": "What to run (default prompt)" },
+                    "text": { "type": "string", "description": "Message to send (job_type=message)" },
+                    "tool": { "type": "string", "description": "Tool name (job_type=tool)" },
+                    "arguments": { "type": "object", "description": "Tool arguments (job_type=tool)" },
+                    "prompt": { "type": "string", "description": "Instruction for the agent (job_type=prompt)" },
+                    "channel": { "type": "string", "description": "Channel to send the result to (e.g. telegram, zalo)" },
+                    "thread_id": { "type": "string", "description": "Chat/thread ID in that channel" },
+                    "is_group": { "type": "boolean", "description": "The thread is a group chat" },
+                    "misfire": { "type": "string", "enum": ["skip", "run_once"], "description": "If a run was missed while offline: skip it, or run once on startup" }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let id = || args["id"].as_str().ok_or_else(|| BizClawError::Tool("Missing 'id'".into()));
+
+        let output = match args["action"].as_str().unwrap_or("") {
+            "list" => self.list()?,
+            "create" => self.create(&args)?,
+            "delete" => match self.store.delete(id()?)? {
+                true => format!("Deleted job {}", id()?),
+                false => format!("No job {}", id()?),
+            },
+            action @ ("pause" | "resume") => match self.store.set_paused(id()?, action == "pause")? {
+                true => format!("Job {} {action}d", id()?),
+                false => format!("No job {}", id()?),
+            },
+            other => return Err(BizClawError::Tool(format!("Unknown scheduler action: {other}"))),
+        };
+
+        Ok(ToolResult::ok(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> JobStore {
+        JobStore::open(Path::new(":memory:"), chrono_tz::Asia::Ho_Chi_Minh).unwrap()
+    }
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_cron_next_after() {
+        let tz = chrono_tz::Asia::Ho_Chi_Minh;
+        // 18:00 ICT = 11:00 UTC
+        let daily = CronExpr::parse("0 18 * * *").unwrap();
+        assert_eq!(daily.next_after(utc("2026-10-17T10:00:00Z"), tz), Some(utc("2026-10-17T11:00:00Z")));
+        assert_eq!(daily.next_after(utc("2026-10-17T11:00:00Z"), tz), Some(utc("2026-10-18T11:00:00Z")));
+
+        // 2026-10-17 is a Saturday; next Monday 09:00 ICT.
+        let monday = CronExpr::parse("0 9 * * mon").unwrap();
+        assert_eq!(monday.next_after(utc("2026-10-17T00:00:00Z"), tz), Some(utc("2026-10-19T02:00:00Z")));
+
+        let every_15 = CronExpr::parse("*/15 8-9 1,15 * *").unwrap();
+        assert_eq!(every_15.next_after(utc("2026-10-17T00:00:00Z"), tz), Some(utc("2026-11-01T01:00:00Z")));
+
+        for bad in ["* * * *", "60 * * * *", "*/0 * * * *", "5-1 * * * *", "0 0 * foo *"] {
+            assert!(CronExpr::parse(bad).is_err(), "{bad}");
+        }
+        assert!(CronExpr::parse("0 0 * * 7").unwrap().day_matches(NaiveDate::from_ymd_opt(2026, 10, 18).unwrap()));
+    }
+
+    #[test]
+    fn test_take_due_and_misfire_policies() {
+        let store = store();
+        let action = JobAction::Message { text: "hi".into() };
+        let every_minute = Schedule::Cron { expr: "* * * * *".into() };
+        let skip = store.create("skip", every_minute.clone(), action.clone(), "cli", "cli", false, MisfirePolicy::Skip).unwrap();
+        let catch_up = store.create("catch", every_minute, action.clone(), "cli", "cli", false, MisfirePolicy::RunOnce).unwrap();
+        let once_at = Utc::now() + Duration::minutes(5);
+        store.create("once", Schedule::Once { at: once_at }, action, "telegram", "42", false, MisfirePolicy::Skip).unwrap();
+
+        let grace = Duration::minutes(1);
+        let first_run = skip.next_run.unwrap();
+        assert!(store.take_due(first_run - Duration::seconds(1), grace).unwrap().is_empty());
+
+        // On time: both cron jobs fire.
+        let fired = store.take_due(first_run, grace).unwrap();
+        assert_eq!(fired.len(), 2);
+        assert!(fired.iter().all(|f| !f.late));
+
+        // Two hours of downtime: `skip` is dropped, `run_once` fires once, and
+        // the one-shot (skip policy) is removed without running.
+        let after_downtime = first_run + Duration::hours(2);
+        let fired = store.take_due(after_downtime, grace).unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].job.id, catch_up.id);
+        assert!(fired[0].late);
+        assert!(store.take_due(after_downtime, grace).unwrap().is_empty());
+        let jobs = store.list().unwrap();
+        assert_eq!(jobs.len(), 2);
+        assert!(jobs.iter().all(|j| j.next_run.unwrap() > after_downtime));
+    }
+
+    #[tokio::test]
+    async fn test_tool_create_list_pause_delete() {
+        let tool = SchedulerTool::new(Arc::new(store()));
+        let run = |args: serde_json::Value| {
+            let tool = &tool;
+            async move { tool.execute(&args.to_string()).await }
+        };
+
+        let missing_misfire = run(serde_json::json!({"action": "create", "cron": "0 18 * * *", "prompt": "x"})).await;
+        assert!(missing_misfire.is_err());
+
+        let created = run(serde_json::json!({
+            "action": "create", "name": "sales summary", "cron": "0 18 * * *",
+            "job_type": "tool", "tool": "group_summarizer", "arguments": {"action": "summarize"},
+            "channel": "zalo", "thread_id": "g1", "is_group": true, "misfire": "run_once"
+        })).await.unwrap();
+        assert!(created.output.contains("18:00"), "{}", created.output);
+        let remind = run(serde_json::json!({
+            "action": "create", "name": "remind", "at": "2099-01-05 09:00", "text": "Standup", "job_type": "message",
+            "misfire": "skip"
+        })).await.unwrap();
+        assert!(remind.output.contains("2099-01-05 09:00"));
+
+        let id = tool.store.list().unwrap().into_iter().find(|j| j.name == "remind").unwrap().id;
+        run(serde_json::json!({"action": "pause", "id": id})).await.unwrap();
+        let listing = run(serde_json::json!({"action": "list"})).await.unwrap().output;
+        assert!(listing.contains("2 job(s)"));
+        assert!(listing.contains("(paused)"));
+        assert!(listing.contains("zalo:g1"));
+
+        assert!(run(serde_json::json!({"action": "delete", "id": id})).await.unwrap().output.starts_with("Deleted"));
+        assert_eq!(tool.store.list().unwrap().len(), 1);
+    }
+}
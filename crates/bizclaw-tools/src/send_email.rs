@@ -0,0 +1,434 @@
+//! Send Email Tool — lets the agent send an email ("gửi báo giá cho khách X").
+//!
+//! Sends through the `[channel.email]` SMTP account. Unless autonomy is
+//! "full", every recipient must be in `tools.send_email.allowed_domains`;
+//! attachments must be inside the workspace; and sends are capped per hour
+//! so a prompt-injected loop can't turn the agent into a spam relay.
+
+use async_trait::async_trait;
+use bizclaw_core::config::{BizClawConfig, EmailChannelConfig};
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_security::allowlist::Allowlist;
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const RATE_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Send-email tool settings.
+#[derive(Debug, Clone)]
+pub struct SendEmailConfig {
+    pub smtp: EmailChannelConfig,
+    /// Recipient domains allowed; `None` allows any (autonomy "full").
+    pub allowed_domains: Option<Vec<String>>,
+    pub max_per_hour: usize,
+    pub max_attachment_bytes: u64,
+}
+
+impl SendEmailConfig {
+    /// Settings from `[tools.send_email]`, or `None` without a `[channel.email]` account.
+    pub fn from_config(config: &BizClawConfig) -> Option<Self> {
+        let tool = &config.tools.send_email;
+        Some(Self {
+            smtp: config.channel.email.clone()?,
+            allowed_domains: (config.autonomy.level != "full").then(|| tool.allowed_domains.clone()),
+            max_per_hour: tool.max_per_hour,
+            max_attachment_bytes: tool.max_attachment_mb * 1024 * 1024,
+        })
+    }
+}
+
+pub struct SendEmailTool {
+    config: SendEmailConfig,
+    /// Resolves attachment paths against the workspace.
+    allowlist: Allowlist,
+    /// When recent emails were sent, oldest first.
+    sent: Mutex<VecDeque<Instant>>,
+}
+
+impl SendEmailTool {
+    pub fn new(config: SendEmailConfig, allowlist: Allowlist) -> Self {
+        Self { config, allowlist, sent: Mutex::new(VecDeque::new()) }
+    }
+
+    /// Parse recipients, refusing domains outside `allowed_domains`.
+    fn recipients(&self, value: &serde_json::Value, field: &str) -> Result<std::result::Result<Vec<Mailbox>, String>> {
+        let list: Vec<&str> = match value {
+            serde_json::Value::Null => vec![],
+            serde_json::Value::String(s) => s.split([',', ';']).map(str::trim).filter(|s| !s.is_empty()).collect(),
+            serde_json::Value::Array(items) => items.iter().filter_map(|v| v.as_str()).collect(),
+            _ => return Err(BizClawError::Tool(format!("'{field}' must be an address or a list of addresses"))),
+        };
+        let mut mailboxes = Vec::with_capacity(list.len());
+        for recipient in list {
+            let mailbox: Mailbox = recipient.parse()
+                .map_err(|e| BizClawError::Tool(format!("Invalid address '{recipient}' in '{field}': {e}")))?;
+            if let Some(allowed) = &self.config.allowed_domains {
+                let domain = mailbox.email.domain().to_lowercase();
+                if !allowed.iter().any(|d| domain_matches(d, &domain)) {
+                    return Ok(Err(format!("'{domain}' is not in tools.send_email.allowed_domains")));
+                }
+            }
+            mailboxes.push(mailbox);
+        }
+        Ok(Ok(mailboxes))
+    }
+
+    /// Take a slot in the hourly budget, or say when the next one frees up.
+    fn reserve_slot(&self) -> std::result::Result<Instant, Duration> {
+        let now = Instant::now();
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        while sent.front().is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW) {
+            sent.pop_front();
+        }
+        if sent.len() >= self.config.max_per_hour {
+            let oldest = sent.front().copied().unwrap_or(now);
+            return Err(RATE_WINDOW.saturating_sub(now.duration_since(oldest)));
+        }
+        sent.push_back(now);
+        Ok(now)
+    }
+
+    /// Give back a slot whose send failed.
+    fn release_slot(&self, slot: Instant) {
+        let mut sent = self.sent.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(pos) = sent.iter().position(|t| *t == slot) {
+            sent.remove(pos);
+        }
+    }
+
+    /// Read an attachment from inside the workspace.
+    async fn attachment(&self, path: &str) -> Result<std::result::Result<SinglePart, String>> {
+        let resolved = match self.allowlist.check_path(path) {
+            Ok(resolved) if resolved.starts_with(self.allowlist.workspace()) => resolved,
+            Ok(resolved) => return Ok(Err(format!("'{}' is outside the workspace", resolved.display()))),
+            Err(denial) => return Ok(Err(denial.reason)),
+        };
+        let size = tokio::fs::metadata(&resolved).await
+            .map_err(|e| BizClawError::Tool(format!("Attachment '{path}': {e}")))?
+            .len();
+        if size > self.config.max_attachment_bytes {
+            return Err(BizClawError::Tool(format!(
+                "Attachment '{path}' is {size} bytes, over the {} byte limit", self.config.max_attachment_bytes
+            )));
+        }
+        let bytes = tokio::fs::read(&resolved).await
+            .map_err(|e| BizClawError::Tool(format!("Attachment '{path}': {e}")))?;
+        let filename = resolved.file_name().and_then(|f| f.to_str()).unwrap_or("attachment").to_string();
+        let content_type = ContentType::parse(content_type_for(&filename))
+            .map_err(|e| BizClawError::Tool(format!("Attachment content type: {e}")))?;
+        Ok(Ok(Attachment::new(filename).body(bytes, content_type)))
+    }
+
+    fn transport(&self) -> Result<AsyncSmtpTransport<Tokio1Executor>> {
+        let smtp = &self.config.smtp;
+        let builder = match smtp.smtp_security.as_str() {
+            "starttls" => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.smtp_host),
+            "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.smtp_host),
+            "none" => Ok(AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.smtp_host)),
+            other => return Err(BizClawError::Config(format!(
+                "channel.email.smtp_security must be starttls, tls or none, not '{other}'"
+            ))),
+        }
+        .map_err(|e| BizClawError::Tool(format!("SMTP relay: {e}")))?
+        .port(smtp.smtp_port);
+        let builder = if smtp.password.is_empty() {
+            builder
+        } else {
+            builder.credentials(Credentials::new(smtp.email.clone(), smtp.password.clone()))
+        };
+        Ok(builder.build())
+    }
+}
+
+/// `domain` is `pattern` or one of its subdomains.
+fn domain_matches(pattern: &str, domain: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("*.").trim_start_matches('@').to_lowercase();
+    !pattern.is_empty() && (domain == pattern || domain.ends_with(&format!(".{pattern}")))
+}
+
+/// MIME type for common business attachments, by extension.
+fn content_type_for(filename: &str) -> &'static str {
+    let ext = filename.rsplit_once('.').map(|(_, e)| e.to_lowercase()).unwrap_or_default();
+    match ext.as_str() {
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "html" | "htm" => "text/html; charset=utf-8",
+        "docx" => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "xlsx" => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "pptx" => "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+fn denied(subject: &str, reason: String) -> ToolResult {
+    tracing::warn!(target: "bizclaw::audit", tool = "send_email", subject, "denied: {reason}");
+    ToolResult::failure("permission_denied", serde_json::json!({
+        "error": "permission_denied",
+        "tool": "send_email",
+        "subject": subject,
+        "reason": reason,
+    }).to_string())
+}
+
+#[async_trait]
+impl Tool for SendEmailTool {
+    fn name(&self) -> &str { "send_email" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "send_email".into(),
+            description: "Gửi email (ví dụ gửi báo giá cho khách hàng). Trả về Message-ID khi gửi thành công.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "to": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Recipient addresses, e.g. [\"Khach Hang <kh@example.com>\"]"
+                    },
+                    "cc": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "CC addresses (optional)"
+                    },
+                    "subject": { "type": "string", "description": "Subject line" },
+                    "body": { "type": "string", "description": "Message body" },
+                    "format": {
+                        "type": "string",
+                        "enum": ["text", "html"],
+                        "description": "Body format (default text)"
+                    },
+                    "attachment": {
+                        "type": "string",
+                        "description": "Path of a file in the workspace to attach (optional)"
+                    }
+                },
+                "required": ["to", "subject", "body"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(format!("Invalid arguments: {e}")))?;
+        let subject = args["subject"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'subject'".into()))?;
+        let body = args["body"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'body'".into()))?;
+
+        let to = match self.recipients(&args["to"], "to")? {
+            Ok(to) => to,
+            Err(reason) => return Ok(denied(&args["to"].to_string(), reason)),
+        };
+        if to.is_empty() {
+            return Err(BizClawError::Tool("Missing 'to'".into()));
+        }
+        let cc = match self.recipients(&args["cc"], "cc")? {
+            Ok(cc) => cc,
+            Err(reason) => return Ok(denied(&args["cc"].to_string(), reason)),
+        };
+
+        let text = match args["format"].as_str().unwrap_or("text") {
+            "text" => SinglePart::plain(body.to_string()),
+            "html" => SinglePart::html(body.to_string()),
+            other => return Err(BizClawError::Tool(format!("Unknown format '{other}': use text or html"))),
+        };
+        let attachment = match args["attachment"].as_str() {
+            Some(path) => match self.attachment(path).await? {
+                Ok(part) => Some(part),
+                Err(reason) => return Ok(denied(path, reason)),
+            },
+            None => None,
+        };
+
+        let smtp = &self.config.smtp;
+        let from_name = smtp.display_name.as_deref().unwrap_or("BizClaw AI");
+        let from: Mailbox = format!("{from_name} <{}>", smtp.email).parse()
+            .map_err(|e| BizClawError::Config(format!("channel.email.email: {e}")))?;
+        let mut builder = Message::builder().from(from).subject(subject).message_id(None);
+        for mailbox in &to {
+            builder = builder.to(mailbox.clone());
+        }
+        for mailbox in cc {
+            builder = builder.cc(mailbox);
+        }
+        let message = match attachment {
+            Some(attachment) => builder.multipart(MultiPart::mixed().singlepart(text).singlepart(attachment)),
+            None => builder.singlepart(text),
+        }
+        .map_err(|e| BizClawError::Tool(format!("Build email: {e}")))?;
+        let message_id = message.headers().get_raw("Message-ID").unwrap_or_default().to_string();
+
+        let slot = match self.reserve_slot() {
+            Ok(slot) => slot,
+            Err(wait) => {
+                return Ok(denied(
+                    "max_per_hour",
+                    format!(
+                        "Sent {} emails in the last hour; try again in {} minutes",
+                        self.config.max_per_hour,
+                        wait.as_secs().div_ceil(60),
+                    ),
+                ));
+            }
+        };
+        if let Err(e) = self.transport()?.send(message).await {
+            self.release_slot(slot);
+            return Err(BizClawError::Tool(format!("SMTP send: {e}")));
+        }
+
+        let recipients: Vec<String> = to.iter().map(|m| m.email.to_string()).collect();
+        tracing::info!(target: "bizclaw::audit", tool = "send_email", to = %recipients.join(","), message_id, "email sent");
+        Ok(ToolResult::ok(format!("📧 Đã gửi email tới {}. Message-ID: {message_id}", recipients.join(", "))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    /// Minimal SMTP server: accepts every message and hands back the DATA of each.
+    async fn smtp_server() -> (u16, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else { return };
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let (read, mut write) = stream.into_split();
+                    let mut lines = BufReader::new(read).lines();
+                    write.write_all(b"220 localhost ESMTP test\r\n").await.unwrap();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let reply: &[u8] = match line.to_uppercase().get(..4).unwrap_or("") {
+                            "EHLO" => b"250-localhost\r\n250 8BITMIME\r\n",
+                            "DATA" => {
+                                write.write_all(b"354 go ahead\r\n").await.unwrap();
+                                let mut data = String::new();
+                                while let Ok(Some(line)) = lines.next_line().await {
+                                    if line == "." {
+                                        break;
+                                    }
+                                    data.push_str(&line);
+                                    data.push('\n');
+                                }
+                                let _ = tx.send(data);
+                                b"250 queued\r\n"
+                            }
+                            "QUIT" => {
+                                let _ = write.write_all(b"221 bye\r\n").await;
+                                return;
+                            }
+                            _ => b"250 ok\r\n",
+                        };
+                        write.write_all(reply).await.unwrap();
+                    }
+                });
+            }
+        });
+        (port, rx)
+    }
+
+    fn workspace(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("bizclaw-email-{name}-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn tool(port: u16, allowed_domains: Option<Vec<String>>, max_per_hour: usize, ws: &std::path::Path) -> SendEmailTool {
+        let config = SendEmailConfig {
+            smtp: EmailChannelConfig {
+                smtp_host: "127.0.0.1".into(),
+                smtp_port: port,
+                smtp_security: "none".into(),
+                email: "shop@bizclaw.test".into(),
+                display_name: Some("Shop".into()),
+                ..Default::default()
+            },
+            allowed_domains,
+            max_per_hour,
+            max_attachment_bytes: 1024,
+        };
+        let autonomy = bizclaw_core::config::AutonomyConfig::default();
+        SendEmailTool::new(config, Allowlist::new(&autonomy).with_workspace(ws))
+    }
+
+    #[tokio::test]
+    async fn test_send_email_with_attachment() {
+        let (port, mut received) = smtp_server().await;
+        let ws = workspace("send");
+        std::fs::write(ws.join("quote.csv"), "item,price\nwidget,10\n").unwrap();
+        let tool = tool(port, Some(vec!["example.com".into()]), 10, &ws);
+
+        let args = serde_json::json!({
+            "to": ["Khach <kh@sales.example.com>"],
+            "cc": ["boss@example.com"],
+            "subject": "Bao gia",
+            "body": "<p>Gui anh bao gia</p>",
+            "format": "html",
+            "attachment": "quote.csv"
+        });
+        let result = tool.execute(&args.to_string()).await.unwrap();
+        assert!(result.success, "{}", result.output);
+        let data = received.recv().await.unwrap();
+        let message_id = data.lines()
+            .find_map(|l| l.strip_prefix("Message-ID: "))
+            .unwrap();
+        assert!(result.output.contains(message_id), "{}", result.output);
+        assert!(data.contains("To: \"Khach\" <kh@sales.example.com>") || data.contains("To: Khach <kh@sales.example.com>"), "{data}");
+        assert!(data.contains("Cc: boss@example.com"), "{data}");
+        assert!(data.contains("Content-Type: text/html"), "{data}");
+        assert!(data.contains("filename=\"quote.csv\""), "{data}");
+
+        // Outside the workspace.
+        let args = serde_json::json!({ "to": "kh@example.com", "subject": "s", "body": "b", "attachment": "../x.csv" });
+        let result = tool.execute(&args.to_string()).await.unwrap();
+        assert!(!result.success && result.output.contains("permission_denied"), "{}", result.output);
+    }
+
+    async fn send(tool: &SendEmailTool, to: &str) -> ToolResult {
+        let args = serde_json::json!({ "to": [to], "subject": "Hi", "body": "Hello" });
+        tool.execute(&args.to_string()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_send_email_domains_and_rate_limit() {
+        let (port, mut received) = smtp_server().await;
+        let ws = workspace("limit");
+        let limited = tool(port, Some(vec!["example.com".into()]), 2, &ws);
+        let result = send(&limited, "spam@evil.com").await;
+        assert!(!result.success && result.output.contains("allowed_domains"), "{}", result.output);
+        // Lookalike domains don't match.
+        assert!(!send(&limited, "a@notexample.com").await.success);
+
+        assert!(send(&limited, "a@example.com").await.success);
+        assert!(send(&limited, "b@example.com").await.success);
+        let result = send(&limited, "c@example.com").await;
+        assert!(!result.success && result.output.contains("last hour"), "{}", result.output);
+        received.recv().await.unwrap();
+        received.recv().await.unwrap();
+
+        // Full autonomy: any domain.
+        let full = tool(port, None, 10, &ws);
+        assert!(send(&full, "anyone@elsewhere.org").await.success);
+
+        // A failed send doesn't use up the budget.
+        let down = tool(1, None, 1, &ws);
+        assert!(down.execute(&serde_json::json!({ "to": ["a@x.com"], "subject": "s", "body": "b" }).to_string()).await.is_err());
+        assert!(down.reserve_slot().is_ok());
+    }
+}
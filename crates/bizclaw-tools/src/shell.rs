@@ -1,14 +1,60 @@
 //! Shell command execution tool.
+//!
+//! With an autonomy policy attached, every executable in the command line must
+//! be in `autonomy.allowed_commands` unless the level is "full". At level
+//! "approval" a refused command waits for a human to approve it instead.
 
 use async_trait::async_trait;
+use bizclaw_core::config::AutonomyConfig;
 use bizclaw_core::error::Result;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_security::allowlist::{Allowlist, Denial};
+use bizclaw_security::approval::Approver;
 
-pub struct ShellTool;
+pub struct ShellTool {
+    /// Working directory used when a call doesn't pass `workdir`.
+    default_workdir: Option<String>,
+    /// Command/path policy; `None` means unrestricted.
+    allowlist: Option<Allowlist>,
+    /// Asked about commands the allowlist refuses; `None` refuses them.
+    approver: Option<Approver>,
+}
 
 impl ShellTool {
-    pub fn new() -> Self { Self }
+    pub fn new() -> Self { Self { default_workdir: None, allowlist: None, approver: None } }
+
+    pub fn with_workdir(workdir: Option<String>) -> Self {
+        Self { default_workdir: workdir, allowlist: None, approver: None }
+    }
+
+    /// Enforce `allowed_commands` (and workdir path rules) unless the level is
+    /// "full". At "approval" refused commands go to the global approval broker.
+    pub fn with_autonomy(mut self, autonomy: &AutonomyConfig) -> Self {
+        self.allowlist = (autonomy.level != "full").then(|| Allowlist::new(autonomy));
+        self.approver = Approver::from_autonomy(autonomy);
+        self
+    }
+
+    /// Ask `approver` about refused commands instead of refusing them.
+    pub fn with_approver(mut self, approver: Approver) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Use an explicit policy (e.g. one with a custom workspace).
+    pub fn with_allowlist(mut self, allowlist: Allowlist) -> Self {
+        self.allowlist = Some(allowlist);
+        self
+    }
+
+    fn check(&self, command: &str, workdir: Option<&str>) -> std::result::Result<(), Denial> {
+        let Some(allowlist) = &self.allowlist else { return Ok(()) };
+        if let Some(dir) = workdir {
+            allowlist.check_path(dir)?;
+        }
+        allowlist.check_command(command)
+    }
 }
 
 impl Default for ShellTool {
@@ -19,6 +65,8 @@ impl Default for ShellTool {
 impl Tool for ShellTool {
     fn name(&self) -> &str { "shell" }
 
+    fn concurrency_safe(&self) -> bool { false }
+
     fn definition(&self) -> ToolDefinition {
         ToolDefinition {
             name: "shell".into(),
@@ -47,32 +95,165 @@ impl Tool for ShellTool {
         let command = args["command"].as_str()
             .ok_or_else(|| bizclaw_core::error::BizClawError::Tool("Missing 'command'".into()))?;
 
-        let workdir = args["workdir"].as_str();
+        let workdir = args["workdir"].as_str().or(self.default_workdir.as_deref());
+
+        if let Err(denial) = self.check(command, workdir) {
+            let Some(approver) = &self.approver else {
+                denial.audit("shell");
+                return Ok(ToolResult::failure("permission_denied", denial.to_json("shell").to_string()));
+            };
+            if let Err(decision) = approver.approve("shell", arguments, &denial).await {
+                let json = Approver::denied_json("shell", &denial, &decision);
+                return Ok(ToolResult::failure("approval_denied", json.to_string()));
+            }
+        }
 
         let mut cmd = tokio::process::Command::new("sh");
         cmd.arg("-c").arg(command);
         if let Some(dir) = workdir {
             cmd.current_dir(dir);
         }
+        // If the registry times us out, this future is dropped mid-wait; the
+        // child (and anything it spawned) must die with it.
+        cmd.kill_on_drop(true);
+        #[cfg(unix)]
+        cmd.process_group(0);
 
-        let output = cmd
-            .output()
-            .await
+        let child = cmd
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .spawn()
             .map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
+        let mut group = ProcessGroupGuard(child.id());
+
+        let output = child.wait_with_output().await;
+        // Finished normally: leave intentionally backgrounded processes alone.
+        group.0 = None;
+        let output = output.map_err(|e| bizclaw_core::error::BizClawError::Tool(e.to_string()))?;
 
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         let stderr = String::from_utf8_lossy(&output.stderr).to_string();
 
+        let exit_code = output.status.code().unwrap_or(-1);
+        let data = serde_json::json!({ "exit_code": exit_code, "stdout": stdout, "stderr": stderr });
+
         let result = if output.status.success() {
-            stdout
+            ToolResult::ok(stdout)
         } else {
-            format!("STDOUT:\n{stdout}\nSTDERR:\n{stderr}\nExit code: {}", output.status.code().unwrap_or(-1))
+            ToolResult::failure(
+                "exit_status",
+                format!("STDOUT:\n{stdout}\nSTDERR:\n{stderr}\nExit code: {exit_code}"),
+            )
+        };
+        Ok(result.with_data(data))
+    }
+}
+
+/// Kills the command's whole process group if dropped while still armed, so
+/// grandchildren (`sh -c "sleep 100; ..."`) don't outlive a timed-out call.
+struct ProcessGroupGuard(Option<u32>);
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.0 {
+            // SAFETY: plain syscall; the group id is the child's pid (process_group(0)).
+            unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn restricted() -> ShellTool {
+        let autonomy = AutonomyConfig {
+            allowed_commands: vec!["echo".into()],
+            forbidden_paths: vec![],
+            ..Default::default()
+        };
+        ShellTool::new().with_autonomy(&autonomy)
+    }
+
+    #[tokio::test]
+    async fn test_allowed_command_runs() {
+        let result = restricted().execute(r#"{"command": "echo hi"}"#).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output.trim(), "hi");
+    }
+
+    #[tokio::test]
+    async fn test_denied_command_is_structured() {
+        for command in ["rm -rf x", "echo ok; rm x", "bash -c 'echo hi'", "echo $(id)"] {
+            let args = serde_json::json!({ "command": command }).to_string();
+            let result = restricted().execute(&args).await.unwrap();
+            assert!(!result.success, "{command} should be denied");
+            let denial: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+            assert_eq!(denial["error"], "permission_denied");
+            assert_eq!(result.error_kind.as_deref(), Some("permission_denied"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_exit_code_and_streams_in_data() {
+        let result = ShellTool::new().execute(r#"{"command": "echo out; echo err >&2; exit 3"}"#).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error_kind.as_deref(), Some("exit_status"));
+        assert_eq!(result.data["exit_code"], 3);
+        assert_eq!(result.data["stdout"], "out\n");
+        assert_eq!(result.data["stderr"], "err\n");
+
+        let result = ShellTool::new().execute(r#"{"command": "echo hi"}"#).await.unwrap();
+        assert_eq!(result.data["exit_code"], 0);
+        assert!(result.error_kind.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_full_autonomy_is_unrestricted() {
+        let autonomy = AutonomyConfig { level: "full".into(), allowed_commands: vec![], ..Default::default() };
+        let tool = ShellTool::new().with_autonomy(&autonomy);
+        assert!(tool.execute(r#"{"command": "true"}"#).await.unwrap().success);
+    }
+
+    #[tokio::test]
+    async fn test_approval_level_asks_for_refused_commands() {
+        use bizclaw_security::approval::ApprovalBroker;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let autonomy = AutonomyConfig {
+            level: "approval".into(),
+            allowed_commands: vec!["echo".into()],
+            forbidden_paths: vec![],
+            ..Default::default()
         };
+        let broker = Arc::new(ApprovalBroker::new());
+        let tool = ShellTool::new()
+            .with_autonomy(&autonomy)
+            .with_approver(Approver::new(broker.clone(), Duration::from_secs(5)));
+
+        assert!(tool.execute(r#"{"command": "echo hi"}"#).await.unwrap().success);
+        assert!(broker.pending().is_empty());
+
+        let mut events = broker.subscribe();
+        let approver = tokio::spawn({
+            let broker = broker.clone();
+            async move {
+                let first = events.recv().await.unwrap();
+                assert_eq!(first.tool, "shell");
+                assert!(first.reason.contains("printf"));
+                broker.resolve(&first.id, true, "ops");
+                let second = events.recv().await.unwrap();
+                broker.resolve(&second.id, false, "ops");
+            }
+        });
+        let approved = tool.execute(r#"{"command": "printf ok"}"#).await.unwrap();
+        assert_eq!(approved.output, "ok");
 
-        Ok(ToolResult {
-            tool_call_id: String::new(),
-            output: result,
-            success: output.status.success(),
-        })
+        let denied = tool.execute(r#"{"command": "printf no"}"#).await.unwrap();
+        assert_eq!(denied.error_kind.as_deref(), Some("approval_denied"));
+        assert!(denied.output.contains("denied by ops"));
+        approver.await.unwrap();
     }
 }
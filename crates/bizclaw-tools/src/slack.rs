@@ -0,0 +1,318 @@
+//! Slack Tool — post messages, read channels, and upload files via the Slack Web API.
+//!
+//! Uses a bot token (`xoxb-...`). Slack reports failures as HTTP 200 with
+//! `"ok": false`, so every response is checked for that field. Only files
+//! inside the workspace, and not under `forbidden_paths`, can be uploaded.
+
+use async_trait::async_trait;
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_security::allowlist::Allowlist;
+use chrono::{FixedOffset, Offset, TimeZone};
+use serde::{Deserialize, Serialize};
+
+const SLACK_API: &str = "https://slack.com/api";
+
+/// Slack tool configuration.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SlackConfig {
+    pub bot_token: String,
+    /// Channel used when a call doesn't name one
+    pub default_channel: Option<String>,
+}
+
+impl From<&bizclaw_core::config::SlackToolConfig> for SlackConfig {
+    fn from(cfg: &bizclaw_core::config::SlackToolConfig) -> Self {
+        Self {
+            bot_token: cfg.bot_token.clone(),
+            default_channel: cfg.default_channel.clone(),
+        }
+    }
+}
+
+/// Slack messaging tool.
+pub struct SlackTool {
+    token: String,
+    default_channel: Option<String>,
+    client: reqwest::Client,
+    /// Which files may be uploaded.
+    allowlist: Allowlist,
+}
+
+impl SlackTool {
+    pub fn new(config: SlackConfig) -> Self {
+        Self {
+            token: config.bot_token,
+            default_channel: config.default_channel,
+            client: reqwest::Client::new(),
+            allowlist: Allowlist::new(&Default::default()),
+        }
+    }
+
+    /// Upload only files this policy allows, inside its workspace.
+    pub fn with_allowlist(mut self, allowlist: Allowlist) -> Self {
+        self.allowlist = allowlist;
+        self
+    }
+
+    /// Resolve a file to upload, refusing one outside the workspace or
+    /// under `forbidden_paths`.
+    fn upload_path(&self, file_path: &str) -> Result<std::path::PathBuf> {
+        match self.allowlist.check_path(file_path) {
+            Ok(resolved) if resolved.starts_with(self.allowlist.workspace()) => Ok(resolved),
+            Ok(resolved) => Err(BizClawError::PermissionDenied(format!(
+                "Slack upload: '{}' is outside the workspace", resolved.display()
+            ))),
+            Err(denial) => {
+                denial.audit("slack");
+                Err(BizClawError::PermissionDenied(format!("Slack upload: {denial}")))
+            }
+        }
+    }
+
+    /// Send a request and return the body, mapping `"ok": false` to a tool error.
+    async fn send(&self, method: &str, req: reqwest::RequestBuilder) -> Result<serde_json::Value> {
+        let response = req
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .map_err(|e| BizClawError::Tool(format!("Slack {method} failed: {e}")))?;
+        let body: serde_json::Value = response.json().await
+            .map_err(|e| BizClawError::Tool(format!("Parse Slack {method} response failed: {e}")))?;
+        check_ok(method, body)
+    }
+
+    async fn get(&self, method: &str, query: &[(&str, String)]) -> Result<serde_json::Value> {
+        let req = self.client.get(format!("{SLACK_API}/{method}")).query(query);
+        self.send(method, req).await
+    }
+
+    async fn post_json(&self, method: &str, body: serde_json::Value) -> Result<serde_json::Value> {
+        let req = self.client.post(format!("{SLACK_API}/{method}")).json(&body);
+        self.send(method, req).await
+    }
+
+    fn channel<'a>(&'a self, requested: Option<&'a str>) -> Result<&'a str> {
+        requested.or(self.default_channel.as_deref())
+            .ok_or_else(|| BizClawError::Tool("Missing 'channel' and no default_channel configured".into()))
+    }
+
+    async fn send_message(&self, channel: &str, text: &str, blocks: Option<serde_json::Value>) -> Result<String> {
+        let mut body = serde_json::json!({ "channel": channel, "text": text });
+        if let Some(blocks) = blocks {
+            body["blocks"] = blocks;
+        }
+        let resp = self.post_json("chat.postMessage", body).await?;
+        Ok(format!(
+            "Message sent to {} (ts {})",
+            resp["channel"].as_str().unwrap_or(channel),
+            resp["ts"].as_str().unwrap_or("?")
+        ))
+    }
+
+    async fn list_channels(&self) -> Result<String> {
+        let resp = self.get("conversations.list", &[
+            ("types", "public_channel,private_channel".into()),
+            ("exclude_archived", "true".into()),
+            ("limit", "200".into()),
+        ]).await?;
+        let channels = resp["channels"].as_array().cloned().unwrap_or_default();
+        if channels.is_empty() {
+            return Ok("No channels visible to the bot.".into());
+        }
+        let mut out = format!("{} channel(s):\n", channels.len());
+        for ch in &channels {
+            out.push_str(&format!(
+                "#{} ({}){}\n",
+                ch["name"].as_str().unwrap_or("?"),
+                ch["id"].as_str().unwrap_or("?"),
+                if ch["is_member"].as_bool() == Some(true) { "" } else { " — bot not a member" },
+            ));
+        }
+        Ok(out)
+    }
+
+    async fn get_channel_history(&self, channel: &str, limit: u64) -> Result<String> {
+        let resp = self.get("conversations.history", &[
+            ("channel", channel.to_string()),
+            ("limit", limit.to_string()),
+        ]).await?;
+        let mut messages = resp["messages"].as_array().cloned().unwrap_or_default();
+        // Slack returns newest first; read top-down in chronological order.
+        messages.reverse();
+        let offset = chrono::Local::now().offset().fix();
+        let lines: Vec<String> = messages.iter()
+            .map(|m| format_message(m, &offset))
+            .collect();
+        if lines.is_empty() {
+            return Ok(format!("No messages in {channel}."));
+        }
+        Ok(lines.join("\n"))
+    }
+
+    async fn upload_file(&self, channel: &str, file_path: &str, title: Option<&str>) -> Result<String> {
+        let path = self.upload_path(file_path)?;
+        let bytes = tokio::fs::read(&path).await
+            .map_err(|e| BizClawError::Tool(format!("Cannot read {file_path}: {e}")))?;
+        let filename = path.file_name().and_then(|f| f.to_str()).unwrap_or("upload").to_string();
+
+        // 1. Reserve an upload URL.
+        let reserve = self.get("files.getUploadURLExternal", &[
+            ("filename", filename.clone()),
+            ("length", bytes.len().to_string()),
+        ]).await?;
+        let upload_url = reserve["upload_url"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Slack did not return an upload_url".into()))?;
+        let file_id = reserve["file_id"].as_str().unwrap_or_default().to_string();
+
+        // 2. Send the bytes.
+        let upload = self.client.post(upload_url).body(bytes).send().await
+            .map_err(|e| BizClawError::Tool(format!("Slack upload failed: {e}")))?;
+        if !upload.status().is_success() {
+            return Err(BizClawError::Tool(format!("Slack upload failed: HTTP {}", upload.status())));
+        }
+
+        // 3. Share it in the channel.
+        self.post_json("files.completeUploadExternal", serde_json::json!({
+            "files": [{ "id": file_id, "title": title.unwrap_or(&filename) }],
+            "channel_id": channel,
+        })).await?;
+        Ok(format!("Uploaded {filename} to {channel} (file {file_id})"))
+    }
+}
+
+/// Map Slack's `"ok": false` envelope to a tool error.
+fn check_ok(method: &str, body: serde_json::Value) -> Result<serde_json::Value> {
+    if body["ok"].as_bool() == Some(true) {
+        return Ok(body);
+    }
+    Err(BizClawError::Tool(format!(
+        "Slack {method} error: {}",
+        body["error"].as_str().unwrap_or("unknown_error")
+    )))
+}
+
+/// `[HH:MM] @user: text` in the given timezone.
+fn format_message(msg: &serde_json::Value, tz: &FixedOffset) -> String {
+    let secs = msg["ts"].as_str()
+        .and_then(|ts| ts.split('.').next())
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(0);
+    let time = tz.timestamp_opt(secs, 0).single()
+        .map(|t| t.format("%H:%M").to_string())
+        .unwrap_or_else(|| "--:--".into());
+    let user = msg["user"].as_str()
+        .or_else(|| msg["username"].as_str())
+        .or_else(|| msg["bot_id"].as_str())
+        .unwrap_or("unknown");
+    format!("[{time}] @{user}: {}", msg["text"].as_str().unwrap_or(""))
+}
+
+#[async_trait]
+impl Tool for SlackTool {
+    fn name(&self) -> &str { "slack" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "slack".into(),
+            description: "Send Slack messages, list channels, read channel history, and upload files.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": {
+                        "type": "string",
+                        "enum": ["send_message", "list_channels", "get_channel_history", "upload_file"],
+                        "description": "Operation to perform"
+                    },
+                    "channel": { "type": "string", "description": "Channel ID or name (defaults to the configured channel)" },
+                    "text": { "type": "string", "description": "Message text (send_message)" },
+                    "blocks": { "type": "array", "description": "Block Kit blocks (send_message, optional)" },
+                    "limit": { "type": "integer", "description": "Messages to fetch (get_channel_history, default 20)" },
+                    "file_path": { "type": "string", "description": "Local file to upload (upload_file)" },
+                    "title": { "type": "string", "description": "File title (upload_file, optional)" }
+                },
+                "required": ["action"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let str_arg = |key: &str| {
+            args[key].as_str().ok_or_else(|| BizClawError::Tool(format!("Missing '{key}'")))
+        };
+
+        let output = match str_arg("action")? {
+            "send_message" => {
+                let channel = self.channel(args["channel"].as_str())?;
+                let blocks = Some(args["blocks"].clone()).filter(|b| !b.is_null());
+                self.send_message(channel, str_arg("text")?, blocks).await?
+            }
+            "list_channels" => self.list_channels().await?,
+            "get_channel_history" => {
+                let channel = self.channel(args["channel"].as_str())?;
+                self.get_channel_history(channel, args["limit"].as_u64().unwrap_or(20)).await?
+            }
+            "upload_file" => {
+                let channel = self.channel(args["channel"].as_str())?;
+                self.upload_file(channel, str_arg("file_path")?, args["title"].as_str()).await?
+            }
+            other => return Err(BizClawError::Tool(format!("Unknown slack action: {other}"))),
+        };
+
+        Ok(ToolResult::ok(output))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_ok() {
+        assert!(check_ok("chat.postMessage", serde_json::json!({"ok": true})).is_ok());
+        let err = check_ok("chat.postMessage", serde_json::json!({"ok": false, "error": "channel_not_found"}))
+            .unwrap_err();
+        assert!(err.to_string().contains("channel_not_found"));
+    }
+
+    #[test]
+    fn test_format_message() {
+        let msg = serde_json::json!({ "ts": "1700000000.000200", "user": "U123", "text": "hello" });
+        let ict = FixedOffset::east_opt(7 * 3600).unwrap();
+        // 1700000000 = 2023-11-14 22:13:20 UTC
+        assert_eq!(format_message(&msg, &ict), "[05:13] @U123: hello");
+    }
+
+    #[test]
+    fn test_default_channel() {
+        let tool = SlackTool::new(SlackConfig { bot_token: "x".into(), default_channel: Some("C1".into()) });
+        assert_eq!(tool.channel(None).unwrap(), "C1");
+        assert_eq!(tool.channel(Some("C2")).unwrap(), "C2");
+        assert!(SlackTool::new(SlackConfig::default()).channel(None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_upload_only_from_workspace() {
+        let root = std::env::temp_dir().join(format!("bizclaw-slack-{}", uuid::Uuid::new_v4().simple()));
+        let ws = root.join("ws");
+        std::fs::create_dir_all(&ws).unwrap();
+        std::fs::write(ws.join("report.csv"), "a,b\n").unwrap();
+        std::fs::write(root.join("secret.txt"), "token").unwrap();
+
+        let autonomy = bizclaw_core::config::AutonomyConfig { workspace_only: false, ..Default::default() };
+        let tool = SlackTool::new(SlackConfig { bot_token: "x".into(), default_channel: Some("C1".into()) })
+            .with_allowlist(Allowlist::new(&autonomy).with_workspace(&ws));
+        assert!(tool.upload_path("report.csv").is_ok());
+        let outside = root.join("secret.txt").to_string_lossy().into_owned();
+        for path in ["../secret.txt", outside.as_str(), "/etc/passwd", "~/.ssh/id_ed25519"] {
+            let err = tool.execute(&serde_json::json!({"action": "upload_file", "file_path": path}).to_string())
+                .await.unwrap_err();
+            assert!(matches!(err, BizClawError::PermissionDenied(_)), "{path}: {err}");
+        }
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,723 @@
+//! Web Fetch Tool — download a page and return it as readable text.
+//!
+//! Pages go through the same URL checks as `http_request` (host rules, no
+//! internal addresses, every redirect re-checked). HTML is reduced to
+//! markdown-ish text: scripts, styles, navigation, and forms are dropped;
+//! headings, links, lists, tables, and code blocks are kept. Results are
+//! cached for `cache_ttl` so the agent can re-read a page cheaply.
+
+use async_trait::async_trait;
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+use bizclaw_core::error::{BizClawError, Result};
+use reqwest::Url;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::http_request::{HttpRequestConfig, HttpRequestTool};
+
+/// Elements whose raw content is skipped up to the closing tag.
+const RAW_SKIP: &[&str] = &["script", "style", "noscript", "template", "title"];
+/// Elements dropped with everything inside them.
+const SKIP: &[&str] = &["nav", "footer", "aside", "form", "svg", "iframe", "button", "select", "head"];
+const VOID: &[&str] = &["area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "source", "track", "wbr"];
+
+/// Web fetch tool configuration.
+#[derive(Debug, Clone)]
+pub struct WebFetchConfig {
+    /// Host rules and limits for the download itself.
+    pub http: HttpRequestConfig,
+    pub max_tokens: usize,
+    pub cache_ttl: Duration,
+    pub extract_pdf: bool,
+}
+
+impl WebFetchConfig {
+    pub fn from_config(config: &bizclaw_core::config::BizClawConfig) -> Self {
+        let fetch = &config.tools.web_fetch;
+        Self {
+            http: HttpRequestConfig {
+                max_response_bytes: fetch.max_download_bytes,
+                ..(&config.tools.http_request).into()
+            },
+            max_tokens: fetch.max_tokens,
+            cache_ttl: Duration::from_secs(fetch.cache_ttl_secs),
+            extract_pdf: fetch.extract_pdf,
+        }
+    }
+}
+
+impl Default for WebFetchConfig {
+    fn default() -> Self {
+        Self::from_config(&bizclaw_core::config::BizClawConfig::default())
+    }
+}
+
+/// A fetched page, before truncation.
+#[derive(Debug, Clone)]
+struct Page {
+    title: Option<String>,
+    url: String,
+    text: String,
+    success: bool,
+}
+
+/// Page download and extraction tool.
+pub struct WebFetchTool {
+    http: HttpRequestTool,
+    max_tokens: usize,
+    max_download_bytes: usize,
+    cache_ttl: Duration,
+    extract_pdf: bool,
+    timeout: Duration,
+    cache: Mutex<HashMap<String, (Instant, Page)>>,
+}
+
+impl WebFetchTool {
+    pub fn new(config: WebFetchConfig) -> Self {
+        Self {
+            max_download_bytes: config.http.max_response_bytes,
+            timeout: Duration::from_secs(config.http.timeout_secs.max(1)),
+            http: HttpRequestTool::new(config.http),
+            max_tokens: config.max_tokens,
+            cache_ttl: config.cache_ttl,
+            extract_pdf: config.extract_pdf,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn cached(&self, url: &str) -> Option<Page> {
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (at, _)| at.elapsed() < self.cache_ttl);
+        cache.get(url).map(|(_, page)| page.clone())
+    }
+
+    /// Download and extract `url`; `Err` carries a refusal reason from the URL checks.
+    async fn fetch(&self, url: Url) -> Result<std::result::Result<Page, String>> {
+        let headers = serde_json::Map::new();
+        let response = match self.http.send(reqwest::Method::GET, url, &headers, None, self.timeout).await? {
+            Ok(response) => response,
+            Err(reason) => return Ok(Err(reason)),
+        };
+
+        let status = response.status();
+        let final_url = response.url().clone();
+        let content_type = response.headers().get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .to_lowercase();
+        let (body, truncated) = self.http.read_body(response).await?;
+
+        let mut page = if content_type.contains("pdf") || body.starts_with(b"%PDF") {
+            self.pdf_page(&body, truncated).await
+        } else if content_type.contains("html") || (content_type.is_empty() && looks_like_html(&body)) {
+            html_to_markdown(&String::from_utf8_lossy(&body), &final_url)
+        } else if content_type.is_empty() || content_type.starts_with("text/") || content_type.contains("json") || content_type.contains("xml") {
+            Page { title: None, url: String::new(), text: String::from_utf8_lossy(&body).into_owned(), success: true }
+        } else {
+            Page {
+                title: None,
+                url: String::new(),
+                text: format!("[{content_type} content, {} bytes — not text]", body.len()),
+                success: true,
+            }
+        };
+        if page.url.is_empty() {
+            page.url = final_url.to_string();
+        }
+        if truncated && page.success {
+            page.text.push_str(&format!("\n\n[download stopped at {} bytes]", self.max_download_bytes));
+        }
+        if !status.is_success() {
+            page.text = format!("HTTP {status}\n\n{}", page.text);
+            page.success = false;
+        }
+        Ok(Ok(page))
+    }
+
+    async fn pdf_page(&self, body: &[u8], truncated: bool) -> Page {
+        let size = body.len();
+        let summary = |note: &str| Page {
+            title: None,
+            url: String::new(),
+            text: format!("[PDF document, {size} bytes{}. {note}]", if truncated { " (partial download)" } else { "" }),
+            success: true,
+        };
+        if !self.extract_pdf {
+            return summary("Text extraction is off (tools.web_fetch.extract_pdf)");
+        }
+        if truncated {
+            return summary("Too large to extract text");
+        }
+        let bytes = body.to_vec();
+        match tokio::task::spawn_blocking(move || pdf_extract::extract_text_from_mem(&bytes)).await {
+            Ok(Ok(text)) => Page { title: None, url: String::new(), text, success: true },
+            Ok(Err(e)) => summary(&format!("Text extraction failed: {e}")),
+            Err(e) => summary(&format!("Text extraction failed: {e}")),
+        }
+    }
+}
+
+fn looks_like_html(body: &[u8]) -> bool {
+    let head = String::from_utf8_lossy(&body[..body.len().min(512)]).to_lowercase();
+    head.contains("<html") || head.contains("<!doctype html")
+}
+
+/// Cut `text` to about `max_tokens` tokens, at a line or word break when possible.
+fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    let total = bizclaw_core::tokens::estimate_tokens(text);
+    if total <= max_tokens {
+        return text.to_string();
+    }
+    // Longest prefix (on a char boundary) that fits the budget.
+    let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).collect();
+    let (mut lo, mut hi) = (0, boundaries.len());
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        if bizclaw_core::tokens::estimate_tokens(&text[..boundaries[mid - 1]]) <= max_tokens {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    let end = if lo == 0 { 0 } else { boundaries[lo - 1] };
+    let cut = &text[..end];
+    let cut = match cut.rfind('\n').or_else(|| cut.rfind(' ')) {
+        Some(pos) if pos > end / 2 => &cut[..pos],
+        _ => cut,
+    };
+    format!(
+        "{}\n\n[content truncated: showing ~{} of ~{total} tokens]",
+        cut.trim_end(),
+        bizclaw_core::tokens::estimate_tokens(cut)
+    )
+}
+
+/// Builds the markdown output, tracking the block structure it's inside.
+#[derive(Default)]
+struct Writer {
+    out: String,
+    pre: usize,
+    list_depth: usize,
+    /// Start of the open `<a>` in `out`, and its target.
+    link: Option<(usize, Option<String>)>,
+    in_cell: bool,
+    row_cells: usize,
+    row_has_th: bool,
+    table_header_done: bool,
+}
+
+impl Writer {
+    fn newlines(&mut self, n: usize) {
+        if self.in_cell {
+            self.space();
+            return;
+        }
+        while self.out.ends_with(' ') {
+            self.out.pop();
+        }
+        if self.out.is_empty() {
+            return;
+        }
+        let have = self.out.len() - self.out.trim_end_matches('\n').len();
+        for _ in have..n {
+            self.out.push('\n');
+        }
+    }
+
+    fn space(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with([' ', '\n', '[', '|']) {
+            self.out.push(' ');
+        }
+    }
+
+    fn text(&mut self, raw: &str) {
+        let text = decode_entities(raw);
+        if self.pre > 0 {
+            self.out.push_str(&text);
+            return;
+        }
+        if text.starts_with(char::is_whitespace) {
+            self.space();
+        }
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if words.is_empty() {
+            return;
+        }
+        self.out.push_str(&words.join(" "));
+        if text.ends_with(char::is_whitespace) {
+            self.out.push(' ');
+        }
+    }
+
+    fn open(&mut self, name: &str, attrs: &str, base: &Url) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.newlines(2);
+                let level = name[1..].parse().unwrap_or(1);
+                self.out.push_str(&"#".repeat(level));
+                self.out.push(' ');
+            }
+            "p" | "blockquote" | "figure" | "dl" => self.newlines(2),
+            "div" | "section" | "article" | "main" | "header" | "dt" | "dd" | "figcaption" => self.newlines(1),
+            "br" => {
+                if self.in_cell { self.space() } else { self.out.push('\n') }
+            }
+            "hr" => {
+                self.newlines(2);
+                self.out.push_str("---");
+                self.newlines(2);
+            }
+            "ul" | "ol" => {
+                self.newlines(if self.list_depth == 0 { 2 } else { 1 });
+                self.list_depth += 1;
+            }
+            "li" => {
+                self.newlines(1);
+                self.out.push_str(&"  ".repeat(self.list_depth.saturating_sub(1)));
+                self.out.push_str("- ");
+            }
+            "table" => {
+                self.newlines(2);
+                self.table_header_done = false;
+            }
+            "tr" => {
+                self.newlines(1);
+                self.out.push('|');
+                self.row_cells = 0;
+                self.row_has_th = false;
+            }
+            "td" | "th" => {
+                self.in_cell = true;
+                self.row_cells += 1;
+                self.row_has_th |= name == "th";
+                self.out.push(' ');
+            }
+            "pre" => {
+                self.newlines(2);
+                self.out.push_str("```\n");
+                self.pre += 1;
+            }
+            "code" if self.pre == 0 => self.out.push('`'),
+            "a" => {
+                let href = attr(attrs, "href")
+                    .filter(|h| !h.starts_with('#') && !h.to_lowercase().starts_with("javascript:"))
+                    .and_then(|h| base.join(&h).ok())
+                    .map(|u| u.to_string());
+                self.space();
+                self.link = Some((self.out.len(), href));
+                self.out.push('[');
+            }
+            _ => {}
+        }
+    }
+
+    fn close(&mut self, name: &str) {
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "p" | "blockquote" | "figure" | "dl" | "table" => self.newlines(2),
+            "div" | "section" | "article" | "main" | "header" | "dt" | "dd" | "figcaption" | "li" => self.newlines(1),
+            "ul" | "ol" => {
+                self.list_depth = self.list_depth.saturating_sub(1);
+                self.newlines(if self.list_depth == 0 { 2 } else { 1 });
+            }
+            "td" | "th" => {
+                while self.out.ends_with(' ') {
+                    self.out.pop();
+                }
+                self.out.push_str(" |");
+                self.in_cell = false;
+            }
+            "tr" => {
+                self.in_cell = false;
+                if !self.table_header_done {
+                    self.table_header_done = true;
+                    if self.row_has_th && self.row_cells > 0 {
+                        self.out.push_str("\n|");
+                        self.out.push_str(&" --- |".repeat(self.row_cells));
+                    }
+                }
+                self.newlines(1);
+            }
+            "pre" => {
+                self.pre = self.pre.saturating_sub(1);
+                if !self.out.ends_with('\n') {
+                    self.out.push('\n');
+                }
+                self.out.push_str("```");
+                self.newlines(2);
+            }
+            "code" if self.pre == 0 => self.out.push('`'),
+            "a" => {
+                let Some((start, href)) = self.link.take() else { return };
+                let label = self.out[start + 1..].trim().to_string();
+                match href {
+                    _ if label.is_empty() => self.out.truncate(start),
+                    Some(href) => {
+                        self.out.truncate(start);
+                        self.out.push_str(&format!("[{label}]({href})"));
+                    }
+                    None => {
+                        self.out.remove(start);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(self) -> String {
+        let mut text = String::new();
+        let mut blank = 0;
+        for line in self.out.lines() {
+            let line = line.trim_end();
+            if line.is_empty() {
+                blank += 1;
+                if blank > 1 { continue; }
+            } else {
+                blank = 0;
+            }
+            text.push_str(line);
+            text.push('\n');
+        }
+        text.trim().to_string()
+    }
+}
+
+/// Convert an HTML document to markdown-ish text.
+fn html_to_markdown(html: &str, base: &Url) -> Page {
+    let lower = html.to_ascii_lowercase();
+    let mut w = Writer::default();
+    let mut title = None;
+    let mut canonical = None;
+    let mut skip_depth = 0usize;
+    let mut pos = 0;
+
+    while pos < html.len() {
+        let Some(lt) = html[pos..].find('<').map(|i| pos + i) else {
+            if skip_depth == 0 { w.text(&html[pos..]); }
+            break;
+        };
+        if lt > pos && skip_depth == 0 {
+            w.text(&html[pos..lt]);
+        }
+        if !html[lt + 1..].starts_with(|c: char| c.is_ascii_alphabetic() || matches!(c, '/' | '!' | '?')) {
+            if skip_depth == 0 { w.text("<"); }
+            pos = lt + 1;
+            continue;
+        }
+        if lower[lt..].starts_with("<!--") {
+            pos = lower[lt..].find("-->").map_or(html.len(), |i| lt + i + 3);
+            continue;
+        }
+        let Some(gt) = tag_end(html, lt) else { break };
+        let tag = &html[lt + 1..gt];
+        pos = gt + 1;
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+
+        let closing = tag.starts_with('/');
+        let tag = tag.trim_start_matches('/');
+        let name_end = tag.find(|c: char| c.is_whitespace() || c == '/').unwrap_or(tag.len());
+        let name = tag[..name_end].to_lowercase();
+        let attrs = &tag[name_end..];
+
+        if !closing && RAW_SKIP.contains(&name.as_str()) {
+            let close = format!("</{name}");
+            let end = lower[pos..].find(&close).map_or(html.len(), |i| pos + i);
+            if name == "title" && title.is_none() {
+                title = Some(decode_entities(html[pos..end].trim()).split_whitespace().collect::<Vec<_>>().join(" "));
+            }
+            pos = html[end..].find('>').map_or(html.len(), |i| end + i + 1);
+            continue;
+        }
+        if name == "link" && canonical.is_none()
+            && attr(attrs, "rel").is_some_and(|r| r.eq_ignore_ascii_case("canonical"))
+        {
+            canonical = attr(attrs, "href").and_then(|h| base.join(&h).ok()).map(|u| u.to_string());
+        }
+        if SKIP.contains(&name.as_str()) {
+            if closing {
+                skip_depth = skip_depth.saturating_sub(1);
+            } else if !tag.ends_with('/') {
+                skip_depth += 1;
+            }
+            continue;
+        }
+        if skip_depth > 0 {
+            continue;
+        }
+        if closing {
+            w.close(&name);
+        } else {
+            w.open(&name, attrs, base);
+            if tag.ends_with('/') && !VOID.contains(&name.as_str()) {
+                w.close(&name);
+            }
+        }
+    }
+
+    Page {
+        title: title.filter(|t| !t.is_empty()),
+        url: canonical.unwrap_or_default(),
+        text: w.finish(),
+        success: true,
+    }
+}
+
+/// Index of the `>` closing the tag opened at `lt`, ignoring `>` inside quoted attributes.
+fn tag_end(html: &str, lt: usize) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in html[lt..].char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '>') => return Some(lt + i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Value of attribute `name` in a tag's attribute text.
+fn attr(attrs: &str, name: &str) -> Option<String> {
+    let mut rest = attrs;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == '/');
+        if rest.is_empty() {
+            return None;
+        }
+        let key_end = rest.find(|c: char| c.is_whitespace() || c == '=' || c == '/').unwrap_or(rest.len());
+        let key = &rest[..key_end];
+        rest = rest[key_end..].trim_start();
+        let value = if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            let (value, remaining) = match after.chars().next() {
+                Some(q @ ('"' | '\'')) => {
+                    let end = after[1..].find(q).map_or(after.len(), |i| i + 1);
+                    (&after[1..end], after.get(end + 1..).unwrap_or(""))
+                }
+                _ => {
+                    let end = after.find(char::is_whitespace).unwrap_or(after.len());
+                    (&after[..end], &after[end..])
+                }
+            };
+            rest = remaining;
+            Some(value)
+        } else {
+            None
+        };
+        if key.eq_ignore_ascii_case(name) {
+            return Some(decode_entities(value.unwrap_or("")));
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest[1..].find(';').filter(|&i| i <= 10).and_then(|i| {
+            let entity = &rest[1..i + 1];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                "mdash" => Some('—'),
+                "ndash" => Some('–'),
+                "hellip" => Some('…'),
+                "copy" => Some('©'),
+                _ => match entity.strip_prefix('#') {
+                    Some(hex) if hex.starts_with(['x', 'X']) => u32::from_str_radix(&hex[1..], 16).ok().and_then(char::from_u32),
+                    Some(dec) => dec.parse().ok().and_then(char::from_u32),
+                    None => None,
+                },
+            };
+            c.map(|c| (c, i + 2))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[async_trait]
+impl Tool for WebFetchTool {
+    fn name(&self) -> &str { "web_fetch" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "web_fetch".into(),
+            description: "Fetch a web page and return its title, canonical URL, and readable text (markdown). Use after web_search to read a result.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "url": { "type": "string", "description": "Full http(s) URL" },
+                    "max_tokens": { "type": "integer", "description": "Approximate length limit for the returned text" }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let url_str = args["url"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'url'".into()))?;
+        let url = Url::parse(url_str)
+            .map_err(|e| BizClawError::Tool(format!("Invalid url '{url_str}': {e}")))?;
+        let max_tokens = args["max_tokens"].as_u64()
+            .map(|n| (n as usize).clamp(100, self.max_tokens))
+            .unwrap_or(self.max_tokens);
+
+        let page = match self.cached(url.as_str()) {
+            Some(page) => page,
+            None => match self.fetch(url.clone()).await? {
+                Ok(page) => {
+                    self.cache.lock().unwrap().insert(url.to_string(), (Instant::now(), page.clone()));
+                    page
+                }
+                Err(reason) => {
+                    tracing::warn!(target: "bizclaw::audit", tool = "web_fetch", url = url_str, "denied: {reason}");
+                    return Ok(ToolResult::failure("permission_denied", serde_json::json!({
+                        "error": "permission_denied",
+                        "tool": "web_fetch",
+                        "subject": url_str,
+                        "reason": reason,
+                    }).to_string()));
+                }
+            },
+        };
+
+        let mut output = String::new();
+        if let Some(title) = &page.title {
+            output.push_str(&format!("Title: {title}\n"));
+        }
+        output.push_str(&format!("URL: {}\n\n", page.url));
+        output.push_str(&truncate_to_tokens(&page.text, max_tokens));
+
+        let result = if page.success {
+            ToolResult::ok(output)
+        } else {
+            ToolResult::failure("http_status", output)
+        };
+        Ok(result.with_data(serde_json::json!({ "url": page.url, "title": page.title })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head>
+  <title>Pricing &amp; Plans</title>
+  <link rel="canonical" href="/pricing">
+  <script>var x = "<p>not text</p>";</script>
+  <style>p { color: red }</style>
+</head><body>
+  <nav><a href="/">Home</a> <a href="/about">About</a></nav>
+  <h1>Plans</h1>
+  <p>Pick a <a href="https://example.com/plans">plan</a> that fits.</p>
+  <table>
+    <tr><th>Plan</th><th>Price</th></tr>
+    <tr><td>Free</td><td>$0</td></tr>
+  </table>
+  <ul><li>One</li><li>Two</li></ul>
+  <footer>Copyright</footer>
+</body></html>"#;
+
+    #[test]
+    fn test_html_to_markdown() {
+        let base = Url::parse("https://example.com/pricing?ref=x").unwrap();
+        let page = html_to_markdown(PAGE, &base);
+        assert_eq!(page.title.as_deref(), Some("Pricing & Plans"));
+        assert_eq!(page.url, "https://example.com/pricing");
+        assert!(page.text.starts_with("# Plans"), "{}", page.text);
+        assert!(page.text.contains("Pick a [plan](https://example.com/plans) that fits."));
+        assert!(page.text.contains("| Plan | Price |\n| --- | --- |\n| Free | $0 |"), "{}", page.text);
+        assert!(page.text.contains("- One\n- Two"));
+        for dropped in ["not text", "color: red", "Home", "About", "Copyright"] {
+            assert!(!page.text.contains(dropped), "{dropped} in {}", page.text);
+        }
+    }
+
+    #[test]
+    fn test_truncate_to_tokens() {
+        let text = "word ".repeat(1000);
+        assert_eq!(truncate_to_tokens("short", 100), "short");
+        let cut = truncate_to_tokens(&text, 100);
+        assert!(cut.contains("[content truncated"));
+        assert!(cut.len() < 500);
+    }
+
+    #[test]
+    fn test_decode_entities() {
+        assert_eq!(decode_entities("a &lt;b&gt; &#233;&#x41; & c &bogus;"), "a <b> éA & c &bogus;");
+    }
+
+    static HITS: AtomicUsize = AtomicUsize::new(0);
+
+    #[tokio::test]
+    async fn test_fetch_guards_and_cache() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let _ = stream.read(&mut buf).await;
+                HITS.fetch_add(1, Ordering::SeqCst);
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{PAGE}",
+                    PAGE.len()
+                );
+                let _ = stream.write_all(reply.as_bytes()).await;
+            }
+        });
+        let url = format!("http://127.0.0.1:{port}/pricing");
+        let args = serde_json::json!({ "url": url }).to_string();
+
+        let denied = WebFetchTool::new(WebFetchConfig::default()).execute(&args).await.unwrap();
+        assert!(!denied.success);
+        assert!(denied.output.contains("permission_denied"));
+
+        let mut config = WebFetchConfig::default();
+        config.http.allowed_hosts = vec!["127.0.0.1".into()];
+        let tool = WebFetchTool::new(config);
+        let first = tool.execute(&args).await.unwrap();
+        assert!(first.success, "{}", first.output);
+        assert!(first.output.starts_with(&format!("Title: Pricing & Plans\nURL: http://127.0.0.1:{port}/pricing\n\n# Plans")));
+        let second = tool.execute(&args).await.unwrap();
+        assert_eq!(first.output, second.output);
+        assert_eq!(HITS.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pdf_reported_without_extraction() {
+        let tool = WebFetchTool::new(WebFetchConfig::default());
+        let page = tool.pdf_page(b"%PDF-1.4 fake", false).await;
+        assert!(page.text.contains("PDF document, 13 bytes"));
+        assert!(page.text.contains("extract_pdf"));
+    }
+}
@@ -1,16 +1,191 @@
 //! Web Search Tool — enables the agent to search the internet.
 //!
-//! Uses DuckDuckGo Instant Answer API (no API key required).
+//! Backends: DuckDuckGo HTML search (no API key required), Brave Search API,
+//! a self-hosted SearxNG instance, and SerpAPI. Each is normalized into the
+//! same [`SearchResult`]s and output; when one fails (error, rate limit,
+//! unexpected response) the next configured backend is tried.
 
 use async_trait::async_trait;
+use bizclaw_core::config::WebSearchBackendConfig;
 use bizclaw_core::traits::Tool;
 use bizclaw_core::types::{ToolDefinition, ToolResult};
 use bizclaw_core::error::Result;
+use serde::Serialize;
 
-pub struct WebSearchTool;
+/// Web search configuration.
+#[derive(Debug, Clone)]
+pub struct WebSearchConfig {
+    /// "duckduckgo", "brave", "searxng" or "serpapi".
+    pub backend: String,
+    pub api_key: String,
+    /// SearxNG instance, or a replacement API endpoint for the others.
+    pub base_url: String,
+    pub max_results: usize,
+    pub safe_search: bool,
+    /// e.g. "vi-VN"; empty leaves it to the backend.
+    pub locale: String,
+    pub timeout_secs: u64,
+    /// Tried in order when the backends before them fail.
+    pub fallbacks: Vec<WebSearchBackendConfig>,
+}
+
+impl Default for WebSearchConfig {
+    fn default() -> Self {
+        Self {
+            backend: "duckduckgo".into(),
+            api_key: String::new(),
+            base_url: String::new(),
+            max_results: 5,
+            safe_search: true,
+            locale: String::new(),
+            timeout_secs: 10,
+            fallbacks: Vec::new(),
+        }
+    }
+}
+
+impl From<&bizclaw_core::config::WebSearchToolConfig> for WebSearchConfig {
+    fn from(cfg: &bizclaw_core::config::WebSearchToolConfig) -> Self {
+        Self {
+            backend: cfg.backend.clone(),
+            api_key: cfg.api_key.clone(),
+            base_url: cfg.base_url.clone(),
+            max_results: cfg.max_results,
+            safe_search: cfg.safe_search,
+            locale: cfg.locale.clone(),
+            timeout_secs: cfg.timeout_secs,
+            fallbacks: cfg.fallbacks.clone(),
+        }
+    }
+}
+
+impl WebSearchConfig {
+    fn backends(&self) -> impl Iterator<Item = WebSearchBackendConfig> + '_ {
+        let primary = WebSearchBackendConfig {
+            backend: self.backend.clone(),
+            api_key: self.api_key.clone(),
+            base_url: self.base_url.clone(),
+        };
+        std::iter::once(primary).chain(self.fallbacks.iter().cloned())
+    }
+
+    /// Language and region parts of `locale` ("vi-VN" → "vi", "VN").
+    fn language_region(&self) -> (Option<&str>, Option<&str>) {
+        let mut parts = self.locale.split(['-', '_']).filter(|p| !p.is_empty());
+        (parts.next(), parts.next())
+    }
+}
+
+/// One search hit, the same for every backend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+}
+
+pub struct WebSearchTool {
+    config: WebSearchConfig,
+}
+
+impl Default for WebSearchTool {
+    fn default() -> Self {
+        Self::new(WebSearchConfig::default())
+    }
+}
 
 impl WebSearchTool {
-    pub fn new() -> Self { Self }
+    pub fn new(config: WebSearchConfig) -> Self {
+        Self { config }
+    }
+
+    /// Search one backend.
+    async fn search(
+        &self,
+        client: &reqwest::Client,
+        backend: &WebSearchBackendConfig,
+        query: &str,
+        max: usize,
+    ) -> std::result::Result<Vec<SearchResult>, String> {
+        let cfg = &self.config;
+        let (language, region) = cfg.language_region();
+        let endpoint = |default: &str| {
+            Some(backend.base_url.trim_end_matches('/')).filter(|u| !u.is_empty()).unwrap_or(default).to_string()
+        };
+
+        let request = match backend.backend.as_str() {
+            "duckduckgo" => {
+                let mut params = vec![("q", query.to_string()), ("kp", if cfg.safe_search { "1" } else { "-2" }.into())];
+                if let (Some(lang), Some(region)) = (language, region) {
+                    params.push(("kl", format!("{}-{}", region.to_lowercase(), lang.to_lowercase())));
+                }
+                client.get(endpoint("https://html.duckduckgo.com/html/")).query(&params)
+            }
+            "brave" => {
+                let mut params = vec![
+                    ("q", query.to_string()),
+                    ("count", max.min(20).to_string()),
+                    ("safesearch", if cfg.safe_search { "strict" } else { "off" }.into()),
+                ];
+                params.extend(language.map(|l| ("search_lang", l.to_lowercase())));
+                params.extend(region.map(|r| ("country", r.to_uppercase())));
+                client.get(endpoint("https://api.search.brave.com/res/v1/web/search"))
+                    .header("X-Subscription-Token", &backend.api_key)
+                    .header("Accept", "application/json")
+                    .query(&params)
+            }
+            "searxng" => {
+                let mut params = vec![
+                    ("q", query.to_string()),
+                    ("format", "json".into()),
+                    ("safesearch", if cfg.safe_search { "2" } else { "0" }.into()),
+                ];
+                if !cfg.locale.is_empty() {
+                    params.push(("language", cfg.locale.clone()));
+                }
+                client.get(format!("{}/search", endpoint(""))).query(&params)
+            }
+            "serpapi" => {
+                let mut params = vec![
+                    ("engine", "google".to_string()),
+                    ("q", query.to_string()),
+                    ("num", max.to_string()),
+                    ("safe", if cfg.safe_search { "active" } else { "off" }.into()),
+                    ("api_key", backend.api_key.clone()),
+                ];
+                params.extend(language.map(|l| ("hl", l.to_lowercase())));
+                params.extend(region.map(|r| ("gl", r.to_lowercase())));
+                client.get(endpoint("https://serpapi.com/search.json")).query(&params)
+            }
+            other => return Err(format!("unknown backend \"{other}\"")),
+        };
+
+        let response = request.send().await.map_err(|e| format!("request failed: {e}"))?;
+        let status = response.status();
+        let body = response.text().await.map_err(|e| format!("read failed: {e}"))?;
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err("rate limited (HTTP 429)".into());
+        }
+        if !status.is_success() {
+            let detail: String = body.chars().take(200).collect();
+            return Err(format!("HTTP {status}: {detail}"));
+        }
+
+        if backend.backend == "duckduckgo" {
+            return Ok(parse_ddg_results(&body, max));
+        }
+        let json: serde_json::Value = serde_json::from_str(&body)
+            .map_err(|e| format!("invalid JSON response: {e}"))?;
+        let mut results = match backend.backend.as_str() {
+            "brave" => parse_brave(&json),
+            "searxng" => parse_searxng(&json),
+            _ => parse_serpapi(&json),
+        }?;
+        results.truncate(max);
+        Ok(results)
+    }
 }
 
 #[async_trait]
@@ -41,71 +216,337 @@ impl Tool for WebSearchTool {
 
         let max_results: usize = args["max_results"].as_u64()
             .map(|v| v as usize)
-            .unwrap_or(5);
+            .unwrap_or(self.config.max_results);
 
-        // Use DuckDuckGo HTML search (no API key needed)
         let client = reqwest::Client::builder()
             .user_agent("BizClaw/1.0")
-            .timeout(std::time::Duration::from_secs(10))
+            .timeout(std::time::Duration::from_secs(self.config.timeout_secs))
             .build()
             .map_err(|e| bizclaw_core::error::BizClawError::Tool(format!("HTTP error: {e}")))?;
 
-        let url = format!("https://html.duckduckgo.com/html/?q={}", urlencoding::encode(query));
-        let response = client.get(&url).send().await
-            .map_err(|e| bizclaw_core::error::BizClawError::Tool(format!("Search failed: {e}")))?;
-
-        let html = response.text().await
-            .map_err(|e| bizclaw_core::error::BizClawError::Tool(format!("Read failed: {e}")))?;
+        let mut failures = Vec::new();
+        for backend in self.config.backends() {
+            match self.search(&client, &backend, query, max_results).await {
+                Ok(results) => {
+                    let output = format_results(query, &results);
+                    return Ok(ToolResult::ok(output).with_data(serde_json::json!({
+                        "backend": backend.backend,
+                        "results": results,
+                    })));
+                }
+                Err(e) => {
+                    tracing::warn!("web_search: {} failed: {e}", backend.backend);
+                    failures.push(format!("{}: {e}", backend.backend));
+                }
+            }
+        }
+        Ok(ToolResult::failure("search_failed", format!("Search failed: {}", failures.join("; "))))
+    }
+}
 
-        let results = parse_ddg_results(&html, max_results);
+fn format_results(query: &str, results: &[SearchResult]) -> String {
+    if results.is_empty() {
+        return format!("No results found for: {query}");
+    }
+    let mut out = format!("Search results for \"{query}\":\n\n");
+    for (i, r) in results.iter().enumerate() {
+        out.push_str(&format!("{}. {}\n   {}\n   {}\n", i + 1, r.title, r.snippet, r.url));
+        if let Some(published) = &r.published {
+            out.push_str(&format!("   Published: {published}\n"));
+        }
+        out.push('\n');
+    }
+    out
+}
 
-        let output = if results.is_empty() {
-            format!("No results found for: {query}")
-        } else {
-            let mut out = format!("Search results for \"{query}\":\n\n");
-            for (i, r) in results.iter().enumerate() {
-                out.push_str(&format!("{}. {}\n   {}\n   {}\n\n", i + 1, r.0, r.1, r.2));
-            }
-            out
-        };
+/// Build a result from JSON fields, skipping hits without a title or URL.
+fn result(title: &serde_json::Value, url: &serde_json::Value, snippet: &serde_json::Value, published: &serde_json::Value) -> Option<SearchResult> {
+    let title = clean_text(title.as_str()?);
+    let url = url.as_str()?.trim().to_string();
+    if title.is_empty() || url.is_empty() {
+        return None;
+    }
+    Some(SearchResult {
+        title,
+        url,
+        snippet: clean_text(snippet.as_str().unwrap_or("")),
+        published: published.as_str().map(str::trim).filter(|p| !p.is_empty()).map(String::from),
+    })
+}
 
-        Ok(ToolResult {
-            tool_call_id: String::new(),
-            output,
-            success: true,
+/// Brave Search API: `web.results[]` (absent when nothing matched).
+fn parse_brave(json: &serde_json::Value) -> std::result::Result<Vec<SearchResult>, String> {
+    if json["type"] == "ErrorResponse" {
+        return Err(format!("Brave error: {}", json["error"]["detail"].as_str().unwrap_or("unknown")));
+    }
+    let hits = json["web"]["results"].as_array().map(Vec::as_slice).unwrap_or_default();
+    Ok(hits.iter()
+        .filter_map(|h| {
+            let published = if h["page_age"].is_string() { &h["page_age"] } else { &h["age"] };
+            result(&h["title"], &h["url"], &h["description"], published)
         })
+        .collect())
+}
+
+/// SearxNG JSON API: `results[]`.
+fn parse_searxng(json: &serde_json::Value) -> std::result::Result<Vec<SearchResult>, String> {
+    let hits = json["results"].as_array()
+        .ok_or("unexpected SearxNG response (no results list)")?;
+    Ok(hits.iter()
+        .filter_map(|h| result(&h["title"], &h["url"], &h["content"], &h["publishedDate"]))
+        .collect())
+}
+
+/// SerpAPI (Google engine): `organic_results[]`, or `error`.
+fn parse_serpapi(json: &serde_json::Value) -> std::result::Result<Vec<SearchResult>, String> {
+    if let Some(error) = json["error"].as_str() {
+        // SerpAPI reports an empty result page as an error too.
+        if error.contains("hasn't returned any results") {
+            return Ok(Vec::new());
+        }
+        return Err(format!("SerpAPI error: {error}"));
     }
+    let hits = json["organic_results"].as_array().map(Vec::as_slice).unwrap_or_default();
+    Ok(hits.iter()
+        .filter_map(|h| result(&h["title"], &h["link"], &h["snippet"], &h["date"]))
+        .collect())
 }
 
-fn parse_ddg_results(html: &str, max: usize) -> Vec<(String, String, String)> {
+fn parse_ddg_results(html: &str, max: usize) -> Vec<SearchResult> {
     let mut results = Vec::new();
 
     for segment in html.split("class=\"result__a\"").skip(1).take(max) {
-        let title = extract_between(segment, ">", "</a>")
-            .unwrap_or_default()
-            .replace("<b>", "").replace("</b>", "");
+        let title = clean_text(&extract_between(segment, ">", "</a>").unwrap_or_default());
 
         let url = extract_between(segment, "href=\"", "\"")
+            .map(|href| ddg_target(&href))
             .unwrap_or_default();
 
         let snippet = if let Some(snip_seg) = segment.split("class=\"result__snippet\"").nth(1) {
-            extract_between(snip_seg, ">", "</")
-                .unwrap_or_default()
-                .replace("<b>", "").replace("</b>", "")
+            clean_text(&extract_between(snip_seg, ">", "</a>").unwrap_or_default())
         } else {
             String::new()
         };
 
         if !title.is_empty() {
-            results.push((title.trim().into(), snippet.trim().into(), url.trim().into()));
+            results.push(SearchResult { title, url: url.trim().into(), snippet, published: None });
         }
     }
     results
 }
 
+/// The target of a DuckDuckGo redirect link (`//duckduckgo.com/l/?uddg=...`).
+fn ddg_target(href: &str) -> String {
+    let href = href.replace("&amp;", "&");
+    href.split_once("uddg=")
+        .map(|(_, rest)| rest.split('&').next().unwrap_or(rest))
+        .and_then(|encoded| urlencoding::decode(encoded).ok())
+        .map(|url| url.into_owned())
+        .unwrap_or(href)
+}
+
+/// Text without HTML tags (engines highlight matches with `<b>`/`<strong>`)
+/// and with common entities decoded.
+fn clean_text(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+        .trim()
+        .to_string()
+}
+
 fn extract_between(text: &str, start: &str, end: &str) -> Option<String> {
     let start_idx = text.find(start)? + start.len();
     let remaining = &text[start_idx..];
     let end_idx = remaining.find(end)?;
     Some(remaining[..end_idx].to_string())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_brave() {
+        let json = serde_json::json!({
+            "type": "search",
+            "web": { "results": [
+                {
+                    "title": "Rust <strong>Programming</strong> Language",
+                    "url": "https://www.rust-lang.org/",
+                    "description": "A language empowering everyone to build reliable &amp; efficient software.",
+                    "page_age": "2026-02-01T00:00:00"
+                },
+                { "title": "The Book", "url": "https://doc.rust-lang.org/book/", "description": "Learn Rust", "age": "3 days ago" },
+                { "title": "", "url": "https://skipped.example" }
+            ]}
+        });
+        let results = parse_brave(&json).unwrap();
+        assert_eq!(results, [
+            SearchResult {
+                title: "Rust Programming Language".into(),
+                url: "https://www.rust-lang.org/".into(),
+                snippet: "A language empowering everyone to build reliable & efficient software.".into(),
+                published: Some("2026-02-01T00:00:00".into()),
+            },
+            SearchResult {
+                title: "The Book".into(),
+                url: "https://doc.rust-lang.org/book/".into(),
+                snippet: "Learn Rust".into(),
+                published: Some("3 days ago".into()),
+            },
+        ]);
+        assert!(parse_brave(&serde_json::json!({ "type": "search" })).unwrap().is_empty());
+        let err = parse_brave(&serde_json::json!({ "type": "ErrorResponse", "error": { "detail": "bad token" } }));
+        assert_eq!(err.unwrap_err(), "Brave error: bad token");
+    }
+
+    #[test]
+    fn test_parse_searxng() {
+        let json = serde_json::json!({
+            "query": "rust",
+            "results": [
+                { "title": "Rust", "url": "https://www.rust-lang.org/", "content": "Fast, reliable", "engine": "bing" },
+                { "title": "Rust 1.90 released", "url": "https://blog.rust-lang.org/", "content": "Release notes", "publishedDate": "2026-01-05T00:00:00" }
+            ]
+        });
+        let results = parse_searxng(&json).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].snippet, "Fast, reliable");
+        assert_eq!(results[0].published, None);
+        assert_eq!(results[1].published.as_deref(), Some("2026-01-05T00:00:00"));
+        assert!(parse_searxng(&serde_json::json!({ "error": "x" })).is_err());
+    }
+
+    #[test]
+    fn test_parse_serpapi() {
+        let json = serde_json::json!({
+            "search_metadata": { "status": "Success" },
+            "organic_results": [
+                { "position": 1, "title": "Rust", "link": "https://www.rust-lang.org/", "snippet": "Rust is fast", "date": "Jan 5, 2026" },
+                { "position": 2, "title": "Rust (video game)", "link": "https://rust.facepunch.com/", "snippet": "Survive" }
+            ]
+        });
+        let results = parse_serpapi(&json).unwrap();
+        assert_eq!(results[0].url, "https://www.rust-lang.org/");
+        assert_eq!(results[0].published.as_deref(), Some("Jan 5, 2026"));
+        assert_eq!(results[1].title, "Rust (video game)");
+
+        let none = serde_json::json!({ "error": "Google hasn't returned any results for this query." });
+        assert!(parse_serpapi(&none).unwrap().is_empty());
+        let bad_key = serde_json::json!({ "error": "Invalid API key." });
+        assert_eq!(parse_serpapi(&bad_key).unwrap_err(), "SerpAPI error: Invalid API key.");
+    }
+
+    #[test]
+    fn test_parse_ddg() {
+        let html = r#"<div class="result"><a rel="nofollow" class="result__a" href="//duckduckgo.com/l/?uddg=https%3A%2F%2Fwww.rust-lang.org%2F&amp;rut=abc"><b>Rust</b> Programming Language</a>
+            <a class="result__snippet" href="x">A language empowering <b>everyone</b></a></div>"#;
+        assert_eq!(parse_ddg_results(html, 5), [SearchResult {
+            title: "Rust Programming Language".into(),
+            url: "https://www.rust-lang.org/".into(),
+            snippet: "A language empowering everyone".into(),
+            published: None,
+        }]);
+    }
+
+    #[test]
+    fn test_format_results() {
+        let results = [SearchResult {
+            title: "Rust".into(),
+            url: "https://www.rust-lang.org/".into(),
+            snippet: "Fast".into(),
+            published: Some("2026-01-05".into()),
+        }];
+        assert_eq!(
+            format_results("rust", &results),
+            "Search results for \"rust\":\n\n1. Rust\n   Fast\n   https://www.rust-lang.org/\n   Published: 2026-01-05\n\n"
+        );
+        assert_eq!(format_results("rust", &[]), "No results found for: rust");
+    }
+
+    /// Search API stand-in: records each request head and answers with
+    /// `status` and `body`.
+    async fn search_api(status: u16, body: serde_json::Value) -> (String, tokio::sync::mpsc::UnboundedReceiver<String>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut request = Vec::new();
+                let mut buf = [0u8; 4096];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let n = stream.read(&mut buf).await.unwrap_or(0);
+                    if n == 0 { break }
+                    request.extend_from_slice(&buf[..n]);
+                }
+                let _ = tx.send(String::from_utf8_lossy(&request).into_owned());
+                let body = body.to_string();
+                let response = format!(
+                    "HTTP/1.1 {status} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+            }
+        });
+        (base, rx)
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_next_backend() {
+        let (brave, mut brave_requests) = search_api(429, serde_json::json!({ "type": "ErrorResponse" })).await;
+        let (searx, mut searx_requests) = search_api(200, serde_json::json!({ "results": [
+            { "title": "Rust", "url": "https://www.rust-lang.org/", "content": "Fast" }
+        ]})).await;
+        let tool = WebSearchTool::new(WebSearchConfig {
+            backend: "brave".into(),
+            api_key: "brave-key".into(),
+            base_url: brave,
+            locale: "vi-VN".into(),
+            fallbacks: vec![WebSearchBackendConfig { backend: "searxng".into(), api_key: String::new(), base_url: searx }],
+            ..Default::default()
+        });
+
+        let result = tool.execute(r#"{"query":"rust lang"}"#).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.data["backend"], "searxng");
+        assert_eq!(result.data["results"][0]["url"], "https://www.rust-lang.org/");
+        assert!(result.output.contains("1. Rust\n   Fast"), "{}", result.output);
+
+        let brave_request = brave_requests.recv().await.unwrap().to_lowercase();
+        assert!(brave_request.contains("x-subscription-token: brave-key"), "{brave_request}");
+        assert!(brave_request.contains("q=rust+lang") && brave_request.contains("safesearch=strict"));
+        assert!(brave_request.contains("search_lang=vi") && brave_request.contains("country=vn"));
+        let searx_request = searx_requests.recv().await.unwrap();
+        assert!(searx_request.starts_with("GET /search?") && searx_request.contains("format=json"), "{searx_request}");
+    }
+
+    #[tokio::test]
+    async fn test_all_backends_failing() {
+        let (serp, _requests) = search_api(200, serde_json::json!({ "error": "Invalid API key." })).await;
+        let tool = WebSearchTool::new(WebSearchConfig {
+            backend: "serpapi".into(),
+            api_key: "k".into(),
+            base_url: serp,
+            ..Default::default()
+        });
+        let result = tool.execute(r#"{"query":"rust"}"#).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.error_kind.as_deref(), Some("search_failed"));
+        assert_eq!(result.output, "Search failed: serpapi: SerpAPI error: Invalid API key.");
+    }
+}
@@ -0,0 +1,153 @@
+//! Zalo Media Tool — lets the agent send an image or file to the Zalo chat
+//! it is answering ("gửi ảnh sản phẩm cho khách").
+//!
+//! Images are sent as pictures and other files as attachments. Paths are
+//! checked against the autonomy policy, as the file tool's are.
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::{Channel, Tool};
+use bizclaw_core::types::{ThreadType, ToolDefinition, ToolResult};
+use bizclaw_security::allowlist::Allowlist;
+use std::sync::Arc;
+
+pub struct ZaloMediaTool {
+    channel: Arc<dyn Channel>,
+    /// Chat the conversation is in, where media goes.
+    thread_id: String,
+    thread_type: ThreadType,
+    allowlist: Allowlist,
+}
+
+impl ZaloMediaTool {
+    /// Send to `thread_id` on `channel` (the Zalo channel).
+    pub fn new(channel: Arc<dyn Channel>, thread_id: &str, thread_type: ThreadType, allowlist: Allowlist) -> Self {
+        Self { channel, thread_id: thread_id.to_string(), thread_type, allowlist }
+    }
+}
+
+#[async_trait]
+impl Tool for ZaloMediaTool {
+    fn name(&self) -> &str { "zalo_media" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "zalo_media".into(),
+            description: "Gửi ảnh hoặc file vào cuộc trò chuyện Zalo hiện tại. Ảnh hiển thị dạng hình, \
+                file khác gửi dạng tệp đính kèm.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "action": { "type": "string", "enum": ["send_image", "send_file"] },
+                    "path": { "type": "string", "description": "Local path of the image or file" },
+                    "caption": { "type": "string", "description": "Text sent before it (optional)" }
+                },
+                "required": ["action", "path"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(format!("Invalid arguments: {e}")))?;
+        let action = args["action"].as_str().unwrap_or("send_file");
+        if !matches!(action, "send_image" | "send_file") {
+            return Err(BizClawError::Tool(format!("Unknown action: {action}")));
+        }
+        let requested = args["path"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'path'".into()))?;
+        let path = match self.allowlist.check_path(requested) {
+            Ok(path) => path,
+            Err(denial) => {
+                denial.audit("zalo_media");
+                return Ok(ToolResult::failure("permission_denied", denial.to_json("zalo_media").to_string()));
+            }
+        };
+        if !path.is_file() {
+            return Err(BizClawError::Tool(format!("No file at '{requested}'")));
+        }
+        let image = infer::get_from_path(&path).ok().flatten().is_some_and(|t| t.mime_type().starts_with("image/"));
+        if action == "send_image" && !image {
+            return Ok(ToolResult::failure("invalid_input", format!("'{requested}' is not an image; use send_file")));
+        }
+
+        if let Some(caption) = args["caption"].as_str().filter(|c| !c.trim().is_empty()) {
+            self.channel.send(bizclaw_core::types::OutgoingMessage {
+                thread_id: self.thread_id.clone(),
+                content: caption.to_string(),
+                thread_type: self.thread_type.clone(),
+                reply_to: None,
+            }).await?;
+        }
+        self.channel.send_media(&self.thread_id, self.thread_type.clone(), &path).await?;
+        Ok(ToolResult::ok(format!("Sent {requested} to the chat")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bizclaw_core::types::{IncomingMessage, OutgoingMessage};
+    use std::path::{Path, PathBuf};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct FakeZalo {
+        sent: Mutex<Vec<String>>,
+        media: Mutex<Vec<(String, PathBuf)>>,
+    }
+
+    #[async_trait]
+    impl Channel for FakeZalo {
+        fn name(&self) -> &str { "zalo" }
+        async fn connect(&mut self) -> Result<()> { Ok(()) }
+        async fn disconnect(&mut self) -> Result<()> { Ok(()) }
+        fn is_connected(&self) -> bool { true }
+        async fn listen(&self) -> Result<Box<dyn tokio_stream::Stream<Item = IncomingMessage> + Send + Unpin>> {
+            Ok(Box::new(tokio_stream::empty()))
+        }
+        async fn send(&self, message: OutgoingMessage) -> Result<()> {
+            self.sent.lock().unwrap().push(message.content);
+            Ok(())
+        }
+        async fn send_media(&self, thread_id: &str, _thread_type: ThreadType, path: &Path) -> Result<()> {
+            self.media.lock().unwrap().push((thread_id.to_string(), path.to_path_buf()));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sends_to_the_chat_within_policy() {
+        let ws = std::env::temp_dir().join(format!("bizclaw-zalo-media-{}", uuid::Uuid::new_v4().simple()));
+        std::fs::create_dir_all(&ws).unwrap();
+        let image = ws.join("sp.png");
+        std::fs::write(&image, b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+        let quote = ws.join("quote.pdf");
+        std::fs::write(&quote, b"%PDF-1.7\n").unwrap();
+        let channel = Arc::new(FakeZalo::default());
+        let autonomy = bizclaw_core::config::AutonomyConfig::default();
+        let tool = ZaloMediaTool::new(
+            channel.clone(), "u1", ThreadType::Direct, Allowlist::new(&autonomy).with_workspace(ws.clone()),
+        );
+
+        let args = serde_json::json!({ "action": "send_image", "path": image, "caption": "Mẫu mới" });
+        let result = tool.execute(&args.to_string()).await.unwrap();
+        assert!(result.success, "{}", result.output);
+        assert_eq!(channel.sent.lock().unwrap().as_slice(), ["Mẫu mới"]);
+        assert_eq!(channel.media.lock().unwrap().as_slice(), [("u1".to_string(), image.canonicalize().unwrap())]);
+
+        let args = serde_json::json!({ "action": "send_image", "path": quote });
+        let not_image = tool.execute(&args.to_string()).await.unwrap();
+        assert_eq!(not_image.error_kind.as_deref(), Some("invalid_input"));
+        let args = serde_json::json!({ "action": "send_file", "path": quote });
+        assert!(tool.execute(&args.to_string()).await.unwrap().success);
+
+        let args = serde_json::json!({ "action": "send_file", "path": "/etc/passwd" });
+        let denied = tool.execute(&args.to_string()).await.unwrap();
+        assert_eq!(denied.error_kind.as_deref(), Some("permission_denied"));
+        let args = serde_json::json!({ "action": "send_file", "path": ws.join("missing.pdf") });
+        assert!(tool.execute(&args.to_string()).await.is_err());
+        assert_eq!(channel.media.lock().unwrap().len(), 2);
+        std::fs::remove_dir_all(&ws).unwrap();
+    }
+}
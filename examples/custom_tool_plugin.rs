@@ -0,0 +1,57 @@
+//! A minimal tool plugin: counts the words and characters in a text.
+//!
+//! Build it and point `plugin_dir` at the output:
+//!
+//! ```sh
+//! cargo build --release --example custom_tool_plugin
+//! mkdir -p ~/.bizclaw/plugins
+//! cp target/release/examples/libcustom_tool_plugin.so ~/.bizclaw/plugins/
+//! ```
+//!
+//! ```toml
+//! plugin_dir = "/home/me/.bizclaw/plugins"
+//! ```
+//!
+//! A plugin in its own crate needs `crate-type = ["cdylib"]` and must be
+//! built with the same Rust compiler and bizclaw version as the binary that
+//! loads it (see `bizclaw_tools::plugin`).
+
+use async_trait::async_trait;
+use bizclaw_core::error::{BizClawError, Result};
+use bizclaw_core::traits::Tool;
+use bizclaw_core::types::{ToolDefinition, ToolResult};
+
+pub struct WordCountTool;
+
+#[async_trait]
+impl Tool for WordCountTool {
+    fn name(&self) -> &str { "word_count" }
+
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "word_count".into(),
+            description: "Count the words and characters in a text.".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "text": { "type": "string", "description": "Text to count" }
+                },
+                "required": ["text"]
+            }),
+        }
+    }
+
+    async fn execute(&self, arguments: &str) -> Result<ToolResult> {
+        let args: serde_json::Value = serde_json::from_str(arguments)
+            .map_err(|e| BizClawError::Tool(e.to_string()))?;
+        let text = args["text"].as_str()
+            .ok_or_else(|| BizClawError::Tool("Missing 'text'".into()))?;
+
+        let words = text.split_whitespace().count();
+        let chars = text.chars().count();
+        Ok(ToolResult::ok(format!("{words} words, {chars} characters"))
+            .with_data(serde_json::json!({ "words": words, "chars": chars })))
+    }
+}
+
+bizclaw_tools::export_tool!(WordCountTool);
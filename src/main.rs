@@ -100,6 +100,10 @@ enum Commands {
         /// Open browser automatically
         #[arg(long)]
         open: bool,
+
+        /// Also start the configured channels and answer their messages
+        #[arg(long)]
+        channels: bool,
     },
 
     /// Interactive setup wizard
@@ -134,6 +138,11 @@ enum BrainAction {
         #[arg(default_value = "Hello, who are you?")]
         prompt: String,
     },
+    /// Check a GGUF model file and show its metadata
+    ValidateModel {
+        /// Path to the .gguf file
+        path: std::path::PathBuf,
+    },
 }
 
 #[derive(Subcommand)]
@@ -147,6 +156,8 @@ enum ConfigAction {
         key: String,
         value: String,
     },
+    /// List supported BIZCLAW_* environment variables and their current values
+    EnvVars,
 }
 
 #[tokio::main]
@@ -165,11 +176,11 @@ async fn main() -> Result<()> {
         .init();
 
     // Load config
-    let mut config = if let Some(path) = &cli.config {
-        bizclaw_core::BizClawConfig::load_from(std::path::Path::new(path))?
-    } else {
-        bizclaw_core::BizClawConfig::load()?
+    let config_path = match &cli.config {
+        Some(path) => Some(std::path::PathBuf::from(path)),
+        None => Some(bizclaw_core::BizClawConfig::default_path()).filter(|p| p.exists()),
     };
+    let mut config = bizclaw_core::BizClawConfig::load_with_env(config_path.as_deref())?;
 
     match cli.command {
         Commands::Agent { message, interactive, provider, model } => {
@@ -181,13 +192,13 @@ async fn main() -> Result<()> {
                 config.default_model = m;
             }
 
-            let mut agent = bizclaw_agent::Agent::new(config)?;
+            let mut agent = bizclaw_agent::Agent::new(config.clone())?;
 
             if interactive || message.is_none() {
                 // Interactive mode
                 println!("🦀 BizClaw v{} — Interactive Mode", env!("CARGO_PKG_VERSION"));
-                println!("   Provider: {} | Model: {}", agent.provider_name(), "default");
-                println!("   Type /quit to exit, /clear to reset conversation\n");
+                println!("   Provider: {} | Model: {}", agent.provider_name(), agent.model());
+                println!("   Type /help for commands, /quit to exit\n");
 
                 let mut cli_channel = bizclaw_channels::cli::CliChannel::new();
                 cli_channel.connect().await?;
@@ -200,10 +211,44 @@ async fn main() -> Result<()> {
                 use std::io::Write;
                 std::io::stdout().flush()?;
 
-                while let Some(incoming) = stream.next().await {
-                    if incoming.content == "/clear" {
-                        agent.clear_conversation();
-                        println!("🔄 Conversation cleared.\n");
+                // Scheduled jobs are run between turns; only CLI-bound output can be delivered here.
+                let mut fired_jobs = match (config.tools.scheduler.enabled, bizclaw_tools::scheduler::JobStore::from_config(&config)) {
+                    (true, Ok(store)) => Some(bizclaw_tools::scheduler::spawn_runner(
+                        std::sync::Arc::new(store),
+                        std::time::Duration::from_secs(config.tools.scheduler.tick_secs.max(1)),
+                    )),
+                    (true, Err(e)) => {
+                        tracing::warn!("Scheduler disabled: {e}");
+                        None
+                    }
+                    (false, _) => None,
+                };
+
+                loop {
+                    let incoming = tokio::select! {
+                        incoming = stream.next() => match incoming {
+                            Some(incoming) => incoming,
+                            None => break,
+                        },
+                        Some(fired) = async { fired_jobs.as_mut()?.recv().await } => {
+                            match agent.run_job(&fired).await {
+                                Ok(out) if fired.job.channel == "cli" => {
+                                    println!("\n⏰ {}", fired.job.name);
+                                    cli_channel.send(out).await?;
+                                }
+                                Ok(_) => tracing::warn!(
+                                    "Job '{}' targets channel '{}', which is not running in interactive mode",
+                                    fired.job.name, fired.job.channel
+                                ),
+                                Err(e) => println!("\n❌ Job '{}' failed: {e}\n", fired.job.name),
+                            }
+                            print!("You: ");
+                            std::io::stdout().flush()?;
+                            continue;
+                        }
+                    };
+                    if let Some(command) = bizclaw_channels::cli::SlashCommand::parse(&incoming.content) {
+                        run_slash_command(&mut agent, command);
                         print!("You: ");
                         std::io::stdout().flush()?;
                         continue;
@@ -233,21 +278,12 @@ async fn main() -> Result<()> {
             match action {
                 ChannelAction::Start { channel } => {
                     println!("🦀 BizClaw Channel Listener");
-                    if let Some(ch) = channel {
+                    if let Some(ch) = &channel {
                         println!("Starting channel: {ch}");
                     } else {
                         println!("Starting all configured channels...");
                     }
-
-                    // Start configured channels
-                    if let Some(zalo_config) = &config.channel.zalo {
-                        if zalo_config.enabled {
-                            println!("  📱 Zalo ({}) channel starting...", zalo_config.mode);
-                            let mut zalo = bizclaw_channels::zalo::ZaloChannel::new(zalo_config.clone());
-                            use bizclaw_core::traits::Channel;
-                            zalo.connect().await?;
-                        }
-                    }
+                    start_channels(&config, channel.as_deref(), message_quota()?).await?;
 
                     println!("\nChannels are running. Press Ctrl+C to stop.");
                     tokio::signal::ctrl_c().await?;
@@ -261,7 +297,13 @@ async fn main() -> Result<()> {
                     println!("  {} telegram  — Telegram bot",
                         if config.channel.telegram.is_some() { "✅" } else { "⬜" });
                     println!("  {} discord   — Discord bot",
-                        if config.channel.discord.is_some() { "✅" } else { "⬜" });
+                        if config.channel.discord.as_ref().is_some_and(|d| d.enabled) { "✅" } else { "⬜" });
+                    println!("  {} whatsapp  — WhatsApp Business (Cloud API)",
+                        if config.channel.whatsapp.as_ref().is_some_and(|w| w.enabled) { "✅" } else { "⬜" });
+                    println!("  {} email     — Email (IMAP + SMTP)",
+                        if config.channel.email.as_ref().is_some_and(|e| e.enabled) { "✅" } else { "⬜" });
+                    println!("  {} webhook   — Signed HTTP webhooks (CRMs, n8n, Zapier)",
+                        if config.channel.webhook.as_ref().is_some_and(|w| w.enabled) { "✅" } else { "⬜" });
                 }
             }
         }
@@ -413,6 +455,24 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+                BrainAction::ValidateModel { path } => {
+                    let meta = bizclaw_brain::gguf::validate::validate_gguf(&path)?;
+                    println!("✅ Valid GGUF model: {}\n", path.display());
+                    println!("   Version:          {}", meta.version);
+                    println!("   Architecture:     {}", meta.arch);
+                    println!("   Tensors:          {}", meta.tensor_count);
+                    println!("   Context length:   {}", meta.context_length);
+                    println!("   Layers:           {}", meta.n_layers);
+                    println!("   Attention heads:  {} ({} KV)", meta.n_heads, meta.n_kv_heads);
+                    println!("   Embedding length: {}", meta.embedding_length);
+                    match meta.rope_theta {
+                        Some(theta) => println!("   RoPE theta:       {theta}"),
+                        None => println!("   RoPE theta:       (not set, 10000)"),
+                    }
+                    if let Some(vocab) = meta.vocab_size {
+                        println!("   Vocabulary:       {vocab}");
+                    }
+                }
             }
         }
 
@@ -431,6 +491,26 @@ async fn main() -> Result<()> {
                     println!("Setting {key} = {value}");
                     println!("(Direct config editing — edit ~/.bizclaw/config.toml)");
                 }
+                ConfigAction::EnvVars => {
+                    for var in config.env_vars() {
+                        let value = if var.secret && !var.value.is_empty() {
+                            "********".to_string()
+                        } else {
+                            var.value
+                        };
+                        let set = if std::env::var_os(&var.name).is_some()
+                            || var.alias.as_ref().is_some_and(|a| std::env::var_os(a).is_some())
+                        {
+                            "  (from env)"
+                        } else {
+                            ""
+                        };
+                        match &var.alias {
+                            Some(alias) => println!("{} | {alias} = {value}{set}", var.name),
+                            None => println!("{} = {value}{set}", var.name),
+                        }
+                    }
+                }
             }
         }
 
@@ -458,7 +538,7 @@ async fn main() -> Result<()> {
 
             println!("🦀 BizClaw v{} — Chat Mode", env!("CARGO_PKG_VERSION"));
             println!("   Provider: {}", agent.provider_name());
-            println!("   Type /quit to exit, /clear to reset conversation\n");
+            println!("   Type /help for commands, /quit to exit\n");
 
             let mut cli_channel = bizclaw_channels::cli::CliChannel::new();
             cli_channel.connect().await?;
@@ -472,18 +552,8 @@ async fn main() -> Result<()> {
             std::io::stdout().flush()?;
 
             while let Some(incoming) = stream.next().await {
-                if incoming.content == "/clear" {
-                    agent.clear_conversation();
-                    println!("🔄 Conversation cleared.\n");
-                    print!("You: ");
-                    std::io::stdout().flush()?;
-                    continue;
-                }
-
-                if incoming.content == "/info" {
-                    let conv = agent.conversation();
-                    println!("\n📊 Provider: {} | Messages: {} | System prompt: ✅\n",
-                        agent.provider_name(), conv.len());
+                if let Some(command) = bizclaw_channels::cli::SlashCommand::parse(&incoming.content) {
+                    run_slash_command(&mut agent, command);
                     print!("You: ");
                     std::io::stdout().flush()?;
                     continue;
@@ -504,7 +574,7 @@ async fn main() -> Result<()> {
             println!("\n👋 Goodbye!");
         }
 
-        Commands::Serve { port, open } => {
+        Commands::Serve { port, open, channels } => {
             println!("🦀 BizClaw v{} — Web Dashboard", env!("CARGO_PKG_VERSION"));
 
             let mut gw_config = config.gateway.clone();
@@ -519,8 +589,16 @@ async fn main() -> Result<()> {
             if open {
                 let _ = std::process::Command::new("open").arg(&url).spawn();
             }
-
-            bizclaw_gateway::start_server(&gw_config).await?;
+            let quota = message_quota()?;
+            let channels = if channels {
+                let manager = start_channels(&config, None, quota.clone()).await?;
+                println!();
+                Some(manager)
+            } else {
+                None
+            };
+
+            bizclaw_gateway::start_server(&gw_config, quota, channels).await?;
         }
 
         Commands::Init => {
@@ -531,6 +609,220 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
+/// Start the enabled channels (or just `only`) and answer their messages
+/// in the background, within `quota`.
+async fn start_channels(
+    config: &bizclaw_core::BizClawConfig,
+    only: Option<&str>,
+    quota: Option<std::sync::Arc<dyn bizclaw_core::quota::MessageQuota>>,
+) -> Result<std::sync::Arc<bizclaw_channels::manager::ChannelManager>> {
+    let (agent_tx, incoming) = tokio::sync::mpsc::unbounded_channel();
+    let status = channel_status_store()?;
+    let history = channel_history()?;
+    let manager = bizclaw_channels::manager::ChannelManager::start(config, only, agent_tx, status, history).await?;
+    for running in manager.channels() {
+        println!("  ✅ {} channel started", running.name());
+    }
+    let manager = std::sync::Arc::new(manager);
+    let identities = user_identities()?;
+    tokio::spawn(route_to_agents(manager.clone(), incoming, identities, quota, config.clone()));
+    Ok(manager)
+}
+
+/// Run a REPL slash command (`/model`, `/provider`, `/clear`, `/tools`, `/save`, …).
+fn run_slash_command(agent: &mut bizclaw_agent::Agent, command: bizclaw_channels::cli::SlashCommand) {
+    use bizclaw_channels::cli::SlashCommand;
+    match command {
+        SlashCommand::Model(None) => println!("🧠 Model: {}\n", agent.model()),
+        SlashCommand::Model(Some(model)) => {
+            agent.set_model(&model);
+            println!("🧠 Model set to {model}\n");
+        }
+        SlashCommand::Provider(None) => println!("🔌 Provider: {}\n", agent.provider_name()),
+        SlashCommand::Provider(Some(provider)) => match agent.set_provider(&provider) {
+            Ok(()) => println!("🔌 Provider set to {} (model: {})\n", agent.provider_name(), agent.model()),
+            Err(e) => println!("❌ Could not switch to provider '{provider}': {e}\n"),
+        },
+        SlashCommand::Clear => {
+            agent.clear_conversation();
+            println!("🔄 Conversation cleared.\n");
+        }
+        SlashCommand::Tools => {
+            let tools = agent.tools();
+            println!("🛠️  {} tools:", tools.len());
+            for tool in tools {
+                let summary = tool.description.lines().next().unwrap_or_default();
+                println!("   {:<20} {summary}", tool.name);
+            }
+            println!();
+        }
+        SlashCommand::Save(None) => println!("Usage: /save <file>\n"),
+        SlashCommand::Save(Some(path)) => {
+            let transcript: String = agent.conversation().iter()
+                .filter(|m| m.role != bizclaw_core::types::Role::System)
+                .map(|m| format!("## {}\n\n{}\n\n", m.role, m.content))
+                .collect();
+            match std::fs::write(&path, transcript) {
+                Ok(()) => println!("💾 Saved {} messages to {}\n", agent.conversation().len() - 1, path.display()),
+                Err(e) => println!("❌ Could not write {}: {e}\n", path.display()),
+            }
+        }
+        SlashCommand::Info => println!(
+            "\n📊 Provider: {} | Model: {} | Messages: {}\n",
+            agent.provider_name(), agent.model(), agent.conversation().len(),
+        ),
+        SlashCommand::Help => println!("{}\n", bizclaw_channels::cli::HELP),
+        SlashCommand::Unknown(name) => println!("Unknown command /{name}. Type /help for the list.\n"),
+    }
+}
+
+/// Answer a channel's messages as they arrive (Telegram polling or webhook,
+/// Discord Gateway, WhatsApp webhook, IMAP polling), with one agent (and so one conversation) per chat.
+/// Hand each channel's messages to its own [`run_channel`] loop, so a slow
+/// reply on one channel doesn't hold up the others.
+///
+/// With `identities`, a user's direct chats share one agent across channels
+/// (see [`bizclaw_channels::identity::session_id`]). With `quota`, messages
+/// over the tenant's daily limit are refused.
+async fn route_to_agents(
+    manager: std::sync::Arc<bizclaw_channels::manager::ChannelManager>,
+    mut incoming: tokio::sync::mpsc::UnboundedReceiver<bizclaw_core::types::IncomingMessage>,
+    identities: Option<std::sync::Arc<dyn bizclaw_channels::identity::UserIdentities>>,
+    quota: Option<std::sync::Arc<dyn bizclaw_core::quota::MessageQuota>>,
+    config: bizclaw_core::BizClawConfig,
+) {
+    let sessions = Sessions::default();
+    let mut loops = std::collections::HashMap::new();
+    while let Some(msg) = incoming.recv().await {
+        let Some(replies) = manager.get(&msg.channel) else {
+            tracing::warn!("Message from unknown channel {}", msg.channel);
+            continue;
+        };
+        let tx = loops.entry(msg.channel.clone()).or_insert_with(|| {
+            let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+            let messages = tokio_stream::wrappers::UnboundedReceiverStream::new(rx);
+            let settings = manager.chat_settings(&msg.channel);
+            let (identities, sessions, quota) = (identities.clone(), sessions.clone(), quota.clone());
+            tokio::spawn(run_channel(msg.channel.clone(), replies, messages, settings, identities, sessions, quota, config.clone()));
+            tx
+        });
+        let _ = tx.send(msg);
+    }
+}
+
+/// Each conversation's agent by session ID, shared by the channel loops.
+type Sessions = std::sync::Arc<tokio::sync::Mutex<std::collections::HashMap<String, std::sync::Arc<tokio::sync::Mutex<bizclaw_agent::Agent>>>>>;
+
+#[allow(clippy::too_many_arguments)]
+async fn run_channel(
+    label: String,
+    replies: std::sync::Arc<dyn bizclaw_core::traits::Channel>,
+    mut messages: impl tokio_stream::Stream<Item = bizclaw_core::types::IncomingMessage> + Unpin,
+    settings: Option<std::sync::Arc<bizclaw_channels::chat_settings::ChatSettingsStore>>,
+    identities: Option<std::sync::Arc<dyn bizclaw_channels::identity::UserIdentities>>,
+    sessions: Sessions,
+    quota: Option<std::sync::Arc<dyn bizclaw_core::quota::MessageQuota>>,
+    config: bizclaw_core::BizClawConfig,
+) {
+    use bizclaw_channels::chat_settings::ChatSettings;
+    use std::collections::hash_map::Entry;
+    use tokio_stream::StreamExt;
+
+    let presence = config.channel.presence(replies.name());
+    // The chat settings each chat's agent was last brought up to date with.
+    let mut applied: std::collections::HashMap<String, ChatSettings> = std::collections::HashMap::new();
+    while let Some(msg) = messages.next().await {
+        if let Some(quota) = &quota
+            && !bizclaw_channels::quota::admit(quota.as_ref(), replies.as_ref(), &msg).await
+        {
+            continue;
+        }
+        let chat = settings.as_ref().map(|s| s.get(&msg.thread_id)).unwrap_or_default();
+        let session = bizclaw_channels::identity::session_id(&msg, identities.as_deref());
+        let agent = match sessions.lock().await.entry(session) {
+            Entry::Occupied(entry) => entry.get().clone(),
+            Entry::Vacant(entry) => match bizclaw_agent::Agent::new(config.clone()) {
+                Ok(mut agent) => {
+                    // Zalo chats can be sent images and files by the agent.
+                    if replies.name() == "zalo" && config.tools.zalo_media.enabled {
+                        agent.register_tool(Box::new(bizclaw_tools::zalo_media::ZaloMediaTool::new(
+                            replies.clone(),
+                            &msg.thread_id,
+                            msg.thread_type.clone(),
+                            bizclaw_security::allowlist::Allowlist::new(&config.autonomy),
+                        )));
+                    }
+                    entry.insert(std::sync::Arc::new(tokio::sync::Mutex::new(agent))).clone()
+                }
+                Err(e) => {
+                    tracing::error!("{label}: could not start an agent for chat {}: {e}", msg.thread_id);
+                    continue;
+                }
+            },
+        };
+        let mut agent = agent.lock().await;
+        let applied = applied.entry(msg.thread_id.clone())
+            .or_insert_with(|| ChatSettings { model: None, reset_at: chat.reset_at });
+        // Apply /reset and /model from the chat.
+        if chat.reset_at != applied.reset_at {
+            agent.clear_conversation();
+        }
+        agent.set_model(chat.model.as_deref().unwrap_or(&config.default_model));
+        *applied = chat;
+        let (progress, steps) = tokio::sync::mpsc::unbounded_channel();
+        agent.set_progress(Some(progress));
+        let turn = agent.reply(&msg, replies.as_ref());
+        let replied = bizclaw_channels::presence::while_busy(replies.as_ref(), &msg.thread_id, &presence, steps, turn).await;
+        if let Err(e) = replied {
+            tracing::error!("{label}: reply to chat {} failed: {e}", msg.thread_id);
+        }
+    }
+}
+
+/// Where channels record their connection status: the platform's
+/// `tenant_channels`, when running as a tenant started by the platform.
+fn channel_status_store() -> Result<Option<std::sync::Arc<dyn bizclaw_channels::manager::ChannelStatusStore>>> {
+    let (Ok(tenant_id), Some(db_path)) = (std::env::var("BIZCLAW_TENANT_ID"), std::env::var_os("BIZCLAW_PLATFORM_DB")) else {
+        return Ok(None);
+    };
+    let store = bizclaw_platform::db::TenantChannelStatusStore::open(std::path::Path::new(&db_path), &tenant_id)?;
+    Ok(Some(std::sync::Arc::new(store)))
+}
+
+/// Where channel messages are recorded: the platform's `channel_messages`,
+/// when running as a tenant started by the platform.
+fn channel_history() -> Result<Option<std::sync::Arc<dyn bizclaw_channels::history::MessageHistory>>> {
+    let (Ok(tenant_id), Some(db_path)) = (std::env::var("BIZCLAW_TENANT_ID"), std::env::var_os("BIZCLAW_PLATFORM_DB")) else {
+        return Ok(None);
+    };
+    let history = bizclaw_platform::db::TenantChannelHistory::open(std::path::Path::new(&db_path), &tenant_id)?;
+    Ok(Some(std::sync::Arc::new(history)))
+}
+
+/// Where channel users are matched across channels: the platform's
+/// `user_identities`, when running as a tenant started by the platform.
+fn user_identities() -> Result<Option<std::sync::Arc<dyn bizclaw_channels::identity::UserIdentities>>> {
+    let (Ok(tenant_id), Some(db_path)) = (std::env::var("BIZCLAW_TENANT_ID"), std::env::var_os("BIZCLAW_PLATFORM_DB")) else {
+        return Ok(None);
+    };
+    let identities = bizclaw_platform::db::TenantUserIdentities::open(std::path::Path::new(&db_path), &tenant_id)?;
+    Ok(Some(std::sync::Arc::new(identities)))
+}
+
+/// The platform's daily message quota for this tenant, when running as a
+/// tenant started by the platform. Days roll over in `BIZCLAW_QUOTA_TIMEZONE`.
+fn message_quota() -> Result<Option<std::sync::Arc<dyn bizclaw_core::quota::MessageQuota>>> {
+    let (Ok(tenant_id), Some(db_path)) = (std::env::var("BIZCLAW_TENANT_ID"), std::env::var_os("BIZCLAW_PLATFORM_DB")) else {
+        return Ok(None);
+    };
+    let timezone = match std::env::var("BIZCLAW_QUOTA_TIMEZONE") {
+        Ok(tz) => tz.parse().map_err(|e| anyhow::anyhow!("BIZCLAW_QUOTA_TIMEZONE: {e}"))?,
+        Err(_) => bizclaw_platform::db::DEFAULT_QUOTA_TIMEZONE,
+    };
+    let quota = bizclaw_platform::db::TenantMessageQuota::open(std::path::Path::new(&db_path), &tenant_id, timezone)?;
+    Ok(Some(std::sync::Arc::new(quota)))
+}
+
 /// Interactive setup wizard.
 async fn run_init_wizard() -> Result<()> {
     use std::io::{self, Write, BufRead};
@@ -584,11 +876,13 @@ async fn run_init_wizard() -> Result<()> {
     let enable_gateway = !input.trim().eq_ignore_ascii_case("n");
 
     // Build config
-    let mut config = bizclaw_core::BizClawConfig::default();
-    config.default_provider = provider.into();
-    config.default_model = default_model.into();
-    config.api_key = api_key;
-    config.identity.name = bot_name.into();
+    let mut config = bizclaw_core::BizClawConfig {
+        default_provider: provider.into(),
+        default_model: default_model.into(),
+        api_key,
+        ..Default::default()
+    };
+    config.identity.name = bot_name;
 
     // Save
     config.save()?;
@@ -6,7 +6,13 @@
 //! Usage:
 //!   bizclaw-platform                     # Start admin server (default port 3000)
 //!   bizclaw-platform --port 8080         # Custom port
+//!   bizclaw-platform --domain example.com # Serve tenants at <slug>.example.com
+//!   bizclaw-platform --restart-plans pro # Only auto-restart crashed "pro" tenants
 //!   bizclaw-platform --init-admin        # Create default admin user
+//!
+//! Set `BIZCLAW_MASTER_KEY` to encrypt channel and tenant secrets in the
+//! database, and `BIZCLAW_DEFAULT_API_KEY` to the provider API key of
+//! tenants without their own.
 
 use anyhow::Result;
 use clap::Parser;
@@ -32,6 +38,26 @@ struct Cli {
     #[arg(long, default_value = "10001")]
     base_port: u16,
 
+    /// Domain for subdomain routing: `<slug>.<domain>` is proxied to the tenant
+    #[arg(long, default_value = "bizclaw.vn")]
+    domain: String,
+
+    /// Seconds between tenant health checks; 0 disables auto-restart
+    #[arg(long, default_value = "30")]
+    health_check_secs: u64,
+
+    /// Plans whose crashed tenants are restarted, comma-separated (empty = all)
+    #[arg(long, default_value = "")]
+    restart_plans: String,
+
+    /// Restarts in a row before a crashed tenant is marked "error"
+    #[arg(long, default_value = "5")]
+    max_restarts: u32,
+
+    /// IANA timezone whose midnight resets tenants' daily message quotas
+    #[arg(long, default_value = "Asia/Ho_Chi_Minh")]
+    quota_timezone: chrono_tz::Tz,
+
     /// Data directory
     #[arg(long, default_value = "~/.bizclaw/tenants")]
     data_dir: String,
@@ -91,7 +117,16 @@ async fn main() -> Result<()> {
     std::fs::create_dir_all(&data_dir)?;
 
     // Open database
-    let db = bizclaw_platform::PlatformDb::open(std::path::Path::new(&db_path))?;
+    let mut db = bizclaw_platform::PlatformDb::open(std::path::Path::new(&db_path))?
+        .with_quota_timezone(cli.quota_timezone);
+    // Channel tokens and cookies are encrypted with a key from BIZCLAW_MASTER_KEY.
+    match std::env::var("BIZCLAW_MASTER_KEY") {
+        Ok(secret) => {
+            let cipher = bizclaw_security::secrets::SecretCipher::from_master_secret(&secret)?;
+            db = db.with_secret_cipher(cipher)?;
+        }
+        Err(_) => tracing::warn!("BIZCLAW_MASTER_KEY is not set: channel and tenant secrets are stored in plaintext"),
+    }
 
     // --init-admin: create admin user and exit
     if cli.init_admin {
@@ -131,10 +166,17 @@ async fn main() -> Result<()> {
     // Build admin state
     let state = Arc::new(bizclaw_platform::admin::AdminState {
         db: Mutex::new(db),
-        manager: Mutex::new(bizclaw_platform::TenantManager::new(&data_dir)),
+        manager: Mutex::new(bizclaw_platform::TenantManager::new(&data_dir)
+            .with_db_path(&db_path)
+            .with_quota_timezone(cli.quota_timezone)
+            .with_default_api_key(std::env::var("BIZCLAW_DEFAULT_API_KEY").unwrap_or_default())),
         jwt_secret: cli.jwt_secret.clone(),
         bizclaw_bin: cli.bizclaw_bin.clone(),
         base_port: cli.base_port,
+        domain: cli.domain.clone(),
+        webhooks: bizclaw_channels::webhook::WebhookDeliveryQueue::start(Some(Arc::new(
+            bizclaw_platform::db::FailedWebhookStore::open(std::path::Path::new(&db_path))?,
+        ))),
     });
 
     // Start server
@@ -145,6 +187,17 @@ async fn main() -> Result<()> {
     println!("   📂 Data Dir:        {data_dir}");
     println!("   🔧 BizClaw Binary:  {}", cli.bizclaw_bin);
     println!("   🔌 Tenant Base Port: {}", cli.base_port);
+    println!("   🔀 Tenant Routing:   http://<slug>.{}", cli.domain);
+    if cli.health_check_secs > 0 {
+        let policy = bizclaw_platform::tenant::RestartPolicy {
+            check_interval: std::time::Duration::from_secs(cli.health_check_secs),
+            plans: cli.restart_plans.split(',').map(str::trim).filter(|p| !p.is_empty()).map(String::from).collect(),
+            max_restarts: cli.max_restarts,
+            ..Default::default()
+        };
+        println!("   🩺 Auto-Restart:     every {}s, up to {} restarts", cli.health_check_secs, cli.max_restarts);
+        bizclaw_platform::AdminServer::spawn_supervisor(state.clone(), policy);
+    }
     println!();
 
     bizclaw_platform::AdminServer::start(state, cli.port).await